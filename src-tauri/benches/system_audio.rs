@@ -0,0 +1,66 @@
+//! Benchmarks for the system audio export path.
+//!
+//! ```text
+//! cargo bench --features bench --bench system_audio
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use runningbord_lib::bench::{self, Fixture};
+use std::hint::black_box;
+
+/// Buffer lengths to measure: the default and the maximum.
+const BUFFER_SECONDS: [u32; 2] = [30, 300];
+
+fn snapshot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot");
+    for seconds in BUFFER_SECONDS {
+        let fixture = Fixture::new(seconds);
+        group.throughput(Throughput::Elements(seconds as u64 * 16_000));
+        group.bench_with_input(BenchmarkId::from_parameter(seconds), &fixture, |b, f| {
+            b.iter(|| black_box(f.snapshot()))
+        });
+    }
+    group.finish();
+}
+
+fn downsample(c: &mut Criterion) {
+    let mut group = c.benchmark_group("downsample_48k_stereo");
+    group.sample_size(10);
+    for seconds in BUFFER_SECONDS {
+        let mono = bench::speech_like(seconds as usize * 48_000);
+        let stereo: Vec<f32> = mono.iter().flat_map(|s| [*s, -*s]).collect();
+        group.throughput(Throughput::Elements(seconds as u64 * 48_000));
+        group.bench_with_input(BenchmarkId::from_parameter(seconds), &stereo, |b, input| {
+            b.iter(|| black_box(bench::downsample(input, 48_000, 2)))
+        });
+    }
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("opus_encode");
+    group.sample_size(10);
+    for seconds in BUFFER_SECONDS {
+        let samples = bench::speech_like(seconds as usize * 16_000);
+        group.throughput(Throughput::Elements(samples.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(seconds), &samples, |b, s| {
+            b.iter(|| black_box(bench::encode(s)))
+        });
+    }
+    group.finish();
+}
+
+fn export(c: &mut Criterion) {
+    let mut group = c.benchmark_group("export");
+    group.sample_size(10);
+    for seconds in BUFFER_SECONDS {
+        let fixture = Fixture::new(seconds);
+        group.bench_with_input(BenchmarkId::from_parameter(seconds), &fixture, |b, f| {
+            b.iter(|| black_box(f.export()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, snapshot, downsample, encode, export);
+criterion_main!(benches);
@@ -1,4 +1,5 @@
 use crate::api::get_stored_credentials;
+use crate::network;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
@@ -210,7 +211,7 @@ pub async fn activate_license_api(
     };
 
     // Make HTTP request to activation endpoint with authorization header
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let url = format!("{}/activate", payment_endpoint);
 
     let response = client
@@ -267,7 +268,7 @@ pub async fn deactivate_license_api(app: AppHandle) -> Result<ActivationResponse
         app_version: app_version.clone(),
     };
     // Make HTTP request to activation endpoint with authorization header
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let url = format!("{}/deactivate", payment_endpoint);
 
     let response = client
@@ -332,7 +333,7 @@ pub async fn validate_license_api(app: AppHandle) -> Result<ValidateResponse, St
     }
 
     // Make HTTP request to validate endpoint with authorization header
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let url = format!("{}/validate", payment_endpoint);
 
     let response = client
@@ -388,13 +389,13 @@ pub fn mask_license_key_cmd(license_key: String) -> String {
 }
 
 #[tauri::command]
-pub async fn get_checkout_url() -> Result<CheckoutResponse, String> {
+pub async fn get_checkout_url(app: AppHandle) -> Result<CheckoutResponse, String> {
     // Get payment endpoint and API access key from environment
     let payment_endpoint = get_payment_endpoint()?;
     let api_access_key = get_api_access_key()?;
 
     // Make HTTP request to checkout endpoint with authorization header
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let url = format!("{}/checkout", payment_endpoint);
 
     let response = client
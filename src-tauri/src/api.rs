@@ -1,3 +1,9 @@
+use crate::network;
+use crate::network_log;
+use crate::offline_queue::{self, QueuedRequest};
+use crate::rate_limit::CallKind;
+use crate::retrieval;
+use crate::secrets;
 use base64::{engine::general_purpose, Engine as _};
 use futures_util::StreamExt;
 use reqwest::multipart::{Form, Part};
@@ -9,7 +15,7 @@ use std::path::PathBuf;
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_machine_uid::MachineUidExt;
 
-fn get_app_endpoint() -> Result<String, String> {
+pub(crate) fn get_app_endpoint() -> Result<String, String> {
     if let Ok(endpoint) = env::var("APP_ENDPOINT") {
         return Ok(endpoint);
     }
@@ -86,6 +92,26 @@ pub struct AudioResponse {
     success: bool,
     transcription: Option<String>,
     error: Option<String>,
+    /// Set when the request was queued while offline; the transcript comes
+    /// with `offline-request-completed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    queued_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChatResponse {
+    response: Option<String>,
+    error: Option<String>,
+    /// Set when the request was queued while offline; the response comes
+    /// with `offline-request-completed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queued_id: Option<String>,
+}
+
+impl AudioResponse {
+    pub fn into_transcription(self) -> Option<String> {
+        self.transcription
+    }
 }
 
 // Chat API Structs
@@ -196,6 +222,26 @@ pub struct UserAudioConfig {
 pub async fn transcribe_audio(
     app: AppHandle,
     audio_base64: String,
+) -> Result<AudioResponse, String> {
+    if !offline_queue::is_offline(&app) {
+        let result = transcribe(app.clone(), audio_base64.clone()).await;
+        if result.is_ok() || !offline_queue::is_offline(&app) {
+            return result;
+        }
+    }
+    let id = offline_queue::enqueue(&app, QueuedRequest::Transcription { audio_base64 })?;
+    Ok(AudioResponse {
+        success: false,
+        transcription: None,
+        error: Some("Offline; the audio will be transcribed once back online".to_string()),
+        queued_id: Some(id),
+    })
+}
+
+/// Transcribe now, without queueing while offline.
+pub(crate) async fn transcribe(
+    app: AppHandle,
+    audio_base64: String,
 ) -> Result<AudioResponse, String> {
     let (_, _, selected_model) = get_stored_credentials(&app).await?;
     let provider = selected_model.as_ref().map(|model| model.provider.clone());
@@ -208,24 +254,40 @@ pub async fn transcribe_audio(
     })?;
 
     let audio_bytes = decode_audio_base64(&audio_base64)?;
-    let client = reqwest::Client::new();
+    let audio_seconds = crate::usage::audio_duration_seconds(&audio_bytes);
+    crate::rate_limit::acquire(&app, CallKind::Transcription, provider.as_deref(), 0).await?;
+    let client = network::client(&app, provider.as_deref())?;
+    let url = network::provider_url(&app, provider.as_deref(), &user_audio_config.url)?;
     let error_provider = provider.clone();
     let error_model = model.clone();
+    let token = provider_token(provider.as_deref(), &user_audio_config.user_token).await?;
     match perform_user_audio_transcription(
+        &app,
+        provider.as_deref(),
         &client,
-        &user_audio_config.url,
-        &user_audio_config.user_token,
+        &url,
+        &token,
         &user_audio_config.model,
         user_audio_config.headers.as_ref(),
         &audio_bytes,
     )
     .await
     {
-        Ok(transcription) => Ok(AudioResponse {
-            success: true,
-            transcription: Some(transcription),
-            error: None,
-        }),
+        Ok(transcription) => {
+            crate::stream_server::publish_transcript(&app, &transcription);
+            crate::usage::record_transcription(
+                &app,
+                provider.as_deref(),
+                &user_audio_config.model,
+                audio_seconds,
+            );
+            Ok(AudioResponse {
+                success: true,
+                transcription: Some(transcription),
+                error: None,
+                queued_id: None,
+            })
+        }
         Err(primary_error) => {
             let fallback_error_message = if let (Some(fallback_url), Some(fallback_token)) = (
                 user_audio_config.fallback_url.as_ref(),
@@ -235,10 +297,13 @@ pub async fn transcribe_audio(
                     .fallback_model
                     .as_ref()
                     .unwrap_or(&user_audio_config.model);
+                let fallback_url = network::provider_url(&app, provider.as_deref(), fallback_url)?;
 
                 match perform_user_audio_transcription(
+                    &app,
+                    provider.as_deref(),
                     &client,
-                    fallback_url,
+                    &fallback_url,
                     fallback_token,
                     fallback_model,
                     user_audio_config.headers.as_ref(),
@@ -247,10 +312,18 @@ pub async fn transcribe_audio(
                 .await
                 {
                     Ok(transcription) => {
+                        crate::stream_server::publish_transcript(&app, &transcription);
+                        crate::usage::record_transcription(
+                            &app,
+                            provider.as_deref(),
+                            fallback_model,
+                            audio_seconds,
+                        );
                         return Ok(AudioResponse {
                             success: true,
                             transcription: Some(transcription),
                             error: None,
+                            queued_id: None,
                         });
                     }
                     Err(fallback_error) => Some(fallback_error),
@@ -304,7 +377,7 @@ async fn fetch_api_response_config(
     let (license_key, instance_id, _) = get_stored_credentials(app).await?;
 
     // Make HTTP request to response endpoint
-    let client = reqwest::Client::new();
+    let client = network::client(app, None)?;
     let url = format!("{}/api/response", app_endpoint);
 
     let mut request = client
@@ -324,6 +397,7 @@ async fn fetch_api_response_config(
     }
 
     let response = request.send().await.map_err(|e| {
+        offline_queue::observe_error(app, &e);
         let error_msg = format!("{}", e);
         if error_msg.contains("url (") {
             let parts: Vec<&str> = error_msg.split(" for url (").collect();
@@ -388,6 +462,19 @@ fn map_api_error_message(error_rules: &[ApiConfigError], sources: &[String]) ->
         })
 }
 
+/// The bearer token for `provider`'s endpoint: the user's own API key if
+/// one is stored for the provider (see `secrets`), else the config's.
+async fn provider_token(provider: Option<&str>, config_token: &str) -> Result<String, String> {
+    // Ids that can't name a secret have no stored key.
+    let Some(secret) = provider.and_then(|p| secrets::api_key_secret(p).ok()) else {
+        return Ok(config_token.to_string());
+    };
+    let stored = tauri::async_runtime::spawn_blocking(move || secrets::get(&secret))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(stored.unwrap_or_else(|| config_token.to_string()))
+}
+
 fn decode_audio_base64(audio_base64: &str) -> Result<Vec<u8>, String> {
     let trimmed = audio_base64.trim();
     let base64_str = if let Some(idx) = trimmed.find(',') {
@@ -402,6 +489,8 @@ fn decode_audio_base64(audio_base64: &str) -> Result<Vec<u8>, String> {
 }
 
 async fn perform_user_audio_transcription(
+    app: &AppHandle,
+    provider: Option<&str>,
     client: &reqwest::Client,
     url: &str,
     token: &str,
@@ -429,30 +518,43 @@ async fn perform_user_audio_transcription(
         }
     }
 
-    let response = client
+    let request = client
         .post(url)
         .bearer_auth(token)
         .multipart(form)
-        .send()
-        .await
-        .map_err(|e| format!("Transcription request failed to send: {}", e))?;
+        .build()
+        .map_err(|e| format!("Failed to build transcription request: {}", e))?;
+    let log = network_log::start(app, provider, &request);
+    let response = match client.execute(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            log.finish(None, None, Some(&e.to_string()));
+            offline_queue::observe_error(app, &e);
+            return Err(format!("Transcription request failed to send: {}", e));
+        }
+    };
 
-    if !response.status().is_success() {
-        let status = response.status();
+    let status = response.status();
+    if !status.is_success() {
         let error_text = response
             .text()
             .await
             .unwrap_or_else(|_| "Unable to read transcription error response".to_string());
+        log.finish(Some(status.as_u16()), Some(&error_text), None);
         return Err(format!(
             "Transcription request returned {} with body: {}",
             status, error_text
         ));
     }
 
-    let body_text = response
-        .text()
-        .await
-        .map_err(|e| format!("Failed to read transcription response: {}", e))?;
+    let body_text = match response.text().await {
+        Ok(body_text) => body_text,
+        Err(e) => {
+            log.finish(Some(status.as_u16()), None, Some(&e.to_string()));
+            return Err(format!("Failed to read transcription response: {}", e));
+        }
+    };
+    log.finish(Some(status.as_u16()), Some(&body_text), None);
 
     if body_text.trim().is_empty() {
         return Err("Transcription response was empty".to_string());
@@ -485,7 +587,71 @@ pub async fn chat_stream_response(
     image_base64: Option<serde_json::Value>,
     audio_base64: Option<String>,
     history: Option<String>,
+) -> Result<ChatResponse, String> {
+    if !offline_queue::is_offline(&app) {
+        let result = chat_completion(
+            app.clone(),
+            user_message.clone(),
+            system_prompt.clone(),
+            image_base64.clone(),
+            audio_base64.clone(),
+            history.clone(),
+            ChatOptions {
+                stream_events: true,
+                retrieve_context: true,
+            },
+        )
+        .await;
+        if result.is_ok() || !offline_queue::is_offline(&app) {
+            return result.map(|response| ChatResponse {
+                response: Some(response),
+                error: None,
+                queued_id: None,
+            });
+        }
+    }
+    let id = offline_queue::enqueue(
+        &app,
+        QueuedRequest::Chat {
+            user_message,
+            system_prompt,
+            image_base64,
+            audio_base64,
+            history,
+        },
+    )?;
+    Ok(ChatResponse {
+        response: None,
+        error: Some("Offline; the request will be sent once back online".to_string()),
+        queued_id: Some(id),
+    })
+}
+
+/// How `chat_completion` treats a request.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChatOptions {
+    /// Emit the response as `chat_stream_chunk`s and a `chat_stream_complete`.
+    pub stream_events: bool,
+    /// Add excerpts of earlier recordings relevant to the message (see
+    /// `retrieval`).
+    pub retrieve_context: bool,
+}
+
+/// Send a chat request now, without queueing while offline.
+pub(crate) async fn chat_completion(
+    app: AppHandle,
+    user_message: String,
+    system_prompt: Option<String>,
+    image_base64: Option<serde_json::Value>,
+    audio_base64: Option<String>,
+    history: Option<String>,
+    options: ChatOptions,
 ) -> Result<String, String> {
+    let ChatOptions {
+        stream_events,
+        retrieve_context,
+    } = options;
+
     // Get stored credentials to get selected model
     let (_, _, selected_model) = get_stored_credentials(&app).await?;
     let (provider, model) = selected_model.as_ref().map_or((None, None), |m| {
@@ -502,6 +668,29 @@ pub async fn chat_stream_response(
         serde_json::json!({})
     };
 
+    // Excerpts of earlier meetings relevant to the question, if retrieval is on
+    let retrieved = if retrieve_context {
+        retrieval::retrieve_or_skip(&app, &user_message, &api_config.model).await
+    } else {
+        None
+    };
+    if let Some(context) = retrieved.as_ref().filter(|_| stream_events) {
+        let _ = app.emit("chat-context-retrieved", context);
+    }
+
+    // Prompt tokens, for the provider's tokens-per-minute limit
+    let prompt_tokens: usize = [
+        system_prompt.as_deref(),
+        history.as_deref(),
+        Some(user_message.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|text| crate::tokens::count(text, &api_config.model).tokens)
+    .sum::<usize>()
+        + retrieved.as_ref().map_or(0, |context| context.tokens);
+    let prompt_tokens = u32::try_from(prompt_tokens).unwrap_or(u32::MAX);
+
     // Build messages array in OpenAI format
     let mut messages: Vec<serde_json::Value> = Vec::new();
 
@@ -513,6 +702,14 @@ pub async fn chat_stream_response(
         }));
     }
 
+    // Add retrieved context after the system prompt, so it can't override it
+    if let Some(context) = retrieved {
+        messages.push(serde_json::json!({
+            "role": "system",
+            "content": context.text
+        }));
+    }
+
     // Add history if provided
     if let Some(history_str) = history {
         if let Ok(history_messages) = serde_json::from_str::<Vec<serde_json::Value>>(&history_str) {
@@ -588,21 +785,29 @@ pub async fn chat_stream_response(
         }
     }
 
+    // Wait for the provider's rate limits
+    crate::rate_limit::acquire(&app, CallKind::Chat, provider.as_deref(), prompt_tokens).await?;
+
     // Make HTTP request to the configured endpoint with streaming
-    let client = reqwest::Client::new();
+    let client = network::client(&app, provider.as_deref())?;
+    let url = network::provider_url(&app, provider.as_deref(), &api_config.url)?;
     let error_rules = api_config.errors.clone().unwrap_or_default();
-    let response = match client
-        .post(&api_config.url)
+    let token = provider_token(provider.as_deref(), &api_config.user_token).await?;
+    let request = client
+        .post(&url)
         .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_config.user_token))
+        .header("Authorization", format!("Bearer {}", token))
         .json(&request_body)
-        .send()
-        .await
-    {
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+    let log = network_log::start(&app, provider.as_deref(), &request);
+    let response = match client.execute(request).await {
         Ok(resp) => resp,
         Err(e) => {
+            log.finish(None, None, Some(&e.to_string()));
+            offline_queue::observe_error(&app, &e);
             let mut sources = vec![e.to_string()];
-            if let Ok(url) = Url::parse(&api_config.url) {
+            if let Ok(url) = Url::parse(&url) {
                 sources.push(url.to_string());
             }
             let final_message = map_api_error_message(&error_rules, &sources);
@@ -627,6 +832,7 @@ pub async fn chat_stream_response(
             .text()
             .await
             .unwrap_or_else(|_| "Unknown server error".to_string());
+        log.finish(Some(status.as_u16()), Some(&error_text), None);
 
         let mut sources = vec![error_text.clone(), status.to_string()];
 
@@ -654,6 +860,7 @@ pub async fn chat_stream_response(
     }
 
     // Handle streaming response
+    let status_code = response.status().as_u16();
     let mut stream = response.bytes_stream();
     let mut full_response = String::new();
     let mut buffer = String::new();
@@ -702,7 +909,9 @@ pub async fn chat_stream_response(
                                             {
                                                 full_response.push_str(content);
                                                 // Emit just the content to frontend
-                                                let _ = app.emit("chat_stream_chunk", content);
+                                                if stream_events {
+                                                    let _ = app.emit("chat_stream_chunk", content);
+                                                }
                                                 stream_started = true;
                                             }
                                         }
@@ -717,6 +926,11 @@ pub async fn chat_stream_response(
                 buffer = incomplete_line;
             }
             Err(e) => {
+                log.finish(
+                    Some(status_code),
+                    Some(&full_response),
+                    Some(&e.to_string()),
+                );
                 let sources = vec![e.to_string()];
                 let final_message = map_api_error_message(&error_rules, &sources);
                 tauri::async_runtime::spawn({
@@ -734,10 +948,28 @@ pub async fn chat_stream_response(
         }
     }
 
+    log.finish(Some(status_code), Some(&full_response), None);
+
     // Emit completion event
-    let _ = app.emit("chat_stream_complete", &full_response);
+    if stream_events {
+        let _ = app.emit("chat_stream_complete", &full_response);
+    }
 
     if stream_started && !full_response.is_empty() {
+        // Provider-reported counts where given, else our own
+        let (input_tokens, output_tokens) = usage
+            .as_ref()
+            .map(crate::usage::reported_tokens)
+            .unwrap_or_default();
+        crate::usage::record_chat(
+            &app,
+            provider.as_deref(),
+            &api_config.model,
+            input_tokens.unwrap_or(prompt_tokens),
+            output_tokens.unwrap_or_else(|| {
+                crate::tokens::count(&full_response, &api_config.model).tokens as u32
+            }),
+        );
         tauri::async_runtime::spawn({
             let activity_app = app.clone();
             let activity_model = api_config.model.clone();
@@ -758,6 +990,28 @@ pub async fn chat_stream_response(
     Ok(full_response)
 }
 
+/// The model's whole reply to `user_message`, for backend tasks: no events,
+/// no retrieved context.
+pub(crate) async fn complete(
+    app: &AppHandle,
+    system_prompt: &str,
+    user_message: String,
+) -> Result<String, String> {
+    chat_completion(
+        app.clone(),
+        user_message,
+        Some(system_prompt.to_string()),
+        None,
+        None,
+        None,
+        ChatOptions {
+            stream_events: false,
+            retrieve_context: false,
+        },
+    )
+    .await
+}
+
 async fn user_activity(
     app: AppHandle,
     activity_metrics: Option<serde_json::Value>,
@@ -811,7 +1065,7 @@ async fn user_activity(
     }
 
     let activity_url = format!("{}/api/activity", app_endpoint.trim_end_matches('/'));
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
 
     let _ = client
         .post(&activity_url)
@@ -877,7 +1131,9 @@ async fn report_api_error(
     });
 
     let error_url = format!("{}/api/error", app_endpoint.trim_end_matches('/'));
-    let client = reqwest::Client::new();
+    let Ok(client) = network::client(&app, None) else {
+        return;
+    };
 
     tracing::debug!("Reporting API error: {:?}", payload);
 
@@ -913,7 +1169,7 @@ pub async fn fetch_models(app: AppHandle) -> Result<Vec<Model>, String> {
     let app_version = app.package_info().version.to_string();
 
     // Make HTTP request to models endpoint
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let url = format!("{}/api/models", app_endpoint);
 
     let response = client
@@ -971,11 +1227,11 @@ pub async fn fetch_models(app: AppHandle) -> Result<Vec<Model>, String> {
 
 // Fetch Runningbord Prompts API
 #[tauri::command]
-pub async fn fetch_prompts() -> Result<RunningbordPromptsResponse, String> {
+pub async fn fetch_prompts(app: AppHandle) -> Result<RunningbordPromptsResponse, String> {
     let app_endpoint = get_app_endpoint()?;
     let api_access_key = get_api_access_key()?;
 
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let url = format!("{}/api/prompts", app_endpoint);
 
     let response = client
@@ -1038,7 +1294,7 @@ pub async fn create_system_prompt(
     let machine_id: String = app.machine_uid().get_machine_uid().unwrap().id.unwrap();
     let app_version: String = app.package_info().version.to_string();
     // Make HTTP request to models endpoint
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let url = format!("{}/api/prompt", app_endpoint);
 
     let response = client
@@ -1125,7 +1381,7 @@ pub async fn get_activity(app: AppHandle) -> Result<serde_json::Value, String> {
 
     let app_version = app.package_info().version.to_string();
 
-    let client = reqwest::Client::new();
+    let client = network::client(&app, None)?;
     let activity_url = format!("{}/api/activity", app_endpoint.trim_end_matches('/'));
 
     let response = client
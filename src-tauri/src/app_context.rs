@@ -0,0 +1,228 @@
+//! What the user is looking at right now, for grounding questions sent to
+//! the LLM ("user is currently in Xcode, window Foo.swift").
+//!
+//! When the foreground app is a browser, the current tab's URL and title are
+//! read as well:
+//! On macOS: AppleScript (`osascript`) for Safari and Chromium browsers,
+//! which needs the Automation permission for each browser. Firefox has no
+//! scripting dictionary and only gets its window title.
+//! On Windows: UI Automation (via PowerShell) reads the address bar, which
+//! usually omits the scheme; `https://` is assumed.
+//! On Linux: not available.
+//!
+//! Apps on the privacy blocklist are reported by name only.
+
+use crate::frontmost_app::{frontmost_app, window_title, FrontmostApp};
+use crate::privacy::app_blocked;
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BrowserTab {
+    pub url: String,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveWindow {
+    pub app: FrontmostApp,
+    pub title: Option<String>,
+    /// Set when `app` is a supported browser.
+    pub browser_tab: Option<BrowserTab>,
+    /// One sentence for the prompt, e.g.
+    /// `User is currently in Xcode, window "Foo.swift — MyApp".`
+    pub summary: String,
+}
+
+impl ActiveWindow {
+    fn new(app: FrontmostApp, title: Option<String>, browser_tab: Option<BrowserTab>) -> Self {
+        let mut summary = format!("User is currently in {}", app.name);
+        if let Some(tab) = &browser_tab {
+            if let Some(tab_title) = &tab.title {
+                summary.push_str(&format!(", tab \"{}\"", tab_title));
+            }
+            summary.push_str(&format!(" at {}", tab.url));
+        } else if let Some(title) = &title {
+            summary.push_str(&format!(", window \"{}\"", title));
+        }
+        summary.push('.');
+        Self {
+            app,
+            title,
+            browser_tab,
+            summary,
+        }
+    }
+}
+
+/// Turn what a browser reports as the current address into a URL. Address
+/// bars drop the scheme, and while the user is typing a search they hold
+/// text that isn't a URL at all.
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn normalize_url(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if raw.is_empty() || raw.contains(char::is_whitespace) {
+        return None;
+    }
+    let has_scheme = raw.contains("://")
+        || ["about:", "data:", "mailto:", "javascript:"]
+            .iter()
+            .any(|scheme| raw.starts_with(scheme));
+    if has_scheme {
+        Some(raw.to_string())
+    } else if raw.contains('.') || raw.starts_with("localhost") {
+        Some(format!("https://{}", raw))
+    } else {
+        None
+    }
+}
+
+/// Parse the `URL\nTITLE` lines printed by the platform scripts.
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn parse_tab_output(output: &str) -> Option<BrowserTab> {
+    let mut lines = output.lines();
+    let url = normalize_url(lines.next()?)?;
+    let title = lines
+        .next()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string);
+    Some(BrowserTab { url, title })
+}
+
+/// The current tab of `app`, if it is a supported browser.
+pub fn browser_tab(app: &FrontmostApp) -> Option<BrowserTab> {
+    platform::browser_tab(app)
+}
+
+/// The foreground app, its window title and, for browsers, the current tab.
+pub fn active_window(app: &AppHandle) -> Option<ActiveWindow> {
+    let front = frontmost_app()?;
+    if app_blocked(app, &front) {
+        return Some(ActiveWindow::new(front, None, None));
+    }
+    let title = window_title(&front);
+    let tab = browser_tab(&front);
+    Some(ActiveWindow::new(front, title, tab))
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{parse_tab_output, BrowserTab, FrontmostApp};
+    use std::process::Command;
+
+    /// Bundle IDs that use Safari's scripting dictionary.
+    const SAFARI: &[&str] = &["com.apple.Safari", "com.apple.SafariTechnologyPreview"];
+    /// Bundle IDs that use Chrome's scripting dictionary.
+    const CHROMIUM: &[&str] = &[
+        "com.google.Chrome",
+        "com.google.Chrome.beta",
+        "com.google.Chrome.canary",
+        "com.microsoft.edgemac",
+        "com.brave.Browser",
+        "com.vivaldi.Vivaldi",
+        "com.operasoftware.Opera",
+        "company.thebrowser.Browser",
+    ];
+
+    pub fn browser_tab(app: &FrontmostApp) -> Option<BrowserTab> {
+        let id = app.app_id.as_str();
+        let script = if SAFARI.contains(&id) {
+            format!(
+                "tell application id \"{}\" to tell front document to return (URL as text) & linefeed & name",
+                id
+            )
+        } else if CHROMIUM.contains(&id) {
+            format!(
+                "tell application id \"{}\" to tell active tab of front window to return (URL as text) & linefeed & title",
+                id
+            )
+        } else {
+            return None;
+        };
+        let output = Command::new("osascript")
+            .args(["-e", &script])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            tracing::debug!(
+                "Browser tab lookup for {} failed: {}",
+                id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        parse_tab_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{parse_tab_output, BrowserTab, FrontmostApp};
+    use crate::hidden_command;
+
+    const BROWSERS: &[&str] = &[
+        "chrome.exe",
+        "msedge.exe",
+        "brave.exe",
+        "vivaldi.exe",
+        "opera.exe",
+        "firefox.exe",
+    ];
+
+    /// Prints the first edit field (the address bar) of the process's main
+    /// window, then the window name without its " - Browser" suffix.
+    const SCRIPT: &str = r#"
+Add-Type -AssemblyName UIAutomationClient,UIAutomationTypes
+$A = [Windows.Automation.AutomationElement]
+$w = $A::FromHandle((Get-Process -Id {pid}).MainWindowHandle)
+$c = New-Object Windows.Automation.PropertyCondition($A::ControlTypeProperty, [Windows.Automation.ControlType]::Edit)
+$e = $w.FindFirst([Windows.Automation.TreeScope]::Descendants, $c)
+$e.GetCurrentPattern([Windows.Automation.ValuePattern]::Pattern).Current.Value
+$w.Current.Name -replace ' [-—] [^-—]+$', ''
+"#;
+
+    pub fn browser_tab(app: &FrontmostApp) -> Option<BrowserTab> {
+        if !BROWSERS
+            .iter()
+            .any(|exe| app.app_id.eq_ignore_ascii_case(exe))
+        {
+            return None;
+        }
+        let script = SCRIPT.replace("{pid}", &app.pid?.to_string());
+        let output = hidden_command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            tracing::debug!(
+                "Browser tab lookup for {} failed: {}",
+                app.app_id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        parse_tab_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::{BrowserTab, FrontmostApp};
+
+    pub fn browser_tab(_app: &FrontmostApp) -> Option<BrowserTab> {
+        None
+    }
+}
+
+/// Return the foreground app, window title and browser tab, or `None` if
+/// the platform doesn't expose them (e.g. Wayland).
+#[tauri::command]
+pub async fn get_active_window(app: AppHandle) -> Result<Option<ActiveWindow>, String> {
+    tauri::async_runtime::spawn_blocking(move || active_window(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,43 @@
+use super::*;
+
+#[test]
+fn address_bar_text_becomes_a_url() {
+    assert_eq!(
+        normalize_url("https://example.com/a?b=c"),
+        Some("https://example.com/a?b=c".to_string())
+    );
+    assert_eq!(
+        normalize_url("github.com/rust-lang/rust "),
+        Some("https://github.com/rust-lang/rust".to_string())
+    );
+    assert_eq!(
+        normalize_url("localhost:3000/login"),
+        Some("https://localhost:3000/login".to_string())
+    );
+    assert_eq!(
+        normalize_url("about:blank"),
+        Some("about:blank".to_string())
+    );
+    assert_eq!(normalize_url("how to center a div"), None);
+    assert_eq!(normalize_url("weather"), None);
+    assert_eq!(normalize_url(""), None);
+}
+
+#[test]
+fn tab_output_is_url_then_title() {
+    assert_eq!(
+        parse_tab_output("https://docs.rs/serde\nserde - Rust\n"),
+        Some(BrowserTab {
+            url: "https://docs.rs/serde".to_string(),
+            title: Some("serde - Rust".to_string()),
+        })
+    );
+    assert_eq!(
+        parse_tab_output("https://example.com\n\n"),
+        Some(BrowserTab {
+            url: "https://example.com".to_string(),
+            title: None,
+        })
+    );
+    assert_eq!(parse_tab_output("\nNew Tab\n"), None);
+}
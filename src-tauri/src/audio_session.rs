@@ -0,0 +1,852 @@
+//! Audio session handling around assistant speech: while a TTS answer is
+//! played, other apps' audio can be lowered ("ducked") so the answer is
+//! heard over it, and put back when it ends.
+//!
+//! The webview brackets playback with `audio_session_speech_started` and
+//! `audio_session_speech_ended`, and `tts` does the same for the answers it
+//! plays; overlapping answers keep the audio ducked until the last one
+//! ends. Ducking is per app stream, never the system volume, so the answer
+//! itself (played by this process or its webview's child processes) stays
+//! at full volume. Streams that start mid-answer aren't ducked. A stream is
+//! only put back if the user hasn't changed its volume in the meantime.
+//!
+//! The volume calls run on a worker thread, so callers never wait on them.
+//! What is about to be ducked is saved first, and `init` puts it back on
+//! the next start if the app died while ducked.
+//!
+//! On Windows: the audio sessions of the default output device.
+//! On Linux: PipeWire output streams, listed with `pw-dump` and set with
+//! `wpctl`.
+//! On macOS: not available. There's no per-app volume, and lowering the
+//! output volume would lower the answer too.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use tauri::{AppHandle, Manager};
+
+/// Volume difference below which the current volume still counts as the
+/// ducked one; the platforms round what they're given.
+const VOLUME_TOLERANCE: f32 = 0.02;
+/// Parent links followed when checking whether a process is ours.
+#[cfg(any(target_os = "windows", target_os = "linux", test))]
+const MAX_PROCESS_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DuckingConfig {
+    pub enabled: bool,
+    /// Fraction of each stream's volume to play other audio at, 0–1.
+    pub level: f32,
+}
+
+impl Default for DuckingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            level: 0.3,
+        }
+    }
+}
+
+impl DuckingConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.level) {
+            return Err(format!("level must be between 0 and 1, got {}", self.level));
+        }
+        if self.enabled {
+            platform::check_supported()?;
+        }
+        Ok(())
+    }
+}
+
+/// A stream volume change to undo.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Ducked {
+    /// Platform id of the stream.
+    stream: String,
+    original: f32,
+    ducked: f32,
+}
+
+/// Another app's output stream.
+#[cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(dead_code))]
+struct Stream {
+    id: String,
+    /// 0–1.
+    volume: f32,
+}
+
+#[derive(Default)]
+struct Session {
+    /// Answers currently being spoken.
+    speaking: usize,
+    /// Streams lowered for the current answers; `None` while not ducked.
+    ducked: Option<Vec<Ducked>>,
+}
+
+#[derive(Default)]
+pub struct AudioSessionState {
+    config: Mutex<DuckingConfig>,
+    session: Mutex<Session>,
+    /// Wakes the worker to duck or restore; started on first use.
+    worker: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct AudioSessionStatus {
+    pub speaking: bool,
+    pub ducked: bool,
+    /// Streams lowered while ducked.
+    pub ducked_streams: usize,
+}
+
+/// Whether to put `ducked.original` back: not if the user moved the volume
+/// away from what it was ducked to.
+fn should_restore(current: Option<f32>, ducked: &Ducked) -> bool {
+    match current {
+        Some(current) => (current - ducked.ducked).abs() <= VOLUME_TOLERANCE,
+        None => true,
+    }
+}
+
+/// Whether `pid` is `ancestor` or was started by it, following `parent`.
+#[cfg(any(target_os = "windows", target_os = "linux", test))]
+fn descends_from(pid: u32, ancestor: u32, parent: impl Fn(u32) -> Option<u32>) -> bool {
+    let mut pid = pid;
+    for _ in 0..MAX_PROCESS_DEPTH {
+        if pid == ancestor {
+            return true;
+        }
+        match parent(pid) {
+            Some(next) if next != 0 && next != pid => pid = next,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// `wpctl get-volume` prints `Volume: 0.40`, with ` [MUTED]` when muted.
+#[cfg(any(target_os = "linux", test))]
+fn parse_wpctl_volume(output: &str) -> Option<f32> {
+    let value = output.trim().strip_prefix("Volume:")?;
+    let volume: f32 = value.split_whitespace().next()?.parse().ok()?;
+    Some(volume.clamp(0.0, 1.0))
+}
+
+/// Output streams in `pw-dump`'s JSON, as `(node id, process id)`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_pw_dump_streams(json: &str) -> Vec<(u32, Option<u32>)> {
+    let Ok(serde_json::Value::Array(objects)) = serde_json::from_str(json) else {
+        return Vec::new();
+    };
+    objects
+        .iter()
+        .filter_map(|object| {
+            let props = object.get("info")?.get("props")?;
+            if props.get("media.class")?.as_str()? != "Stream/Output/Audio" {
+                return None;
+            }
+            let id = u32::try_from(object.get("id")?.as_u64()?).ok()?;
+            // A number, or a string in older PipeWire versions.
+            let pid = props.get("application.process.id").and_then(|pid| {
+                pid.as_u64()
+                    .or_else(|| pid.as_str()?.parse().ok())
+                    .and_then(|pid| u32::try_from(pid).ok())
+            });
+            Some((id, pid))
+        })
+        .collect()
+}
+
+/// Parent process id from `/proc/<pid>/stat`: the field after the state,
+/// which follows the parenthesized command name.
+#[cfg(any(target_os = "linux", test))]
+fn parse_stat_parent(stat: &str) -> Option<u32> {
+    stat.rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{descends_from, Stream};
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+    use std::ptr;
+
+    #[repr(C)]
+    struct Guid {
+        data1: u32,
+        data2: u16,
+        data3: u16,
+        data4: [u8; 8],
+    }
+
+    const CLSID_MM_DEVICE_ENUMERATOR: Guid = Guid {
+        data1: 0xBCDE0395,
+        data2: 0xE52F,
+        data3: 0x467C,
+        data4: [0x8E, 0x3D, 0xC4, 0x57, 0x92, 0x91, 0x69, 0x2E],
+    };
+    const IID_IMM_DEVICE_ENUMERATOR: Guid = Guid {
+        data1: 0xA95664D2,
+        data2: 0x9614,
+        data3: 0x4F35,
+        data4: [0xA7, 0x46, 0xDE, 0x8D, 0xB6, 0x36, 0x17, 0xE6],
+    };
+    const IID_IAUDIO_SESSION_MANAGER2: Guid = Guid {
+        data1: 0x77AA99A0,
+        data2: 0x1BD6,
+        data3: 0x484F,
+        data4: [0x8B, 0xC7, 0x2C, 0x65, 0x4C, 0x9A, 0x9B, 0x6F],
+    };
+    const IID_IAUDIO_SESSION_CONTROL2: Guid = Guid {
+        data1: 0xBFB7FF88,
+        data2: 0x7239,
+        data3: 0x4FC9,
+        data4: [0x8F, 0xA2, 0x07, 0xC9, 0x50, 0xBE, 0x9C, 0x6D],
+    };
+    const IID_ISIMPLE_AUDIO_VOLUME: Guid = Guid {
+        data1: 0x87CE5498,
+        data2: 0x68D6,
+        data3: 0x44E5,
+        data4: [0x92, 0x15, 0x6D, 0xA4, 0x7E, 0xF8, 0x83, 0xD8],
+    };
+
+    const COINIT_MULTITHREADED: u32 = 0;
+    /// COM is already set up on this thread in another mode; use it as is.
+    const RPC_E_CHANGED_MODE: i32 = 0x8001_0106_u32 as i32;
+    const CLSCTX_ALL: u32 = 0x17;
+    const E_RENDER: i32 = 0;
+    const E_MULTIMEDIA: i32 = 1;
+    const TH32CS_SNAPPROCESS: u32 = 0x2;
+    const INVALID_HANDLE_VALUE: isize = -1;
+
+    // Vtable slots, counting IUnknown's three.
+    const QUERY_INTERFACE: usize = 0;
+    const RELEASE: usize = 2;
+    const GET_DEFAULT_AUDIO_ENDPOINT: usize = 4;
+    const ACTIVATE: usize = 3;
+    const GET_SESSION_ENUMERATOR: usize = 5;
+    const GET_COUNT: usize = 3;
+    const GET_SESSION: usize = 4;
+    const GET_SESSION_INSTANCE_IDENTIFIER: usize = 13;
+    const GET_PROCESS_ID: usize = 14;
+    const SET_MASTER_VOLUME: usize = 3;
+    const GET_MASTER_VOLUME: usize = 4;
+
+    type QueryInterface =
+        unsafe extern "system" fn(*mut c_void, *const Guid, *mut *mut c_void) -> i32;
+    type Release = unsafe extern "system" fn(*mut c_void) -> u32;
+    type GetDefaultAudioEndpoint =
+        unsafe extern "system" fn(*mut c_void, i32, i32, *mut *mut c_void) -> i32;
+    type Activate = unsafe extern "system" fn(
+        *mut c_void,
+        *const Guid,
+        u32,
+        *const c_void,
+        *mut *mut c_void,
+    ) -> i32;
+    type GetInterface = unsafe extern "system" fn(*mut c_void, *mut *mut c_void) -> i32;
+    type GetCount = unsafe extern "system" fn(*mut c_void, *mut i32) -> i32;
+    type GetSession = unsafe extern "system" fn(*mut c_void, i32, *mut *mut c_void) -> i32;
+    type GetString = unsafe extern "system" fn(*mut c_void, *mut *mut u16) -> i32;
+    type GetProcessId = unsafe extern "system" fn(*mut c_void, *mut u32) -> i32;
+    type SetMasterVolume = unsafe extern "system" fn(*mut c_void, f32, *const Guid) -> i32;
+    type GetMasterVolume = unsafe extern "system" fn(*mut c_void, *mut f32) -> i32;
+
+    #[repr(C)]
+    struct ProcessEntry32W {
+        size: u32,
+        usage: u32,
+        process_id: u32,
+        default_heap_id: usize,
+        module_id: u32,
+        threads: u32,
+        parent_process_id: u32,
+        pri_class_base: i32,
+        flags: u32,
+        exe_file: [u16; 260],
+    }
+
+    #[link(name = "ole32")]
+    extern "system" {
+        fn CoInitializeEx(reserved: *mut c_void, coinit: u32) -> i32;
+        fn CoUninitialize();
+        fn CoCreateInstance(
+            clsid: *const Guid,
+            outer: *mut c_void,
+            context: u32,
+            iid: *const Guid,
+            out: *mut *mut c_void,
+        ) -> i32;
+        fn CoTaskMemFree(memory: *mut c_void);
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateToolhelp32Snapshot(flags: u32, process_id: u32) -> *mut c_void;
+        fn Process32FirstW(snapshot: *mut c_void, entry: *mut ProcessEntry32W) -> i32;
+        fn Process32NextW(snapshot: *mut c_void, entry: *mut ProcessEntry32W) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    /// COM initialized for the current thread while alive.
+    struct ComScope {
+        initialized: bool,
+    }
+
+    impl ComScope {
+        fn init() -> Result<Self, String> {
+            let hr = unsafe { CoInitializeEx(ptr::null_mut(), COINIT_MULTITHREADED) };
+            if hr < 0 && hr != RPC_E_CHANGED_MODE {
+                return Err(format!("CoInitializeEx failed: 0x{:08X}", hr as u32));
+            }
+            Ok(Self {
+                initialized: hr >= 0,
+            })
+        }
+    }
+
+    impl Drop for ComScope {
+        fn drop(&mut self) {
+            if self.initialized {
+                unsafe { CoUninitialize() };
+            }
+        }
+    }
+
+    /// An owned COM interface pointer, released on drop.
+    struct Com(*mut c_void);
+
+    impl Com {
+        /// The interface `out` from a call that returned `hr`.
+        fn from_call(hr: i32, out: *mut c_void, call: &str) -> Result<Self, String> {
+            if hr < 0 || out.is_null() {
+                return Err(format!("{} failed: 0x{:08X}", call, hr as u32));
+            }
+            Ok(Self(out))
+        }
+
+        /// Method `slot` of the interface's vtable, as `F`.
+        unsafe fn method<F: Copy>(&self, slot: usize) -> F {
+            let vtable = *(self.0 as *const *const usize);
+            std::mem::transmute_copy(&*vtable.add(slot))
+        }
+
+        fn query(&self, iid: &Guid) -> Result<Com, String> {
+            let mut out = ptr::null_mut();
+            let hr = unsafe {
+                let query: QueryInterface = self.method(QUERY_INTERFACE);
+                query(self.0, iid, &mut out)
+            };
+            Com::from_call(hr, out, "QueryInterface")
+        }
+    }
+
+    impl Drop for Com {
+        fn drop(&mut self) {
+            unsafe {
+                let release: Release = self.method(RELEASE);
+                release(self.0);
+            }
+        }
+    }
+
+    /// An audio session on the default output device.
+    struct Session {
+        id: String,
+        process_id: u32,
+        /// ISimpleAudioVolume.
+        volume: Com,
+    }
+
+    impl Session {
+        fn volume(&self) -> Option<f32> {
+            let mut level = 0.0f32;
+            let hr = unsafe {
+                let get: GetMasterVolume = self.volume.method(GET_MASTER_VOLUME);
+                get(self.volume.0, &mut level)
+            };
+            (hr >= 0).then(|| level.clamp(0.0, 1.0))
+        }
+
+        fn set_volume(&self, level: f32) -> Result<(), String> {
+            let hr = unsafe {
+                let set: SetMasterVolume = self.volume.method(SET_MASTER_VOLUME);
+                set(self.volume.0, level.clamp(0.0, 1.0), ptr::null())
+            };
+            if hr < 0 {
+                return Err(format!("SetMasterVolume failed: 0x{:08X}", hr as u32));
+            }
+            Ok(())
+        }
+    }
+
+    fn wide_to_string(wide: *const u16) -> String {
+        unsafe {
+            let len = (0..).take_while(|&i| *wide.add(i) != 0).count();
+            String::from_utf16_lossy(std::slice::from_raw_parts(wide, len))
+        }
+    }
+
+    /// Sessions of the default output device. Must be dropped before the
+    /// `ComScope` they were made in.
+    fn sessions() -> Result<Vec<Session>, String> {
+        unsafe {
+            let mut out = ptr::null_mut();
+            let hr = CoCreateInstance(
+                &CLSID_MM_DEVICE_ENUMERATOR,
+                ptr::null_mut(),
+                CLSCTX_ALL,
+                &IID_IMM_DEVICE_ENUMERATOR,
+                &mut out,
+            );
+            let enumerator = Com::from_call(hr, out, "CoCreateInstance")?;
+            let get_endpoint: GetDefaultAudioEndpoint =
+                enumerator.method(GET_DEFAULT_AUDIO_ENDPOINT);
+            let hr = get_endpoint(enumerator.0, E_RENDER, E_MULTIMEDIA, &mut out);
+            let device = Com::from_call(hr, out, "GetDefaultAudioEndpoint")?;
+            let activate: Activate = device.method(ACTIVATE);
+            let hr = activate(
+                device.0,
+                &IID_IAUDIO_SESSION_MANAGER2,
+                CLSCTX_ALL,
+                ptr::null(),
+                &mut out,
+            );
+            let manager = Com::from_call(hr, out, "Activate")?;
+            let get_enumerator: GetInterface = manager.method(GET_SESSION_ENUMERATOR);
+            let hr = get_enumerator(manager.0, &mut out);
+            let session_enumerator = Com::from_call(hr, out, "GetSessionEnumerator")?;
+            let mut count = 0;
+            let get_count: GetCount = session_enumerator.method(GET_COUNT);
+            if get_count(session_enumerator.0, &mut count) < 0 {
+                return Err("Failed to count audio sessions".to_string());
+            }
+            let get_session: GetSession = session_enumerator.method(GET_SESSION);
+            let mut sessions = Vec::new();
+            for index in 0..count {
+                let hr = get_session(session_enumerator.0, index, &mut out);
+                let Ok(control) = Com::from_call(hr, out, "GetSession") else {
+                    continue;
+                };
+                let Ok(control2) = control.query(&IID_IAUDIO_SESSION_CONTROL2) else {
+                    continue;
+                };
+                let mut process_id = 0;
+                let get_process_id: GetProcessId = control2.method(GET_PROCESS_ID);
+                if get_process_id(control2.0, &mut process_id) < 0 {
+                    continue;
+                }
+                let mut wide = ptr::null_mut();
+                let get_id: GetString = control2.method(GET_SESSION_INSTANCE_IDENTIFIER);
+                if get_id(control2.0, &mut wide) < 0 || wide.is_null() {
+                    continue;
+                }
+                let id = wide_to_string(wide);
+                CoTaskMemFree(wide.cast());
+                let Ok(volume) = control.query(&IID_ISIMPLE_AUDIO_VOLUME) else {
+                    continue;
+                };
+                sessions.push(Session {
+                    id,
+                    process_id,
+                    volume,
+                });
+            }
+            Ok(sessions)
+        }
+    }
+
+    fn with_sessions<T>(f: impl FnOnce(&[Session]) -> Result<T, String>) -> Result<T, String> {
+        // Declared first so it's dropped after the sessions.
+        let _com = ComScope::init()?;
+        let sessions = sessions()?;
+        f(&sessions)
+    }
+
+    /// Each running process's parent.
+    fn parents() -> HashMap<u32, u32> {
+        let mut parents = HashMap::new();
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+            if snapshot as isize == INVALID_HANDLE_VALUE {
+                return parents;
+            }
+            let mut entry: ProcessEntry32W = std::mem::zeroed();
+            entry.size = std::mem::size_of::<ProcessEntry32W>() as u32;
+            let mut more = Process32FirstW(snapshot, &mut entry) != 0;
+            while more {
+                parents.insert(entry.process_id, entry.parent_process_id);
+                more = Process32NextW(snapshot, &mut entry) != 0;
+            }
+            CloseHandle(snapshot);
+        }
+        parents
+    }
+
+    pub fn check_supported() -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn other_streams() -> Result<Vec<Stream>, String> {
+        let parents = parents();
+        let own = std::process::id();
+        with_sessions(|sessions| {
+            Ok(sessions
+                .iter()
+                // Process 0 is the system sounds session.
+                .filter(|session| session.process_id != 0)
+                .filter(|session| {
+                    !descends_from(session.process_id, own, |pid| parents.get(&pid).copied())
+                })
+                .filter_map(|session| {
+                    Some(Stream {
+                        id: session.id.clone(),
+                        volume: session.volume()?,
+                    })
+                })
+                .collect())
+        })
+    }
+
+    /// `None` if the stream has ended.
+    pub fn stream_volume(id: &str) -> Result<Option<f32>, String> {
+        with_sessions(|sessions| {
+            Ok(sessions
+                .iter()
+                .find(|session| session.id == id)
+                .and_then(|session| session.volume()))
+        })
+    }
+
+    pub fn set_stream_volume(id: &str, volume: f32) -> Result<(), String> {
+        with_sessions(|sessions| {
+            sessions
+                .iter()
+                .find(|session| session.id == id)
+                .ok_or_else(|| "The audio session has ended".to_string())?
+                .set_volume(volume)
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{
+        descends_from, parse_pw_dump_streams, parse_stat_parent, parse_wpctl_volume, Stream,
+    };
+    use std::fs;
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> Result<String, String> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn parent(pid: u32) -> Option<u32> {
+        parse_stat_parent(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+    }
+
+    pub fn check_supported() -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn other_streams() -> Result<Vec<Stream>, String> {
+        let own = std::process::id();
+        let streams = parse_pw_dump_streams(&run("pw-dump", &[])?);
+        Ok(streams
+            .into_iter()
+            .filter(|(_, pid)| !pid.is_some_and(|pid| descends_from(pid, own, parent)))
+            .filter_map(|(id, _)| {
+                let id = id.to_string();
+                let volume = stream_volume(&id).ok()??;
+                Some(Stream { id, volume })
+            })
+            .collect())
+    }
+
+    /// `None` if the stream has ended; `wpctl` fails for ids that are gone.
+    pub fn stream_volume(id: &str) -> Result<Option<f32>, String> {
+        Ok(run("wpctl", &["get-volume", id])
+            .ok()
+            .and_then(|output| parse_wpctl_volume(&output)))
+    }
+
+    pub fn set_stream_volume(id: &str, volume: f32) -> Result<(), String> {
+        run("wpctl", &["set-volume", id, &format!("{:.3}", volume)]).map(|_| ())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod platform {
+    use super::Stream;
+
+    const UNSUPPORTED: &str =
+        "Ducking needs per-app volume control, which this platform doesn't have";
+
+    pub fn check_supported() -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn other_streams() -> Result<Vec<Stream>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn stream_volume(_id: &str) -> Result<Option<f32>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn set_stream_volume(_id: &str, _volume: f32) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+/// Where the streams being ducked are saved, for `init` after a crash.
+fn saved_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data_dir.join("ducked_streams.json"))
+}
+
+fn save(app: &AppHandle, ducked: &[Ducked]) {
+    let result = saved_path(app).and_then(|path| {
+        let json = serde_json::to_vec(ducked).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to save ducked streams: {}", e);
+    }
+}
+
+fn forget_saved(app: &AppHandle) {
+    if let Ok(path) = saved_path(app) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Lower every other app's stream to `level` of its volume. The plan is
+/// saved before anything is changed.
+fn duck(app: &AppHandle, level: f32) -> Result<Vec<Ducked>, String> {
+    let ducked: Vec<Ducked> = platform::other_streams()?
+        .into_iter()
+        .map(|stream| Ducked {
+            stream: stream.id,
+            original: stream.volume,
+            ducked: stream.volume * level,
+        })
+        .collect();
+    save(app, &ducked);
+    for d in &ducked {
+        if let Err(e) = platform::set_stream_volume(&d.stream, d.ducked) {
+            tracing::warn!("Failed to duck stream {}: {}", d.stream, e);
+        }
+    }
+    Ok(ducked)
+}
+
+/// Put back ducked volumes, except where the stream has ended or the user
+/// has changed its volume since.
+fn restore(ducked: &[Ducked]) {
+    for d in ducked {
+        let current = match platform::stream_volume(&d.stream) {
+            Ok(None) => continue,
+            Ok(current) => current,
+            Err(_) => None,
+        };
+        if !should_restore(current, d) {
+            tracing::info!(
+                "Volume of stream {} changed while ducked; leaving it",
+                d.stream
+            );
+            continue;
+        }
+        if let Err(e) = platform::set_stream_volume(&d.stream, d.original) {
+            tracing::warn!("Failed to restore stream {}: {}", d.stream, e);
+        }
+    }
+}
+
+fn current_config(app: &AppHandle) -> Result<DuckingConfig, String> {
+    Ok(*app
+        .state::<AudioSessionState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?)
+}
+
+/// Duck or restore so the streams match the config and whether an answer
+/// is playing. Locks are only held to read and record the state, never
+/// across the volume calls.
+fn reconcile(app: &AppHandle) -> Result<(), String> {
+    let config = current_config(app)?;
+    let state = app.state::<AudioSessionState>();
+    let (want, ducked) = {
+        let session = state.session.lock().map_err(|e| e.to_string())?;
+        (
+            config.enabled && session.speaking > 0,
+            session.ducked.is_some(),
+        )
+    };
+    if want && !ducked {
+        let streams = duck(app, config.level)?;
+        state.session.lock().map_err(|e| e.to_string())?.ducked = Some(streams);
+    } else if !want && ducked {
+        let streams = state
+            .session
+            .lock()
+            .map_err(|e| e.to_string())?
+            .ducked
+            .take();
+        restore(&streams.unwrap_or_default());
+        forget_saved(app);
+    }
+    Ok(())
+}
+
+fn run_worker(app: AppHandle, wakeups: mpsc::Receiver<()>) {
+    while wakeups.recv().is_ok() {
+        // A burst of starts and ends is settled once.
+        while wakeups.try_recv().is_ok() {}
+        if let Err(e) = reconcile(&app) {
+            tracing::warn!("Failed to update ducking: {}", e);
+        }
+    }
+}
+
+/// Have the worker catch up with the state, starting it if needed.
+fn wake_worker(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AudioSessionState>();
+    let mut worker = state.worker.lock().map_err(|e| e.to_string())?;
+    if worker.as_ref().is_some_and(|tx| tx.send(()).is_ok()) {
+        return Ok(());
+    }
+    let (tx, rx) = mpsc::channel();
+    let worker_app = app.clone();
+    thread::Builder::new()
+        .name("audio-ducking".to_string())
+        .spawn(move || run_worker(worker_app, rx))
+        .map_err(|e| format!("Failed to start the ducking worker: {}", e))?;
+    tx.send(()).map_err(|e| e.to_string())?;
+    *worker = Some(tx);
+    Ok(())
+}
+
+/// Put back streams left ducked by a previous run that didn't exit
+/// cleanly. Called once at startup.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Ok(path) = saved_path(&app) else {
+            return;
+        };
+        let Ok(json) = fs::read(&path) else {
+            return;
+        };
+        match serde_json::from_slice::<Vec<Ducked>>(&json) {
+            Ok(ducked) => {
+                tracing::info!("Restoring {} streams left ducked", ducked.len());
+                restore(&ducked);
+            }
+            Err(e) => tracing::warn!("Failed to read {}: {}", path.display(), e),
+        }
+        let _ = fs::remove_file(&path);
+    });
+}
+
+/// Undo any ducking on exit, so quitting mid-answer doesn't leave other
+/// apps quiet.
+pub fn restore_on_exit(app: &AppHandle) {
+    let ducked = app
+        .state::<AudioSessionState>()
+        .session
+        .lock()
+        .ok()
+        .and_then(|mut session| session.ducked.take());
+    if let Some(ducked) = ducked {
+        restore(&ducked);
+        forget_saved(app);
+    }
+}
+
+#[tauri::command]
+pub fn audio_session_set_config(app: AppHandle, config: DuckingConfig) -> Result<(), String> {
+    config.validate()?;
+    *app.state::<AudioSessionState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    wake_worker(&app)
+}
+
+#[tauri::command]
+pub fn audio_session_get_config(app: AppHandle) -> Result<DuckingConfig, String> {
+    current_config(&app)
+}
+
+/// An answer started playing: duck other apps if enabled and not already
+/// ducked. Returns without waiting for the volume changes.
+pub fn speech_started(app: &AppHandle) -> Result<(), String> {
+    app.state::<AudioSessionState>()
+        .session
+        .lock()
+        .map_err(|e| e.to_string())?
+        .speaking += 1;
+    wake_worker(app)
+}
+
+/// An answer stopped playing: restore other apps once no answer is being
+/// spoken. Returns without waiting for the volume changes.
+pub fn speech_ended(app: &AppHandle) -> Result<(), String> {
+    {
+        let state = app.state::<AudioSessionState>();
+        let mut session = state.session.lock().map_err(|e| e.to_string())?;
+        session.speaking = session.speaking.saturating_sub(1);
+    }
+    wake_worker(app)
+}
+
+/// Called by the webview when it starts playing an answer itself.
+#[tauri::command]
+pub fn audio_session_speech_started(app: AppHandle) -> Result<(), String> {
+    speech_started(&app)
+}
+
+/// Called by the webview when an answer it played ends.
+#[tauri::command]
+pub fn audio_session_speech_ended(app: AppHandle) -> Result<(), String> {
+    speech_ended(&app)
+}
+
+#[tauri::command]
+pub fn audio_session_status(app: AppHandle) -> Result<AudioSessionStatus, String> {
+    let state = app.state::<AudioSessionState>();
+    let session = state.session.lock().map_err(|e| e.to_string())?;
+    Ok(AudioSessionStatus {
+        speaking: session.speaking > 0,
+        ducked: session.ducked.is_some(),
+        ducked_streams: session.ducked.as_ref().map_or(0, Vec::len),
+    })
+}
+
+#[cfg(test)]
+mod tests;
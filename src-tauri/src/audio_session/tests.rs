@@ -0,0 +1,80 @@
+use super::*;
+use std::collections::HashMap;
+
+#[test]
+fn volume_outputs_are_parsed() {
+    assert_eq!(parse_wpctl_volume("Volume: 0.40\n"), Some(0.4));
+    assert_eq!(parse_wpctl_volume("Volume: 0.25 [MUTED]\n"), Some(0.25));
+    assert_eq!(parse_wpctl_volume("Volume: 1.50"), Some(1.0));
+    assert_eq!(parse_wpctl_volume("error"), None);
+}
+
+#[test]
+fn volume_is_restored_only_if_unchanged() {
+    let ducked = Ducked {
+        stream: "42".to_string(),
+        original: 0.8,
+        ducked: 0.24,
+    };
+    assert!(should_restore(Some(0.25), &ducked));
+    assert!(!should_restore(Some(0.6), &ducked));
+    // Unreadable volume: still put it back.
+    assert!(should_restore(None, &ducked));
+}
+
+#[test]
+fn ducking_level_is_validated() {
+    let config = DuckingConfig {
+        enabled: false,
+        level: 1.5,
+    };
+    assert!(DuckingConfig::default().validate().is_ok());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn output_streams_are_read_from_pw_dump() {
+    let json = r#"[
+        {"id": 30, "type": "PipeWire:Interface:Node",
+         "info": {"props": {"media.class": "Audio/Sink"}}},
+        {"id": 71, "type": "PipeWire:Interface:Node",
+         "info": {"props": {"media.class": "Stream/Output/Audio",
+                            "application.process.id": 4242}}},
+        {"id": 72, "type": "PipeWire:Interface:Node",
+         "info": {"props": {"media.class": "Stream/Output/Audio",
+                            "application.process.id": "77"}}},
+        {"id": 73, "type": "PipeWire:Interface:Node",
+         "info": {"props": {"media.class": "Stream/Input/Audio"}}},
+        {"id": 74, "type": "PipeWire:Interface:Node",
+         "info": {"props": {"media.class": "Stream/Output/Audio"}}},
+        {"id": 3, "type": "PipeWire:Interface:Client", "info": {}}
+    ]"#;
+    assert_eq!(
+        parse_pw_dump_streams(json),
+        vec![(71, Some(4242)), (72, Some(77)), (74, None)]
+    );
+    assert!(parse_pw_dump_streams("not json").is_empty());
+}
+
+#[test]
+fn parent_is_read_from_proc_stat() {
+    assert_eq!(
+        parse_stat_parent("1234 (Web Content) S 1200 1234 1200 0 -1"),
+        Some(1200)
+    );
+    // The command name may contain spaces and parentheses.
+    assert_eq!(parse_stat_parent("99 (a) b) R 7 99 7"), Some(7));
+    assert_eq!(parse_stat_parent("garbage"), None);
+}
+
+#[test]
+fn own_child_processes_are_recognized() {
+    let parents = HashMap::from([(30, 20), (20, 10), (10, 1), (50, 1), (60, 60)]);
+    let parent = |pid: u32| parents.get(&pid).copied();
+    assert!(descends_from(10, 10, parent));
+    assert!(descends_from(30, 10, parent));
+    assert!(!descends_from(50, 10, parent));
+    // A process listed as its own parent doesn't loop.
+    assert!(!descends_from(60, 10, parent));
+    assert!(!descends_from(99, 10, parent));
+}
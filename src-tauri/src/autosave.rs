@@ -0,0 +1,203 @@
+//! Auto-save on quit: when enabled, the retained buffer is encoded and
+//! written to a timestamped file in the recordings folder as the app exits,
+//! so closing the app right after a call doesn't lose it. Saved files are
+//! added to the recordings history.
+//!
+//! Off by default, and skipped for encrypted buffers. The folder defaults
+//! to `<app data>/recordings`.
+//!
+//! Captures started automatically for a meeting or calendar event are
+//! always saved the same way when they stop, since stopping zeroizes the
+//! buffer and the recording is what they were started for.
+
+use crate::history::{self, NewRecording};
+use crate::system_audio::{stop_automatic_session, SystemAudioState};
+use crate::system_audio_encoder::{encoded_duration_seconds, ExportFormat};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+/// History tag of auto-saved recordings.
+const HISTORY_TAG: &str = "auto-save";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoSaveConfig {
+    pub enabled: bool,
+    pub format: ExportFormat,
+    /// Folder to save into; `None` for `<app data>/recordings`.
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Default)]
+pub struct AutoSaveState {
+    config: Mutex<AutoSaveConfig>,
+}
+
+fn default_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("recordings"))
+}
+
+/// The folder recordings are saved to: the configured one, else the
+/// default.
+pub(crate) fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let directory = app
+        .state::<AutoSaveState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .directory
+        .clone();
+    match directory {
+        Some(dir) => Ok(dir),
+        None => default_dir(app),
+    }
+}
+
+/// `recording-2026-10-16_14-05-09.ogg`.
+fn file_name(time: DateTime<Local>, format: ExportFormat) -> String {
+    format!(
+        "recording-{}.{}",
+        time.format("%Y-%m-%d_%H-%M-%S"),
+        format.extension()
+    )
+}
+
+/// Write `bytes` to a new file in `dir` named after `time`, adding a
+/// counter if that name is taken.
+pub(crate) fn write_recording(
+    dir: &Path,
+    time: DateTime<Local>,
+    format: ExportFormat,
+    bytes: &[u8],
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let name = file_name(time, format);
+    let mut path = dir.join(&name);
+    let mut counter = 1;
+    while path.exists() {
+        counter += 1;
+        let stem = name.trim_end_matches(&format!(".{}", format.extension()));
+        path = dir.join(format!("{}-{}.{}", stem, counter, format.extension()));
+    }
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Encode the retained buffer in `format`, write it to the recordings
+/// folder and add it to history tagged `tag`. `Ok(None)` if nothing was
+/// captured.
+async fn save_buffer(
+    app: &AppHandle,
+    audio: &Arc<SystemAudioState>,
+    format: ExportFormat,
+    tag: &str,
+) -> Result<Option<PathBuf>, String> {
+    if audio.written_position() == 0 {
+        return Ok(None);
+    }
+    if audio.is_buffer_encrypted() {
+        // Same rule as crash recovery: plaintext must not reach the disk.
+        return Err("the capture buffer is encrypted".to_string());
+    }
+    let dir = recordings_dir(app)?;
+    let bytes = {
+        let audio = audio.clone();
+        tauri::async_runtime::spawn_blocking(move || audio.get_recent_formats(&[format]))
+            .await
+            .map_err(|e| e.to_string())??
+            .encoded
+            .remove(0)
+    };
+    let path = write_recording(&dir, Local::now(), format, &bytes)?;
+    let recording = NewRecording {
+        path: path.to_string_lossy().to_string(),
+        duration_seconds: encoded_duration_seconds(&bytes),
+        tags: vec![tag.to_string()],
+        ..Default::default()
+    };
+    history::record(app, recording).await.map_err(|e| {
+        format!(
+            "saved {} but failed to add it to history: {}",
+            path.display(),
+            e
+        )
+    })?;
+    Ok(Some(path))
+}
+
+fn config(app: &AppHandle) -> Result<AutoSaveConfig, String> {
+    Ok(app
+        .state::<AutoSaveState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+/// Save the retained buffer if auto-save is on and anything was captured.
+/// Called from the exit path before capture is stopped, since stopping
+/// zeroizes the buffer.
+pub fn save_on_exit(app: &AppHandle, audio: &Arc<SystemAudioState>) {
+    let Ok(config) = config(app) else {
+        return;
+    };
+    if !config.enabled {
+        return;
+    }
+    match tauri::async_runtime::block_on(save_buffer(app, audio, config.format, HISTORY_TAG)) {
+        Ok(Some(path)) => tracing::info!("Auto-saved the capture buffer to {}", path.display()),
+        Ok(None) => {}
+        Err(e) => tracing::error!("Auto-save failed: {}", e),
+    }
+}
+
+/// Save what the automatic capture `session` recorded, tagged `tag`, then
+/// stop it. Nothing happens if another session is running by now. Returns
+/// whether capture was stopped.
+pub(crate) async fn save_and_stop_session(
+    app: &AppHandle,
+    audio: &Arc<SystemAudioState>,
+    session: u64,
+    tag: &str,
+) -> bool {
+    if !audio.is_session_running(session) {
+        return false;
+    }
+    let format = config(app).map(|c| c.format).unwrap_or_default();
+    match save_buffer(app, audio, format, tag).await {
+        Ok(Some(path)) => tracing::info!("Saved the {} capture to {}", tag, path.display()),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("The {} capture was not saved: {}", tag, e),
+    }
+    stop_automatic_session(audio, session).await
+}
+
+#[tauri::command]
+pub fn auto_save_set_config(app: AppHandle, config: AutoSaveConfig) -> Result<(), String> {
+    if let Some(dir) = &config.directory {
+        if !dir.is_absolute() {
+            return Err(format!("{} is not an absolute path", dir.display()));
+        }
+    }
+    *app.state::<AutoSaveState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn auto_save_get_config(app: AppHandle) -> Result<AutoSaveConfig, String> {
+    config(&app)
+}
+
+#[cfg(test)]
+mod tests;
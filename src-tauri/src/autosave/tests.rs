@@ -0,0 +1,33 @@
+use super::*;
+use chrono::TimeZone;
+
+fn at(hour: u32, min: u32, sec: u32) -> DateTime<Local> {
+    Local
+        .with_ymd_and_hms(2026, 10, 16, hour, min, sec)
+        .unwrap()
+}
+
+#[test]
+fn file_names_carry_the_time_and_extension() {
+    assert_eq!(
+        file_name(at(14, 5, 9), ExportFormat::OggOpus),
+        "recording-2026-10-16_14-05-09.ogg"
+    );
+    assert_eq!(
+        file_name(at(9, 0, 0), ExportFormat::Wav),
+        "recording-2026-10-16_09-00-00.wav"
+    );
+}
+
+#[test]
+fn recordings_never_overwrite_each_other() {
+    let dir = std::env::temp_dir().join(format!("runningbord-autosave-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let first = write_recording(&dir, at(14, 5, 9), ExportFormat::OggOpus, b"one").unwrap();
+    let second = write_recording(&dir, at(14, 5, 9), ExportFormat::OggOpus, b"two").unwrap();
+    assert_eq!(first, dir.join("recording-2026-10-16_14-05-09.ogg"));
+    assert_eq!(second, dir.join("recording-2026-10-16_14-05-09-2.ogg"));
+    assert_eq!(fs::read(&first).unwrap(), b"one");
+    assert_eq!(fs::read(&second).unwrap(), b"two");
+    let _ = fs::remove_dir_all(&dir);
+}
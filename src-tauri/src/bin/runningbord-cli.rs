@@ -0,0 +1,97 @@
+//! Headless command-line front end for system audio capture.
+//!
+//! ```text
+//! runningbord-cli record <out.ogg> [--duration <secs>] [--backend <spec>]
+//! runningbord-cli daemon [--port <port>] [--socket <path>] [--start <buffer_secs>] [--backend <spec>] [--recordings <dir>]
+//! runningbord-cli start [buffer_secs] | stop | status | shutdown [--socket <path> | --port <port>]
+//! runningbord-cli dump <name.ogg> [--socket <path> | --port <port>]
+//! ```
+//!
+//! `--socket` sets where the daemon serves the IPC protocol, which the app
+//! and the other commands use. `--port` also opens the token-protected TCP
+//! control port, and has the commands use it instead.
+//! `dump` has the daemon write `<name.ogg>` in its recordings folder,
+//! `--recordings` or the app's default one.
+//! `--backend` takes `platform`, `sine[:<hz>]` or `file:<path.wav>`; the
+//! mock backends need no audio device.
+
+use runningbord_lib::headless::{self, CaptureBackendConfig};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage:
+  runningbord-cli record <out.ogg> [--duration <secs>] [--backend <spec>]
+  runningbord-cli daemon [--port <port>] [--socket <path>] [--start <buffer_secs>] [--backend <spec>] [--recordings <dir>]
+  runningbord-cli start [buffer_secs] [--socket <path> | --port <port>]
+  runningbord-cli stop|status|shutdown [--socket <path> | --port <port>]
+  runningbord-cli dump <name.ogg> [--socket <path> | --port <port>]";
+
+/// Remove `--name <value>` from `args` and parse the value.
+fn take_flag<T: std::str::FromStr>(
+    args: &mut Vec<String>,
+    name: &str,
+) -> Result<Option<T>, String> {
+    let Some(i) = args.iter().position(|a| a == name) else {
+        return Ok(None);
+    };
+    if i + 1 >= args.len() {
+        return Err(format!("{} needs a value", name));
+    }
+    let value = args.remove(i + 1);
+    args.remove(i);
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+async fn run(mut args: Vec<String>) -> Result<(), String> {
+    if args.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let command = args.remove(0);
+    let port = take_flag::<u16>(&mut args, "--port")?;
+    let socket = take_flag::<String>(&mut args, "--socket")?;
+
+    match command.as_str() {
+        "record" => {
+            let duration = take_flag::<u64>(&mut args, "--duration")?;
+            let backend = take_flag::<CaptureBackendConfig>(&mut args, "--backend")?;
+            let path = args.first().ok_or(USAGE)?;
+            let bytes = headless::record_to_file(&PathBuf::from(path), duration, backend).await?;
+            println!("wrote {} bytes to {}", bytes, path);
+        }
+        "daemon" => {
+            let autostart = take_flag::<u32>(&mut args, "--start")?;
+            let backend = take_flag::<CaptureBackendConfig>(&mut args, "--backend")?;
+            let recordings = take_flag::<PathBuf>(&mut args, "--recordings")?;
+            headless::run_daemon(port, socket, autostart, backend, recordings).await?;
+        }
+        "start" | "stop" | "status" | "shutdown" | "dump" => {
+            let line = match args.first() {
+                Some(arg) => format!("{} {}", command, arg),
+                None if command == "dump" => return Err(USAGE.to_string()),
+                None => command.clone(),
+            };
+            let reply = match port {
+                Some(port) => headless::send_command(port, &line).await?,
+                None => headless::send_ipc_command(socket, &line).await?,
+            };
+            println!("{}", reply);
+        }
+        "help" | "--help" | "-h" => println!("{}", USAGE),
+        other => return Err(format!("Unknown command: {}\n{}", other, USAGE)),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
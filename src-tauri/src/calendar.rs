@@ -0,0 +1,939 @@
+//! Calendar integration: starts system audio capture shortly before
+//! scheduled meetings and labels the session with the event title, so
+//! exports (`TITLE` comment), status and the history entries the frontend
+//! creates from them can be tagged.
+//!
+//! On macOS: EventKit (after `calendar_request_access`).
+//! Elsewhere, or by choice: an ICS subscription URL. Recurring events are
+//! expanded: `RRULE` with `FREQ` daily to yearly, `INTERVAL`, `COUNT`,
+//! `UNTIL`, `BYDAY` and `BYMONTHDAY`, minus `EXDATE`s and instances moved
+//! by a `RECURRENCE-ID` override; other rule parts are ignored. `TZID` times
+//! are converted from their IANA zone. Zone names that aren't IANA ones,
+//! like Outlook's Windows names, are read as local time.
+//!
+//! Recordings are saved to the recordings folder when capture stops (see
+//! `autosave`). Emits `calendar-recording-started` and
+//! `calendar-recording-stopped`.
+
+use crate::autosave;
+use crate::focus_mode::suppresses_auto_capture;
+use crate::network;
+use crate::system_audio::{start_automatic_session, SystemAudioState};
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the scheduler checks whether to start or stop.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How often events are re-read from the calendar.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+/// How far ahead events are read.
+const LOOKAHEAD_HOURS: u32 = 24;
+/// Capture keeps running this long past an event's end, for overruns.
+const TRAILING_MS: u64 = 60_000;
+const ICS_TIMEOUT: Duration = Duration::from_secs(20);
+const DEFAULT_LEAD_SECONDS: u32 = 60;
+const DEFAULT_BUFFER_SECONDS: u32 = 300;
+/// History tag of scheduled recordings.
+const HISTORY_TAG: &str = "calendar";
+/// Most periods of a recurrence walked through, so a rule that never
+/// reaches the window ends. A daily rule gets about 270 years.
+const MAX_RECURRENCE_PERIODS: u32 = 100_000;
+
+/// A timed (not all-day) calendar event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarEvent {
+    pub id: String,
+    pub title: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CalendarSource {
+    /// The system calendar (EventKit); macOS only.
+    System,
+    /// An ICS feed; `webcal://` URLs are fetched over HTTPS.
+    Ics { url: String },
+}
+
+/// Scheduled auto-recording settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarConfig {
+    pub source: CalendarSource,
+    /// Start capture this long before each event.
+    #[serde(default = "default_lead_seconds")]
+    pub lead_seconds: u32,
+    /// Ring buffer length of scheduled recordings.
+    #[serde(default = "default_buffer_seconds")]
+    pub buffer_seconds: u32,
+}
+
+fn default_lead_seconds() -> u32 {
+    DEFAULT_LEAD_SECONDS
+}
+
+fn default_buffer_seconds() -> u32 {
+    DEFAULT_BUFFER_SECONDS
+}
+
+/// Payload of `calendar-recording-started` and `calendar-recording-stopped`.
+#[derive(Clone, Serialize)]
+pub struct CalendarRecordingEvent {
+    pub event: CalendarEvent,
+    /// Whether capture was started for the event, as opposed to already
+    /// running when it began.
+    pub started_capture: bool,
+}
+
+/// The event being recorded and the capture session started for it.
+struct ScheduledRecording {
+    event: CalendarEvent,
+    session: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct CalendarState {
+    config: Mutex<Option<CalendarConfig>>,
+    /// Bumped on every config change so a superseded scheduler exits.
+    generation: AtomicU64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Events overlapping the next `hours` from `source`, by start time.
+pub async fn upcoming_events(
+    app: &AppHandle,
+    source: &CalendarSource,
+    hours: u32,
+) -> Result<Vec<CalendarEvent>, String> {
+    let from_ms = now_millis();
+    let to_ms = from_ms + hours as u64 * 3_600_000;
+    let mut events = match source {
+        CalendarSource::System => {
+            tauri::async_runtime::spawn_blocking(move || platform::events(from_ms, to_ms))
+                .await
+                .map_err(|e| e.to_string())??
+        }
+        CalendarSource::Ics { url } => fetch_ics(app, url, from_ms, to_ms).await?,
+    };
+    events.retain(|e| e.end_ms > from_ms && e.start_ms < to_ms);
+    events.sort_by_key(|e| e.start_ms);
+    Ok(events)
+}
+
+async fn fetch_ics(
+    app: &AppHandle,
+    url: &str,
+    from_ms: u64,
+    to_ms: u64,
+) -> Result<Vec<CalendarEvent>, String> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let response = network::client(app, None)?
+        .get(&url)
+        .timeout(ICS_TIMEOUT)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch calendar: {}", e))?;
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read calendar: {}", e))?;
+    Ok(parse_ics(&text, from_ms, to_ms))
+}
+
+/// Timed events of an iCalendar document overlapping `from_ms..to_ms`,
+/// with recurring events expanded into their instances. All-day and
+/// cancelled events are skipped.
+pub fn parse_ics(text: &str, from_ms: u64, to_ms: u64) -> Vec<CalendarEvent> {
+    // Unfold: a line starting with a space or tab continues the previous one.
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut parsed = Vec::new();
+    let mut current: Option<IcsEvent> = None;
+    // Components nested in the event (VALARM) have their own properties.
+    let mut nested = 0usize;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match (name.to_ascii_uppercase().as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                current = Some(IcsEvent::default());
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                parsed.extend(current.take());
+            }
+            (_, Some(_)) if nested > 0 => {}
+            ("UID", Some(event)) => event.uid = value.to_string(),
+            ("SUMMARY", Some(event)) => event.summary = unescape_text(value),
+            ("STATUS", Some(event)) => {
+                event.cancelled = value.eq_ignore_ascii_case("CANCELLED");
+            }
+            ("DTSTART", Some(event)) => event.start = parse_ics_time(params, value),
+            ("DTEND", Some(event)) => {
+                event.end = parse_ics_time(params, value).and_then(|t| t.ms());
+            }
+            ("DURATION", Some(event)) => event.duration_ms = parse_ics_duration(value),
+            ("RRULE", Some(event)) => event.rule = parse_rrule(value),
+            ("EXDATE", Some(event)) => event.exdates.extend(
+                value
+                    .split(',')
+                    .filter_map(|v| parse_ics_time(params, v).and_then(|t| t.ms())),
+            ),
+            ("RECURRENCE-ID", Some(event)) => {
+                event.recurrence_id = parse_ics_time(params, value).and_then(|t| t.ms());
+            }
+            _ => {}
+        }
+    }
+
+    // Instances moved or cancelled by an override of their own.
+    let overridden: HashSet<(String, u64)> = parsed
+        .iter()
+        .filter_map(|e| Some((e.uid.clone(), e.recurrence_id?)))
+        .collect();
+    let mut events: Vec<CalendarEvent> = parsed
+        .into_iter()
+        .flat_map(|e| e.finish(&overridden, from_ms, to_ms))
+        .collect();
+    events.sort_by_key(|e| e.start_ms);
+    events
+}
+
+#[derive(Default)]
+struct IcsEvent {
+    uid: String,
+    summary: String,
+    cancelled: bool,
+    start: Option<IcsTime>,
+    end: Option<u64>,
+    duration_ms: Option<u64>,
+    rule: Option<RecurrenceRule>,
+    exdates: Vec<u64>,
+    /// Start of the recurring instance this event replaces.
+    recurrence_id: Option<u64>,
+}
+
+impl IcsEvent {
+    /// The event's instances overlapping `from_ms..to_ms`.
+    fn finish(
+        self,
+        overridden: &HashSet<(String, u64)>,
+        from_ms: u64,
+        to_ms: u64,
+    ) -> Vec<CalendarEvent> {
+        let Some(start) = self.start else {
+            return Vec::new();
+        };
+        let Some(start_ms) = start.ms() else {
+            return Vec::new();
+        };
+        let end_ms = self
+            .end
+            .or_else(|| self.duration_ms.map(|d| start_ms + d))
+            .unwrap_or(start_ms);
+        if self.cancelled || end_ms < start_ms {
+            return Vec::new();
+        }
+        let length = end_ms - start_ms;
+        let starts = match &self.rule {
+            // An override replaces one instance and doesn't recur itself.
+            Some(rule) if self.recurrence_id.is_none() => {
+                occurrences(start, rule, from_ms.saturating_sub(length), to_ms)
+            }
+            _ => vec![start_ms],
+        };
+        let id = if self.uid.is_empty() {
+            format!("{}@{}", self.summary, start_ms)
+        } else {
+            self.uid.clone()
+        };
+        let is_series = self.rule.is_some() && self.recurrence_id.is_none();
+        starts
+            .into_iter()
+            .filter(|&at| at + length > from_ms && at < to_ms)
+            .filter(|at| !self.exdates.contains(at))
+            .filter(|&at| !(is_series && overridden.contains(&(self.uid.clone(), at))))
+            .map(|at| CalendarEvent {
+                id: id.clone(),
+                title: self.summary.clone(),
+                start_ms: at,
+                end_ms: at + length,
+            })
+            .collect()
+    }
+}
+
+/// Zone of an iCalendar date-time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IcsZone {
+    Utc,
+    /// A `TZID` naming an IANA zone.
+    Named(Tz),
+    /// Floating, or a `TZID` that isn't an IANA name.
+    Local,
+}
+
+/// An iCalendar date-time as written, so recurrences step in its zone's
+/// wall-clock time and keep their hour across DST changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct IcsTime {
+    naive: NaiveDateTime,
+    zone: IcsZone,
+}
+
+impl IcsTime {
+    fn ms(&self) -> Option<u64> {
+        zone_ms(self.zone, self.naive)
+    }
+}
+
+/// `naive` in `zone` as ms since the epoch; `None` for wall-clock times a
+/// DST change skips.
+fn zone_ms(zone: IcsZone, naive: NaiveDateTime) -> Option<u64> {
+    let ms = match zone {
+        IcsZone::Utc => naive.and_utc().timestamp_millis(),
+        IcsZone::Named(tz) => tz
+            .from_local_datetime(&naive)
+            .earliest()?
+            .timestamp_millis(),
+        IcsZone::Local => Local
+            .from_local_datetime(&naive)
+            .earliest()?
+            .timestamp_millis(),
+    };
+    u64::try_from(ms).ok()
+}
+
+/// Value of the property parameter `name`, unquoted.
+fn ics_param<'a>(params: &'a str, name: &str) -> Option<&'a str> {
+    params.split(';').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.eq_ignore_ascii_case(name)
+            .then(|| value.trim_matches('"'))
+    })
+}
+
+/// `20250102T150000Z` (UTC), `TZID=Europe/Paris:20250102T150000` (that
+/// zone) or `20250102T150000` (floating, read as local time). Dates without
+/// a time (`VALUE=DATE`) give `None`.
+fn parse_ics_time(params: &str, value: &str) -> Option<IcsTime> {
+    let value = value.trim();
+    if ics_param(params, "VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE"))
+        && !value.contains('T')
+    {
+        return None;
+    }
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(v) => (v, true),
+        None => (value, false),
+    };
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let zone = if utc {
+        IcsZone::Utc
+    } else {
+        ics_param(params, "TZID")
+            .and_then(|name| name.parse::<Tz>().ok())
+            .map_or(IcsZone::Local, IcsZone::Named)
+    };
+    Some(IcsTime { naive, zone })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The supported part of an `RRULE`.
+#[derive(Debug, Clone, PartialEq)]
+struct RecurrenceRule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    /// `UNTIL`, as written: UTC, or wall-clock time in the start's zone.
+    until: Option<(NaiveDateTime, bool)>,
+    /// `BYDAY` weekdays, with which one in the month for monthly rules
+    /// (`-1` is the last); `None` for all of them.
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// `BYMONTHDAY`, negative counting from the end of the month.
+    by_month_day: Vec<i32>,
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    Some(match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;UNTIL=20250301T000000Z`. `None`
+/// without a supported `FREQ`.
+fn parse_rrule(value: &str) -> Option<RecurrenceRule> {
+    let mut rule = RecurrenceRule {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+    };
+    let mut frequency = None;
+    for part in value.split(';') {
+        let Some((key, value)) = part.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_ascii_uppercase();
+        match key.trim().to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = match value.as_str() {
+                    "DAILY" => Some(Frequency::Daily),
+                    "WEEKLY" => Some(Frequency::Weekly),
+                    "MONTHLY" => Some(Frequency::Monthly),
+                    "YEARLY" => Some(Frequency::Yearly),
+                    _ => return None,
+                }
+            }
+            "INTERVAL" => rule.interval = value.parse().ok().filter(|&n| n > 0)?,
+            "COUNT" => rule.count = Some(value.parse().ok()?),
+            "UNTIL" => {
+                let (date, utc) = match value.strip_suffix('Z') {
+                    Some(v) => (v, true),
+                    None => (value.as_str(), false),
+                };
+                rule.until = Some(match NaiveDateTime::parse_from_str(date, "%Y%m%dT%H%M%S") {
+                    Ok(at) => (at, utc),
+                    // A date: the whole of that day.
+                    Err(_) => (
+                        NaiveDate::parse_from_str(date, "%Y%m%d")
+                            .ok()?
+                            .and_hms_opt(23, 59, 59)?,
+                        false,
+                    ),
+                });
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    let (nth, code) = day.split_at(day.len().saturating_sub(2));
+                    let nth = match nth {
+                        "" => None,
+                        n => Some(n.trim_start_matches('+').parse().ok()?),
+                    };
+                    rule.by_day.push((nth, parse_weekday(code)?));
+                }
+            }
+            "BYMONTHDAY" => {
+                for day in value.split(',') {
+                    rule.by_month_day.push(day.parse().ok()?);
+                }
+            }
+            _ => {}
+        }
+    }
+    rule.frequency = frequency?;
+    Some(rule)
+}
+
+/// Day `day` of the month starting at `first`, negative from the end.
+fn month_day(first: NaiveDate, day: i32) -> Option<NaiveDate> {
+    let next = first.checked_add_months(Months::new(1))?;
+    let date = if day > 0 {
+        first.checked_add_days(Days::new(day as u64 - 1))?
+    } else {
+        next.checked_sub_days(Days::new(day.unsigned_abs() as u64))?
+    };
+    (date >= first && date < next).then_some(date)
+}
+
+/// The `weekday`s of the month starting at `first`; only the `nth` one
+/// (negative from the end) if given.
+fn month_weekdays(first: NaiveDate, weekday: Weekday, nth: Option<i32>) -> Vec<NaiveDate> {
+    let offset = (7 + weekday.num_days_from_monday() - first.weekday().num_days_from_monday()) % 7;
+    let all: Vec<NaiveDate> = (0..5)
+        .filter_map(|week| month_day(first, (offset + 7 * week + 1) as i32))
+        .collect();
+    match nth {
+        None => all,
+        Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => all
+            .len()
+            .checked_sub(n.unsigned_abs() as usize)
+            .and_then(|i| all.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Candidate dates of the `period`th period of `rule` from `start`, in
+/// order. `None` once the calendar runs out.
+fn period_dates(start: NaiveDate, rule: &RecurrenceRule, period: u32) -> Option<Vec<NaiveDate>> {
+    let step = period.checked_mul(rule.interval)?;
+    let mut dates = match rule.frequency {
+        Frequency::Daily => {
+            let date = start.checked_add_days(Days::new(step.into()))?;
+            let on_day =
+                rule.by_day.is_empty() || rule.by_day.iter().any(|&(_, day)| day == date.weekday());
+            if on_day {
+                vec![date]
+            } else {
+                Vec::new()
+            }
+        }
+        Frequency::Weekly => {
+            let monday = start
+                .checked_sub_days(Days::new(start.weekday().num_days_from_monday().into()))?
+                .checked_add_days(Days::new(u64::from(step) * 7))?;
+            let days: Vec<Weekday> = if rule.by_day.is_empty() {
+                vec![start.weekday()]
+            } else {
+                rule.by_day.iter().map(|&(_, day)| day).collect()
+            };
+            days.into_iter()
+                .filter_map(|day| {
+                    monday.checked_add_days(Days::new(day.num_days_from_monday().into()))
+                })
+                .collect()
+        }
+        Frequency::Monthly => {
+            let first = start.with_day(1)?.checked_add_months(Months::new(step))?;
+            if !rule.by_day.is_empty() {
+                rule.by_day
+                    .iter()
+                    .flat_map(|&(nth, day)| month_weekdays(first, day, nth))
+                    .collect()
+            } else if !rule.by_month_day.is_empty() {
+                rule.by_month_day
+                    .iter()
+                    .filter_map(|&day| month_day(first, day))
+                    .collect()
+            } else {
+                month_day(first, start.day() as i32).into_iter().collect()
+            }
+        }
+        Frequency::Yearly => {
+            let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+            NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                .into_iter()
+                .collect()
+        }
+    };
+    dates.sort();
+    dates.dedup();
+    Some(dates)
+}
+
+/// Starts of the instances of `rule` beginning at `start`, in ms, that
+/// start within `from_ms..to_ms`.
+fn occurrences(start: IcsTime, rule: &RecurrenceRule, from_ms: u64, to_ms: u64) -> Vec<u64> {
+    let until_ms = rule
+        .until
+        .and_then(|(at, utc)| zone_ms(if utc { IcsZone::Utc } else { start.zone }, at));
+    let mut starts = Vec::new();
+    let mut count = 0u32;
+    for period in 0..MAX_RECURRENCE_PERIODS {
+        let Some(dates) = period_dates(start.naive.date(), rule, period) else {
+            break;
+        };
+        for date in dates {
+            let naive = date.and_time(start.naive.time());
+            if naive < start.naive {
+                continue;
+            }
+            if rule.count.is_some_and(|max| count >= max) {
+                return starts;
+            }
+            count += 1;
+            let Some(at) = zone_ms(start.zone, naive) else {
+                continue;
+            };
+            if at >= to_ms || until_ms.is_some_and(|until| at > until) {
+                return starts;
+            }
+            if at >= from_ms {
+                starts.push(at);
+            }
+        }
+    }
+    starts
+}
+
+/// `PT1H30M`, `P1D`, `PT45M`, ... in ms.
+fn parse_ics_duration(value: &str) -> Option<u64> {
+    let value = value.strip_prefix('+').unwrap_or(value).strip_prefix('P')?;
+    let mut total_ms = 0u64;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            unit => {
+                let n: u64 = number.parse().ok()?;
+                number.clear();
+                total_ms += n * match (unit, in_time) {
+                    ('W', false) => 604_800_000,
+                    ('D', false) => 86_400_000,
+                    ('H', true) => 3_600_000,
+                    ('M', true) => 60_000,
+                    ('S', true) => 1_000,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(total_ms)
+}
+
+/// Undo iCalendar TEXT escaping (`\,`, `\;`, `\n`, `\\`).
+fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Start capture for `event` and label the session with its title.
+async fn start_recording(
+    app: &AppHandle,
+    config: &CalendarConfig,
+    event: CalendarEvent,
+) -> Result<ScheduledRecording, String> {
+    let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+    let session = start_automatic_session(app.clone(), &audio, config.buffer_seconds).await?;
+    audio.set_session_label(Some(event.title.clone()));
+    tracing::info!("Recording calendar event \"{}\"", event.title);
+    let _ = app.emit(
+        "calendar-recording-started",
+        CalendarRecordingEvent {
+            event: event.clone(),
+            started_capture: session.is_some(),
+        },
+    );
+    Ok(ScheduledRecording { event, session })
+}
+
+/// Save and stop the capture started for `recording` and drop its label.
+async fn stop_recording(app: &AppHandle, recording: ScheduledRecording) {
+    let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+    if let Some(session) = recording.session {
+        autosave::save_and_stop_session(app, &audio, session, HISTORY_TAG).await;
+    }
+    if audio.session_label().as_deref() == Some(recording.event.title.as_str()) {
+        audio.set_session_label(None);
+    }
+    let _ = app.emit(
+        "calendar-recording-stopped",
+        CalendarRecordingEvent {
+            event: recording.event,
+            started_capture: recording.session.is_some(),
+        },
+    );
+}
+
+/// Re-read the calendar periodically and start and stop capture around
+/// events until the config changes.
+fn spawn_scheduler(app: AppHandle, config: CalendarConfig, generation: u64) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<CalendarState>();
+        let mut events: Vec<CalendarEvent> = Vec::new();
+        let mut refreshed: Option<Instant> = None;
+        // Events already recorded (or skipped), so stopping capture by hand
+        // mid-event doesn't restart it. Keyed by start too, since every
+        // instance of a recurring event shares its id.
+        let mut handled: HashSet<(String, u64)> = HashSet::new();
+        let mut recording: Option<ScheduledRecording> = None;
+        let lead_ms = config.lead_seconds as u64 * 1000;
+
+        while state.generation.load(Ordering::SeqCst) == generation {
+            if refreshed.map_or(true, |at| at.elapsed() >= REFRESH_INTERVAL) {
+                match upcoming_events(&app, &config.source, LOOKAHEAD_HOURS).await {
+                    Ok(fresh) => events = fresh,
+                    Err(e) => tracing::warn!("Failed to read calendar: {}", e),
+                }
+                refreshed = Some(Instant::now());
+            }
+
+            let now = now_millis();
+            if let Some(current) = recording.take() {
+                if now < current.event.end_ms + TRAILING_MS {
+                    recording = Some(current);
+                } else {
+                    stop_recording(&app, current).await;
+                }
+            }
+            if recording.is_none() {
+                let due = events.iter().find(|e| {
+                    e.start_ms.saturating_sub(lead_ms) <= now
+                        && now < e.end_ms
+                        && !handled.contains(&(e.id.clone(), e.start_ms))
+                });
+                // Left unhandled while Focus is on, so capture can still
+                // start if it ends before the event does.
+                if let Some(event) = due.cloned().filter(|_| !suppresses_auto_capture(&app)) {
+                    handled.insert((event.id.clone(), event.start_ms));
+                    match start_recording(&app, &config, event).await {
+                        Ok(started) => recording = Some(started),
+                        Err(e) => tracing::warn!("Failed to start scheduled capture: {}", e),
+                    }
+                }
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+        if let Some(current) = recording {
+            stop_recording(&app, current).await;
+        }
+    });
+}
+
+/// Turn scheduled recording on with `config`, or off with `None`. A
+/// recording in progress when it is turned off is stopped.
+#[tauri::command]
+pub fn calendar_set_auto_record(
+    app: AppHandle,
+    config: Option<CalendarConfig>,
+) -> Result<(), String> {
+    if let Some(config) = &config {
+        if config.buffer_seconds == 0 {
+            return Err("buffer_seconds must be positive".to_string());
+        }
+        if let CalendarSource::Ics { url } = &config.source {
+            if !["https://", "http://", "webcal://"]
+                .iter()
+                .any(|scheme| url.starts_with(scheme))
+            {
+                return Err(format!("Unsupported calendar URL: {}", url));
+            }
+        }
+    }
+    let state = app.state::<CalendarState>();
+    *state.config.lock().map_err(|e| e.to_string())? = config.clone();
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Some(config) = config {
+        spawn_scheduler(app.clone(), config, generation);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn calendar_get_auto_record(app: AppHandle) -> Result<Option<CalendarConfig>, String> {
+    let state = app.state::<CalendarState>();
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(config.clone())
+}
+
+/// Events in the next `hours` (default 24) from `source`, or from the
+/// configured source if `None`.
+#[tauri::command]
+pub async fn calendar_upcoming_events(
+    app: AppHandle,
+    source: Option<CalendarSource>,
+    hours: Option<u32>,
+) -> Result<Vec<CalendarEvent>, String> {
+    let source = match source {
+        Some(source) => source,
+        None => app
+            .state::<CalendarState>()
+            .config
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_ref()
+            .map(|c| c.source.clone())
+            .ok_or_else(|| "No calendar configured".to_string())?,
+    };
+    upcoming_events(&app, &source, hours.unwrap_or(LOOKAHEAD_HOURS)).await
+}
+
+/// Ask for access to the system calendar. Returns whether it was granted.
+#[tauri::command]
+pub async fn calendar_request_access() -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(platform::request_access)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::CalendarEvent;
+    use block2::RcBlock;
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject, Bool};
+    use objc2::sel;
+    use objc2_foundation::NSString;
+    use std::ffi::CStr;
+    use std::ptr;
+    use std::sync::mpsc;
+
+    #[link(name = "EventKit", kind = "framework")]
+    extern "C" {}
+
+    /// EKEntityTypeEvent.
+    const ENTITY_TYPE_EVENT: isize = 0;
+    /// EKAuthorizationStatusAuthorized / EKAuthorizationStatusFullAccess.
+    const AUTHORIZATION_FULL_ACCESS: isize = 3;
+
+    fn class(name: &[u8]) -> Result<&'static AnyClass, String> {
+        CStr::from_bytes_with_nul(name)
+            .ok()
+            .and_then(AnyClass::get)
+            .ok_or_else(|| "EventKit is not available".to_string())
+    }
+
+    unsafe fn event_store() -> Result<Retained<AnyObject>, String> {
+        let store: Option<Retained<AnyObject>> = msg_send![class(b"EKEventStore\0")?, new];
+        store.ok_or_else(|| "Failed to open the calendar store".to_string())
+    }
+
+    unsafe fn date(ms: u64) -> Result<Retained<AnyObject>, String> {
+        let date: Option<Retained<AnyObject>> = msg_send![
+            class(b"NSDate\0")?,
+            dateWithTimeIntervalSince1970: ms as f64 / 1000.0
+        ];
+        date.ok_or_else(|| "Failed to create date".to_string())
+    }
+
+    unsafe fn millis(date: Option<Retained<AnyObject>>) -> Option<u64> {
+        let seconds: f64 = msg_send![&*date?, timeIntervalSince1970];
+        (seconds >= 0.0).then(|| (seconds * 1000.0) as u64)
+    }
+
+    pub fn request_access() -> Result<bool, String> {
+        unsafe {
+            let store = event_store()?;
+            let (tx, rx) = mpsc::channel();
+            let block = RcBlock::new(move |granted: Bool, _error: *mut AnyObject| {
+                let _ = tx.send(granted.as_bool());
+            });
+            let full_access: bool = msg_send![
+                &*store,
+                respondsToSelector: sel!(requestFullAccessToEventsWithCompletion:)
+            ];
+            if full_access {
+                let _: () = msg_send![&*store, requestFullAccessToEventsWithCompletion: &*block];
+            } else {
+                let _: () = msg_send![
+                    &*store,
+                    requestAccessToEntityType: ENTITY_TYPE_EVENT,
+                    completion: &*block
+                ];
+            }
+            rx.recv()
+                .map_err(|_| "Calendar access request was dropped".to_string())
+        }
+    }
+
+    pub fn events(from_ms: u64, to_ms: u64) -> Result<Vec<CalendarEvent>, String> {
+        unsafe {
+            let status: isize = msg_send![
+                class(b"EKEventStore\0")?,
+                authorizationStatusForEntityType: ENTITY_TYPE_EVENT
+            ];
+            if status != AUTHORIZATION_FULL_ACCESS {
+                return Err("Calendar access has not been granted".to_string());
+            }
+            let store = event_store()?;
+            let (from, to) = (date(from_ms)?, date(to_ms)?);
+            let predicate: Option<Retained<AnyObject>> = msg_send![
+                &*store,
+                predicateForEventsWithStartDate: &*from,
+                endDate: &*to,
+                calendars: ptr::null::<AnyObject>()
+            ];
+            let predicate = predicate.ok_or_else(|| "Failed to query calendar".to_string())?;
+            let found: Option<Retained<AnyObject>> =
+                msg_send![&*store, eventsMatchingPredicate: &*predicate];
+            let Some(found) = found else {
+                return Ok(Vec::new());
+            };
+            let count: usize = msg_send![&*found, count];
+            let mut events = Vec::with_capacity(count);
+            for i in 0..count {
+                let event: Option<Retained<AnyObject>> = msg_send![&*found, objectAtIndex: i];
+                let Some(event) = event else {
+                    continue;
+                };
+                let all_day: bool = msg_send![&*event, isAllDay];
+                if all_day {
+                    continue;
+                }
+                let (Some(start_ms), Some(end_ms)) = (
+                    millis(msg_send![&*event, startDate]),
+                    millis(msg_send![&*event, endDate]),
+                ) else {
+                    continue;
+                };
+                let id: Option<Retained<NSString>> = msg_send![&*event, eventIdentifier];
+                let title: Option<Retained<NSString>> = msg_send![&*event, title];
+                events.push(CalendarEvent {
+                    id: id.map(|s| s.to_string()).unwrap_or_default(),
+                    title: title.map(|s| s.to_string()).unwrap_or_default(),
+                    start_ms,
+                    end_ms,
+                });
+            }
+            Ok(events)
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::CalendarEvent;
+
+    pub fn request_access() -> Result<bool, String> {
+        Err("The system calendar is only supported on macOS; use an ICS URL".to_string())
+    }
+
+    pub fn events(_from_ms: u64, _to_ms: u64) -> Result<Vec<CalendarEvent>, String> {
+        Err("The system calendar is only supported on macOS; use an ICS URL".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,269 @@
+use super::*;
+
+const FEED: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:standup-1\r
+SUMMARY:Team standup\\, daily\r
+DTSTART:20250102T150000Z\r
+DTEND:20250102T151500Z\r
+BEGIN:VALARM\r
+ACTION:DISPLAY\r
+SUMMARY:Reminder\r
+DURATION:PT5M\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:review-2\r
+SUMMARY:Design review folded\r
+  onto two lines\r
+DTSTART:20250102T170000Z\r
+DURATION:PT1H30M\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:offsite\r
+SUMMARY:Offsite\r
+DTSTART;VALUE=DATE:20250103\r
+DTEND;VALUE=DATE:20250104\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:cancelled\r
+SUMMARY:Cancelled sync\r
+STATUS:CANCELLED\r
+DTSTART:20250102T180000Z\r
+DTEND:20250102T183000Z\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+/// 2025-01-02T15:00:00Z.
+const JAN_2_15H_MS: u64 = 1_735_830_000_000;
+
+/// A week from 2025-01-01.
+const WEEK: (u64, u64) = (1_735_689_600_000, 1_735_689_600_000 + 7 * DAY_MS);
+const DAY_MS: u64 = 86_400_000;
+
+fn ms(zone: &str, text: &str) -> u64 {
+    let tz: Tz = zone.parse().unwrap();
+    tz.from_local_datetime(&NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap())
+        .unwrap()
+        .timestamp_millis() as u64
+}
+
+fn starts(feed: &str, from_ms: u64, to_ms: u64) -> Vec<u64> {
+    parse_ics(feed, from_ms, to_ms)
+        .iter()
+        .map(|e| e.start_ms)
+        .collect()
+}
+
+/// A calendar of events with these properties.
+fn calendar(events: &[&str]) -> String {
+    let events: String = events
+        .iter()
+        .map(|props| format!("BEGIN:VEVENT\r\n{}END:VEVENT\r\n", props))
+        .collect();
+    format!("BEGIN:VCALENDAR\r\n{}END:VCALENDAR\r\n", events)
+}
+
+/// A calendar of one event, `series`, with these properties.
+fn series(props: &str) -> String {
+    calendar(&[&format!("UID:series\r\nSUMMARY:Sync\r\n{}", props)])
+}
+
+#[test]
+fn ics_timed_events_are_parsed() {
+    let events = parse_ics(FEED, WEEK.0, WEEK.1);
+    assert_eq!(
+        events,
+        vec![
+            CalendarEvent {
+                id: "standup-1".to_string(),
+                title: "Team standup, daily".to_string(),
+                start_ms: JAN_2_15H_MS,
+                end_ms: JAN_2_15H_MS + 15 * 60_000,
+            },
+            CalendarEvent {
+                id: "review-2".to_string(),
+                title: "Design review folded onto two lines".to_string(),
+                start_ms: JAN_2_15H_MS + 2 * 3_600_000,
+                end_ms: JAN_2_15H_MS + 2 * 3_600_000 + 90 * 60_000,
+            },
+        ]
+    );
+}
+
+#[test]
+fn ics_events_outside_the_window_are_left_out() {
+    assert!(parse_ics(FEED, WEEK.1, WEEK.1 + DAY_MS).is_empty());
+}
+
+#[test]
+fn ics_tzid_times_are_converted_from_their_zone() {
+    let at = |params: &str, value: &str| parse_ics_time(params, value).and_then(|t| t.ms());
+    assert_eq!(
+        at("TZID=America/New_York", "20250102T093000"),
+        Some(ms("America/New_York", "2025-01-02 09:30"))
+    );
+    assert_eq!(
+        at("TZID=\"Europe/Paris\"", "20250102T093000"),
+        Some(ms("Europe/Paris", "2025-01-02 09:30"))
+    );
+    assert_eq!(
+        at("", "20250102T093000Z"),
+        Some(ms("UTC", "2025-01-02 09:30"))
+    );
+    assert_eq!(parse_ics_time("VALUE=DATE", "20250102"), None);
+}
+
+#[test]
+fn ics_unknown_zones_and_floating_times_are_local() {
+    let expected = Local
+        .with_ymd_and_hms(2025, 1, 2, 9, 30, 0)
+        .earliest()
+        .unwrap()
+        .timestamp_millis() as u64;
+    for params in ["", "TZID=Pacific Standard Time"] {
+        let time = parse_ics_time(params, "20250102T093000").unwrap();
+        assert_eq!(time.zone, IcsZone::Local, "{}", params);
+        assert_eq!(time.ms(), Some(expected));
+    }
+}
+
+#[test]
+fn weekly_series_skip_exdates_and_moved_instances() {
+    let feed = calendar(&[
+        "UID:series\r\nSUMMARY:Sync\r\n\
+         DTSTART;TZID=Europe/Berlin:20250106T090000\r\n\
+         DTEND;TZID=Europe/Berlin:20250106T093000\r\n\
+         RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR\r\n\
+         EXDATE;TZID=Europe/Berlin:20250110T090000\r\n",
+        "UID:series\r\nSUMMARY:Sync (moved)\r\n\
+         RECURRENCE-ID;TZID=Europe/Berlin:20250108T090000\r\n\
+         DTSTART;TZID=Europe/Berlin:20250108T140000\r\n\
+         DTEND;TZID=Europe/Berlin:20250108T143000\r\n",
+    ]);
+    let from = ms("Europe/Berlin", "2025-01-06 00:00");
+    let events = parse_ics(&feed, from, from + 14 * DAY_MS);
+    let got: Vec<(u64, &str)> = events
+        .iter()
+        .map(|e| (e.start_ms, e.title.as_str()))
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            (ms("Europe/Berlin", "2025-01-06 09:00"), "Sync"),
+            (ms("Europe/Berlin", "2025-01-08 14:00"), "Sync (moved)"),
+            (ms("Europe/Berlin", "2025-01-13 09:00"), "Sync"),
+            (ms("Europe/Berlin", "2025-01-15 09:00"), "Sync"),
+            (ms("Europe/Berlin", "2025-01-17 09:00"), "Sync"),
+        ]
+    );
+    assert!(events.iter().all(|e| e.id == "series"));
+    assert!(events.iter().all(|e| e.end_ms - e.start_ms == 30 * 60_000));
+}
+
+#[test]
+fn recurrences_keep_their_wall_clock_time_across_dst() {
+    let feed = series(
+        "DTSTART;TZID=America/New_York:20250307T093000\r\nDURATION:PT30M\r\n\
+         RRULE:FREQ=DAILY;COUNT=3\r\n",
+    );
+    let from = ms("America/New_York", "2025-03-01 00:00");
+    assert_eq!(
+        starts(&feed, from, from + 30 * DAY_MS),
+        vec![
+            ms("America/New_York", "2025-03-07 09:30"),
+            ms("America/New_York", "2025-03-08 09:30"),
+            ms("America/New_York", "2025-03-09 09:30"),
+        ]
+    );
+}
+
+#[test]
+fn count_until_and_interval_bound_a_series() {
+    let from = ms("UTC", "2025-01-01 00:00");
+    let until = series(
+        "DTSTART:20250101T100000Z\r\nDURATION:PT1H\r\n\
+         RRULE:FREQ=WEEKLY;INTERVAL=2;UNTIL=20250129T100000Z\r\n",
+    );
+    assert_eq!(
+        starts(&until, from, from + 90 * DAY_MS),
+        vec![
+            ms("UTC", "2025-01-01 10:00"),
+            ms("UTC", "2025-01-15 10:00"),
+            ms("UTC", "2025-01-29 10:00"),
+        ]
+    );
+    // COUNT counts from the first instance, even before the window.
+    let count = series("DTSTART:20250101T100000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=DAILY;COUNT=5\r\n");
+    assert_eq!(
+        starts(&count, from + 3 * DAY_MS, from + 90 * DAY_MS),
+        vec![ms("UTC", "2025-01-04 10:00"), ms("UTC", "2025-01-05 10:00")]
+    );
+}
+
+#[test]
+fn endless_series_are_expanded_in_the_window_only() {
+    let feed = series("DTSTART:20100104T150000Z\r\nDURATION:PT15M\r\nRRULE:FREQ=DAILY\r\n");
+    assert_eq!(
+        starts(&feed, WEEK.0, WEEK.0 + 2 * DAY_MS),
+        vec![ms("UTC", "2025-01-01 15:00"), ms("UTC", "2025-01-02 15:00")]
+    );
+}
+
+#[test]
+fn monthly_and_yearly_rules() {
+    let from = ms("UTC", "2025-01-01 00:00");
+    let year = from + 365 * DAY_MS;
+    let second_tuesday = series(
+        "DTSTART:20250114T160000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=MONTHLY;BYDAY=2TU;COUNT=3\r\n",
+    );
+    assert_eq!(
+        starts(&second_tuesday, from, year),
+        vec![
+            ms("UTC", "2025-01-14 16:00"),
+            ms("UTC", "2025-02-11 16:00"),
+            ms("UTC", "2025-03-11 16:00"),
+        ]
+    );
+    let last_friday = series(
+        "DTSTART:20250131T160000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=2\r\n",
+    );
+    assert_eq!(
+        starts(&last_friday, from, year),
+        vec![ms("UTC", "2025-01-31 16:00"), ms("UTC", "2025-02-28 16:00")]
+    );
+    // Months without a 31st are skipped.
+    let thirty_first =
+        series("DTSTART:20250131T160000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=MONTHLY;COUNT=3\r\n");
+    assert_eq!(
+        starts(&thirty_first, from, year),
+        vec![
+            ms("UTC", "2025-01-31 16:00"),
+            ms("UTC", "2025-03-31 16:00"),
+            ms("UTC", "2025-05-31 16:00"),
+        ]
+    );
+    let yearly = series("DTSTART:20240315T160000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=YEARLY\r\n");
+    assert_eq!(
+        starts(&yearly, from, year),
+        vec![ms("UTC", "2025-03-15 16:00")]
+    );
+}
+
+#[test]
+fn unsupported_rules_leave_the_first_instance() {
+    assert_eq!(parse_rrule("FREQ=HOURLY"), None);
+    assert_eq!(parse_rrule("INTERVAL=2"), None);
+    let feed = series("DTSTART:20250102T150000Z\r\nDURATION:PT15M\r\nRRULE:FREQ=SECONDLY\r\n");
+    assert_eq!(starts(&feed, WEEK.0, WEEK.1), vec![JAN_2_15H_MS]);
+}
+
+#[test]
+fn ics_durations() {
+    assert_eq!(parse_ics_duration("PT45M"), Some(45 * 60_000));
+    assert_eq!(parse_ics_duration("P1DT2H"), Some(26 * 3_600_000));
+    assert_eq!(parse_ics_duration("P1W"), Some(7 * 86_400_000));
+    assert_eq!(parse_ics_duration("PT5X"), None);
+}
@@ -0,0 +1,426 @@
+//! Live captions of the ongoing call: new audio is read from the capture
+//! buffer as it arrives (like `stream_server`), cut into utterances at
+//! pauses, transcribed one by one and emitted as `captions://segment` for
+//! the overlay to render.
+//!
+//! With `speaker_labels` on, speakers are told apart locally: each
+//! utterance's voice print, the mean of its MFCCs (see `wake_word`), joins
+//! the closest speaker seen this session or starts a new one. That
+//! separates voices, it doesn't know who they are, so labels are
+//! "Speaker 1", "Speaker 2"... and start over with each capture session.
+//! This is experimental: the prints aren't normalized for the channel (no
+//! cepstral mean normalization) and the threshold was only checked against
+//! synthetic prints, so one voice can get two labels or two voices one.
+//!
+//! Off by default: every utterance is a transcription request. While the
+//! live transcript is on, captions are added to it (`live_transcript::record`)
+//! and it stops transcribing the audio itself, so nothing is sent twice.
+
+use crate::api;
+use crate::live_transcript::{self, TranscriptSegment};
+use crate::offline_queue;
+use crate::system_audio::{linear_to_dbfs, SystemAudioState, OUTPUT_SAMPLE_RATE};
+use crate::system_audio_encoder::encode_wav;
+use crate::wake_word::{Frame, Mfcc, CEPSTRA};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+use zeroize::Zeroize;
+
+/// How often new audio is read from the buffer.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Level analysis frames: 10 ms.
+const FRAME_LEN: usize = OUTPUT_SAMPLE_RATE as usize / 100;
+/// Frames at least this loud count as speech.
+const SPEECH_DBFS: f32 = -45.0;
+/// Utterances with less speech than this (200 ms) are clicks and coughs.
+const MIN_SPEECH_FRAMES: usize = 20;
+/// Cosine distance between voice prints under which two utterances are
+/// taken to be the same speaker.
+const SAME_SPEAKER_DISTANCE: f32 = 0.15;
+/// Past this many speakers, utterances join the closest one.
+const MAX_SPEAKERS: usize = 8;
+/// Utterances waiting for transcription. When transcription falls behind,
+/// new ones are dropped so the captions stay live.
+const QUEUE_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CaptionsConfig {
+    pub enabled: bool,
+    /// Silence that ends an utterance.
+    pub pause_ms: u32,
+    /// Longest utterance; longer speech is cut here so captions keep up.
+    pub max_segment_seconds: u32,
+    /// Label utterances by speaker. Experimental; see the module docs.
+    pub speaker_labels: bool,
+}
+
+impl Default for CaptionsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pause_ms: 700,
+            max_segment_seconds: 8,
+            speaker_labels: false,
+        }
+    }
+}
+
+impl CaptionsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(200..=5000).contains(&self.pause_ms) {
+            return Err("pauseMs must be between 200 and 5000".to_string());
+        }
+        if !(2..=30).contains(&self.max_segment_seconds) {
+            return Err("maxSegmentSeconds must be between 2 and 30".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct CaptionsState {
+    config: Mutex<CaptionsConfig>,
+}
+
+/// Payload of `captions://segment`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionSegment {
+    /// Counts up through the capture session.
+    pub id: u64,
+    /// 1-based within the capture session. `None` unless `speaker_labels`
+    /// is on.
+    pub speaker: Option<usize>,
+    pub speaker_label: Option<String>,
+    pub text: String,
+    /// Wall-clock span of the utterance, ms since the Unix epoch.
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// Speech between two pauses.
+struct Utterance {
+    samples: Vec<f32>,
+    start_ms: u64,
+    end_ms: u64,
+}
+
+impl Drop for Utterance {
+    fn drop(&mut self) {
+        self.samples.zeroize();
+    }
+}
+
+fn samples_to_ms(samples: usize) -> u64 {
+    samples as u64 * 1000 / OUTPUT_SAMPLE_RATE as u64
+}
+
+/// Cuts incoming audio into utterances at pauses, dropping the silence.
+struct Segmenter {
+    pause_frames: usize,
+    max_frames: usize,
+    /// Audio since the last cut; starts with speech.
+    samples: Vec<f32>,
+    /// Wall-clock time of `samples[0]`.
+    start_ms: u64,
+    /// How much of `samples` has been looked at, in whole frames.
+    analyzed: usize,
+    speech_frames: usize,
+    /// Quiet frames at the end of what has been looked at.
+    quiet_run: usize,
+}
+
+impl Segmenter {
+    fn new(config: &CaptionsConfig) -> Self {
+        Self {
+            pause_frames: (config.pause_ms / 10) as usize,
+            max_frames: config.max_segment_seconds as usize * 100,
+            samples: Vec::new(),
+            start_ms: 0,
+            analyzed: 0,
+            speech_frames: 0,
+            quiet_run: 0,
+        }
+    }
+
+    /// Append `samples`, the first captured at `start_ms`, and return the
+    /// utterances they complete.
+    fn push(&mut self, samples: &[f32], start_ms: u64) -> Vec<Utterance> {
+        if self.samples.is_empty() {
+            self.start_ms = start_ms;
+        }
+        self.samples.extend_from_slice(samples);
+        let mut utterances = Vec::new();
+        while self.analyzed + FRAME_LEN <= self.samples.len() {
+            let frame = &self.samples[self.analyzed..self.analyzed + FRAME_LEN];
+            let rms = (frame.iter().map(|s| s * s).sum::<f32>() / FRAME_LEN as f32).sqrt();
+            self.analyzed += FRAME_LEN;
+            if linear_to_dbfs(rms) >= SPEECH_DBFS {
+                self.speech_frames += 1;
+                self.quiet_run = 0;
+            } else {
+                self.quiet_run += 1;
+            }
+            if self.speech_frames == 0 {
+                // Nothing said yet: drop the silence as it comes.
+                self.discard(self.analyzed);
+                continue;
+            }
+            let frames = self.analyzed / FRAME_LEN;
+            if self.quiet_run >= self.pause_frames || frames >= self.max_frames {
+                utterances.extend(self.cut());
+            }
+        }
+        utterances
+    }
+
+    /// End the current utterance, without its trailing silence. `None` if
+    /// it's too short to be speech.
+    fn cut(&mut self) -> Option<Utterance> {
+        let speech = self.analyzed - self.quiet_run * FRAME_LEN;
+        let utterance = (self.speech_frames >= MIN_SPEECH_FRAMES).then(|| Utterance {
+            samples: self.samples[..speech].to_vec(),
+            start_ms: self.start_ms,
+            end_ms: self.start_ms + samples_to_ms(speech),
+        });
+        self.discard(self.analyzed);
+        utterance
+    }
+
+    /// Drop the first `len` samples and reset the counts.
+    fn discard(&mut self, len: usize) {
+        self.samples[..len].zeroize();
+        self.samples.drain(..len);
+        self.start_ms += samples_to_ms(len);
+        self.analyzed = 0;
+        self.speech_frames = 0;
+        self.quiet_run = 0;
+    }
+}
+
+impl Drop for Segmenter {
+    fn drop(&mut self) {
+        self.samples.zeroize();
+    }
+}
+
+/// Mean MFCCs over the speech frames of `samples`. `None` if there are
+/// none.
+fn voice_print(samples: &[f32]) -> Option<Frame> {
+    let mut sum = [0.0f32; CEPSTRA];
+    let mut count = 0;
+    for (frame, dbfs) in Mfcc::new().push(samples) {
+        if dbfs >= SPEECH_DBFS {
+            sum.iter_mut().zip(frame).for_each(|(s, c)| *s += c);
+            count += 1;
+        }
+    }
+    (count > 0).then(|| sum.map(|s| s / count as f32))
+}
+
+fn cosine_distance(a: &Frame, b: &Frame) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &Frame| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        return 1.0;
+    }
+    1.0 - dot / norms
+}
+
+/// Online clustering of voice prints into speakers.
+#[derive(Debug, Default)]
+struct SpeakerTracker {
+    /// Per speaker: mean voice print and how many utterances it's from.
+    speakers: Vec<(Frame, usize)>,
+}
+
+impl SpeakerTracker {
+    /// The 0-based speaker of an utterance with `print`.
+    fn assign(&mut self, print: &Frame) -> usize {
+        let closest = self
+            .speakers
+            .iter()
+            .enumerate()
+            .map(|(i, (centroid, _))| (i, cosine_distance(centroid, print)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let index = match closest {
+            Some((i, distance))
+                if distance < SAME_SPEAKER_DISTANCE || self.speakers.len() == MAX_SPEAKERS =>
+            {
+                i
+            }
+            _ => {
+                self.speakers.push(([0.0; CEPSTRA], 0));
+                self.speakers.len() - 1
+            }
+        };
+        let (centroid, count) = &mut self.speakers[index];
+        *count += 1;
+        let weight = 1.0 / *count as f32;
+        centroid
+            .iter_mut()
+            .zip(print)
+            .for_each(|(c, p)| *c += (p - *c) * weight);
+        index
+    }
+}
+
+fn config(app: &AppHandle) -> Result<CaptionsConfig, String> {
+    Ok(app
+        .state::<CaptionsState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+/// Whether captions are on. The live transcript leaves transcription to
+/// them while they are.
+pub(crate) fn is_enabled(app: &AppHandle) -> bool {
+    config(app).is_ok_and(|config| config.enabled)
+}
+
+/// Transcribe utterances in order and emit them, until the sender is
+/// dropped at the end of the capture session.
+async fn run_captioner(
+    app: AppHandle,
+    speaker_labels: bool,
+    mut utterances: mpsc::Receiver<Utterance>,
+) {
+    let mut speakers = SpeakerTracker::default();
+    let mut id = 0;
+    while let Some(utterance) = utterances.recv().await {
+        let print = speaker_labels
+            .then(|| voice_print(&utterance.samples))
+            .flatten();
+        let mut wav = encode_wav(&utterance.samples, OUTPUT_SAMPLE_RATE);
+        let audio_base64 = base64::engine::general_purpose::STANDARD.encode(&wav);
+        wav.zeroize();
+        let text = match api::transcribe(app.clone(), audio_base64).await {
+            Ok(response) => response.into_transcription().unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Caption transcription failed: {}", e);
+                continue;
+            }
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let speaker = speaker_labels.then(|| print.map_or(0, |p| speakers.assign(&p)) + 1);
+        id += 1;
+        let segment = CaptionSegment {
+            id,
+            speaker,
+            speaker_label: speaker.map(|speaker| format!("Speaker {}", speaker)),
+            text: text.to_string(),
+            start_ms: utterance.start_ms,
+            end_ms: utterance.end_ms,
+        };
+        let _ = app.emit("captions://segment", &segment);
+        live_transcript::record(
+            &app,
+            TranscriptSegment {
+                start_ms: segment.start_ms,
+                end_ms: segment.end_ms,
+                text: segment.text,
+            },
+        );
+    }
+}
+
+/// One capture session's captioning.
+struct Session {
+    config: CaptionsConfig,
+    /// Buffer read position.
+    position: usize,
+    segmenter: Segmenter,
+    utterances: mpsc::Sender<Utterance>,
+}
+
+impl Session {
+    /// Caption from the buffer's live edge on, not the backlog.
+    fn start(app: &AppHandle, audio: &SystemAudioState, config: CaptionsConfig) -> Self {
+        let (utterances, receiver) = mpsc::channel(QUEUE_LEN);
+        tauri::async_runtime::spawn(run_captioner(app.clone(), config.speaker_labels, receiver));
+        Self {
+            segmenter: Segmenter::new(&config),
+            config,
+            position: audio.written_position(),
+            utterances,
+        }
+    }
+}
+
+/// Start the worker. Called once at startup; it does nothing until
+/// captions are enabled.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+        let mut session: Option<Session> = None;
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Ok(config) = config(&app) else {
+                continue;
+            };
+            // Dropping the session ends its captioner once it's caught up.
+            if !config.enabled || !audio.is_recording() {
+                session = None;
+                continue;
+            }
+            if session.as_ref().is_some_and(|s| s.config != config) {
+                session = None;
+            }
+            let session = session.get_or_insert_with(|| Session::start(&app, &audio, config));
+            let start_ms = audio.wall_time_ms_at(session.position);
+            let Ok((mut samples, next)) = audio.read_since(session.position) else {
+                continue;
+            };
+            // A new capture session restarted the count; follow it.
+            if next < session.position {
+                *session = Session::start(&app, &audio, session.config.clone());
+                continue;
+            }
+            session.position = next;
+            let Some(start_ms) = start_ms.filter(|_| !samples.is_empty()) else {
+                continue;
+            };
+            let utterances = session.segmenter.push(&samples, start_ms);
+            samples.zeroize();
+            for utterance in utterances {
+                if offline_queue::is_offline(&app) {
+                    continue;
+                }
+                if session.utterances.try_send(utterance).is_err() {
+                    tracing::debug!("Captions are behind; dropped an utterance");
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn captions_set_config(app: AppHandle, config: CaptionsConfig) -> Result<(), String> {
+    config.validate()?;
+    *app.state::<CaptionsState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn captions_get_config(app: AppHandle) -> Result<CaptionsConfig, String> {
+    config(&app)
+}
+
+#[cfg(test)]
+mod tests;
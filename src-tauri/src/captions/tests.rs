@@ -0,0 +1,99 @@
+use super::*;
+
+fn config(pause_ms: u32, max_segment_seconds: u32) -> CaptionsConfig {
+    CaptionsConfig {
+        enabled: true,
+        pause_ms,
+        max_segment_seconds,
+        speaker_labels: false,
+    }
+}
+
+fn silence(ms: usize) -> Vec<f32> {
+    vec![0.0; ms * OUTPUT_SAMPLE_RATE as usize / 1000]
+}
+
+fn tone(ms: usize) -> Vec<f32> {
+    (0..ms * OUTPUT_SAMPLE_RATE as usize / 1000)
+        .map(|i| 0.1 * (i as f32 * 0.2).sin())
+        .collect()
+}
+
+#[test]
+fn utterances_are_cut_at_pauses_without_the_silence() {
+    let mut segmenter = Segmenter::new(&config(200, 8));
+    let audio = [silence(100), tone(500), silence(300)].concat();
+    let utterances = segmenter.push(&audio, 10_000);
+    assert_eq!(utterances.len(), 1);
+    assert_eq!(utterances[0].start_ms, 10_100);
+    assert_eq!(utterances[0].end_ms, 10_600);
+    assert_eq!(utterances[0].samples.len(), tone(500).len());
+
+    // Split across pushes; the next one starts after the dropped silence.
+    assert!(segmenter.push(&tone(400), 10_900).is_empty());
+    let utterances = segmenter.push(&silence(250), 11_300);
+    assert_eq!(utterances.len(), 1);
+    assert_eq!(
+        (utterances[0].start_ms, utterances[0].end_ms),
+        (10_900, 11_300)
+    );
+}
+
+#[test]
+fn blips_are_dropped_and_long_speech_is_cut() {
+    let mut segmenter = Segmenter::new(&config(200, 2));
+    let blip = [tone(100), silence(300)].concat();
+    assert!(segmenter.push(&blip, 0).is_empty());
+
+    let utterances = segmenter.push(&tone(2500), 1_000);
+    assert_eq!(utterances.len(), 1);
+    assert_eq!(
+        (utterances[0].start_ms, utterances[0].end_ms),
+        (1_000, 3_000)
+    );
+    // The rest carries on as the next utterance.
+    let utterances = segmenter.push(&silence(200), 3_500);
+    assert_eq!(
+        (utterances[0].start_ms, utterances[0].end_ms),
+        (3_000, 3_500)
+    );
+}
+
+#[test]
+fn close_voice_prints_share_a_speaker() {
+    let mut a = [0.0f32; CEPSTRA];
+    a[0] = 10.0;
+    a[1] = 2.0;
+    let mut a_again = a;
+    a_again[2] = 0.5;
+    let mut b = [0.0f32; CEPSTRA];
+    b[0] = -4.0;
+    b[3] = 9.0;
+
+    let mut speakers = SpeakerTracker::default();
+    assert_eq!(speakers.assign(&a), 0);
+    assert_eq!(speakers.assign(&b), 1);
+    assert_eq!(speakers.assign(&a_again), 0);
+    assert_eq!(speakers.assign(&b), 1);
+    assert!(cosine_distance(&a, &a) < 1e-6);
+    assert_eq!(cosine_distance(&a, &[0.0; CEPSTRA]), 1.0);
+}
+
+#[test]
+fn speakers_are_capped() {
+    let mut speakers = SpeakerTracker::default();
+    for i in 0..MAX_SPEAKERS + 2 {
+        let mut print = [0.0f32; CEPSTRA];
+        print[i % CEPSTRA] = 1.0;
+        assert!(speakers.assign(&print) < MAX_SPEAKERS);
+    }
+    assert_eq!(speakers.speakers.len(), MAX_SPEAKERS);
+}
+
+#[test]
+fn config_is_validated() {
+    assert!(CaptionsConfig::default().validate().is_ok());
+    assert!(config(100, 8).validate().is_err());
+    assert!(config(700, 1).validate().is_err());
+    assert!(config(700, 31).validate().is_err());
+}
@@ -1,3 +1,4 @@
+use crate::privacy::screen_capture_blocked;
 use base64::Engine;
 use image::codecs::jpeg::JpegEncoder;
 use image::codecs::png::PngEncoder;
@@ -24,12 +25,15 @@ pub struct SelectionCoords {
 #[derive(Debug, Clone)]
 pub struct MonitorInfo {
     pub image: image::RgbaImage,
+    /// Pixels per point.
+    pub scale_factor: f32,
 }
 
 // Store captured images from all monitors temporarily for cropping
 pub struct CaptureState {
     pub captured_monitors: Arc<Mutex<HashMap<usize, MonitorInfo>>>,
     pub overlay_active: Arc<AtomicBool>,
+    config: Mutex<ScreenshotConfig>,
 }
 
 impl Default for CaptureState {
@@ -37,10 +41,366 @@ impl Default for CaptureState {
         Self {
             captured_monitors: Arc::default(),
             overlay_active: Arc::new(AtomicBool::new(false)),
+            config: Mutex::default(),
         }
     }
 }
 
+/// Lossy screenshots are scaled down to fit this unless told otherwise.
+pub const DEFAULT_MAX_DIMENSION: u32 = 1600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenshotFormat {
+    /// Lossless, keeps alpha.
+    Png,
+    #[default]
+    Jpeg,
+    /// Lossy WebP, about half the size of JPEG at the same quality.
+    Webp,
+}
+
+impl ScreenshotFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+/// How screenshots are encoded when a command doesn't say. The commands
+/// returning bare base64 keep to JPEG or PNG; see `resolve_base64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScreenshotConfig {
+    pub format: ScreenshotFormat,
+    /// 1-100; PNG ignores it.
+    pub quality: u8,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            format: ScreenshotFormat::Jpeg,
+            quality: 75,
+        }
+    }
+}
+
+fn validate_quality(quality: u8) -> Result<(), String> {
+    if !(1..=100).contains(&quality) {
+        return Err(format!("quality must be 1-100, got {}", quality));
+    }
+    Ok(())
+}
+
+impl ScreenshotConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_quality(self.quality)
+    }
+
+    /// Format and quality for one screenshot. An explicit `format` wins;
+    /// otherwise `compression_enabled: false` asks for PNG, and anything
+    /// else gets the configured format.
+    pub fn resolve(
+        &self,
+        format: Option<ScreenshotFormat>,
+        compression_enabled: Option<bool>,
+        quality: Option<u8>,
+    ) -> Result<(ScreenshotFormat, u8), String> {
+        let format = format.unwrap_or(match compression_enabled {
+            Some(false) => ScreenshotFormat::Png,
+            _ => self.format,
+        });
+        let quality = quality.unwrap_or(self.quality);
+        validate_quality(quality)?;
+        Ok((format, quality))
+    }
+
+    /// `resolve` for the commands that return bare base64, whose callers
+    /// label it JPEG or PNG: the configured format applies only when
+    /// `format` is passed, otherwise it's JPEG, or PNG when
+    /// `compression_enabled` is false.
+    pub fn resolve_base64(
+        &self,
+        format: Option<ScreenshotFormat>,
+        compression_enabled: Option<bool>,
+        quality: Option<u8>,
+    ) -> Result<(ScreenshotFormat, u8), String> {
+        let format = format.unwrap_or(match compression_enabled {
+            Some(false) => ScreenshotFormat::Png,
+            _ => ScreenshotFormat::Jpeg,
+        });
+        self.resolve(Some(format), compression_enabled, quality)
+    }
+}
+
+pub fn screenshot_config(app: &tauri::AppHandle) -> Result<ScreenshotConfig, String> {
+    app.state::<CaptureState>()
+        .config
+        .lock()
+        .map(|config| *config)
+        .map_err(|e| e.to_string())
+}
+
+/// What a screenshot shows besides the windows themselves. Vision models
+/// read decorations as content, so each can be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScreenshotOptions {
+    /// Draw the mouse pointer.
+    pub include_cursor: bool,
+    /// Keep the shadows windows cast. Only honoured on macOS.
+    pub include_shadows: bool,
+    /// Keep the menu bar. Only honoured on macOS.
+    pub include_menu_bar: bool,
+}
+
+impl Default for ScreenshotOptions {
+    fn default() -> Self {
+        Self {
+            include_cursor: false,
+            include_shadows: true,
+            include_menu_bar: true,
+        }
+    }
+}
+
+/// How far a screenshot is scaled down before it's encoded. Retina
+/// captures are twice the size they look, which LLM APIs charge for.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScreenshotScale {
+    /// Every captured pixel.
+    #[default]
+    Native,
+    /// One pixel per point, i.e. 1x.
+    Logical,
+    /// The longest side at most `max_dimension` pixels.
+    #[serde(rename_all = "camelCase")]
+    MaxDimension { max_dimension: u32 },
+}
+
+impl ScreenshotScale {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::MaxDimension { max_dimension: 0 } => {
+                Err("maxDimension must be above 0".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Output size for a `width`x`height` capture of a display with
+    /// `scale_factor` pixels per point. Never larger than the capture.
+    fn size(&self, width: u32, height: u32, scale_factor: f32) -> (u32, u32) {
+        let factor = match self {
+            Self::Native => 1.0,
+            Self::Logical => 1.0 / scale_factor.max(1.0),
+            Self::MaxDimension { max_dimension } => {
+                (*max_dimension as f32 / width.max(height).max(1) as f32).min(1.0)
+            }
+        };
+        scaled_size(width, height, factor)
+    }
+
+    pub fn apply(&self, image: image::RgbaImage, scale_factor: f32) -> image::RgbaImage {
+        let (width, height) = self.size(image.width(), image.height(), scale_factor);
+        if (width, height) == image.dimensions() {
+            return image;
+        }
+        image::imageops::resize(&image, width, height, FilterType::Triangle)
+    }
+}
+
+/// The classic arrow pointer: `X` outline, `.` fill.
+#[cfg(any(not(target_os = "macos"), test))]
+const POINTER: [&str; 19] = [
+    "X           ",
+    "XX          ",
+    "X.X         ",
+    "X..X        ",
+    "X...X       ",
+    "X....X      ",
+    "X.....X     ",
+    "X......X    ",
+    "X.......X   ",
+    "X........X  ",
+    "X.........X ",
+    "X......XXXXX",
+    "X...X..X    ",
+    "X..XX..X    ",
+    "X.X  X..X   ",
+    "XX   X..X   ",
+    "X     X..X  ",
+    "      X..X  ",
+    "       XX   ",
+];
+
+/// Draw the pointer with its tip at (`x`, `y`), each cell `scale` pixels
+/// square. Whatever falls outside the image is clipped.
+#[cfg(any(not(target_os = "macos"), test))]
+fn draw_pointer(image: &mut image::RgbaImage, x: i64, y: i64, scale: u32) {
+    let scale = scale.max(1) as i64;
+    for (row, line) in POINTER.iter().enumerate() {
+        for (col, cell) in line.bytes().enumerate() {
+            let color = match cell {
+                b'X' => image::Rgba([0, 0, 0, 255]),
+                b'.' => image::Rgba([255, 255, 255, 255]),
+                _ => continue,
+            };
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let px = x + col as i64 * scale + dx;
+                    let py = y + row as i64 * scale + dy;
+                    if (0..image.width() as i64).contains(&px)
+                        && (0..image.height() as i64).contains(&py)
+                    {
+                        image.put_pixel(px as u32, py as u32, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Encode a captured image for transfer: downscaled JPEG when compression is
+/// enabled, otherwise a lossless PNG (preserves alpha).
+pub fn encode_image(
+    image: image::RgbaImage,
+    compression_enabled: Option<bool>,
+    compression_max_dimension: Option<u32>,
+    compression_quality: Option<u8>,
+) -> Result<Vec<u8>, String> {
+    let format = if compression_enabled.unwrap_or(true) {
+        ScreenshotFormat::Jpeg
+    } else {
+        ScreenshotFormat::Png
+    };
+    encode_as(
+        image,
+        format,
+        compression_quality.unwrap_or(75),
+        Some(compression_max_dimension.unwrap_or(DEFAULT_MAX_DIMENSION)),
+    )
+}
+
+/// Encode `image` as `format`. Lossy formats are first scaled down to fit
+/// `max_dimension`, when given; PNG is always full size.
+pub fn encode_as(
+    image: image::RgbaImage,
+    format: ScreenshotFormat,
+    quality: u8,
+    max_dimension: Option<u32>,
+) -> Result<Vec<u8>, String> {
+    let mut out_buf = Vec::new();
+    if format == ScreenshotFormat::Png {
+        PngEncoder::new(&mut out_buf)
+            .write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8.into(),
+            )
+            .map_err(|e| format!("Failed to encode to PNG: {}", e))?;
+        return Ok(out_buf);
+    }
+
+    let (w, h) = image.dimensions();
+    let (new_w, new_h) = match max_dimension {
+        Some(max_dim) if w.max(h) > max_dim => {
+            let scale = (max_dim as f64 / w.max(h) as f64) as f32;
+            let nw = (w as f32 * scale).round() as u32;
+            let nh = (h as f32 * scale).round() as u32;
+            (nw, nh)
+        }
+        _ => (w, h),
+    };
+    let resized = if (new_w, new_h) != (w, h) {
+        image::imageops::resize(&image, new_w, new_h, FilterType::Triangle)
+    } else {
+        image
+    };
+
+    if format == ScreenshotFormat::Webp {
+        let webp = webp::Encoder::from_rgba(resized.as_raw(), resized.width(), resized.height())
+            .encode(quality as f32);
+        return Ok(webp.to_vec());
+    }
+    // Convert to RGB (drops alpha) and encode as JPEG
+    let rgb = DynamicImage::ImageRgba8(resized).to_rgb8();
+    JpegEncoder::new_with_quality(&mut out_buf, quality)
+        .encode(
+            rgb.as_raw(),
+            rgb.width(),
+            rgb.height(),
+            ColorType::Rgb8.into(),
+        )
+        .map_err(|e| format!("Failed to encode to JPEG: {}", e))?;
+    Ok(out_buf)
+}
+
+/// `width`x`height` scaled by `scale`, at least one pixel a side.
+fn scaled_size(width: u32, height: u32, scale: f32) -> (u32, u32) {
+    let side = |n: u32| ((n as f32 * scale).round() as u32).clamp(1, n.max(1));
+    (side(width), side(height))
+}
+
+/// Scale `image` by `scale` and encode it, trading quality for speed with
+/// a box-filter downscale.
+pub fn encode_frame(
+    image: image::RgbaImage,
+    scale: f32,
+    format: ScreenshotFormat,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let (width, height) = scaled_size(image.width(), image.height(), scale);
+    let image = if (width, height) == image.dimensions() {
+        image
+    } else {
+        image::imageops::thumbnail(&image, width, height)
+    };
+    encode_as(image, format, quality, None)
+}
+
+fn primary_monitor() -> Result<Monitor, String> {
+    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+    let index = monitors.iter().position(|m| m.is_primary()).unwrap_or(0);
+    monitors
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| "No monitors found".to_string())
+}
+
+/// Capture the primary monitor at full resolution.
+pub fn capture_primary_monitor_image() -> Result<image::RgbaImage, String> {
+    primary_monitor()?
+        .capture_image()
+        .map_err(|e| format!("Failed to capture image: {}", e))
+}
+
+/// Capture `monitor` with `options`; anything but the defaults goes through
+/// the platform capture.
+fn capture_monitor(
+    app: &tauri::AppHandle,
+    monitor: &Monitor,
+    options: &ScreenshotOptions,
+) -> Result<image::RgbaImage, String> {
+    if *options == ScreenshotOptions::default() {
+        return monitor
+            .capture_image()
+            .map_err(|e| format!("Failed to capture image: {}", e));
+    }
+    platform::capture(app, monitor, options)
+}
+
+/// Capture the primary monitor and encode it with `encode_image` defaults.
+pub fn capture_primary_monitor() -> Result<Vec<u8>, String> {
+    encode_image(capture_primary_monitor_image()?, None, None, None)
+}
+
 #[tauri::command]
 pub async fn start_screen_capture(app: tauri::AppHandle) -> Result<(), String> {
     // Get all monitors
@@ -80,6 +440,7 @@ pub async fn start_screen_capture(app: tauri::AppHandle) -> Result<(), String> {
 
         let monitor_info = MonitorInfo {
             image: captured_image,
+            scale_factor: monitor.scale_factor(),
         };
 
         captured_monitors.insert(idx, monitor_info);
@@ -207,7 +568,18 @@ pub async fn capture_selected_area(
     compression_enabled: Option<bool>,
     compression_max_dimension: Option<u32>,
     compression_quality: Option<u8>,
+    scale: Option<ScreenshotScale>,
+    mask_sensitive: Option<bool>,
+    format: Option<ScreenshotFormat>,
 ) -> Result<String, String> {
+    let scale = scale.unwrap_or_default();
+    scale.validate()?;
+    let (format, quality) = screenshot_config(&app)?.resolve_base64(
+        format,
+        compression_enabled,
+        compression_quality,
+    )?;
+
     // Get the stored captured monitors
     let state = app.state::<CaptureState>();
     let mut captured_monitors = state.captured_monitors.lock().unwrap();
@@ -232,48 +604,19 @@ pub async fn capture_selected_area(
     let height = coords.height.min(img_height - y);
 
     // Crop the image to the selected area
-    let cropped = monitor_info.image.view(x, y, width, height).to_image();
-
-    // Compression settings (from frontend)
-    let use_compression = compression_enabled.unwrap_or(true);
-    let max_dim = compression_max_dimension.unwrap_or(1600);
-    let jpeg_quality = compression_quality.unwrap_or(75);
-
-    let base64_str = if use_compression {
-        let dyn_img = DynamicImage::ImageRgba8(cropped);
-        let (w, h) = dyn_img.dimensions();
-        let (new_w, new_h) = if w.max(h) > max_dim {
-            let scale = (max_dim as f64 / w.max(h) as f64) as f32;
-            let nw = (w as f32 * scale).round() as u32;
-            let nh = (h as f32 * scale).round() as u32;
-            (nw, nh)
-        } else {
-            (w, h)
-        };
-
-        let resized = if (new_w, new_h) != (w, h) {
-            let buf = image::imageops::resize(&dyn_img, new_w, new_h, FilterType::Triangle);
-            DynamicImage::ImageRgba8(buf)
-        } else {
-            dyn_img.clone()
-        };
-
-        // Convert to RGB (drops alpha) and encode as JPEG
-        let rgb = resized.to_rgb8();
-        let mut out_buf = Vec::new();
-        JpegEncoder::new_with_quality(&mut out_buf, jpeg_quality)
-            .encode(&rgb.as_raw(), rgb.width(), rgb.height(), ColorType::Rgb8.into())
-            .map_err(|e| format!("Failed to encode to JPEG: {}", e))?;
+    let mut cropped = monitor_info.image.view(x, y, width, height).to_image();
+    if mask_sensitive.unwrap_or(false) {
+        crate::screen_mask::mask_sensitive(&mut cropped)?;
+    }
+    let cropped = scale.apply(cropped, monitor_info.scale_factor);
 
-        base64::engine::general_purpose::STANDARD.encode(out_buf)
-    } else {
-        // Return a PNG if compression is disabled (preserves alpha)
-        let mut out_buf = Vec::new();
-        PngEncoder::new(&mut out_buf)
-            .write_image(&cropped.as_raw(), cropped.width(), cropped.height(), ColorType::Rgba8.into())
-            .map_err(|e| format!("Failed to encode to PNG: {}", e))?;
-        base64::engine::general_purpose::STANDARD.encode(out_buf)
-    };
+    let encoded = encode_as(
+        cropped,
+        format,
+        quality,
+        Some(compression_max_dimension.unwrap_or(DEFAULT_MAX_DIMENSION)),
+    )?;
+    let base64_str = base64::engine::general_purpose::STANDARD.encode(encoded);
 
     captured_monitors.clear();
     drop(captured_monitors);
@@ -296,7 +639,25 @@ pub async fn capture_selected_area(
 }
 
 #[tauri::command]
-pub async fn capture_to_base64(window: tauri::WebviewWindow, compression_enabled: Option<bool>, compression_max_dimension: Option<u32>, compression_quality: Option<u8>) -> Result<String, String> {
+pub async fn capture_to_base64(
+    window: tauri::WebviewWindow,
+    compression_enabled: Option<bool>,
+    compression_max_dimension: Option<u32>,
+    compression_quality: Option<u8>,
+    options: Option<ScreenshotOptions>,
+    scale: Option<ScreenshotScale>,
+    mask_sensitive: Option<bool>,
+    format: Option<ScreenshotFormat>,
+) -> Result<String, String> {
+    let app = window.app_handle().clone();
+    let options = options.unwrap_or_default();
+    let scale = scale.unwrap_or_default();
+    scale.validate()?;
+    let (format, quality) = screenshot_config(&app)?.resolve_base64(
+        format,
+        compression_enabled,
+        compression_quality,
+    )?;
     let monitor_fallback = window
         .current_monitor()
         .ok()
@@ -404,53 +765,358 @@ pub async fn capture_to_base64(window: tauri::WebviewWindow, compression_enabled
             })
             .ok_or_else(|| "Failed to determine target monitor".to_string())?;
 
-        let image = monitor
-            .capture_image()
-            .map_err(|e| format!("Failed to capture image: {}", e))?;
+        let mut image = capture_monitor(&app, &monitor, &options)?;
+        if mask_sensitive.unwrap_or(false) {
+            crate::screen_mask::mask_sensitive(&mut image)?;
+        }
+        let image = scale.apply(image, monitor.scale_factor());
 
-        // Compression settings (from frontend)
-        let use_compression = compression_enabled.unwrap_or(true);
-        let max_dim = compression_max_dimension.unwrap_or(1600);
-        let jpeg_quality = compression_quality.unwrap_or(75);
-
-        let base64_str = if use_compression {
-            let dyn_img = DynamicImage::ImageRgba8(image);
-            let (w, h) = dyn_img.dimensions();
-            let (new_w, new_h) = if w.max(h) > max_dim {
-                let scale = (max_dim as f64 / w.max(h) as f64) as f32;
-                let nw = (w as f32 * scale).round() as u32;
-                let nh = (h as f32 * scale).round() as u32;
-                (nw, nh)
-            } else {
-                (w, h)
-            };
+        let encoded = encode_as(
+            image,
+            format,
+            quality,
+            Some(compression_max_dimension.unwrap_or(DEFAULT_MAX_DIMENSION)),
+        )?;
+        let base64_str = base64::engine::general_purpose::STANDARD.encode(encoded);
 
-            let resized = if (new_w, new_h) != (w, h) {
-                let buf = image::imageops::resize(&dyn_img, new_w, new_h, FilterType::Triangle);
-                DynamicImage::ImageRgba8(buf)
-            } else {
-                dyn_img.clone()
+        Ok(base64_str)
+    })
+    .await
+    .map_err(|e| format!("Task panicked: {}", e))?
+}
+
+/// The primary display scaled by `scale` (0-1, default 0.5), in `format`
+/// at `quality` (the screenshot settings by default). Returned as raw bytes
+/// rather than base64, for sending frames to a vision model in a loop.
+/// `mask_sensitive` masks sensitive text first, at the cost of an OCR pass
+/// per frame.
+#[tauri::command]
+pub async fn grab_frame(
+    app: tauri::AppHandle,
+    scale: Option<f32>,
+    quality: Option<u8>,
+    options: Option<ScreenshotOptions>,
+    mask_sensitive: Option<bool>,
+    format: Option<ScreenshotFormat>,
+) -> Result<tauri::ipc::Response, String> {
+    let options = options.unwrap_or_default();
+    let scale = scale.unwrap_or(0.5);
+    if !(scale > 0.0 && scale <= 1.0) {
+        return Err(format!("scale must be in (0, 1], got {}", scale));
+    }
+    let (format, quality) = screenshot_config(&app)?.resolve(format, None, quality)?;
+    if let Some(reason) = screen_capture_blocked(&app) {
+        return Err(reason.to_string());
+    }
+    let frame = tauri::async_runtime::spawn_blocking(move || {
+        let mut image = capture_monitor(&app, &primary_monitor()?, &options)?;
+        if mask_sensitive.unwrap_or(false) {
+            crate::screen_mask::mask_sensitive(&mut image)?;
+        }
+        encode_frame(image, scale, format, quality)
+    })
+    .await
+    .map_err(|e| format!("Task panicked: {}", e))??;
+    Ok(tauri::ipc::Response::new(frame))
+}
+
+/// Set how screenshots are encoded when a command doesn't say.
+#[tauri::command]
+pub fn screenshot_set_config(
+    app: tauri::AppHandle,
+    config: ScreenshotConfig,
+) -> Result<(), String> {
+    config.validate()?;
+    *app.state::<CaptureState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn screenshot_get_config(app: tauri::AppHandle) -> Result<ScreenshotConfig, String> {
+    screenshot_config(&app)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::ScreenshotOptions;
+    use block2::RcBlock;
+    use objc2::encode::{Encode, Encoding};
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2::{msg_send, sel};
+    use objc2_foundation::NSString;
+    use std::ffi::{c_void, CStr};
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use xcap::Monitor;
+
+    #[link(name = "ScreenCaptureKit", kind = "framework")]
+    extern "C" {}
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGImageRetain(image: *mut c_void) -> *mut c_void;
+        fn CGImageRelease(image: *mut c_void);
+        fn CGImageGetWidth(image: *mut c_void) -> usize;
+        fn CGImageGetHeight(image: *mut c_void) -> usize;
+        fn CGColorSpaceCreateDeviceRGB() -> *mut c_void;
+        fn CGColorSpaceRelease(space: *mut c_void);
+        fn CGBitmapContextCreate(
+            data: *mut c_void,
+            width: usize,
+            height: usize,
+            bits_per_component: usize,
+            bytes_per_row: usize,
+            space: *mut c_void,
+            bitmap_info: u32,
+        ) -> *mut c_void;
+        fn CGContextDrawImage(context: *mut c_void, rect: CGRect, image: *mut c_void);
+        fn CGContextRelease(context: *mut c_void);
+    }
+
+    /// kCGImageAlphaPremultipliedLast | kCGBitmapByteOrder32Big: RGBA bytes.
+    const RGBA: u32 = 1 | (4 << 12);
+    /// Longest to wait for a ScreenCaptureKit completion handler.
+    const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[repr(C)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    #[repr(C)]
+    struct CGSize {
+        width: f64,
+        height: f64,
+    }
+
+    #[repr(C)]
+    struct CGRect {
+        origin: CGPoint,
+        size: CGSize,
+    }
+
+    unsafe impl Encode for CGPoint {
+        const ENCODING: Encoding = Encoding::Struct("CGPoint", &[f64::ENCODING, f64::ENCODING]);
+    }
+
+    unsafe impl Encode for CGSize {
+        const ENCODING: Encoding = Encoding::Struct("CGSize", &[f64::ENCODING, f64::ENCODING]);
+    }
+
+    unsafe impl Encode for CGRect {
+        const ENCODING: Encoding =
+            Encoding::Struct("CGRect", &[CGPoint::ENCODING, CGSize::ENCODING]);
+    }
+
+    /// A retained CGImage handed over from the completion handler's thread.
+    struct Image(*mut c_void);
+
+    // SAFETY: CGImage is immutable.
+    unsafe impl Send for Image {}
+
+    impl Drop for Image {
+        fn drop(&mut self) {
+            unsafe { CGImageRelease(self.0) }
+        }
+    }
+
+    /// Shareable content handed over from the completion handler's thread.
+    struct Shared(Option<Retained<AnyObject>>);
+
+    // SAFETY: moved once to the waiting thread, which is then its only user.
+    unsafe impl Send for Shared {}
+
+    fn class(name: &[u8]) -> Result<&'static AnyClass, String> {
+        CStr::from_bytes_with_nul(name)
+            .ok()
+            .and_then(AnyClass::get)
+            .ok_or_else(|| "Screenshot options need macOS 14 or later".to_string())
+    }
+
+    unsafe fn error_message(error: *mut AnyObject) -> Option<String> {
+        let error = error.as_ref()?;
+        let text: Option<Retained<NSString>> = msg_send![error, localizedDescription];
+        Some(text.map(|t| t.to_string()).unwrap_or_default())
+    }
+
+    /// The SCDisplay with CoreGraphics display id `id`.
+    unsafe fn display(id: u32) -> Result<Retained<AnyObject>, String> {
+        let (tx, rx) = mpsc::channel::<Result<Shared, String>>();
+        let block = RcBlock::new(move |content: *mut AnyObject, error: *mut AnyObject| {
+            let result = match error_message(error) {
+                Some(e) => Err(e),
+                None => Ok(Shared(Retained::retain(content))),
             };
+            let _ = tx.send(result);
+        });
+        let _: () = msg_send![
+            class(b"SCShareableContent\0")?,
+            getShareableContentExcludingDesktopWindows: false,
+            onScreenWindowsOnly: true,
+            completionHandler: &*block
+        ];
+        let content = rx
+            .recv_timeout(CALLBACK_TIMEOUT)
+            .map_err(|_| "Listing displays timed out".to_string())?
+            .map_err(|e| {
+                format!(
+                    "Failed to list displays (is Screen Recording allowed?): {}",
+                    e
+                )
+            })?
+            .0
+            .ok_or_else(|| "No displays to capture".to_string())?;
+        let displays: Option<Retained<AnyObject>> = msg_send![&*content, displays];
+        let displays = displays.ok_or_else(|| "No displays to capture".to_string())?;
+        let count: usize = msg_send![&*displays, count];
+        for i in 0..count {
+            let display: Option<Retained<AnyObject>> = msg_send![&*displays, objectAtIndex: i];
+            if let Some(display) = display {
+                let display_id: u32 = msg_send![&*display, displayID];
+                if display_id == id {
+                    return Ok(display);
+                }
+            }
+        }
+        Err(format!("Display {} not found", id))
+    }
 
-            // Convert to RGB and encode as JPEG
-            let rgb = resized.to_rgb8();
-            let mut out_buf = Vec::new();
-            JpegEncoder::new_with_quality(&mut out_buf, jpeg_quality)
-                .encode(&rgb.as_raw(), rgb.width(), rgb.height(), ColorType::Rgb8.into())
-                .map_err(|e| format!("Failed to encode to JPEG: {}", e))?;
-            base64::engine::general_purpose::STANDARD.encode(out_buf)
-        } else {
-            // No compression requested; return PNG to preserve alpha
-            let mut out_buf = Vec::new();
-            PngEncoder::new(&mut out_buf)
-                .write_image(&image.as_raw(), image.width(), image.height(), ColorType::Rgba8.into())
-                .map_err(|e| format!("Failed to encode to PNG: {}", e))?;
-            base64::engine::general_purpose::STANDARD.encode(out_buf)
+    unsafe fn to_rgba(image: &Image) -> Result<image::RgbaImage, String> {
+        let width = CGImageGetWidth(image.0);
+        let height = CGImageGetHeight(image.0);
+        let mut pixels = vec![0u8; width * height * 4];
+        let space = CGColorSpaceCreateDeviceRGB();
+        let context = CGBitmapContextCreate(
+            pixels.as_mut_ptr().cast(),
+            width,
+            height,
+            8,
+            width * 4,
+            space,
+            RGBA,
+        );
+        CGColorSpaceRelease(space);
+        if context.is_null() {
+            return Err("Failed to create a bitmap context".to_string());
+        }
+        let rect = CGRect {
+            origin: CGPoint { x: 0.0, y: 0.0 },
+            size: CGSize {
+                width: width as f64,
+                height: height as f64,
+            },
         };
+        CGContextDrawImage(context, rect, image.0);
+        CGContextRelease(context);
+        image::RgbaImage::from_raw(width as u32, height as u32, pixels)
+            .ok_or_else(|| "Screenshot has an unexpected size".to_string())
+    }
 
+    pub fn capture(
+        _app: &tauri::AppHandle,
+        monitor: &Monitor,
+        options: &ScreenshotOptions,
+    ) -> Result<image::RgbaImage, String> {
+        unsafe {
+            let display = display(monitor.id())?;
+            let no_windows: Retained<AnyObject> = msg_send![class(b"NSArray\0")?, array];
+            let filter: Allocated<AnyObject> = msg_send![class(b"SCContentFilter\0")?, alloc];
+            let filter: Option<Retained<AnyObject>> =
+                msg_send![filter, initWithDisplay: &*display, excludingWindows: &*no_windows];
+            let filter = filter.ok_or_else(|| "Failed to create content filter".to_string())?;
+            if !options.include_menu_bar {
+                let supported: bool =
+                    msg_send![&*filter, respondsToSelector: sel!(setIncludeMenuBar:)];
+                if !supported {
+                    return Err("Leaving out the menu bar needs macOS 14.2 or later".to_string());
+                }
+                let _: () = msg_send![&*filter, setIncludeMenuBar: false];
+            }
 
-        Ok(base64_str)
-    })
-    .await
-    .map_err(|e| format!("Task panicked: {}", e))?
+            // Full pixel size of what the filter shows, not points.
+            let info: Option<Retained<AnyObject>> =
+                msg_send![class(b"SCShareableContent\0")?, infoForFilter: &*filter];
+            let (scale, width, height) = match &info {
+                Some(info) => {
+                    let scale: f32 = msg_send![&**info, pointPixelScale];
+                    let rect: CGRect = msg_send![&**info, contentRect];
+                    (scale as f64, rect.size.width, rect.size.height)
+                }
+                None => {
+                    let width: isize = msg_send![&*display, width];
+                    let height: isize = msg_send![&*display, height];
+                    (1.0, width as f64, height as f64)
+                }
+            };
+
+            let config: Retained<AnyObject> = msg_send![class(b"SCStreamConfiguration\0")?, new];
+            let _: () = msg_send![&*config, setWidth: (width * scale) as usize];
+            let _: () = msg_send![&*config, setHeight: (height * scale) as usize];
+            let _: () = msg_send![&*config, setShowsCursor: options.include_cursor];
+            let _: () = msg_send![&*config, setIgnoreShadowsDisplay: !options.include_shadows];
+
+            let (tx, rx) = mpsc::channel::<Result<Image, String>>();
+            let block = RcBlock::new(move |image: *mut c_void, error: *mut AnyObject| {
+                let result = match error_message(error) {
+                    Some(e) => Err(e),
+                    None if image.is_null() => Err("No image returned".to_string()),
+                    None => Ok(Image(CGImageRetain(image))),
+                };
+                let _ = tx.send(result);
+            });
+            let _: () = msg_send![
+                class(b"SCScreenshotManager\0")?,
+                captureImageWithFilter: &*filter,
+                configuration: &*config,
+                completionHandler: &*block
+            ];
+            let image = rx
+                .recv_timeout(CALLBACK_TIMEOUT)
+                .map_err(|_| "Screenshot timed out".to_string())?
+                .map_err(|e| format!("Failed to capture image: {}", e))?;
+            to_rgba(&image)
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::{draw_pointer, ScreenshotOptions};
+    use xcap::Monitor;
+
+    /// Pointer cells are this many pixels square per 1080 rows of screen.
+    const POINTER_ROWS: u32 = 1080;
+
+    /// Window shadows and the menu bar are macOS ideas; only the pointer is
+    /// drawn in here.
+    pub fn capture(
+        app: &tauri::AppHandle,
+        monitor: &Monitor,
+        options: &ScreenshotOptions,
+    ) -> Result<image::RgbaImage, String> {
+        let mut image = monitor
+            .capture_image()
+            .map_err(|e| format!("Failed to capture image: {}", e))?;
+        if options.include_cursor {
+            // Physical desktop coordinates, as the monitor's position is.
+            if let Ok(position) = app.cursor_position() {
+                let scale = image.height() / POINTER_ROWS;
+                draw_pointer(
+                    &mut image,
+                    position.x.round() as i64 - monitor.x() as i64,
+                    position.y.round() as i64 - monitor.y() as i64,
+                    scale,
+                );
+            }
+        }
+        Ok(image)
+    }
 }
+
+#[cfg(test)]
+mod tests;
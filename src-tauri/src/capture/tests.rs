@@ -0,0 +1,195 @@
+use super::*;
+
+#[test]
+fn frames_scale_proportionally() {
+    assert_eq!(scaled_size(1920, 1080, 0.5), (960, 540));
+    assert_eq!(scaled_size(1920, 1080, 1.0), (1920, 1080));
+    assert_eq!(scaled_size(2560, 1600, 0.25), (640, 400));
+}
+
+#[test]
+fn scaled_frames_keep_at_least_a_pixel() {
+    assert_eq!(scaled_size(100, 3, 0.01), (1, 1));
+}
+
+#[test]
+fn frames_encode_as_jpeg_at_the_scaled_size() {
+    let image = image::RgbaImage::from_pixel(64, 32, image::Rgba([200, 40, 40, 255]));
+    let jpeg = encode_frame(image, 0.5, ScreenshotFormat::Jpeg, 70).unwrap();
+    assert_eq!(jpeg[..2], [0xFF, 0xD8]);
+    let decoded = image::load_from_memory(&jpeg).unwrap();
+    assert_eq!(decoded.dimensions(), (32, 16));
+}
+
+#[test]
+fn screenshot_options_default_to_a_plain_capture() {
+    let options: ScreenshotOptions = serde_json::from_str("{}").unwrap();
+    assert_eq!(options, ScreenshotOptions::default());
+    assert!(!options.include_cursor);
+    assert!(options.include_shadows);
+    assert!(options.include_menu_bar);
+
+    let options: ScreenshotOptions =
+        serde_json::from_str(r#"{"includeCursor":true,"includeMenuBar":false}"#).unwrap();
+    assert!(options.include_cursor);
+    assert!(options.include_shadows);
+    assert!(!options.include_menu_bar);
+}
+
+#[test]
+fn pointer_is_drawn_from_its_tip() {
+    let mut image = image::RgbaImage::from_pixel(40, 40, image::Rgba([0, 0, 255, 255]));
+    draw_pointer(&mut image, 10, 5, 1);
+    assert_eq!(image.get_pixel(10, 5).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(11, 7).0, [255, 255, 255, 255]);
+    // Right of the tip is left alone.
+    assert_eq!(image.get_pixel(11, 5).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(9, 5).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn pointer_scales_and_clips_at_the_edges() {
+    let mut image = image::RgbaImage::from_pixel(8, 8, image::Rgba([0, 0, 255, 255]));
+    draw_pointer(&mut image, 0, 0, 2);
+    assert_eq!(image.get_pixel(1, 1).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(2, 4).0, [255, 255, 255, 255]);
+
+    let mut image = image::RgbaImage::from_pixel(8, 8, image::Rgba([0, 0, 255, 255]));
+    draw_pointer(&mut image, -3, 6, 1);
+    draw_pointer(&mut image, 100, 100, 4);
+}
+
+#[test]
+fn screenshot_scale_parses_each_mode() {
+    let scale: ScreenshotScale = serde_json::from_str(r#"{"type":"native"}"#).unwrap();
+    assert_eq!(scale, ScreenshotScale::Native);
+    let scale: ScreenshotScale = serde_json::from_str(r#"{"type":"logical"}"#).unwrap();
+    assert_eq!(scale, ScreenshotScale::Logical);
+    let scale: ScreenshotScale =
+        serde_json::from_str(r#"{"type":"maxDimension","maxDimension":1024}"#).unwrap();
+    assert_eq!(
+        scale,
+        ScreenshotScale::MaxDimension {
+            max_dimension: 1024
+        }
+    );
+    assert!(ScreenshotScale::MaxDimension { max_dimension: 0 }
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn retina_captures_shrink_to_points() {
+    let scale = ScreenshotScale::Logical;
+    assert_eq!(scale.size(3024, 1964, 2.0), (1512, 982));
+    assert_eq!(scale.size(1920, 1080, 1.0), (1920, 1080));
+    assert_eq!(scale.size(1920, 1080, 0.0), (1920, 1080));
+    assert_eq!(ScreenshotScale::Native.size(3024, 1964, 2.0), (3024, 1964));
+}
+
+#[test]
+fn max_dimension_bounds_the_longest_side_without_upscaling() {
+    let scale = ScreenshotScale::MaxDimension {
+        max_dimension: 1000,
+    };
+    assert_eq!(scale.size(2000, 1000, 2.0), (1000, 500));
+    assert_eq!(scale.size(1000, 4000, 1.0), (250, 1000));
+    assert_eq!(scale.size(800, 600, 1.0), (800, 600));
+}
+
+#[test]
+fn scaling_resizes_the_image() {
+    let image = image::RgbaImage::new(200, 100);
+    let image = ScreenshotScale::Logical.apply(image, 2.0);
+    assert_eq!(image.dimensions(), (100, 50));
+}
+
+#[test]
+fn screenshot_formats_use_lowercase_names() {
+    let config: ScreenshotConfig =
+        serde_json::from_str(r#"{"format":"webp","quality":60}"#).unwrap();
+    assert_eq!(config.format, ScreenshotFormat::Webp);
+    assert_eq!(config.quality, 60);
+    let config: ScreenshotConfig = serde_json::from_str("{}").unwrap();
+    assert_eq!(config, ScreenshotConfig::default());
+    assert!(ScreenshotConfig {
+        quality: 0,
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+}
+
+#[test]
+fn explicit_format_wins_over_the_settings() {
+    let config = ScreenshotConfig {
+        format: ScreenshotFormat::Webp,
+        quality: 60,
+    };
+    assert_eq!(
+        config.resolve(None, None, None),
+        Ok((ScreenshotFormat::Webp, 60))
+    );
+    assert_eq!(
+        config.resolve(None, Some(true), Some(80)),
+        Ok((ScreenshotFormat::Webp, 80))
+    );
+    assert_eq!(
+        config.resolve(None, Some(false), None),
+        Ok((ScreenshotFormat::Png, 60))
+    );
+    assert_eq!(
+        config.resolve(Some(ScreenshotFormat::Jpeg), Some(false), None),
+        Ok((ScreenshotFormat::Jpeg, 60))
+    );
+    assert!(config.resolve(None, None, Some(101)).is_err());
+}
+
+#[test]
+fn base64_commands_keep_to_jpeg_or_png() {
+    let config = ScreenshotConfig {
+        format: ScreenshotFormat::Webp,
+        quality: 60,
+    };
+    assert_eq!(
+        config.resolve_base64(None, None, None),
+        Ok((ScreenshotFormat::Jpeg, 60))
+    );
+    assert_eq!(
+        config.resolve_base64(None, Some(false), Some(80)),
+        Ok((ScreenshotFormat::Png, 80))
+    );
+    assert_eq!(
+        config.resolve_base64(Some(ScreenshotFormat::Webp), Some(false), None),
+        Ok((ScreenshotFormat::Webp, 60))
+    );
+}
+
+#[test]
+fn each_format_is_encoded() {
+    let image = image::RgbaImage::from_pixel(64, 32, image::Rgba([20, 120, 200, 255]));
+    let png = encode_as(image.clone(), ScreenshotFormat::Png, 75, Some(16)).unwrap();
+    assert_eq!(png[..4], [0x89, b'P', b'N', b'G']);
+    assert_eq!(
+        image::load_from_memory(&png).unwrap().dimensions(),
+        (64, 32)
+    );
+
+    let jpeg = encode_as(image.clone(), ScreenshotFormat::Jpeg, 75, Some(16)).unwrap();
+    assert_eq!(jpeg[..2], [0xFF, 0xD8]);
+    assert_eq!(
+        image::load_from_memory(&jpeg).unwrap().dimensions(),
+        (16, 8)
+    );
+
+    let webp = encode_as(image, ScreenshotFormat::Webp, 75, None).unwrap();
+    assert_eq!(&webp[..4], b"RIFF");
+    assert_eq!(&webp[8..12], b"WEBP");
+}
+
+#[test]
+fn formats_have_mime_types() {
+    assert_eq!(ScreenshotFormat::Png.mime_type(), "image/png");
+    assert_eq!(ScreenshotFormat::Jpeg.mime_type(), "image/jpeg");
+    assert_eq!(ScreenshotFormat::Webp.mime_type(), "image/webp");
+}
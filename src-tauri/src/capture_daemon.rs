@@ -0,0 +1,343 @@
+//! Background capture daemon: `runningbord-cli daemon` run as a launchd
+//! agent, so the ring buffer survives the app being closed or restarted.
+//! The app attaches to it over the daemon's IPC socket (see `daemon_ipc`).
+//!
+//! While attached, `system_audio_get_recent_base64` reads from the daemon
+//! whenever the app isn't capturing itself.
+//!
+//! Installing the agent is macOS only. On other platforms the daemon can be
+//! started by hand and attached to with `capture_daemon_attach`.
+
+use crate::daemon_ipc::{self, Request, Response};
+use crate::headless::DEFAULT_BUFFER_SECONDS;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Name of the CLI binary, expected next to the app executable.
+const CLI_NAME: &str = "runningbord-cli";
+
+#[derive(Default)]
+pub struct CaptureDaemonState {
+    /// IPC endpoint of the attached daemon.
+    endpoint: Mutex<Option<String>>,
+}
+
+impl CaptureDaemonState {
+    pub fn endpoint(&self) -> Option<String> {
+        self.endpoint.lock().ok().and_then(|e| e.clone())
+    }
+
+    fn set_endpoint(&self, endpoint: Option<String>) {
+        if let Ok(mut e) = self.endpoint.lock() {
+            *e = endpoint;
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct CaptureDaemonStatus {
+    /// The launchd agent is installed.
+    pub installed: bool,
+    /// IPC endpoint of the attached daemon.
+    pub endpoint: Option<String>,
+    /// The attached daemon answered.
+    pub reachable: bool,
+    /// The daemon's capture status, as reported by its `status` command.
+    pub capture: Option<serde_json::Value>,
+}
+
+/// launchd label of the agent.
+fn agent_label(app: &AppHandle) -> String {
+    format!("{}.capture-daemon", app.config().identifier)
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// launchd property list that keeps `program daemon` running at login. The
+/// agent is reached only through its IPC socket, which only the user can
+/// open, so it gets no TCP control port.
+#[cfg(any(target_os = "macos", test))]
+fn agent_plist(label: &str, program: &str, socket: &str, buffer_seconds: u32) -> String {
+    let args = [
+        program.to_string(),
+        "daemon".to_string(),
+        "--socket".to_string(),
+        socket.to_string(),
+        "--start".to_string(),
+        buffer_seconds.to_string(),
+    ];
+    let args = args
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", escape_xml(arg)))
+        .collect::<String>();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>ProcessType</key>
+    <string>Interactive</string>
+</dict>
+</plist>
+"#,
+        escape_xml(label),
+        args
+    )
+}
+
+/// Path of the CLI binary shipped next to the app executable.
+fn cli_path() -> Result<std::path::PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let name = if cfg!(windows) {
+        format!("{}.exe", CLI_NAME)
+    } else {
+        CLI_NAME.to_string()
+    };
+    let path = exe.with_file_name(name);
+    if !path.exists() {
+        return Err(format!("{} not found next to the app", CLI_NAME));
+    }
+    Ok(path)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::agent_plist;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn plist_path(label: &str) -> Result<PathBuf, String> {
+        let home = std::env::var_os("HOME").ok_or("HOME is not set")?;
+        Ok(PathBuf::from(home)
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", label)))
+    }
+
+    /// `gui/<uid>`, the launchd domain of the logged-in user.
+    fn gui_domain() -> Result<String, String> {
+        let output = Command::new("id")
+            .arg("-u")
+            .output()
+            .map_err(|e| e.to_string())?;
+        Ok(format!(
+            "gui/{}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        ))
+    }
+
+    fn launchctl(args: &[&str]) -> Result<(), String> {
+        let output = Command::new("launchctl")
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run launchctl: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "launchctl {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_installed(label: &str) -> bool {
+        plist_path(label).is_ok_and(|p| p.exists())
+    }
+
+    pub fn install(
+        label: &str,
+        program: &str,
+        socket: &str,
+        buffer_seconds: u32,
+    ) -> Result<(), String> {
+        let path = plist_path(label)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let domain = gui_domain()?;
+        // Replace an agent loaded from an older plist.
+        let _ = launchctl(&["bootout", &format!("{}/{}", domain, label)]);
+        std::fs::write(&path, agent_plist(label, program, socket, buffer_seconds))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        launchctl(&["bootstrap", &domain, &path.to_string_lossy()])
+    }
+
+    pub fn uninstall(label: &str) -> Result<(), String> {
+        let path = plist_path(label)?;
+        let _ = launchctl(&["bootout", &format!("{}/{}", gui_domain()?, label)]);
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    pub fn is_installed(_label: &str) -> bool {
+        false
+    }
+
+    pub fn install(
+        _label: &str,
+        _program: &str,
+        _socket: &str,
+        _buffer_seconds: u32,
+    ) -> Result<(), String> {
+        Err("The background capture agent is only supported on macOS".to_string())
+    }
+
+    pub fn uninstall(_label: &str) -> Result<(), String> {
+        Err("The background capture agent is only supported on macOS".to_string())
+    }
+}
+
+/// Attach to the installed agent at startup. It may still be starting, so
+/// it isn't contacted until it's used.
+pub fn init(app: &AppHandle) {
+    if !platform::is_installed(&agent_label(app)) {
+        return;
+    }
+    match daemon_ipc::default_endpoint() {
+        Ok(endpoint) => app
+            .state::<CaptureDaemonState>()
+            .set_endpoint(Some(endpoint)),
+        Err(e) => tracing::warn!("Can't attach to the capture daemon: {}", e),
+    }
+}
+
+/// Send `request` to the attached daemon.
+async fn request(app: &AppHandle, request: Request) -> Result<Response, String> {
+    let endpoint = app
+        .state::<CaptureDaemonState>()
+        .endpoint()
+        .ok_or("No capture daemon attached")?;
+    daemon_ipc::request(&endpoint, &request).await
+}
+
+fn unexpected(response: Response) -> String {
+    format!(
+        "Unexpected response from the capture daemon: {:?}",
+        response
+    )
+}
+
+/// The attached daemon's retained audio as base64 OGG/Opus, or `None` if no
+/// daemon is attached.
+pub async fn recent_base64(app: &AppHandle) -> Option<Result<String, String>> {
+    app.state::<CaptureDaemonState>().endpoint()?;
+    Some(match request(app, Request::GetRecent).await {
+        Ok(Response::Recent { audio_base64 }) => Ok(audio_base64),
+        Ok(other) => Err(unexpected(other)),
+        Err(e) => Err(e),
+    })
+}
+
+/// Install and load the launchd agent, then attach to it. Capture starts in
+/// the daemon with `buffer_seconds` (default 120).
+#[tauri::command]
+pub async fn capture_daemon_install(
+    app: AppHandle,
+    buffer_seconds: Option<u32>,
+) -> Result<(), String> {
+    let program = cli_path()?;
+    let label = agent_label(&app);
+    let seconds = buffer_seconds.unwrap_or(DEFAULT_BUFFER_SECONDS);
+    let endpoint = daemon_ipc::default_endpoint()?;
+    let socket = endpoint.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        platform::install(&label, &program.to_string_lossy(), &socket, seconds)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    app.state::<CaptureDaemonState>()
+        .set_endpoint(Some(endpoint));
+    Ok(())
+}
+
+/// Unload and remove the launchd agent. The daemon's buffer goes with it.
+#[tauri::command]
+pub async fn capture_daemon_uninstall(app: AppHandle) -> Result<(), String> {
+    let label = agent_label(&app);
+    tauri::async_runtime::spawn_blocking(move || platform::uninstall(&label))
+        .await
+        .map_err(|e| e.to_string())??;
+    app.state::<CaptureDaemonState>().set_endpoint(None);
+    Ok(())
+}
+
+/// Attach to a daemon already listening at `endpoint` (default
+/// `daemon_ipc::default_endpoint()`).
+#[tauri::command]
+pub async fn capture_daemon_attach(app: AppHandle, endpoint: Option<String>) -> Result<(), String> {
+    let endpoint = match endpoint {
+        Some(endpoint) => endpoint,
+        None => daemon_ipc::default_endpoint()?,
+    };
+    daemon_ipc::request(&endpoint, &Request::Status).await?;
+    app.state::<CaptureDaemonState>()
+        .set_endpoint(Some(endpoint));
+    Ok(())
+}
+
+/// Stop reading from the daemon. It keeps running.
+#[tauri::command]
+pub fn capture_daemon_detach(app: AppHandle) -> Result<(), String> {
+    app.state::<CaptureDaemonState>().set_endpoint(None);
+    Ok(())
+}
+
+/// Start capture in the attached daemon.
+#[tauri::command]
+pub async fn capture_daemon_start(
+    app: AppHandle,
+    buffer_seconds: Option<u32>,
+) -> Result<(), String> {
+    request(&app, Request::Start { buffer_seconds })
+        .await
+        .map(|_| ())
+}
+
+/// Stop capture in the attached daemon. Its buffer is zeroized.
+#[tauri::command]
+pub async fn capture_daemon_stop(app: AppHandle) -> Result<(), String> {
+    request(&app, Request::Stop).await.map(|_| ())
+}
+
+#[tauri::command]
+pub async fn capture_daemon_status(app: AppHandle) -> Result<CaptureDaemonStatus, String> {
+    let label = agent_label(&app);
+    let installed = tauri::async_runtime::spawn_blocking(move || platform::is_installed(&label))
+        .await
+        .map_err(|e| e.to_string())?;
+    let endpoint = app.state::<CaptureDaemonState>().endpoint();
+    let capture = match request(&app, Request::Status).await {
+        Ok(Response::Status { status }) => Some(status),
+        _ => None,
+    };
+    Ok(CaptureDaemonStatus {
+        installed,
+        endpoint,
+        reachable: capture.is_some(),
+        capture,
+    })
+}
+
+#[cfg(test)]
+mod tests;
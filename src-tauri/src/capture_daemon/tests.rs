@@ -0,0 +1,36 @@
+use super::*;
+
+#[test]
+fn agent_plist_runs_the_daemon() {
+    let plist = agent_plist(
+        "com.example.app.capture-daemon",
+        "/Applications/A & B.app/Contents/MacOS/runningbord-cli",
+        "/Users/me/Library/Caches/runningbord-capture.sock",
+        300,
+    );
+    assert!(plist.contains("<string>com.example.app.capture-daemon</string>"));
+    assert!(plist
+        .contains("<string>/Applications/A &amp; B.app/Contents/MacOS/runningbord-cli</string>"));
+    let args: Vec<&str> = plist
+        .lines()
+        .skip_while(|line| !line.contains("<array>"))
+        .skip(1)
+        .take_while(|line| !line.contains("</array>"))
+        .map(|line| {
+            line.trim()
+                .trim_start_matches("<string>")
+                .trim_end_matches("</string>")
+        })
+        .collect();
+    assert_eq!(
+        args[1..],
+        [
+            "daemon",
+            "--socket",
+            "/Users/me/Library/Caches/runningbord-capture.sock",
+            "--start",
+            "300"
+        ]
+    );
+    assert!(plist.contains("<key>KeepAlive</key>\n    <true/>"));
+}
@@ -0,0 +1,508 @@
+//! Clipboard context: the current clipboard text or image for the context
+//! builder, and an optional watcher that keeps the last few copies.
+//!
+//! Text is capped at `max_text_bytes`; images are downscaled to JPEG like
+//! screenshots. History lives in memory only, is zeroized when cleared, and
+//! skips copies made while a privacy-blocklisted app is in the foreground.
+//!
+//! Copies that password managers mark as secret or transient are never
+//! read: the nspasteboard.org types on macOS, the clipboard monitor opt-out
+//! formats on Windows, KDE's password manager hint on Linux (listed with
+//! `wl-paste` or `xclip`, whichever the session has).
+//!
+//! The watcher checks the clipboard's change counter (`changeCount` on
+//! macOS, `GetClipboardSequenceNumber` on Windows) and only reads a copy
+//! when it moves. Linux has no such counter, so there the contents are
+//! read and compared on every poll.
+//!
+//! `clipboard_read_image` pulls just the image (say, a screenshot the user
+//! already took) and normalizes it with the screenshot settings.
+
+use crate::capture::{
+    encode_as, encode_image, screenshot_config, ScreenshotFormat, ScreenshotScale,
+    DEFAULT_MAX_DIMENSION,
+};
+use crate::privacy::frontmost_app_blocked;
+use base64::Engine;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use zeroize::Zeroize;
+
+/// How often the watcher checks for a new copy.
+const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+const DEFAULT_MAX_TEXT_BYTES: usize = 64 * 1024;
+/// Clipboard images larger than this (raw RGBA) are not read at all.
+const MAX_IMAGE_BYTES: usize = 64 * 1024 * 1024;
+const DEFAULT_HISTORY_ENTRIES: usize = 5;
+const MAX_HISTORY_ENTRIES: usize = 20;
+/// Clipboard types that mark a copy as secret or short-lived, for
+/// clipboard watchers to leave alone.
+const CONCEALED_TYPES: &[&str] = &[
+    "org.nspasteboard.ConcealedType",
+    "org.nspasteboard.TransientType",
+    "ExcludeClipboardContentFromMonitorProcessing",
+    "Clipboard Viewer Ignore",
+    "x-kde-passwordManagerHint",
+];
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ClipboardContent {
+    Text {
+        text: String,
+        /// The clipboard held more than `max_text_bytes`.
+        truncated: bool,
+    },
+    Image {
+        /// Base64 JPEG, downscaled like screenshots.
+        image_base64: String,
+        /// Size of the copied image before downscaling.
+        width: u32,
+        height: u32,
+    },
+}
+
+/// A clipboard image ready to attach to an LLM request.
+#[derive(Clone, Serialize)]
+pub struct ClipboardImage {
+    /// Base64, in `mime_type`.
+    pub image_base64: String,
+    pub mime_type: &'static str,
+    /// Size of the copied image before downscaling.
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ClipboardEntry {
+    #[serde(flatten)]
+    pub content: ClipboardContent,
+    pub captured_at_ms: u64,
+}
+
+impl Zeroize for ClipboardEntry {
+    fn zeroize(&mut self) {
+        match &mut self.content {
+            ClipboardContent::Text { text, .. } => text.zeroize(),
+            ClipboardContent::Image { image_base64, .. } => image_base64.zeroize(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ClipboardState {
+    /// Bumped on every start/stop so a superseded watcher thread exits.
+    generation: AtomicU64,
+    /// Newest last.
+    history: Mutex<VecDeque<ClipboardEntry>>,
+}
+
+impl ClipboardState {
+    fn clear_history(&self) {
+        if let Ok(mut history) = self.history.lock() {
+            for mut entry in history.drain(..) {
+                entry.zeroize();
+            }
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The longest prefix of `text` within `max_bytes` that ends on a char
+/// boundary.
+fn truncate_text(mut text: String, max_bytes: usize) -> (String, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let kept = text[..end].to_string();
+    text.zeroize();
+    (kept, true)
+}
+
+/// The clipboard's image, if it holds one.
+fn read_image(
+    clipboard: &mut arboard::Clipboard,
+) -> Result<Option<arboard::ImageData<'static>>, String> {
+    match clipboard.get_image() {
+        Ok(image) if image.bytes.len() > MAX_IMAGE_BYTES => Err(format!(
+            "Clipboard image is too large ({}x{})",
+            image.width, image.height
+        )),
+        Ok(image) => Ok(Some(image)),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(format!("Failed to read clipboard image: {}", e)),
+    }
+}
+
+fn to_rgba(image: arboard::ImageData<'static>) -> Result<image::RgbaImage, String> {
+    image::RgbaImage::from_raw(
+        image.width as u32,
+        image.height as u32,
+        image.bytes.into_owned(),
+    )
+    .ok_or_else(|| "Clipboard image has an unexpected size".to_string())
+}
+
+/// Raw clipboard contents, before capping and encoding.
+enum RawClipboard {
+    Text(String),
+    Image(arboard::ImageData<'static>),
+}
+
+impl RawClipboard {
+    fn read(clipboard: &mut arboard::Clipboard) -> Result<Option<Self>, String> {
+        match clipboard.get_text() {
+            Ok(text) if !text.is_empty() => return Ok(Some(Self::Text(text))),
+            Ok(_) | Err(arboard::Error::ContentNotAvailable) => {}
+            Err(e) => return Err(format!("Failed to read clipboard: {}", e)),
+        }
+        Ok(read_image(clipboard)?.map(Self::Image))
+    }
+
+    /// Drop a copy that won't be kept, wiping it first.
+    fn discard(self) {
+        match self {
+            Self::Text(mut text) => text.zeroize(),
+            Self::Image(image) => image.bytes.into_owned().zeroize(),
+        }
+    }
+
+    /// Cheap fingerprint for spotting a new copy.
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            Self::Text(text) => text.hash(&mut hasher),
+            Self::Image(image) => {
+                (image.width, image.height).hash(&mut hasher);
+                image.bytes.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    fn into_entry(self, max_text_bytes: usize) -> Result<ClipboardEntry, String> {
+        let content = match self {
+            Self::Text(text) => {
+                let (text, truncated) = truncate_text(text, max_text_bytes);
+                ClipboardContent::Text { text, truncated }
+            }
+            Self::Image(image) => {
+                let (width, height) = (image.width as u32, image.height as u32);
+                let jpeg = encode_image(to_rgba(image)?, Some(true), None, None)?;
+                ClipboardContent::Image {
+                    image_base64: base64::engine::general_purpose::STANDARD.encode(&jpeg),
+                    width,
+                    height,
+                }
+            }
+        };
+        Ok(ClipboardEntry {
+            content,
+            captured_at_ms: now_millis(),
+        })
+    }
+}
+
+/// Whether `types` include one of `CONCEALED_TYPES`.
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn has_concealed_type(types: &[String]) -> bool {
+    types.iter().any(|t| CONCEALED_TYPES.contains(&t.trim()))
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::has_concealed_type;
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2_foundation::NSString;
+    use std::ffi::CStr;
+
+    fn pasteboard() -> Option<Retained<AnyObject>> {
+        let class = CStr::from_bytes_with_nul(b"NSPasteboard\0")
+            .ok()
+            .and_then(AnyClass::get)?;
+        unsafe { msg_send![class, generalPasteboard] }
+    }
+
+    pub fn change_count() -> Option<u64> {
+        let pasteboard = pasteboard()?;
+        let count: isize = unsafe { msg_send![&*pasteboard, changeCount] };
+        Some(count as u64)
+    }
+
+    pub fn concealed() -> bool {
+        let Some(pasteboard) = pasteboard() else {
+            return false;
+        };
+        unsafe {
+            let types: Option<Retained<AnyObject>> = msg_send![&*pasteboard, types];
+            let Some(types) = types else {
+                return false;
+            };
+            let count: usize = msg_send![&*types, count];
+            let types: Vec<String> = (0..count)
+                .filter_map(|i| {
+                    let name: Option<Retained<NSString>> = msg_send![&*types, objectAtIndex: i];
+                    name.map(|name| name.to_string())
+                })
+                .collect();
+            has_concealed_type(&types)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::CONCEALED_TYPES;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetClipboardSequenceNumber() -> u32;
+        fn RegisterClipboardFormatW(name: *const u16) -> u32;
+        fn IsClipboardFormatAvailable(format: u32) -> i32;
+    }
+
+    /// `None` if the clipboard can't be seen from this session.
+    pub fn change_count() -> Option<u64> {
+        let count = unsafe { GetClipboardSequenceNumber() };
+        (count != 0).then_some(count as u64)
+    }
+
+    pub fn concealed() -> bool {
+        CONCEALED_TYPES.iter().any(|name| {
+            let name: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+            unsafe {
+                let format = RegisterClipboardFormatW(name.as_ptr());
+                format != 0 && IsClipboardFormatAvailable(format) != 0
+            }
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::has_concealed_type;
+    use std::process::Command;
+
+    pub fn change_count() -> Option<u64> {
+        None
+    }
+
+    /// Types on the clipboard, from whichever tool the session has.
+    fn types() -> Option<Vec<String>> {
+        let output = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            Command::new("wl-paste").arg("--list-types").output()
+        } else {
+            Command::new("xclip")
+                .args(["-selection", "clipboard", "-t", "TARGETS", "-o"])
+                .output()
+        }
+        .ok()?;
+        output.status.success().then(|| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        })
+    }
+
+    pub fn concealed() -> bool {
+        types().is_some_and(|types| has_concealed_type(&types))
+    }
+}
+
+fn open_clipboard() -> Result<arboard::Clipboard, String> {
+    arboard::Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))
+}
+
+/// Poll the clipboard and keep the last `max_entries` copies until the
+/// watcher is stopped or restarted. Emits `clipboard-changed`.
+fn spawn_watcher(app: AppHandle, generation: u64, max_entries: usize) {
+    thread::spawn(move || {
+        let state = app.state::<ClipboardState>();
+        let mut clipboard = match open_clipboard() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                tracing::warn!("Clipboard watcher not started: {}", e);
+                return;
+            }
+        };
+        // What was already on the clipboard isn't a new copy.
+        let mut last_count = platform::change_count();
+        let mut last = RawClipboard::read(&mut clipboard)
+            .ok()
+            .flatten()
+            .map(|raw| {
+                let fingerprint = raw.fingerprint();
+                raw.discard();
+                fingerprint
+            });
+        while state.generation.load(Ordering::SeqCst) == generation {
+            thread::sleep(POLL_INTERVAL);
+            // With a change counter, nothing is read until it moves.
+            let counted = match platform::change_count() {
+                Some(count) if last_count == Some(count) => continue,
+                Some(count) => {
+                    last_count = Some(count);
+                    true
+                }
+                None => false,
+            };
+            // Password managers' copies aren't even read.
+            if counted && platform::concealed() {
+                continue;
+            }
+            let raw = match RawClipboard::read(&mut clipboard) {
+                Ok(Some(raw)) => raw,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::debug!("Clipboard watcher: {}", e);
+                    continue;
+                }
+            };
+            if !counted {
+                let fingerprint = raw.fingerprint();
+                if last == Some(fingerprint) {
+                    raw.discard();
+                    continue;
+                }
+                last = Some(fingerprint);
+            }
+            if (!counted && platform::concealed()) || frontmost_app_blocked(&app) {
+                raw.discard();
+                continue;
+            }
+            let entry = match raw.into_entry(DEFAULT_MAX_TEXT_BYTES) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::debug!("Clipboard watcher: {}", e);
+                    continue;
+                }
+            };
+            if state.generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            if let Ok(mut history) = state.history.lock() {
+                history.push_back(entry.clone());
+                while history.len() > max_entries {
+                    if let Some(mut old) = history.pop_front() {
+                        old.zeroize();
+                    }
+                }
+            }
+            let _ = app.emit("clipboard-changed", entry);
+        }
+    });
+}
+
+/// Read the clipboard now: text (capped at `max_text_bytes`, 64 KiB by
+/// default) if there is any, otherwise an image. `None` if it's empty or
+/// marked secret.
+#[tauri::command]
+pub async fn clipboard_read(
+    max_text_bytes: Option<usize>,
+) -> Result<Option<ClipboardEntry>, String> {
+    let max_text_bytes = max_text_bytes.unwrap_or(DEFAULT_MAX_TEXT_BYTES);
+    tauri::async_runtime::spawn_blocking(move || {
+        if platform::concealed() {
+            return Ok(None);
+        }
+        RawClipboard::read(&mut open_clipboard()?)?
+            .map(|raw| raw.into_entry(max_text_bytes))
+            .transpose()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The image on the clipboard, even if it also holds text, scaled to fit
+/// `max_dimension` (1600 by default) and encoded in `format` at `quality`
+/// (the screenshot settings by default). `None` if there's no image or it's
+/// marked secret.
+#[tauri::command]
+pub async fn clipboard_read_image(
+    app: AppHandle,
+    format: Option<ScreenshotFormat>,
+    quality: Option<u8>,
+    max_dimension: Option<u32>,
+) -> Result<Option<ClipboardImage>, String> {
+    let (format, quality) = screenshot_config(&app)?.resolve(format, None, quality)?;
+    let fit = ScreenshotScale::MaxDimension {
+        max_dimension: max_dimension.unwrap_or(DEFAULT_MAX_DIMENSION),
+    };
+    fit.validate()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if platform::concealed() {
+            return Ok(None);
+        }
+        let Some(image) = read_image(&mut open_clipboard()?)? else {
+            return Ok(None);
+        };
+        let (width, height) = (image.width as u32, image.height as u32);
+        let rgba = fit.apply(to_rgba(image)?, 1.0);
+        let encoded = encode_as(rgba, format, quality, None)?;
+        Ok(Some(ClipboardImage {
+            image_base64: base64::engine::general_purpose::STANDARD.encode(&encoded),
+            mime_type: format.mime_type(),
+            width,
+            height,
+        }))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Start keeping the last `max_entries` (default 5, at most 20) clipboard
+/// copies. Restarting keeps the history collected so far.
+#[tauri::command]
+pub fn clipboard_watch_start(app: AppHandle, max_entries: Option<usize>) -> Result<(), String> {
+    let max_entries = max_entries.unwrap_or(DEFAULT_HISTORY_ENTRIES);
+    if !(1..=MAX_HISTORY_ENTRIES).contains(&max_entries) {
+        return Err(format!(
+            "max_entries must be between 1 and {}, got {}",
+            MAX_HISTORY_ENTRIES, max_entries
+        ));
+    }
+    let state = app.state::<ClipboardState>();
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    spawn_watcher(app.clone(), generation, max_entries);
+    Ok(())
+}
+
+/// Stop watching and wipe the kept copies.
+#[tauri::command]
+pub fn clipboard_watch_stop(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<ClipboardState>();
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    state.clear_history();
+    Ok(())
+}
+
+/// Copies kept by the watcher, oldest first.
+#[tauri::command]
+pub fn clipboard_history(app: AppHandle) -> Result<Vec<ClipboardEntry>, String> {
+    let state = app.state::<ClipboardState>();
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    Ok(history.iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests;
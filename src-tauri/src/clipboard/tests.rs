@@ -0,0 +1,50 @@
+use super::*;
+
+#[test]
+fn text_is_truncated_on_a_char_boundary() {
+    assert_eq!(
+        truncate_text("hello".to_string(), 5),
+        ("hello".to_string(), false)
+    );
+    assert_eq!(
+        truncate_text("hello".to_string(), 3),
+        ("hel".to_string(), true)
+    );
+    // "é" is two bytes; cutting through it keeps the char before.
+    assert_eq!(
+        truncate_text("caé".to_string(), 3),
+        ("ca".to_string(), true)
+    );
+    assert_eq!(truncate_text("é".to_string(), 1), (String::new(), true));
+}
+
+#[test]
+fn clipboard_images_must_match_their_size() {
+    let image = arboard::ImageData {
+        width: 2,
+        height: 1,
+        bytes: vec![255; 8].into(),
+    };
+    assert_eq!(to_rgba(image).unwrap().dimensions(), (2, 1));
+    let short = arboard::ImageData {
+        width: 2,
+        height: 2,
+        bytes: vec![255; 8].into(),
+    };
+    assert!(to_rgba(short).is_err());
+}
+
+#[test]
+fn password_manager_markers_conceal_a_copy() {
+    let types = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    assert!(has_concealed_type(&types(&[
+        "public.utf8-plain-text",
+        "org.nspasteboard.ConcealedType",
+    ])));
+    assert!(has_concealed_type(&types(&[
+        "text/plain",
+        "x-kde-passwordManagerHint"
+    ])));
+    assert!(!has_concealed_type(&types(&["TARGETS", "UTF8_STRING"])));
+    assert!(!has_concealed_type(&[]));
+}
@@ -0,0 +1,322 @@
+//! Cloud sync for saved recordings: every history entry's audio file and
+//! transcript are uploaded to the configured backend, and the outcome is
+//! tracked per entry in the history database (failed uploads are retried
+//! on the next run). Entries are uploaded once; later transcript edits
+//! aren't re-sent.
+//!
+//! Off by default. Backends implement `SyncBackend`; WebDAV is the first.
+//! Passwords live in the credential store (see `secrets`), never in the
+//! config.
+
+use crate::history::{self, HistoryEntry, SyncRecord};
+use crate::network;
+use crate::secrets;
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the background task looks for new recordings.
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Secret holding the WebDAV password.
+pub const WEBDAV_PASSWORD_SECRET: &str = "sync.webdav.password";
+
+/// Destination for uploads.
+pub trait SyncBackend: Send + Sync {
+    /// Stable name, used to track sync state per backend.
+    fn name(&self) -> &'static str;
+    /// Store `bytes` as `file_name` and return its remote URL.
+    fn upload(
+        &self,
+        file_name: String,
+        bytes: Vec<u8>,
+        content_type: &'static str,
+    ) -> BoxFuture<'static, Result<String, String>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SyncBackendConfig {
+    /// Uploads into the collection at `url` with HTTP basic auth; the
+    /// password is the `sync.webdav.password` secret.
+    #[serde(rename = "webdav")]
+    WebDav { url: String, username: String },
+}
+
+impl SyncBackendConfig {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::WebDav { url, .. } => {
+                let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+                match parsed.scheme() {
+                    "https" => Ok(()),
+                    // The password goes out with every request, so plain
+                    // http is only allowed to a server on this machine.
+                    "http" if is_loopback(&parsed) => Ok(()),
+                    "http" => Err(
+                        "WebDAV URL must be https unless the server is on this machine".to_string(),
+                    ),
+                    _ => Err("WebDAV URL must be http(s)".to_string()),
+                }
+            }
+        }
+    }
+
+    /// The backend, sending its requests through `client`.
+    fn build(&self, client: reqwest::Client) -> Result<Arc<dyn SyncBackend>, String> {
+        match self {
+            Self::WebDav { url, username } => Ok(Arc::new(WebDavBackend {
+                client,
+                url: url.clone(),
+                username: username.clone(),
+                password: secrets::require(WEBDAV_PASSWORD_SECRET)?,
+            })),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncConfig {
+    pub enabled: bool,
+    pub backend: Option<SyncBackendConfig>,
+}
+
+#[derive(Default)]
+pub struct CloudSyncState {
+    config: Mutex<SyncConfig>,
+    /// Set while a sync runs, so the timer and `cloud_sync_run_now` don't
+    /// upload the same entry twice.
+    running: AtomicBool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub uploaded: usize,
+    pub failed: usize,
+}
+
+/// Whether `url` points at this machine: `localhost` or a loopback address.
+fn is_loopback(url: &reqwest::Url) -> bool {
+    match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    }
+}
+
+/// `url` with `file_name` appended as one percent-encoded path segment.
+fn join_url(url: &str, file_name: &str) -> Result<String, String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    url.path_segments_mut()
+        .map_err(|_| "URL cannot take a path".to_string())?
+        .pop_if_empty()
+        .push(file_name);
+    Ok(url.to_string())
+}
+
+/// Remote names for an entry: its audio file and transcript, prefixed with
+/// the entry id so names from different days never collide.
+fn remote_names(entry: &HistoryEntry) -> (String, String) {
+    let path = Path::new(&entry.path);
+    let file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "audio".to_string());
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "audio".to_string());
+    (
+        format!("{}-{}", entry.id, file),
+        format!("{}-{}.txt", entry.id, stem),
+    )
+}
+
+fn content_type(path: &str) -> &'static str {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("ogg") => "audio/ogg",
+        Some(ext) if ext.eq_ignore_ascii_case("wav") => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
+
+pub struct WebDavBackend {
+    client: reqwest::Client,
+    url: String,
+    username: String,
+    password: String,
+}
+
+impl SyncBackend for WebDavBackend {
+    fn name(&self) -> &'static str {
+        "webdav"
+    }
+
+    fn upload(
+        &self,
+        file_name: String,
+        bytes: Vec<u8>,
+        content_type: &'static str,
+    ) -> BoxFuture<'static, Result<String, String>> {
+        let client = self.client.clone();
+        let collection = self.url.clone();
+        let username = self.username.clone();
+        let password = self.password.clone();
+        Box::pin(async move {
+            let target = join_url(&collection, &file_name)?;
+            // Create the collection; 405 means it already exists.
+            let mkcol = reqwest::Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+            let response = client
+                .request(mkcol, &collection)
+                .basic_auth(&username, Some(&password))
+                .send()
+                .await
+                .map_err(|e| format!("WebDAV request failed: {}", e))?;
+            let status = response.status();
+            if !status.is_success() && status != reqwest::StatusCode::METHOD_NOT_ALLOWED {
+                return Err(format!("WebDAV MKCOL failed: {}", status));
+            }
+            let response = client
+                .put(&target)
+                .basic_auth(&username, Some(&password))
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .body(bytes)
+                .send()
+                .await
+                .map_err(|e| format!("WebDAV upload failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("WebDAV upload failed: {}", response.status()));
+            }
+            Ok(target)
+        })
+    }
+}
+
+/// Upload one entry's audio and transcript. Returns the audio's URL.
+async fn upload_entry(backend: &dyn SyncBackend, entry: &HistoryEntry) -> Result<String, String> {
+    let (audio_name, transcript_name) = remote_names(entry);
+    let audio = tokio::fs::read(&entry.path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", entry.path, e))?;
+    let url = backend
+        .upload(audio_name, audio, content_type(&entry.path))
+        .await?;
+    if let Some(transcript) = entry.transcript.as_deref().filter(|t| !t.is_empty()) {
+        backend
+            .upload(
+                transcript_name,
+                transcript.as_bytes().to_vec(),
+                "text/plain; charset=utf-8",
+            )
+            .await?;
+    }
+    Ok(url)
+}
+
+/// Upload everything not yet on the configured backend.
+async fn sync(app: &AppHandle) -> Result<SyncReport, String> {
+    let state = app.state::<CloudSyncState>();
+    let config = state.config.lock().map_err(|e| e.to_string())?.clone();
+    let Some(backend) = config.backend.filter(|_| config.enabled) else {
+        return Ok(SyncReport::default());
+    };
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("A sync is already running".to_string());
+    }
+    let result = async {
+        let client = network::client(app, None)?;
+        let backend = tauri::async_runtime::spawn_blocking(move || backend.build(client))
+            .await
+            .map_err(|e| e.to_string())??;
+        let mut report = SyncReport::default();
+        for entry in history::unsynced(app, backend.name()).await? {
+            let outcome = upload_entry(backend.as_ref(), &entry).await;
+            if let Err(e) = &outcome {
+                tracing::warn!("Sync of recording {} failed: {}", entry.id, e);
+                report.failed += 1;
+            } else {
+                report.uploaded += 1;
+            }
+            history::set_sync_state(
+                app,
+                entry.id,
+                backend.name(),
+                outcome.as_deref().map_err(String::as_str),
+            )
+            .await?;
+        }
+        Ok(report)
+    }
+    .await;
+    state.running.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Start the periodic sync. Called once at startup; it does nothing until
+/// sync is enabled.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SYNC_INTERVAL);
+        loop {
+            interval.tick().await;
+            match sync(&app).await {
+                Ok(report) if report.uploaded + report.failed > 0 => tracing::info!(
+                    "Cloud sync uploaded {} recordings, {} failed",
+                    report.uploaded,
+                    report.failed
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Cloud sync failed: {}", e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn cloud_sync_set_config(app: AppHandle, config: SyncConfig) -> Result<(), String> {
+    if let Some(backend) = &config.backend {
+        backend.validate()?;
+    }
+    if config.enabled && config.backend.is_none() {
+        return Err("Choose a sync backend before enabling sync".to_string());
+    }
+    *app.state::<CloudSyncState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cloud_sync_get_config(app: AppHandle) -> Result<SyncConfig, String> {
+    Ok(app
+        .state::<CloudSyncState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+/// Sync now instead of waiting for the next run.
+#[tauri::command]
+pub async fn cloud_sync_run_now(app: AppHandle) -> Result<SyncReport, String> {
+    sync(&app).await
+}
+
+/// Upload state of every entry that has been synced or tried.
+#[tauri::command]
+pub async fn cloud_sync_status(app: AppHandle) -> Result<Vec<SyncRecord>, String> {
+    history::sync_states(&app).await
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,55 @@
+use super::*;
+
+fn entry(id: i64, path: &str) -> HistoryEntry {
+    HistoryEntry {
+        id,
+        path: path.to_string(),
+        duration_seconds: None,
+        transcript: None,
+        tags: Vec::new(),
+        screenshot_path: None,
+        created_at: "2025-01-01 00:00:00".to_string(),
+        snippet: None,
+    }
+}
+
+#[test]
+fn remote_names_are_prefixed_with_the_entry_id() {
+    let (audio, transcript) = remote_names(&entry(7, "/tmp/rec/recording-2025.ogg"));
+    assert_eq!(audio, "7-recording-2025.ogg");
+    assert_eq!(transcript, "7-recording-2025.txt");
+}
+
+#[test]
+fn join_url_appends_one_encoded_segment() {
+    assert_eq!(
+        join_url("https://dav.example.com/files/rec/", "7-a b.ogg").unwrap(),
+        "https://dav.example.com/files/rec/7-a%20b.ogg"
+    );
+    assert_eq!(
+        join_url("https://dav.example.com/files/rec", "7-a/b.ogg").unwrap(),
+        "https://dav.example.com/files/rec/7-a%2Fb.ogg"
+    );
+}
+
+#[test]
+fn webdav_needs_https_off_this_machine() {
+    let webdav = |url: &str| SyncBackendConfig::WebDav {
+        url: url.to_string(),
+        username: "me".to_string(),
+    };
+    assert!(webdav("https://dav.example.com/rec/").validate().is_ok());
+    assert!(webdav("http://dav.example.com/rec/").validate().is_err());
+    assert!(webdav("http://192.168.1.2/rec/").validate().is_err());
+    assert!(webdav("http://localhost:8080/rec/").validate().is_ok());
+    assert!(webdav("http://127.0.0.1/rec/").validate().is_ok());
+    assert!(webdav("http://[::1]/rec/").validate().is_ok());
+    assert!(webdav("ftp://localhost/rec/").validate().is_err());
+}
+
+#[test]
+fn content_type_follows_the_extension() {
+    assert_eq!(content_type("a.OGG"), "audio/ogg");
+    assert_eq!(content_type("a.wav"), "audio/wav");
+    assert_eq!(content_type("a"), "application/octet-stream");
+}
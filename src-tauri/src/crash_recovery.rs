@@ -0,0 +1,182 @@
+//! Crash-time audio snapshot: a panic hook writes the last N seconds of the
+//! capture buffer to `<app data>/recovery/` as WAV, so a crash right after
+//! an important call doesn't lose the audio.
+//!
+//! Off until `crash_recovery_set_seconds` is called. Encrypted buffers are
+//! never written out.
+
+use crate::crash_report;
+use crate::system_audio::SystemAudioState;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// Longest snapshot, matching the longest capture buffer.
+const MAX_RECOVERY_SECONDS: u32 = 300;
+/// Older recovery files beyond this many are deleted.
+const MAX_RECOVERY_FILES: usize = 5;
+const FILE_PREFIX: &str = "crash-";
+const FILE_SUFFIX: &str = ".wav";
+
+/// Set while the hook is writing, so a panic inside it doesn't recurse.
+static IN_HOOK: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+pub struct CrashRecoveryState {
+    /// Seconds to save on a panic; 0 disables. Shared with the hook.
+    seconds: Arc<AtomicU32>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RecoveryFile {
+    pub name: String,
+    pub path: String,
+    pub bytes: u64,
+    /// When the crash happened, in ms since the Unix epoch.
+    pub saved_at_ms: u64,
+}
+
+fn recovery_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("recovery"))
+}
+
+fn file_name(saved_at_ms: u64) -> String {
+    format!("{}{}{}", FILE_PREFIX, saved_at_ms, FILE_SUFFIX)
+}
+
+/// Crash time encoded in a recovery file name, or `None` for other files.
+fn parse_file_name(name: &str) -> Option<u64> {
+    name.strip_prefix(FILE_PREFIX)?
+        .strip_suffix(FILE_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// Recovery files in `dir` as `(saved_at_ms, name)`, newest first.
+fn recovery_files(dir: &Path) -> Vec<(u64, String)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(u64, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            Some((parse_file_name(&name)?, name))
+        })
+        .collect();
+    files.sort_by(|a, b| b.cmp(a));
+    files
+}
+
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(bytes)
+}
+
+/// Save `wav` into `dir` and drop the oldest files beyond
+/// `MAX_RECOVERY_FILES`.
+fn write_snapshot(dir: &Path, wav: &[u8]) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let path = dir.join(file_name(now_ms));
+    write_private(&path, wav).map_err(|e| e.to_string())?;
+    for (_, name) in recovery_files(dir).into_iter().skip(MAX_RECOVERY_FILES) {
+        let _ = fs::remove_file(dir.join(name));
+    }
+    Ok(path)
+}
+
+/// Install the panic hook. The previous hook still runs afterwards.
+pub fn install(app: &AppHandle) {
+    let dir = match recovery_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Crash recovery disabled: {}", e);
+            return;
+        }
+    };
+    let seconds = app.state::<CrashRecoveryState>().seconds.clone();
+    let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let seconds = seconds.load(Ordering::Relaxed);
+        let expected = crash_report::panic_expected();
+        if seconds > 0 && !expected && !IN_HOOK.swap(true, Ordering::SeqCst) {
+            if let Some(wav) = audio.crash_snapshot_wav(seconds) {
+                match write_snapshot(&dir, &wav) {
+                    Ok(path) => eprintln!("Saved recent audio to {}", path.display()),
+                    Err(e) => eprintln!("Failed to save recent audio: {}", e),
+                }
+            }
+            IN_HOOK.store(false, Ordering::SeqCst);
+        }
+        previous(info);
+    }));
+}
+
+/// Save the last `seconds` (at most 300) of audio if the app panics; 0
+/// turns it off.
+#[tauri::command]
+pub fn crash_recovery_set_seconds(app: AppHandle, seconds: u32) -> Result<(), String> {
+    app.state::<CrashRecoveryState>()
+        .seconds
+        .store(seconds.min(MAX_RECOVERY_SECONDS), Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn crash_recovery_get_seconds(app: AppHandle) -> Result<u32, String> {
+    Ok(app
+        .state::<CrashRecoveryState>()
+        .seconds
+        .load(Ordering::Relaxed))
+}
+
+/// Audio saved by earlier crashes, newest first.
+#[tauri::command]
+pub fn crash_recovery_list(app: AppHandle) -> Result<Vec<RecoveryFile>, String> {
+    let dir = recovery_dir(&app)?;
+    Ok(recovery_files(&dir)
+        .into_iter()
+        .map(|(saved_at_ms, name)| {
+            let path = dir.join(&name);
+            RecoveryFile {
+                bytes: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                path: path.to_string_lossy().to_string(),
+                name,
+                saved_at_ms,
+            }
+        })
+        .collect())
+}
+
+/// Delete a recovery file by name, as listed by `crash_recovery_list`.
+#[tauri::command]
+pub fn crash_recovery_delete(app: AppHandle, name: String) -> Result<(), String> {
+    if parse_file_name(&name).is_none() {
+        return Err(format!("Not a recovery file: {}", name));
+    }
+    let path = recovery_dir(&app)?.join(&name);
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn file_names_round_trip() {
+    let name = file_name(1_760_000_000_123);
+    assert_eq!(name, "crash-1760000000123.wav");
+    assert_eq!(parse_file_name(&name), Some(1_760_000_000_123));
+    assert_eq!(parse_file_name("crash-../x.wav"), None);
+    assert_eq!(parse_file_name("notes.txt"), None);
+}
+
+#[test]
+fn snapshots_are_written_and_pruned() {
+    let dir = std::env::temp_dir().join(format!("runningbord-recovery-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for ms in 1..=MAX_RECOVERY_FILES as u64 {
+        fs::write(dir.join(file_name(ms)), b"old").unwrap();
+    }
+    fs::write(dir.join("other.txt"), b"keep").unwrap();
+
+    let path = write_snapshot(&dir, b"RIFF").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"RIFF");
+
+    let files = recovery_files(&dir);
+    assert_eq!(files.len(), MAX_RECOVERY_FILES);
+    assert_eq!(dir.join(&files[0].1), path);
+    // The oldest was dropped; unrelated files are left alone.
+    assert!(!dir.join(file_name(1)).exists());
+    assert!(dir.join("other.txt").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
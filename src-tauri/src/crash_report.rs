@@ -0,0 +1,259 @@
+//! Backend panic and error reporting. Panics (from the panic hook) and
+//! `tracing::error!` events (from a tracing layer) are emitted to the
+//! frontend as `backend-error` and appended to `<app data>/crash.log`, one
+//! JSON report per line, so users can attach them to bug reports.
+//!
+//! Code that recovers from a panic on purpose runs it through
+//! `catch_expected_panic`, which the panic hooks here and in
+//! `crash_recovery` leave alone.
+
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+/// The log is rotated to `crash.log.1` past this size.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const LOG_NAME: &str = "crash.log";
+
+thread_local! {
+    /// Set while a report is being written, so errors raised by the
+    /// reporting itself aren't reported again.
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+    /// Set while running code whose panics are caught and handled.
+    static EXPECTED_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f`, catching a panic like `catch_unwind` but without it being
+/// reported as a crash. For third-party code known to panic on bad input.
+pub(crate) fn catch_expected_panic<T>(
+    f: impl FnOnce() -> T + std::panic::UnwindSafe,
+) -> std::thread::Result<T> {
+    let outer = EXPECTED_PANIC.with(|expected| expected.replace(true));
+    let result = std::panic::catch_unwind(f);
+    EXPECTED_PANIC.with(|expected| expected.set(outer));
+    result
+}
+
+/// Whether a panic on this thread is caught by `catch_expected_panic`.
+pub(crate) fn panic_expected() -> bool {
+    EXPECTED_PANIC.with(Cell::get)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    Panic,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub kind: ReportKind,
+    pub message: String,
+    /// `file:line` of the panic.
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub thread: Option<String>,
+    /// Module that logged the error.
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub backtrace: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub time_ms: u64,
+    pub app_version: String,
+}
+
+impl ErrorReport {
+    fn new(kind: ReportKind, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            location: None,
+            thread: None,
+            target: None,
+            backtrace: None,
+            time_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+pub(crate) fn log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join(LOG_NAME))
+}
+
+/// Append `report` to the log at `path`, rotating it first if it's full.
+fn append_report(path: &Path, report: &ErrorReport) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    if fs::metadata(path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+        let _ = fs::rename(path, path.with_extension("log.1"));
+    }
+    let mut line = serde_json::to_string(report).map_err(|e| e.to_string())?;
+    line.push('\n');
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| e.to_string())
+}
+
+/// The last `limit` reports in the log at `path`, newest first. Lines that
+/// don't parse are skipped.
+fn read_reports(path: &Path, limit: usize) -> Vec<ErrorReport> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+/// Persist and emit `report`. Safe to call from the panic hook.
+fn report(app: &AppHandle, report: ErrorReport) {
+    // `try_with`: the hook can run while thread locals are being torn down.
+    if REPORTING.try_with(|r| r.replace(true)).unwrap_or(true) {
+        return;
+    }
+    match log_path(app) {
+        Ok(path) => {
+            if let Err(e) = append_report(&path, &report) {
+                eprintln!("Failed to write crash log: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to write crash log: {}", e),
+    }
+    let _ = app.emit("backend-error", &report);
+    let _ = REPORTING.try_with(|r| r.set(false));
+}
+
+/// Text of a panic payload.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+/// Collects an event's `message` and its other fields as `name=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.push(format!("{}={}", field.name(), value));
+        }
+    }
+}
+
+/// Reports every `tracing::error!` event.
+struct ErrorCaptureLayer {
+    app: AppHandle,
+}
+
+impl<S: Subscriber> Layer<S> for ErrorCaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let mut message = visitor.message;
+        if !visitor.fields.is_empty() {
+            message = format!("{} {}", message, visitor.fields.join(" "))
+                .trim()
+                .to_string();
+        }
+        let mut error = ErrorReport::new(ReportKind::Error, message);
+        error.target = Some(event.metadata().target().to_string());
+        error.location = event
+            .metadata()
+            .file()
+            .zip(event.metadata().line())
+            .map(|(file, line)| format!("{}:{}", file, line));
+        report(&self.app, error);
+    }
+}
+
+/// Install the panic hook and the error-capture tracing layer. The previous
+/// panic hook still runs afterwards.
+pub fn install(app: &AppHandle) {
+    let hook_app = app.clone();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !panic_expected() {
+            let mut panic = ErrorReport::new(ReportKind::Panic, panic_message(info.payload()));
+            panic.location = info
+                .location()
+                .map(|l| format!("{}:{}", l.file(), l.line()));
+            panic.thread = std::thread::current().name().map(str::to_string);
+            panic.backtrace = Some(std::backtrace::Backtrace::force_capture().to_string());
+            report(&hook_app, panic);
+        }
+        previous(info);
+    }));
+
+    let subscriber = tracing_subscriber::registry().with(ErrorCaptureLayer { app: app.clone() });
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        eprintln!("A tracing subscriber is already set; backend errors won't be reported");
+    }
+}
+
+/// Reports from the crash log, newest first (at most `limit`, default 100).
+#[tauri::command]
+pub fn crash_log_get(app: AppHandle, limit: Option<usize>) -> Result<Vec<ErrorReport>, String> {
+    Ok(read_reports(&log_path(&app)?, limit.unwrap_or(100)))
+}
+
+#[tauri::command]
+pub fn crash_log_clear(app: AppHandle) -> Result<(), String> {
+    let path = log_path(&app)?;
+    for path in [path.with_extension("log.1"), path] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete {}: {}", path.display(), e)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,67 @@
+use super::*;
+
+fn temp_log(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("runningbord-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir.join(LOG_NAME)
+}
+
+#[test]
+fn reports_are_read_back_newest_first() {
+    let path = temp_log("crash-log");
+    for message in ["first", "second", "third"] {
+        append_report(&path, &ErrorReport::new(ReportKind::Error, message.into())).unwrap();
+    }
+    let mut panic = ErrorReport::new(ReportKind::Panic, "boom".into());
+    panic.location = Some("src/lib.rs:1".into());
+    append_report(&path, &panic).unwrap();
+    // A torn line from a crash mid-write is skipped.
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(b"{\"kind\":\"pan")
+        .unwrap();
+
+    let reports = read_reports(&path, 3);
+    assert_eq!(reports.len(), 3);
+    assert_eq!(reports[0], panic);
+    assert_eq!(reports[1].message, "third");
+    assert_eq!(reports[2].message, "second");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn full_log_is_rotated() {
+    let path = temp_log("crash-rotate");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, vec![b'\n'; MAX_LOG_BYTES as usize]).unwrap();
+    append_report(&path, &ErrorReport::new(ReportKind::Error, "fresh".into())).unwrap();
+    assert_eq!(read_reports(&path, 10).len(), 1);
+    assert_eq!(
+        fs::metadata(path.with_extension("log.1")).unwrap().len(),
+        MAX_LOG_BYTES
+    );
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn panic_payloads_are_read() {
+    assert_eq!(panic_message(&"static"), "static");
+    assert_eq!(panic_message(&String::from("owned")), "owned");
+    assert_eq!(panic_message(&42), "Box<dyn Any>");
+}
+
+#[test]
+fn expected_panics_are_caught_and_flagged() {
+    assert!(!panic_expected());
+    let result = catch_expected_panic(|| {
+        assert!(panic_expected());
+        // Nested calls keep the flag until the outermost returns.
+        assert_eq!(catch_expected_panic(|| 1).ok(), Some(1));
+        assert!(panic_expected());
+        panic!("malformed input")
+    });
+    assert!(result.is_err());
+    assert!(!panic_expected());
+}
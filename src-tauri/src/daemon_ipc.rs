@@ -0,0 +1,326 @@
+//! IPC protocol between the app and the capture daemon, over a unix domain
+//! socket (a named pipe on Windows).
+//!
+//! Every message is one frame: a 4-byte big-endian payload length followed
+//! by a JSON object carrying the protocol `version` and a `type` tag:
+//!
+//! ```text
+//! -> {"version":1,"type":"start","buffer_seconds":120}
+//! <- {"version":1,"type":"ok"}
+//! -> {"version":1,"type":"get_recent"}
+//! <- {"version":1,"type":"recent","audio_base64":"T2dn..."}
+//! ```
+//!
+//! A connection carries any number of request/response pairs in order.
+//! `PROTOCOL_VERSION` is bumped on incompatible changes; both sides refuse
+//! messages of another version. Optional fields can be added without a bump.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Largest payload either side accepts. Five minutes of OGG/Opus in base64
+/// is a few MB.
+pub const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Start capture; without `buffer_seconds` the length is picked from
+    /// the machine's memory.
+    Start {
+        #[serde(default)]
+        buffer_seconds: Option<u32>,
+    },
+    Stop,
+    /// The retained audio as base64 OGG/Opus.
+    GetRecent,
+    Status,
+    /// Write the retained audio as OGG/Opus to the new file `name` in the
+    /// daemon's recordings folder.
+    Dump {
+        name: String,
+    },
+    /// Stop capture and exit.
+    Shutdown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Recent {
+        audio_base64: String,
+    },
+    /// The daemon's `SystemAudioStatus`.
+    Status {
+        status: serde_json::Value,
+    },
+    /// Where `Dump` wrote the audio.
+    Saved {
+        path: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u32,
+    #[serde(flatten)]
+    body: T,
+}
+
+#[derive(Deserialize)]
+struct VersionOnly {
+    version: u32,
+}
+
+/// Frame `body` with the current protocol version.
+pub fn encode<T: Serialize>(body: &T) -> Result<Vec<u8>, String> {
+    let payload = serde_json::to_vec(&Envelope {
+        version: PROTOCOL_VERSION,
+        body,
+    })
+    .map_err(|e| e.to_string())?;
+    if payload.len() > MAX_FRAME_BYTES {
+        return Err(format!("Message too large ({} bytes)", payload.len()));
+    }
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Parse a frame payload, refusing other protocol versions.
+pub fn decode<T: DeserializeOwned>(payload: &[u8]) -> Result<T, String> {
+    let version = serde_json::from_slice::<VersionOnly>(payload)
+        .map_err(|e| format!("Malformed message: {}", e))?
+        .version;
+    if version != PROTOCOL_VERSION {
+        return Err(format!(
+            "Unsupported protocol version {} (expected {})",
+            version, PROTOCOL_VERSION
+        ));
+    }
+    serde_json::from_slice::<Envelope<T>>(payload)
+        .map(|envelope| envelope.body)
+        .map_err(|e| format!("Malformed message: {}", e))
+}
+
+/// Read one frame payload. `None` if the peer closed the connection between
+/// frames.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Vec<u8>>, String> {
+    let mut header = [0u8; 4];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    }
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(format!("Frame too large ({} bytes)", len));
+    }
+    let mut payload = vec![0u8; len];
+    reader
+        .read_exact(&mut payload)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(payload))
+}
+
+pub async fn write_message<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    body: &T,
+) -> Result<(), String> {
+    writer
+        .write_all(&encode(body)?)
+        .await
+        .map_err(|e| e.to_string())?;
+    writer.flush().await.map_err(|e| e.to_string())
+}
+
+/// Send `request` to the daemon at `endpoint` and wait for its response.
+/// An `Error` response is returned as `Err`.
+pub async fn request(endpoint: &str, request: &Request) -> Result<Response, String> {
+    let mut stream = platform::connect(endpoint).await?;
+    write_message(&mut stream, request).await?;
+    let payload = read_frame(&mut stream)
+        .await?
+        .ok_or_else(|| "Daemon closed the connection".to_string())?;
+    match decode::<Response>(&payload)? {
+        Response::Error { message } => Err(message),
+        response => Ok(response),
+    }
+}
+
+/// Socket path (pipe name on Windows) the daemon listens on by default.
+pub fn default_endpoint() -> Result<String, String> {
+    platform::default_endpoint()
+}
+
+/// Per-user directory for the daemon's private files, created if missing.
+/// On unix only its owner can enter it.
+pub fn runtime_dir() -> Result<std::path::PathBuf, String> {
+    platform::runtime_dir()
+}
+
+pub use platform::IpcListener;
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::path::PathBuf;
+    use tokio::net::{UnixListener, UnixStream};
+
+    pub type IpcStream = UnixStream;
+
+    pub fn default_endpoint() -> Result<String, String> {
+        Ok(runtime_dir()?
+            .join("capture.sock")
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// Not the temp directory: it's shared, launchd agents may not inherit
+    /// TMPDIR, and a fixed name there can be squatted by another user.
+    pub fn runtime_dir() -> Result<PathBuf, String> {
+        let base = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .or_else(|| {
+                let home = PathBuf::from(std::env::var_os("HOME")?);
+                Some(if cfg!(target_os = "macos") {
+                    home.join("Library/Caches")
+                } else {
+                    home.join(".cache")
+                })
+            })
+            .ok_or("Neither XDG_RUNTIME_DIR nor HOME is set")?;
+        let dir = base.join("runningbord");
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        // `mode` only applies to directories it creates.
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| e.to_string())?;
+        Ok(dir)
+    }
+
+    pub async fn connect(endpoint: &str) -> Result<IpcStream, String> {
+        UnixStream::connect(endpoint)
+            .await
+            .map_err(|e| format!("No daemon at {} ({})", endpoint, e))
+    }
+
+    pub struct IpcListener {
+        listener: UnixListener,
+    }
+
+    impl IpcListener {
+        /// Listen on `endpoint`, replacing a stale socket file. Only the
+        /// current user can connect: the socket is created in a directory
+        /// nobody else can enter, so there's no window between bind and
+        /// chmod in which another user could connect.
+        pub async fn bind(endpoint: &str) -> Result<Self, String> {
+            let dir = std::path::Path::new(endpoint)
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or(std::path::Path::new("."));
+            let mode = std::fs::metadata(dir)
+                .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+                .permissions()
+                .mode();
+            if mode & 0o077 != 0 {
+                return Err(format!(
+                    "{} is open to other users; put the socket in a 0700 directory",
+                    dir.display()
+                ));
+            }
+            if std::path::Path::new(endpoint).exists() {
+                if UnixStream::connect(endpoint).await.is_ok() {
+                    return Err(format!("A daemon is already listening at {}", endpoint));
+                }
+                let _ = std::fs::remove_file(endpoint);
+            }
+            let listener = UnixListener::bind(endpoint)
+                .map_err(|e| format!("Failed to bind {}: {}", endpoint, e))?;
+            std::fs::set_permissions(endpoint, std::fs::Permissions::from_mode(0o600))
+                .map_err(|e| e.to_string())?;
+            Ok(Self { listener })
+        }
+
+        pub async fn accept(&mut self) -> Result<IpcStream, String> {
+            self.listener
+                .accept()
+                .await
+                .map(|(stream, _)| stream)
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeServer, ServerOptions};
+
+    pub type IpcStream = NamedPipeServer;
+
+    pub fn default_endpoint() -> Result<String, String> {
+        Ok(r"\\.\pipe\runningbord-capture".to_string())
+    }
+
+    pub fn runtime_dir() -> Result<std::path::PathBuf, String> {
+        let dir = std::path::PathBuf::from(
+            std::env::var_os("LOCALAPPDATA").ok_or("LOCALAPPDATA is not set")?,
+        )
+        .join("runningbord");
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(dir)
+    }
+
+    pub async fn connect(
+        endpoint: &str,
+    ) -> Result<tokio::net::windows::named_pipe::NamedPipeClient, String> {
+        ClientOptions::new()
+            .open(endpoint)
+            .map_err(|e| format!("No daemon at {} ({})", endpoint, e))
+    }
+
+    pub struct IpcListener {
+        endpoint: String,
+        /// The instance waiting for the next client.
+        next: NamedPipeServer,
+    }
+
+    impl IpcListener {
+        /// Listen on the pipe `endpoint`. Remote clients are rejected.
+        pub async fn bind(endpoint: &str) -> Result<Self, String> {
+            let next = ServerOptions::new()
+                .first_pipe_instance(true)
+                .reject_remote_clients(true)
+                .create(endpoint)
+                .map_err(|e| format!("Failed to create pipe {}: {}", endpoint, e))?;
+            Ok(Self {
+                endpoint: endpoint.to_string(),
+                next,
+            })
+        }
+
+        pub async fn accept(&mut self) -> Result<IpcStream, String> {
+            self.next.connect().await.map_err(|e| e.to_string())?;
+            let next = ServerOptions::new()
+                .reject_remote_clients(true)
+                .create(&self.endpoint)
+                .map_err(|e| e.to_string())?;
+            Ok(std::mem::replace(&mut self.next, next))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,82 @@
+use super::*;
+
+fn payload(frame: &[u8]) -> &[u8] {
+    let len = u32::from_be_bytes(frame[..4].try_into().unwrap()) as usize;
+    assert_eq!(frame.len(), 4 + len);
+    &frame[4..]
+}
+
+#[test]
+fn requests_are_framed_with_the_version() {
+    let frame = encode(&Request::Start {
+        buffer_seconds: Some(120),
+    })
+    .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(payload(&frame)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"version": 1, "type": "start", "buffer_seconds": 120})
+    );
+}
+
+#[test]
+fn messages_round_trip() {
+    for request in [
+        Request::Start {
+            buffer_seconds: None,
+        },
+        Request::Stop,
+        Request::GetRecent,
+        Request::Status,
+        Request::Dump {
+            name: "call.ogg".to_string(),
+        },
+        Request::Shutdown,
+    ] {
+        let frame = encode(&request).unwrap();
+        assert_eq!(decode::<Request>(payload(&frame)).unwrap(), request);
+    }
+    let response = Response::Recent {
+        audio_base64: "T2dnUw==".to_string(),
+    };
+    let frame = encode(&response).unwrap();
+    assert_eq!(decode::<Response>(payload(&frame)).unwrap(), response);
+}
+
+#[test]
+fn optional_fields_may_be_left_out() {
+    assert_eq!(
+        decode::<Request>(br#"{"version":1,"type":"start"}"#).unwrap(),
+        Request::Start {
+            buffer_seconds: None
+        }
+    );
+}
+
+#[test]
+fn other_versions_and_unknown_types_are_refused() {
+    let err = decode::<Request>(br#"{"version":2,"type":"stop"}"#).unwrap_err();
+    assert!(err.contains("version 2"), "{}", err);
+    assert!(decode::<Request>(br#"{"type":"stop"}"#).is_err());
+    assert!(decode::<Request>(br#"{"version":1,"type":"reboot"}"#).is_err());
+}
+
+#[test]
+fn frames_are_read_back() {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let mut bytes = encode(&Request::Stop).unwrap();
+        bytes.extend(encode(&Request::Status).unwrap());
+        let mut reader = bytes.as_slice();
+        let first = read_frame(&mut reader).await.unwrap().unwrap();
+        assert_eq!(decode::<Request>(&first).unwrap(), Request::Stop);
+        let second = read_frame(&mut reader).await.unwrap().unwrap();
+        assert_eq!(decode::<Request>(&second).unwrap(), Request::Status);
+        assert_eq!(read_frame(&mut reader).await.unwrap(), None);
+
+        let oversized = ((MAX_FRAME_BYTES + 1) as u32).to_be_bytes();
+        assert!(read_frame(&mut oversized.as_slice()).await.is_err());
+    });
+}
@@ -0,0 +1,335 @@
+//! Diagnostics bundle for bug reports: one zip with what a maintainer needs
+//! to see and nothing a user wouldn't want to attach to a public issue.
+//!
+//! ```text
+//! system.json         app, Tauri and OS versions
+//! capabilities.json   what capture and speech features work on this machine
+//! settings.json       current settings, credentials and URL paths stripped
+//! stats.json          capture status, the last 30 days of usage, offline queue
+//! network_log.json    the opt-in provider log, without bodies
+//! crash.log           recent panics and errors (and crash.log.1)
+//! ```
+//!
+//! No audio, screenshots, transcripts or prompts go in, and API keys live in
+//! the credential store, which is never read here.
+
+use crate::history::{self, UsageGrouping};
+use crate::network_log::{self, REDACTED};
+use crate::system_audio::SystemAudioState;
+use crate::{
+    audio_session, autosave, capture, cloud_sync, crash_report, embeddings, network, offline_queue,
+    rate_limit, s3_upload, secure_input, tts, tts_native, webhook,
+};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Seek, Write};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const USAGE_DAYS: i64 = 30;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    app_version: String,
+    tauri_version: &'static str,
+    os: &'static str,
+    os_family: &'static str,
+    arch: &'static str,
+    /// Milliseconds since the Unix epoch.
+    created_at: u64,
+}
+
+/// Outcome of one probe: the value, or why it couldn't be read.
+#[derive(Serialize)]
+struct Probe<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl<T> From<Result<T, String>> for Probe<T> {
+    fn from(result: Result<T, String>) -> Self {
+        match result {
+            Ok(value) => Self {
+                value: Some(value),
+                error: None,
+            },
+            Err(error) => Self {
+                value: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Capabilities {
+    system_audio_supported: bool,
+    system_audio_backend: String,
+    monitors: Probe<usize>,
+    native_voices: Probe<usize>,
+    secure_input_active: bool,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Scheme, host and port of `text` if it's a URL. Webhook and provider URLs
+/// can carry tokens in their path or query, so nothing else is kept.
+fn url_origin(text: &str) -> Option<String> {
+    let url = Url::parse(text).ok()?;
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{}://{}:{}", url.scheme(), host, port),
+        None => format!("{}://{}", url.scheme(), host),
+    })
+}
+
+/// `value` with credential fields and every header value replaced and URLs
+/// cut to their origin. Numbers and flags are kept: `maxTokens` isn't a
+/// secret.
+fn sanitize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        Value::Object(headers) if name.eq_ignore_ascii_case("headers") => {
+                            Value::Object(
+                                headers
+                                    .into_iter()
+                                    .map(|(header, _)| (header, REDACTED.into()))
+                                    .collect(),
+                            )
+                        }
+                        Value::String(_) | Value::Array(_) | Value::Object(_)
+                            if network_log::is_sensitive(&name) =>
+                        {
+                            REDACTED.into()
+                        }
+                        value => sanitize(value),
+                    };
+                    (name, value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(sanitize).collect()),
+        Value::String(text) => Value::String(url_origin(&text).unwrap_or(text)),
+        value => value,
+    }
+}
+
+/// `result` as sanitized JSON, or `{"error": ...}`.
+fn sanitized<T: Serialize>(result: Result<T, String>) -> Value {
+    match result.and_then(|value| serde_json::to_value(value).map_err(|e| e.to_string())) {
+        Ok(value) => sanitize(value),
+        Err(error) => json!({ "error": error }),
+    }
+}
+
+/// Log text with credential fields and base64 payloads redacted.
+fn redact_log(text: &str) -> String {
+    text.lines()
+        .map(network_log::redact_body)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn settings(app: &AppHandle) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        (
+            "audioSession",
+            sanitized(audio_session::audio_session_get_config(app.clone())),
+        ),
+        (
+            "autoSave",
+            sanitized(autosave::auto_save_get_config(app.clone())),
+        ),
+        (
+            "cloudSync",
+            sanitized(cloud_sync::cloud_sync_get_config(app.clone())),
+        ),
+        (
+            "embeddings",
+            sanitized(embeddings::embeddings_get_config(app.clone())),
+        ),
+        (
+            "network",
+            sanitized(network::network_get_config(app.clone())),
+        ),
+        (
+            "rateLimit",
+            sanitized(rate_limit::rate_limit_get_config(app.clone())),
+        ),
+        ("s3", sanitized(s3_upload::s3_get_config(app.clone()))),
+        (
+            "screenshot",
+            sanitized(capture::screenshot_get_config(app.clone())),
+        ),
+        ("tts", sanitized(tts::tts_get_config(app.clone()))),
+        (
+            "webhook",
+            sanitized(webhook::webhook_get_config(app.clone())),
+        ),
+    ])
+}
+
+async fn capabilities(app: &AppHandle) -> Result<Capabilities, String> {
+    let status = app.state::<Arc<SystemAudioState>>().status()?;
+    let monitors = tauri::async_runtime::spawn_blocking(|| {
+        xcap::Monitor::all()
+            .map(|monitors| monitors.len())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(Capabilities {
+        system_audio_supported: status.supported,
+        system_audio_backend: status.backend,
+        monitors: monitors.into(),
+        native_voices: tts_native::tts_native_list_voices()
+            .await
+            .map(|voices| voices.len())
+            .into(),
+        secure_input_active: secure_input::secure_input_active(),
+    })
+}
+
+async fn stats(app: &AppHandle) -> Value {
+    let mut system_audio = sanitized(app.state::<Arc<SystemAudioState>>().status());
+    // The calendar event title or whatever else the user named the session.
+    if let Some(status) = system_audio.as_object_mut() {
+        status.remove("session_label");
+    }
+    let from = (chrono::Utc::now() - chrono::Duration::days(USAGE_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+    let usage = history::usage_summary(app, UsageGrouping::Day, Some(&from), None).await;
+    json!({
+        "systemAudio": system_audio,
+        "usageByDay": sanitized(usage),
+        "offlineQueue": sanitized(offline_queue::offline_queue_status(app.clone())),
+    })
+}
+
+/// Provider log entries without request and response bodies, which hold
+/// prompts and transcripts.
+fn network_log_entries(app: &AppHandle) -> Value {
+    sanitized(
+        network_log::network_log_entries(app.clone()).map(|entries| {
+            entries
+                .into_iter()
+                .map(|mut entry| {
+                    entry.request_body = None;
+                    entry.response_body = None;
+                    entry
+                })
+                .collect::<Vec<_>>()
+        }),
+    )
+}
+
+fn pretty<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| e.to_string())
+}
+
+/// Everything that goes in the bundle, as `(name, contents)`.
+async fn collect(app: &AppHandle) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let system = SystemInfo {
+        app_version: app.package_info().version.to_string(),
+        tauri_version: tauri::VERSION,
+        os: std::env::consts::OS,
+        os_family: std::env::consts::FAMILY,
+        arch: std::env::consts::ARCH,
+        created_at: now_millis(),
+    };
+    let mut files = vec![
+        ("system.json".to_string(), pretty(&system)?),
+        (
+            "capabilities.json".to_string(),
+            pretty(&Probe::from(capabilities(app).await))?,
+        ),
+        ("settings.json".to_string(), pretty(&settings(app))?),
+        ("stats.json".to_string(), pretty(&stats(app).await)?),
+        (
+            "network_log.json".to_string(),
+            pretty(&network_log_entries(app))?,
+        ),
+    ];
+    let log = crash_report::log_path(app)?;
+    for path in [log.clone(), log.with_extension("log.1")] {
+        if let (Some(name), Ok(text)) = (path.file_name(), fs::read_to_string(&path)) {
+            files.push((
+                name.to_string_lossy().to_string(),
+                redact_log(&text).into_bytes(),
+            ));
+        }
+    }
+    Ok(files)
+}
+
+/// Write `files` to `writer` as a deflated zip.
+fn write_bundle<W: Write + Seek>(writer: W, files: &[(String, Vec<u8>)]) -> Result<W, String> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(writer);
+    for (name, bytes) in files {
+        zip.start_file(name.as_str(), options)
+            .and_then(|_| zip.write_all(bytes).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {} to the bundle: {}", name, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish the bundle: {}", e))
+}
+
+/// Write the diagnostics bundle to `destination`, or to a path picked in
+/// the native Save dialog. Returns the path written, or `None` if the
+/// dialog was cancelled.
+#[tauri::command]
+pub async fn export_diagnostics(
+    app: AppHandle,
+    destination: Option<String>,
+) -> Result<Option<String>, String> {
+    let path = match destination {
+        Some(path) => path.into(),
+        None => {
+            let name = format!(
+                "runningbord-diagnostics-{}.zip",
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            );
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("Zip archive", &["zip"])
+                .set_file_name(&name)
+                .save_file()
+            else {
+                return Ok(None);
+            };
+            path
+        }
+    };
+    let files = collect(&app).await?;
+    let written = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = fs::File::create(&written)
+            .map_err(|e| format!("Failed to create {}: {}", written.display(), e))?;
+        write_bundle(file, &files).map(|_| ())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,71 @@
+use super::*;
+use std::io::{Cursor, Read};
+
+#[test]
+fn settings_lose_credentials_and_url_paths() {
+    let settings = json!({
+        "enabled": true,
+        "accessKeyId": "AKIA123",
+        "maxTokens": 4096,
+        "url": "https://hooks.slack.com/services/T000/B000/XXXX",
+        "proxy": { "url": "socks5://user:pw@127.0.0.1:1080", "username": "me" },
+        "providers": {
+            "openai": {
+                "baseUrl": "https://example.com/v1?key=abc",
+                "headers": { "OpenAI-Organization": "org-1", "X-Team": "a" }
+            }
+        },
+        "path": "/tmp/recordings",
+        "apiKey": null,
+    });
+    assert_eq!(
+        sanitize(settings),
+        json!({
+            "enabled": true,
+            "accessKeyId": REDACTED,
+            "maxTokens": 4096,
+            "url": "https://hooks.slack.com",
+            "proxy": { "url": "socks5://127.0.0.1:1080", "username": "me" },
+            "providers": {
+                "openai": {
+                    "baseUrl": "https://example.com",
+                    "headers": { "OpenAI-Organization": REDACTED, "X-Team": REDACTED }
+                }
+            },
+            "path": "/tmp/recordings",
+            "apiKey": null,
+        })
+    );
+}
+
+#[test]
+fn failed_reads_are_reported_not_fatal() {
+    assert_eq!(
+        sanitized::<u32>(Err("no state".to_string())),
+        json!({ "error": "no state" })
+    );
+    assert_eq!(
+        serde_json::to_value(Probe::from(Ok::<_, String>(2))).unwrap(),
+        json!({ "value": 2 })
+    );
+}
+
+#[test]
+fn bundle_holds_every_file() {
+    let files = vec![
+        ("system.json".to_string(), b"{}".to_vec()),
+        ("crash.log".to_string(), b"line".to_vec()),
+    ];
+    let bytes = write_bundle(Cursor::new(Vec::new()), &files)
+        .unwrap()
+        .into_inner();
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(archive.len(), 2);
+    let mut log = String::new();
+    archive
+        .by_name("crash.log")
+        .unwrap()
+        .read_to_string(&mut log)
+        .unwrap();
+    assert_eq!(log, "line");
+}
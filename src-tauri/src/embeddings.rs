@@ -0,0 +1,471 @@
+//! Embeddings of transcripts and notes, kept in a vector index (see
+//! `vector_index`) at `<app data>/vectors.idx` for retrieval.
+//!
+//! Text is cut into overlapping chunks and sent to an OpenAI-compatible
+//! `/embeddings` endpoint. The provider id picks the API key
+//! (`provider.<id>.api_key`, optional) and the network overrides, so a
+//! local model works by pointing the provider's base URL at Ollama,
+//! LM Studio or llama.cpp's server.
+//!
+//! While enabled, history transcripts are embedded in the background as
+//! entries are added or edited, and dropped when entries are deleted.
+//! Notes live in the frontend, which indexes them with `embeddings_index`.
+//!
+//! Embedding takes a round trip, so updates to one source can finish out
+//! of order, or after the source was removed. Each index or remove of a
+//! source starts a new generation, and an embedding is only written if its
+//! generation is still the latest when it reaches the index.
+
+use crate::network;
+use crate::network_log;
+use crate::offline_queue;
+use crate::secrets;
+use crate::vector_index::{Source, VectorIndex, VectorMatch};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const OPENAI_EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+const INDEX_NAME: &str = "vectors.idx";
+/// Roughly 500 tokens of English per chunk.
+const CHUNK_CHARS: usize = 2000;
+/// Carried over between chunks, so a sentence cut at a boundary is still
+/// found whole in one of them.
+const CHUNK_OVERLAP: usize = 200;
+/// Chunks sent per request.
+const BATCH_SIZE: usize = 64;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmbeddingsConfig {
+    /// Nothing is embedded, and nothing leaves the machine, until this is
+    /// set.
+    pub enabled: bool,
+    /// Provider id for the API key and network overrides.
+    pub provider: String,
+    pub model: String,
+    /// Shorter vectors, for models that support it (`text-embedding-3-*`).
+    pub dimensions: Option<u32>,
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: "openai".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            dimensions: None,
+        }
+    }
+}
+
+impl EmbeddingsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        secrets::api_key_secret(&self.provider)?;
+        if self.model.trim().is_empty() {
+            return Err("model must not be empty".to_string());
+        }
+        if self.dimensions == Some(0) {
+            return Err("dimensions must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Names the vectors this config produces; the index is rebuilt from
+    /// scratch when it changes.
+    fn index_model(&self) -> String {
+        match self.dimensions {
+            Some(dimensions) => format!("{}/{}@{}", self.provider, self.model, dimensions),
+            None => format!("{}/{}", self.provider, self.model),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EmbeddingsState {
+    config: Mutex<EmbeddingsConfig>,
+    /// Loaded from disk on first use.
+    index: tokio::sync::Mutex<Option<VectorIndex>>,
+    generations: Mutex<Generations>,
+}
+
+/// The latest generation of every source with an embedding in flight.
+#[derive(Debug, Default)]
+struct Generations {
+    next: u64,
+    latest: HashMap<Source, u64>,
+}
+
+impl Generations {
+    /// Start a new generation of `source`, superseding any in flight.
+    fn begin(&mut self, source: &Source) -> u64 {
+        self.next += 1;
+        self.latest.insert(source.clone(), self.next);
+        self.next
+    }
+
+    /// Supersede whatever is in flight for `source`.
+    fn invalidate(&mut self, source: &Source) {
+        self.latest.remove(source);
+    }
+
+    /// Whether `generation` is still the latest of `source`; if so it's
+    /// finished and forgotten, since it's applied right after.
+    fn finish(&mut self, source: &Source, generation: u64) -> bool {
+        let latest = self.latest.get(source) == Some(&generation);
+        if latest {
+            self.latest.remove(source);
+        }
+        latest
+    }
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// `text` cut at whitespace into chunks of at most `max_chars`, each
+/// starting with up to `overlap` characters of the previous one. Runs of
+/// whitespace become single spaces.
+pub(crate) fn chunk_text(text: &str, max_chars: usize, overlap: usize) -> Vec<String> {
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        // Words longer than a chunk (URLs, base64) are cut.
+        let mut rest = word;
+        while !rest.is_empty() {
+            let end = rest
+                .char_indices()
+                .nth(max_chars)
+                .map_or(rest.len(), |(i, _)| i);
+            words.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let mut end = start;
+        let mut len = 0;
+        while end < words.len() {
+            let add = words[end].chars().count() + usize::from(end > start);
+            if end > start && len + add > max_chars {
+                break;
+            }
+            len += add;
+            end += 1;
+        }
+        chunks.push(words[start..end].join(" "));
+        if end == words.len() {
+            break;
+        }
+        // Back up over the overlap, but always move forward.
+        let mut next = end;
+        let mut carried = 0;
+        while next > start + 1 {
+            let add = words[next - 1].chars().count() + 1;
+            if carried + add > overlap {
+                break;
+            }
+            carried += add;
+            next -= 1;
+        }
+        start = next;
+    }
+    chunks
+}
+
+/// The vectors of an `/embeddings` response body, in input order.
+fn parse_embeddings(body: &str, expected: usize) -> Result<Vec<Vec<f32>>, String> {
+    let mut data = serde_json::from_str::<EmbeddingsResponse>(body)
+        .map_err(|e| format!("Failed to parse the embeddings response: {}", e))?
+        .data;
+    data.sort_by_key(|d| d.index);
+    if data.len() != expected || data.iter().enumerate().any(|(i, d)| d.index != i) {
+        return Err(format!(
+            "Expected {} embeddings, got {}",
+            expected,
+            data.len()
+        ));
+    }
+    Ok(data.into_iter().map(|d| d.embedding).collect())
+}
+
+fn config(app: &AppHandle) -> Result<EmbeddingsConfig, String> {
+    Ok(app
+        .state::<EmbeddingsState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join(INDEX_NAME))
+}
+
+/// Embed `inputs` (at most `BATCH_SIZE`) with the configured model.
+async fn embed(
+    app: &AppHandle,
+    config: &EmbeddingsConfig,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let provider = config.provider.as_str();
+    let secret = secrets::api_key_secret(provider)?;
+    let api_key = tauri::async_runtime::spawn_blocking(move || secrets::get(&secret))
+        .await
+        .map_err(|e| e.to_string())??;
+    let client = network::client(app, Some(provider))?;
+    let url = network::provider_url(app, Some(provider), OPENAI_EMBEDDINGS_URL)?;
+    let mut body = serde_json::json!({ "model": config.model, "input": inputs });
+    if let Some(dimensions) = config.dimensions {
+        body["dimensions"] = dimensions.into();
+    }
+    let mut request = client.post(&url).timeout(REQUEST_TIMEOUT).json(&body);
+    // Local servers usually take no key.
+    if let Some(api_key) = api_key {
+        request = request.bearer_auth(api_key);
+    }
+    let request = request
+        .build()
+        .map_err(|e| format!("Failed to build embeddings request: {}", e))?;
+    let log = network_log::start(app, Some(provider), &request);
+    let response = match client.execute(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            log.finish(None, None, Some(&e.to_string()));
+            offline_queue::observe_error(app, &e);
+            return Err(format!("Embeddings request failed to send: {}", e));
+        }
+    };
+    let status = response.status();
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(e) => {
+            log.finish(Some(status.as_u16()), None, Some(&e.to_string()));
+            return Err(format!("Failed to read embeddings response: {}", e));
+        }
+    };
+    log.finish(Some(status.as_u16()), Some(&body), None);
+    if !status.is_success() {
+        return Err(format!(
+            "Embeddings request returned {} with body: {}",
+            status,
+            body.trim()
+        ));
+    }
+    parse_embeddings(&body, inputs.len())
+}
+
+/// The index on disk if it was built with `model`, else a new one. An
+/// unreadable index is started over; it can always be rebuilt.
+async fn load_index(path: &Path, model: &str) -> Result<VectorIndex, String> {
+    let path = path.to_path_buf();
+    let stored = tauri::async_runtime::spawn_blocking(move || VectorIndex::load(&path))
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_else(|e| {
+            tracing::warn!("Starting a new vector index: {}", e);
+            None
+        });
+    Ok(stored
+        .filter(|index| index.model() == model)
+        .unwrap_or_else(|| VectorIndex::new(model)))
+}
+
+fn generations(app: &AppHandle) -> std::sync::MutexGuard<'_, Generations> {
+    app.state::<EmbeddingsState>()
+        .inner()
+        .generations
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Run `f` on the index for `model`, loading it on first use. `f` returns
+/// its result and whether it changed the index, which is then saved.
+async fn with_index<T>(
+    app: &AppHandle,
+    model: &str,
+    f: impl FnOnce(&mut VectorIndex) -> Result<(T, bool), String>,
+) -> Result<T, String> {
+    let path = index_path(app)?;
+    let state = app.state::<EmbeddingsState>();
+    let mut slot = state.index.lock().await;
+    if !slot.as_ref().is_some_and(|index| index.model() == model) {
+        *slot = Some(load_index(&path, model).await?);
+    }
+    let index = slot.as_mut().expect("index was just loaded");
+    let (result, changed) = f(index)?;
+    if changed {
+        let snapshot = index.clone();
+        tauri::async_runtime::spawn_blocking(move || snapshot.save(&path))
+            .await
+            .map_err(|e| e.to_string())??;
+    }
+    Ok(result)
+}
+
+/// Embed `text` under `source`, replacing what was indexed for it before.
+/// Returns the number of chunks.
+pub async fn index(app: &AppHandle, source: &Source, text: &str) -> Result<usize, String> {
+    let generation = generations(app).begin(source);
+    index_generation(app, source, text, generation).await
+}
+
+/// `index` as `generation` of `source`. Nothing is written if a later
+/// index or remove of `source` started meanwhile.
+async fn index_generation(
+    app: &AppHandle,
+    source: &Source,
+    text: &str,
+    generation: u64,
+) -> Result<usize, String> {
+    let config = config(app)?;
+    if !config.enabled {
+        return Err("Embeddings are turned off".to_string());
+    }
+    let chunks = chunk_text(text, CHUNK_CHARS, CHUNK_OVERLAP);
+    let mut vectors = Vec::with_capacity(chunks.len());
+    for batch in chunks.chunks(BATCH_SIZE) {
+        vectors.extend(embed(app, &config, batch).await?);
+    }
+    let count = chunks.len();
+    with_index(app, &config.index_model(), |index| {
+        // Checked under the index lock, so a newer write can't slip in
+        // between.
+        if !generations(app).finish(source, generation) {
+            return Ok(((), false));
+        }
+        index.replace(source, chunks.into_iter().zip(vectors).collect())?;
+        Ok(((), true))
+    })
+    .await?;
+    Ok(count)
+}
+
+/// Drop everything indexed for `source`, whether or not embeddings are on.
+pub async fn remove(app: &AppHandle, source: &Source) -> Result<(), String> {
+    generations(app).invalidate(source);
+    // Nothing was ever indexed; don't write an empty index.
+    if !index_path(app)?.exists() {
+        return Ok(());
+    }
+    let model = config(app)?.index_model();
+    with_index(app, &model, |index| Ok(((), index.remove(source)))).await
+}
+
+/// The `limit` indexed chunks closest in meaning to `query`, best first.
+pub async fn search(
+    app: &AppHandle,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<VectorMatch>, String> {
+    let config = config(app)?;
+    if !config.enabled {
+        return Err("Embeddings are turned off".to_string());
+    }
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let vector = embed(app, &config, &[query])
+        .await?
+        .pop()
+        .ok_or_else(|| "No embedding for the query".to_string())?;
+    with_index(app, &config.index_model(), |index| {
+        Ok((index.search(vector, limit)?, false))
+    })
+    .await
+}
+
+pub fn enabled(app: &AppHandle) -> bool {
+    config(app).is_ok_and(|config| config.enabled)
+}
+
+/// Index a new or edited transcript in the background, if embeddings are
+/// on.
+pub fn index_in_background(app: &AppHandle, source: Source, text: String) {
+    if !enabled(app) {
+        return;
+    }
+    // Taken now, so updates apply in the order they were made.
+    let generation = generations(app).begin(&source);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = index_generation(&app, &source, &text, generation).await {
+            tracing::warn!("Failed to embed {:?} {}: {}", source.kind, source.id, e);
+        }
+    });
+}
+
+/// Remove a deleted source from the index in the background.
+pub fn remove_in_background(app: &AppHandle, source: Source) {
+    // Now, so an embedding still in flight isn't written after the remove.
+    generations(app).invalidate(&source);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = remove(&app, &source).await {
+            tracing::warn!("Failed to unindex {:?} {}: {}", source.kind, source.id, e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn embeddings_set_config(app: AppHandle, config: EmbeddingsConfig) -> Result<(), String> {
+    config.validate()?;
+    *app.state::<EmbeddingsState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn embeddings_get_config(app: AppHandle) -> Result<EmbeddingsConfig, String> {
+    config(&app)
+}
+
+/// Embed `text` (e.g. a note) under `source`. Returns the number of chunks.
+#[tauri::command]
+pub async fn embeddings_index(
+    app: AppHandle,
+    source: Source,
+    text: String,
+) -> Result<usize, String> {
+    index(&app, &source, &text).await
+}
+
+#[tauri::command]
+pub async fn embeddings_remove(app: AppHandle, source: Source) -> Result<(), String> {
+    remove(&app, &source).await
+}
+
+/// The `limit` (default 10) indexed chunks closest in meaning to `query`,
+/// best first.
+#[tauri::command]
+pub async fn embeddings_search(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<VectorMatch>, String> {
+    search(&app, &query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT)).await
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,79 @@
+use super::*;
+use crate::vector_index::SourceKind;
+
+#[test]
+fn chunks_overlap_and_respect_the_limit() {
+    let text = "one two  three\nfour five six seven";
+    let chunks = chunk_text(text, 14, 6);
+    assert_eq!(
+        chunks,
+        ["one two three", "three four", "four five six", "six seven"]
+    );
+    assert!(chunks.iter().all(|c| c.chars().count() <= 14));
+
+    assert_eq!(chunk_text("short text", 2000, 200), ["short text"]);
+    assert!(chunk_text(" \n\t", 2000, 200).is_empty());
+}
+
+#[test]
+fn long_words_are_cut() {
+    assert_eq!(chunk_text("abcdefgh", 3, 0), ["abc", "def", "gh"]);
+    // Cut on characters, not bytes.
+    assert_eq!(chunk_text("ééé", 2, 0), ["éé", "é"]);
+}
+
+#[test]
+fn responses_come_back_in_input_order() {
+    let body = r#"{"object":"list","data":[
+        {"object":"embedding","index":1,"embedding":[0.0,1.0]},
+        {"object":"embedding","index":0,"embedding":[1.0,0.0]}
+    ],"model":"text-embedding-3-small"}"#;
+    assert_eq!(
+        parse_embeddings(body, 2).unwrap(),
+        [vec![1.0, 0.0], vec![0.0, 1.0]]
+    );
+    assert!(parse_embeddings(body, 3).is_err());
+    assert!(parse_embeddings(r#"{"error":"nope"}"#, 1).is_err());
+}
+
+#[test]
+fn config_names_its_vectors() {
+    let mut config = EmbeddingsConfig::default();
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.index_model(), "openai/text-embedding-3-small");
+    config.dimensions = Some(256);
+    assert_eq!(config.index_model(), "openai/text-embedding-3-small@256");
+
+    config.dimensions = Some(0);
+    assert!(config.validate().is_err());
+    config.dimensions = None;
+    config.provider = "Local Server".to_string();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn only_the_latest_generation_is_written() {
+    let note = Source {
+        kind: SourceKind::Note,
+        id: "a".to_string(),
+    };
+    let mut generations = Generations::default();
+    // Two edits; the later one finishes first.
+    let first = generations.begin(&note);
+    let second = generations.begin(&note);
+    assert!(generations.finish(&note, second));
+    assert!(!generations.finish(&note, first));
+
+    // Removed while embedding.
+    let third = generations.begin(&note);
+    generations.invalidate(&note);
+    assert!(!generations.finish(&note, third));
+
+    // Other sources are independent.
+    let other = Source::transcript(1);
+    let fourth = generations.begin(&note);
+    let fifth = generations.begin(&other);
+    assert!(generations.finish(&note, fourth));
+    assert!(generations.finish(&other, fifth));
+    assert!(generations.latest.is_empty());
+}
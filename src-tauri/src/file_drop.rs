@@ -0,0 +1,239 @@
+//! Files dropped onto the overlay, read as context: text and code files
+//! come back as text (capped at `max_bytes`), tagged with a language for
+//! code fences, so the frontend can attach them without parsing anything.
+//! PDFs come back as the text of their pages.
+//!
+//! Drops on the main window are read in the background and emitted as
+//! `files-dropped`; `ingest_dropped_files` does the same for paths the
+//! frontend already has.
+
+use crate::pdf_text;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, DragDropEvent, Emitter, Manager, WindowEvent};
+
+const DEFAULT_MAX_BYTES: usize = 256 * 1024;
+const MAX_MAX_BYTES: usize = 4 * 1024 * 1024;
+/// Files past this many in one drop are skipped.
+const MAX_FILES: usize = 20;
+/// Files with an unknown extension are read as text if this much of their
+/// start is UTF-8 without NUL bytes.
+const SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DroppedContent {
+    Text {
+        text: String,
+        /// Code fence language, from the extension.
+        language: Option<&'static str>,
+        /// The file was longer than `max_bytes`.
+        truncated: bool,
+    },
+    Pdf {
+        text: String,
+        page_count: usize,
+        /// The text was longer than `max_bytes`.
+        truncated: bool,
+    },
+    Unsupported {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DroppedFile {
+    pub path: String,
+    pub name: String,
+    pub size_bytes: u64,
+    #[serde(flatten)]
+    pub content: DroppedContent,
+}
+
+/// Plain-text extensions, with the language to fence them as.
+fn language_for(path: &Path) -> Option<Option<&'static str>> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "txt" | "log" | "text" => None,
+        "md" | "markdown" => Some("markdown"),
+        "rst" => Some("rst"),
+        "csv" => Some("csv"),
+        "json" => Some("json"),
+        "yaml" | "yml" => Some("yaml"),
+        "toml" => Some("toml"),
+        "xml" => Some("xml"),
+        "html" | "htm" => Some("html"),
+        "css" => Some("css"),
+        "scss" => Some("scss"),
+        "js" | "mjs" | "cjs" => Some("javascript"),
+        "jsx" => Some("jsx"),
+        "ts" | "mts" | "cts" => Some("typescript"),
+        "tsx" => Some("tsx"),
+        "py" => Some("python"),
+        "rs" => Some("rust"),
+        "go" => Some("go"),
+        "java" => Some("java"),
+        "kt" | "kts" => Some("kotlin"),
+        "swift" => Some("swift"),
+        "c" | "h" => Some("c"),
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => Some("cpp"),
+        "cs" => Some("csharp"),
+        "m" | "mm" => Some("objectivec"),
+        "rb" => Some("ruby"),
+        "php" => Some("php"),
+        "sh" | "bash" | "zsh" => Some("bash"),
+        "ps1" => Some("powershell"),
+        "sql" => Some("sql"),
+        "lua" => Some("lua"),
+        "dart" => Some("dart"),
+        "scala" => Some("scala"),
+        "vue" => Some("vue"),
+        "svelte" => Some("svelte"),
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// `bytes` as text, cut to `max_bytes` on a char boundary, or `None` if
+/// they aren't UTF-8 text. A cut through the last char isn't an error.
+fn decode_text(bytes: &[u8], max_bytes: usize) -> Option<(String, bool)> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    let truncated = bytes.len() > max_bytes;
+    let bytes = &bytes[..bytes.len().min(max_bytes)];
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        // Only the cut may split a char.
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    Some((text.trim_start_matches('\u{feff}').to_string(), truncated))
+}
+
+fn unsupported(reason: &str) -> DroppedContent {
+    DroppedContent::Unsupported {
+        reason: reason.to_string(),
+    }
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+fn read_prefix(path: &Path, limit: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(limit as u64).read_to_end(&mut bytes))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(bytes)
+}
+
+fn ingest_file(path: &Path, max_bytes: usize) -> DroppedFile {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let metadata = std::fs::metadata(path);
+    let size_bytes = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+    let content = match metadata {
+        Err(e) => unsupported(&format!("Failed to read file: {}", e)),
+        Ok(metadata) if metadata.is_dir() => unsupported("Folders can't be attached"),
+        Ok(_) if is_pdf(path) => match pdf_text::extract(path, None, max_bytes) {
+            Ok(pdf) => DroppedContent::Pdf {
+                text: pdf.text,
+                page_count: pdf.page_count,
+                truncated: pdf.truncated,
+            },
+            Err(e) => unsupported(&e),
+        },
+        Ok(_) => {
+            let language = language_for(path);
+            // One byte past the cap tells a full file from a truncated one.
+            let limit = match language {
+                Some(_) => max_bytes + 1,
+                None => SNIFF_BYTES.max(max_bytes + 1),
+            };
+            match read_prefix(path, limit) {
+                Err(e) => unsupported(&e),
+                Ok(bytes) => match decode_text(&bytes, max_bytes) {
+                    Some((text, truncated)) => DroppedContent::Text {
+                        text,
+                        language: language.flatten(),
+                        truncated,
+                    },
+                    None if language.is_some() => unsupported("The file is not UTF-8 text"),
+                    None => unsupported("Only text and code files can be attached"),
+                },
+            }
+        }
+    };
+    DroppedFile {
+        path: path.to_string_lossy().to_string(),
+        name,
+        size_bytes,
+        content,
+    }
+}
+
+fn ingest(paths: &[PathBuf], max_bytes: usize) -> Vec<DroppedFile> {
+    paths
+        .iter()
+        .take(MAX_FILES)
+        .map(|path| ingest_file(path, max_bytes))
+        .collect()
+}
+
+/// Read files dropped on the main window and emit them as `files-dropped`.
+pub fn init(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let app = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event {
+            let app = app.clone();
+            let paths = paths.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let files = ingest(&paths, DEFAULT_MAX_BYTES);
+                if let Err(e) = app.emit("files-dropped", files) {
+                    tracing::warn!("Failed to emit dropped files: {}", e);
+                }
+            });
+        }
+    });
+}
+
+/// Read `paths` as attachable context, text capped at `max_bytes` (256 KiB
+/// by default, at most 4 MiB) per file.
+#[tauri::command]
+pub async fn ingest_dropped_files(
+    paths: Vec<PathBuf>,
+    max_bytes: Option<usize>,
+) -> Result<Vec<DroppedFile>, String> {
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_BYTES);
+    if !(1..=MAX_MAX_BYTES).contains(&max_bytes) {
+        return Err(format!(
+            "max_bytes must be between 1 and {}, got {}",
+            MAX_MAX_BYTES, max_bytes
+        ));
+    }
+    if paths.len() > MAX_FILES {
+        return Err(format!(
+            "At most {} files can be attached at once",
+            MAX_FILES
+        ));
+    }
+    tauri::async_runtime::spawn_blocking(move || ingest(&paths, max_bytes))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests;
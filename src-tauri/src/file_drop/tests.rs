@@ -0,0 +1,100 @@
+use super::*;
+use std::fs;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("runningbord-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn languages_come_from_the_extension() {
+    assert_eq!(language_for(Path::new("main.RS")), Some(Some("rust")));
+    assert_eq!(language_for(Path::new("notes.md")), Some(Some("markdown")));
+    assert_eq!(language_for(Path::new("notes.txt")), Some(None));
+    assert_eq!(language_for(Path::new("photo.png")), None);
+    assert_eq!(language_for(Path::new("Makefile")), None);
+}
+
+#[test]
+fn text_is_cut_on_a_char_boundary() {
+    assert_eq!(
+        decode_text(b"hello", 10),
+        Some(("hello".to_string(), false))
+    );
+    assert_eq!(decode_text(b"hello", 3), Some(("hel".to_string(), true)));
+    // "é" is two bytes; cutting through it keeps the char before.
+    assert_eq!(
+        decode_text("caé".as_bytes(), 3),
+        Some(("ca".to_string(), true))
+    );
+    assert_eq!(
+        decode_text("\u{feff}bom".as_bytes(), 10),
+        Some(("bom".to_string(), false))
+    );
+}
+
+#[test]
+fn binary_is_not_text() {
+    assert_eq!(decode_text(b"PK\x03\x04\x00\x00", 10), None);
+    assert_eq!(decode_text(b"\xff\xfeabc", 10), None);
+}
+
+#[test]
+fn dropped_files_are_read_by_kind() {
+    let dir = temp_dir("file-drop");
+    fs::write(dir.join("lib.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.join("long.txt"), "abcdef").unwrap();
+    fs::write(dir.join("README"), "plain words").unwrap();
+    fs::write(dir.join("image.bin"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+    fs::create_dir(dir.join("folder")).unwrap();
+
+    let files = ingest(
+        &[
+            dir.join("lib.rs"),
+            dir.join("long.txt"),
+            dir.join("README"),
+            dir.join("image.bin"),
+            dir.join("folder"),
+            dir.join("missing.md"),
+        ],
+        4,
+    );
+    let contents: Vec<&DroppedContent> = files.iter().map(|f| &f.content).collect();
+    assert_eq!(
+        contents[0],
+        &DroppedContent::Text {
+            text: "fn m".to_string(),
+            language: Some("rust"),
+            truncated: true,
+        }
+    );
+    assert_eq!(files[0].name, "lib.rs");
+    assert_eq!(files[0].size_bytes, 13);
+    assert!(matches!(
+        contents[1],
+        DroppedContent::Text { text, language: None, truncated: true } if text == "abcd"
+    ));
+    assert!(matches!(
+        contents[2],
+        DroppedContent::Text { text, language: None, truncated: true } if text == "plai"
+    ));
+    for content in &contents[3..] {
+        assert!(matches!(content, DroppedContent::Unsupported { .. }));
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn pdfs_are_not_read_as_text() {
+    let dir = temp_dir("file-drop-pdf");
+    fs::write(dir.join("broken.PDF"), "plain words").unwrap();
+
+    let files = ingest(&[dir.join("broken.PDF")], 100);
+    assert!(matches!(
+        files[0].content,
+        DroppedContent::Unsupported { .. }
+    ));
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,326 @@
+//! Do Not Disturb / Focus awareness: while the OS is in a Focus mode the app
+//! can hold back notifications (the frontend reads `suppress_notifications`
+//! from the status), skip automatic capture triggers (meetings, calendar
+//! events, the wake word) and pause system audio capture.
+//!
+//! On macOS: a manually enabled Focus, from the Focus assertions store (may
+//! need Full Disk Access on recent releases). Scheduled Focus modes are not
+//! detected.
+//! On Windows: Focus assist (quiet hours), plus presentation mode and quiet
+//! time as reported by `SHQueryUserNotificationState`.
+//! On Linux: GNOME's "Do Not Disturb", otherwise the notification server's
+//! `Inhibited` property (KDE and others).
+//!
+//! Emits `focus-mode-changed` while a policy is set.
+
+use crate::system_audio::{SystemAudioState, PAUSE_REASON_FOCUS_MODE};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the watcher polls the OS state.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What to hold back while a Focus mode is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FocusPolicy {
+    #[serde(default)]
+    pub suppress_notifications: bool,
+    /// Skip meeting, calendar and wake word triggered capture.
+    #[serde(default)]
+    pub suppress_auto_capture: bool,
+    /// Pause system audio capture.
+    #[serde(default)]
+    pub pause_recording: bool,
+}
+
+impl FocusPolicy {
+    fn any(&self) -> bool {
+        self.suppress_notifications || self.suppress_auto_capture || self.pause_recording
+    }
+}
+
+#[derive(Default)]
+pub struct FocusState {
+    policy: Mutex<FocusPolicy>,
+    /// Last state seen by the watcher; `None` if unknown.
+    active: Mutex<Option<bool>>,
+    /// Bumped on every policy change so a superseded watcher thread exits.
+    generation: AtomicU64,
+}
+
+impl FocusState {
+    fn policy(&self) -> FocusPolicy {
+        self.policy.lock().map(|p| *p).unwrap_or_default()
+    }
+
+    fn active(&self) -> Option<bool> {
+        self.active.lock().ok().and_then(|a| *a)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct FocusStatus {
+    /// `None` if the platform doesn't expose it.
+    pub active: Option<bool>,
+    pub policy: FocusPolicy,
+    /// The frontend should hold back notifications now.
+    pub suppress_notifications: bool,
+    /// System audio capture is paused by the policy.
+    pub recording_paused: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FocusModeChanged {
+    pub active: bool,
+}
+
+/// Whether automatic capture triggers should be skipped right now.
+pub fn suppresses_auto_capture(app: &AppHandle) -> bool {
+    let state = app.state::<FocusState>();
+    state.policy().suppress_auto_capture && state.active() == Some(true)
+}
+
+/// The Focus assertions store holds a record for each Focus turned on by
+/// hand: `{"data": [{"storeAssertionRecords": [...]}]}`.
+#[cfg(any(target_os = "macos", test))]
+fn assertions_active(json: &str) -> Option<bool> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let data = value.get("data")?.as_array()?;
+    Some(data.iter().any(|entry| {
+        entry
+            .get("storeAssertionRecords")
+            .and_then(|records| records.as_array())
+            .is_some_and(|records| !records.is_empty())
+    }))
+}
+
+/// Parse a `gdbus call` boolean reply such as `(<true>,)`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_gdbus_bool(output: &str) -> Option<bool> {
+    let value = output
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim_end_matches(',')
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether a Focus / Do Not Disturb mode is on, or `None` if unknown.
+pub fn focus_active() -> Option<bool> {
+    platform::focus_active()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::assertions_active;
+
+    pub fn focus_active() -> Option<bool> {
+        let home = std::env::var_os("HOME")?;
+        let path = std::path::Path::new(&home).join("Library/DoNotDisturb/DB/Assertions.json");
+        let json = std::fs::read_to_string(path).ok()?;
+        assertions_active(&json)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+    use std::ptr;
+
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn NtQueryWnfStateData(
+            state_name: *const u64,
+            type_id: *const c_void,
+            explicit_scope: *const c_void,
+            change_stamp: *mut u32,
+            buffer: *mut c_void,
+            buffer_size: *mut u32,
+        ) -> i32;
+    }
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHQueryUserNotificationState(state: *mut i32) -> i32;
+    }
+
+    /// WNF_SHEL_QUIETHOURS_ACTIVE_PROFILE_CHANGED: 0 off, 1 priority only,
+    /// 2 alarms only.
+    const WNF_QUIET_HOURS_PROFILE: u64 = 0x0D83_063E_A3BF_1C75;
+    /// QUNS_PRESENTATION_MODE and QUNS_QUIET_TIME.
+    const QUNS_PRESENTATION_MODE: i32 = 4;
+    const QUNS_QUIET_TIME: i32 = 6;
+
+    fn quiet_hours_profile() -> Option<u32> {
+        let mut stamp = 0u32;
+        let mut profile = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        let status = unsafe {
+            NtQueryWnfStateData(
+                &WNF_QUIET_HOURS_PROFILE,
+                ptr::null(),
+                ptr::null(),
+                &mut stamp,
+                &mut profile as *mut u32 as *mut c_void,
+                &mut size,
+            )
+        };
+        (status >= 0 && size as usize == std::mem::size_of::<u32>()).then_some(profile)
+    }
+
+    pub fn focus_active() -> Option<bool> {
+        let mut state = 0i32;
+        let quiet = unsafe { SHQueryUserNotificationState(&mut state) } >= 0
+            && matches!(state, QUNS_PRESENTATION_MODE | QUNS_QUIET_TIME);
+        match quiet_hours_profile() {
+            Some(profile) => Some(profile != 0 || quiet),
+            None => Some(quiet),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::parse_gdbus_bool;
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> Option<String> {
+        let output = Command::new(program).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    pub fn focus_active() -> Option<bool> {
+        // GNOME: Do Not Disturb turns banners off.
+        let gnome = std::env::var("XDG_CURRENT_DESKTOP").is_ok_and(|d| d.contains("GNOME"));
+        if let Some(banners) = run(
+            "gsettings",
+            &["get", "org.gnome.desktop.notifications", "show-banners"],
+        ) {
+            match banners.trim() {
+                "false" => return Some(true),
+                "true" if gnome => return Some(false),
+                _ => {}
+            }
+        }
+        let inhibited = run(
+            "gdbus",
+            &[
+                "call",
+                "--session",
+                "--dest",
+                "org.freedesktop.Notifications",
+                "--object-path",
+                "/org/freedesktop/Notifications",
+                "--method",
+                "org.freedesktop.DBus.Properties.Get",
+                "org.freedesktop.Notifications",
+                "Inhibited",
+            ],
+        )?;
+        parse_gdbus_bool(&inhibited)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    pub fn focus_active() -> Option<bool> {
+        None
+    }
+}
+
+/// Record a fresh reading and apply the recording pause. Emits
+/// `focus-mode-changed` when the state flips.
+fn apply(app: &AppHandle, active: Option<bool>) {
+    let state = app.state::<FocusState>();
+    let previous = state
+        .active
+        .lock()
+        .map(|mut a| std::mem::replace(&mut *a, active))
+        .unwrap_or(None);
+    let pause = active == Some(true) && state.policy().pause_recording;
+    let audio = app.state::<Arc<SystemAudioState>>();
+    if audio.set_paused(PAUSE_REASON_FOCUS_MODE, pause) {
+        tracing::info!(
+            "System audio capture {} for Focus mode",
+            if pause { "paused" } else { "resumed" }
+        );
+    }
+    if let Some(active) = active {
+        // The first reading only counts as a change if Focus is on.
+        let changed = match previous {
+            Some(previous) => previous != active,
+            None => active,
+        };
+        if changed {
+            tracing::info!("Focus mode {}", if active { "on" } else { "off" });
+            let _ = app.emit("focus-mode-changed", FocusModeChanged { active });
+        }
+    }
+}
+
+/// Poll the Focus state until the policy changes.
+fn spawn_watcher(app: AppHandle, generation: u64) {
+    thread::spawn(move || {
+        let state = app.state::<FocusState>();
+        while state.generation.load(Ordering::SeqCst) == generation {
+            let active = focus_active();
+            if state.generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            apply(&app, active);
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Set what to hold back during Focus modes. With every option off the
+/// watcher stops and capture is no longer paused for Focus.
+#[tauri::command]
+pub fn focus_mode_set_policy(app: AppHandle, policy: FocusPolicy) -> Result<(), String> {
+    let state = app.state::<FocusState>();
+    *state.policy.lock().map_err(|e| e.to_string())? = policy;
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    if policy.any() {
+        spawn_watcher(app.clone(), generation);
+    } else {
+        app.state::<Arc<SystemAudioState>>()
+            .set_paused(PAUSE_REASON_FOCUS_MODE, false);
+    }
+    Ok(())
+}
+
+/// Read the Focus state now, along with the policy and its effect.
+#[tauri::command]
+pub async fn focus_mode_status(app: AppHandle) -> Result<FocusStatus, String> {
+    let active = tauri::async_runtime::spawn_blocking(focus_active)
+        .await
+        .map_err(|e| e.to_string())?;
+    let state = app.state::<FocusState>();
+    let policy = state.policy();
+    if policy.any() {
+        apply(&app, active);
+    }
+    let on = active == Some(true);
+    Ok(FocusStatus {
+        active,
+        policy,
+        suppress_notifications: on && policy.suppress_notifications,
+        recording_paused: on && policy.pause_recording,
+    })
+}
+
+#[cfg(test)]
+mod tests;
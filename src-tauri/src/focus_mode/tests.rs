@@ -0,0 +1,20 @@
+use super::*;
+
+#[test]
+fn focus_assertions_are_read() {
+    let on = r#"{"data":[{"storeAssertionRecords":[{"assertionDetails":{"assertionDetailsModeIdentifier":"com.apple.donotdisturb.mode.default"}}]}],"header":{}}"#;
+    assert_eq!(assertions_active(on), Some(true));
+    assert_eq!(assertions_active(r#"{"data":[{}]}"#), Some(false));
+    assert_eq!(
+        assertions_active(r#"{"data":[{"storeAssertionRecords":[]}]}"#),
+        Some(false)
+    );
+    assert_eq!(assertions_active("not json"), None);
+}
+
+#[test]
+fn gdbus_booleans_are_parsed() {
+    assert_eq!(parse_gdbus_bool("(<true>,)\n"), Some(true));
+    assert_eq!(parse_gdbus_bool("(<false>,)"), Some(false));
+    assert_eq!(parse_gdbus_bool("(<uint32 1>,)"), None);
+}
@@ -0,0 +1,279 @@
+//! Frontmost (foreground) application and window title lookup.
+//!
+//! On macOS: NSWorkspace.frontmostApplication (bundle identifier); the
+//! title is that app's frontmost window from CGWindowListCopyWindowInfo,
+//! which needs the Screen Recording permission.
+//! On Windows: the foreground window's process executable name and text.
+//! On Linux (X11): `_NET_ACTIVE_WINDOW` via `xprop` (WM_CLASS, _NET_WM_NAME).
+//! Wayland compositors don't expose this, so the lookup returns `None` there.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FrontmostApp {
+    /// Bundle ID on macOS, executable name on Windows, WM_CLASS on Linux.
+    pub app_id: String,
+    /// Human-readable application name.
+    pub name: String,
+    pub pid: Option<u32>,
+}
+
+impl FrontmostApp {
+    /// Case-insensitive match against a bundle ID, executable name or app name.
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return false;
+        }
+        self.app_id.eq_ignore_ascii_case(pattern) || self.name.eq_ignore_ascii_case(pattern)
+    }
+}
+
+/// Return the application currently in the foreground, if it can be determined.
+pub fn frontmost_app() -> Option<FrontmostApp> {
+    platform::frontmost_app()
+}
+
+/// Title of the foreground window of `app`, if it has one and the platform
+/// allows reading it.
+pub fn window_title(app: &FrontmostApp) -> Option<String> {
+    platform::window_title(app).filter(|t| !t.trim().is_empty())
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::FrontmostApp;
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2_foundation::NSString;
+    use std::ffi::{c_void, CStr};
+
+    pub fn frontmost_app() -> Option<FrontmostApp> {
+        let cls_name = CStr::from_bytes_with_nul(b"NSWorkspace\0").ok()?;
+        let cls = AnyClass::get(cls_name)?;
+        unsafe {
+            let workspace: Option<Retained<AnyObject>> = msg_send![cls, sharedWorkspace];
+            let workspace = workspace?;
+            let app: Option<Retained<AnyObject>> = msg_send![&*workspace, frontmostApplication];
+            let app = app?;
+            let bundle_id: Option<Retained<NSString>> = msg_send![&*app, bundleIdentifier];
+            let name: Option<Retained<NSString>> = msg_send![&*app, localizedName];
+            let pid: i32 = msg_send![&*app, processIdentifier];
+
+            let name = name.map(|n| n.to_string()).unwrap_or_default();
+            let app_id = bundle_id.map(|b| b.to_string()).unwrap_or_else(|| name.clone());
+            Some(FrontmostApp {
+                app_id,
+                name,
+                pid: u32::try_from(pid).ok(),
+            })
+        }
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGWindowListCopyWindowInfo(option: u32, relative_to_window: u32) -> *const c_void;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+
+    const K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY: u32 = 1 << 0;
+    const K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS: u32 = 1 << 4;
+
+    pub fn window_title(app: &FrontmostApp) -> Option<String> {
+        let pid = app.pid? as i64;
+        unsafe {
+            let info = CGWindowListCopyWindowInfo(
+                K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY | K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS,
+                0,
+            );
+            if info.is_null() {
+                return None;
+            }
+            // CFArray of CFDictionary, toll-free bridged to NSArray/NSDictionary.
+            let windows = &*(info as *const AnyObject);
+            let number = |window: &AnyObject, key: &str| -> Option<i64> {
+                let key = NSString::from_str(key);
+                let value: Option<Retained<AnyObject>> = msg_send![window, objectForKey: &*key];
+                Some(msg_send![&*value?, longLongValue])
+            };
+            let count: usize = msg_send![windows, count];
+            let mut title = None;
+            // Front to back, so the first normal-layer window is the front one.
+            for i in 0..count {
+                let window: &AnyObject = msg_send![windows, objectAtIndex: i];
+                if number(window, "kCGWindowOwnerPID") != Some(pid)
+                    || number(window, "kCGWindowLayer") != Some(0)
+                {
+                    continue;
+                }
+                let key = NSString::from_str("kCGWindowName");
+                let name: Option<Retained<NSString>> = msg_send![window, objectForKey: &*key];
+                title = name.map(|n| n.to_string());
+                break;
+            }
+            CFRelease(info);
+            title
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::FrontmostApp;
+    use std::ffi::c_void;
+
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> *mut c_void;
+        fn GetWindowThreadProcessId(hwnd: *mut c_void, process_id: *mut u32) -> u32;
+        fn GetWindowTextW(hwnd: *mut c_void, text: *mut u16, max_count: i32) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OpenProcess(desired_access: u32, inherit_handle: i32, process_id: u32) -> *mut c_void;
+        fn QueryFullProcessImageNameW(
+            process: *mut c_void,
+            flags: u32,
+            exe_name: *mut u16,
+            size: *mut u32,
+        ) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    pub fn frontmost_app() -> Option<FrontmostApp> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                return None;
+            }
+            let mut pid: u32 = 0;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            if pid == 0 {
+                return None;
+            }
+
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+            let mut buf = [0u16; 1024];
+            let mut size = buf.len() as u32;
+            let ok = QueryFullProcessImageNameW(process, 0, buf.as_mut_ptr(), &mut size);
+            CloseHandle(process);
+            if ok == 0 {
+                return None;
+            }
+
+            let path = String::from_utf16_lossy(&buf[..size as usize]);
+            let exe = path.rsplit('\\').next().unwrap_or(&path).to_string();
+            let name = exe
+                .strip_suffix(".exe")
+                .or_else(|| exe.strip_suffix(".EXE"))
+                .unwrap_or(&exe)
+                .to_string();
+            Some(FrontmostApp {
+                app_id: exe,
+                name,
+                pid: Some(pid),
+            })
+        }
+    }
+
+    pub fn window_title(app: &FrontmostApp) -> Option<String> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                return None;
+            }
+            // The foreground may have changed since `app` was looked up.
+            let mut pid: u32 = 0;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            if app.pid.is_some_and(|expected| expected != pid) {
+                return None;
+            }
+            let mut buf = [0u16; 512];
+            let len = GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32);
+            (len > 0).then(|| String::from_utf16_lossy(&buf[..len as usize]))
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::FrontmostApp;
+    use std::process::Command;
+
+    fn xprop(args: &[&str]) -> Option<String> {
+        let output = Command::new("xprop").args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn active_window_id() -> Option<String> {
+        // _NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+        let active = xprop(&["-root", "_NET_ACTIVE_WINDOW"])?;
+        let window_id = active.split_whitespace().last()?.trim_end_matches(',');
+        (window_id != "0x0").then(|| window_id.to_string())
+    }
+
+    pub fn frontmost_app() -> Option<FrontmostApp> {
+        let window_id = active_window_id()?;
+        let window_id = window_id.as_str();
+
+        let props = xprop(&["-id", window_id, "WM_CLASS", "_NET_WM_PID"])?;
+        let mut class: Option<String> = None;
+        let mut pid: Option<u32> = None;
+        for line in props.lines() {
+            if line.starts_with("WM_CLASS") {
+                // WM_CLASS(STRING) = "instance", "Class"
+                class = line
+                    .split('"')
+                    .filter(|part| !part.trim().is_empty() && !part.contains('='))
+                    .filter(|part| part.trim() != ",")
+                    .last()
+                    .map(|c| c.to_string());
+            } else if line.starts_with("_NET_WM_PID") {
+                pid = line.split_whitespace().last().and_then(|p| p.parse().ok());
+            }
+        }
+
+        let class = class?;
+        Some(FrontmostApp {
+            app_id: class.clone(),
+            name: class,
+            pid,
+        })
+    }
+
+    pub fn window_title(_app: &FrontmostApp) -> Option<String> {
+        let window_id = active_window_id()?;
+        // _NET_WM_NAME(UTF8_STRING) = "main.rs - Code"
+        let line = xprop(&["-id", &window_id, "_NET_WM_NAME"])?;
+        let (_, value) = line.trim().split_once(" = ")?;
+        let value = value.strip_prefix('"')?.strip_suffix('"')?;
+        Some(value.replace("\\\"", "\"").replace("\\\\", "\\"))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    use super::FrontmostApp;
+
+    pub fn frontmost_app() -> Option<FrontmostApp> {
+        None
+    }
+
+    pub fn window_title(_app: &FrontmostApp) -> Option<String> {
+        None
+    }
+}
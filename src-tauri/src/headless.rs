@@ -0,0 +1,502 @@
+//! Headless capture daemon: runs system audio capture without the Tauri
+//! window. Driven by the `runningbord-cli` binary.
+//!
+//! The daemon serves the framed protocol in `daemon_ipc` on a unix domain
+//! socket (a named pipe on Windows), which only the user running it can
+//! open. The app and the CLI talk to it there.
+//!
+//! Given a port, it also listens on `127.0.0.1:<port>` for one-line text
+//! commands and answers each with a single line starting with `ok` or
+//! `error`. Any local process can reach that port, so a connection must
+//! first send
+//! `auth <token>`, with the token the daemon writes at startup to
+//! `daemon.token` in `daemon_ipc::runtime_dir()`, readable only by the
+//! user running it. Then:
+//! - `start [buffer_seconds]`
+//! - `stop`
+//! - `dump <name>`  write the retained audio as OGG/Opus to the file `<name>`
+//!                  in the recordings folder; it must not exist yet
+//! - `recent`       the retained audio as base64 OGG/Opus
+//! - `status`       capture status as JSON
+//! - `shutdown`     stop capture and exit the daemon
+
+use crate::daemon_ipc::{self, IpcListener, Request, Response};
+use crate::http_api::constant_time_eq;
+use crate::system_audio::{
+    start_system_audio, stop_system_audio, SystemAudioState, SystemAudioStatus, MAX_BUFFER_SECONDS,
+};
+pub use crate::system_audio_backend::CaptureBackendConfig;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+/// Default ring buffer length when `start` gives none.
+pub const DEFAULT_BUFFER_SECONDS: u32 = 120;
+/// The app's bundle identifier, which names its data folder. Must match
+/// `identifier` in tauri.conf.json.
+const APP_IDENTIFIER: &str = "com.srikanthnani.runningbord";
+/// Control token file in `daemon_ipc::runtime_dir()`.
+const TOKEN_FILE: &str = "daemon.token";
+
+/// The app's default recordings folder, `<app data>/recordings`.
+pub fn default_recordings_dir() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER).join("recordings"))
+        .ok_or_else(|| "Failed to get the data directory".to_string())
+}
+
+/// Where `dump <name>` writes: `name` must be a plain file name, so a
+/// client can't write outside `dir`.
+fn dump_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let plain = Path::new(name).file_name().is_some_and(|n| n == name);
+    if !plain || name.starts_with('.') {
+        return Err(format!("Not a plain file name: {}", name));
+    }
+    Ok(dir.join(name))
+}
+
+/// Whether `line` is `auth <token>` with the daemon's token.
+fn authenticates(line: &str, token: &str) -> bool {
+    line.trim()
+        .strip_prefix("auth ")
+        .is_some_and(|provided| constant_time_eq(provided.trim().as_bytes(), token.as_bytes()))
+}
+
+fn token_path() -> Result<PathBuf, String> {
+    Ok(daemon_ipc::runtime_dir()?.join(TOKEN_FILE))
+}
+
+/// Generate a control token and store it where only this user can read it.
+fn write_token() -> Result<String, String> {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let path = token_path()?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(token.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(token)
+}
+
+fn read_token() -> Result<String, String> {
+    let path = token_path()?;
+    std::fs::read_to_string(&path)
+        .map(|token| token.trim().to_string())
+        .map_err(|e| format!("No daemon token at {} ({})", path.display(), e))
+}
+
+/// System audio capture without a Tauri app around it.
+pub struct Headless {
+    state: Arc<SystemAudioState>,
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Headless {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(SystemAudioState::new()),
+        }
+    }
+
+    /// Capture from `backend` instead of the default one.
+    pub fn with_backend(backend: CaptureBackendConfig) -> Result<Self, String> {
+        let headless = Self::new();
+        headless.state.set_capture_backend(backend)?;
+        Ok(headless)
+    }
+
+    pub async fn start(&self, buffer_seconds: u32) -> Result<(), String> {
+        start_system_audio(&self.state, Some(buffer_seconds)).await
+    }
+
+    pub async fn stop(&self) {
+        stop_system_audio(&self.state).await;
+    }
+
+    /// Write the retained audio to `path` as OGG/Opus. Returns the byte count.
+    pub fn dump(&self, path: &Path) -> Result<usize, String> {
+        let bytes = self.state.get_recent_ogg()?;
+        std::fs::write(path, &bytes)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(bytes.len())
+    }
+
+    /// Write the retained audio to the new file `name` in `dir`. Returns the
+    /// path written.
+    fn dump_to(&self, dir: &Path, name: &str) -> Result<PathBuf, String> {
+        let path = dump_path(dir, name)?;
+        let bytes = self.state.get_recent_ogg()?;
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&bytes))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn status(&self) -> Result<SystemAudioStatus, String> {
+        self.state.status()
+    }
+
+    /// The retained audio as base64 OGG/Opus.
+    pub fn recent_base64(&self) -> Result<String, String> {
+        self.state.get_recent_base64()
+    }
+
+    /// Answer one request. `dump` writes to `recordings_dir`.
+    async fn handle_request(&self, request: Request, recordings_dir: &Path) -> Response {
+        let result = match request {
+            Request::Start { buffer_seconds } => start_system_audio(&self.state, buffer_seconds)
+                .await
+                .map(|_| Response::Ok),
+            Request::Stop | Request::Shutdown => {
+                self.stop().await;
+                Ok(Response::Ok)
+            }
+            Request::GetRecent => self
+                .recent_base64()
+                .map(|audio_base64| Response::Recent { audio_base64 }),
+            Request::Status => self.status().and_then(|status| {
+                serde_json::to_value(status)
+                    .map(|status| Response::Status { status })
+                    .map_err(|e| e.to_string())
+            }),
+            Request::Dump { name } => {
+                self.dump_to(recordings_dir, &name)
+                    .map(|path| Response::Saved {
+                        path: path.to_string_lossy().to_string(),
+                    })
+            }
+        };
+        result.unwrap_or_else(|message| Response::Error { message })
+    }
+
+    /// Run one control command. Returns the reply line and whether the
+    /// daemon should exit afterwards.
+    async fn handle(&self, line: &str, recordings_dir: &Path) -> (String, bool) {
+        let response = match parse_command(line) {
+            Ok(request) => {
+                let exit = request == Request::Shutdown;
+                let response = self.handle_request(request, recordings_dir).await;
+                return (reply_line(response), exit);
+            }
+            Err(message) => Response::Error { message },
+        };
+        (reply_line(response), false)
+    }
+}
+
+/// The request a control command line stands for.
+fn parse_command(line: &str) -> Result<Request, String> {
+    let mut parts = line.trim().splitn(2, ' ');
+    let command = parts.next().unwrap_or("");
+    let arg = parts.next().map(str::trim).filter(|a| !a.is_empty());
+    match command {
+        "start" => match arg.map(str::parse::<u32>).transpose() {
+            Ok(seconds) => Ok(Request::Start {
+                buffer_seconds: Some(seconds.unwrap_or(DEFAULT_BUFFER_SECONDS)),
+            }),
+            Err(e) => Err(format!("Invalid buffer seconds: {}", e)),
+        },
+        "stop" => Ok(Request::Stop),
+        "dump" => match arg {
+            Some(name) => Ok(Request::Dump {
+                name: name.to_string(),
+            }),
+            None => Err("Usage: dump <name>".to_string()),
+        },
+        "recent" => Ok(Request::GetRecent),
+        "status" => Ok(Request::Status),
+        "shutdown" => Ok(Request::Shutdown),
+        "" => Err("Empty command".to_string()),
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+/// The control protocol's reply line for `response`.
+fn reply_line(response: Response) -> String {
+    match response {
+        Response::Ok => "ok".to_string(),
+        Response::Recent { audio_base64 } => format!("ok {}", audio_base64),
+        Response::Status { status } => format!("ok {}", status),
+        Response::Saved { path } => format!("ok wrote {}", path),
+        Response::Error { message } => format!("error {}", message.replace('\n', " ")),
+    }
+}
+
+/// The TCP control port and the token its clients must send.
+type ControlPort = (TcpListener, Arc<String>);
+
+/// Accept on the control port, or wait forever if there is none.
+async fn accept_control(
+    control: &Option<ControlPort>,
+) -> std::io::Result<(TcpStream, Arc<String>)> {
+    match control {
+        Some((listener, token)) => listener
+            .accept()
+            .await
+            .map(|(stream, _)| (stream, token.clone())),
+        None => std::future::pending().await,
+    }
+}
+
+async fn handle_connection(
+    headless: Arc<Headless>,
+    token: Arc<String>,
+    recordings_dir: Arc<PathBuf>,
+    stream: TcpStream,
+    shutdown: watch::Sender<bool>,
+) {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let authenticated = matches!(
+        lines.next_line().await,
+        Ok(Some(line)) if authenticates(&line, &token)
+    );
+    let reply = if authenticated {
+        "ok authenticated\n"
+    } else {
+        "error Not authenticated\n"
+    };
+    if write.write_all(reply.as_bytes()).await.is_err() || !authenticated {
+        return;
+    }
+    while let Ok(Some(line)) = lines.next_line().await {
+        let (reply, exit) = headless.handle(&line, &recordings_dir).await;
+        if write
+            .write_all(format!("{}\n", reply).as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+        if exit {
+            let _ = shutdown.send(true);
+            break;
+        }
+    }
+}
+
+async fn handle_ipc_connection<S: AsyncRead + AsyncWrite + Unpin>(
+    headless: Arc<Headless>,
+    recordings_dir: Arc<PathBuf>,
+    mut stream: S,
+    shutdown: watch::Sender<bool>,
+) {
+    loop {
+        let mut exit = false;
+        let response = match daemon_ipc::read_frame(&mut stream).await {
+            Ok(Some(payload)) => match daemon_ipc::decode::<Request>(&payload) {
+                Ok(request) => {
+                    exit = request == Request::Shutdown;
+                    headless.handle_request(request, &recordings_dir).await
+                }
+                Err(message) => Response::Error { message },
+            },
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("IPC read failed: {}", e);
+                break;
+            }
+        };
+        if daemon_ipc::write_message(&mut stream, &response)
+            .await
+            .is_err()
+        {
+            break;
+        }
+        if exit {
+            let _ = shutdown.send(true);
+            break;
+        }
+    }
+}
+
+fn headless_for(backend: Option<CaptureBackendConfig>) -> Result<Headless, String> {
+    match backend {
+        Some(backend) => Headless::with_backend(backend),
+        None => Ok(Headless::new()),
+    }
+}
+
+/// Run the control daemon until a `shutdown` command or Ctrl-C. When
+/// `autostart` is set, capture starts immediately with that buffer length.
+/// The IPC socket defaults to `daemon_ipc::default_endpoint()`, and `dump`
+/// writes to `recordings`, by default `default_recordings_dir()`. The TCP
+/// control port is only opened when `port` is given.
+pub async fn run_daemon(
+    port: Option<u16>,
+    socket: Option<String>,
+    autostart: Option<u32>,
+    backend: Option<CaptureBackendConfig>,
+    recordings: Option<PathBuf>,
+) -> Result<(), String> {
+    let recordings_dir = Arc::new(match recordings {
+        Some(dir) => dir,
+        None => default_recordings_dir()?,
+    });
+    let endpoint = match socket {
+        Some(socket) => socket,
+        None => daemon_ipc::default_endpoint()?,
+    };
+    let mut ipc = IpcListener::bind(&endpoint).await?;
+    let control = match port {
+        Some(port) => {
+            let listener = TcpListener::bind(("127.0.0.1", port))
+                .await
+                .map_err(|e| format!("Failed to bind control port {}: {}", port, e))?;
+            eprintln!("runningbord daemon control port on 127.0.0.1:{}", port);
+            Some((listener, Arc::new(write_token()?)))
+        }
+        None => None,
+    };
+    let headless = Arc::new(headless_for(backend)?);
+    if let Some(seconds) = autostart {
+        headless.start(seconds).await?;
+    }
+
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    eprintln!("runningbord daemon listening on {}", endpoint);
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => break,
+            _ = tokio::signal::ctrl_c() => {
+                headless.stop().await;
+                break;
+            }
+            accepted = accept_control(&control) => match accepted {
+                Ok((stream, token)) => {
+                    tokio::spawn(handle_connection(
+                        headless.clone(),
+                        token,
+                        recordings_dir.clone(),
+                        stream,
+                        shutdown_tx.clone(),
+                    ));
+                }
+                Err(e) => eprintln!("Control accept failed: {}", e),
+            },
+            accepted = ipc.accept() => match accepted {
+                Ok(stream) => {
+                    tokio::spawn(handle_ipc_connection(
+                        headless.clone(),
+                        recordings_dir.clone(),
+                        stream,
+                        shutdown_tx.clone(),
+                    ));
+                }
+                Err(e) => eprintln!("IPC accept failed: {}", e),
+            }
+        }
+    }
+    #[cfg(unix)]
+    let _ = std::fs::remove_file(&endpoint);
+    if control.is_some() {
+        if let Ok(path) = token_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    Ok(())
+}
+
+/// Send one command to a running daemon over its IPC socket (default
+/// `daemon_ipc::default_endpoint()`) and return its reply.
+pub async fn send_ipc_command(socket: Option<String>, command: &str) -> Result<String, String> {
+    let endpoint = match socket {
+        Some(socket) => socket,
+        None => daemon_ipc::default_endpoint()?,
+    };
+    let response = daemon_ipc::request(&endpoint, &parse_command(command)?).await?;
+    let reply = reply_line(response);
+    Ok(reply.strip_prefix("ok ").unwrap_or(&reply).to_string())
+}
+
+/// Send one command to a running daemon's control port and return its
+/// reply.
+pub async fn send_command(port: u16, command: &str) -> Result<String, String> {
+    let token = read_token()?;
+    let stream = TcpStream::connect(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("No daemon on 127.0.0.1:{} ({})", port, e))?;
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let mut reply = String::new();
+    for line in [format!("auth {}", token), command.to_string()] {
+        write
+            .write_all(format!("{}\n", line).as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        reply = lines
+            .next_line()
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Daemon closed the connection".to_string())?;
+        if let Some(err) = reply.strip_prefix("error ") {
+            return Err(err.to_string());
+        }
+    }
+    Ok(reply.strip_prefix("ok ").unwrap_or(&reply).to_string())
+}
+
+/// Record in the foreground for `duration_secs` (or until Ctrl-C) and write
+/// the captured audio to `path`. The whole recording has to fit in the ring
+/// buffer, so durations longer than it are refused; without one, the last
+/// `DEFAULT_BUFFER_SECONDS` are written.
+pub async fn record_to_file(
+    path: &Path,
+    duration_secs: Option<u64>,
+    backend: Option<CaptureBackendConfig>,
+) -> Result<usize, String> {
+    if duration_secs.is_some_and(|d| d > u64::from(MAX_BUFFER_SECONDS)) {
+        return Err(format!(
+            "--duration can be at most {} seconds",
+            MAX_BUFFER_SECONDS
+        ));
+    }
+    let buffer_seconds = duration_secs
+        .map(|d| d.max(1) as u32)
+        .unwrap_or(DEFAULT_BUFFER_SECONDS);
+    let headless = headless_for(backend)?;
+    headless.start(buffer_seconds).await?;
+    // The buffer is shortened when memory is low.
+    let kept = headless.status()?.buffer_seconds;
+    if kept < buffer_seconds {
+        headless.stop().await;
+        return Err(format!(
+            "Not enough free memory to record {} seconds; at most {} fit",
+            buffer_seconds, kept
+        ));
+    }
+    match duration_secs {
+        Some(secs) => {
+            tokio::select! {
+                _ = tokio::time::sleep(std::time::Duration::from_secs(secs)) => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        None => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+    // Dump before stopping: stop zeroizes the buffer.
+    let written = headless.dump(path);
+    headless.stop().await;
+    written
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,75 @@
+use super::*;
+
+#[test]
+fn dump_path_stays_in_the_recordings_dir() {
+    let dir = Path::new("/recordings");
+    assert_eq!(
+        dump_path(dir, "call.ogg").unwrap(),
+        PathBuf::from("/recordings/call.ogg")
+    );
+    for name in [
+        "../call.ogg",
+        "sub/call.ogg",
+        "/tmp/call.ogg",
+        "..",
+        ".",
+        ".hidden",
+        "",
+    ] {
+        assert!(dump_path(dir, name).is_err(), "{}", name);
+    }
+}
+
+#[test]
+fn only_the_daemon_token_authenticates() {
+    assert!(authenticates("auth secret", "secret"));
+    assert!(authenticates("auth secret\r\n", "secret"));
+    assert!(!authenticates("auth wrong", "secret"));
+    assert!(!authenticates("auth ", "secret"));
+    assert!(!authenticates("status", "secret"));
+    assert!(!authenticates("secret", "secret"));
+}
+
+#[test]
+fn control_lines_map_to_ipc_requests() {
+    assert_eq!(
+        parse_command("start 60").unwrap(),
+        Request::Start {
+            buffer_seconds: Some(60)
+        }
+    );
+    assert_eq!(
+        parse_command("start").unwrap(),
+        Request::Start {
+            buffer_seconds: Some(DEFAULT_BUFFER_SECONDS)
+        }
+    );
+    assert_eq!(
+        parse_command(" dump call.ogg \n").unwrap(),
+        Request::Dump {
+            name: "call.ogg".to_string()
+        }
+    );
+    assert_eq!(parse_command("recent").unwrap(), Request::GetRecent);
+    assert_eq!(parse_command("shutdown").unwrap(), Request::Shutdown);
+    for line in ["", "dump", "start soon", "reboot"] {
+        assert!(parse_command(line).is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn responses_become_one_reply_line() {
+    assert_eq!(reply_line(Response::Ok), "ok");
+    assert_eq!(
+        reply_line(Response::Saved {
+            path: "/recordings/call.ogg".to_string()
+        }),
+        "ok wrote /recordings/call.ogg"
+    );
+    assert_eq!(
+        reply_line(Response::Error {
+            message: "two\nlines".to_string()
+        }),
+        "error two lines"
+    );
+}
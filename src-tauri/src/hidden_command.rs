@@ -0,0 +1,30 @@
+//! Helper programs started without a console window. On Windows a console
+//! program started from a GUI app gets a window of its own, which flashes
+//! up on every spawn; elsewhere these are plain `Command::new`.
+
+use std::ffi::OsStr;
+
+/// Process creation flag that suppresses the console window.
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// `std::process::Command::new(program)`, without a console window.
+pub fn new(program: impl AsRef<OsStr>) -> std::process::Command {
+    #[allow(unused_mut)]
+    let mut command = std::process::Command::new(program);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+/// `tokio::process::Command::new(program)`, without a console window.
+pub fn new_async(program: impl AsRef<OsStr>) -> tokio::process::Command {
+    #[allow(unused_mut)]
+    let mut command = tokio::process::Command::new(program);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+}
@@ -0,0 +1,699 @@
+//! Recordings history: every clip saved to disk (by the save dialog or by
+//! auto-save on quit) plus whatever the frontend registers, with its
+//! duration, transcript, tags and linked screenshot, in
+//! `<app data>/history.db`. Transcripts and tags are full-text searchable,
+//! and transcripts are embedded for semantic search while `embeddings` is
+//! on. Entries can carry a summary with action items (see `summarize`). The
+//! same database keeps the token usage and cost of provider calls.
+//!
+//! The database belongs to the backend; the frontend's `runningbord.db` is
+//! separate and managed by `tauri-plugin-sql`.
+
+use crate::embeddings;
+use crate::vector_index::Source;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::Row;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tokio::sync::OnceCell;
+
+const SCHEMA: &str = include_str!("history/schema.sql");
+const DEFAULT_LIMIT: u32 = 50;
+
+const COLUMNS: &str = "recordings.id, recordings.path, recordings.duration_seconds, \
+    recordings.transcript, recordings.tags, recordings.screenshot_path, recordings.created_at";
+
+#[derive(Default)]
+pub struct HistoryState {
+    pool: OnceCell<SqlitePool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub path: String,
+    pub duration_seconds: Option<f64>,
+    pub transcript: Option<String>,
+    pub tags: Vec<String>,
+    pub screenshot_path: Option<String>,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    pub created_at: String,
+    /// Matching excerpt of the transcript, with hits in `[brackets]`. Only
+    /// set by `history_search`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+/// Upload state of one entry on one sync backend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncRecord {
+    pub recording_id: i64,
+    pub backend: String,
+    pub remote_url: Option<String>,
+    /// Why the last upload failed; `None` once uploaded.
+    pub error: Option<String>,
+    pub updated_at: String,
+}
+
+/// Something to do that came up in a recording.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionItem {
+    pub task: String,
+    /// Who it's for, if someone was named.
+    pub owner: Option<String>,
+    /// When it's due, as said, e.g. "Friday".
+    pub due: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingSummary {
+    pub recording_id: i64,
+    pub summary: String,
+    pub action_items: Vec<ActionItem>,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    pub created_at: String,
+}
+
+/// One provider call, for usage tracking.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewUsage {
+    /// `"chat"` or `"transcription"`.
+    pub kind: &'static str,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub audio_seconds: f64,
+    /// `None` if the model's price isn't known.
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGrouping {
+    Day,
+    Provider,
+    Model,
+}
+
+impl UsageGrouping {
+    fn column(self) -> &'static str {
+        match self {
+            Self::Day => "date(created_at)",
+            Self::Provider => "provider",
+            Self::Model => "model",
+        }
+    }
+
+    /// Days in order; providers and models by spend.
+    fn order(self) -> &'static str {
+        match self {
+            Self::Day => "key",
+            Self::Provider | Self::Model => "cost_usd DESC, key",
+        }
+    }
+}
+
+/// Usage totals for one day, provider or model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageSummary {
+    /// `YYYY-MM-DD` (UTC), provider id or model id.
+    pub key: String,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub audio_seconds: f64,
+    /// Estimated USD, over requests with a known price.
+    pub cost_usd: f64,
+    /// Requests left out of `cost_usd` for lack of a price.
+    pub unpriced_requests: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NewRecording {
+    pub path: String,
+    pub duration_seconds: Option<f64>,
+    pub transcript: Option<String>,
+    pub tags: Vec<String>,
+    pub screenshot_path: Option<String>,
+}
+
+/// Trimmed, non-empty, first occurrence only.
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !out.iter().any(|t| t == tag) {
+            out.push(tag.to_string());
+        }
+    }
+    out
+}
+
+/// FTS5 query matching every word of `input`, the last one as a prefix so
+/// results follow typing. Words are quoted, so FTS syntax in the input is
+/// searched for literally. `None` if there are no words.
+fn fts_query(input: &str) -> Option<String> {
+    let words: Vec<String> = input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", words.join(" ")))
+}
+
+fn entry_from_row(row: &SqliteRow) -> Result<HistoryEntry, sqlx::Error> {
+    let tags: String = row.try_get("tags")?;
+    Ok(HistoryEntry {
+        id: row.try_get("id")?,
+        path: row.try_get("path")?,
+        duration_seconds: row.try_get("duration_seconds")?,
+        transcript: row.try_get("transcript")?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        screenshot_path: row.try_get("screenshot_path")?,
+        created_at: row.try_get("created_at")?,
+        snippet: None,
+    })
+}
+
+fn summary_from_row(row: &SqliteRow) -> Result<RecordingSummary, sqlx::Error> {
+    let action_items: String = row.try_get("action_items")?;
+    Ok(RecordingSummary {
+        recording_id: row.try_get("recording_id")?,
+        summary: row.try_get("summary")?,
+        action_items: serde_json::from_str(&action_items).unwrap_or_default(),
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+async fn open(path: &Path) -> Result<SqlitePool, String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    create_schema(&pool).await?;
+    Ok(pool)
+}
+
+async fn create_schema(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::raw_sql(SCHEMA)
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to create the history schema: {}", e))
+}
+
+/// The history database, opened on first use.
+async fn pool(app: &AppHandle) -> Result<SqlitePool, String> {
+    let state = app.state::<HistoryState>();
+    state
+        .pool
+        .get_or_try_init(|| async {
+            let dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+            open(&dir.join("history.db")).await
+        })
+        .await
+        .cloned()
+}
+
+async fn insert(pool: &SqlitePool, recording: NewRecording) -> Result<HistoryEntry, String> {
+    if recording.path.trim().is_empty() {
+        return Err("path must not be empty".to_string());
+    }
+    let tags = serde_json::to_string(&normalize_tags(recording.tags)).map_err(|e| e.to_string())?;
+    let id = sqlx::query(
+        "INSERT INTO recordings (path, duration_seconds, transcript, tags, screenshot_path) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&recording.path)
+    .bind(recording.duration_seconds)
+    .bind(&recording.transcript)
+    .bind(&tags)
+    .bind(&recording.screenshot_path)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    get(pool, id)
+        .await?
+        .ok_or_else(|| format!("Recording {} vanished after insert", id))
+}
+
+async fn get(pool: &SqlitePool, id: i64) -> Result<Option<HistoryEntry>, String> {
+    sqlx::query(&format!("SELECT {} FROM recordings WHERE id = ?", COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|row| entry_from_row(&row).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+/// Newest first, optionally only those tagged `tag`.
+async fn list(
+    pool: &SqlitePool,
+    tag: Option<&str>,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<HistoryEntry>, String> {
+    sqlx::query(&format!(
+        "SELECT {} FROM recordings \
+         WHERE ?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(recordings.tags) WHERE value = ?1) \
+         ORDER BY id DESC LIMIT ?2 OFFSET ?3",
+        COLUMNS
+    ))
+    .bind(tag)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| entry_from_row(row).map_err(|e| e.to_string()))
+    .collect()
+}
+
+/// Best matches first.
+async fn search(pool: &SqlitePool, query: &str, limit: u32) -> Result<Vec<HistoryEntry>, String> {
+    let Some(query) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+    sqlx::query(&format!(
+        "SELECT {}, snippet(recordings_fts, 0, '[', ']', '…', 12) AS snippet \
+         FROM recordings_fts JOIN recordings ON recordings.id = recordings_fts.rowid \
+         WHERE recordings_fts MATCH ? ORDER BY bm25(recordings_fts) LIMIT ?",
+        COLUMNS
+    ))
+    .bind(&query)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| {
+        let mut entry = entry_from_row(row).map_err(|e| e.to_string())?;
+        entry.snippet = row
+            .try_get::<Option<String>, _>("snippet")
+            .map_err(|e| e.to_string())?
+            .filter(|s| !s.is_empty());
+        Ok(entry)
+    })
+    .collect()
+}
+
+/// `None` leaves a field unchanged.
+async fn update(
+    pool: &SqlitePool,
+    id: i64,
+    transcript: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<HistoryEntry, String> {
+    let tags = tags
+        .map(|tags| serde_json::to_string(&normalize_tags(tags)))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    sqlx::query(
+        "UPDATE recordings SET transcript = COALESCE(?, transcript), tags = COALESCE(?, tags) \
+         WHERE id = ?",
+    )
+    .bind(transcript)
+    .bind(tags)
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    get(pool, id)
+        .await?
+        .ok_or_else(|| format!("No recording with id {}", id))
+}
+
+/// Remove the entry and return it, so the caller can delete its files.
+async fn remove(pool: &SqlitePool, id: i64) -> Result<HistoryEntry, String> {
+    let entry = get(pool, id)
+        .await?
+        .ok_or_else(|| format!("No recording with id {}", id))?;
+    sqlx::query("DELETE FROM recordings WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(entry)
+}
+
+/// The entries with the given ids, in that order. Unknown ids are an error.
+pub async fn entries(app: &AppHandle, ids: &[i64]) -> Result<Vec<HistoryEntry>, String> {
+    let pool = pool(app).await?;
+    let mut entries = Vec::with_capacity(ids.len());
+    for &id in ids {
+        entries.push(
+            get(&pool, id)
+                .await?
+                .ok_or_else(|| format!("No recording with id {}", id))?,
+        );
+    }
+    Ok(entries)
+}
+
+/// Entries not uploaded to `backend` yet (never tried, or the last try
+/// failed), oldest first.
+async fn pending_sync(pool: &SqlitePool, backend: &str) -> Result<Vec<HistoryEntry>, String> {
+    sqlx::query(&format!(
+        "SELECT {} FROM recordings WHERE NOT EXISTS (\
+            SELECT 1 FROM recording_sync WHERE recording_sync.recording_id = recordings.id \
+            AND recording_sync.backend = ? AND recording_sync.error IS NULL) \
+         ORDER BY id",
+        COLUMNS
+    ))
+    .bind(backend)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| entry_from_row(row).map_err(|e| e.to_string()))
+    .collect()
+}
+
+/// Record an upload: `Ok(remote_url)` or `Err(reason)`.
+async fn upsert_sync(
+    pool: &SqlitePool,
+    id: i64,
+    backend: &str,
+    result: Result<&str, &str>,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO recording_sync (recording_id, backend, remote_url, error) VALUES (?, ?, ?, ?) \
+         ON CONFLICT (recording_id, backend) DO UPDATE SET remote_url = excluded.remote_url, \
+         error = excluded.error, updated_at = datetime('now')",
+    )
+    .bind(id)
+    .bind(backend)
+    .bind(result.ok())
+    .bind(result.err())
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+async fn sync_records(pool: &SqlitePool) -> Result<Vec<SyncRecord>, String> {
+    sqlx::query(
+        "SELECT recording_id, backend, remote_url, error, updated_at FROM recording_sync \
+         ORDER BY recording_id DESC",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| {
+        Ok(SyncRecord {
+            recording_id: row.try_get("recording_id")?,
+            backend: row.try_get("backend")?,
+            remote_url: row.try_get("remote_url")?,
+            error: row.try_get("error")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    })
+    .collect::<Result<_, sqlx::Error>>()
+    .map_err(|e| e.to_string())
+}
+
+/// Set entry `id`'s summary, replacing any earlier one.
+async fn upsert_summary(
+    pool: &SqlitePool,
+    id: i64,
+    summary: &str,
+    action_items: &[ActionItem],
+) -> Result<RecordingSummary, String> {
+    let action_items = serde_json::to_string(action_items).map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO recording_summaries (recording_id, summary, action_items) VALUES (?, ?, ?) \
+         ON CONFLICT (recording_id) DO UPDATE SET summary = excluded.summary, \
+         action_items = excluded.action_items, created_at = datetime('now')",
+    )
+    .bind(id)
+    .bind(summary)
+    .bind(&action_items)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    get_summary(pool, id)
+        .await?
+        .ok_or_else(|| format!("Summary of recording {} vanished after insert", id))
+}
+
+async fn get_summary(pool: &SqlitePool, id: i64) -> Result<Option<RecordingSummary>, String> {
+    sqlx::query(
+        "SELECT recording_id, summary, action_items, created_at FROM recording_summaries \
+         WHERE recording_id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .map(|row| summary_from_row(&row).map_err(|e| e.to_string()))
+    .transpose()
+}
+
+async fn insert_usage(pool: &SqlitePool, usage: &NewUsage) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO provider_usage \
+         (kind, provider, model, input_tokens, output_tokens, audio_seconds, cost_usd) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(usage.kind)
+    .bind(&usage.provider)
+    .bind(&usage.model)
+    .bind(usage.input_tokens)
+    .bind(usage.output_tokens)
+    .bind(usage.audio_seconds)
+    .bind(usage.cost_usd)
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Usage grouped by `grouping`, between the UTC dates `from` and `to`
+/// (`YYYY-MM-DD`, inclusive) when given.
+async fn usage_totals(
+    pool: &SqlitePool,
+    grouping: UsageGrouping,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<Vec<UsageSummary>, String> {
+    sqlx::query(&format!(
+        "SELECT {} AS key, COUNT(*) AS requests, SUM(input_tokens) AS input_tokens, \
+         SUM(output_tokens) AS output_tokens, TOTAL(audio_seconds) AS audio_seconds, \
+         TOTAL(cost_usd) AS cost_usd, SUM(cost_usd IS NULL) AS unpriced_requests \
+         FROM provider_usage \
+         WHERE (?1 IS NULL OR date(created_at) >= ?1) AND (?2 IS NULL OR date(created_at) <= ?2) \
+         GROUP BY key ORDER BY {}",
+        grouping.column(),
+        grouping.order()
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| {
+        Ok(UsageSummary {
+            key: row.try_get("key")?,
+            requests: row.try_get("requests")?,
+            input_tokens: row.try_get("input_tokens")?,
+            output_tokens: row.try_get("output_tokens")?,
+            audio_seconds: row.try_get("audio_seconds")?,
+            cost_usd: row.try_get("cost_usd")?,
+            unpriced_requests: row.try_get("unpriced_requests")?,
+        })
+    })
+    .collect::<Result<_, sqlx::Error>>()
+    .map_err(|e| e.to_string())
+}
+
+pub async fn record_usage(app: &AppHandle, usage: &NewUsage) -> Result<(), String> {
+    insert_usage(&pool(app).await?, usage).await
+}
+
+/// See `usage_totals`.
+pub async fn usage_summary(
+    app: &AppHandle,
+    grouping: UsageGrouping,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<Vec<UsageSummary>, String> {
+    usage_totals(&pool(app).await?, grouping, from, to).await
+}
+
+/// See `pending_sync`.
+pub async fn unsynced(app: &AppHandle, backend: &str) -> Result<Vec<HistoryEntry>, String> {
+    pending_sync(&pool(app).await?, backend).await
+}
+
+pub async fn set_sync_state(
+    app: &AppHandle,
+    id: i64,
+    backend: &str,
+    result: Result<&str, &str>,
+) -> Result<(), String> {
+    upsert_sync(&pool(app).await?, id, backend, result).await
+}
+
+/// Upload state of every entry that has been synced or tried.
+pub async fn sync_states(app: &AppHandle) -> Result<Vec<SyncRecord>, String> {
+    sync_records(&pool(app).await?).await
+}
+
+/// `None` if there's no entry with that id.
+pub async fn entry(app: &AppHandle, id: i64) -> Result<Option<HistoryEntry>, String> {
+    get(&pool(app).await?, id).await
+}
+
+/// See `search`.
+pub async fn search_text(
+    app: &AppHandle,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<HistoryEntry>, String> {
+    search(&pool(app).await?, query, limit).await
+}
+
+/// See `upsert_summary`.
+pub async fn save_summary(
+    app: &AppHandle,
+    id: i64,
+    summary: &str,
+    action_items: &[ActionItem],
+) -> Result<RecordingSummary, String> {
+    upsert_summary(&pool(app).await?, id, summary, action_items).await
+}
+
+/// Every entry, newest first.
+pub async fn all_entries(app: &AppHandle) -> Result<Vec<HistoryEntry>, String> {
+    list(&pool(app).await?, None, u32::MAX, 0).await
+}
+
+/// Remove an entry and, with `delete_files`, its audio file and screenshot.
+pub async fn delete(app: &AppHandle, id: i64, delete_files: bool) -> Result<(), String> {
+    delete_where(app, id, |_| delete_files).await
+}
+
+/// Remove an entry and those of its files `delete_file` accepts.
+pub(crate) async fn delete_where(
+    app: &AppHandle,
+    id: i64,
+    delete_file: impl Fn(&Path) -> bool,
+) -> Result<(), String> {
+    let entry = remove(&pool(app).await?, id).await?;
+    embeddings::remove_in_background(app, Source::transcript(id));
+    for path in std::iter::once(entry.path).chain(entry.screenshot_path) {
+        if !delete_file(Path::new(&path)) {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete {}: {}", path, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Add a clip the backend saved to the history.
+pub async fn record(app: &AppHandle, recording: NewRecording) -> Result<HistoryEntry, String> {
+    let entry = insert(&pool(app).await?, recording).await?;
+    if let Some(transcript) = entry.transcript.clone().filter(|t| !t.trim().is_empty()) {
+        embeddings::index_in_background(app, Source::transcript(entry.id), transcript);
+    }
+    Ok(entry)
+}
+
+/// Register an exported clip, e.g. one the frontend saved or transcribed.
+#[tauri::command]
+pub async fn history_add(app: AppHandle, recording: NewRecording) -> Result<HistoryEntry, String> {
+    record(&app, recording).await
+}
+
+/// Newest first, `limit` (default 50) at a time, optionally only those
+/// tagged `tag`.
+#[tauri::command]
+pub async fn history_list(
+    app: AppHandle,
+    tag: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<HistoryEntry>, String> {
+    list(
+        &pool(&app).await?,
+        tag.as_deref(),
+        limit.unwrap_or(DEFAULT_LIMIT),
+        offset.unwrap_or(0),
+    )
+    .await
+}
+
+/// Full-text search over transcripts and tags, best matches first.
+#[tauri::command]
+pub async fn history_search(
+    app: AppHandle,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<HistoryEntry>, String> {
+    search(&pool(&app).await?, &query, limit.unwrap_or(DEFAULT_LIMIT)).await
+}
+
+/// Set an entry's transcript and/or tags, e.g. once transcription finishes.
+#[tauri::command]
+pub async fn history_update(
+    app: AppHandle,
+    id: i64,
+    transcript: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<HistoryEntry, String> {
+    let reindex = transcript.is_some();
+    let entry = update(&pool(&app).await?, id, transcript, tags).await?;
+    if reindex {
+        let transcript = entry.transcript.clone().unwrap_or_default();
+        embeddings::index_in_background(&app, Source::transcript(id), transcript);
+    }
+    Ok(entry)
+}
+
+/// An entry's summary and action items, if it has been summarized.
+#[tauri::command]
+pub async fn history_get_summary(
+    app: AppHandle,
+    id: i64,
+) -> Result<Option<RecordingSummary>, String> {
+    get_summary(&pool(&app).await?, id).await
+}
+
+/// Remove an entry. With `delete_files`, its audio file and screenshot are
+/// deleted from disk too.
+#[tauri::command]
+pub async fn history_delete(
+    app: AppHandle,
+    id: i64,
+    delete_files: Option<bool>,
+) -> Result<(), String> {
+    delete(&app, id, delete_files.unwrap_or(false)).await
+}
+
+#[cfg(test)]
+mod tests;
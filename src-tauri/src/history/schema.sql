@@ -0,0 +1,83 @@
+-- Exported clips and their transcripts, in history.db (owned by the
+-- backend, not the frontend's runningbord.db).
+CREATE TABLE IF NOT EXISTS recordings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    path TEXT NOT NULL,
+    duration_seconds REAL,
+    transcript TEXT,
+    -- JSON array of strings
+    tags TEXT DEFAULT '[]' NOT NULL,
+    screenshot_path TEXT,
+    created_at TEXT DEFAULT (datetime('now')) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_recordings_created_at ON recordings(created_at);
+
+-- Full-text index over transcripts and tags, kept in sync by the triggers
+-- below
+CREATE VIRTUAL TABLE IF NOT EXISTS recordings_fts USING fts5(
+    transcript,
+    tags,
+    content = 'recordings',
+    content_rowid = 'id'
+);
+
+CREATE TRIGGER IF NOT EXISTS recordings_fts_insert
+AFTER INSERT ON recordings
+BEGIN
+    INSERT INTO recordings_fts(rowid, transcript, tags)
+    VALUES (new.id, new.transcript, new.tags);
+END;
+
+CREATE TRIGGER IF NOT EXISTS recordings_fts_delete
+AFTER DELETE ON recordings
+BEGIN
+    INSERT INTO recordings_fts(recordings_fts, rowid, transcript, tags)
+    VALUES ('delete', old.id, old.transcript, old.tags);
+END;
+
+CREATE TRIGGER IF NOT EXISTS recordings_fts_update
+AFTER UPDATE ON recordings
+BEGIN
+    INSERT INTO recordings_fts(recordings_fts, rowid, transcript, tags)
+    VALUES ('delete', old.id, old.transcript, old.tags);
+    INSERT INTO recordings_fts(rowid, transcript, tags)
+    VALUES (new.id, new.transcript, new.tags);
+END;
+
+-- Upload state per recording and sync backend (see cloud_sync)
+CREATE TABLE IF NOT EXISTS recording_sync (
+    recording_id INTEGER NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+    backend TEXT NOT NULL,
+    remote_url TEXT,
+    -- Last failure; NULL once uploaded
+    error TEXT,
+    updated_at TEXT DEFAULT (datetime('now')) NOT NULL,
+    PRIMARY KEY (recording_id, backend)
+);
+
+-- Summary and action items of a recording (see summarize)
+CREATE TABLE IF NOT EXISTS recording_summaries (
+    recording_id INTEGER PRIMARY KEY REFERENCES recordings(id) ON DELETE CASCADE,
+    summary TEXT NOT NULL,
+    -- JSON array of {task, owner, due}
+    action_items TEXT DEFAULT '[]' NOT NULL,
+    created_at TEXT DEFAULT (datetime('now')) NOT NULL
+);
+
+-- Tokens, audio and estimated cost of each provider call (see usage)
+CREATE TABLE IF NOT EXISTS provider_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'chat' or 'transcription'
+    kind TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    input_tokens INTEGER DEFAULT 0 NOT NULL,
+    output_tokens INTEGER DEFAULT 0 NOT NULL,
+    audio_seconds REAL DEFAULT 0 NOT NULL,
+    -- USD; NULL when the model's price isn't known
+    cost_usd REAL,
+    created_at TEXT DEFAULT (datetime('now')) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_provider_usage_created_at ON provider_usage(created_at);
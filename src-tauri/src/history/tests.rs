@@ -0,0 +1,249 @@
+use super::*;
+
+/// A fresh in-memory history. One connection: each in-memory connection is
+/// its own database.
+fn memory_pool() -> SqlitePool {
+    tauri::async_runtime::block_on(async {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        create_schema(&pool).await.unwrap();
+        pool
+    })
+}
+
+fn recording(path: &str, transcript: &str, tags: &[&str]) -> NewRecording {
+    NewRecording {
+        path: path.to_string(),
+        duration_seconds: Some(12.5),
+        transcript: Some(transcript.to_string()),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        screenshot_path: None,
+    }
+}
+
+#[test]
+fn fts_queries_quote_every_word() {
+    assert_eq!(
+        fts_query("budget review").unwrap(),
+        "\"budget\" \"review\"*"
+    );
+    assert_eq!(
+        fts_query("say \"hi\" OR").unwrap(),
+        "\"say\" \"\"\"hi\"\"\" \"OR\"*"
+    );
+    assert_eq!(fts_query("   "), None);
+}
+
+#[test]
+fn tags_are_trimmed_and_deduplicated() {
+    let tags = vec![
+        " work ".to_string(),
+        "".to_string(),
+        "work".to_string(),
+        "call".to_string(),
+    ];
+    assert_eq!(normalize_tags(tags), vec!["work", "call"]);
+}
+
+#[test]
+fn entries_round_trip_and_list_newest_first() {
+    let pool = memory_pool();
+    tauri::async_runtime::block_on(async {
+        let first = insert(&pool, recording("/tmp/a.ogg", "hello", &["work", " work"]))
+            .await
+            .unwrap();
+        assert_eq!(first.tags, vec!["work"]);
+        assert_eq!(first.duration_seconds, Some(12.5));
+        insert(&pool, recording("/tmp/b.ogg", "bye", &["home"]))
+            .await
+            .unwrap();
+
+        let all = list(&pool, None, 10, 0).await.unwrap();
+        let paths: Vec<&str> = all.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/tmp/b.ogg", "/tmp/a.ogg"]);
+        let work = list(&pool, Some("work"), 10, 0).await.unwrap();
+        assert_eq!(work, vec![first]);
+        assert_eq!(list(&pool, None, 1, 1).await.unwrap().len(), 1);
+    });
+}
+
+#[test]
+fn search_finds_transcripts_and_follows_edits() {
+    let pool = memory_pool();
+    tauri::async_runtime::block_on(async {
+        let budget = insert(
+            &pool,
+            recording(
+                "/tmp/a.ogg",
+                "we agreed on the budget for next quarter",
+                &[],
+            ),
+        )
+        .await
+        .unwrap();
+        insert(&pool, recording("/tmp/b.ogg", "lunch plans", &["standup"]))
+            .await
+            .unwrap();
+
+        let hits = search(&pool, "budg", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, budget.id);
+        assert!(hits[0].snippet.as_deref().unwrap().contains("[budget]"));
+        assert_eq!(search(&pool, "standup", 10).await.unwrap().len(), 1);
+        assert!(search(&pool, "\"unbalanced", 10).await.unwrap().is_empty());
+
+        update(&pool, budget.id, Some("revised forecast".to_string()), None)
+            .await
+            .unwrap();
+        assert!(search(&pool, "budget", 10).await.unwrap().is_empty());
+        assert_eq!(search(&pool, "forecast", 10).await.unwrap().len(), 1);
+
+        remove(&pool, budget.id).await.unwrap();
+        assert!(search(&pool, "forecast", 10).await.unwrap().is_empty());
+        assert!(remove(&pool, budget.id).await.is_err());
+    });
+}
+
+#[test]
+fn sync_state_is_tracked_per_backend() {
+    let pool = memory_pool();
+    tauri::async_runtime::block_on(async {
+        let a = insert(&pool, recording("/tmp/a.ogg", "one", &[]))
+            .await
+            .unwrap();
+        let b = insert(&pool, recording("/tmp/b.ogg", "two", &[]))
+            .await
+            .unwrap();
+        let pending = |backend: &'static str| {
+            let pool = pool.clone();
+            async move {
+                pending_sync(&pool, backend)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|e| e.id)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(pending("webdav").await, vec![a.id, b.id]);
+
+        upsert_sync(&pool, a.id, "webdav", Ok("https://dav/a.ogg"))
+            .await
+            .unwrap();
+        upsert_sync(&pool, b.id, "webdav", Err("timed out"))
+            .await
+            .unwrap();
+        // Failed uploads are retried; other backends are independent.
+        assert_eq!(pending("webdav").await, vec![b.id]);
+        assert_eq!(pending("other").await, vec![a.id, b.id]);
+
+        upsert_sync(&pool, b.id, "webdav", Ok("https://dav/b.ogg"))
+            .await
+            .unwrap();
+        assert!(pending("webdav").await.is_empty());
+        let records = sync_records(&pool).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].remote_url.as_deref(), Some("https://dav/b.ogg"));
+        assert_eq!(records[0].error, None);
+
+        // Deleting an entry drops its sync state.
+        remove(&pool, a.id).await.unwrap();
+        assert_eq!(sync_records(&pool).await.unwrap().len(), 1);
+    });
+}
+
+#[test]
+fn summaries_are_replaced_and_deleted_with_their_entry() {
+    let pool = memory_pool();
+    tauri::async_runtime::block_on(async {
+        let entry = insert(&pool, recording("/tmp/a.ogg", "standup", &[]))
+            .await
+            .unwrap();
+        assert_eq!(get_summary(&pool, entry.id).await.unwrap(), None);
+
+        let item = ActionItem {
+            task: "Send the deck".to_string(),
+            owner: Some("Sam".to_string()),
+            due: None,
+        };
+        upsert_summary(&pool, entry.id, "first", &[]).await.unwrap();
+        let saved = upsert_summary(&pool, entry.id, "second", &[item.clone()])
+            .await
+            .unwrap();
+        assert_eq!(saved.summary, "second");
+        assert_eq!(saved.action_items, vec![item]);
+        assert_eq!(get_summary(&pool, entry.id).await.unwrap(), Some(saved));
+
+        // Only existing entries can be summarized.
+        assert!(upsert_summary(&pool, entry.id + 1, "x", &[]).await.is_err());
+
+        remove(&pool, entry.id).await.unwrap();
+        assert_eq!(get_summary(&pool, entry.id).await.unwrap(), None);
+    });
+}
+
+fn usage(provider: &str, model: &str, tokens: u32, cost_usd: Option<f64>) -> NewUsage {
+    NewUsage {
+        kind: "chat",
+        provider: provider.to_string(),
+        model: model.to_string(),
+        input_tokens: tokens,
+        output_tokens: tokens / 2,
+        audio_seconds: 0.0,
+        cost_usd,
+    }
+}
+
+#[test]
+fn usage_is_summed_by_provider_model_and_day() {
+    let pool = memory_pool();
+    tauri::async_runtime::block_on(async {
+        insert_usage(&pool, &usage("openai", "gpt-4o", 1000, Some(0.5)))
+            .await
+            .unwrap();
+        insert_usage(&pool, &usage("openai", "gpt-4o-mini", 2000, Some(0.1)))
+            .await
+            .unwrap();
+        insert_usage(&pool, &usage("local", "llama3", 500, None))
+            .await
+            .unwrap();
+        sqlx::query("UPDATE provider_usage SET created_at = '2024-05-01 10:00:00' WHERE id = 3")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let by_provider = usage_totals(&pool, UsageGrouping::Provider, None, None)
+            .await
+            .unwrap();
+        assert_eq!(by_provider.len(), 2);
+        assert_eq!(by_provider[0].key, "openai");
+        assert_eq!(by_provider[0].requests, 2);
+        assert_eq!(by_provider[0].input_tokens, 3000);
+        assert_eq!(by_provider[0].output_tokens, 1500);
+        assert!((by_provider[0].cost_usd - 0.6).abs() < 1e-9);
+        assert_eq!(by_provider[1].key, "local");
+        assert_eq!(by_provider[1].cost_usd, 0.0);
+        assert_eq!(by_provider[1].unpriced_requests, 1);
+
+        let by_model = usage_totals(&pool, UsageGrouping::Model, None, None)
+            .await
+            .unwrap();
+        let models: Vec<&str> = by_model.iter().map(|u| u.key.as_str()).collect();
+        assert_eq!(models, ["gpt-4o", "gpt-4o-mini", "llama3"]);
+
+        let by_day = usage_totals(&pool, UsageGrouping::Day, None, Some("2024-05-01"))
+            .await
+            .unwrap();
+        assert_eq!(by_day.len(), 1);
+        assert_eq!(by_day[0].key, "2024-05-01");
+        assert_eq!(by_day[0].requests, 1);
+
+        let since = usage_totals(&pool, UsageGrouping::Day, Some("2024-05-02"), None)
+            .await
+            .unwrap();
+        assert_eq!(since.iter().map(|u| u.requests).sum::<i64>(), 2);
+    });
+}
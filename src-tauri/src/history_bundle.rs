@@ -0,0 +1,148 @@
+//! History export bundle: selected history entries packed into one zip for
+//! sharing or backup.
+//!
+//! ```text
+//! manifest.json              every entry's metadata plus its files below
+//! 12/recording-...ogg        the audio, under its original name
+//! 12/transcript.txt
+//! 12/screenshot.png
+//! ```
+//!
+//! Files that no longer exist on disk are left out and listed under the
+//! entry's `missing` in the manifest instead of failing the export.
+
+use crate::history::{self, HistoryEntry};
+use serde::Serialize;
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::Path;
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Serialize)]
+struct BundleManifest {
+    app_version: String,
+    entries: Vec<BundleItem>,
+}
+
+#[derive(Serialize)]
+struct BundleItem {
+    #[serde(flatten)]
+    entry: HistoryEntry,
+    /// Paths inside the zip.
+    audio_file: Option<String>,
+    transcript_file: Option<String>,
+    screenshot_file: Option<String>,
+    /// Paths of files that couldn't be read from disk.
+    missing: Vec<String>,
+}
+
+/// Last component of `path`, falling back to `fallback`.
+fn file_name(path: &str, fallback: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Write the bundle for `entries` to `writer`. Audio and images are stored
+/// as-is (they're already compressed); text is deflated.
+fn write_bundle<W: Write + Seek>(writer: W, entries: Vec<HistoryEntry>) -> Result<W, String> {
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(writer);
+    let mut add = |name: &str, bytes: &[u8], options: SimpleFileOptions| {
+        zip.start_file(name, options)
+            .and_then(|_| zip.write_all(bytes).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {} to the bundle: {}", name, e))
+    };
+
+    let mut items = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let dir = entry.id.to_string();
+        let mut item = BundleItem {
+            audio_file: None,
+            transcript_file: None,
+            screenshot_file: None,
+            missing: Vec::new(),
+            entry,
+        };
+        match fs::read(&item.entry.path) {
+            Ok(bytes) => {
+                let name = format!("{}/{}", dir, file_name(&item.entry.path, "audio"));
+                add(&name, &bytes, stored)?;
+                item.audio_file = Some(name);
+            }
+            Err(_) => item.missing.push(item.entry.path.clone()),
+        }
+        if let Some(transcript) = item.entry.transcript.as_deref().filter(|t| !t.is_empty()) {
+            let name = format!("{}/transcript.txt", dir);
+            add(&name, transcript.as_bytes(), deflated)?;
+            item.transcript_file = Some(name);
+        }
+        if let Some(screenshot) = item.entry.screenshot_path.clone() {
+            match fs::read(&screenshot) {
+                Ok(bytes) => {
+                    let name = format!("{}/{}", dir, file_name(&screenshot, "screenshot"));
+                    add(&name, &bytes, stored)?;
+                    item.screenshot_file = Some(name);
+                }
+                Err(_) => item.missing.push(screenshot),
+            }
+        }
+        items.push(item);
+    }
+
+    let manifest = serde_json::to_vec_pretty(&BundleManifest {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        entries: items,
+    })
+    .map_err(|e| e.to_string())?;
+    add(MANIFEST_NAME, &manifest, deflated)?;
+    zip.finish()
+        .map_err(|e| format!("Failed to finish the bundle: {}", e))
+}
+
+/// Pack the history entries `ids` into a zip at `destination`, or at a path
+/// picked in the native Save dialog. Returns the path written, or `None` if
+/// the dialog was cancelled.
+#[tauri::command]
+pub async fn history_export_bundle(
+    app: AppHandle,
+    ids: Vec<i64>,
+    destination: Option<String>,
+) -> Result<Option<String>, String> {
+    if ids.is_empty() {
+        return Err("No recordings selected".to_string());
+    }
+    let entries = history::entries(&app, &ids).await?;
+    let path = match destination {
+        Some(path) => path.into(),
+        None => {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("Zip archive", &["zip"])
+                .set_file_name("recordings.zip")
+                .save_file()
+            else {
+                return Ok(None);
+            };
+            path
+        }
+    };
+    let written = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = fs::File::create(&written)
+            .map_err(|e| format!("Failed to create {}: {}", written.display(), e))?;
+        write_bundle(file, entries).map(|_| ())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,70 @@
+use super::*;
+use std::io::{Cursor, Read};
+
+fn entry(
+    id: i64,
+    path: &Path,
+    transcript: Option<&str>,
+    screenshot: Option<&Path>,
+) -> HistoryEntry {
+    HistoryEntry {
+        id,
+        path: path.to_string_lossy().to_string(),
+        duration_seconds: Some(3.0),
+        transcript: transcript.map(str::to_string),
+        tags: vec!["work".to_string()],
+        screenshot_path: screenshot.map(|p| p.to_string_lossy().to_string()),
+        created_at: "2026-10-16 12:00:00".to_string(),
+        snippet: None,
+    }
+}
+
+fn read(archive: &mut zip::ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    archive
+        .by_name(name)
+        .unwrap_or_else(|_| panic!("{} missing from the bundle", name))
+        .read_to_end(&mut bytes)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn bundle_holds_files_and_manifest() {
+    let dir = std::env::temp_dir().join(format!("runningbord-bundle-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let audio = dir.join("clip.ogg");
+    let screenshot = dir.join("shot.png");
+    fs::write(&audio, b"OggS audio").unwrap();
+    fs::write(&screenshot, b"png").unwrap();
+
+    let entries = vec![
+        entry(7, &audio, Some("hello there"), Some(&screenshot)),
+        entry(8, &dir.join("gone.ogg"), None, None),
+    ];
+    let bytes = write_bundle(Cursor::new(Vec::new()), entries)
+        .unwrap()
+        .into_inner();
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(read(&mut archive, "7/clip.ogg"), b"OggS audio");
+    assert_eq!(read(&mut archive, "7/transcript.txt"), b"hello there");
+    assert_eq!(read(&mut archive, "7/shot.png"), b"png");
+    assert_eq!(archive.len(), 4);
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&read(&mut archive, MANIFEST_NAME)).unwrap();
+    let items = manifest["entries"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["id"], 7);
+    assert_eq!(items[0]["audio_file"], "7/clip.ogg");
+    assert_eq!(items[0]["transcript_file"], "7/transcript.txt");
+    assert_eq!(items[0]["tags"][0], "work");
+    assert_eq!(items[1]["audio_file"], serde_json::Value::Null);
+    assert_eq!(
+        items[1]["missing"][0],
+        dir.join("gone.ogg").to_string_lossy().as_ref()
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,252 @@
+//! Optional localhost HTTP API for scripting the running app.
+//!
+//! Every request must carry `Authorization: Bearer <token>`; the token is
+//! returned by `http_api_start`.
+//!
+//! - `GET  /status`          capture status JSON
+//! - `GET  /capture/recent`  retained system audio as `audio/ogg`
+//! - `GET  /screenshot`      primary monitor as `image/jpeg`; 403 while a
+//!                           password is typed or a blocklisted app is in
+//!                           front
+//! - `POST /transcribe`      transcribe the request body (OGG/Opus), or the
+//!                           retained system audio if the body is empty
+
+use crate::privacy::screen_capture_blocked;
+use crate::system_audio::SystemAudioState;
+use axum::body::Bytes;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+/// Default port for the HTTP API.
+const DEFAULT_PORT: u16 = 4850;
+
+struct RunningServer {
+    port: u16,
+    token: String,
+    shutdown: watch::Sender<bool>,
+}
+
+#[derive(Default)]
+pub struct HttpApiState {
+    server: Mutex<Option<RunningServer>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct HttpApiInfo {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub token: Option<String>,
+}
+
+#[derive(Clone)]
+struct ApiContext {
+    app: AppHandle,
+    token: Arc<String>,
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({ "error": message.into() }))).into_response()
+}
+
+/// Compare without short-circuiting so response timing doesn't leak the token.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn require_token(State(token): State<Arc<String>>, request: Request, next: Next) -> Response {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+    if !constant_time_eq(provided.as_bytes(), token.as_bytes()) {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid or missing token");
+    }
+    next.run(request).await
+}
+
+async fn status(State(ctx): State<ApiContext>) -> Response {
+    match ctx.app.state::<Arc<SystemAudioState>>().status() {
+        Ok(status) => Json(status).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+async fn capture_recent(State(ctx): State<ApiContext>) -> Response {
+    let audio = ctx.app.state::<Arc<SystemAudioState>>().inner().clone();
+    match tokio::task::spawn_blocking(move || audio.get_recent_timed()).await {
+        Ok(Ok(timed)) => {
+            let mut response = ([(header::CONTENT_TYPE, "audio/ogg")], timed.ogg).into_response();
+            // Wall-clock span of the clip, ms since the Unix epoch.
+            for (name, value) in [
+                ("x-audio-start-ms", timed.start_time_ms),
+                ("x-audio-end-ms", timed.end_time_ms),
+            ] {
+                if let Some(ms) = value {
+                    response.headers_mut().insert(name, ms.into());
+                }
+            }
+            response
+        }
+        Ok(Err(e)) => error_response(StatusCode::NOT_FOUND, e),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn screenshot(State(ctx): State<ApiContext>) -> Response {
+    if let Some(reason) = screen_capture_blocked(&ctx.app) {
+        return error_response(StatusCode::FORBIDDEN, reason);
+    }
+    match tokio::task::spawn_blocking(crate::capture::capture_primary_monitor).await {
+        Ok(Ok(bytes)) => ([(header::CONTENT_TYPE, "image/jpeg")], bytes).into_response(),
+        Ok(Err(e)) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn transcribe(State(ctx): State<ApiContext>, body: Bytes) -> Response {
+    let audio_base64 = if body.is_empty() {
+        let audio = ctx.app.state::<Arc<SystemAudioState>>().inner().clone();
+        match tokio::task::spawn_blocking(move || audio.get_recent_base64()).await {
+            Ok(Ok(b64)) => b64,
+            Ok(Err(e)) => return error_response(StatusCode::NOT_FOUND, e),
+            Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    } else {
+        base64::engine::general_purpose::STANDARD.encode(&body)
+    };
+    match crate::api::transcribe(ctx.app.clone(), audio_base64).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => error_response(StatusCode::BAD_GATEWAY, e),
+    }
+}
+
+fn router(ctx: ApiContext) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/capture/recent", get(capture_recent))
+        .route("/screenshot", get(screenshot))
+        .route("/transcribe", post(transcribe))
+        .layer(middleware::from_fn_with_state(
+            ctx.token.clone(),
+            require_token,
+        ))
+        .with_state(ctx)
+}
+
+/// Start the localhost HTTP API. Returns the bound port and the bearer token
+/// clients must send; a random token is generated when none is given.
+#[tauri::command]
+pub async fn http_api_start(
+    app: AppHandle,
+    port: Option<u16>,
+    token: Option<String>,
+) -> Result<HttpApiInfo, String> {
+    let state = app.state::<HttpApiState>();
+    if let Some(server) = state.server.lock().map_err(|e| e.to_string())?.as_ref() {
+        return Ok(HttpApiInfo {
+            running: true,
+            port: Some(server.port),
+            token: Some(server.token.clone()),
+        });
+    }
+
+    let token = token
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+
+    let listener = TcpListener::bind(("127.0.0.1", port.unwrap_or(DEFAULT_PORT)))
+        .await
+        .map_err(|e| format!("Failed to bind HTTP API: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read HTTP API address: {}", e))?
+        .port();
+
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    {
+        let mut guard = state.server.lock().map_err(|e| e.to_string())?;
+        if let Some(server) = guard.as_ref() {
+            // Lost a race with a concurrent start; keep the existing server.
+            return Ok(HttpApiInfo {
+                running: true,
+                port: Some(server.port),
+                token: Some(server.token.clone()),
+            });
+        }
+        *guard = Some(RunningServer {
+            port,
+            token: token.clone(),
+            shutdown: shutdown_tx,
+        });
+    }
+
+    let app_router = router(ApiContext {
+        app: app.clone(),
+        token: Arc::new(token.clone()),
+    });
+    tauri::async_runtime::spawn(async move {
+        let result = axum::serve(listener, app_router)
+            .with_graceful_shutdown(async move {
+                let _ = shutdown_rx.changed().await;
+            })
+            .await;
+        if let Err(e) = result {
+            tracing::warn!("HTTP API server error: {}", e);
+        }
+        tracing::info!("HTTP API stopped");
+    });
+
+    tracing::info!("HTTP API listening on http://127.0.0.1:{}", port);
+    Ok(HttpApiInfo {
+        running: true,
+        port: Some(port),
+        token: Some(token),
+    })
+}
+
+#[tauri::command]
+pub fn http_api_stop(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<HttpApiState>();
+    let server = state.server.lock().map_err(|e| e.to_string())?.take();
+    if let Some(server) = server {
+        let _ = server.shutdown.send(true);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn http_api_status(app: AppHandle) -> Result<HttpApiInfo, String> {
+    let state = app.state::<HttpApiState>();
+    let guard = state.server.lock().map_err(|e| e.to_string())?;
+    Ok(match guard.as_ref() {
+        Some(server) => HttpApiInfo {
+            running: true,
+            port: Some(server.port),
+            token: Some(server.token.clone()),
+        },
+        None => HttpApiInfo {
+            running: false,
+            port: None,
+            token: None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,53 @@
+use super::*;
+
+#[test]
+fn tokens_compare_by_content_and_length() {
+    assert!(constant_time_eq(b"secret", b"secret"));
+    assert!(constant_time_eq(b"", b""));
+    assert!(!constant_time_eq(b"secret", b"secreT"));
+    assert!(!constant_time_eq(b"secret", b"secret2"));
+    assert!(!constant_time_eq(b"", b"secret"));
+}
+
+/// Status of a GET to a route behind `require_token` with token `secret`,
+/// sending `authorization` if given.
+fn get_status(authorization: Option<&str>) -> u16 {
+    tauri::async_runtime::block_on(async {
+        let routes = Router::new()
+            .route("/status", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(
+                Arc::new("secret".to_string()),
+                require_token,
+            ));
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move { axum::serve(listener, routes).await });
+        let mut request = reqwest::Client::new().get(format!("http://127.0.0.1:{}/status", port));
+        if let Some(value) = authorization {
+            request = request.header(header::AUTHORIZATION, value);
+        }
+        let status = request.send().await.unwrap().status().as_u16();
+        server.abort();
+        status
+    })
+}
+
+#[test]
+fn the_right_bearer_token_is_let_through() {
+    assert_eq!(get_status(Some("Bearer secret")), 200);
+}
+
+#[test]
+fn missing_or_wrong_tokens_are_unauthorized() {
+    assert_eq!(get_status(None), 401);
+    assert_eq!(get_status(Some("Bearer nope")), 401);
+    assert_eq!(get_status(Some("Bearer ")), 401);
+    assert_eq!(get_status(Some("Bearer secret2")), 401);
+}
+
+#[test]
+fn other_schemes_are_unauthorized() {
+    assert_eq!(get_status(Some("Basic secret")), 401);
+    assert_eq!(get_status(Some("Token secret")), 401);
+    assert_eq!(get_status(Some("secret")), 401);
+}
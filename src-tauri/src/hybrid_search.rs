@@ -0,0 +1,231 @@
+//! `search_history`: full-text and semantic search over history transcripts
+//! in one ranked list.
+//!
+//! The full-text index (see `history`) finds the words; the vector index
+//! (see `embeddings`) finds the meaning, e.g. "budget" for "how much will it
+//! cost". The two rankings are merged by reciprocal rank fusion, which needs
+//! no calibration between BM25 and cosine scores. Without embeddings, or if
+//! the embeddings provider can't be reached, results are full-text only.
+
+use crate::embeddings;
+use crate::history::{self, HistoryEntry};
+use crate::vector_index::{SourceKind, VectorMatch};
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+const DEFAULT_LIMIT: u32 = 20;
+/// Damps the lead of the top ranks, per the original RRF paper.
+const RRF_K: f64 = 60.0;
+/// Each ranking is asked for this many times `limit` candidates, since the
+/// other may reorder them.
+const CANDIDATE_FACTOR: u32 = 3;
+const SNIPPET_CHARS: usize = 240;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryHit {
+    pub entry_id: i64,
+    /// Fused rank score; only the order means anything.
+    pub score: f64,
+    /// Excerpt around the match. Full-text hits are in `[brackets]`.
+    pub snippet: String,
+    pub text_match: bool,
+    pub semantic_match: bool,
+    /// Cosine similarity of the best matching chunk, for semantic matches.
+    pub similarity: Option<f32>,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`, when the entry was saved.
+    pub created_at: String,
+    /// Where in the recording the match roughly is. Transcripts carry no
+    /// word timings, so this assumes an even pace of speech.
+    pub offset_seconds: Option<f64>,
+    pub duration_seconds: Option<f64>,
+    pub tags: Vec<String>,
+    pub audio_path: String,
+    /// `file://` URL of the recording; `None` if it's no longer on disk.
+    pub audio_url: Option<String>,
+}
+
+/// One entry's place in either ranking.
+#[derive(Debug, Clone, PartialEq)]
+struct Fused {
+    entry_id: i64,
+    score: f64,
+    /// From the full-text ranking.
+    snippet: Option<String>,
+    /// Best chunk from the semantic ranking.
+    chunk: Option<VectorMatch>,
+}
+
+/// Merge the full-text ranking (`(entry id, snippet)`, best first) with the
+/// semantic one (chunks, best first; an entry's later chunks are ignored).
+/// An entry scores `1 / (RRF_K + rank)` in each ranking it appears in.
+fn fuse(text: Vec<(i64, Option<String>)>, semantic: Vec<(i64, VectorMatch)>) -> Vec<Fused> {
+    let mut fused: Vec<Fused> = Vec::new();
+    let mut positions: HashMap<i64, usize> = HashMap::new();
+    let mut slot = |fused: &mut Vec<Fused>, entry_id: i64| {
+        *positions.entry(entry_id).or_insert_with(|| {
+            fused.push(Fused {
+                entry_id,
+                score: 0.0,
+                snippet: None,
+                chunk: None,
+            });
+            fused.len() - 1
+        })
+    };
+    for (rank, (entry_id, snippet)) in text.into_iter().enumerate() {
+        let i = slot(&mut fused, entry_id);
+        fused[i].score += 1.0 / (RRF_K + rank as f64 + 1.0);
+        fused[i].snippet = snippet;
+    }
+    let mut rank = 0;
+    for (entry_id, chunk) in semantic {
+        let i = slot(&mut fused, entry_id);
+        if fused[i].chunk.is_some() {
+            continue;
+        }
+        rank += 1;
+        fused[i].score += 1.0 / (RRF_K + rank as f64);
+        fused[i].chunk = Some(chunk);
+    }
+    // Stable: ties keep full-text order.
+    fused.sort_by(|a, b| b.score.total_cmp(&a.score));
+    fused
+}
+
+/// The first `max_chars` of `text`, with an ellipsis if cut.
+fn shorten(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+/// The first `[highlighted]` term of a full-text snippet.
+fn highlighted(snippet: &str) -> Option<&str> {
+    let start = snippet.find('[')? + 1;
+    let end = start + snippet[start..].find(']')?;
+    Some(&snippet[start..end]).filter(|term| !term.is_empty())
+}
+
+/// Estimated time of `needle` in a recording of `duration` seconds, from
+/// how far into `transcript` it first appears.
+fn offset_seconds(transcript: &str, needle: &str, duration: f64) -> Option<f64> {
+    let transcript = transcript
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let needle = needle
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if needle.is_empty() || !duration.is_finite() || duration <= 0.0 {
+        return None;
+    }
+    let at = transcript.find(&needle)?;
+    let before = transcript[..at].chars().count() as f64;
+    let total = transcript.chars().count() as f64;
+    Some((before / total * duration * 10.0).round() / 10.0)
+}
+
+fn hit(entry: HistoryEntry, fused: Fused) -> HistoryHit {
+    let transcript = entry.transcript.as_deref().unwrap_or_default();
+    let needle = match (&fused.snippet, &fused.chunk) {
+        (Some(snippet), _) => highlighted(snippet),
+        (None, Some(chunk)) => Some(chunk.text.as_str()),
+        (None, None) => None,
+    };
+    let offset_seconds = needle
+        .zip(entry.duration_seconds)
+        .and_then(|(needle, duration)| offset_seconds(transcript, needle, duration));
+    let snippet = match (&fused.snippet, &fused.chunk) {
+        (Some(snippet), _) => snippet.clone(),
+        (None, Some(chunk)) => shorten(&chunk.text, SNIPPET_CHARS),
+        (None, None) => shorten(transcript, SNIPPET_CHARS),
+    };
+    let audio_url = Some(Path::new(&entry.path))
+        .filter(|path| path.is_file())
+        .and_then(|path| Url::from_file_path(path).ok())
+        .map(String::from);
+    HistoryHit {
+        entry_id: entry.id,
+        score: fused.score,
+        snippet,
+        text_match: fused.snippet.is_some(),
+        semantic_match: fused.chunk.is_some(),
+        similarity: fused.chunk.as_ref().map(|chunk| chunk.score),
+        created_at: entry.created_at,
+        offset_seconds,
+        duration_seconds: entry.duration_seconds,
+        tags: entry.tags,
+        audio_path: entry.path,
+        audio_url,
+    }
+}
+
+/// Transcript chunks semantically close to `query`, as `(entry id, chunk)`,
+/// or nothing if embeddings are off or fail.
+async fn semantic_matches(app: &AppHandle, query: &str, limit: u32) -> Vec<(i64, VectorMatch)> {
+    if !embeddings::enabled(app) {
+        return Vec::new();
+    }
+    match embeddings::search(app, query, limit as usize).await {
+        Ok(matches) => matches
+            .into_iter()
+            .filter(|m| m.source.kind == SourceKind::Transcript)
+            .filter_map(|m| Some((m.source.id.parse().ok()?, m)))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Semantic search failed, using full-text only: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// History entries matching `query` by words or by meaning, best first,
+/// `limit` (default 20) at most.
+#[tauri::command]
+pub async fn search_history(
+    app: AppHandle,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<HistoryHit>, String> {
+    let query = query.trim();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    if query.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+    let candidates = limit.saturating_mul(CANDIDATE_FACTOR);
+    let mut entries: HashMap<i64, HistoryEntry> = HashMap::new();
+    let mut text = Vec::new();
+    for mut entry in history::search_text(&app, query, candidates).await? {
+        text.push((entry.id, entry.snippet.take()));
+        entries.insert(entry.id, entry);
+    }
+    let semantic = semantic_matches(&app, query, candidates).await;
+
+    let mut hits = Vec::new();
+    for fused in fuse(text, semantic) {
+        if hits.len() == limit as usize {
+            break;
+        }
+        let entry = match entries.remove(&fused.entry_id) {
+            Some(entry) => entry,
+            // Semantic only. Skip entries deleted since they were indexed.
+            None => match history::entry(&app, fused.entry_id).await? {
+                Some(entry) => entry,
+                None => continue,
+            },
+        };
+        hits.push(hit(entry, fused));
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests;
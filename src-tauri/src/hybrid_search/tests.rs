@@ -0,0 +1,105 @@
+use super::*;
+use crate::vector_index::Source;
+
+fn chunk(entry_id: i64, text: &str, score: f32) -> (i64, VectorMatch) {
+    (
+        entry_id,
+        VectorMatch {
+            source: Source::transcript(entry_id),
+            chunk: 0,
+            text: text.to_string(),
+            score,
+        },
+    )
+}
+
+fn entry(path: &str, transcript: &str) -> HistoryEntry {
+    HistoryEntry {
+        id: 4,
+        path: path.to_string(),
+        duration_seconds: Some(100.0),
+        transcript: Some(transcript.to_string()),
+        tags: vec!["work".to_string()],
+        screenshot_path: None,
+        created_at: "2026-10-16 12:00:00".to_string(),
+        snippet: None,
+    }
+}
+
+#[test]
+fn fusion_favours_entries_found_both_ways() {
+    let text = vec![(1, Some("[a]".to_string())), (2, Some("[b]".to_string()))];
+    let semantic = vec![
+        chunk(2, "b", 0.9),
+        chunk(3, "c", 0.8),
+        chunk(3, "c again", 0.7),
+        chunk(1, "a", 0.6),
+    ];
+    let fused = fuse(text, semantic);
+    assert_eq!(
+        fused.iter().map(|f| f.entry_id).collect::<Vec<_>>(),
+        [2, 1, 3]
+    );
+    // Only the best chunk of each entry counts.
+    assert_eq!(fused[2].chunk.as_ref().unwrap().text, "c");
+    assert_eq!(fused[0].snippet.as_deref(), Some("[b]"));
+    assert!((fused[2].score - 1.0 / 62.0).abs() < 1e-12);
+
+    // Full-text only keeps its order.
+    let fused = fuse(vec![(5, None), (6, None)], Vec::new());
+    assert_eq!(fused.iter().map(|f| f.entry_id).collect::<Vec<_>>(), [5, 6]);
+}
+
+#[test]
+fn snippets_and_offsets() {
+    assert_eq!(shorten("short", 10), "short");
+    assert_eq!(shorten("a b c d e", 4), "a b…");
+    assert_eq!(highlighted("…the [budget] for…"), Some("budget"));
+    assert_eq!(highlighted("no hits"), None);
+
+    let transcript = "aaaa\nbbbb  cccc dddd";
+    assert_eq!(offset_seconds(transcript, "CCCC", 19.0), Some(10.0));
+    assert_eq!(offset_seconds(transcript, "bbbb cccc", 19.0), Some(5.0));
+    assert_eq!(offset_seconds(transcript, "zzzz", 19.0), None);
+    assert_eq!(offset_seconds(transcript, "aaaa", 0.0), None);
+}
+
+#[test]
+fn hits_link_to_audio_on_disk() {
+    let dir = std::env::temp_dir().join(format!("runningbord-hybrid-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let audio = dir.join("clip.ogg");
+    std::fs::write(&audio, b"OggS").unwrap();
+    let transcript = "we talked about the budget and then lunch";
+
+    let semantic = Fused {
+        entry_id: 4,
+        score: 0.5,
+        snippet: None,
+        chunk: Some(chunk(4, "then lunch", 0.42).1),
+    };
+    let found = hit(entry(audio.to_str().unwrap(), transcript), semantic);
+    assert!(found.semantic_match && !found.text_match);
+    assert_eq!(found.snippet, "then lunch");
+    assert_eq!(found.similarity, Some(0.42));
+    assert_eq!(found.offset_seconds, Some(75.6));
+    assert!(found
+        .audio_url
+        .as_deref()
+        .is_some_and(|url| url.starts_with("file://") && url.ends_with("clip.ogg")));
+
+    let text = Fused {
+        entry_id: 4,
+        score: 0.5,
+        snippet: Some("the [budget] and".to_string()),
+        chunk: None,
+    };
+    let found = hit(
+        entry(dir.join("gone.ogg").to_str().unwrap(), transcript),
+        text,
+    );
+    assert_eq!(found.snippet, "the [budget] and");
+    assert_eq!(found.offset_seconds, Some(48.8));
+    assert_eq!(found.audio_url, None);
+    let _ = std::fs::remove_dir_all(&dir);
+}
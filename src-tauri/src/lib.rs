@@ -1,10 +1,75 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 mod activate;
 mod api;
+mod app_context;
+mod audio_session;
+mod autosave;
+mod calendar;
+mod captions;
 mod capture;
+mod capture_daemon;
+mod clipboard;
+mod cloud_sync;
+mod crash_recovery;
+mod crash_report;
+mod daemon_ipc;
 mod db;
+mod diagnostics;
+mod embeddings;
+mod file_drop;
+mod focus_mode;
+mod frontmost_app;
+pub mod headless;
+mod hidden_command;
+mod history;
+mod history_bundle;
+mod http_api;
+mod hybrid_search;
+mod live_transcript;
+mod meeting;
+mod moment;
+mod network;
+mod network_log;
+mod offline_queue;
+mod pdf_text;
+mod playback;
+mod privacy;
+mod provider_fetch;
+mod rate_limit;
+mod retention;
+mod retrieval;
+mod s3_upload;
+mod screen_clip;
+mod screen_mask;
+mod screen_record;
+mod screen_text;
+mod secrets;
+mod secure_input;
+mod session_lock;
+mod share;
 mod shortcuts;
+mod shutdown;
+mod stream_server;
+mod summarize;
 mod system_audio;
+mod system_audio_backend;
+mod system_audio_cipher;
+mod system_audio_dsp;
+mod system_audio_encoder;
+mod system_audio_loudness;
+mod system_audio_memory;
+mod system_audio_perf;
+mod system_audio_spectrum;
+mod tokens;
+mod tts;
+mod tts_native;
+mod tts_piper;
+mod update_check;
+mod usage;
+mod vector_index;
+mod wake_word;
+mod wake_word_onnx;
+mod webhook;
 mod window;
 
 #[cfg(target_os = "macos")]
@@ -16,8 +81,12 @@ mod system_audio_linux;
 #[cfg(target_os = "windows")]
 mod system_audio_windows;
 
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub use system_audio::bench;
+
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri::{AppHandle, Manager, RunEvent, WebviewWindow};
 use tauri_plugin_posthog::{init as posthog_init, PostHogConfig, PostHogOptions};
 use capture::CaptureState;
 use system_audio::SystemAudioState;
@@ -43,6 +112,37 @@ pub fn run() {
         )
         .manage(CaptureState::default())
         .manage(Arc::new(SystemAudioState::new()))
+        .manage(capture_daemon::CaptureDaemonState::default())
+        .manage(crash_recovery::CrashRecoveryState::default())
+        .manage(autosave::AutoSaveState::default())
+        .manage(history::HistoryState::default())
+        .manage(retention::RetentionState::default())
+        .manage(cloud_sync::CloudSyncState::default())
+        .manage(s3_upload::S3State::default())
+        .manage(webhook::WebhookState::default())
+        .manage(playback::PlaybackState::default())
+        .manage(audio_session::AudioSessionState::default())
+        .manage(tts::TtsState::default())
+        .manage(screen_record::ScreenRecordState::default())
+        .manage(screen_clip::ScreenClipState::default())
+        .manage(privacy::PrivacyState::default())
+        .manage(focus_mode::FocusState::default())
+        .manage(session_lock::SessionLockState::default())
+        .manage(stream_server::StreamServerState::default())
+        .manage(http_api::HttpApiState::default())
+        .manage(wake_word::WakeWordState::default())
+        .manage(meeting::MeetingState::default())
+        .manage(calendar::CalendarState::default())
+        .manage(clipboard::ClipboardState::default())
+        .manage(rate_limit::RateLimitState::default())
+        .manage(network::NetworkState::default())
+        .manage(network_log::NetworkLogState::default())
+        .manage(offline_queue::OfflineState::default())
+        .manage(update_check::UpdateState::default())
+        .manage(embeddings::EmbeddingsState::default())
+        .manage(retrieval::RetrievalState::default())
+        .manage(live_transcript::LiveTranscriptState::default())
+        .manage(captions::CaptionsState::default())
         .manage(shortcuts::WindowVisibility {
             is_hidden: Mutex::new(false),
         })
@@ -81,9 +181,12 @@ pub fn run() {
             window::toggle_dashboard,
             window::move_window,
             capture::capture_to_base64,
+            capture::grab_frame,
             capture::start_screen_capture,
             capture::capture_selected_area,
             capture::close_overlay_window,
+            capture::screenshot_set_config,
+            capture::screenshot_get_config,
             shortcuts::check_shortcuts_registered,
             shortcuts::get_registered_shortcuts,
             shortcuts::update_shortcuts,
@@ -103,9 +206,182 @@ pub fn run() {
             system_audio::system_audio_start,
             system_audio::system_audio_stop,
             system_audio::system_audio_get_recent_base64,
+            system_audio::system_audio_get_recent_timed,
+            system_audio::system_audio_export_chunked,
+            system_audio::system_audio_estimate_export,
+            system_audio::system_audio_measure_loudness,
+            system_audio::system_audio_spectrum,
+            system_audio::system_audio_export_formats,
+            system_audio::system_audio_export_time_compressed,
             system_audio::system_audio_save_ogg_base64,
             system_audio::system_audio_is_recording,
             system_audio::system_audio_status,
+            system_audio::system_audio_add_marker,
+            system_audio::system_audio_list_markers,
+            system_audio::get_audio_between_markers,
+            system_audio::get_audio_between_times,
+            system_audio::get_audio_at,
+            system_audio::system_audio_set_buffer_encryption,
+            system_audio::system_audio_verify_zeroized,
+            system_audio::system_audio_measure_latency,
+            system_audio::system_audio_set_encode_options,
+            system_audio::system_audio_get_encode_options,
+            system_audio::system_audio_set_capture_backend,
+            system_audio::system_audio_set_dsp_config,
+            system_audio::system_audio_get_dsp_config,
+            system_audio::system_audio_reconfigure,
+            system_audio::system_audio_get_capture_settings,
+            capture_daemon::capture_daemon_install,
+            capture_daemon::capture_daemon_uninstall,
+            capture_daemon::capture_daemon_attach,
+            capture_daemon::capture_daemon_detach,
+            capture_daemon::capture_daemon_start,
+            capture_daemon::capture_daemon_stop,
+            capture_daemon::capture_daemon_status,
+            crash_recovery::crash_recovery_set_seconds,
+            crash_recovery::crash_recovery_get_seconds,
+            crash_recovery::crash_recovery_list,
+            crash_recovery::crash_recovery_delete,
+            crash_report::crash_log_get,
+            crash_report::crash_log_clear,
+            diagnostics::export_diagnostics,
+            autosave::auto_save_set_config,
+            autosave::auto_save_get_config,
+            history::history_add,
+            history::history_list,
+            history::history_search,
+            history::history_update,
+            history::history_delete,
+            history::history_get_summary,
+            history_bundle::history_export_bundle,
+            retention::retention_set_policy,
+            retention::retention_get_policy,
+            retention::retention_run_now,
+            secrets::secret_set,
+            secrets::secret_delete,
+            secrets::secret_exists,
+            secrets::set_api_key,
+            secrets::has_api_key,
+            secrets::delete_api_key,
+            provider_fetch::provider_fetch,
+            cloud_sync::cloud_sync_set_config,
+            cloud_sync::cloud_sync_get_config,
+            cloud_sync::cloud_sync_run_now,
+            cloud_sync::cloud_sync_status,
+            s3_upload::s3_set_config,
+            s3_upload::s3_get_config,
+            s3_upload::s3_upload,
+            webhook::webhook_set_config,
+            webhook::webhook_get_config,
+            webhook::webhook_test,
+            share::share_set_webhook,
+            share::share_clear_webhook,
+            share::share_webhook_configured,
+            share::share_recent,
+            playback::playback_output_devices,
+            playback::playback_start,
+            playback::playback_pause,
+            playback::playback_resume,
+            playback::playback_seek,
+            playback::playback_stop,
+            playback::playback_status,
+            audio_session::audio_session_set_config,
+            audio_session::audio_session_get_config,
+            audio_session::audio_session_speech_started,
+            audio_session::audio_session_speech_ended,
+            audio_session::audio_session_status,
+            tts::tts_set_config,
+            tts::tts_get_config,
+            tts::tts_set_output_device,
+            tts::tts_synthesize,
+            tts::tts_speak,
+            tts::tts_stop,
+            tts::tts_is_speaking,
+            tts_piper::tts_piper_list_voices,
+            tts_piper::tts_piper_download_voice,
+            tts_piper::tts_piper_delete_voice,
+            tts_native::tts_native_list_voices,
+            privacy::system_audio_set_privacy_blocklist,
+            privacy::system_audio_get_privacy_blocklist,
+            privacy::get_frontmost_app,
+            privacy::privacy_set_secure_input_pause,
+            privacy::privacy_get_secure_input_pause,
+            focus_mode::focus_mode_set_policy,
+            focus_mode::focus_mode_status,
+            session_lock::session_lock_set_enabled,
+            session_lock::session_lock_status,
+            app_context::get_active_window,
+            screen_text::get_screen_text,
+            screen_record::screen_record_start,
+            screen_record::screen_record_stop,
+            screen_record::screen_record_mux_audio,
+            screen_record::screen_record_status,
+            screen_clip::screen_clip_start,
+            screen_clip::screen_clip_stop,
+            screen_clip::screen_clip_capture,
+            screen_clip::screen_clip_status,
+            stream_server::stream_server_start,
+            stream_server::stream_server_stop,
+            stream_server::stream_server_status,
+            http_api::http_api_start,
+            http_api::http_api_stop,
+            http_api::http_api_status,
+            moment::capture_moment,
+            meeting::meeting_watch_start,
+            meeting::meeting_watch_stop,
+            meeting::is_meeting_active,
+            meeting::get_active_meeting,
+            meeting::meeting_set_auto_capture,
+            meeting::meeting_get_auto_capture,
+            calendar::calendar_set_auto_record,
+            calendar::calendar_get_auto_record,
+            calendar::calendar_upcoming_events,
+            calendar::calendar_request_access,
+            clipboard::clipboard_read,
+            clipboard::clipboard_read_image,
+            clipboard::clipboard_watch_start,
+            clipboard::clipboard_watch_stop,
+            clipboard::clipboard_history,
+            file_drop::ingest_dropped_files,
+            pdf_text::pdf_extract_text,
+            tokens::count_tokens,
+            rate_limit::rate_limit_set_config,
+            rate_limit::rate_limit_get_config,
+            network::network_set_config,
+            network::network_get_config,
+            network_log::network_log_set_enabled,
+            network_log::network_log_get_enabled,
+            network_log::network_log_entries,
+            network_log::network_log_clear,
+            usage::get_usage_summary,
+            offline_queue::offline_queue_status,
+            offline_queue::offline_queue_remove,
+            offline_queue::offline_queue_clear,
+            update_check::check_for_update,
+            update_check::update_set_channel,
+            update_check::update_get_channel,
+            embeddings::embeddings_set_config,
+            embeddings::embeddings_get_config,
+            embeddings::embeddings_index,
+            embeddings::embeddings_remove,
+            embeddings::embeddings_search,
+            hybrid_search::search_history,
+            retrieval::retrieval_set_config,
+            retrieval::retrieval_get_config,
+            retrieval::retrieve_context,
+            summarize::summarize_recent,
+            live_transcript::live_transcript_set_config,
+            live_transcript::live_transcript_get_config,
+            live_transcript::live_transcript_recent,
+            live_transcript::live_transcript_at,
+            live_transcript::live_transcript_between,
+            captions::captions_set_config,
+            captions::captions_get_config,
+            wake_word::wake_word_enroll,
+            wake_word::wake_word_clear,
+            wake_word::wake_word_start,
+            wake_word::wake_word_stop,
+            wake_word::wake_word_status,
             api::transcribe_audio,
             api::chat_stream_response,
             api::fetch_models,
@@ -120,6 +396,19 @@ pub fn run() {
             #[cfg(target_os = "macos")]
             init(app.app_handle());
             let app_handle = app.handle();
+            capture_daemon::init(app_handle);
+            crash_recovery::install(app_handle);
+            crash_report::install(app_handle);
+            tauri::async_runtime::spawn_blocking(secrets::migrate_legacy_api_keys);
+            session_lock::init(app_handle);
+            shutdown::install(app_handle);
+            audio_session::init(app_handle);
+            retention::init(app_handle);
+            cloud_sync::init(app_handle);
+            live_transcript::init(app_handle);
+            captions::init(app_handle);
+            file_drop::init(app_handle);
+            offline_queue::init(app_handle);
             if app_handle.get_webview_window("dashboard").is_none() {
                 if let Err(e) = window::create_dashboard_window(&app_handle) {
                     eprintln!("Failed to pre-create dashboard window on startup: {}", e);
@@ -191,8 +480,13 @@ pub fn run() {
     }
 
     builder
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let RunEvent::Exit = event {
+                shutdown::run(app_handle);
+            }
+        });
 }
 
 #[cfg(target_os = "macos")]
@@ -0,0 +1,330 @@
+//! Background transcription of the whole capture buffer into a rolling
+//! transcript, so "what did they say 4 minutes ago" is answered from text
+//! already there instead of waiting on a transcription.
+//!
+//! While enabled and capturing, the buffer is cut into fixed segments of
+//! wall-clock time and each is transcribed once it's complete, starting
+//! with whatever the buffer already holds. The transcript mirrors the
+//! buffer: segments are dropped as their audio ages out, and everything is
+//! cleared when capture stops (which zeroizes the buffer) or the feature is
+//! turned off. Segment edges don't follow speech, so a word can be split
+//! between two segments.
+//!
+//! While captions are on they already transcribe every utterance, so the
+//! worker stops sending audio and the transcript is filled from them
+//! (`record`) instead, one entry per utterance.
+//!
+//! Off by default: every segment is a transcription request. Encrypted
+//! buffers are skipped, since the transcript would be a plaintext copy.
+
+use crate::api;
+use crate::captions;
+use crate::offline_queue;
+use crate::system_audio::{SystemAudioState, OUTPUT_SAMPLE_RATE};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::time::MissedTickBehavior;
+
+/// How often the worker looks for a completed segment.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Wait after a failed transcription before trying again.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LiveTranscriptConfig {
+    pub enabled: bool,
+    /// Audio per transcription request. Shorter means fresher text but
+    /// more requests and less context for the model.
+    pub segment_seconds: u32,
+}
+
+impl Default for LiveTranscriptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            segment_seconds: 30,
+        }
+    }
+}
+
+impl LiveTranscriptConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(5..=600).contains(&self.segment_seconds) {
+            return Err("segmentSeconds must be between 5 and 600".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct LiveTranscriptState {
+    config: Mutex<LiveTranscriptConfig>,
+    transcript: Mutex<RollingTranscript>,
+}
+
+/// Payload of `live-transcript-segment`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSegment {
+    /// Wall-clock span of the audio, ms since the Unix epoch.
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Transcribed segments, oldest first.
+#[derive(Debug, Default)]
+struct RollingTranscript {
+    segments: VecDeque<TranscriptSegment>,
+}
+
+impl RollingTranscript {
+    fn push(&mut self, segment: TranscriptSegment) {
+        let at = self
+            .segments
+            .partition_point(|s| s.start_ms <= segment.start_ms);
+        self.segments.insert(at, segment);
+    }
+
+    /// Drop segments that end at or before `oldest_ms`.
+    fn prune(&mut self, oldest_ms: u64) {
+        while self.segments.front().is_some_and(|s| s.end_ms <= oldest_ms) {
+            self.segments.pop_front();
+        }
+    }
+
+    /// Segments overlapping `[start_ms, end_ms)`.
+    fn between(&self, start_ms: u64, end_ms: u64) -> Vec<TranscriptSegment> {
+        self.segments
+            .iter()
+            .filter(|s| s.start_ms < end_ms && s.end_ms > start_ms)
+            .cloned()
+            .collect()
+    }
+
+    fn clear(&mut self) {
+        self.segments.clear();
+    }
+}
+
+/// The next segment of `segment_ms` after `cursor_ms`, if the buffer
+/// reaches `edge_ms` past its end.
+fn next_segment(cursor_ms: u64, edge_ms: u64, segment_ms: u64) -> Option<(u64, u64)> {
+    let end = cursor_ms.checked_add(segment_ms)?;
+    (end <= edge_ms).then_some((cursor_ms, end))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn config(app: &AppHandle) -> Result<LiveTranscriptConfig, String> {
+    Ok(app
+        .state::<LiveTranscriptState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+fn with_transcript<T>(app: &AppHandle, f: impl FnOnce(&mut RollingTranscript) -> T) -> T {
+    let state = app.state::<LiveTranscriptState>();
+    let mut transcript = state
+        .transcript
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(&mut transcript)
+}
+
+/// Wall-clock time of the oldest sample still in the buffer.
+fn oldest_ms(audio: &SystemAudioState) -> Option<u64> {
+    let retained = audio.status().ok()?.buffer_seconds as usize * OUTPUT_SAMPLE_RATE as usize;
+    audio.wall_time_ms_at(audio.written_position().saturating_sub(retained))
+}
+
+/// Transcribe `[start_ms, end_ms)`. `Ok(None)` if there's no audio to
+/// transcribe there, e.g. it's silent or has aged out.
+async fn transcribe(
+    app: &AppHandle,
+    audio: &Arc<SystemAudioState>,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<Option<String>, String> {
+    let audio = audio.clone();
+    let audio_base64 = match tauri::async_runtime::spawn_blocking(move || {
+        audio.get_audio_between_times_base64(start_ms, end_ms)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    {
+        Ok(audio_base64) => audio_base64,
+        Err(e) => {
+            tracing::debug!("Live transcript skipped {}..{}: {}", start_ms, end_ms, e);
+            return Ok(None);
+        }
+    };
+    api::transcribe(app.clone(), audio_base64)
+        .await?
+        .into_transcription()
+        .map(Some)
+        .ok_or_else(|| "Transcription failed".to_string())
+}
+
+/// Start the worker. Called once at startup; it does nothing until the
+/// live transcript is enabled.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+        // End of the transcribed audio; `None` until capture is seen.
+        let mut cursor_ms: Option<u64> = None;
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Ok(config) = config(&app) else {
+                continue;
+            };
+            if !config.enabled || !audio.is_recording() || audio.is_buffer_encrypted() {
+                cursor_ms = None;
+                with_transcript(&app, RollingTranscript::clear);
+                continue;
+            }
+            if offline_queue::is_offline(&app) {
+                continue;
+            }
+            let Some(oldest) = oldest_ms(&audio) else {
+                continue;
+            };
+            with_transcript(&app, |t| t.prune(oldest));
+            // Captions are transcribing this audio already and `record`
+            // their text; pick up at the live edge if they're turned off.
+            if captions::is_enabled(&app) {
+                cursor_ms = audio.wall_time_ms_at(audio.written_position());
+                continue;
+            }
+            // Catch up from the start of the buffer, or from where the
+            // buffer now starts if it has moved past the cursor.
+            let mut cursor = cursor_ms.unwrap_or(oldest).max(oldest);
+            let segment_ms = u64::from(config.segment_seconds) * 1000;
+            let edge = audio
+                .wall_time_ms_at(audio.written_position())
+                .unwrap_or(cursor);
+            while let Some((start, end)) = next_segment(cursor, edge, segment_ms) {
+                match transcribe(&app, &audio, start, end).await {
+                    Ok(text) => {
+                        cursor = end;
+                        let text = text.unwrap_or_default();
+                        if text.trim().is_empty() {
+                            continue;
+                        }
+                        let segment = TranscriptSegment {
+                            start_ms: start,
+                            end_ms: end,
+                            text: text.trim().to_string(),
+                        };
+                        let _ = app.emit("live-transcript-segment", &segment);
+                        with_transcript(&app, |t| t.push(segment));
+                    }
+                    Err(e) => {
+                        tracing::warn!("Live transcription failed: {}", e);
+                        tokio::time::sleep(RETRY_DELAY).await;
+                        break;
+                    }
+                }
+            }
+            cursor_ms = Some(cursor);
+        }
+    });
+}
+
+/// Add `segment`, transcribed by captions, to the transcript if it's being
+/// kept.
+pub(crate) fn record(app: &AppHandle, segment: TranscriptSegment) {
+    let keep = config(app).is_ok_and(|config| config.enabled)
+        && !app.state::<Arc<SystemAudioState>>().is_buffer_encrypted();
+    if !keep {
+        return;
+    }
+    let _ = app.emit("live-transcript-segment", &segment);
+    with_transcript(app, |t| t.push(segment));
+}
+
+#[tauri::command]
+pub fn live_transcript_set_config(
+    app: AppHandle,
+    config: LiveTranscriptConfig,
+) -> Result<(), String> {
+    config.validate()?;
+    if !config.enabled {
+        with_transcript(&app, RollingTranscript::clear);
+    }
+    *app.state::<LiveTranscriptState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn live_transcript_get_config(app: AppHandle) -> Result<LiveTranscriptConfig, String> {
+    config(&app)
+}
+
+/// What was said in the last `seconds` (everything transcribed if `None`),
+/// oldest first.
+#[tauri::command]
+pub fn live_transcript_recent(
+    app: AppHandle,
+    seconds: Option<f64>,
+) -> Result<Vec<TranscriptSegment>, String> {
+    let start_ms = match seconds {
+        None => 0,
+        Some(seconds) if seconds.is_finite() && seconds > 0.0 => {
+            now_ms().saturating_sub((seconds * 1000.0) as u64)
+        }
+        Some(seconds) => return Err(format!("seconds must be positive, got {}", seconds)),
+    };
+    Ok(with_transcript(&app, |t| t.between(start_ms, u64::MAX)))
+}
+
+/// What was said around `seconds_ago`: the segment covering that moment.
+#[tauri::command]
+pub fn live_transcript_at(
+    app: AppHandle,
+    seconds_ago: f64,
+) -> Result<Option<TranscriptSegment>, String> {
+    if !seconds_ago.is_finite() || seconds_ago < 0.0 {
+        return Err(format!(
+            "seconds_ago must not be negative, got {}",
+            seconds_ago
+        ));
+    }
+    let at_ms = now_ms().saturating_sub((seconds_ago * 1000.0) as u64);
+    Ok(with_transcript(&app, |t| t.between(at_ms, at_ms + 1))
+        .into_iter()
+        .next())
+}
+
+/// Segments overlapping the wall-clock span `[start_ms, end_ms)`, oldest
+/// first.
+#[tauri::command]
+pub fn live_transcript_between(
+    app: AppHandle,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<Vec<TranscriptSegment>, String> {
+    if start_ms >= end_ms {
+        return Err("start_ms must be before end_ms".to_string());
+    }
+    Ok(with_transcript(&app, |t| t.between(start_ms, end_ms)))
+}
+
+#[cfg(test)]
+mod tests;
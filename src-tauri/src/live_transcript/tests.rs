@@ -0,0 +1,53 @@
+use super::*;
+
+fn segment(start_ms: u64, end_ms: u64, text: &str) -> TranscriptSegment {
+    TranscriptSegment {
+        start_ms,
+        end_ms,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn only_complete_segments_are_due() {
+    assert_eq!(next_segment(1_000, 31_000, 30_000), Some((1_000, 31_000)));
+    assert_eq!(next_segment(1_000, 30_999, 30_000), None);
+    assert_eq!(next_segment(u64::MAX - 1, u64::MAX, 30_000), None);
+}
+
+#[test]
+fn segments_stay_in_order_and_age_out() {
+    let mut transcript = RollingTranscript::default();
+    transcript.push(segment(30, 60, "second"));
+    transcript.push(segment(0, 30, "first"));
+    transcript.push(segment(60, 90, "third"));
+    let texts =
+        |segments: Vec<TranscriptSegment>| segments.into_iter().map(|s| s.text).collect::<Vec<_>>();
+    assert_eq!(
+        texts(transcript.between(0, u64::MAX)),
+        ["first", "second", "third"]
+    );
+    // Overlap, not containment; ends are exclusive.
+    assert_eq!(texts(transcript.between(29, 31)), ["first", "second"]);
+    assert_eq!(texts(transcript.between(60, 61)), ["third"]);
+    assert!(transcript.between(90, 100).is_empty());
+
+    transcript.prune(59);
+    assert_eq!(texts(transcript.between(0, u64::MAX)), ["second", "third"]);
+    transcript.prune(60);
+    assert_eq!(texts(transcript.between(0, u64::MAX)), ["third"]);
+    transcript.clear();
+    assert!(transcript.between(0, u64::MAX).is_empty());
+}
+
+#[test]
+fn segment_length_is_bounded() {
+    assert!(LiveTranscriptConfig::default().validate().is_ok());
+    for segment_seconds in [0, 4, 601] {
+        let config = LiveTranscriptConfig {
+            segment_seconds,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,583 @@
+//! Meeting detection: notices when Zoom, Teams, Webex or Google Meet is
+//! running and using the microphone, and emits `meeting-started` /
+//! `meeting-ended` so the app can adapt during calls. With auto-capture on,
+//! system audio capture starts with a meeting-length buffer when a meeting
+//! begins, and when it ends the recording is saved to the recordings folder
+//! and capture stops.
+//!
+//! "In a meeting" means a known meeting app currently holds the microphone:
+//! On macOS: Core Audio process objects that are running input (14.0+).
+//! On Windows: the microphone consent store entries still in use.
+//! On Linux: PulseAudio/PipeWire source outputs via `pactl`.
+//! Google Meet is only recognised as its installed app; a Meet tab in an
+//! ordinary browser looks like any other browser audio.
+
+use crate::autosave;
+use crate::focus_mode::suppresses_auto_capture;
+use crate::system_audio::{start_automatic_session, SystemAudioState};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the watcher polls for microphone clients.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// A meeting ends only after its app has released the microphone for this
+/// long, so rejoining or switching devices doesn't end it.
+const END_GRACE: Duration = Duration::from_secs(10);
+/// Buffer length of auto-captured meetings: the longest the ring holds.
+const DEFAULT_MEETING_BUFFER_SECONDS: u32 = 300;
+/// History tag of auto-captured meetings.
+const HISTORY_TAG: &str = "meeting";
+
+/// Display name and identifiers of each supported meeting app: bundle IDs
+/// (helpers match by prefix) on macOS, executable names or package family
+/// names elsewhere. Compared case-insensitively.
+const MEETING_APPS: &[(&str, &[&str])] = &[
+    (
+        "Zoom",
+        &[
+            "us.zoom.xos",
+            "zoom.exe",
+            "cpthost.exe",
+            "zoom",
+            "zoom.real",
+        ],
+    ),
+    (
+        "Microsoft Teams",
+        &[
+            "com.microsoft.teams",
+            "com.microsoft.teams2",
+            "msteams_8wekyb3d8bbwe",
+            "ms-teams.exe",
+            "teams.exe",
+            "teams",
+            "teams-for-linux",
+        ],
+    ),
+    (
+        "Webex",
+        &[
+            "cisco-systems.spark",
+            "com.cisco.webexmeetingsapp",
+            "com.webex.meetingmanager",
+            "ciscocollabhost.exe",
+            "webexmta.exe",
+            "atmgr.exe",
+            "webex",
+        ],
+    ),
+    // The Meet progressive web app installed from Chrome.
+    (
+        "Google Meet",
+        &["com.google.chrome.app.kjgfgldnnfoeklkmfkjfagphfepbbdan"],
+    ),
+];
+
+/// A process currently recording from an input device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MicrophoneClient {
+    /// Bundle ID on macOS, executable or package name elsewhere.
+    pub app_id: String,
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ActiveMeeting {
+    /// Display name, e.g. "Zoom".
+    pub app: String,
+    pub app_id: String,
+    pub pid: Option<u32>,
+    pub started_at_ms: u64,
+}
+
+/// Payload of `meeting-started` and `meeting-ended`.
+#[derive(Clone, Serialize)]
+pub struct MeetingEvent {
+    pub meeting: ActiveMeeting,
+    /// Set on `meeting-ended`.
+    pub ended_at_ms: Option<u64>,
+}
+
+/// Display name of the meeting app `app_id` belongs to.
+pub fn meeting_app_name(app_id: &str) -> Option<&'static str> {
+    let app_id = app_id.to_ascii_lowercase();
+    MEETING_APPS
+        .iter()
+        .find(|(_, ids)| {
+            ids.iter().any(|id| {
+                app_id == *id
+                    || app_id
+                        .strip_prefix(id)
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+        })
+        .map(|(name, _)| *name)
+}
+
+/// The first microphone client that is a meeting app, if any.
+pub fn detect_meeting() -> Option<(&'static str, MicrophoneClient)> {
+    platform::microphone_clients()
+        .into_iter()
+        .find_map(|client| meeting_app_name(&client.app_id).map(|name| (name, client)))
+}
+
+/// Turns polls into start/end transitions, holding a meeting open through
+/// short gaps.
+#[derive(Default)]
+struct MeetingTracker {
+    current: Option<ActiveMeeting>,
+    last_seen: Option<Instant>,
+}
+
+enum MeetingChange {
+    Started(ActiveMeeting),
+    Ended(ActiveMeeting),
+}
+
+impl MeetingTracker {
+    fn update(
+        &mut self,
+        now: Instant,
+        now_ms: u64,
+        detected: Option<(&'static str, MicrophoneClient)>,
+    ) -> Option<MeetingChange> {
+        match (detected, &self.current) {
+            (Some(_), Some(_)) => {
+                self.last_seen = Some(now);
+                None
+            }
+            (Some((name, client)), None) => {
+                let meeting = ActiveMeeting {
+                    app: name.to_string(),
+                    app_id: client.app_id,
+                    pid: client.pid,
+                    started_at_ms: now_ms,
+                };
+                self.current = Some(meeting.clone());
+                self.last_seen = Some(now);
+                Some(MeetingChange::Started(meeting))
+            }
+            (None, Some(_)) => {
+                let seen = self.last_seen.unwrap_or(now);
+                if now.duration_since(seen) < END_GRACE {
+                    return None;
+                }
+                self.last_seen = None;
+                self.current.take().map(MeetingChange::Ended)
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// Meeting profile for automatic capture.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MeetingAutoCapture {
+    pub enabled: bool,
+    pub buffer_seconds: u32,
+}
+
+impl Default for MeetingAutoCapture {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            buffer_seconds: DEFAULT_MEETING_BUFFER_SECONDS,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MeetingState {
+    watching: AtomicBool,
+    /// Bumped on every start/stop so a superseded watcher thread exits.
+    generation: AtomicU64,
+    current: Mutex<Option<ActiveMeeting>>,
+    auto_capture: Mutex<MeetingAutoCapture>,
+    /// Capture session started for the current meeting, so capture the
+    /// user started themselves is never stopped.
+    auto_session: Mutex<Option<u64>>,
+}
+
+impl MeetingState {
+    pub fn current(&self) -> Option<ActiveMeeting> {
+        self.current.lock().ok().and_then(|m| m.clone())
+    }
+}
+
+/// Start capture for a meeting that just began, if auto-capture is on and
+/// nothing is recording yet.
+fn start_meeting_capture(app: &AppHandle) {
+    let state = app.state::<MeetingState>();
+    let Ok(profile) = state.auto_capture.lock().map(|p| *p) else {
+        return;
+    };
+    if !profile.enabled {
+        return;
+    }
+    if suppresses_auto_capture(app) {
+        tracing::info!("Meeting capture skipped: Focus mode is on");
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+        match start_automatic_session(app.clone(), &audio, profile.buffer_seconds).await {
+            Ok(session) => {
+                if let Ok(mut current) = app.state::<MeetingState>().auto_session.lock() {
+                    *current = session;
+                }
+            }
+            Err(e) => tracing::warn!("Failed to start meeting capture: {}", e),
+        }
+    });
+}
+
+/// Save and stop the capture `start_meeting_capture` started, if it's
+/// still running.
+fn stop_meeting_capture(app: &AppHandle) {
+    let session = app
+        .state::<MeetingState>()
+        .auto_session
+        .lock()
+        .ok()
+        .and_then(|mut s| s.take());
+    let Some(session) = session else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+        if autosave::save_and_stop_session(&app, &audio, session, HISTORY_TAG).await {
+            tracing::info!("Stopped meeting capture");
+        }
+    });
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Poll for meeting apps until the watcher is stopped or restarted.
+fn spawn_watcher(app: AppHandle, generation: u64) {
+    thread::spawn(move || {
+        let state = app.state::<MeetingState>();
+        let mut tracker = MeetingTracker::default();
+        while state.generation.load(Ordering::SeqCst) == generation {
+            let change = tracker.update(Instant::now(), now_millis(), detect_meeting());
+            if state.generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            if let Ok(mut current) = state.current.lock() {
+                *current = tracker.current.clone();
+            }
+            match change {
+                Some(MeetingChange::Started(meeting)) => {
+                    tracing::info!("Meeting started in {}", meeting.app);
+                    start_meeting_capture(&app);
+                    let _ = app.emit(
+                        "meeting-started",
+                        MeetingEvent {
+                            meeting,
+                            ended_at_ms: None,
+                        },
+                    );
+                }
+                Some(MeetingChange::Ended(meeting)) => {
+                    tracing::info!("Meeting in {} ended", meeting.app);
+                    stop_meeting_capture(&app);
+                    let _ = app.emit(
+                        "meeting-ended",
+                        MeetingEvent {
+                            meeting,
+                            ended_at_ms: Some(now_millis()),
+                        },
+                    );
+                }
+                None => {}
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Start watching for meetings. Does nothing if already watching.
+#[tauri::command]
+pub fn meeting_watch_start(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<MeetingState>();
+    if !state.watching.swap(true, Ordering::SeqCst) {
+        let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        spawn_watcher(app.clone(), generation);
+    }
+    Ok(())
+}
+
+/// Stop watching. A meeting in progress is forgotten without an end event,
+/// and capture started for it keeps running.
+#[tauri::command]
+pub fn meeting_watch_stop(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<MeetingState>();
+    state.watching.store(false, Ordering::SeqCst);
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    *state.current.lock().map_err(|e| e.to_string())? = None;
+    Ok(())
+}
+
+/// Whether a meeting is in progress: the watcher's view while it runs,
+/// otherwise a one-off check.
+#[tauri::command]
+pub async fn is_meeting_active(app: AppHandle) -> Result<bool, String> {
+    let state = app.state::<MeetingState>();
+    if state.watching.load(Ordering::SeqCst) {
+        return Ok(state.current().is_some());
+    }
+    tauri::async_runtime::spawn_blocking(|| detect_meeting().is_some())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Turn automatic meeting capture on or off. `buffer_seconds` (default 300)
+/// is the buffer length used for meetings. Turning it on starts the
+/// watcher; it takes effect from the next meeting that starts.
+#[tauri::command]
+pub fn meeting_set_auto_capture(
+    app: AppHandle,
+    enabled: bool,
+    buffer_seconds: Option<u32>,
+) -> Result<MeetingAutoCapture, String> {
+    let profile = MeetingAutoCapture {
+        enabled,
+        buffer_seconds: buffer_seconds.unwrap_or(DEFAULT_MEETING_BUFFER_SECONDS),
+    };
+    if profile.buffer_seconds == 0 {
+        return Err("buffer_seconds must be positive".to_string());
+    }
+    *app.state::<MeetingState>()
+        .auto_capture
+        .lock()
+        .map_err(|e| e.to_string())? = profile;
+    if enabled {
+        meeting_watch_start(app)?;
+    }
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn meeting_get_auto_capture(app: AppHandle) -> Result<MeetingAutoCapture, String> {
+    let state = app.state::<MeetingState>();
+    let profile = state.auto_capture.lock().map_err(|e| e.to_string())?;
+    Ok(*profile)
+}
+
+/// The meeting in progress, as seen by the watcher.
+#[tauri::command]
+pub fn get_active_meeting(app: AppHandle) -> Option<ActiveMeeting> {
+    app.state::<MeetingState>().current()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::MicrophoneClient;
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2_foundation::NSString;
+    use std::ffi::{c_void, CStr};
+    use std::ptr;
+
+    type AudioObjectID = u32;
+
+    #[repr(C)]
+    struct AudioObjectPropertyAddress {
+        m_selector: u32,
+        m_scope: u32,
+        m_element: u32,
+    }
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        fn AudioObjectGetPropertyData(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            data_size: *mut u32,
+            data: *mut c_void,
+        ) -> i32;
+    }
+
+    const K_AUDIO_OBJECT_SYSTEM_OBJECT: AudioObjectID = 1;
+    const K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL: u32 = 0x676c_6f62; // 'glob'
+    const K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN: u32 = 0;
+    const K_AUDIO_HARDWARE_PROPERTY_PROCESS_OBJECT_LIST: u32 = 0x7072_7323; // 'prs#'
+    const K_AUDIO_PROCESS_PROPERTY_PID: u32 = 0x7070_6964; // 'ppid'
+    const K_AUDIO_PROCESS_PROPERTY_IS_RUNNING_INPUT: u32 = 0x7069_7269; // 'piri'
+    /// More audio clients than any real system has at once.
+    const MAX_PROCESS_OBJECTS: usize = 512;
+
+    unsafe fn get<T: Default>(object_id: AudioObjectID, selector: u32) -> Option<T> {
+        let address = AudioObjectPropertyAddress {
+            m_selector: selector,
+            m_scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+            m_element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+        };
+        let mut value = T::default();
+        let mut size = std::mem::size_of::<T>() as u32;
+        let status = AudioObjectGetPropertyData(
+            object_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            (&mut value as *mut T).cast(),
+        );
+        (status == 0).then_some(value)
+    }
+
+    unsafe fn process_objects() -> Vec<AudioObjectID> {
+        let address = AudioObjectPropertyAddress {
+            m_selector: K_AUDIO_HARDWARE_PROPERTY_PROCESS_OBJECT_LIST,
+            m_scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+            m_element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+        };
+        let mut objects = vec![0 as AudioObjectID; MAX_PROCESS_OBJECTS];
+        let mut size = std::mem::size_of_val(objects.as_slice()) as u32;
+        let status = AudioObjectGetPropertyData(
+            K_AUDIO_OBJECT_SYSTEM_OBJECT,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            objects.as_mut_ptr().cast(),
+        );
+        if status != 0 {
+            return Vec::new();
+        }
+        objects.truncate(size as usize / std::mem::size_of::<AudioObjectID>());
+        objects
+    }
+
+    unsafe fn bundle_id(pid: i32) -> Option<String> {
+        let cls_name = CStr::from_bytes_with_nul(b"NSRunningApplication\0").ok()?;
+        let cls = AnyClass::get(cls_name)?;
+        let app: Option<Retained<AnyObject>> =
+            msg_send![cls, runningApplicationWithProcessIdentifier: pid];
+        let app = app?;
+        let bundle_id: Option<Retained<NSString>> = msg_send![&*app, bundleIdentifier];
+        bundle_id.map(|b| b.to_string())
+    }
+
+    pub fn microphone_clients() -> Vec<MicrophoneClient> {
+        unsafe {
+            process_objects()
+                .into_iter()
+                .filter(|&object| {
+                    get::<u32>(object, K_AUDIO_PROCESS_PROPERTY_IS_RUNNING_INPUT) == Some(1)
+                })
+                .filter_map(|object| {
+                    let pid = get::<i32>(object, K_AUDIO_PROCESS_PROPERTY_PID)?;
+                    Some(MicrophoneClient {
+                        app_id: bundle_id(pid)?,
+                        pid: u32::try_from(pid).ok(),
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::MicrophoneClient;
+    use crate::hidden_command;
+
+    const CONSENT_STORE: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone";
+
+    /// Apps whose consent store entry has a start time but no stop time are
+    /// using the microphone right now. Desktop apps live under
+    /// `NonPackaged` with `#` for path separators; packaged apps are keyed
+    /// by package family name.
+    pub fn microphone_clients() -> Vec<MicrophoneClient> {
+        let Ok(output) = hidden_command::new("reg")
+            .args(["query", CONSENT_STORE, "/s", "/v", "LastUsedTimeStop"])
+            .output()
+        else {
+            return Vec::new();
+        };
+        let mut clients = Vec::new();
+        let mut key: Option<String> = None;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if line.starts_with("HKEY_") {
+                key = line.rsplit('\\').next().map(|k| k.to_string());
+            } else if line.trim_start().starts_with("LastUsedTimeStop") {
+                let in_use = line.split_whitespace().last() == Some("0x0");
+                if let Some(key) = key.take().filter(|_| in_use) {
+                    let app_id = key.rsplit('#').next().unwrap_or(&key).to_string();
+                    clients.push(MicrophoneClient { app_id, pid: None });
+                }
+            }
+        }
+        clients
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::MicrophoneClient;
+    use std::process::Command;
+
+    /// `pactl list source-outputs`: one block per recording stream, with
+    /// `application.process.binary = "zoom"` among its properties.
+    pub fn microphone_clients() -> Vec<MicrophoneClient> {
+        let Ok(output) = Command::new("pactl")
+            .args(["list", "source-outputs"])
+            .output()
+        else {
+            return Vec::new();
+        };
+        let mut clients: Vec<MicrophoneClient> = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let line = line.trim();
+            if line.starts_with("Source Output #") {
+                clients.push(MicrophoneClient {
+                    app_id: String::new(),
+                    pid: None,
+                });
+                continue;
+            }
+            let Some((name, value)) = line.split_once(" = ") else {
+                continue;
+            };
+            let value = value.trim_matches('"');
+            let Some(client) = clients.last_mut() else {
+                continue;
+            };
+            match name {
+                "application.process.binary" => client.app_id = value.to_string(),
+                "application.process.id" => client.pid = value.parse().ok(),
+                _ => {}
+            }
+        }
+        clients.retain(|c| !c.app_id.is_empty());
+        clients
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    use super::MicrophoneClient;
+
+    pub fn microphone_clients() -> Vec<MicrophoneClient> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests;
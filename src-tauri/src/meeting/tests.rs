@@ -0,0 +1,56 @@
+use super::*;
+
+fn client(app_id: &str) -> MicrophoneClient {
+    MicrophoneClient {
+        app_id: app_id.to_string(),
+        pid: Some(42),
+    }
+}
+
+#[test]
+fn meeting_apps_match_ids_and_helpers() {
+    assert_eq!(meeting_app_name("us.zoom.xos"), Some("Zoom"));
+    assert_eq!(meeting_app_name("Zoom.exe"), Some("Zoom"));
+    assert_eq!(
+        meeting_app_name("com.microsoft.teams2.helper"),
+        Some("Microsoft Teams")
+    );
+    assert_eq!(
+        meeting_app_name("MSTeams_8wekyb3d8bbwe"),
+        Some("Microsoft Teams")
+    );
+    assert_eq!(meeting_app_name("Cisco-Systems.Spark"), Some("Webex"));
+    assert_eq!(meeting_app_name("com.google.Chrome"), None);
+    assert_eq!(meeting_app_name("us.zoom.xosfake"), None);
+}
+
+#[test]
+fn meeting_ends_only_after_grace_period() {
+    let mut tracker = MeetingTracker::default();
+    let t0 = Instant::now();
+    let zoom = || Some(("Zoom", client("us.zoom.xos")));
+
+    match tracker.update(t0, 1_000, zoom()) {
+        Some(MeetingChange::Started(meeting)) => {
+            assert_eq!(meeting.app, "Zoom");
+            assert_eq!(meeting.started_at_ms, 1_000);
+        }
+        _ => panic!("expected a start"),
+    }
+    assert!(tracker.update(t0 + POLL_INTERVAL, 3_000, zoom()).is_none());
+
+    // A short gap keeps the meeting open.
+    let gap = t0 + POLL_INTERVAL + END_GRACE / 2;
+    assert!(tracker.update(gap, 8_000, None).is_none());
+    assert!(tracker.current.is_some());
+
+    let later = t0 + POLL_INTERVAL + END_GRACE;
+    match tracker.update(later, 13_000, None) {
+        Some(MeetingChange::Ended(meeting)) => assert_eq!(meeting.started_at_ms, 1_000),
+        _ => panic!("expected an end"),
+    }
+    assert!(tracker.current.is_none());
+    assert!(tracker
+        .update(later + POLL_INTERVAL, 15_000, None)
+        .is_none());
+}
@@ -0,0 +1,121 @@
+//! Moment capture: a screenshot of the primary monitor, the last few
+//! seconds of system audio and its transcription, assembled into one
+//! context for the assistant.
+//!
+//! Each part is captured independently; a part that fails is left out and
+//! its error reported, so a missing screen permission doesn't also lose the
+//! audio. The screenshot is skipped the same way while a password is typed
+//! or a blocklisted app is in front.
+
+use crate::privacy::screen_capture_blocked;
+use crate::system_audio::{RecentAudio, SystemAudioState};
+use base64::Engine;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Audio included when the caller doesn't say.
+pub const DEFAULT_MOMENT_SECONDS: f64 = 30.0;
+
+/// What started a moment capture.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MomentTrigger {
+    Manual,
+    WakeWord,
+}
+
+/// Payload of `moment-captured` and result of `capture_moment`.
+#[derive(Clone, Serialize)]
+pub struct MomentContext {
+    pub trigger: MomentTrigger,
+    pub captured_at_ms: u64,
+    /// Primary monitor as base64 JPEG.
+    pub screenshot_base64: Option<String>,
+    pub audio: Option<RecentAudio>,
+    pub transcription: Option<String>,
+    /// One message per part that could not be captured.
+    pub errors: Vec<String>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Capture the screen and the last `seconds` of audio, then transcribe the
+/// audio. Never fails as a whole; see `MomentContext::errors`.
+pub async fn assemble_moment(
+    app: &AppHandle,
+    seconds: f64,
+    trigger: MomentTrigger,
+) -> MomentContext {
+    let mut context = MomentContext {
+        trigger,
+        captured_at_ms: now_millis(),
+        screenshot_base64: None,
+        audio: None,
+        transcription: None,
+        errors: Vec::new(),
+    };
+
+    let state = app.state::<Arc<SystemAudioState>>().inner().clone();
+    let blocked = screen_capture_blocked(app);
+    let screenshot = tauri::async_runtime::spawn_blocking(move || match blocked {
+        Some(reason) => Err(reason.to_string()),
+        None => crate::capture::capture_primary_monitor(),
+    });
+    let audio = tauri::async_runtime::spawn_blocking(move || state.get_last_timed(seconds));
+
+    match screenshot.await.map_err(|e| e.to_string()).and_then(|r| r) {
+        Ok(jpeg) => {
+            context.screenshot_base64 =
+                Some(base64::engine::general_purpose::STANDARD.encode(&jpeg))
+        }
+        Err(e) => context.errors.push(format!("Screenshot: {}", e)),
+    }
+    let audio = match audio.await.map_err(|e| e.to_string()).and_then(|r| r) {
+        Ok(audio) => RecentAudio {
+            audio_base64: base64::engine::general_purpose::STANDARD.encode(&audio.ogg),
+            start_time_ms: audio.start_time_ms,
+            end_time_ms: audio.end_time_ms,
+        },
+        Err(e) => {
+            context.errors.push(format!("Audio: {}", e));
+            return context;
+        }
+    };
+
+    match crate::api::transcribe_audio(app.clone(), audio.audio_base64.clone()).await {
+        Ok(response) => context.transcription = response.into_transcription(),
+        Err(e) => context.errors.push(format!("Transcription: {}", e)),
+    }
+    context.audio = Some(audio);
+    crate::webhook::moment_captured(app, &context);
+    context
+}
+
+/// Assemble a moment in the background and emit it as `moment-captured`.
+pub fn capture_moment_in_background(app: AppHandle, seconds: f64, trigger: MomentTrigger) {
+    tauri::async_runtime::spawn(async move {
+        let context = assemble_moment(&app, seconds, trigger).await;
+        if !context.errors.is_empty() {
+            tracing::warn!("Moment capture incomplete: {}", context.errors.join("; "));
+        }
+        let _ = app.emit("moment-captured", context);
+    });
+}
+
+/// Capture a moment now: screenshot, the last `seconds` of audio (default
+/// 30) and its transcription.
+#[tauri::command]
+pub async fn capture_moment(app: AppHandle, seconds: Option<f64>) -> Result<MomentContext, String> {
+    let seconds = seconds.unwrap_or(DEFAULT_MOMENT_SECONDS);
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!("seconds must be positive, got {}", seconds));
+    }
+    Ok(assemble_moment(&app, seconds, MomentTrigger::Manual).await)
+}
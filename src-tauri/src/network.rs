@@ -0,0 +1,214 @@
+//! Outbound HTTP: an optional HTTP(S) or SOCKS proxy for every call the
+//! app makes, and per-provider base URL overrides and extra headers for AI,
+//! STT and TTS providers, for corporate networks and self-hosted gateways.
+//!
+//! A proxy password, if any, lives in the credential store under
+//! `PROXY_PASSWORD_SECRET` rather than in the config. It's read when the
+//! config is set, so store it before calling `network_set_config`.
+
+use crate::secrets;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, NoProxy, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+pub const PROXY_PASSWORD_SECRET: &str = "network.proxy.password";
+
+const PROXY_SCHEMES: [&str; 4] = ["http", "https", "socks5", "socks5h"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` (DNS through the
+    /// proxy) URL.
+    pub url: String,
+    /// Basic auth user; the password is `PROXY_PASSWORD_SECRET`.
+    pub username: Option<String>,
+    /// Hosts, domains (`.corp.example`) and CIDRs that bypass the proxy.
+    pub no_proxy: Vec<String>,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            username: None,
+            no_proxy: vec!["localhost".to_string(), "127.0.0.1".to_string()],
+        }
+    }
+}
+
+impl ProxyConfig {
+    fn validate(&self) -> Result<(), String> {
+        let url = Url::parse(&self.url).map_err(|e| format!("Invalid proxy URL: {}", e))?;
+        if !PROXY_SCHEMES.contains(&url.scheme()) {
+            return Err(format!(
+                "Proxy URL must be one of {}, got {}",
+                PROXY_SCHEMES.join(", "),
+                url.scheme()
+            ));
+        }
+        if url.host_str().is_none() {
+            return Err("Proxy URL has no host".to_string());
+        }
+        if self.username.as_ref().is_some_and(|u| u.trim().is_empty()) {
+            return Err("Proxy username must not be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProviderNetwork {
+    /// Replaces the scheme, host and leading path of the provider's URLs,
+    /// e.g. `https://gateway.corp.example/openai`.
+    pub base_url: Option<String>,
+    /// Sent with every request to the provider, unless the request sets
+    /// the header itself.
+    pub headers: BTreeMap<String, String>,
+}
+
+impl ProviderNetwork {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(base_url) = &self.base_url {
+            let url = Url::parse(base_url).map_err(|e| format!("Invalid base URL: {}", e))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(format!("Base URL must be http or https: {}", base_url));
+            }
+            if url.query().is_some() || url.fragment().is_some() {
+                return Err(format!("Base URL can't have a query: {}", base_url));
+            }
+        }
+        header_map(&self.headers).map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkConfig {
+    pub proxy: Option<ProxyConfig>,
+    /// Overrides by provider id, e.g. `"openai"`.
+    pub providers: HashMap<String, ProviderNetwork>,
+}
+
+impl NetworkConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(proxy) = &self.proxy {
+            proxy.validate()?;
+        }
+        for (provider, network) in &self.providers {
+            network
+                .validate()
+                .map_err(|e| format!("{} ({})", e, provider))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct NetworkState {
+    config: Mutex<NetworkConfig>,
+    /// Read with the config, so building a client never waits on the
+    /// credential store.
+    proxy_password: Mutex<Option<String>>,
+}
+
+fn header_map(headers: &BTreeMap<String, String>) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("Invalid header name: {:?}", name))?;
+        let mut value = HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("Invalid value for header {}", name))?;
+        value.set_sensitive(true);
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+/// `url` moved onto `base_url`: its path is appended to the base's, and its
+/// query kept.
+fn rebase_url(url: &str, base_url: &str) -> Result<String, String> {
+    let url = Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    let mut rebased =
+        Url::parse(base_url).map_err(|e| format!("Invalid base URL {}: {}", base_url, e))?;
+    let path = format!("{}{}", rebased.path().trim_end_matches('/'), url.path());
+    rebased.set_path(&path);
+    rebased.set_query(url.query());
+    Ok(rebased.to_string())
+}
+
+fn network_config(app: &AppHandle) -> Result<NetworkConfig, String> {
+    app.state::<NetworkState>()
+        .config
+        .lock()
+        .map(|config| config.clone())
+        .map_err(|e| e.to_string())
+}
+
+/// An HTTP client going through the configured proxy, sending `provider`'s
+/// extra headers.
+pub fn client(app: &AppHandle, provider: Option<&str>) -> Result<Client, String> {
+    let config = network_config(app)?;
+    let mut builder = Client::builder();
+    if let Some(proxy) = &config.proxy {
+        let mut proxied = Proxy::all(&proxy.url)
+            .map_err(|e| format!("Invalid proxy URL: {}", e))?
+            .no_proxy(NoProxy::from_string(&proxy.no_proxy.join(",")));
+        if let Some(username) = &proxy.username {
+            let password = app
+                .state::<NetworkState>()
+                .proxy_password
+                .lock()
+                .map_err(|e| e.to_string())?
+                .clone()
+                .unwrap_or_default();
+            proxied = proxied.basic_auth(username, &password);
+        }
+        builder = builder.proxy(proxied);
+    }
+    if let Some(network) = provider.and_then(|p| config.providers.get(p)) {
+        builder = builder.default_headers(header_map(&network.headers)?);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// `url` with `provider`'s base URL override applied, if it has one.
+pub fn provider_url(app: &AppHandle, provider: Option<&str>, url: &str) -> Result<String, String> {
+    let config = network_config(app)?;
+    match provider
+        .and_then(|p| config.providers.get(p))
+        .and_then(|network| network.base_url.as_deref())
+    {
+        Some(base_url) => rebase_url(url, base_url),
+        None => Ok(url.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn network_set_config(app: AppHandle, config: NetworkConfig) -> Result<(), String> {
+    config.validate()?;
+    let proxy_password = match config.proxy.as_ref().and_then(|p| p.username.as_ref()) {
+        Some(_) => tauri::async_runtime::spawn_blocking(|| secrets::get(PROXY_PASSWORD_SECRET))
+            .await
+            .map_err(|e| e.to_string())??,
+        None => None,
+    };
+    let state = app.state::<NetworkState>();
+    *state.config.lock().map_err(|e| e.to_string())? = config;
+    *state.proxy_password.lock().map_err(|e| e.to_string())? = proxy_password;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn network_get_config(app: AppHandle) -> Result<NetworkConfig, String> {
+    network_config(&app)
+}
+
+#[cfg(test)]
+mod tests;
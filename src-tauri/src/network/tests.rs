@@ -0,0 +1,64 @@
+use super::*;
+
+#[test]
+fn urls_are_rebased_onto_the_override() {
+    assert_eq!(
+        rebase_url(
+            "https://api.openai.com/v1/chat/completions",
+            "https://gateway.corp.example/openai/"
+        ),
+        Ok("https://gateway.corp.example/openai/v1/chat/completions".to_string())
+    );
+    assert_eq!(
+        rebase_url(
+            "https://api.elevenlabs.io/v1/text-to-speech/abc/stream?x=1",
+            "http://10.0.0.5:8080"
+        ),
+        Ok("http://10.0.0.5:8080/v1/text-to-speech/abc/stream?x=1".to_string())
+    );
+    assert!(rebase_url("https://api.openai.com/v1", "not a url").is_err());
+}
+
+#[test]
+fn proxies_need_a_supported_scheme() {
+    let proxy = |url: &str| ProxyConfig {
+        url: url.to_string(),
+        ..ProxyConfig::default()
+    };
+    assert!(proxy("http://proxy.corp:3128").validate().is_ok());
+    assert!(proxy("socks5h://127.0.0.1:1080").validate().is_ok());
+    assert!(proxy("ftp://proxy.corp").validate().is_err());
+    assert!(proxy("proxy.corp:3128").validate().is_err());
+}
+
+#[test]
+fn provider_overrides_are_validated() {
+    let mut network = ProviderNetwork {
+        base_url: Some("https://gateway.corp.example/v1".to_string()),
+        headers: BTreeMap::from([("X-Gateway-Key".to_string(), "abc".to_string())]),
+    };
+    assert!(network.validate().is_ok());
+
+    network.base_url = Some("ws://gateway.corp.example".to_string());
+    assert!(network.validate().is_err());
+
+    network.base_url = None;
+    network
+        .headers
+        .insert("Bad Header".to_string(), "x".to_string());
+    assert!(network.validate().is_err());
+
+    let config = NetworkConfig {
+        proxy: None,
+        providers: HashMap::from([("openai".to_string(), network)]),
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn headers_are_marked_sensitive() {
+    let headers = BTreeMap::from([("Authorization".to_string(), " Bearer x ".to_string())]);
+    let map = header_map(&headers).unwrap();
+    assert_eq!(map["authorization"], "Bearer x");
+    assert!(map["authorization"].is_sensitive());
+}
@@ -0,0 +1,225 @@
+//! Opt-in debug log of provider requests and responses, for answering
+//! "why did my transcription fail" from the app. Credentials, audio and
+//! images are redacted before anything is kept, bodies are cut to
+//! `MAX_BODY_BYTES`, and only the last `MAX_ENTRIES` calls are held, in
+//! memory. Turning the log off clears it.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{Request, Url};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const MAX_ENTRIES: usize = 100;
+const MAX_BODY_BYTES: usize = 16 * 1024;
+pub(crate) const REDACTED: &str = "[redacted]";
+
+/// Header, query and JSON field names whose values are credentials.
+const SENSITIVE_NAMES: [&str; 7] = [
+    "auth",
+    "key",
+    "token",
+    "secret",
+    "password",
+    "cookie",
+    "signature",
+];
+
+static DATA_URL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"data:([\w.+-]+/[\w.+-]+);base64,([A-Za-z0-9+/=]+)").unwrap());
+/// Bare base64 payloads (audio, images) too long to be anything else.
+static BASE64_BLOB: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Za-z0-9+/]{256,}={0,2}").unwrap());
+static SECRET_FIELD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)("[\w-]*(?:key|token|secret|password)[\w-]*"\s*:\s*)"[^"]*""#).unwrap()
+});
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkLogEntry {
+    pub id: u64,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub provider: Option<String>,
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Option<String>,
+    pub status: Option<u16>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Default)]
+pub struct NetworkLogState {
+    enabled: AtomicBool,
+    entries: Mutex<VecDeque<NetworkLogEntry>>,
+    next_id: AtomicU64,
+}
+
+/// A logged request waiting for its outcome; a no-op while the log is off.
+pub struct PendingRequest(Option<(AppHandle, Instant, NetworkLogEntry)>);
+
+impl PendingRequest {
+    /// Record how the request ended. `response_body` is redacted like the
+    /// request's.
+    pub fn finish(self, status: Option<u16>, response_body: Option<&str>, error: Option<&str>) {
+        let Some((app, started, mut entry)) = self.0 else {
+            return;
+        };
+        entry.status = status;
+        entry.response_body = response_body.map(redact_body);
+        entry.error = error.map(redact_body);
+        entry.duration_ms = started.elapsed().as_millis() as u64;
+        let state = app.state::<NetworkLogState>();
+        if !state.enabled.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Ok(mut entries) = state.entries.lock() {
+            if entries.len() == MAX_ENTRIES {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub(crate) fn is_sensitive(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_NAMES.iter().any(|s| name.contains(s))
+}
+
+/// `url` with sensitive query values and any userinfo replaced.
+fn redact_url(url: &Url) -> String {
+    let mut url = url.clone();
+    if url.password().is_some() {
+        let _ = url.set_password(Some(REDACTED));
+    }
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = match is_sensitive(&name) {
+                    true => REDACTED.to_string(),
+                    false => value.into_owned(),
+                };
+                (name.into_owned(), value)
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url.to_string()
+}
+
+/// Cut `text` to `MAX_BODY_BYTES` on a char boundary, noting the cut.
+fn truncate(text: String) -> String {
+    if text.len() <= MAX_BODY_BYTES {
+        return text;
+    }
+    let mut end = MAX_BODY_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… [{} more bytes]", &text[..end], text.len() - end)
+}
+
+/// `body` without data URLs, base64 payloads or credential fields.
+pub(crate) fn redact_body(body: &str) -> String {
+    let body = DATA_URL.replace_all(body, |caps: &regex::Captures| {
+        format!(
+            "data:{};base64,[{} chars redacted]",
+            &caps[1],
+            caps[2].len()
+        )
+    });
+    let body = BASE64_BLOB.replace_all(&body, |caps: &regex::Captures| {
+        format!("[{} chars of base64 redacted]", caps[0].len())
+    });
+    let body = SECRET_FIELD.replace_all(&body, format!("$1\"{}\"", REDACTED));
+    truncate(body.into_owned())
+}
+
+/// Start logging `request` to `provider`, if the log is on.
+pub fn start(app: &AppHandle, provider: Option<&str>, request: &Request) -> PendingRequest {
+    let state = app.state::<NetworkLogState>();
+    if !state.enabled.load(Ordering::SeqCst) {
+        return PendingRequest(None);
+    }
+    let request_headers = request
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.is_sensitive() || is_sensitive(name.as_str()) {
+                true => REDACTED.to_string(),
+                false => String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            };
+            (name.to_string(), value)
+        })
+        .collect();
+    // Multipart forms (audio uploads) are streamed, so never kept.
+    let request_body = match request.body().and_then(|body| body.as_bytes()) {
+        Some(bytes) => Some(redact_body(&String::from_utf8_lossy(bytes))),
+        None if request.body().is_some() => Some("[streamed body not logged]".to_string()),
+        None => None,
+    };
+    let entry = NetworkLogEntry {
+        id: state.next_id.fetch_add(1, Ordering::SeqCst),
+        timestamp: now_millis(),
+        provider: provider.map(str::to_string),
+        method: request.method().to_string(),
+        url: redact_url(request.url()),
+        request_headers,
+        request_body,
+        status: None,
+        response_body: None,
+        error: None,
+        duration_ms: 0,
+    };
+    PendingRequest(Some((app.clone(), Instant::now(), entry)))
+}
+
+#[tauri::command]
+pub fn network_log_set_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let state = app.state::<NetworkLogState>();
+    state.enabled.store(enabled, Ordering::SeqCst);
+    if !enabled {
+        state.entries.lock().map_err(|e| e.to_string())?.clear();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn network_log_get_enabled(app: AppHandle) -> bool {
+    app.state::<NetworkLogState>()
+        .enabled
+        .load(Ordering::SeqCst)
+}
+
+/// Logged calls, oldest first.
+#[tauri::command]
+pub fn network_log_entries(app: AppHandle) -> Result<Vec<NetworkLogEntry>, String> {
+    let state = app.state::<NetworkLogState>();
+    let entries = state.entries.lock().map_err(|e| e.to_string())?;
+    Ok(entries.iter().cloned().collect())
+}
+
+#[tauri::command]
+pub fn network_log_clear(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<NetworkLogState>();
+    state.entries.lock().map_err(|e| e.to_string())?.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
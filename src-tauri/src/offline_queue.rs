@@ -0,0 +1,455 @@
+//! Offline queue: transcription and chat requests made while the network is
+//! down are saved to `<app data>/offline_queue.json` and replayed, oldest
+//! first, once the app endpoint is reachable again, so a hotkey capture on
+//! flaky Wi-Fi isn't lost.
+//!
+//! The app counts as offline after a request fails to connect or times
+//! out; a watcher then probes the endpoint every `PROBE_INTERVAL` until it
+//! answers. Events: `offline-status` when connectivity changes,
+//! `offline-request-queued`, and `offline-request-completed` with each
+//! replayed request's result. Replayed chats don't stream chunks.
+
+use crate::network;
+use crate::system_audio::SystemAudioState;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+const QUEUE_FILE: &str = "offline_queue.json";
+/// Requests queued past this many are refused; each may carry audio.
+const MAX_QUEUED: usize = 20;
+/// Requests older than this are dropped instead of replayed.
+const MAX_AGE_MS: u64 = 24 * 60 * 60 * 1000;
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum QueuedRequest {
+    Transcription {
+        audio_base64: String,
+    },
+    Chat {
+        user_message: String,
+        system_prompt: Option<String>,
+        image_base64: Option<serde_json::Value>,
+        audio_base64: Option<String>,
+        history: Option<String>,
+    },
+}
+
+impl QueuedRequest {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Transcription { .. } => "transcription",
+            Self::Chat { .. } => "chat",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct QueuedItem {
+    id: String,
+    queued_at_ms: u64,
+    #[serde(flatten)]
+    request: QueuedRequest,
+}
+
+/// A queued request without its payload.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedSummary {
+    pub id: String,
+    pub kind: &'static str,
+    pub queued_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineStatus {
+    pub online: bool,
+    pub queued: Vec<QueuedSummary>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletedEvent {
+    pub id: String,
+    pub kind: &'static str,
+    /// The transcript or chat response.
+    pub result: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct OfflineState {
+    offline: AtomicBool,
+    /// Whether the probe/replay task is running.
+    watcher_active: AtomicBool,
+    /// Loaded from disk by `init`.
+    queue: Mutex<Vec<QueuedItem>>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn queue_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join(QUEUE_FILE))
+}
+
+/// The saved queue; empty if there is none or it can't be read.
+fn load(path: &Path) -> Vec<QueuedItem> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Replace the inherited ACL on `path` with one granting only its owner
+/// access, the Windows counterpart of mode 0600.
+#[cfg(target_os = "windows")]
+fn restrict_to_owner(path: &Path) -> std::io::Result<()> {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl: *const u16,
+            revision: u32,
+            descriptor: *mut *mut c_void,
+            size: *mut u32,
+        ) -> i32;
+        fn SetFileSecurityW(file: *const u16, information: u32, descriptor: *mut c_void) -> i32;
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LocalFree(memory: *mut c_void) -> *mut c_void;
+    }
+
+    const SDDL_REVISION_1: u32 = 1;
+    const DACL_SECURITY_INFORMATION: u32 = 0x4;
+    // Protected DACL, so nothing is inherited from the folder: full
+    // access for the file's owner and no one else.
+    let sddl: Vec<u16> = "D:P(A;;FA;;;OW)".encode_utf16().chain(Some(0)).collect();
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut descriptor = ptr::null_mut();
+    unsafe {
+        if ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl.as_ptr(),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            ptr::null_mut(),
+        ) == 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        let set = SetFileSecurityW(path.as_ptr(), DACL_SECURITY_INFORMATION, descriptor);
+        let error = std::io::Error::last_os_error();
+        LocalFree(descriptor);
+        if set == 0 {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Write `items` to `path` readable only by the user, replacing it whole.
+fn save(path: &Path, items: &[QueuedItem]) -> Result<(), String> {
+    use std::io::Write;
+    if items.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let bytes = serde_json::to_vec(items).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&tmp)
+        .and_then(|mut file| {
+            // Before any bytes land, so the queue is never readable by others.
+            #[cfg(target_os = "windows")]
+            restrict_to_owner(&tmp)?;
+            file.write_all(&bytes)
+        })
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| format!("Failed to save the offline queue: {}", e))
+}
+
+/// Remove and return items queued more than `MAX_AGE_MS` before `now_ms`.
+fn take_expired(items: &mut Vec<QueuedItem>, now_ms: u64) -> Vec<QueuedItem> {
+    let (expired, kept) = items
+        .drain(..)
+        .partition(|item| now_ms.saturating_sub(item.queued_at_ms) > MAX_AGE_MS);
+    *items = kept;
+    expired
+}
+
+/// Apply `change` to the queue and save it.
+fn update_queue<T>(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Vec<QueuedItem>) -> T,
+) -> Result<T, String> {
+    let path = queue_path(app)?;
+    let state = app.state::<OfflineState>();
+    let mut queue = state.queue.lock().map_err(|e| e.to_string())?;
+    let result = change(&mut queue);
+    save(&path, &queue)?;
+    Ok(result)
+}
+
+fn set_online(app: &AppHandle, online: bool) {
+    let state = app.state::<OfflineState>();
+    if state.offline.swap(!online, Ordering::SeqCst) == online {
+        let _ = app.emit("offline-status", serde_json::json!({ "online": online }));
+    }
+}
+
+pub fn is_offline(app: &AppHandle) -> bool {
+    app.state::<OfflineState>().offline.load(Ordering::SeqCst)
+}
+
+/// Note a failed request: one that couldn't connect or timed out means
+/// the network is down.
+pub fn observe_error(app: &AppHandle, error: &reqwest::Error) {
+    if error.is_connect() || error.is_timeout() {
+        set_online(app, false);
+        spawn_watcher(app.clone());
+    }
+}
+
+/// Queue `request` for when the network is back; returns its id. Refused
+/// while the capture buffer is encrypted, since the queue is saved to disk
+/// as it is.
+pub fn enqueue(app: &AppHandle, request: QueuedRequest) -> Result<String, String> {
+    if app.state::<Arc<SystemAudioState>>().is_buffer_encrypted() {
+        return Err(
+            "Offline, and requests can't be queued while the capture buffer is encrypted"
+                .to_string(),
+        );
+    }
+    let kind = request.kind();
+    let id = update_queue(app, |queue| {
+        if queue.len() >= MAX_QUEUED {
+            return Err(format!(
+                "Offline and {} requests are already waiting; try again once online",
+                MAX_QUEUED
+            ));
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        queue.push(QueuedItem {
+            id: id.clone(),
+            queued_at_ms: now_millis(),
+            request,
+        });
+        Ok(id)
+    })??;
+    let _ = app.emit(
+        "offline-request-queued",
+        serde_json::json!({ "id": id, "kind": kind }),
+    );
+    spawn_watcher(app.clone());
+    Ok(id)
+}
+
+/// Whether the app endpoint answers at all; any HTTP status will do.
+async fn probe(app: &AppHandle) -> bool {
+    let Ok(endpoint) = crate::api::get_app_endpoint() else {
+        return true;
+    };
+    let Ok(client) = network::client(app, None) else {
+        return true;
+    };
+    client
+        .head(endpoint)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .is_ok()
+}
+
+async fn run(app: &AppHandle, request: QueuedRequest) -> Result<String, String> {
+    match request {
+        QueuedRequest::Transcription { audio_base64 } => {
+            crate::api::transcribe(app.clone(), audio_base64)
+                .await?
+                .into_transcription()
+                .ok_or_else(|| "Transcription failed".to_string())
+        }
+        QueuedRequest::Chat {
+            user_message,
+            system_prompt,
+            image_base64,
+            audio_base64,
+            history,
+        } => {
+            crate::api::chat_completion(
+                app.clone(),
+                user_message,
+                system_prompt,
+                image_base64,
+                audio_base64,
+                history,
+                crate::api::ChatOptions {
+                    stream_events: false,
+                    retrieve_context: true,
+                },
+            )
+            .await
+        }
+    }
+}
+
+fn emit_completed(app: &AppHandle, item: &QueuedItem, result: Result<String, String>) {
+    let (result, error) = match result {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    let _ = app.emit(
+        "offline-request-completed",
+        CompletedEvent {
+            id: item.id.clone(),
+            kind: item.request.kind(),
+            result,
+            error,
+        },
+    );
+}
+
+/// Send queued requests oldest first until the queue is empty or the
+/// network drops again. Requests that fail for other reasons are reported
+/// and dropped, not retried.
+async fn replay(app: &AppHandle) -> Result<(), String> {
+    loop {
+        let (expired, next) = update_queue(app, |queue| {
+            (take_expired(queue, now_millis()), queue.first().cloned())
+        })?;
+        for item in &expired {
+            emit_completed(app, item, Err("Expired while offline".to_string()));
+        }
+        let Some(item) = next else {
+            return Ok(());
+        };
+        let result = run(app, item.request.clone()).await;
+        if result.is_err() && is_offline(app) {
+            return Ok(());
+        }
+        update_queue(app, |queue| queue.retain(|queued| queued.id != item.id))?;
+        emit_completed(app, &item, result);
+    }
+}
+
+fn queue_is_empty(app: &AppHandle) -> bool {
+    app.state::<OfflineState>()
+        .queue
+        .lock()
+        .map(|queue| queue.is_empty())
+        .unwrap_or(true)
+}
+
+/// Probe until online, then replay the queue; runs until both hold.
+fn spawn_watcher(app: AppHandle) {
+    let state = app.state::<OfflineState>();
+    if state.watcher_active.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let online = probe(&app).await;
+            set_online(&app, online);
+            if online {
+                if let Err(e) = replay(&app).await {
+                    tracing::warn!("Offline queue replay failed: {}", e);
+                }
+                if !is_offline(&app) && queue_is_empty(&app) {
+                    break;
+                }
+            }
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+        let state = app.state::<OfflineState>();
+        state.watcher_active.store(false, Ordering::SeqCst);
+        // A request queued between the check and the store started no
+        // watcher.
+        if !queue_is_empty(&app) {
+            spawn_watcher(app.clone());
+        }
+    });
+}
+
+/// Load requests queued before the last quit and replay them when online.
+pub fn init(app: &AppHandle) {
+    let Ok(path) = queue_path(app) else {
+        return;
+    };
+    let items = load(&path);
+    if items.is_empty() {
+        return;
+    }
+    if let Ok(mut queue) = app.state::<OfflineState>().queue.lock() {
+        *queue = items;
+    }
+    spawn_watcher(app.clone());
+}
+
+#[tauri::command]
+pub fn offline_queue_status(app: AppHandle) -> Result<OfflineStatus, String> {
+    let state = app.state::<OfflineState>();
+    let queue = state.queue.lock().map_err(|e| e.to_string())?;
+    Ok(OfflineStatus {
+        online: !state.offline.load(Ordering::SeqCst),
+        queued: queue
+            .iter()
+            .map(|item| QueuedSummary {
+                id: item.id.clone(),
+                kind: item.request.kind(),
+                queued_at_ms: item.queued_at_ms,
+            })
+            .collect(),
+    })
+}
+
+/// Drop one queued request without sending it.
+#[tauri::command]
+pub fn offline_queue_remove(app: AppHandle, id: String) -> Result<(), String> {
+    update_queue(&app, |queue| {
+        let before = queue.len();
+        queue.retain(|item| item.id != id);
+        match queue.len() < before {
+            true => Ok(()),
+            false => Err(format!("No queued request with id {}", id)),
+        }
+    })?
+}
+
+#[tauri::command]
+pub fn offline_queue_clear(app: AppHandle) -> Result<(), String> {
+    update_queue(&app, |queue| queue.clear())
+}
+
+#[cfg(test)]
+mod tests;
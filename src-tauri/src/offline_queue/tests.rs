@@ -0,0 +1,70 @@
+use super::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("runningbord-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn item(id: &str, queued_at_ms: u64) -> QueuedItem {
+    QueuedItem {
+        id: id.to_string(),
+        queued_at_ms,
+        request: QueuedRequest::Chat {
+            user_message: "what's on screen?".to_string(),
+            system_prompt: None,
+            image_base64: Some(serde_json::json!(["aGk="])),
+            audio_base64: None,
+            history: None,
+        },
+    }
+}
+
+#[test]
+fn queue_round_trips_through_disk() {
+    let path = temp_dir("offline-queue").join(QUEUE_FILE);
+    assert!(load(&path).is_empty());
+
+    let items = vec![
+        item("a", 1),
+        QueuedItem {
+            id: "b".to_string(),
+            queued_at_ms: 2,
+            request: QueuedRequest::Transcription {
+                audio_base64: "UklGRg==".to_string(),
+            },
+        },
+    ];
+    save(&path, &items).unwrap();
+    assert_eq!(load(&path), items);
+
+    // An empty queue removes the file.
+    save(&path, &[]).unwrap();
+    assert!(!path.exists());
+    save(&path, &[]).unwrap();
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn unreadable_queue_is_empty() {
+    let path = temp_dir("offline-queue-corrupt").join(QUEUE_FILE);
+    fs::write(&path, "{not json").unwrap();
+    assert!(load(&path).is_empty());
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn old_requests_expire() {
+    let now = MAX_AGE_MS + 1000;
+    let mut items = vec![item("old", 0), item("edge", 1000), item("new", now)];
+    let expired = take_expired(&mut items, now);
+    assert_eq!(
+        expired.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(),
+        ["old"]
+    );
+    assert_eq!(
+        items.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(),
+        ["edge", "new"]
+    );
+}
@@ -0,0 +1,183 @@
+//! Text of PDFs for context: the text layer of the selected pages, so a
+//! document can be sent as text rather than as the whole file. Scanned
+//! PDFs without a text layer come back empty.
+
+use crate::crash_report;
+use serde::Serialize;
+use std::path::Path;
+
+/// PDFs larger than this are not read.
+const MAX_PDF_BYTES: u64 = 64 * 1024 * 1024;
+pub const DEFAULT_MAX_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PdfPage {
+    /// 1-based.
+    pub number: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PdfText {
+    pub page_count: usize,
+    pub pages: Vec<PdfPage>,
+    /// Page texts separated by blank lines.
+    pub text: String,
+    /// More than `max_bytes` of text was found; the rest was dropped.
+    pub truncated: bool,
+}
+
+/// 1-based page numbers selected by `spec`, e.g. `"1-3,7,10-"`, in order
+/// and without repeats. Pages past `page_count` are an error.
+fn parse_pages(spec: &str, page_count: usize) -> Result<Vec<usize>, String> {
+    let mut pages = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let number = |s: &str| -> Result<usize, String> {
+            s.trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n >= 1)
+                .ok_or_else(|| format!("Invalid page number in \"{}\"", part))
+        };
+        let (first, last) = match part.split_once('-') {
+            Some((first, "")) => (number(first)?, page_count),
+            Some((first, last)) => (number(first)?, number(last)?),
+            None => (number(part)?, number(part)?),
+        };
+        if first > last {
+            return Err(format!("Page range \"{}\" is backwards", part));
+        }
+        if last > page_count {
+            return Err(format!(
+                "Page {} is past the end of the document ({} pages)",
+                last, page_count
+            ));
+        }
+        pages.extend(first..=last);
+    }
+    if pages.is_empty() {
+        return Err("No pages selected".to_string());
+    }
+    pages.sort_unstable();
+    pages.dedup();
+    Ok(pages)
+}
+
+/// Trim trailing spaces and collapse runs of blank lines, which the text
+/// layer is full of.
+fn clean_page(text: &str) -> String {
+    let mut cleaned = String::new();
+    let mut blank_lines = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !cleaned.is_empty() {
+            cleaned.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        cleaned.push_str(line);
+        blank_lines = 0;
+    }
+    cleaned
+}
+
+/// The longest prefix of `text` within `max_bytes` that ends on a char
+/// boundary.
+fn truncate(text: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Keep the `selected` pages of `all`, stopping once their text reaches
+/// `max_bytes`.
+fn collect_pages(all: &[String], selected: &[usize], max_bytes: usize) -> PdfText {
+    let mut pages = Vec::new();
+    let mut text = String::new();
+    let mut truncated = false;
+    for &number in selected {
+        let page = clean_page(&all[number - 1]);
+        if page.is_empty() {
+            continue;
+        }
+        let separator = if text.is_empty() { "" } else { "\n\n" };
+        let room = max_bytes.saturating_sub(text.len() + separator.len());
+        let kept = truncate(&page, room);
+        if kept.len() < page.len() {
+            truncated = true;
+        }
+        if !kept.is_empty() {
+            text.push_str(separator);
+            text.push_str(kept);
+            pages.push(PdfPage {
+                number,
+                text: kept.to_string(),
+            });
+        }
+        if truncated {
+            break;
+        }
+    }
+    PdfText {
+        page_count: all.len(),
+        pages,
+        text,
+        truncated,
+    }
+}
+
+/// Text of the pages of the PDF at `path` selected by `pages` (all of them
+/// if `None`), capped at `max_bytes`.
+pub fn extract(path: &Path, pages: Option<&str>, max_bytes: usize) -> Result<PdfText, String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_PDF_BYTES {
+        return Err(format!(
+            "PDF is too large ({} MiB, at most {} MiB)",
+            size / (1024 * 1024),
+            MAX_PDF_BYTES / (1024 * 1024)
+        ));
+    }
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    // The parser panics on some malformed files.
+    let all =
+        crash_report::catch_expected_panic(|| pdf_extract::extract_text_from_mem_by_pages(&bytes))
+            .map_err(|_| "The PDF could not be parsed".to_string())?
+            .map_err(|e| format!("Failed to read the PDF: {}", e))?;
+    if all.is_empty() {
+        return Err("The PDF has no pages".to_string());
+    }
+    let selected = match pages {
+        Some(spec) => parse_pages(spec, all.len())?,
+        None => (1..=all.len()).collect(),
+    };
+    Ok(collect_pages(&all, &selected, max_bytes))
+}
+
+/// Extract the text layer of the PDF at `path`. `pages` picks pages like
+/// `"1-3,7,10-"` (all by default); text is capped at `max_bytes` (256 KiB
+/// by default).
+#[tauri::command]
+pub async fn pdf_extract_text(
+    path: String,
+    pages: Option<String>,
+    max_bytes: Option<usize>,
+) -> Result<PdfText, String> {
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_BYTES);
+    if max_bytes == 0 {
+        return Err("max_bytes must be above 0".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        extract(Path::new(&path), pages.as_deref(), max_bytes)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests;
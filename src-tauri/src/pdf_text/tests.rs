@@ -0,0 +1,58 @@
+use super::*;
+
+fn pages(texts: &[&str]) -> Vec<String> {
+    texts.iter().map(|t| t.to_string()).collect()
+}
+
+#[test]
+fn page_specs_select_sorted_unique_pages() {
+    assert_eq!(parse_pages("1-3,7", 10), Ok(vec![1, 2, 3, 7]));
+    assert_eq!(parse_pages(" 9- , 2 ,2", 10), Ok(vec![2, 9, 10]));
+    assert_eq!(parse_pages("5", 5), Ok(vec![5]));
+}
+
+#[test]
+fn bad_page_specs_are_rejected() {
+    assert!(parse_pages("", 10).is_err());
+    assert!(parse_pages("0", 10).is_err());
+    assert!(parse_pages("4-2", 10).is_err());
+    assert!(parse_pages("11", 10).is_err());
+    assert!(parse_pages("a-b", 10).is_err());
+}
+
+#[test]
+fn pages_are_cleaned() {
+    assert_eq!(
+        clean_page("\n\nTitle   \n\n\n\nBody line\nnext  \n\n"),
+        "Title\n\nBody line\nnext"
+    );
+    assert_eq!(clean_page(" \n\n"), "");
+}
+
+#[test]
+fn selected_pages_are_joined() {
+    let all = pages(&["one", "  \n", "three"]);
+    let text = collect_pages(&all, &[1, 2, 3], 100);
+    assert_eq!(text.page_count, 3);
+    assert_eq!(text.text, "one\n\nthree");
+    assert_eq!(
+        text.pages.iter().map(|p| p.number).collect::<Vec<_>>(),
+        [1, 3]
+    );
+    assert!(!text.truncated);
+}
+
+#[test]
+fn text_is_capped_on_a_char_boundary() {
+    let all = pages(&["abcd", "éfgh", "ijkl"]);
+    // "abcd" + "\n\n" leaves 2 bytes, "é" exactly.
+    let text = collect_pages(&all, &[1, 2, 3], 8);
+    assert_eq!(text.text, "abcd\n\né");
+    assert_eq!(text.pages.len(), 2);
+    assert!(text.truncated);
+
+    let text = collect_pages(&all, &[1, 2, 3], 7);
+    assert_eq!(text.text, "abcd");
+    assert_eq!(text.pages.len(), 1);
+    assert!(text.truncated);
+}
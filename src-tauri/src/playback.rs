@@ -0,0 +1,357 @@
+//! In-app playback of the recent capture buffer, so the user can review
+//! what was captured before sending it anywhere.
+//!
+//! The audio is snapshotted once when playback starts, run through the same
+//! DSP pipeline as exports, and played on a dedicated thread that owns the
+//! output stream. Pause, resume and seek only flip atomics the stream
+//! callback reads; nothing is re-snapshotted. The snapshot is zeroized when
+//! playback ends.
+
+use crate::system_audio::SystemAudioState;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use zeroize::Zeroize;
+
+/// How often the player thread checks for stop and end of audio.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// `Transport::seek` value meaning no seek is pending.
+const NO_SEEK: usize = usize::MAX;
+
+/// Shared between the stream callback and the commands.
+struct Transport {
+    /// Mono PCM at `sample_rate`.
+    samples: Vec<f32>,
+    sample_rate: u32,
+    /// Playhead, in samples; written by the callback.
+    position: AtomicUsize,
+    /// Seek target in samples, picked up by the next callback.
+    seek: AtomicUsize,
+    paused: AtomicBool,
+    finished: AtomicBool,
+}
+
+impl Drop for Transport {
+    fn drop(&mut self) {
+        self.samples.zeroize();
+    }
+}
+
+impl Transport {
+    fn seconds(&self, samples: usize) -> f64 {
+        samples as f64 / self.sample_rate as f64
+    }
+}
+
+struct Player {
+    transport: Arc<Transport>,
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+    device: String,
+}
+
+#[derive(Default)]
+pub struct PlaybackState {
+    player: Mutex<Option<Player>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PlaybackStatus {
+    pub active: bool,
+    pub paused: bool,
+    pub finished: bool,
+    pub position_seconds: f64,
+    pub duration_seconds: f64,
+    pub device: Option<String>,
+}
+
+/// Fill `out` (interleaved, `channels` wide) from `samples` starting at
+/// `cursor`, advancing it by `step` source samples per output frame with
+/// linear interpolation. Frames past the end are silent. Returns whether
+/// the end was reached.
+fn render<T>(samples: &[f32], cursor: &mut f64, step: f64, out: &mut [T], channels: usize) -> bool
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let mut ended = false;
+    for frame in out.chunks_mut(channels) {
+        let index = *cursor as usize;
+        let value = match (samples.get(index), samples.get(index + 1)) {
+            (Some(&a), Some(&b)) => {
+                let t = (*cursor - index as f64) as f32;
+                a + (b - a) * t
+            }
+            (Some(&a), None) => a,
+            _ => {
+                ended = true;
+                0.0
+            }
+        };
+        frame.fill(T::from_sample(value));
+        if !ended {
+            *cursor += step;
+        }
+    }
+    ended || *cursor as usize >= samples.len()
+}
+
+/// The output device named `name`, or the default one.
+pub(crate) fn find_output_device(name: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match name {
+        None => host
+            .default_output_device()
+            .ok_or_else(|| "No output device found".to_string()),
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| format!("Failed to list output devices: {}", e))?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| format!("Output device not found: {}", name)),
+    }
+}
+
+fn build_output_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    transport: Arc<Transport>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let step = transport.sample_rate as f64 / config.sample_rate.0 as f64;
+    let mut cursor = 0.0;
+    device.build_output_stream(
+        config,
+        move |out: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let seek = transport.seek.swap(NO_SEEK, Ordering::AcqRel);
+            if seek != NO_SEEK {
+                cursor = seek as f64;
+                transport.finished.store(false, Ordering::Release);
+            }
+            if transport.paused.load(Ordering::Acquire)
+                || transport.finished.load(Ordering::Acquire)
+            {
+                out.fill(T::EQUILIBRIUM);
+                return;
+            }
+            if render(&transport.samples, &mut cursor, step, out, channels) {
+                transport.finished.store(true, Ordering::Release);
+            }
+            transport.position.store(
+                (cursor as usize).min(transport.samples.len()),
+                Ordering::Release,
+            );
+        },
+        |err| tracing::error!("Playback stream error: {}", err),
+        None,
+    )
+}
+
+fn open_output(device: &cpal::Device, transport: Arc<Transport>) -> Result<cpal::Stream, String> {
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get output config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => build_output_stream::<f32>(device, &config, transport),
+        cpal::SampleFormat::I16 => build_output_stream::<i16>(device, &config, transport),
+        cpal::SampleFormat::I32 => build_output_stream::<i32>(device, &config, transport),
+        cpal::SampleFormat::U16 => build_output_stream::<u16>(device, &config, transport),
+        other => return Err(format!("Unsupported output sample format: {}", other)),
+    }
+    .map_err(|e| format!("Failed to open output device: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start playback: {}", e))?;
+    Ok(stream)
+}
+
+/// Play `transport` on the output device named `device` (the default if
+/// `None`) from a new thread until `stop` is set, and return the thread
+/// with the device's name. Paused or finished playback keeps the stream
+/// open, so it can resume or seek back; `playback-finished` is emitted when
+/// the end is reached.
+fn spawn_player(
+    app: AppHandle,
+    device: Option<String>,
+    transport: Arc<Transport>,
+    stop: Arc<AtomicBool>,
+) -> Result<(thread::JoinHandle<()>, String), String> {
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<String, String>>();
+    let handle = thread::spawn(move || {
+        let opened = find_output_device(device.as_deref()).and_then(|device| {
+            let name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+            open_output(&device, transport.clone()).map(|stream| (stream, name))
+        });
+        let stream = match opened {
+            Ok((stream, name)) => {
+                let _ = ready_tx.send(Ok(name));
+                stream
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        let mut was_finished = false;
+        while !stop.load(Ordering::SeqCst) {
+            let finished = transport.finished.load(Ordering::Acquire);
+            if finished && !was_finished {
+                let _ = app.emit("playback-finished", ());
+            }
+            was_finished = finished;
+            thread::sleep(POLL_INTERVAL);
+        }
+        drop(stream);
+    });
+    let name = ready_rx
+        .recv()
+        .map_err(|_| "Playback thread exited".to_string())??;
+    Ok((handle, name))
+}
+
+fn with_player<T>(app: &AppHandle, f: impl FnOnce(&Player) -> T) -> Result<T, String> {
+    let state = app.state::<PlaybackState>();
+    let player = state.player.lock().map_err(|e| e.to_string())?;
+    player
+        .as_ref()
+        .map(f)
+        .ok_or_else(|| "Nothing is playing".to_string())
+}
+
+/// Names of the available output devices, for `playback_start`.
+#[tauri::command]
+pub async fn playback_output_devices() -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let devices = cpal::default_host()
+            .output_devices()
+            .map_err(|e| format!("Failed to list output devices: {}", e))?;
+        Ok(devices.filter_map(|device| device.name().ok()).collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Play the last `seconds` of the buffer (all of it if omitted) on
+/// `device` (the default output if omitted), replacing any playback.
+#[tauri::command]
+pub async fn playback_start(
+    app: AppHandle,
+    seconds: Option<f64>,
+    device: Option<String>,
+) -> Result<PlaybackStatus, String> {
+    playback_stop(app.clone()).await?;
+    let state = app.state::<Arc<SystemAudioState>>().inner().clone();
+    let player_app = app.clone();
+    let player = tauri::async_runtime::spawn_blocking(move || {
+        let block = state.get_last_processed(seconds)?;
+        if block.samples.is_empty() {
+            return Err("No audio left after processing".to_string());
+        }
+        let transport = Arc::new(Transport {
+            samples: block.samples,
+            sample_rate: block.sample_rate,
+            position: AtomicUsize::new(0),
+            seek: AtomicUsize::new(NO_SEEK),
+            paused: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+        let (thread, device) = spawn_player(player_app, device, transport.clone(), stop.clone())?;
+        Ok::<_, String>(Player {
+            transport,
+            stop,
+            thread,
+            device,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    *app.state::<PlaybackState>()
+        .player
+        .lock()
+        .map_err(|e| e.to_string())? = Some(player);
+    playback_status(app)
+}
+
+#[tauri::command]
+pub fn playback_pause(app: AppHandle) -> Result<(), String> {
+    with_player(&app, |p| p.transport.paused.store(true, Ordering::Release))
+}
+
+#[tauri::command]
+pub fn playback_resume(app: AppHandle) -> Result<(), String> {
+    with_player(&app, |p| p.transport.paused.store(false, Ordering::Release))
+}
+
+/// Move the playhead to `seconds` from the start of the played window.
+#[tauri::command]
+pub fn playback_seek(app: AppHandle, seconds: f64) -> Result<(), String> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(format!("seconds must not be negative, got {}", seconds));
+    }
+    with_player(&app, |p| {
+        let transport = &p.transport;
+        let target =
+            ((seconds * transport.sample_rate as f64) as usize).min(transport.samples.len());
+        transport.seek.store(target, Ordering::Release);
+        transport.position.store(target, Ordering::Release);
+    })
+}
+
+/// Stop playback and release the output device.
+#[tauri::command]
+pub async fn playback_stop(app: AppHandle) -> Result<(), String> {
+    let player = app
+        .state::<PlaybackState>()
+        .player
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+    if let Some(player) = player {
+        player.stop.store(true, Ordering::SeqCst);
+        tauri::async_runtime::spawn_blocking(move || player.thread.join())
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|_| "Playback thread panicked".to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn playback_status(app: AppHandle) -> Result<PlaybackStatus, String> {
+    let state = app.state::<PlaybackState>();
+    let player = state.player.lock().map_err(|e| e.to_string())?;
+    Ok(match player.as_ref() {
+        Some(player) => {
+            let transport = &player.transport;
+            PlaybackStatus {
+                active: true,
+                paused: transport.paused.load(Ordering::Acquire),
+                finished: transport.finished.load(Ordering::Acquire),
+                position_seconds: transport.seconds(transport.position.load(Ordering::Acquire)),
+                duration_seconds: transport.seconds(transport.samples.len()),
+                device: Some(player.device.clone()),
+            }
+        }
+        None => PlaybackStatus {
+            active: false,
+            paused: false,
+            finished: false,
+            position_seconds: 0.0,
+            duration_seconds: 0.0,
+            device: None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests;
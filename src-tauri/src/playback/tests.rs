@@ -0,0 +1,30 @@
+use super::*;
+
+#[test]
+fn render_copies_at_the_source_rate_to_every_channel() {
+    let samples = [0.1, 0.2, 0.3];
+    let mut cursor = 0.0;
+    let mut out = [0.0f32; 4];
+    assert!(!render(&samples, &mut cursor, 1.0, &mut out, 2));
+    assert_eq!(out, [0.1, 0.1, 0.2, 0.2]);
+    assert_eq!(cursor, 2.0);
+}
+
+#[test]
+fn render_interpolates_when_upsampling() {
+    let samples = [0.0, 1.0];
+    let mut cursor = 0.0;
+    let mut out = [0.0f32; 3];
+    render(&samples, &mut cursor, 0.5, &mut out, 1);
+    assert_eq!(out, [0.0, 0.5, 1.0]);
+}
+
+#[test]
+fn render_pads_with_silence_past_the_end() {
+    let samples = [0.5, 0.5];
+    let mut cursor = 1.0;
+    let mut out = [1.0f32; 3];
+    assert!(render(&samples, &mut cursor, 1.0, &mut out, 1));
+    assert_eq!(out, [0.5, 0.0, 0.0]);
+    assert_eq!(cursor, 2.0);
+}
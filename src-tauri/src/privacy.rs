@@ -0,0 +1,235 @@
+//! Privacy blocklist: pauses system audio capture while a listed app
+//! (password manager, banking app, ...) is in the foreground.
+//!
+//! Secure input pause: optionally pauses capture and the wake word
+//! microphone while the user types into a password field, so a secret read
+//! aloud while typing it isn't recorded.
+
+use crate::frontmost_app::{frontmost_app, FrontmostApp};
+use crate::secure_input::secure_input_active;
+use crate::system_audio::{SystemAudioState, PAUSE_REASON_PRIVACY_APP, PAUSE_REASON_SECURE_INPUT};
+use crate::wake_word::WakeWordState;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the frontmost-app watcher polls.
+const WATCH_INTERVAL: Duration = Duration::from_millis(750);
+/// How often the secure input watcher polls; short, since the first
+/// keystrokes are spoken along with them.
+const SECURE_INPUT_INTERVAL: Duration = Duration::from_millis(200);
+/// Capture stays paused this long after secure input ends.
+const SECURE_INPUT_HOLD: Duration = Duration::from_millis(1500);
+
+#[derive(Default)]
+pub struct PrivacyState {
+    /// Bundle IDs / executable names / app names that pause capture.
+    blocklist: Mutex<Vec<String>>,
+    /// Whether the watcher thread is running.
+    watcher_active: AtomicBool,
+    /// Whether to pause while a password field has focus.
+    secure_input_pause: AtomicBool,
+    /// Whether the secure input watcher thread is running.
+    secure_input_watcher_active: AtomicBool,
+}
+
+impl PrivacyState {
+    fn blocked_by(&self, app: &FrontmostApp) -> bool {
+        match self.blocklist.lock() {
+            Ok(list) => list.iter().any(|pattern| app.matches(pattern)),
+            Err(_) => false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.blocklist.lock().map(|l| l.is_empty()).unwrap_or(true)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct PrivacyPauseEvent {
+    pub paused: bool,
+    pub app: Option<FrontmostApp>,
+}
+
+/// Poll the frontmost app and toggle the privacy pause on the audio state
+/// until the blocklist is cleared.
+fn spawn_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let privacy = app.state::<PrivacyState>();
+        let audio = app.state::<Arc<SystemAudioState>>();
+        loop {
+            if privacy.is_empty() {
+                if audio.set_paused(PAUSE_REASON_PRIVACY_APP, false) {
+                    let _ = app.emit(
+                        "system-audio-privacy-paused",
+                        PrivacyPauseEvent {
+                            paused: false,
+                            app: None,
+                        },
+                    );
+                }
+                privacy.watcher_active.store(false, Ordering::SeqCst);
+                // A new blocklist may have been set between the check and the store.
+                if privacy.is_empty() || privacy.watcher_active.swap(true, Ordering::SeqCst) {
+                    return;
+                }
+            }
+
+            let front = frontmost_app();
+            let blocked = front.as_ref().map(|a| privacy.blocked_by(a)).unwrap_or(false);
+            if audio.set_paused(PAUSE_REASON_PRIVACY_APP, blocked) {
+                tracing::info!(
+                    "System audio capture {} (frontmost app: {:?})",
+                    if blocked { "paused" } else { "resumed" },
+                    front.as_ref().map(|a| a.app_id.as_str())
+                );
+                let _ = app.emit(
+                    "system-audio-privacy-paused",
+                    PrivacyPauseEvent {
+                        paused: blocked,
+                        app: front,
+                    },
+                );
+            }
+
+            thread::sleep(WATCH_INTERVAL);
+        }
+    });
+}
+
+#[derive(Clone, Serialize)]
+pub struct SecureInputPauseEvent {
+    pub paused: bool,
+}
+
+/// Pause or resume system audio capture and the wake word listener for
+/// secure input. Emits `privacy-secure-input-paused` on a change.
+fn set_secure_input_paused(app: &AppHandle, paused: bool) {
+    app.state::<WakeWordState>().set_paused(paused);
+    let audio = app.state::<Arc<SystemAudioState>>();
+    if audio.set_paused(PAUSE_REASON_SECURE_INPUT, paused) {
+        tracing::info!(
+            "Capture {} for secure input",
+            if paused { "paused" } else { "resumed" }
+        );
+        let _ = app.emit(
+            "privacy-secure-input-paused",
+            SecureInputPauseEvent { paused },
+        );
+    }
+}
+
+/// Poll for secure input and pause while it is on (and for
+/// `SECURE_INPUT_HOLD` after), until the option is turned off.
+fn spawn_secure_input_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let privacy = app.state::<PrivacyState>();
+        let mut last_active: Option<Instant> = None;
+        loop {
+            if !privacy.secure_input_pause.load(Ordering::SeqCst) {
+                set_secure_input_paused(&app, false);
+                privacy
+                    .secure_input_watcher_active
+                    .store(false, Ordering::SeqCst);
+                // The option may have been turned back on between the check and the store.
+                if !privacy.secure_input_pause.load(Ordering::SeqCst)
+                    || privacy
+                        .secure_input_watcher_active
+                        .swap(true, Ordering::SeqCst)
+                {
+                    return;
+                }
+            }
+
+            if secure_input_active() {
+                last_active = Some(Instant::now());
+            }
+            let paused = last_active.is_some_and(|at| at.elapsed() < SECURE_INPUT_HOLD);
+            set_secure_input_paused(&app, paused);
+
+            thread::sleep(SECURE_INPUT_INTERVAL);
+        }
+    });
+}
+
+/// Turn the secure input pause on or off.
+#[tauri::command]
+pub fn privacy_set_secure_input_pause(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let state = app.state::<PrivacyState>();
+    state.secure_input_pause.store(enabled, Ordering::SeqCst);
+    if enabled
+        && !state
+            .secure_input_watcher_active
+            .swap(true, Ordering::SeqCst)
+    {
+        spawn_secure_input_watcher(app.clone());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn privacy_get_secure_input_pause(app: AppHandle) -> Result<bool, String> {
+    let state = app.state::<PrivacyState>();
+    Ok(state.secure_input_pause.load(Ordering::SeqCst))
+}
+
+/// Replace the privacy blocklist. An empty list stops the watcher.
+#[tauri::command]
+pub fn system_audio_set_privacy_blocklist(
+    app: AppHandle,
+    apps: Vec<String>,
+) -> Result<(), String> {
+    let state = app.state::<PrivacyState>();
+    {
+        let mut list = state.blocklist.lock().map_err(|e| e.to_string())?;
+        *list = apps
+            .into_iter()
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+    }
+    if !state.is_empty() && !state.watcher_active.swap(true, Ordering::SeqCst) {
+        spawn_watcher(app.clone());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn system_audio_get_privacy_blocklist(app: AppHandle) -> Result<Vec<String>, String> {
+    let state = app.state::<PrivacyState>();
+    let list = state.blocklist.lock().map_err(|e| e.to_string())?;
+    Ok(list.clone())
+}
+
+/// Whether `front` is on the privacy blocklist.
+pub fn app_blocked(app: &AppHandle, front: &FrontmostApp) -> bool {
+    app.state::<PrivacyState>().blocked_by(front)
+}
+
+/// Whether the app in the foreground is on the privacy blocklist.
+pub fn frontmost_app_blocked(app: &AppHandle) -> bool {
+    let privacy = app.state::<PrivacyState>();
+    !privacy.is_empty() && frontmost_app().is_some_and(|front| privacy.blocked_by(&front))
+}
+
+/// Why the screen mustn't be captured right now, if it mustn't: a password
+/// is being typed or a blocklisted app is in the foreground.
+pub fn screen_capture_blocked(app: &AppHandle) -> Option<&'static str> {
+    if secure_input_active() {
+        Some("A password field has focus")
+    } else if frontmost_app_blocked(app) {
+        Some("The foreground app is on the privacy blocklist")
+    } else {
+        None
+    }
+}
+
+/// Return the app currently in the foreground (for picking blocklist entries).
+#[tauri::command]
+pub fn get_frontmost_app() -> Option<FrontmostApp> {
+    frontmost_app()
+}
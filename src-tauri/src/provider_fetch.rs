@@ -0,0 +1,259 @@
+//! Requests to AI and STT providers built by the webview from a provider's
+//! curl template, sent from the backend so the API key never reaches the
+//! webview. The webview leaves `secrets::API_KEY_PLACEHOLDER` where the key
+//! goes, and the key stored for the provider is filled in here.
+//!
+//! Any script in the webview can call this, so the key only goes where the
+//! provider would read it and nowhere it could be echoed back: to the origin
+//! it was stored for, in auth headers and form fields. A placeholder in the
+//! URL, another header or the body is refused.
+//!
+//! The response streams back over a channel as it arrives, so streamed
+//! answers show up as they're generated: a `head` event, then `chunk`s of
+//! the body, then `end`.
+
+use crate::network;
+use crate::network_log;
+use crate::offline_queue;
+use crate::rate_limit::{self, CallKind};
+use crate::secrets;
+use base64::Engine;
+use futures_util::StreamExt;
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::multipart::{Form, Part};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderRequest {
+    /// Which of the provider's rate limits the request counts against.
+    #[serde(default)]
+    pub kind: CallKind,
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    #[serde(default)]
+    pub body: Option<ProviderBody>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ProviderBody {
+    Text {
+        text: String,
+    },
+    /// Raw bytes, e.g. audio for a `--data-binary` upload.
+    #[serde(rename_all = "camelCase")]
+    Bytes {
+        data_base64: String,
+    },
+    Form {
+        fields: Vec<FormField>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FormField {
+    Text {
+        name: String,
+        value: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    File {
+        name: String,
+        file_name: String,
+        mime_type: String,
+        data_base64: String,
+    },
+}
+
+/// Sent over `on_event`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ProviderEvent {
+    #[serde(rename_all = "camelCase")]
+    Head {
+        status: u16,
+        status_text: String,
+        content_type: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Chunk {
+        data_base64: String,
+    },
+    End,
+}
+
+fn decode(data_base64: &str) -> Result<Vec<u8>, String> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data_base64)
+        .map_err(|e| format!("Invalid base64 in request: {}", e))
+}
+
+/// Refuses `text` if it asks for the key somewhere it isn't filled in.
+fn no_api_key(text: &str, place: &str) -> Result<(), String> {
+    if secrets::mentions_api_key(text) {
+        return Err(format!(
+            "The API key can only go in an auth header or form field, not the {}",
+            place
+        ));
+    }
+    Ok(())
+}
+
+/// Headers providers read a key from: `Authorization`, or any whose name
+/// mentions a key or token (`x-api-key`, `xi-api-key`, `x-goog-api-key`, ...).
+fn is_auth_header(name: &HeaderName) -> bool {
+    let name = name.as_str();
+    name == "authorization" || name.contains("key") || name.contains("token")
+}
+
+/// `value` with the key filled in, for auth headers. Values that carry the
+/// key are marked sensitive, which keeps them out of the network log.
+fn header_value(name: &HeaderName, value: &str, api_key: &str) -> Result<HeaderValue, String> {
+    if !is_auth_header(name) {
+        no_api_key(value, &format!("{} header", name))?;
+    }
+    let filled = secrets::fill_api_key(value, api_key);
+    let carries_key = filled != value;
+    let mut value =
+        HeaderValue::from_str(&filled).map_err(|e| format!("Invalid header value: {}", e))?;
+    value.set_sensitive(carries_key);
+    Ok(value)
+}
+
+fn form(fields: Vec<FormField>, api_key: &str) -> Result<Form, String> {
+    fields.into_iter().try_fold(Form::new(), |form, field| {
+        Ok(match field {
+            FormField::Text { name, value } => {
+                form.text(name, secrets::fill_api_key(&value, api_key))
+            }
+            FormField::File {
+                name,
+                file_name,
+                mime_type,
+                data_base64,
+            } => {
+                let part = Part::bytes(decode(&data_base64)?)
+                    .file_name(file_name)
+                    .mime_str(&mime_type)
+                    .map_err(|e| format!("Invalid MIME type {}: {}", mime_type, e))?;
+                form.part(name, part)
+            }
+        })
+    })
+}
+
+/// Send `request` to `provider` with its stored API key filled in, and
+/// stream the response over `on_event`. Fails if no key is stored, or if the
+/// request goes anywhere but the origin the key was stored for.
+#[tauri::command]
+pub async fn provider_fetch(
+    app: AppHandle,
+    provider: String,
+    request: ProviderRequest,
+    on_event: Channel<ProviderEvent>,
+) -> Result<(), String> {
+    let (api_key, origin) = {
+        let provider = provider.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            Ok::<_, String>((
+                secrets::api_key(&provider)?,
+                secrets::api_key_origin(&provider)?,
+            ))
+        })
+        .await
+        .map_err(|e| e.to_string())??
+    };
+    let api_key = api_key.ok_or_else(|| format!("No API key stored for {}", provider))?;
+    let origin = origin.ok_or_else(|| {
+        format!(
+            "No endpoint recorded for {}'s API key; store the key again",
+            provider
+        )
+    })?;
+    let method = Method::from_bytes(request.method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid method {}", request.method))?;
+    no_api_key(&request.url, "URL")?;
+    let client = network::client(&app, Some(&provider))?;
+    let url = network::provider_url(&app, Some(&provider), &request.url)?;
+    if secrets::url_origin(&url)? != origin {
+        return Err(format!(
+            "{}'s API key can only be sent to {}, not {}",
+            provider, origin, url
+        ));
+    }
+    let mut builder = client.request(method, &url);
+    for (name, value) in &request.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name {}: {}", name, e))?;
+        let value = header_value(&name, value, &api_key)?;
+        builder = builder.header(name, value);
+    }
+    builder = match request.body {
+        None => builder,
+        Some(ProviderBody::Text { text }) => {
+            no_api_key(&text, "request body")?;
+            builder.body(text)
+        }
+        Some(ProviderBody::Bytes { data_base64 }) => builder.body(decode(&data_base64)?),
+        Some(ProviderBody::Form { fields }) => builder.multipart(form(fields, &api_key)?),
+    };
+    let kind = request.kind;
+    let request = builder
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+    rate_limit::acquire(&app, kind, Some(&provider), 0).await?;
+    let log = network_log::start(&app, Some(&provider), &request);
+    let response = match client.execute(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            log.finish(None, None, Some(&e.to_string()));
+            offline_queue::observe_error(&app, &e);
+            return Err(format!("Request to {} failed to send: {}", provider, e));
+        }
+    };
+    let status = response.status();
+    let send = |event: ProviderEvent| {
+        on_event
+            .send(event)
+            .map_err(|e| format!("Failed to send response: {}", e))
+    };
+    send(ProviderEvent::Head {
+        status: status.as_u16(),
+        status_text: status.canonical_reason().unwrap_or_default().to_string(),
+        content_type: response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+    })?;
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                log.finish(Some(status.as_u16()), None, Some(&e.to_string()));
+                return Err(format!("Failed to read response from {}: {}", provider, e));
+            }
+        };
+        send(ProviderEvent::Chunk {
+            data_base64: base64::engine::general_purpose::STANDARD.encode(&chunk),
+        })?;
+    }
+    log.finish(Some(status.as_u16()), None, None);
+    send(ProviderEvent::End)
+}
+
+#[cfg(test)]
+mod tests;
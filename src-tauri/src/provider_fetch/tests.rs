@@ -0,0 +1,98 @@
+use super::*;
+
+#[test]
+fn requests_deserialize_from_the_webview_shape() {
+    let request: ProviderRequest = serde_json::from_value(serde_json::json!({
+        "url": "https://api.openai.com/v1/audio/transcriptions",
+        "headers": [["Authorization", "Bearer {{API_KEY}}"]],
+        "body": {
+            "type": "form",
+            "fields": [
+                { "type": "text", "name": "model", "value": "whisper-1" },
+                {
+                    "type": "file",
+                    "name": "file",
+                    "fileName": "audio.wav",
+                    "mimeType": "audio/wav",
+                    "dataBase64": "UklGRg=="
+                }
+            ]
+        }
+    }))
+    .unwrap();
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.headers.len(), 1);
+    let Some(ProviderBody::Form { fields }) = request.body else {
+        panic!("expected a form body");
+    };
+    assert!(matches!(&fields[1], FormField::File { file_name, .. } if file_name == "audio.wav"));
+    assert!(form(fields, "sk-1").is_ok());
+}
+
+#[test]
+fn headers_carrying_the_key_are_sensitive() {
+    let authorization = HeaderName::from_static("authorization");
+    let auth = header_value(&authorization, "Bearer {{API_KEY}}", "sk-1").unwrap();
+    assert_eq!(auth.to_str().unwrap(), "Bearer sk-1");
+    assert!(auth.is_sensitive());
+    let content_type = HeaderName::from_static("content-type");
+    let plain = header_value(&content_type, "application/json", "sk-1").unwrap();
+    assert!(!plain.is_sensitive());
+    assert!(header_value(&authorization, "{{API_KEY}}", "bad\nkey").is_err());
+}
+
+#[test]
+fn the_key_only_goes_in_auth_headers() {
+    for name in [
+        "x-api-key",
+        "xi-api-key",
+        "x-goog-api-key",
+        "ocp-apim-subscription-key",
+    ] {
+        let name = HeaderName::from_static(name);
+        assert!(
+            header_value(&name, "{{API_KEY}}", "sk-1").is_ok(),
+            "{}",
+            name
+        );
+    }
+    for name in ["referer", "x-echo", "content-type"] {
+        let name = HeaderName::from_static(name);
+        assert!(
+            header_value(&name, "{{API_KEY}}", "sk-1").is_err(),
+            "{}",
+            name
+        );
+    }
+    assert!(no_api_key("https://x.test/v1?key=%7B%7BAPI_KEY%7D%7D", "URL").is_err());
+    assert!(no_api_key(r#"{"echo":"{{API_KEY}}"}"#, "request body").is_err());
+    assert!(no_api_key(r#"{"model":"gpt-4o"}"#, "request body").is_ok());
+}
+
+#[test]
+fn requests_count_as_chat_unless_told_otherwise() {
+    let request: ProviderRequest =
+        serde_json::from_value(serde_json::json!({ "url": "https://x.test" })).unwrap();
+    assert_eq!(request.kind, CallKind::Chat);
+    let request: ProviderRequest = serde_json::from_value(
+        serde_json::json!({ "url": "https://x.test", "kind": "transcription" }),
+    )
+    .unwrap();
+    assert_eq!(request.kind, CallKind::Transcription);
+}
+
+#[test]
+fn events_serialize_tagged() {
+    let head = serde_json::to_value(ProviderEvent::Head {
+        status: 200,
+        status_text: "OK".to_string(),
+        content_type: None,
+    })
+    .unwrap();
+    assert_eq!(head["type"], "head");
+    assert_eq!(head["statusText"], "OK");
+    assert_eq!(
+        serde_json::to_value(ProviderEvent::End).unwrap(),
+        serde_json::json!({ "type": "end" })
+    );
+}
@@ -0,0 +1,281 @@
+//! Per-provider rate limits for chat and transcription calls. Calls over a
+//! provider's requests- or tokens-per-minute budget wait their turn in a
+//! FIFO queue instead of failing at the API; while a call waits,
+//! `rate-limit-queue` reports its place in line.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+/// Limits count calls started within this long.
+const WINDOW: Duration = Duration::from_secs(60);
+/// Bucket for calls whose provider isn't known.
+const DEFAULT_PROVIDER: &str = "default";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CallKind {
+    #[default]
+    Chat,
+    Transcription,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProviderLimits {
+    /// Requests per minute; `None` for no limit.
+    pub rpm: Option<u32>,
+    /// Prompt tokens per minute; `None` for no limit.
+    pub tpm: Option<u32>,
+}
+
+impl Default for ProviderLimits {
+    fn default() -> Self {
+        Self {
+            rpm: Some(60),
+            tpm: None,
+        }
+    }
+}
+
+impl ProviderLimits {
+    fn validate(&self) -> Result<(), String> {
+        if self.rpm == Some(0) || self.tpm == Some(0) {
+            return Err("Rate limits must be above 0; leave them unset for no limit".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RateLimitConfig {
+    /// Limits for providers not in `providers`.
+    pub default: ProviderLimits,
+    /// Limits by provider id, e.g. `"openai"`.
+    pub providers: HashMap<String, ProviderLimits>,
+}
+
+impl RateLimitConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.default.validate()?;
+        for (provider, limits) in &self.providers {
+            limits
+                .validate()
+                .map_err(|e| format!("{} ({})", e, provider))?;
+        }
+        Ok(())
+    }
+
+    fn limits_for(&self, provider: &str) -> ProviderLimits {
+        self.providers
+            .get(provider)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+#[derive(Default)]
+struct Bucket {
+    /// Start time and prompt tokens of recent calls, oldest first.
+    window: VecDeque<(Instant, u32)>,
+    /// Tickets of waiting calls, in order.
+    queue: VecDeque<u64>,
+    /// Notified whenever the queue moves.
+    changed: Arc<Notify>,
+}
+
+#[derive(Default)]
+pub struct RateLimitState {
+    config: Mutex<RateLimitConfig>,
+    buckets: Mutex<HashMap<(CallKind, String), Bucket>>,
+    next_ticket: AtomicU64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitQueueEvent {
+    pub kind: CallKind,
+    pub provider: String,
+    /// Calls ahead of this one.
+    pub position: usize,
+    /// Time until this call can start, once it's first in line.
+    pub wait_ms: u64,
+    /// `false` once the call has started.
+    pub waiting: bool,
+}
+
+/// Drop calls that left the window.
+fn prune(window: &mut VecDeque<(Instant, u32)>, now: Instant) {
+    while window.front().is_some_and(|&(at, _)| at + WINDOW <= now) {
+        window.pop_front();
+    }
+}
+
+/// How long until a call of `tokens` fits within `limits`, given the
+/// calls in `window`. A call larger than the whole token budget goes once
+/// the window is empty.
+fn wait_for(
+    window: &VecDeque<(Instant, u32)>,
+    limits: ProviderLimits,
+    tokens: u32,
+    now: Instant,
+) -> Duration {
+    let expiry = |i: usize| (window[i].0 + WINDOW).saturating_duration_since(now);
+    let mut wait = Duration::ZERO;
+    if let Some(rpm) = limits.rpm {
+        let rpm = rpm as usize;
+        if window.len() >= rpm {
+            wait = wait.max(expiry(window.len() - rpm));
+        }
+    }
+    if let Some(tpm) = limits.tpm {
+        let budget = u64::from(tpm);
+        let needed = u64::from(tokens).min(budget);
+        let mut used: u64 = window.iter().map(|&(_, t)| u64::from(t)).sum();
+        let mut oldest = 0;
+        while used + needed > budget && oldest < window.len() {
+            used -= u64::from(window[oldest].1);
+            wait = wait.max(expiry(oldest));
+            oldest += 1;
+        }
+    }
+    wait
+}
+
+/// A call's place in its bucket's queue, given up if the call is dropped
+/// while waiting.
+struct Ticket<'a> {
+    state: &'a RateLimitState,
+    key: (CallKind, String),
+    id: u64,
+    started: bool,
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        if self.started {
+            return;
+        }
+        if let Ok(mut buckets) = self.state.buckets.lock() {
+            if let Some(bucket) = buckets.get_mut(&self.key) {
+                bucket.queue.retain(|&id| id != self.id);
+                bucket.changed.notify_waiters();
+            }
+        }
+    }
+}
+
+/// Wait until a `kind` call to `provider` using `tokens` prompt tokens
+/// fits within its limits, then count it against them.
+pub async fn acquire(
+    app: &AppHandle,
+    kind: CallKind,
+    provider: Option<&str>,
+    tokens: u32,
+) -> Result<(), String> {
+    let state = app.state::<RateLimitState>();
+    let provider = provider.unwrap_or(DEFAULT_PROVIDER).to_string();
+    let limits = state
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .limits_for(&provider);
+    let key = (kind, provider.clone());
+    let id = state.next_ticket.fetch_add(1, Ordering::SeqCst);
+    let changed = {
+        let mut buckets = state.buckets.lock().map_err(|e| e.to_string())?;
+        let bucket = buckets.entry(key.clone()).or_default();
+        bucket.queue.push_back(id);
+        bucket.changed.clone()
+    };
+    let mut ticket = Ticket {
+        state: &state,
+        key,
+        id,
+        started: false,
+    };
+
+    let mut last_position = None;
+    loop {
+        // Created before the check so a move in between isn't missed.
+        let notified = changed.notified();
+        let (position, wait) = {
+            let mut buckets = state.buckets.lock().map_err(|e| e.to_string())?;
+            let bucket = buckets.entry(ticket.key.clone()).or_default();
+            let now = Instant::now();
+            prune(&mut bucket.window, now);
+            let position = bucket.queue.iter().position(|&t| t == id).unwrap_or(0);
+            let wait = match position {
+                0 => wait_for(&bucket.window, limits, tokens, now),
+                _ => Duration::ZERO,
+            };
+            if position == 0 && wait.is_zero() {
+                bucket.window.push_back((now, tokens));
+                bucket.queue.pop_front();
+                bucket.changed.notify_waiters();
+                ticket.started = true;
+                break;
+            }
+            (position, wait)
+        };
+        if last_position != Some(position) {
+            last_position = Some(position);
+            let _ = app.emit(
+                "rate-limit-queue",
+                RateLimitQueueEvent {
+                    kind,
+                    provider: provider.clone(),
+                    position,
+                    wait_ms: wait.as_millis() as u64,
+                    waiting: true,
+                },
+            );
+        }
+        if position == 0 {
+            tokio::time::sleep(wait).await;
+        } else {
+            notified.await;
+        }
+    }
+
+    if last_position.is_some() {
+        let _ = app.emit(
+            "rate-limit-queue",
+            RateLimitQueueEvent {
+                kind,
+                provider,
+                position: 0,
+                wait_ms: 0,
+                waiting: false,
+            },
+        );
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn rate_limit_set_config(app: AppHandle, config: RateLimitConfig) -> Result<(), String> {
+    config.validate()?;
+    *app.state::<RateLimitState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn rate_limit_get_config(app: AppHandle) -> Result<RateLimitConfig, String> {
+    app.state::<RateLimitState>()
+        .config
+        .lock()
+        .map(|config| config.clone())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests;
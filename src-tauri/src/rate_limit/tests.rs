@@ -0,0 +1,79 @@
+use super::*;
+
+fn limits(rpm: Option<u32>, tpm: Option<u32>) -> ProviderLimits {
+    ProviderLimits { rpm, tpm }
+}
+
+fn window(start: Instant, calls: &[(u64, u32)]) -> VecDeque<(Instant, u32)> {
+    calls
+        .iter()
+        .map(|&(secs, tokens)| (start + Duration::from_secs(secs), tokens))
+        .collect()
+}
+
+#[test]
+fn calls_under_the_limits_go_at_once() {
+    let start = Instant::now();
+    let calls = window(start, &[(0, 100), (10, 100)]);
+    let now = start + Duration::from_secs(20);
+    assert_eq!(
+        wait_for(&calls, limits(Some(3), Some(1000)), 500, now),
+        Duration::ZERO
+    );
+    assert_eq!(
+        wait_for(&calls, limits(None, None), 5000, now),
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn requests_wait_for_the_oldest_call_to_expire() {
+    let start = Instant::now();
+    let calls = window(start, &[(0, 0), (10, 0), (20, 0)]);
+    let now = start + Duration::from_secs(30);
+    assert_eq!(
+        wait_for(&calls, limits(Some(3), None), 0, now),
+        Duration::from_secs(30)
+    );
+    assert_eq!(
+        wait_for(&calls, limits(Some(2), None), 0, now),
+        Duration::from_secs(40)
+    );
+}
+
+#[test]
+fn tokens_wait_until_enough_budget_frees_up() {
+    let start = Instant::now();
+    let calls = window(start, &[(0, 400), (10, 400), (20, 100)]);
+    let now = start + Duration::from_secs(30);
+    let tpm = limits(None, Some(1000));
+    assert_eq!(wait_for(&calls, tpm, 100, now), Duration::ZERO);
+    assert_eq!(wait_for(&calls, tpm, 400, now), Duration::from_secs(30));
+    assert_eq!(wait_for(&calls, tpm, 800, now), Duration::from_secs(40));
+    // Larger than the budget: waits for the window to empty.
+    assert_eq!(wait_for(&calls, tpm, 5000, now), Duration::from_secs(50));
+}
+
+#[test]
+fn expired_calls_are_pruned() {
+    let start = Instant::now();
+    let mut calls = window(start, &[(0, 1), (30, 2), (70, 3)]);
+    prune(&mut calls, start + Duration::from_secs(90));
+    assert_eq!(calls, window(start, &[(70, 3)]));
+}
+
+#[test]
+fn providers_fall_back_to_the_default() {
+    let mut config = RateLimitConfig::default();
+    config
+        .providers
+        .insert("openai".to_string(), limits(Some(500), Some(30_000)));
+    assert_eq!(config.limits_for("openai"), limits(Some(500), Some(30_000)));
+    assert_eq!(config.limits_for("groq"), ProviderLimits::default());
+    assert!(config.validate().is_ok());
+
+    config
+        .providers
+        .insert("groq".to_string(), limits(Some(0), None));
+    assert!(config.validate().is_err());
+}
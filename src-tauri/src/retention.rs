@@ -0,0 +1,202 @@
+//! Retention for saved recordings: history entries older than a maximum
+//! age, and then the oldest entries beyond a maximum total size, are
+//! deleted together with their audio and screenshot files. A background
+//! task applies the policy every hour.
+//!
+//! Only files inside the recordings folder are deleted. The frontend can
+//! register clips and screenshots from anywhere, and those belong to the
+//! user: their entries are removed, the files stay, and they don't count
+//! towards the size limit.
+//!
+//! No limits by default.
+
+use crate::autosave;
+use crate::history::{self, HistoryEntry};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionPolicy {
+    /// Delete recordings older than this many days.
+    pub max_age_days: Option<u32>,
+    /// Delete the oldest recordings while their files take more than this.
+    pub max_total_bytes: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct RetentionState {
+    policy: Mutex<RetentionPolicy>,
+    /// Set while a cleanup runs, so the timer and `retention_run_now`
+    /// don't overlap.
+    running: AtomicBool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupReport {
+    pub deleted: usize,
+    pub freed_bytes: u64,
+}
+
+/// What the policy needs to know about one history entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    id: i64,
+    /// Creation time in ms since the Unix epoch; `None` if unparseable,
+    /// which exempts the entry from the age limit.
+    created_ms: Option<i64>,
+    bytes: u64,
+}
+
+/// Entries to delete under `policy` at `now_ms`, oldest first: everything
+/// past the age limit, then the oldest entries until the rest fits in the
+/// size limit.
+fn plan(candidates: &[Candidate], policy: &RetentionPolicy, now_ms: i64) -> Vec<Candidate> {
+    let mut by_age = candidates.to_vec();
+    // Unknown ages count as newest; ties by id.
+    by_age.sort_by_key(|c| (c.created_ms.unwrap_or(i64::MAX), c.id));
+    let cutoff = policy
+        .max_age_days
+        .map(|days| now_ms - i64::from(days) * DAY_MS);
+    let mut total: u64 = candidates.iter().map(|c| c.bytes).sum();
+    by_age
+        .into_iter()
+        .filter(|candidate| {
+            let expired = matches!(
+                (cutoff, candidate.created_ms),
+                (Some(cutoff), Some(created)) if created < cutoff
+            );
+            let over_size = policy.max_total_bytes.is_some_and(|max| total > max);
+            if expired || over_size {
+                total -= candidate.bytes;
+            }
+            expired || over_size
+        })
+        .collect()
+}
+
+/// `created_at` as stored by the history (UTC, `YYYY-MM-DD HH:MM:SS`).
+fn created_ms(created_at: &str) -> Option<i64> {
+    NaiveDateTime::parse_from_str(created_at, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|time| time.and_utc().timestamp_millis())
+}
+
+/// Whether `path` is inside `dir`, the canonical recordings folder, after
+/// resolving links and `..`. `None` (no folder yet) owns nothing.
+fn owned(dir: Option<&Path>, path: &Path) -> bool {
+    let Some(dir) = dir else {
+        return false;
+    };
+    path.canonicalize()
+        .is_ok_and(|path| path.starts_with(dir) && path != dir)
+}
+
+/// Size of `path` if retention may delete it, else 0.
+fn owned_bytes(dir: Option<&Path>, path: &str) -> u64 {
+    if !owned(dir, Path::new(path)) {
+        return 0;
+    }
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn candidate(entry: &HistoryEntry, dir: Option<&Path>) -> Candidate {
+    Candidate {
+        id: entry.id,
+        created_ms: created_ms(&entry.created_at),
+        bytes: owned_bytes(dir, &entry.path)
+            + entry
+                .screenshot_path
+                .as_deref()
+                .map_or(0, |path| owned_bytes(dir, path)),
+    }
+}
+
+/// Apply the current policy once.
+async fn cleanup(app: &AppHandle) -> Result<CleanupReport, String> {
+    let state = app.state::<RetentionState>();
+    let policy = state.policy.lock().map_err(|e| e.to_string())?.clone();
+    if policy == RetentionPolicy::default() {
+        return Ok(CleanupReport::default());
+    }
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("A cleanup is already running".to_string());
+    }
+    let result = async {
+        let dir = autosave::recordings_dir(app)?.canonicalize().ok();
+        let dir = dir.as_deref();
+        let entries = history::all_entries(app).await?;
+        let candidates: Vec<Candidate> = entries.iter().map(|e| candidate(e, dir)).collect();
+        let mut report = CleanupReport::default();
+        for candidate in plan(&candidates, &policy, Utc::now().timestamp_millis()) {
+            // Keep going: one locked file shouldn't stop the rest.
+            match history::delete_where(app, candidate.id, |path| owned(dir, path)).await {
+                Ok(()) => {
+                    report.deleted += 1;
+                    report.freed_bytes += candidate.bytes;
+                }
+                Err(e) => tracing::warn!("Retention cleanup skipped {}: {}", candidate.id, e),
+            }
+        }
+        Ok(report)
+    }
+    .await;
+    state.running.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Start the hourly cleanup. Called once at startup.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match cleanup(&app).await {
+                Ok(report) if report.deleted > 0 => tracing::info!(
+                    "Retention cleanup deleted {} recordings ({} bytes)",
+                    report.deleted,
+                    report.freed_bytes
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Retention cleanup failed: {}", e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn retention_set_policy(app: AppHandle, policy: RetentionPolicy) -> Result<(), String> {
+    *app.state::<RetentionState>()
+        .policy
+        .lock()
+        .map_err(|e| e.to_string())? = policy;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn retention_get_policy(app: AppHandle) -> Result<RetentionPolicy, String> {
+    Ok(app
+        .state::<RetentionState>()
+        .policy
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+/// Apply the policy now instead of waiting for the next hourly run.
+#[tauri::command]
+pub async fn retention_run_now(app: AppHandle) -> Result<CleanupReport, String> {
+    cleanup(&app).await
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,99 @@
+use super::*;
+
+const NOW: i64 = 1_760_000_000_000;
+
+fn candidate(id: i64, age_days: Option<i64>, bytes: u64) -> Candidate {
+    Candidate {
+        id,
+        created_ms: age_days.map(|days| NOW - days * DAY_MS),
+        bytes,
+    }
+}
+
+fn ids(plan: Vec<Candidate>) -> Vec<i64> {
+    plan.into_iter().map(|c| c.id).collect()
+}
+
+#[test]
+fn no_limits_keep_everything() {
+    let candidates = [candidate(1, Some(400), 10), candidate(2, None, 10)];
+    assert!(plan(&candidates, &RetentionPolicy::default(), NOW).is_empty());
+}
+
+#[test]
+fn entries_past_the_age_limit_are_deleted() {
+    let candidates = [
+        candidate(1, Some(40), 10),
+        candidate(2, Some(10), 10),
+        candidate(3, None, 10),
+        candidate(4, Some(31), 10),
+    ];
+    let policy = RetentionPolicy {
+        max_age_days: Some(30),
+        max_total_bytes: None,
+    };
+    assert_eq!(ids(plan(&candidates, &policy, NOW)), vec![1, 4]);
+}
+
+#[test]
+fn oldest_entries_go_until_the_rest_fits() {
+    let candidates = [
+        candidate(1, Some(1), 300),
+        candidate(2, Some(3), 300),
+        candidate(3, Some(2), 300),
+        candidate(4, None, 300),
+    ];
+    let policy = RetentionPolicy {
+        max_age_days: None,
+        max_total_bytes: Some(650),
+    };
+    assert_eq!(ids(plan(&candidates, &policy, NOW)), vec![2, 3]);
+}
+
+#[test]
+fn age_and_size_limits_combine() {
+    let candidates = [
+        candidate(1, Some(100), 50),
+        candidate(2, Some(5), 500),
+        candidate(3, Some(1), 500),
+    ];
+    let policy = RetentionPolicy {
+        max_age_days: Some(30),
+        max_total_bytes: Some(600),
+    };
+    assert_eq!(ids(plan(&candidates, &policy, NOW)), vec![1, 2]);
+}
+
+#[test]
+fn history_timestamps_parse_as_utc() {
+    assert_eq!(created_ms("1970-01-02 00:00:00"), Some(DAY_MS));
+    assert_eq!(created_ms("yesterday"), None);
+}
+
+#[test]
+fn only_files_in_the_recordings_folder_are_owned() {
+    let root = std::env::temp_dir().join(format!("runningbord-retention-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let recordings = root.join("recordings");
+    let elsewhere = root.join("recordings-elsewhere");
+    std::fs::create_dir_all(&recordings).unwrap();
+    std::fs::create_dir_all(&elsewhere).unwrap();
+    std::fs::write(recordings.join("a.ogg"), b"audio").unwrap();
+    std::fs::write(elsewhere.join("b.ogg"), b"audio").unwrap();
+    let dir = recordings.canonicalize().unwrap();
+
+    assert!(owned(Some(&dir), &recordings.join("a.ogg")));
+    assert!(!owned(Some(&dir), &elsewhere.join("b.ogg")));
+    assert!(!owned(
+        Some(&dir),
+        &recordings.join("../recordings-elsewhere/b.ogg")
+    ));
+    assert!(!owned(Some(&dir), &recordings));
+    assert!(!owned(Some(&dir), &recordings.join("missing.ogg")));
+    assert!(!owned(None, &recordings.join("a.ogg")));
+    let a = recordings.join("a.ogg").to_string_lossy().to_string();
+    let b = elsewhere.join("b.ogg").to_string_lossy().to_string();
+    assert_eq!(owned_bytes(Some(&dir), &a), 5);
+    assert_eq!(owned_bytes(Some(&dir), &b), 0);
+    let _ = std::fs::remove_dir_all(&root);
+}
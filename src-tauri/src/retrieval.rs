@@ -0,0 +1,221 @@
+//! Retrieval for chat questions: the past transcript chunks closest to the
+//! question (see `embeddings`), added to the prompt as an extra system
+//! message so answers can draw on earlier meetings.
+//!
+//! Off by default, and a no-op while embeddings are off. Chunks are taken
+//! best first while they fit in `max_tokens`; ones below `min_similarity`
+//! are left out rather than padding the prompt with unrelated talk. A
+//! failed retrieval never fails the question, it's just asked without.
+
+use crate::embeddings;
+use crate::history;
+use crate::tokens;
+use crate::vector_index::SourceKind;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const PREAMBLE: &str = "Excerpts from the user's earlier recordings that may be relevant to \
+    their question. Use them only if they help, and say so when an answer relies on them.";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetrievalConfig {
+    pub enabled: bool,
+    /// Most chunks to include.
+    pub top_k: u32,
+    /// Token budget for the whole message, preamble included.
+    pub max_tokens: u32,
+    /// Cosine similarity, 0 to 1. What's relevant depends on the model;
+    /// 0.3 suits OpenAI's `text-embedding-3-*`.
+    pub min_similarity: f32,
+}
+
+impl Default for RetrievalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            top_k: 5,
+            max_tokens: 1500,
+            min_similarity: 0.3,
+        }
+    }
+}
+
+impl RetrievalConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.top_k == 0 {
+            return Err("topK must be at least 1".to_string());
+        }
+        if self.max_tokens == 0 {
+            return Err("maxTokens must be at least 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.min_similarity) {
+            return Err("minSimilarity must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct RetrievalState {
+    config: Mutex<RetrievalConfig>,
+}
+
+/// A chunk that made it into the prompt.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSource {
+    pub entry_id: i64,
+    pub chunk: u32,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`, when the recording was saved.
+    pub created_at: String,
+    pub similarity: f32,
+}
+
+/// Payload of `chat-context-retrieved` and result of `retrieve_context`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievedContext {
+    /// The system message, ready for the prompt.
+    pub text: String,
+    pub sources: Vec<ContextSource>,
+    pub tokens: usize,
+}
+
+/// A candidate chunk with the recording it came from.
+#[derive(Debug, Clone, PartialEq)]
+struct Excerpt {
+    source: ContextSource,
+    text: String,
+}
+
+impl Excerpt {
+    fn render(&self) -> String {
+        let when = self
+            .source
+            .created_at
+            .get(..16)
+            .unwrap_or(&self.source.created_at);
+        format!("[Recording from {} UTC]\n{}", when, self.text.trim())
+    }
+}
+
+/// The message for `excerpts` (best first) within `max_tokens` as counted
+/// by `count`. Excerpts that don't fit are skipped, so a long one doesn't
+/// crowd out shorter ones after it. `None` if none fit.
+fn assemble(
+    excerpts: Vec<Excerpt>,
+    max_tokens: usize,
+    count: impl Fn(&str) -> usize,
+) -> Option<RetrievedContext> {
+    let mut text = PREAMBLE.to_string();
+    let mut tokens = count(&text);
+    let mut sources = Vec::new();
+    for excerpt in excerpts {
+        let part = format!("\n\n{}", excerpt.render());
+        let part_tokens = count(&part);
+        if tokens + part_tokens > max_tokens {
+            continue;
+        }
+        text.push_str(&part);
+        tokens += part_tokens;
+        sources.push(excerpt.source);
+    }
+    (!sources.is_empty()).then_some(RetrievedContext {
+        text,
+        sources,
+        tokens,
+    })
+}
+
+fn config(app: &AppHandle) -> Result<RetrievalConfig, String> {
+    Ok(app
+        .state::<RetrievalState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+/// Past transcript excerpts relevant to `question`, budgeted in `model`'s
+/// tokens. `None` if retrieval or embeddings are off or nothing relevant
+/// was found.
+pub async fn retrieve(
+    app: &AppHandle,
+    question: &str,
+    model: &str,
+) -> Result<Option<RetrievedContext>, String> {
+    let config = config(app)?;
+    if !config.enabled || !embeddings::enabled(app) || question.trim().is_empty() {
+        return Ok(None);
+    }
+    let matches = embeddings::search(app, question, config.top_k as usize).await?;
+    let mut excerpts = Vec::new();
+    for found in matches {
+        if found.source.kind != SourceKind::Transcript || found.score < config.min_similarity {
+            continue;
+        }
+        let Ok(entry_id) = found.source.id.parse() else {
+            continue;
+        };
+        // Skip entries deleted since they were indexed.
+        let Some(entry) = history::entry(app, entry_id).await? else {
+            continue;
+        };
+        excerpts.push(Excerpt {
+            source: ContextSource {
+                entry_id,
+                chunk: found.chunk,
+                created_at: entry.created_at,
+                similarity: found.score,
+            },
+            text: found.text,
+        });
+    }
+    Ok(assemble(excerpts, config.max_tokens as usize, |text| {
+        tokens::count(text, model).tokens
+    }))
+}
+
+/// `retrieve`, logging failures instead of returning them, for callers
+/// that should carry on without context.
+pub async fn retrieve_or_skip(
+    app: &AppHandle,
+    question: &str,
+    model: &str,
+) -> Option<RetrievedContext> {
+    retrieve(app, question, model).await.unwrap_or_else(|e| {
+        tracing::warn!("Context retrieval failed, asking without it: {}", e);
+        None
+    })
+}
+
+#[tauri::command]
+pub fn retrieval_set_config(app: AppHandle, config: RetrievalConfig) -> Result<(), String> {
+    config.validate()?;
+    *app.state::<RetrievalState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn retrieval_get_config(app: AppHandle) -> Result<RetrievalConfig, String> {
+    config(&app)
+}
+
+/// What would be added to the prompt for `question`, budgeted in `model`'s
+/// tokens (estimated with a common encoding if not given).
+#[tauri::command]
+pub async fn retrieve_context(
+    app: AppHandle,
+    question: String,
+    model: Option<String>,
+) -> Result<Option<RetrievedContext>, String> {
+    retrieve(&app, &question, model.as_deref().unwrap_or_default()).await
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,70 @@
+use super::*;
+
+fn excerpt(entry_id: i64, text: &str) -> Excerpt {
+    Excerpt {
+        source: ContextSource {
+            entry_id,
+            chunk: 0,
+            created_at: "2026-10-14 09:30:12".to_string(),
+            similarity: 0.5,
+        },
+        text: text.to_string(),
+    }
+}
+
+/// One token per word.
+fn words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+#[test]
+fn excerpts_fill_the_budget_best_first() {
+    let preamble = words(PREAMBLE);
+    // Each excerpt costs 5 words of header plus its text.
+    let excerpts = vec![
+        excerpt(1, "we agreed on the launch date"),
+        excerpt(2, "a very long tangent about lunch that does not fit"),
+        excerpt(3, "budget is fine"),
+    ];
+    let context = assemble(excerpts, preamble + 11 + 8, words).unwrap();
+    assert_eq!(
+        context
+            .sources
+            .iter()
+            .map(|s| s.entry_id)
+            .collect::<Vec<_>>(),
+        [1, 3]
+    );
+    assert_eq!(context.tokens, preamble + 11 + 8);
+    assert!(context.text.starts_with(PREAMBLE));
+    assert!(context
+        .text
+        .ends_with("[Recording from 2026-10-14 09:30 UTC]\nbudget is fine"));
+
+    assert_eq!(
+        assemble(vec![excerpt(1, "too long")], preamble, words),
+        None
+    );
+    assert_eq!(assemble(Vec::new(), 10_000, words), None);
+}
+
+#[test]
+fn config_is_validated() {
+    assert_eq!(RetrievalConfig::default().validate(), Ok(()));
+    for config in [
+        RetrievalConfig {
+            top_k: 0,
+            ..Default::default()
+        },
+        RetrievalConfig {
+            max_tokens: 0,
+            ..Default::default()
+        },
+        RetrievalConfig {
+            min_similarity: 1.5,
+            ..Default::default()
+        },
+    ] {
+        assert!(config.validate().is_err(), "{:?}", config);
+    }
+}
@@ -0,0 +1,406 @@
+//! Upload of exported files (recordings, transcripts, bundles) to an
+//! S3-compatible bucket: AWS, R2, MinIO, Backblaze and the like. Requests
+//! are signed with AWS Signature Version 4.
+//!
+//! The access key pair lives in the credential store (see `secrets`) under
+//! `s3.access_key_id` and `s3.secret_access_key`; the config only holds
+//! where to upload. Other modules share files through `upload_shared`,
+//! which returns a link that works without credentials.
+
+use crate::network;
+use crate::secrets;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+pub const ACCESS_KEY_ID_SECRET: &str = "s3.access_key_id";
+pub const SECRET_ACCESS_KEY_SECRET: &str = "s3.secret_access_key";
+/// Longest validity SigV4 allows for a presigned URL: 7 days.
+const MAX_PRESIGN_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct S3Config {
+    /// e.g. `https://s3.us-east-1.amazonaws.com` or `http://localhost:9000`.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// `<endpoint>/<bucket>/<key>` instead of `<bucket>.<endpoint host>/<key>`.
+    /// Most self-hosted servers need this.
+    pub path_style: bool,
+    /// Prepended to every object key, e.g. `recordings/`.
+    pub key_prefix: String,
+    /// Base of the returned URL when objects are served from elsewhere
+    /// (a CDN or public bucket domain). Defaults to the upload URL.
+    pub public_base_url: Option<String>,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            region: "us-east-1".to_string(),
+            bucket: String::new(),
+            path_style: false,
+            key_prefix: String::new(),
+            public_base_url: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct S3State {
+    config: Mutex<S3Config>,
+}
+
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 encoding: everything but `A-Za-z0-9-._~` is percent-encoded, and
+/// `/` too unless `keep_slash`.
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{}", secret_access_key).as_bytes(), date);
+    let key = hmac(&key, region);
+    let key = hmac(&key, service);
+    hmac(&key, "aws4_request")
+}
+
+fn scope(amz_date: &str, region: &str) -> String {
+    format!("{}/{}/s3/aws4_request", &amz_date[..8], region)
+}
+
+/// The parts of a request SigV4 signs.
+struct CanonicalRequest<'a> {
+    method: &'a str,
+    /// Already URI-encoded.
+    path: &'a str,
+    /// Sorted and encoded; empty for none.
+    query: &'a str,
+    /// Lowercase names with trimmed values, sorted by name. Must include
+    /// `host`.
+    headers: &'a [(&'a str, String)],
+    payload_hash: &'a str,
+}
+
+impl CanonicalRequest<'_> {
+    fn signed_headers(&self) -> String {
+        self.headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Hex signature of the request at `amz_date`.
+    fn signature(&self, credentials: &Credentials, region: &str, amz_date: &str) -> String {
+        let canonical_headers: String = self
+            .headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let canonical = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            self.method,
+            self.path,
+            self.query,
+            canonical_headers,
+            self.signed_headers(),
+            self.payload_hash
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope(amz_date, region),
+            sha256_hex(canonical.as_bytes())
+        );
+        let key = signing_key(&credentials.secret_access_key, &amz_date[..8], region, "s3");
+        hex(&hmac(&key, &string_to_sign))
+    }
+
+    /// `Authorization` header value; the headers must include `x-amz-date`.
+    fn authorization(&self, credentials: &Credentials, region: &str, amz_date: &str) -> String {
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id,
+            scope(amz_date, region),
+            self.signed_headers(),
+            self.signature(credentials, region, amz_date)
+        )
+    }
+}
+
+/// `url` signed for GETs without credentials until `expires_secs` after
+/// `amz_date`.
+fn presigned_url(
+    credentials: &Credentials,
+    region: &str,
+    url: &reqwest::Url,
+    amz_date: &str,
+    expires_secs: u64,
+) -> String {
+    // Parameters in sorted order, as the canonical query needs them.
+    let query = [
+        ("X-Amz-Algorithm", "AWS4-HMAC-SHA256".to_string()),
+        (
+            "X-Amz-Credential",
+            format!("{}/{}", credentials.access_key_id, scope(amz_date, region)),
+        ),
+        ("X-Amz-Date", amz_date.to_string()),
+        ("X-Amz-Expires", expires_secs.to_string()),
+        ("X-Amz-SignedHeaders", "host".to_string()),
+    ]
+    .iter()
+    .map(|(name, value)| format!("{}={}", name, uri_encode(value, false)))
+    .collect::<Vec<_>>()
+    .join("&");
+    let signature = CanonicalRequest {
+        method: "GET",
+        path: url.path(),
+        query: &query,
+        headers: &[("host", host_header(url))],
+        payload_hash: "UNSIGNED-PAYLOAD",
+    }
+    .signature(credentials, region, amz_date);
+    format!("{}?{}&X-Amz-Signature={}", url, query, signature)
+}
+
+/// `Host` as sent for `url`: the port only when it isn't the default.
+fn host_header(url: &reqwest::Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// Upload URL for `key`, path-style or virtual-hosted per the config.
+fn object_url(config: &S3Config, key: &str) -> Result<reqwest::Url, String> {
+    let mut url =
+        reqwest::Url::parse(&config.endpoint).map_err(|e| format!("Invalid endpoint: {}", e))?;
+    if !matches!(url.scheme(), "https" | "http") {
+        return Err("S3 endpoint must be http(s)".to_string());
+    }
+    if config.bucket.is_empty() {
+        return Err("No S3 bucket configured".to_string());
+    }
+    let base = url.path().trim_end_matches('/').to_string();
+    let path = if config.path_style {
+        format!(
+            "{}/{}/{}",
+            base,
+            uri_encode(&config.bucket, false),
+            uri_encode(key, true)
+        )
+    } else {
+        let host = url.host_str().ok_or("Endpoint has no host")?.to_string();
+        url.set_host(Some(&format!("{}.{}", config.bucket, host)))
+            .map_err(|e| format!("Invalid bucket name: {}", e))?;
+        format!("{}/{}", base, uri_encode(key, true))
+    };
+    url.set_path(&path);
+    Ok(url)
+}
+
+/// The URL to hand out for `key`: under `public_base_url` if set.
+fn public_url(config: &S3Config, key: &str, upload_url: &reqwest::Url) -> String {
+    match config.public_base_url.as_deref().filter(|b| !b.is_empty()) {
+        Some(base) => format!("{}/{}", base.trim_end_matches('/'), uri_encode(key, true)),
+        None => upload_url.to_string(),
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("srt") => "application/x-subrip",
+        Some("vtt") => "text/vtt",
+        Some("json") => "application/json",
+        Some("md") => "text/markdown; charset=utf-8",
+        Some("zip") => "application/zip",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn put_object(
+    app: &AppHandle,
+    config: &S3Config,
+    credentials: &Credentials,
+    key: &str,
+    bytes: Vec<u8>,
+    content_type: &str,
+) -> Result<reqwest::Url, String> {
+    let url = object_url(config, key)?;
+    let host = host_header(&url);
+    let payload_hash = sha256_hex(&bytes);
+    let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let headers = [
+        ("content-type", content_type.to_string()),
+        ("host", host),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    let authorization = CanonicalRequest {
+        method: "PUT",
+        path: url.path(),
+        query: "",
+        headers: &headers,
+        payload_hash: &payload_hash,
+    }
+    .authorization(credentials, &config.region, &amz_date);
+    let response = network::client(app, None)?
+        .put(url.clone())
+        .header("content-type", content_type)
+        .header("x-amz-content-sha256", payload_hash)
+        .header("x-amz-date", amz_date)
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .body(bytes)
+        .send()
+        .await
+        .map_err(|e| format!("S3 upload failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("S3 upload failed: {} {}", status, body.trim()));
+    }
+    Ok(url)
+}
+
+fn current_config(app: &AppHandle) -> Result<S3Config, String> {
+    Ok(app
+        .state::<S3State>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+async fn credentials() -> Result<Credentials, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        Ok::<_, String>(Credentials {
+            access_key_id: secrets::require(ACCESS_KEY_ID_SECRET)?,
+            secret_access_key: secrets::require(SECRET_ACCESS_KEY_SECRET)?,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Upload `bytes` as `<key prefix><name>` and return a link that works
+/// without credentials for `expires` (at most 7 days, the SigV4 limit):
+/// under `public_base_url` if set, presigned otherwise.
+pub async fn upload_shared(
+    app: &AppHandle,
+    name: &str,
+    bytes: Vec<u8>,
+    content_type: &str,
+    expires: Duration,
+) -> Result<String, String> {
+    let config = current_config(app)?;
+    let credentials = credentials().await?;
+    let key = format!("{}{}", config.key_prefix, name);
+    let url = put_object(app, &config, &credentials, &key, bytes, content_type).await?;
+    if config
+        .public_base_url
+        .as_deref()
+        .is_some_and(|b| !b.is_empty())
+    {
+        return Ok(public_url(&config, &key, &url));
+    }
+    let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    Ok(presigned_url(
+        &credentials,
+        &config.region,
+        &url,
+        &amz_date,
+        expires.as_secs().clamp(1, MAX_PRESIGN_SECS),
+    ))
+}
+
+#[tauri::command]
+pub fn s3_set_config(app: AppHandle, config: S3Config) -> Result<(), String> {
+    if !config.endpoint.is_empty() {
+        object_url(&config, "check")?;
+    }
+    *app.state::<S3State>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn s3_get_config(app: AppHandle) -> Result<S3Config, String> {
+    current_config(&app)
+}
+
+/// Upload the file at `path` as `<key prefix><key>` (the file name unless
+/// `key` is given) and return the object URL.
+#[tauri::command]
+pub async fn s3_upload(
+    app: AppHandle,
+    path: String,
+    key: Option<String>,
+) -> Result<String, String> {
+    let config = current_config(&app)?;
+    let path = Path::new(&path);
+    let name = match key.filter(|k| !k.is_empty()) {
+        Some(key) => key,
+        None => path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or("Path has no file name")?,
+    };
+    let key = format!("{}{}", config.key_prefix, name);
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let credentials = credentials().await?;
+    let url = put_object(&app, &config, &credentials, &key, bytes, content_type(path)).await?;
+    Ok(public_url(&config, &key, &url))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,442 @@
+//! A rolling buffer of the last few seconds of screen, the visual
+//! counterpart of the system-audio ring: while running, the primary display
+//! is captured a few times a second and kept as JPEG, and
+//! `screen_clip_capture` turns the most recent frames into a GIF or an MP4.
+//!
+//! GIFs are encoded in-process; MP4s need `ffmpeg` on the PATH. Nothing is
+//! kept while a password field is focused or a privacy-blocklisted app is
+//! in the foreground, and the buffer is cleared when capture stops.
+
+use crate::capture::{capture_primary_monitor_image, encode_image};
+use crate::hidden_command;
+use crate::privacy::frontmost_app_blocked;
+use crate::secure_input::secure_input_active;
+use base64::Engine;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::FilterType;
+use image::{Delay, Frame};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const MAX_FPS: u32 = 10;
+const MAX_SECONDS: u32 = 30;
+const MIN_DIMENSION: u32 = 240;
+const MAX_DIMENSION: u32 = 1920;
+const JPEG_QUALITY: u8 = 70;
+/// GIFs are scaled down further; full-size GIFs are slow to quantize and
+/// huge.
+const GIF_MAX_WIDTH: u32 = 640;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipConfig {
+    /// Frames captured per second.
+    pub fps: u32,
+    /// How much screen is kept.
+    pub seconds: u32,
+    /// Frames are scaled down to fit this on their longer side.
+    pub max_dimension: u32,
+}
+
+impl Default for ClipConfig {
+    fn default() -> Self {
+        Self {
+            fps: 4,
+            seconds: 10,
+            max_dimension: 1280,
+        }
+    }
+}
+
+impl ClipConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_FPS).contains(&self.fps) {
+            return Err(format!("fps must be between 1 and {}", MAX_FPS));
+        }
+        if !(1..=MAX_SECONDS).contains(&self.seconds) {
+            return Err(format!("seconds must be between 1 and {}", MAX_SECONDS));
+        }
+        if !(MIN_DIMENSION..=MAX_DIMENSION).contains(&self.max_dimension) {
+            return Err(format!(
+                "maxDimension must be between {} and {}",
+                MIN_DIMENSION, MAX_DIMENSION
+            ));
+        }
+        Ok(())
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(1) / self.fps
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClipFormat {
+    #[default]
+    Gif,
+    Mp4,
+}
+
+impl ClipFormat {
+    fn mime_type(self) -> &'static str {
+        match self {
+            Self::Gif => "image/gif",
+            Self::Mp4 => "video/mp4",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipExport {
+    /// Base64 of the GIF or MP4.
+    pub data: String,
+    pub mime_type: &'static str,
+    pub frames: usize,
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipStatus {
+    pub running: bool,
+    pub frames: usize,
+    pub buffered_seconds: f64,
+    pub config: Option<ClipConfig>,
+}
+
+#[derive(Clone)]
+struct ClipFrame {
+    captured: Instant,
+    jpeg: Arc<Vec<u8>>,
+}
+
+/// Frames newest last, no older than the configured length behind the
+/// newest one.
+#[derive(Default)]
+struct FrameRing {
+    frames: VecDeque<ClipFrame>,
+}
+
+impl FrameRing {
+    fn push(&mut self, frame: ClipFrame, keep: Duration) {
+        let newest = frame.captured;
+        self.frames.push_back(frame);
+        while self
+            .frames
+            .front()
+            .is_some_and(|f| newest.duration_since(f.captured) > keep)
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// The frames from the last `span` before the newest.
+    fn last(&self, span: Duration) -> Vec<ClipFrame> {
+        let Some(newest) = self.frames.back().map(|f| f.captured) else {
+            return Vec::new();
+        };
+        self.frames
+            .iter()
+            .filter(|f| newest.duration_since(f.captured) <= span)
+            .cloned()
+            .collect()
+    }
+
+    fn span(&self) -> Duration {
+        match (self.frames.front(), self.frames.back()) {
+            (Some(first), Some(last)) => last.captured - first.captured,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+struct Capturer {
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+    config: ClipConfig,
+}
+
+#[derive(Default)]
+pub struct ScreenClipState {
+    frames: Arc<Mutex<FrameRing>>,
+    /// Held across stopping the old thread and spawning the new one, so
+    /// concurrent starts can't leave a capture thread running unowned.
+    capturer: tokio::sync::Mutex<Option<Capturer>>,
+}
+
+/// How long each frame shows: until the next one was captured, and one
+/// `interval` for the last.
+fn frame_delays(times: &[Instant], interval: Duration) -> Vec<Duration> {
+    let mut delays: Vec<Duration> = times.windows(2).map(|w| w[1] - w[0]).collect();
+    if !times.is_empty() {
+        delays.push(interval);
+    }
+    delays
+}
+
+fn encode_gif(frames: &[ClipFrame], interval: Duration) -> Result<Vec<u8>, String> {
+    let times: Vec<Instant> = frames.iter().map(|f| f.captured).collect();
+    let delays = frame_delays(&times, interval);
+    let mut gif = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut gif);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| format!("Failed to encode GIF: {}", e))?;
+        for (frame, delay) in frames.iter().zip(delays) {
+            let image = image::load_from_memory(&frame.jpeg)
+                .map_err(|e| format!("Failed to decode frame: {}", e))?;
+            let image = if image.width() > GIF_MAX_WIDTH {
+                image.resize(GIF_MAX_WIDTH, u32::MAX, FilterType::Triangle)
+            } else {
+                image
+            };
+            let delay = Delay::from_numer_denom_ms(delay.as_millis() as u32, 1);
+            encoder
+                .encode_frame(Frame::from_parts(image.to_rgba8(), 0, 0, delay))
+                .map_err(|e| format!("Failed to encode GIF: {}", e))?;
+        }
+    }
+    Ok(gif)
+}
+
+/// ffmpeg arguments that read JPEG frames on stdin and write H.264 to
+/// `output`. Odd sizes are rounded down to even, which yuv420p needs.
+fn mp4_args(fps: f64, output: &Path) -> Vec<OsString> {
+    let fps = format!("{:.3}", fps);
+    let mut args = Vec::from(
+        [
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-f",
+            "image2pipe",
+            "-c:v",
+            "mjpeg",
+            "-framerate",
+            &fps,
+            "-i",
+            "-",
+            "-vf",
+            "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+            "-c:v",
+            "libx264",
+            "-pix_fmt",
+            "yuv420p",
+            "-movflags",
+            "+faststart",
+        ]
+        .map(OsString::from),
+    );
+    args.push(output.into());
+    args
+}
+
+fn encode_mp4(frames: &[ClipFrame], fps: f64) -> Result<Vec<u8>, String> {
+    let output = std::env::temp_dir().join(format!("screen-clip-{}.mp4", uuid::Uuid::new_v4()));
+    let mut child = hidden_command::new("ffmpeg")
+        .args(mp4_args(fps, &output))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    let written = match child.stdin.take() {
+        Some(mut stdin) => frames
+            .iter()
+            .try_for_each(|frame| stdin.write_all(&frame.jpeg)),
+        None => Ok(()),
+    };
+    if let Err(e) = written {
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_file(&output);
+        return Err(format!("Failed to write to ffmpeg: {}", e));
+    }
+    let result = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    let mp4 = if result.status.success() {
+        std::fs::read(&output).map_err(|e| format!("Failed to read clip: {}", e))
+    } else {
+        Err(format!(
+            "Encoding the clip failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ))
+    };
+    let _ = std::fs::remove_file(&output);
+    mp4
+}
+
+fn spawn_capturer(
+    app: AppHandle,
+    frames: Arc<Mutex<FrameRing>>,
+    config: ClipConfig,
+    stop: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let keep = Duration::from_secs(config.seconds as u64);
+        let mut next = Instant::now();
+        while !stop.load(Ordering::SeqCst) {
+            // Nothing on screen is kept while it may show a password or a
+            // blocklisted app.
+            if !secure_input_active() && !frontmost_app_blocked(&app) {
+                let captured = Instant::now();
+                let jpeg = capture_primary_monitor_image().and_then(|image| {
+                    encode_image(
+                        image,
+                        Some(true),
+                        Some(config.max_dimension),
+                        Some(JPEG_QUALITY),
+                    )
+                });
+                match jpeg {
+                    Ok(jpeg) => {
+                        if let Ok(mut frames) = frames.lock() {
+                            let frame = ClipFrame {
+                                captured,
+                                jpeg: Arc::new(jpeg),
+                            };
+                            frames.push(frame, keep);
+                        }
+                    }
+                    Err(e) => tracing::debug!("Screen clip frame skipped: {}", e),
+                }
+            }
+            next += config.interval();
+            let now = Instant::now();
+            if next > now {
+                thread::sleep(next - now);
+            } else {
+                // Capture is slower than the frame rate; don't try to catch up.
+                next = now;
+            }
+        }
+    })
+}
+
+/// Start keeping the last `config.seconds` of screen, replacing any running
+/// capture.
+#[tauri::command]
+pub async fn screen_clip_start(app: AppHandle, config: ClipConfig) -> Result<(), String> {
+    config.validate()?;
+    let state = app.state::<ScreenClipState>();
+    let mut capturer = state.capturer.lock().await;
+    stop_capturer(&state, &mut capturer).await?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = spawn_capturer(
+        app.clone(),
+        state.frames.clone(),
+        config.clone(),
+        stop.clone(),
+    );
+    *capturer = Some(Capturer {
+        stop,
+        thread,
+        config,
+    });
+    Ok(())
+}
+
+/// Stop the capture thread in `capturer`, if any, and drop the buffered
+/// frames.
+async fn stop_capturer(
+    state: &ScreenClipState,
+    capturer: &mut Option<Capturer>,
+) -> Result<(), String> {
+    if let Some(capturer) = capturer.take() {
+        capturer.stop.store(true, Ordering::SeqCst);
+        tauri::async_runtime::spawn_blocking(move || capturer.thread.join())
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|_| "Screen clip capture panicked".to_string())?;
+    }
+    *state.frames.lock().map_err(|e| e.to_string())? = FrameRing::default();
+    Ok(())
+}
+
+/// Stop capturing and drop the buffered frames.
+#[tauri::command]
+pub async fn screen_clip_stop(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<ScreenClipState>();
+    let mut capturer = state.capturer.lock().await;
+    stop_capturer(&state, &mut capturer).await
+}
+
+/// The last `seconds` of screen (everything buffered if `None`) as base64
+/// GIF or MP4.
+#[tauri::command]
+pub async fn screen_clip_capture(
+    app: AppHandle,
+    seconds: Option<f64>,
+    format: Option<ClipFormat>,
+) -> Result<ClipExport, String> {
+    if let Some(seconds) = seconds {
+        if !seconds.is_finite() || seconds <= 0.0 {
+            return Err(format!("seconds must be positive, got {}", seconds));
+        }
+    }
+    let format = format.unwrap_or_default();
+    let state = app.state::<ScreenClipState>();
+    let config = state
+        .capturer
+        .lock()
+        .await
+        .as_ref()
+        .map(|c| c.config.clone())
+        .ok_or_else(|| "Screen clip capture is not running".to_string())?;
+    let span = seconds
+        .map(Duration::from_secs_f64)
+        .unwrap_or(Duration::from_secs(config.seconds as u64));
+    let frames = state.frames.lock().map_err(|e| e.to_string())?.last(span);
+    if frames.is_empty() {
+        return Err("No screen frames buffered yet".to_string());
+    }
+    let duration = frames[frames.len() - 1].captured - frames[0].captured + config.interval();
+    let frame_count = frames.len();
+    let bytes = tauri::async_runtime::spawn_blocking(move || match format {
+        ClipFormat::Gif => encode_gif(&frames, config.interval()),
+        // The real rate, which falls below `fps` when capture is slow or
+        // frames were skipped.
+        ClipFormat::Mp4 => encode_mp4(&frames, frames.len() as f64 / duration.as_secs_f64()),
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(ClipExport {
+        data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+        mime_type: format.mime_type(),
+        frames: frame_count,
+        duration_seconds: duration.as_secs_f64(),
+    })
+}
+
+#[tauri::command]
+pub async fn screen_clip_status(app: AppHandle) -> Result<ClipStatus, String> {
+    let state = app.state::<ScreenClipState>();
+    let config = state
+        .capturer
+        .lock()
+        .await
+        .as_ref()
+        .map(|c| c.config.clone());
+    let frames = state.frames.lock().map_err(|e| e.to_string())?;
+    Ok(ClipStatus {
+        running: config.is_some(),
+        frames: frames.frames.len(),
+        buffered_seconds: frames.span().as_secs_f64(),
+        config,
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,91 @@
+use super::*;
+
+fn frame(start: Instant, ms: u64) -> ClipFrame {
+    ClipFrame {
+        captured: start + Duration::from_millis(ms),
+        jpeg: Arc::new(ms.to_le_bytes().to_vec()),
+    }
+}
+
+fn offsets(start: Instant, frames: &[ClipFrame]) -> Vec<u128> {
+    frames
+        .iter()
+        .map(|f| (f.captured - start).as_millis())
+        .collect()
+}
+
+#[test]
+fn config_is_range_checked() {
+    assert!(ClipConfig::default().validate().is_ok());
+    let bad = [
+        ClipConfig {
+            fps: 0,
+            ..Default::default()
+        },
+        ClipConfig {
+            fps: 11,
+            ..Default::default()
+        },
+        ClipConfig {
+            seconds: 31,
+            ..Default::default()
+        },
+        ClipConfig {
+            max_dimension: 100,
+            ..Default::default()
+        },
+    ];
+    for config in bad {
+        assert!(config.validate().is_err(), "{:?}", config);
+    }
+    assert_eq!(ClipConfig::default().interval(), Duration::from_millis(250));
+}
+
+#[test]
+fn ring_keeps_only_the_configured_length() {
+    let start = Instant::now();
+    let mut ring = FrameRing::default();
+    for ms in (0..=3000).step_by(500) {
+        ring.push(frame(start, ms), Duration::from_secs(2));
+    }
+    let kept: Vec<ClipFrame> = ring.frames.iter().cloned().collect();
+    assert_eq!(offsets(start, &kept), [1000, 1500, 2000, 2500, 3000]);
+    assert_eq!(ring.span(), Duration::from_secs(2));
+}
+
+#[test]
+fn last_takes_the_newest_frames() {
+    let start = Instant::now();
+    let mut ring = FrameRing::default();
+    assert!(ring.last(Duration::from_secs(1)).is_empty());
+    for ms in [0, 400, 800, 1200] {
+        ring.push(frame(start, ms), Duration::from_secs(10));
+    }
+    assert_eq!(
+        offsets(start, &ring.last(Duration::from_millis(400))),
+        [800, 1200]
+    );
+    assert_eq!(offsets(start, &ring.last(Duration::from_secs(60))).len(), 4);
+}
+
+#[test]
+fn gif_delays_follow_capture_times() {
+    let start = Instant::now();
+    let times = [0, 250, 600].map(|ms| start + Duration::from_millis(ms));
+    assert_eq!(
+        frame_delays(&times, Duration::from_millis(250)),
+        [250, 350, 250].map(Duration::from_millis)
+    );
+    assert!(frame_delays(&[], Duration::from_millis(250)).is_empty());
+}
+
+#[test]
+fn mp4_reads_jpeg_frames_from_stdin() {
+    let args = mp4_args(3.5, Path::new("clip.mp4"));
+    let args: Vec<&str> = args.iter().map(|a| a.to_str().unwrap()).collect();
+    let rate = args.iter().position(|&a| a == "-framerate").unwrap();
+    assert_eq!(args[rate + 1], "3.500");
+    assert_eq!(args[rate + 2..rate + 4], ["-i", "-"]);
+    assert!(args.contains(&"image2pipe"));
+    assert_eq!(args.last(), Some(&"clip.mp4"));
+}
@@ -0,0 +1,140 @@
+//! Masking of sensitive text in screenshots before they leave the app.
+//! OCR finds the words on screen, and any word that is part of an email
+//! address, a card number (Luhn-checked) or something shaped like an API
+//! key or token is painted over with a solid box. A blur would leave
+//! short strings like card digits recoverable.
+
+use crate::screen_text::{ocr_image, OcrLine, OcrWord};
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::ops::Range;
+
+const MASK_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+/// OCR boxes hug the glyphs, so a margin around each is masked too.
+const PADDING: u32 = 3;
+
+static EMAIL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()
+});
+
+/// 13 to 19 digits, optionally grouped with spaces or dashes.
+static CARD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap());
+
+/// Keys with a well-known prefix, JWTs, and any long run of key characters
+/// (kept only if it mixes letters and digits).
+static TOKEN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\b(?:sk|pk|rk)-[A-Za-z0-9_-]{16,}",
+        r"|\bgh[pousr]_[A-Za-z0-9]{20,}",
+        r"|\bxox[abprs]-[A-Za-z0-9-]{10,}",
+        r"|\bAKIA[0-9A-Z]{16}\b",
+        r"|\bAIza[0-9A-Za-z_-]{35}",
+        r"|\beyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+        r"|\b[A-Za-z0-9_-]{32,}",
+    ))
+    .unwrap()
+});
+
+/// Whether the digits of `text` pass the Luhn check.
+fn luhn_valid(text: &str) -> bool {
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    !digits.is_empty() && sum % 10 == 0
+}
+
+fn has_letters_and_digits(text: &str) -> bool {
+    text.chars().any(|c| c.is_ascii_alphabetic()) && text.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Byte ranges of sensitive text in `text`; they may overlap.
+fn sensitive_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = EMAIL.find_iter(text).map(|m| m.range()).collect();
+    ranges.extend(
+        CARD.find_iter(text)
+            .filter(|m| luhn_valid(m.as_str()))
+            .map(|m| m.range()),
+    );
+    ranges.extend(
+        TOKEN
+            .find_iter(text)
+            .filter(|m| has_letters_and_digits(m.as_str()))
+            .map(|m| m.range()),
+    );
+    ranges
+}
+
+/// The words of `line` that overlap sensitive text, reading the line as
+/// its words joined by spaces (so a card number split into groups is
+/// still found).
+fn sensitive_words(line: &OcrLine) -> Vec<&OcrWord> {
+    let mut text = String::new();
+    let mut spans = Vec::with_capacity(line.len());
+    for word in line {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        let start = text.len();
+        text.push_str(&word.text);
+        spans.push(start..text.len());
+    }
+    let ranges = sensitive_ranges(&text);
+    line.iter()
+        .zip(spans)
+        .filter(|(_, span)| {
+            ranges
+                .iter()
+                .any(|range| range.start < span.end && span.start < range.end)
+        })
+        .map(|(word, _)| word)
+        .collect()
+}
+
+/// Fill `word`'s box, plus padding, in place.
+fn mask_word(image: &mut RgbaImage, word: &OcrWord) {
+    let left = word.x.saturating_sub(PADDING).min(image.width());
+    let top = word.y.saturating_sub(PADDING).min(image.height());
+    let right = word
+        .x
+        .saturating_add(word.width)
+        .saturating_add(PADDING)
+        .min(image.width());
+    let bottom = word
+        .y
+        .saturating_add(word.height)
+        .saturating_add(PADDING)
+        .min(image.height());
+    if right <= left || bottom <= top {
+        return;
+    }
+    for y in top..bottom {
+        for x in left..right {
+            image.put_pixel(x, y, MASK_COLOR);
+        }
+    }
+}
+
+/// Mask every sensitive word in `image`, returning how many were masked.
+pub fn mask_sensitive(image: &mut RgbaImage) -> Result<usize, String> {
+    let lines = ocr_image(image)?;
+    let mut masked = 0;
+    for line in &lines {
+        for word in sensitive_words(line) {
+            mask_word(image, word);
+            masked += 1;
+        }
+    }
+    Ok(masked)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,927 @@
+//! Screen recording to MP4, for "record this demo" workflows.
+//!
+//! On macOS: ScreenCaptureKit, with the file written by SCRecordingOutput
+//! (macOS 15 or later; needs the Screen Recording permission).
+//! On Windows: Windows Graphics Capture through ffmpeg's `gfxcapture`
+//! source (FFmpeg 8 or later on the PATH), encoded as H.264.
+//! On Linux: not available.
+//!
+//! With `includeAudio`, the system-audio capture (which must be running)
+//! is drained to a temporary file while recording and muxed into the MP4
+//! by `ffmpeg` when the recording stops. The tap already leaves out the
+//! app's own output, so spoken answers aren't in it.
+//!
+//! A recording made without audio can get it afterwards with
+//! `screen_record_mux_audio`, from the system-audio buffer, as long as the
+//! buffer still holds the span it was recorded over.
+
+use crate::hidden_command;
+use crate::system_audio::{SystemAudioState, OUTPUT_CHANNELS, OUTPUT_SAMPLE_RATE};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// How often the system-audio buffer is drained while recording; well
+/// inside the buffer's length.
+const AUDIO_POLL: Duration = Duration::from_millis(250);
+const MAX_FPS: u32 = 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordOptions {
+    /// MP4 to write; a new `screen-<time>.mp4` in `<app data>/recordings`
+    /// if `None`.
+    pub path: Option<PathBuf>,
+    /// Display to record, by index; 0 is the main display.
+    pub display: usize,
+    pub fps: u32,
+    pub show_cursor: bool,
+    /// Mux in the system-audio capture, which must be running.
+    pub include_audio: bool,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            path: None,
+            display: 0,
+            fps: 30,
+            show_cursor: true,
+            include_audio: false,
+        }
+    }
+}
+
+impl RecordOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_FPS).contains(&self.fps) {
+            return Err(format!("fps must be between 1 and {}", MAX_FPS));
+        }
+        if let Some(path) = &self.path {
+            check_mp4(path)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_mp4(path: &Path) -> Result<(), String> {
+    let mp4 = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"));
+    if !mp4 {
+        return Err(format!("{} is not an .mp4 path", path.display()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenRecording {
+    pub path: String,
+    pub duration_seconds: f64,
+    pub has_audio: bool,
+    /// When recording started (ms since the Unix epoch, host clock), which
+    /// places it against the system-audio buffer.
+    pub started_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenRecordStatus {
+    pub recording: bool,
+    pub path: Option<String>,
+    pub elapsed_seconds: f64,
+}
+
+/// Copies the system-audio buffer to a raw f32 file from a new thread.
+struct AudioPump {
+    path: PathBuf,
+    /// When the first sample in the file was captured.
+    started: Instant,
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<Result<(), String>>,
+}
+
+impl AudioPump {
+    fn start(audio: Arc<SystemAudioState>, path: PathBuf) -> Result<Self, String> {
+        if !audio.is_recording() {
+            return Err("Start system audio capture to record its audio".to_string());
+        }
+        // Plaintext of an encrypted buffer must not reach the disk.
+        if audio.is_buffer_encrypted() {
+            return Err("System audio is encrypted in memory and can't be recorded".to_string());
+        }
+        let file = File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let mut position = audio.written_position();
+        let started = Instant::now();
+        let thread = thread::spawn(move || {
+            let mut writer = BufWriter::new(file);
+            loop {
+                // Read once more after the stop so the tail isn't lost.
+                let stopping = thread_stop.load(Ordering::Acquire);
+                let (samples, next) = audio.read_since(position)?;
+                position = next;
+                for sample in samples {
+                    writer
+                        .write_all(&sample.to_le_bytes())
+                        .map_err(|e| format!("Failed to write audio: {}", e))?;
+                }
+                if stopping {
+                    break;
+                }
+                thread::sleep(AUDIO_POLL);
+            }
+            writer
+                .flush()
+                .map_err(|e| format!("Failed to write audio: {}", e))
+        });
+        Ok(Self {
+            path,
+            started,
+            stop,
+            thread,
+        })
+    }
+
+    /// Stop and return the file.
+    fn finish(self) -> Result<PathBuf, String> {
+        self.stop.store(true, Ordering::Release);
+        self.thread
+            .join()
+            .map_err(|_| "Audio thread panicked".to_string())??;
+        Ok(self.path)
+    }
+}
+
+struct Recording {
+    output: PathBuf,
+    /// Where the platform recorder writes; `output` unless audio is muxed in
+    /// afterwards.
+    video_path: PathBuf,
+    video: platform::VideoRecorder,
+    audio: Option<AudioPump>,
+    started: Instant,
+    started_at_ms: u64,
+}
+
+#[derive(Default)]
+pub struct ScreenRecordState {
+    recording: Mutex<Option<Recording>>,
+}
+
+/// `screen-2026-10-16_14-05-09.mp4`.
+fn file_name(time: DateTime<Local>) -> String {
+    format!("screen-{}.mp4", time.format("%Y-%m-%d_%H-%M-%S"))
+}
+
+/// A new file in `<app data>/recordings` named after `time`, adding a
+/// counter if that name is taken.
+fn default_path(app: &AppHandle, time: DateTime<Local>) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("recordings");
+    let name = file_name(time);
+    let mut path = dir.join(&name);
+    let mut counter = 1;
+    while path.exists() {
+        counter += 1;
+        path = dir.join(format!("{}-{}.mp4", name.trim_end_matches(".mp4"), counter));
+    }
+    Ok(path)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `output` with `.mp4` replaced by `suffix`, for intermediate files.
+fn sibling_path(output: &Path, suffix: &str) -> PathBuf {
+    let mut name = output.file_stem().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    output.with_file_name(name)
+}
+
+/// How to line up audio that started at `audio_started` with video that
+/// started at `video_started`: how far into the video the audio starts,
+/// and how much of the audio's start to skip.
+fn audio_alignment(audio_started: Instant, video_started: Instant) -> (Duration, Duration) {
+    (
+        audio_started.saturating_duration_since(video_started),
+        video_started.saturating_duration_since(audio_started),
+    )
+}
+
+/// `ffmpeg` without a console window flashing up on Windows.
+fn ffmpeg() -> Command {
+    hidden_command::new("ffmpeg")
+}
+
+/// ffmpeg arguments that copy the video from `video` and add the raw
+/// system audio from `audio` as AAC, starting `audio_offset` into the
+/// video with the first `audio_skip` of the audio left out.
+fn mux_args(
+    video: &Path,
+    audio: &Path,
+    output: &Path,
+    audio_offset: Duration,
+    audio_skip: Duration,
+) -> Vec<OsString> {
+    let rate = OUTPUT_SAMPLE_RATE.to_string();
+    let channels = OUTPUT_CHANNELS.to_string();
+    let mut args =
+        Vec::from(["-y", "-hide_banner", "-loglevel", "error", "-i"].map(OsString::from));
+    args.push(video.into());
+    if !audio_offset.is_zero() {
+        let offset = format!("{:.3}", audio_offset.as_secs_f64());
+        args.extend(["-itsoffset", &offset].map(OsString::from));
+    }
+    if !audio_skip.is_zero() {
+        let skip = format!("{:.3}", audio_skip.as_secs_f64());
+        args.extend(["-ss", &skip].map(OsString::from));
+    }
+    args.extend(["-f", "f32le", "-ar", &rate, "-ac", &channels, "-i"].map(OsString::from));
+    args.push(audio.into());
+    args.extend(
+        [
+            "-map", "0:v:0", "-map", "1:a:0", "-c:v", "copy", "-c:a", "aac",
+        ]
+        .map(OsString::from),
+    );
+    args.push(output.into());
+    args
+}
+
+fn mux(
+    video: &Path,
+    audio: &Path,
+    output: &Path,
+    audio_offset: Duration,
+    audio_skip: Duration,
+) -> Result<(), String> {
+    let result = ffmpeg()
+        .args(mux_args(video, audio, output, audio_offset, audio_skip))
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg to add the audio: {}", e))?;
+    if !result.status.success() {
+        return Err(format!(
+            "Adding the audio failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// ffmpeg arguments that record `options.display` with Windows Graphics
+/// Capture to `path`. Quits on `q` on stdin.
+#[cfg(any(target_os = "windows", test))]
+fn capture_args(path: &Path, options: &RecordOptions) -> Vec<OsString> {
+    let source = format!(
+        "gfxcapture=monitor_idx={}:capture_cursor={}:max_framerate={},hwdownload,format=bgra",
+        options.display,
+        u8::from(options.show_cursor),
+        options.fps
+    );
+    let fps = options.fps.to_string();
+    let mut args = Vec::from(
+        [
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-filter_complex",
+            &source,
+            "-c:v",
+            "libx264",
+            "-preset",
+            "veryfast",
+            "-pix_fmt",
+            "yuv420p",
+            "-r",
+            &fps,
+        ]
+        .map(OsString::from),
+    );
+    args.push(path.into());
+    args
+}
+
+/// Stop the recorder, mux in the audio if any, and clean up.
+fn finish(recording: Recording) -> Result<ScreenRecording, String> {
+    let duration_seconds = recording.started.elapsed().as_secs_f64();
+    let video_started = recording.video.started();
+    let stopped = recording.video.stop();
+    let audio = recording.audio.map(|audio| {
+        // The pump starts first, so its audio usually leads the video.
+        let (offset, skip) = audio_alignment(audio.started, video_started);
+        audio.finish().map(|path| (path, offset, skip))
+    });
+    stopped?;
+    let has_audio = audio.is_some();
+    if let Some(audio) = audio {
+        let muxed = audio.and_then(|(audio_path, offset, skip)| {
+            let muxed = mux(
+                &recording.video_path,
+                &audio_path,
+                &recording.output,
+                offset,
+                skip,
+            );
+            let _ = fs::remove_file(&audio_path);
+            muxed
+        });
+        if let Err(e) = muxed {
+            // Keep the video on its own rather than losing it.
+            let _ = fs::rename(&recording.video_path, &recording.output);
+            return Err(format!(
+                "{}; saved without audio to {}",
+                e,
+                recording.output.display()
+            ));
+        }
+        let _ = fs::remove_file(&recording.video_path);
+    }
+    Ok(ScreenRecording {
+        path: recording.output.to_string_lossy().to_string(),
+        duration_seconds,
+        has_audio,
+        started_at_ms: recording.started_at_ms,
+    })
+}
+
+fn take_recording(app: &AppHandle) -> Result<Option<Recording>, String> {
+    Ok(app
+        .state::<ScreenRecordState>()
+        .recording
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take())
+}
+
+/// Finish a recording left running at exit, so its file is playable.
+/// Called from the exit path before system audio stops.
+pub fn finish_on_exit(app: &AppHandle) {
+    let Ok(Some(recording)) = take_recording(app) else {
+        return;
+    };
+    match finish(recording) {
+        Ok(saved) => tracing::info!("Saved screen recording to {}", saved.path),
+        Err(e) => tracing::warn!("Failed to finish screen recording: {}", e),
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::RecordOptions;
+    use block2::RcBlock;
+    use objc2::encode::{Encode, Encoding};
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::{AnyClass, AnyObject, NSObject};
+    use objc2::{define_class, msg_send, AllocAnyThread, DefinedClass};
+    use objc2_foundation::NSString;
+    use std::ffi::CStr;
+    use std::path::Path;
+    use std::ptr;
+    use std::sync::{mpsc, Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    #[link(name = "ScreenCaptureKit", kind = "framework")]
+    extern "C" {}
+
+    /// AVFileTypeMPEG4.
+    const FILE_TYPE_MPEG4: &str = "public.mpeg-4";
+    /// kCMTimeFlags_Valid.
+    const CM_TIME_VALID: u32 = 1;
+    /// Longest to wait for a ScreenCaptureKit completion handler or the
+    /// recording output to finish the file.
+    const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[repr(C)]
+    struct CMTime {
+        value: i64,
+        timescale: i32,
+        flags: u32,
+        epoch: i64,
+    }
+
+    unsafe impl Encode for CMTime {
+        const ENCODING: Encoding = Encoding::Struct(
+            "?",
+            &[i64::ENCODING, i32::ENCODING, u32::ENCODING, i64::ENCODING],
+        );
+    }
+
+    /// An object handed over from a completion handler's thread.
+    struct Shared(Option<Retained<AnyObject>>);
+
+    // SAFETY: moved once to the waiting thread, which is then its only user.
+    unsafe impl Send for Shared {}
+
+    /// What the recording output reports to its delegate.
+    struct Progress {
+        /// When the first frame was written.
+        started: OnceLock<Instant>,
+        /// Gets `None` once the file is finished, or the error.
+        finished: Mutex<Option<mpsc::Sender<Option<String>>>>,
+    }
+
+    impl Progress {
+        fn finish(&self, error: Option<String>) {
+            if let Some(tx) = self.finished.lock().ok().and_then(|mut tx| tx.take()) {
+                let _ = tx.send(error);
+            }
+        }
+    }
+
+    define_class!(
+        // SAFETY: NSObject has no subclassing requirements and the
+        // delegate doesn't implement Drop.
+        #[unsafe(super(NSObject))]
+        #[name = "RunningbordRecordingOutputDelegate"]
+        #[ivars = Progress]
+        struct RecordingDelegate;
+
+        /// SCRecordingOutputDelegate.
+        impl RecordingDelegate {
+            #[unsafe(method(recordingOutputDidStartRecording:))]
+            fn did_start(&self, _output: *mut AnyObject) {
+                let _ = self.ivars().started.set(Instant::now());
+            }
+
+            #[unsafe(method(recordingOutputDidFinishRecording:))]
+            fn did_finish(&self, _output: *mut AnyObject) {
+                self.ivars().finish(None);
+            }
+
+            #[unsafe(method(recordingOutput:didFailWithError:))]
+            fn did_fail(&self, _output: *mut AnyObject, error: *mut AnyObject) {
+                let error = unsafe { error_message(error) };
+                self.ivars().finish(Some(error.unwrap_or_default()));
+            }
+        }
+    );
+
+    impl RecordingDelegate {
+        fn new(finished: mpsc::Sender<Option<String>>) -> Retained<Self> {
+            let this = Self::alloc().set_ivars(Progress {
+                started: OnceLock::new(),
+                finished: Mutex::new(Some(finished)),
+            });
+            unsafe { msg_send![super(this), init] }
+        }
+    }
+
+    pub struct VideoRecorder {
+        stream: Retained<AnyObject>,
+        /// Retained while the stream writes through it.
+        _output: Retained<AnyObject>,
+        /// The output only holds it weakly.
+        delegate: Retained<RecordingDelegate>,
+        finished: mpsc::Receiver<Option<String>>,
+        /// When capture started, in case the first frame isn't reported.
+        capture_started: Instant,
+    }
+
+    // SAFETY: SCStream can be stopped from any thread.
+    unsafe impl Send for VideoRecorder {}
+
+    fn class(name: &[u8]) -> Result<&'static AnyClass, String> {
+        CStr::from_bytes_with_nul(name)
+            .ok()
+            .and_then(AnyClass::get)
+            .ok_or_else(|| "Screen recording needs macOS 15 or later".to_string())
+    }
+
+    unsafe fn error_message(error: *mut AnyObject) -> Option<String> {
+        let error = error.as_ref()?;
+        let text: Option<Retained<NSString>> = msg_send![error, localizedDescription];
+        Some(text.map(|t| t.to_string()).unwrap_or_default())
+    }
+
+    /// Wait for a completion handler that only reports an error.
+    fn wait_for(rx: mpsc::Receiver<Option<String>>, what: &str) -> Result<(), String> {
+        match rx.recv_timeout(CALLBACK_TIMEOUT) {
+            Ok(None) => Ok(()),
+            Ok(Some(e)) => Err(format!("{} failed: {}", what, e)),
+            Err(_) => Err(format!("{} timed out", what)),
+        }
+    }
+
+    unsafe fn display(index: usize) -> Result<Retained<AnyObject>, String> {
+        let (tx, rx) = mpsc::channel::<Result<Shared, String>>();
+        let block = RcBlock::new(move |content: *mut AnyObject, error: *mut AnyObject| {
+            let result = match error_message(error) {
+                Some(e) => Err(e),
+                None => Ok(Shared(Retained::retain(content))),
+            };
+            let _ = tx.send(result);
+        });
+        let _: () = msg_send![
+            class(b"SCShareableContent\0")?,
+            getShareableContentExcludingDesktopWindows: false,
+            onScreenWindowsOnly: true,
+            completionHandler: &*block
+        ];
+        let content = rx
+            .recv_timeout(CALLBACK_TIMEOUT)
+            .map_err(|_| "Listing displays timed out".to_string())?
+            .map_err(|e| {
+                format!(
+                    "Failed to list displays (is Screen Recording allowed?): {}",
+                    e
+                )
+            })?
+            .0
+            .ok_or_else(|| "No displays to record".to_string())?;
+        let displays: Option<Retained<AnyObject>> = msg_send![&*content, displays];
+        let displays = displays.ok_or_else(|| "No displays to record".to_string())?;
+        let count: usize = msg_send![&*displays, count];
+        if index >= count {
+            return Err(format!("Display {} not found ({} connected)", index, count));
+        }
+        let display: Option<Retained<AnyObject>> = msg_send![&*displays, objectAtIndex: index];
+        display.ok_or_else(|| format!("Display {} not found", index))
+    }
+
+    pub fn start(path: &Path, options: &RecordOptions) -> Result<VideoRecorder, String> {
+        unsafe {
+            let display = display(options.display)?;
+            let no_windows: Retained<AnyObject> = msg_send![class(b"NSArray\0")?, array];
+            let filter: Allocated<AnyObject> = msg_send![class(b"SCContentFilter\0")?, alloc];
+            let filter: Option<Retained<AnyObject>> =
+                msg_send![filter, initWithDisplay: &*display, excludingWindows: &*no_windows];
+            let filter = filter.ok_or_else(|| "Failed to create content filter".to_string())?;
+
+            // Full pixel size, not points.
+            let info: Option<Retained<AnyObject>> =
+                msg_send![class(b"SCShareableContent\0")?, infoForFilter: &*filter];
+            let scale: f32 = match &info {
+                Some(info) => msg_send![&**info, pointPixelScale],
+                None => 1.0,
+            };
+            let width: isize = msg_send![&*display, width];
+            let height: isize = msg_send![&*display, height];
+
+            let config: Retained<AnyObject> = msg_send![class(b"SCStreamConfiguration\0")?, new];
+            let _: () = msg_send![&*config, setWidth: (width as f32 * scale) as usize];
+            let _: () = msg_send![&*config, setHeight: (height as f32 * scale) as usize];
+            let _: () = msg_send![&*config, setShowsCursor: options.show_cursor];
+            let interval = CMTime {
+                value: 1,
+                timescale: options.fps as i32,
+                flags: CM_TIME_VALID,
+                epoch: 0,
+            };
+            let _: () = msg_send![&*config, setMinimumFrameInterval: interval];
+
+            let stream: Allocated<AnyObject> = msg_send![class(b"SCStream\0")?, alloc];
+            let stream: Option<Retained<AnyObject>> = msg_send![
+                stream,
+                initWithFilter: &*filter,
+                configuration: &*config,
+                delegate: ptr::null_mut::<AnyObject>()
+            ];
+            let stream = stream.ok_or_else(|| "Failed to create capture stream".to_string())?;
+
+            let url: Option<Retained<AnyObject>> = msg_send![
+                class(b"NSURL\0")?,
+                fileURLWithPath: &*NSString::from_str(&path.to_string_lossy())
+            ];
+            let url = url.ok_or_else(|| "Failed to create file URL".to_string())?;
+            let output_config: Retained<AnyObject> =
+                msg_send![class(b"SCRecordingOutputConfiguration\0")?, new];
+            let _: () = msg_send![&*output_config, setOutputURL: &*url];
+            let _: () = msg_send![
+                &*output_config,
+                setOutputFileType: &*NSString::from_str(FILE_TYPE_MPEG4)
+            ];
+            let (finished_tx, finished) = mpsc::channel();
+            let delegate = RecordingDelegate::new(finished_tx);
+            let output: Allocated<AnyObject> = msg_send![class(b"SCRecordingOutput\0")?, alloc];
+            let output: Option<Retained<AnyObject>> = msg_send![
+                output,
+                initWithConfiguration: &*output_config,
+                delegate: &*delegate
+            ];
+            let output = output.ok_or_else(|| "Failed to create recording output".to_string())?;
+            let mut error: *mut AnyObject = ptr::null_mut();
+            let added: bool = msg_send![
+                &*stream,
+                addRecordingOutput: &*output,
+                error: &mut error as *mut *mut AnyObject
+            ];
+            if !added {
+                return Err(format!(
+                    "Failed to add recording output: {}",
+                    error_message(error).unwrap_or_default()
+                ));
+            }
+
+            let (tx, rx) = mpsc::channel();
+            let block = RcBlock::new(move |error: *mut AnyObject| {
+                let _ = tx.send(error_message(error));
+            });
+            let _: () = msg_send![&*stream, startCaptureWithCompletionHandler: &*block];
+            wait_for(rx, "Starting the screen recording")?;
+            Ok(VideoRecorder {
+                stream,
+                _output: output,
+                delegate,
+                finished,
+                capture_started: Instant::now(),
+            })
+        }
+    }
+
+    impl VideoRecorder {
+        pub fn started(&self) -> Instant {
+            let started = self.delegate.ivars().started.get();
+            started.copied().unwrap_or(self.capture_started)
+        }
+
+        /// Stop capturing and wait for the output to finish the file.
+        pub fn stop(self) -> Result<(), String> {
+            let (tx, rx) = mpsc::channel();
+            let block = RcBlock::new(move |error: *mut AnyObject| {
+                let _ = tx.send(unsafe { error_message(error) });
+            });
+            unsafe {
+                let _: () = msg_send![&*self.stream, stopCaptureWithCompletionHandler: &*block];
+            }
+            wait_for(rx, "Stopping the screen recording")?;
+            wait_for(self.finished, "Writing the screen recording")
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{capture_args, ffmpeg, RecordOptions};
+    use std::io::{Read, Write};
+    use std::path::Path;
+    use std::process::{Child, Stdio};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// ffmpeg exits within this long if the capture can't start.
+    const STARTUP_CHECK: Duration = Duration::from_millis(750);
+
+    pub struct VideoRecorder {
+        child: Child,
+        started: Instant,
+    }
+
+    pub fn start(path: &Path, options: &RecordOptions) -> Result<VideoRecorder, String> {
+        let mut child = ffmpeg()
+            .args(capture_args(path, options))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                format!(
+                    "Failed to run ffmpeg (FFmpeg 8 or later must be on the PATH): {}",
+                    e
+                )
+            })?;
+        // ffmpeg gives no signal for its first frame; capture starts about
+        // as soon as it's running.
+        let started = Instant::now();
+        thread::sleep(STARTUP_CHECK);
+        if let Ok(Some(status)) = child.try_wait() {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr);
+            }
+            return Err(format!(
+                "Screen recording failed to start ({}): {}",
+                status,
+                stderr.trim()
+            ));
+        }
+        Ok(VideoRecorder { child, started })
+    }
+
+    impl VideoRecorder {
+        pub fn started(&self) -> Instant {
+            self.started
+        }
+
+        pub fn stop(mut self) -> Result<(), String> {
+            // `q` makes ffmpeg finish the file; killing it would leave the
+            // MP4 without its index.
+            if let Some(mut stdin) = self.child.stdin.take() {
+                let _ = stdin.write_all(b"q");
+            }
+            let output = self
+                .child
+                .wait_with_output()
+                .map_err(|e| format!("Failed to stop ffmpeg: {}", e))?;
+            if !output.status.success() {
+                return Err(format!(
+                    "Screen recording failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::RecordOptions;
+    use std::path::Path;
+    use std::time::Instant;
+
+    pub struct VideoRecorder;
+
+    pub fn start(_path: &Path, _options: &RecordOptions) -> Result<VideoRecorder, String> {
+        Err("Screen recording is only supported on macOS and Windows".to_string())
+    }
+
+    impl VideoRecorder {
+        pub fn started(&self) -> Instant {
+            Instant::now()
+        }
+
+        pub fn stop(self) -> Result<(), String> {
+            Ok(())
+        }
+    }
+}
+
+/// Start recording the screen; returns the path the MP4 will be saved to.
+#[tauri::command]
+pub async fn screen_record_start(app: AppHandle, options: RecordOptions) -> Result<String, String> {
+    options.validate()?;
+    {
+        let recording = app
+            .state::<ScreenRecordState>()
+            .recording
+            .lock()
+            .map_err(|e| e.to_string())?;
+        if recording.is_some() {
+            return Err("A screen recording is already running".to_string());
+        }
+    }
+    let output = match &options.path {
+        Some(path) => path.clone(),
+        None => default_path(&app, Local::now())?,
+    };
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let audio = options
+        .include_audio
+        .then(|| {
+            AudioPump::start(
+                app.state::<Arc<SystemAudioState>>().inner().clone(),
+                sibling_path(&output, ".audio.f32"),
+            )
+        })
+        .transpose()?;
+    let video_path = if audio.is_some() {
+        sibling_path(&output, ".video.mp4")
+    } else {
+        output.clone()
+    };
+    let start_path = video_path.clone();
+    let started =
+        tauri::async_runtime::spawn_blocking(move || platform::start(&start_path, &options))
+            .await
+            .map_err(|e| e.to_string())?;
+    let video = match started {
+        Ok(video) => video,
+        Err(e) => {
+            if let Some(audio) = audio {
+                let _ = audio.finish().map(fs::remove_file);
+            }
+            return Err(e);
+        }
+    };
+    let path = output.to_string_lossy().to_string();
+    let recording = Recording {
+        output,
+        video_path,
+        video,
+        audio,
+        started: Instant::now(),
+        started_at_ms: now_millis(),
+    };
+    let raced = {
+        let mut slot = app
+            .state::<ScreenRecordState>()
+            .recording
+            .lock()
+            .map_err(|e| e.to_string())?;
+        match slot.as_ref() {
+            Some(_) => Some(recording),
+            None => {
+                *slot = Some(recording);
+                None
+            }
+        }
+    };
+    if let Some(recording) = raced {
+        // Another start won the race; don't leave this one running.
+        let _ = tauri::async_runtime::spawn_blocking(move || finish(recording)).await;
+        return Err("A screen recording is already running".to_string());
+    }
+    tracing::info!("Screen recording to {}", path);
+    Ok(path)
+}
+
+/// Stop recording and finish the MP4.
+#[tauri::command]
+pub async fn screen_record_stop(app: AppHandle) -> Result<ScreenRecording, String> {
+    let recording =
+        take_recording(&app)?.ok_or_else(|| "No screen recording is running".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || finish(recording))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Add the system audio captured while `recording` was made to a copy of
+/// it at `output` (`<name>.with-audio.mp4` next to it if `None`), replacing
+/// any audio it had. The audio is placed by wall-clock time; if the buffer
+/// no longer reaches back to the start, it starts part way in.
+#[tauri::command]
+pub async fn screen_record_mux_audio(
+    app: AppHandle,
+    recording: ScreenRecording,
+    output: Option<PathBuf>,
+) -> Result<ScreenRecording, String> {
+    let video = PathBuf::from(&recording.path);
+    if !video.is_file() {
+        return Err(format!("{} not found", video.display()));
+    }
+    if !recording.duration_seconds.is_finite() || recording.duration_seconds <= 0.0 {
+        return Err("The recording has no duration".to_string());
+    }
+    let output = output.unwrap_or_else(|| sibling_path(&video, ".with-audio.mp4"));
+    check_mp4(&output)?;
+    if output == video {
+        return Err("The output must not replace the recording".to_string());
+    }
+    let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+    // Plaintext of an encrypted buffer must not reach the disk.
+    if audio.is_buffer_encrypted() {
+        return Err("System audio is encrypted in memory and can't be exported".to_string());
+    }
+    let end_ms = recording.started_at_ms + (recording.duration_seconds * 1000.0).ceil() as u64;
+    let timed = audio.get_raw_between_times(recording.started_at_ms, end_ms)?;
+    let offset = Duration::from_millis(timed.start_time_ms.saturating_sub(recording.started_at_ms));
+    tauri::async_runtime::spawn_blocking(move || {
+        let audio_path = sibling_path(&output, ".audio.f32");
+        let bytes: Vec<u8> = timed.samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        fs::write(&audio_path, bytes)
+            .map_err(|e| format!("Failed to write {}: {}", audio_path.display(), e))?;
+        let muxed = mux(&video, &audio_path, &output, offset, Duration::ZERO);
+        let _ = fs::remove_file(&audio_path);
+        muxed?;
+        Ok(ScreenRecording {
+            path: output.to_string_lossy().to_string(),
+            has_audio: true,
+            ..recording
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn screen_record_status(app: AppHandle) -> Result<ScreenRecordStatus, String> {
+    let state = app.state::<ScreenRecordState>();
+    let recording = state.recording.lock().map_err(|e| e.to_string())?;
+    Ok(match recording.as_ref() {
+        Some(recording) => ScreenRecordStatus {
+            recording: true,
+            path: Some(recording.output.to_string_lossy().to_string()),
+            elapsed_seconds: recording.started.elapsed().as_secs_f64(),
+        },
+        None => ScreenRecordStatus {
+            recording: false,
+            path: None,
+            elapsed_seconds: 0.0,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,142 @@
+use super::*;
+use chrono::TimeZone;
+
+fn strings(args: &[OsString]) -> Vec<&str> {
+    args.iter().map(|a| a.to_str().unwrap()).collect()
+}
+
+#[test]
+fn options_are_validated() {
+    assert!(RecordOptions::default().validate().is_ok());
+    let fps = |fps| RecordOptions {
+        fps,
+        ..Default::default()
+    };
+    assert!(fps(0).validate().is_err());
+    assert!(fps(60).validate().is_ok());
+    assert!(fps(61).validate().is_err());
+    let path = |path: &str| RecordOptions {
+        path: Some(PathBuf::from(path)),
+        ..Default::default()
+    };
+    assert!(path("/tmp/demo.MP4").validate().is_ok());
+    assert!(path("/tmp/demo.mov").validate().is_err());
+    assert!(path("/tmp/demo").validate().is_err());
+}
+
+#[test]
+fn file_names_carry_the_start_time() {
+    let time = Local.with_ymd_and_hms(2026, 10, 16, 14, 5, 9).unwrap();
+    assert_eq!(file_name(time), "screen-2026-10-16_14-05-09.mp4");
+}
+
+#[test]
+fn intermediate_files_sit_next_to_the_output() {
+    let output = Path::new("/tmp/rec/demo.mp4");
+    assert_eq!(
+        sibling_path(output, ".video.mp4"),
+        PathBuf::from("/tmp/rec/demo.video.mp4")
+    );
+    assert_eq!(
+        sibling_path(output, ".audio.f32"),
+        PathBuf::from("/tmp/rec/demo.audio.f32")
+    );
+}
+
+#[test]
+fn mux_copies_video_and_reads_raw_system_audio() {
+    let args = mux_args(
+        Path::new("v.mp4"),
+        Path::new("a.f32"),
+        Path::new("out.mp4"),
+        Duration::ZERO,
+        Duration::ZERO,
+    );
+    assert_eq!(
+        strings(&args),
+        [
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-i",
+            "v.mp4",
+            "-f",
+            "f32le",
+            "-ar",
+            "16000",
+            "-ac",
+            "1",
+            "-i",
+            "a.f32",
+            "-map",
+            "0:v:0",
+            "-map",
+            "1:a:0",
+            "-c:v",
+            "copy",
+            "-c:a",
+            "aac",
+            "out.mp4",
+        ]
+    );
+}
+
+#[test]
+fn late_audio_is_offset_into_the_video() {
+    let args = mux_args(
+        Path::new("v.mp4"),
+        Path::new("a.f32"),
+        Path::new("out.mp4"),
+        Duration::from_millis(1250),
+        Duration::ZERO,
+    );
+    let args = strings(&args);
+    let offset = args.iter().position(|&a| a == "-itsoffset").unwrap();
+    // Applies to the audio input that follows it.
+    assert_eq!(args[offset + 1..offset + 3], ["1.250", "-f"]);
+    assert!(offset > args.iter().position(|&a| a == "v.mp4").unwrap());
+}
+
+#[test]
+fn early_audio_is_trimmed_to_the_video() {
+    let args = mux_args(
+        Path::new("v.mp4"),
+        Path::new("a.f32"),
+        Path::new("out.mp4"),
+        Duration::ZERO,
+        Duration::from_millis(400),
+    );
+    let args = strings(&args);
+    assert!(!args.contains(&"-itsoffset"));
+    let skip = args.iter().position(|&a| a == "-ss").unwrap();
+    assert_eq!(args[skip + 1..skip + 3], ["0.400", "-f"]);
+    assert!(skip > args.iter().position(|&a| a == "v.mp4").unwrap());
+}
+
+#[test]
+fn audio_is_aligned_by_which_started_first() {
+    let video = Instant::now();
+    let ms = Duration::from_millis;
+    assert_eq!(audio_alignment(video - ms(300), video), (ms(0), ms(300)));
+    assert_eq!(audio_alignment(video + ms(250), video), (ms(250), ms(0)));
+    assert_eq!(audio_alignment(video, video), (ms(0), ms(0)));
+}
+
+#[test]
+fn capture_uses_windows_graphics_capture() {
+    let options = RecordOptions {
+        display: 1,
+        fps: 24,
+        show_cursor: false,
+        ..Default::default()
+    };
+    let args = capture_args(Path::new("out.mp4"), &options);
+    let args = strings(&args);
+    let source = args[args.iter().position(|&a| a == "-filter_complex").unwrap() + 1];
+    assert_eq!(
+        source,
+        "gfxcapture=monitor_idx=1:capture_cursor=0:max_framerate=24,hwdownload,format=bgra"
+    );
+    assert_eq!(args[args.len() - 3..], ["-r", "24", "out.mp4"]);
+}
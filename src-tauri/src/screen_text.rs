@@ -0,0 +1,729 @@
+//! On-screen text for the context builder: the text of the focused window
+//! read from the accessibility tree, which is faster and cleaner than OCR,
+//! falling back to OCR of the primary display when the tree has nothing.
+//!
+//! Accessibility:
+//! On macOS: the AX tree of the frontmost app's focused window (needs the
+//! Accessibility permission).
+//! On Windows: UI Automation (via PowerShell) on the app's main window.
+//! On Linux: not available; OCR is always used.
+//!
+//! OCR: Vision on macOS, Windows.Media.Ocr on Windows, the `tesseract`
+//! command on Linux. It reports where each word is, which `screen_mask`
+//! uses to mask sensitive text.
+//!
+//! Secure and password fields are never read, and nothing is read while a
+//! privacy-blocklisted app is in the foreground.
+
+use crate::capture::capture_primary_monitor_image;
+use crate::frontmost_app::{frontmost_app, FrontmostApp};
+use crate::privacy::app_blocked;
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// The accessibility walk stops after this many elements.
+const MAX_NODES: usize = 5000;
+const MAX_DEPTH: usize = 64;
+const MAX_TEXT_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextSource {
+    Accessibility,
+    Ocr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextRole {
+    Heading,
+    Text,
+    Link,
+    Button,
+    /// Text fields and editable documents.
+    Field,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextElement {
+    pub role: TextRole,
+    pub text: String,
+}
+
+/// A word found by OCR and its box, in pixels of the image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcrWord {
+    pub text: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The words of one line of text, left to right.
+pub type OcrLine = Vec<OcrWord>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenText {
+    pub source: TextSource,
+    pub app: Option<FrontmostApp>,
+    /// In reading order (tree order for accessibility, top to bottom for
+    /// OCR, where every line is `Text`).
+    pub elements: Vec<TextElement>,
+    /// Element texts, one per line.
+    pub text: String,
+    /// More than 64 KiB of text was found; the rest was dropped.
+    pub truncated: bool,
+}
+
+impl ScreenText {
+    /// Normalize whitespace, drop empty elements and repeats of the previous
+    /// element (a heading and its static text often carry the same string),
+    /// and cap the total text.
+    fn new(source: TextSource, app: Option<FrontmostApp>, raw: Vec<TextElement>) -> Self {
+        let mut elements: Vec<TextElement> = Vec::new();
+        let mut text = String::new();
+        let mut truncated = false;
+        for element in raw {
+            let cleaned = element
+                .text
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            if cleaned.is_empty() || elements.last().is_some_and(|last| last.text == cleaned) {
+                continue;
+            }
+            if text.len() + cleaned.len() + 1 > MAX_TEXT_BYTES {
+                truncated = true;
+                break;
+            }
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&cleaned);
+            elements.push(TextElement {
+                role: element.role,
+                text: cleaned,
+            });
+        }
+        Self {
+            source,
+            app,
+            elements,
+            text,
+            truncated,
+        }
+    }
+}
+
+/// Parse the `Role<TAB>text` lines printed by the UI Automation script.
+#[cfg(any(target_os = "windows", test))]
+fn parse_uia_output(output: &str) -> Vec<TextElement> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (control_type, text) = line.split_once('\t')?;
+            let role = match control_type {
+                "Text" => TextRole::Text,
+                "Hyperlink" => TextRole::Link,
+                "Button" => TextRole::Button,
+                "Edit" | "Document" => TextRole::Field,
+                _ => return None,
+            };
+            Some(TextElement {
+                role,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Group words into lines, a new line starting whenever `key` changes.
+#[cfg(any(not(target_os = "macos"), test))]
+fn group_lines<K: PartialEq>(words: impl IntoIterator<Item = (K, OcrWord)>) -> Vec<OcrLine> {
+    let mut lines: Vec<OcrLine> = Vec::new();
+    let mut current = None;
+    for (key, word) in words {
+        if current.as_ref() != Some(&key) {
+            lines.push(Vec::new());
+            current = Some(key);
+        }
+        if let Some(line) = lines.last_mut() {
+            line.push(word);
+        }
+    }
+    lines
+}
+
+/// Parse the `line<TAB>x<TAB>y<TAB>width<TAB>height<TAB>word` rows printed
+/// by the OCR script.
+#[cfg(any(target_os = "windows", test))]
+fn parse_ocr_words(output: &str) -> Vec<OcrLine> {
+    group_lines(output.lines().filter_map(|row| {
+        let fields: Vec<&str> = row.splitn(6, '\t').collect();
+        let [line, x, y, width, height, text] = fields[..] else {
+            return None;
+        };
+        let word = OcrWord {
+            text: text.trim().to_string(),
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        };
+        (!word.text.is_empty()).then_some((line.to_string(), word))
+    }))
+}
+
+/// Parse `tesseract ... tsv` output, keeping the word rows (level 5).
+#[cfg(any(not(any(target_os = "macos", target_os = "windows")), test))]
+fn parse_tesseract_tsv(output: &str) -> Vec<OcrLine> {
+    group_lines(output.lines().skip(1).filter_map(|row| {
+        let fields: Vec<&str> = row.splitn(12, '\t').collect();
+        let [level, _page, block, paragraph, line, _word, x, y, width, height, _conf, text] =
+            fields[..]
+        else {
+            return None;
+        };
+        if level != "5" || text.trim().is_empty() {
+            return None;
+        }
+        let word = OcrWord {
+            text: text.trim().to_string(),
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        };
+        Some(((block, paragraph, line), word))
+    }))
+}
+
+/// Whitespace-separated words of `text` with their UTF-16 offset and
+/// length, as NSString ranges count.
+#[cfg(any(target_os = "macos", test))]
+fn word_spans(text: &str) -> Vec<(String, usize, usize)> {
+    let mut spans = Vec::new();
+    let mut word = String::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                spans.push((std::mem::take(&mut word), start, offset - start));
+            }
+        } else {
+            if word.is_empty() {
+                start = offset;
+            }
+            word.push(c);
+        }
+        offset += c.len_utf16();
+    }
+    if !word.is_empty() {
+        spans.push((word, start, offset - start));
+    }
+    spans
+}
+
+/// A Vision box (normalized, origin bottom left) as `(x, y, width,
+/// height)` in pixels of a `width`x`height` image, rounded outwards.
+#[cfg(any(target_os = "macos", test))]
+fn vision_rect_to_pixels(
+    rect: (f64, f64, f64, f64),
+    width: u32,
+    height: u32,
+) -> (u32, u32, u32, u32) {
+    let (x, y, w, h) = rect;
+    let (width, height) = (width as f64, height as f64);
+    let left = (x * width).floor().clamp(0.0, width);
+    let right = ((x + w) * width).ceil().clamp(0.0, width);
+    let top = ((1.0 - y - h) * height).floor().clamp(0.0, height);
+    let bottom = ((1.0 - y) * height).ceil().clamp(0.0, height);
+    (
+        left as u32,
+        top as u32,
+        (right - left).max(0.0) as u32,
+        (bottom - top).max(0.0) as u32,
+    )
+}
+
+/// OCR `image`. It's handed over as an in-memory PNG, never a file, since
+/// it may hold the very text `screen_mask` is about to hide.
+pub fn ocr_image(image: &image::RgbaImage) -> Result<Vec<OcrLine>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode screenshot for OCR: {}", e))?;
+    platform::ocr(&png, image.width(), image.height())
+}
+
+/// Run `command` with `input` on its stdin and collect its output. Stdin
+/// is written from another thread so a full stdout pipe can't deadlock it.
+#[cfg(not(target_os = "macos"))]
+fn output_with_input(
+    command: &mut std::process::Command,
+    input: Vec<u8>,
+) -> std::io::Result<std::process::Output> {
+    use std::io::Write;
+    use std::process::Stdio;
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer
+        .join()
+        .map_err(|_| std::io::Error::other("stdin writer panicked"))??;
+    Ok(output)
+}
+
+/// OCR the primary display, one element per line.
+fn ocr_primary_display() -> Result<Vec<TextElement>, String> {
+    let lines = ocr_image(&capture_primary_monitor_image()?)?;
+    Ok(lines
+        .into_iter()
+        .map(|line| TextElement {
+            role: TextRole::Text,
+            text: line
+                .into_iter()
+                .map(|word| word.text)
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+        .collect())
+}
+
+/// Text of the focused window, or of the whole primary display via OCR if
+/// the accessibility tree is unavailable or empty (or `force_ocr` is set).
+pub fn screen_text(app: &AppHandle, force_ocr: bool) -> Result<ScreenText, String> {
+    let front = frontmost_app();
+    if front.as_ref().is_some_and(|front| app_blocked(app, front)) {
+        return Err("The foreground app is on the privacy blocklist".to_string());
+    }
+    if !force_ocr {
+        if let Some(front) = &front {
+            match platform::accessibility_text(front) {
+                Ok(elements) => {
+                    let text =
+                        ScreenText::new(TextSource::Accessibility, Some(front.clone()), elements);
+                    if !text.elements.is_empty() {
+                        return Ok(text);
+                    }
+                }
+                Err(e) => tracing::debug!("Accessibility text unavailable: {}", e),
+            }
+        }
+    }
+    let elements = ocr_primary_display()?;
+    Ok(ScreenText::new(TextSource::Ocr, front, elements))
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{
+        vision_rect_to_pixels, word_spans, OcrLine, OcrWord, TextElement, TextRole, MAX_DEPTH,
+        MAX_NODES,
+    };
+    use crate::frontmost_app::FrontmostApp;
+    use objc2::encode::{Encode, Encoding};
+    use objc2::msg_send;
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2_foundation::NSString;
+    use std::ffi::{c_void, CStr};
+    use std::ptr;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+        fn AXUIElementCreateApplication(pid: i32) -> *mut c_void;
+        fn AXUIElementCopyAttributeValue(
+            element: *const c_void,
+            attribute: *const c_void,
+            value: *mut *mut c_void,
+        ) -> i32;
+    }
+
+    #[link(name = "Vision", kind = "framework")]
+    extern "C" {}
+
+    /// VNRequestTextRecognitionLevelAccurate.
+    const RECOGNITION_LEVEL_ACCURATE: isize = 0;
+
+    #[repr(C)]
+    struct NSRange {
+        location: usize,
+        length: usize,
+    }
+
+    unsafe impl Encode for NSRange {
+        const ENCODING: Encoding =
+            Encoding::Struct("_NSRange", &[usize::ENCODING, usize::ENCODING]);
+    }
+
+    #[repr(C)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    #[repr(C)]
+    struct CGSize {
+        width: f64,
+        height: f64,
+    }
+
+    #[repr(C)]
+    struct CGRect {
+        origin: CGPoint,
+        size: CGSize,
+    }
+
+    unsafe impl Encode for CGPoint {
+        const ENCODING: Encoding = Encoding::Struct("CGPoint", &[f64::ENCODING, f64::ENCODING]);
+    }
+
+    unsafe impl Encode for CGSize {
+        const ENCODING: Encoding = Encoding::Struct("CGSize", &[f64::ENCODING, f64::ENCODING]);
+    }
+
+    unsafe impl Encode for CGRect {
+        const ENCODING: Encoding =
+            Encoding::Struct("CGRect", &[CGPoint::ENCODING, CGSize::ENCODING]);
+    }
+
+    impl CGRect {
+        fn parts(&self) -> (f64, f64, f64, f64) {
+            (
+                self.origin.x,
+                self.origin.y,
+                self.size.width,
+                self.size.height,
+            )
+        }
+    }
+
+    fn class(name: &[u8]) -> Result<&'static AnyClass, String> {
+        CStr::from_bytes_with_nul(name)
+            .ok()
+            .and_then(AnyClass::get)
+            .ok_or_else(|| "Vision is not available".to_string())
+    }
+
+    /// An AX attribute of `element` (AXUIElementRef is bridged to an object).
+    unsafe fn attribute(element: &AnyObject, name: &str) -> Option<Retained<AnyObject>> {
+        let name = NSString::from_str(name);
+        let mut value: *mut c_void = ptr::null_mut();
+        let err = AXUIElementCopyAttributeValue(
+            element as *const AnyObject as *const c_void,
+            Retained::as_ptr(&name) as *const c_void,
+            &mut value,
+        );
+        if err != 0 {
+            return None;
+        }
+        // Copy rule: we own the returned value.
+        Retained::from_raw(value as *mut AnyObject)
+    }
+
+    unsafe fn string_attribute(element: &AnyObject, name: &str) -> Option<String> {
+        let value = attribute(element, name)?;
+        value.downcast::<NSString>().ok().map(|s| s.to_string())
+    }
+
+    unsafe fn walk(
+        element: &AnyObject,
+        depth: usize,
+        nodes: &mut usize,
+        out: &mut Vec<TextElement>,
+    ) {
+        if *nodes >= MAX_NODES || depth > MAX_DEPTH {
+            return;
+        }
+        *nodes += 1;
+        let role = string_attribute(element, "AXRole").unwrap_or_default();
+        if role == "AXSecureTextField" {
+            return;
+        }
+        let (role, text) = match role.as_str() {
+            "AXStaticText" => (Some(TextRole::Text), string_attribute(element, "AXValue")),
+            "AXTextField" | "AXTextArea" | "AXComboBox" => {
+                (Some(TextRole::Field), string_attribute(element, "AXValue"))
+            }
+            "AXHeading" => (
+                Some(TextRole::Heading),
+                string_attribute(element, "AXTitle"),
+            ),
+            "AXLink" => (Some(TextRole::Link), string_attribute(element, "AXTitle")),
+            "AXButton" => (Some(TextRole::Button), string_attribute(element, "AXTitle")),
+            _ => (None, None),
+        };
+        if let (Some(role), Some(text)) = (role, text) {
+            out.push(TextElement { role, text });
+        }
+        let Some(children) = attribute(element, "AXChildren") else {
+            return;
+        };
+        let count: usize = msg_send![&*children, count];
+        for i in 0..count {
+            let child: *mut AnyObject = msg_send![&*children, objectAtIndex: i];
+            if let Some(child) = child.as_ref() {
+                walk(child, depth + 1, nodes, out);
+            }
+        }
+    }
+
+    pub fn accessibility_text(app: &FrontmostApp) -> Result<Vec<TextElement>, String> {
+        let pid = app
+            .pid
+            .ok_or_else(|| "Foreground app has no pid".to_string())?;
+        unsafe {
+            if !AXIsProcessTrusted() {
+                return Err("Accessibility permission has not been granted".to_string());
+            }
+            let application =
+                Retained::from_raw(AXUIElementCreateApplication(pid as i32) as *mut AnyObject)
+                    .ok_or_else(|| "Failed to open the app's accessibility tree".to_string())?;
+            let window = attribute(&application, "AXFocusedWindow")
+                .ok_or_else(|| "The app has no focused window".to_string())?;
+            let mut elements = Vec::new();
+            walk(&window, 0, &mut 0, &mut elements);
+            Ok(elements)
+        }
+    }
+
+    pub fn ocr(png: &[u8], width: u32, height: u32) -> Result<Vec<OcrLine>, String> {
+        unsafe {
+            let data: Option<Retained<AnyObject>> = msg_send![
+                class(b"NSData\0")?,
+                dataWithBytes: png.as_ptr().cast::<c_void>(),
+                length: png.len()
+            ];
+            let data = data.ok_or_else(|| "Failed to copy screenshot for OCR".to_string())?;
+            let options: Retained<AnyObject> = msg_send![class(b"NSDictionary\0")?, new];
+            let handler: Allocated<AnyObject> =
+                msg_send![class(b"VNImageRequestHandler\0")?, alloc];
+            let handler: Option<Retained<AnyObject>> =
+                msg_send![handler, initWithData: &*data, options: &*options];
+            let handler = handler.ok_or_else(|| "Failed to load screenshot for OCR".to_string())?;
+            let request: Option<Retained<AnyObject>> =
+                msg_send![class(b"VNRecognizeTextRequest\0")?, new];
+            let request = request.ok_or_else(|| "Failed to create OCR request".to_string())?;
+            let _: () = msg_send![&*request, setRecognitionLevel: RECOGNITION_LEVEL_ACCURATE];
+            let requests: Retained<AnyObject> =
+                msg_send![class(b"NSArray\0")?, arrayWithObject: &*request];
+            let ok: bool = msg_send![
+                &*handler,
+                performRequests: &*requests,
+                error: ptr::null_mut::<*mut AnyObject>()
+            ];
+            if !ok {
+                return Err("Text recognition failed".to_string());
+            }
+            let results: Option<Retained<AnyObject>> = msg_send![&*request, results];
+            let Some(results) = results else {
+                return Ok(Vec::new());
+            };
+            let count: usize = msg_send![&*results, count];
+            let mut lines = Vec::with_capacity(count);
+            for i in 0..count {
+                let observation: *mut AnyObject = msg_send![&*results, objectAtIndex: i];
+                let Some(observation) = observation.as_ref() else {
+                    continue;
+                };
+                let candidates: Option<Retained<AnyObject>> =
+                    msg_send![observation, topCandidates: 1usize];
+                let Some(candidates) = candidates else {
+                    continue;
+                };
+                let best: Option<Retained<AnyObject>> = msg_send![&*candidates, firstObject];
+                let Some(best) = best else {
+                    continue;
+                };
+                let text: Option<Retained<NSString>> = msg_send![&*best, string];
+                let Some(text) = text else {
+                    continue;
+                };
+                let line_box: CGRect = msg_send![observation, boundingBox];
+                let mut line = Vec::new();
+                for (text, location, length) in word_spans(&text.to_string()) {
+                    let range = NSRange { location, length };
+                    let word_box: Option<Retained<AnyObject>> = msg_send![
+                        &*best,
+                        boundingBoxForRange: range,
+                        error: ptr::null_mut::<*mut AnyObject>()
+                    ];
+                    // The line's box when the word's is unknown.
+                    let rect = match word_box {
+                        Some(word_box) => {
+                            let rect: CGRect = msg_send![&*word_box, boundingBox];
+                            rect.parts()
+                        }
+                        None => line_box.parts(),
+                    };
+                    let (x, y, w, h) = vision_rect_to_pixels(rect, width, height);
+                    line.push(OcrWord {
+                        text,
+                        x,
+                        y,
+                        width: w,
+                        height: h,
+                    });
+                }
+                lines.push(line);
+            }
+            Ok(lines)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{
+        output_with_input, parse_ocr_words, parse_uia_output, OcrLine, TextElement, MAX_DEPTH,
+        MAX_NODES,
+    };
+    use crate::frontmost_app::FrontmostApp;
+    use crate::hidden_command;
+    use base64::Engine;
+
+    /// Prints `ControlType<TAB>text` for every element of the app's main
+    /// window, skipping password fields.
+    const UIA_SCRIPT: &str = r#"
+[Console]::OutputEncoding = [Text.Encoding]::UTF8
+Add-Type -AssemblyName UIAutomationClient,UIAutomationTypes
+$A = [Windows.Automation.AutomationElement]
+$walker = [Windows.Automation.TreeWalker]::ControlViewWalker
+$script:n = 0
+function Walk($e, $d) {
+  if ($script:n -ge {max_nodes} -or $d -gt {max_depth}) { return }
+  $script:n++
+  $c = $e.Current
+  if ($c.IsPassword) { return }
+  $t = $c.ControlType.ProgrammaticName -replace '^ControlType\.', ''
+  $text = $c.Name
+  $p = $null
+  if (($t -eq 'Edit' -or $t -eq 'Document') -and $e.TryGetCurrentPattern([Windows.Automation.ValuePattern]::Pattern, [ref]$p)) {
+    $text = $p.Current.Value
+  }
+  if ($text) { "$t`t$($text -replace '\s+', ' ')" }
+  $child = $walker.GetFirstChild($e)
+  while ($child) { Walk $child ($d + 1); $child = $walker.GetNextSibling($child) }
+}
+Walk ($A::FromHandle((Get-Process -Id {pid}).MainWindowHandle)) 0
+"#;
+
+    /// Prints `line<TAB>x<TAB>y<TAB>width<TAB>height<TAB>word` for every word
+    /// recognized in the base64 image read from stdin.
+    const OCR_SCRIPT: &str = r#"
+[Console]::OutputEncoding = [Text.Encoding]::UTF8
+Add-Type -AssemblyName System.Runtime.WindowsRuntime
+$asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {
+  $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and
+  $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1'
+} | Select-Object -First 1
+function Await($op, [Type]$type) {
+  $task = $asTask.MakeGenericMethod($type).Invoke($null, @($op))
+  $task.Wait(-1) | Out-Null
+  $task.Result
+}
+[Windows.Graphics.Imaging.BitmapDecoder, Windows.Graphics, ContentType = WindowsRuntime] | Out-Null
+[Windows.Media.Ocr.OcrEngine, Windows.Foundation, ContentType = WindowsRuntime] | Out-Null
+$bytes = [Convert]::FromBase64String([Console]::In.ReadToEnd())
+$stream = [System.IO.WindowsRuntimeStreamExtensions]::AsRandomAccessStream([IO.MemoryStream]::new($bytes))
+$decoder = Await ([Windows.Graphics.Imaging.BitmapDecoder]::CreateAsync($stream)) ([Windows.Graphics.Imaging.BitmapDecoder])
+$bitmap = Await ($decoder.GetSoftwareBitmapAsync()) ([Windows.Graphics.Imaging.SoftwareBitmap])
+$engine = [Windows.Media.Ocr.OcrEngine]::TryCreateFromUserProfileLanguages()
+$result = Await ($engine.RecognizeAsync($bitmap)) ([Windows.Media.Ocr.OcrResult])
+$stream.Dispose()
+$i = 0
+foreach ($line in $result.Lines) {
+  foreach ($word in $line.Words) {
+    $r = $word.BoundingRect
+    "$i`t$([int]$r.X)`t$([int]$r.Y)`t$([int]$r.Width)`t$([int]$r.Height)`t$($word.Text)"
+  }
+  $i++
+}
+"#;
+
+    fn powershell(script: &str, input: Vec<u8>) -> Result<String, String> {
+        let output = output_with_input(
+            hidden_command::new("powershell").args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                script,
+            ]),
+            input,
+        )
+        .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    pub fn accessibility_text(app: &FrontmostApp) -> Result<Vec<TextElement>, String> {
+        let pid = app
+            .pid
+            .ok_or_else(|| "Foreground app has no pid".to_string())?;
+        let script = UIA_SCRIPT
+            .replace("{max_nodes}", &MAX_NODES.to_string())
+            .replace("{max_depth}", &MAX_DEPTH.to_string())
+            .replace("{pid}", &pid.to_string());
+        Ok(parse_uia_output(&powershell(&script, Vec::new())?))
+    }
+
+    pub fn ocr(png: &[u8], _width: u32, _height: u32) -> Result<Vec<OcrLine>, String> {
+        let input = base64::engine::general_purpose::STANDARD.encode(png);
+        let output = powershell(OCR_SCRIPT, input.into_bytes())?;
+        Ok(parse_ocr_words(&output))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::{output_with_input, parse_tesseract_tsv, OcrLine, TextElement};
+    use crate::frontmost_app::FrontmostApp;
+    use std::process::Command;
+
+    pub fn accessibility_text(_app: &FrontmostApp) -> Result<Vec<TextElement>, String> {
+        Err("Accessibility text is not supported on this platform".to_string())
+    }
+
+    pub fn ocr(png: &[u8], _width: u32, _height: u32) -> Result<Vec<OcrLine>, String> {
+        let output = output_with_input(
+            Command::new("tesseract").args(["stdin", "stdout", "tsv"]),
+            png.to_vec(),
+        )
+        .map_err(|e| format!("OCR needs `tesseract` to be installed: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "tesseract failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(parse_tesseract_tsv(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+}
+
+/// Read the text on screen: the focused window's accessibility tree, or OCR
+/// of the primary display when that's unavailable or `force_ocr` is set.
+#[tauri::command]
+pub async fn get_screen_text(
+    app: AppHandle,
+    force_ocr: Option<bool>,
+) -> Result<ScreenText, String> {
+    tauri::async_runtime::spawn_blocking(move || screen_text(&app, force_ocr.unwrap_or(false)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,124 @@
+use super::*;
+
+fn element(role: TextRole, text: &str) -> TextElement {
+    TextElement {
+        role,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn elements_are_cleaned_and_deduplicated() {
+    let text = ScreenText::new(
+        TextSource::Accessibility,
+        None,
+        vec![
+            element(TextRole::Heading, "Release  notes"),
+            element(TextRole::Text, "Release notes"),
+            element(TextRole::Text, "   "),
+            element(TextRole::Link, "Download\nnow"),
+            element(TextRole::Text, "Release notes"),
+        ],
+    );
+    assert_eq!(
+        text.elements,
+        vec![
+            element(TextRole::Heading, "Release notes"),
+            element(TextRole::Link, "Download now"),
+            element(TextRole::Text, "Release notes"),
+        ]
+    );
+    assert_eq!(text.text, "Release notes\nDownload now\nRelease notes");
+    assert!(!text.truncated);
+}
+
+#[test]
+fn text_is_capped() {
+    let line = "x".repeat(1000);
+    let raw = (0..100)
+        .map(|i| element(TextRole::Text, &format!("{}{}", i, line)))
+        .collect();
+    let text = ScreenText::new(TextSource::Ocr, None, raw);
+    assert!(text.truncated);
+    assert!(text.text.len() <= MAX_TEXT_BYTES);
+    assert_eq!(text.elements.len(), text.text.lines().count());
+}
+
+#[test]
+fn uia_output_maps_control_types() {
+    let output = "Window\tInbox - Mail\nText\tHello\nHyperlink\tUnsubscribe\nEdit\tsearch terms\nno tab here\n";
+    assert_eq!(
+        parse_uia_output(output),
+        vec![
+            element(TextRole::Text, "Hello"),
+            element(TextRole::Link, "Unsubscribe"),
+            element(TextRole::Field, "search terms"),
+        ]
+    );
+}
+
+fn word(text: &str, x: u32, y: u32, width: u32, height: u32) -> OcrWord {
+    OcrWord {
+        text: text.to_string(),
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn ocr_words_are_grouped_into_lines() {
+    let output = "0\t10\t20\t50\t12\tHello\r\n0\t65\t20\t40\t12\tworld\r\n1\t10\t40\t30\t12\tBye\r\nnot a row\r\n1\t1\t2\t3\t4\t \r\n";
+    assert_eq!(
+        parse_ocr_words(output),
+        vec![
+            vec![word("Hello", 10, 20, 50, 12), word("world", 65, 20, 40, 12)],
+            vec![word("Bye", 10, 40, 30, 12)],
+        ]
+    );
+}
+
+#[test]
+fn tesseract_tsv_keeps_word_rows() {
+    let output = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+        1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t\n\
+        4\t1\t1\t1\t1\t0\t10\t20\t95\t12\t-1\t\n\
+        5\t1\t1\t1\t1\t1\t10\t20\t50\t12\t96.5\tHello\n\
+        5\t1\t1\t1\t1\t2\t65\t20\t40\t12\t91.0\tworld\n\
+        5\t1\t1\t1\t2\t1\t10\t40\t30\t12\t88.2\tBye\n\
+        5\t1\t2\t1\t1\t1\t10\t80\t30\t12\t95.0\t \n";
+    assert_eq!(
+        parse_tesseract_tsv(output),
+        vec![
+            vec![word("Hello", 10, 20, 50, 12), word("world", 65, 20, 40, 12)],
+            vec![word("Bye", 10, 40, 30, 12)],
+        ]
+    );
+}
+
+#[test]
+fn word_spans_count_utf16_units() {
+    assert_eq!(
+        word_spans("  café 😀x  end"),
+        vec![
+            ("café".to_string(), 2, 4),
+            ("😀x".to_string(), 7, 3),
+            ("end".to_string(), 12, 3),
+        ]
+    );
+    assert!(word_spans(" \t ").is_empty());
+}
+
+#[test]
+fn vision_boxes_flip_to_top_left_pixels() {
+    assert_eq!(
+        vision_rect_to_pixels((0.25, 0.5, 0.5, 0.25), 800, 400),
+        (200, 100, 400, 100)
+    );
+    // Boxes running off the image are clipped.
+    assert_eq!(
+        vision_rect_to_pixels((-0.1, -0.1, 0.3, 0.2), 100, 100),
+        (0, 90, 20, 10)
+    );
+}
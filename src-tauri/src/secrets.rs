@@ -0,0 +1,226 @@
+//! Credentials for integrations (sync backends, upload targets, webhooks),
+//! kept in the OS credential store: Keychain on macOS, Credential Manager
+//! on Windows, the Secret Service on Linux.
+//!
+//! The webview can store and delete secrets but never read them back; only
+//! backend code that talks to the service does. AI, STT and TTS provider
+//! API keys are filed per provider id, under `provider.<id>.api_key`, with
+//! the origin the key was stored for under `provider.<id>.origin`.
+//! Requests the webview builds carry `API_KEY_PLACEHOLDER` where the key
+//! goes, and `provider_fetch` fills it in for that origin only.
+
+use crate::network;
+use keyring::Entry;
+use reqwest::Url;
+use tauri::AppHandle;
+
+/// Credential store service name every secret is filed under.
+const SERVICE: &str = "runningbord";
+/// Stands in for a provider's API key in requests built by the webview.
+pub(crate) const API_KEY_PLACEHOLDER: &str = "{{API_KEY}}";
+/// The placeholder as URL parsers encode it.
+const API_KEY_PLACEHOLDER_ENCODED: &str = "%7B%7BAPI_KEY%7D%7D";
+/// Keys filed under another name before, the provider they belong to, and
+/// that provider's origin.
+const LEGACY_API_KEYS: [(&str, &str, &str); 2] = [
+    ("tts.openai.api_key", "openai", "https://api.openai.com"),
+    (
+        "tts.elevenlabs.api_key",
+        "elevenlabs",
+        "https://api.elevenlabs.io",
+    ),
+];
+
+fn entry(key: &str) -> Result<Entry, String> {
+    if key.trim().is_empty() {
+        return Err("Secret key must not be empty".to_string());
+    }
+    Entry::new(SERVICE, key).map_err(|e| format!("Credential store unavailable: {}", e))
+}
+
+pub fn set(key: &str, value: &str) -> Result<(), String> {
+    entry(key)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store secret {}: {}", key, e))
+}
+
+/// `None` if no secret is stored under `key`.
+pub fn get(key: &str) -> Result<Option<String>, String> {
+    match entry(key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read secret {}: {}", key, e)),
+    }
+}
+
+/// The secret under `key`, or an error naming what to configure.
+pub fn require(key: &str) -> Result<String, String> {
+    get(key)?.ok_or_else(|| format!("No secret stored for {}", key))
+}
+
+/// Secret key for `provider`'s `field`. Ids are lowercase letters, digits,
+/// `-` and `_`, so one provider can't address another's entry.
+fn provider_secret(provider: &str, field: &str) -> Result<String, String> {
+    let valid = !provider.is_empty()
+        && provider
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid provider id: {:?}", provider));
+    }
+    Ok(format!("provider.{}.{}", provider, field))
+}
+
+/// Secret key for `provider`'s API key.
+pub(crate) fn api_key_secret(provider: &str) -> Result<String, String> {
+    provider_secret(provider, "api_key")
+}
+
+/// Secret key for the origin `provider`'s API key may be sent to.
+fn api_key_origin_secret(provider: &str) -> Result<String, String> {
+    provider_secret(provider, "origin")
+}
+
+/// `provider`'s API key, if one is stored.
+pub(crate) fn api_key(provider: &str) -> Result<Option<String>, String> {
+    get(&api_key_secret(provider)?)
+}
+
+/// The origin `provider`'s API key was stored for, if one was recorded.
+pub(crate) fn api_key_origin(provider: &str) -> Result<Option<String>, String> {
+    get(&api_key_origin_secret(provider)?)
+}
+
+/// `url`'s origin (scheme, host and port), e.g. `https://api.openai.com`.
+pub(crate) fn url_origin(url: &str) -> Result<String, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host().is_none() {
+        return Err(format!("URL must be http or https: {}", url));
+    }
+    Ok(parsed.origin().ascii_serialization())
+}
+
+/// Whether `text` carries `API_KEY_PLACEHOLDER`, plain or URL-encoded.
+pub(crate) fn mentions_api_key(text: &str) -> bool {
+    text.contains(API_KEY_PLACEHOLDER)
+        || text
+            .to_ascii_uppercase()
+            .contains(API_KEY_PLACEHOLDER_ENCODED)
+}
+
+/// `text` with `API_KEY_PLACEHOLDER`, plain or URL-encoded, replaced by
+/// `api_key`.
+pub(crate) fn fill_api_key(text: &str, api_key: &str) -> String {
+    let text = text.replace(API_KEY_PLACEHOLDER, api_key);
+    // Case-insensitive, like percent-encoding itself.
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(at) = rest.to_ascii_uppercase().find(API_KEY_PLACEHOLDER_ENCODED) {
+        filled.push_str(&rest[..at]);
+        filled.push_str(api_key);
+        rest = &rest[at + API_KEY_PLACEHOLDER_ENCODED.len()..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// File keys stored under their legacy names per provider. A key already
+/// stored for the provider wins. Called once at startup.
+pub fn migrate_legacy_api_keys() {
+    for (legacy, provider, origin) in LEGACY_API_KEYS {
+        let result = get(legacy).and_then(|value| {
+            let Some(value) = value else {
+                return Ok(());
+            };
+            let secret = api_key_secret(provider)?;
+            if get(&secret)?.is_none() {
+                set(&secret, &value)?;
+                set(&api_key_origin_secret(provider)?, origin)?;
+            }
+            delete(legacy)
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to migrate {}: {}", legacy, e);
+        }
+    }
+}
+
+pub fn delete(key: &str) -> Result<(), String> {
+    match entry(key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete secret {}: {}", key, e)),
+    }
+}
+
+#[tauri::command]
+pub async fn secret_set(key: String, value: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || set(&key, &value))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn secret_delete(key: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || delete(&key))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Whether a secret is stored under `key`. The value itself never leaves
+/// the backend.
+#[tauri::command]
+pub async fn secret_exists(key: String) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || get(&key).map(|v| v.is_some()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Store `provider`'s API key in the credential store, replacing any
+/// previous one. `url` is the provider's endpoint; the key is only ever sent
+/// to its origin, after any base URL override, as configured right now.
+#[tauri::command]
+pub async fn set_api_key(
+    app: AppHandle,
+    provider: String,
+    api_key: String,
+    url: String,
+) -> Result<(), String> {
+    let secret = api_key_secret(&provider)?;
+    let origin_secret = api_key_origin_secret(&provider)?;
+    let origin = url_origin(&network::provider_url(&app, Some(&provider), &url)?)?;
+    let api_key = api_key.trim().to_string();
+    if api_key.is_empty() {
+        return Err("API key must not be empty".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        set(&origin_secret, &origin)?;
+        set(&secret, &api_key)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Whether an API key is stored for `provider`. The key itself never
+/// leaves the backend.
+#[tauri::command]
+pub async fn has_api_key(provider: String) -> Result<bool, String> {
+    let secret = api_key_secret(&provider)?;
+    tauri::async_runtime::spawn_blocking(move || get(&secret).map(|v| v.is_some()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn delete_api_key(provider: String) -> Result<(), String> {
+    let secret = api_key_secret(&provider)?;
+    let origin_secret = api_key_origin_secret(&provider)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        delete(&secret)?;
+        delete(&origin_secret)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,65 @@
+use super::*;
+
+#[test]
+fn api_keys_are_filed_per_provider() {
+    assert_eq!(
+        api_key_secret("openai").as_deref(),
+        Ok("provider.openai.api_key")
+    );
+    assert_eq!(
+        api_key_secret("custom_2-eu").as_deref(),
+        Ok("provider.custom_2-eu.api_key")
+    );
+}
+
+#[test]
+fn provider_ids_cannot_reach_other_entries() {
+    for id in ["", "OpenAI", "openai.api_key", "../s3", "tts openai"] {
+        assert!(api_key_secret(id).is_err(), "{}", id);
+    }
+}
+
+#[test]
+fn the_placeholder_is_filled_plain_or_encoded() {
+    assert_eq!(fill_api_key("Bearer {{API_KEY}}", "sk-1"), "Bearer sk-1");
+    assert_eq!(
+        fill_api_key(
+            "https://x.test/v1?key=%7B%7BAPI_KEY%7D%7D&k=%7b%7bapi_key%7d%7d",
+            "sk-1"
+        ),
+        "https://x.test/v1?key=sk-1&k=sk-1"
+    );
+    assert_eq!(fill_api_key("no key here", "sk-1"), "no key here");
+    // Filled once: a key that looks like the placeholder isn't expanded.
+    assert_eq!(fill_api_key("{{API_KEY}}", "{{API_KEY}}"), "{{API_KEY}}");
+}
+
+#[test]
+fn keys_are_tied_to_an_origin() {
+    assert_eq!(
+        url_origin("https://api.openai.com/v1/chat/completions?x=1").as_deref(),
+        Ok("https://api.openai.com")
+    );
+    assert_eq!(
+        url_origin("http://localhost:11434/v1").as_deref(),
+        Ok("http://localhost:11434")
+    );
+    // A lookalike host or another port is another origin.
+    assert_ne!(
+        url_origin("https://api.openai.com.evil.test/v1"),
+        url_origin("https://api.openai.com/v1")
+    );
+    assert_ne!(
+        url_origin("https://api.openai.com:8443/v1"),
+        url_origin("https://api.openai.com/v1")
+    );
+    assert!(url_origin("file:///etc/passwd").is_err());
+    assert!(url_origin("not a url").is_err());
+}
+
+#[test]
+fn the_placeholder_is_found_plain_or_encoded() {
+    assert!(mentions_api_key("Bearer {{API_KEY}}"));
+    assert!(mentions_api_key("https://x.test/?k=%7b%7bapi_key%7d%7d"));
+    assert!(!mentions_api_key("https://x.test/?k=1"));
+}
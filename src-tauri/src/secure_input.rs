@@ -0,0 +1,78 @@
+//! Whether the user is typing into a password field.
+//!
+//! On macOS: `IsSecureEventInputEnabled`, which is on while any app has a
+//! secure text field (or a terminal's secure keyboard entry) focused.
+//! On Windows: the focused control of the foreground window is a Win32 edit
+//! control with `ES_PASSWORD`; password fields drawn by browsers and other
+//! custom toolkits are not detected.
+//! On Linux: not detected.
+
+pub fn secure_input_active() -> bool {
+    platform::secure_input_active()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn IsSecureEventInputEnabled() -> u8;
+    }
+
+    pub fn secure_input_active() -> bool {
+        unsafe { IsSecureEventInputEnabled() != 0 }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    struct Rect {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    }
+
+    #[repr(C)]
+    struct GuiThreadInfo {
+        size: u32,
+        flags: u32,
+        active: *mut c_void,
+        focus: *mut c_void,
+        capture: *mut c_void,
+        menu_owner: *mut c_void,
+        move_size: *mut c_void,
+        caret: *mut c_void,
+        caret_rect: Rect,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetGUIThreadInfo(thread_id: u32, info: *mut GuiThreadInfo) -> i32;
+        fn GetWindowLongW(hwnd: *mut c_void, index: i32) -> i32;
+    }
+
+    const GWL_STYLE: i32 = -16;
+    const ES_PASSWORD: i32 = 0x0020;
+
+    pub fn secure_input_active() -> bool {
+        unsafe {
+            let mut info: GuiThreadInfo = std::mem::zeroed();
+            info.size = std::mem::size_of::<GuiThreadInfo>() as u32;
+            // Thread 0: the foreground thread.
+            if GetGUIThreadInfo(0, &mut info) == 0 || info.focus.is_null() {
+                return false;
+            }
+            GetWindowLongW(info.focus, GWL_STYLE) & ES_PASSWORD != 0
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    pub fn secure_input_active() -> bool {
+        false
+    }
+}
@@ -0,0 +1,331 @@
+//! Session lock and fast user switching: while the screen is locked or the
+//! user is switched away, the capture backend is stopped (CoreAudio taps
+//! behave unpredictably across these transitions) and started again once
+//! the session is back, keeping the buffer.
+//!
+//! On macOS: `CGSessionCopyCurrentDictionary` (screen lock and whether the
+//! session owns the console).
+//! On Windows: the input desktop (locked while it isn't `Default`) and the
+//! session's WTS connect state.
+//! On Linux: logind's `LockedHint` and `Active` for the current session.
+//!
+//! Emits `session-activity-changed` while enabled (the default).
+
+use crate::system_audio::{resume_capture, suspend_capture, SystemAudioState};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the watcher polls the session state.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SessionActivity {
+    /// The screen is locked.
+    pub locked: bool,
+    /// Another user has the console (fast user switching).
+    pub switched_away: bool,
+}
+
+impl SessionActivity {
+    pub fn is_active(&self) -> bool {
+        !self.locked && !self.switched_away
+    }
+}
+
+pub struct SessionLockState {
+    enabled: AtomicBool,
+    /// Last state seen by the watcher; `None` if unknown.
+    activity: Mutex<Option<SessionActivity>>,
+    /// Bumped on every enable / disable so a superseded watcher exits.
+    generation: AtomicU64,
+}
+
+impl Default for SessionLockState {
+    fn default() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            activity: Mutex::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct SessionLockStatus {
+    pub enabled: bool,
+    /// `None` if the platform doesn't expose it.
+    pub activity: Option<SessionActivity>,
+    /// Capture is stopped until the session is active again.
+    pub capture_suspended: bool,
+}
+
+/// Parse `loginctl show-session -p LockedHint -p Active` output.
+#[cfg(any(target_os = "linux", test))]
+fn parse_loginctl(output: &str) -> Option<SessionActivity> {
+    let value = |key: &str| {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix('='))
+            .map(|v| v == "yes")
+    };
+    Some(SessionActivity {
+        locked: value("LockedHint").unwrap_or(false),
+        switched_away: !value("Active")?,
+    })
+}
+
+/// Whether the session is locked or switched away, or `None` if unknown.
+pub fn session_activity() -> Option<SessionActivity> {
+    platform::session_activity()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::SessionActivity;
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::NSString;
+    use std::ffi::c_void;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> *const c_void;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+
+    pub fn session_activity() -> Option<SessionActivity> {
+        unsafe {
+            let info = CGSessionCopyCurrentDictionary();
+            if info.is_null() {
+                return None;
+            }
+            // CFDictionary of CFBoolean, toll-free bridged to NSDictionary
+            // of NSNumber.
+            let dict = &*(info as *const AnyObject);
+            let flag = |key: &str| -> Option<bool> {
+                let key = NSString::from_str(key);
+                let value: Option<Retained<AnyObject>> = msg_send![dict, objectForKey: &*key];
+                Some(msg_send![&*value?, boolValue])
+            };
+            let activity = SessionActivity {
+                // Only present while locked.
+                locked: flag("CGSSessionScreenIsLocked").unwrap_or(false),
+                switched_away: !flag("kCGSSessionOnConsoleKey").unwrap_or(true),
+            };
+            CFRelease(info);
+            Some(activity)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::SessionActivity;
+    use std::ffi::c_void;
+    use std::ptr;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn OpenInputDesktop(flags: u32, inherit: i32, access: u32) -> *mut c_void;
+        fn CloseDesktop(desktop: *mut c_void) -> i32;
+        fn GetUserObjectInformationW(
+            object: *mut c_void,
+            index: i32,
+            info: *mut c_void,
+            length: u32,
+            needed: *mut u32,
+        ) -> i32;
+    }
+
+    #[link(name = "wtsapi32")]
+    extern "system" {
+        fn WTSQuerySessionInformationW(
+            server: *mut c_void,
+            session: u32,
+            class: i32,
+            buffer: *mut *mut c_void,
+            bytes: *mut u32,
+        ) -> i32;
+        fn WTSFreeMemory(memory: *mut c_void);
+    }
+
+    const DESKTOP_READOBJECTS: u32 = 0x0001;
+    const UOI_NAME: i32 = 2;
+    const WTS_CURRENT_SESSION: u32 = u32::MAX;
+    const WTS_CONNECT_STATE: i32 = 8;
+    const WTS_ACTIVE: i32 = 0;
+
+    /// The secure desktop (lock screen, UAC) can't be opened, and its name
+    /// isn't `Default`.
+    fn input_desktop_is_default() -> bool {
+        unsafe {
+            let desktop = OpenInputDesktop(0, 0, DESKTOP_READOBJECTS);
+            if desktop.is_null() {
+                return false;
+            }
+            let mut name = [0u16; 64];
+            let mut needed = 0u32;
+            let ok = GetUserObjectInformationW(
+                desktop,
+                UOI_NAME,
+                name.as_mut_ptr() as *mut c_void,
+                (name.len() * 2) as u32,
+                &mut needed,
+            );
+            CloseDesktop(desktop);
+            let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+            ok != 0 && String::from_utf16_lossy(&name[..len]).eq_ignore_ascii_case("Default")
+        }
+    }
+
+    fn session_connected() -> Option<bool> {
+        unsafe {
+            let mut buffer: *mut c_void = ptr::null_mut();
+            let mut bytes = 0u32;
+            if WTSQuerySessionInformationW(
+                ptr::null_mut(),
+                WTS_CURRENT_SESSION,
+                WTS_CONNECT_STATE,
+                &mut buffer,
+                &mut bytes,
+            ) == 0
+                || buffer.is_null()
+            {
+                return None;
+            }
+            let state = *(buffer as *const i32);
+            WTSFreeMemory(buffer);
+            Some(state == WTS_ACTIVE)
+        }
+    }
+
+    pub fn session_activity() -> Option<SessionActivity> {
+        let connected = session_connected()?;
+        Some(SessionActivity {
+            // A disconnected session has no input desktop of its own.
+            locked: connected && !input_desktop_is_default(),
+            switched_away: !connected,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{parse_loginctl, SessionActivity};
+    use std::process::Command;
+
+    pub fn session_activity() -> Option<SessionActivity> {
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+        let output = Command::new("loginctl")
+            .args(["show-session", &session, "-p", "LockedHint", "-p", "Active"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_loginctl(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    use super::SessionActivity;
+
+    pub fn session_activity() -> Option<SessionActivity> {
+        None
+    }
+}
+
+/// Record a fresh reading and suspend or resume capture on a change.
+fn apply(app: &AppHandle, activity: SessionActivity) {
+    let state = app.state::<SessionLockState>();
+    let previous = state
+        .activity
+        .lock()
+        .map(|mut a| std::mem::replace(&mut *a, Some(activity)))
+        .unwrap_or(None);
+    let was_active = previous.map_or(true, |p| p.is_active());
+    if previous != Some(activity) {
+        let _ = app.emit("session-activity-changed", activity);
+    }
+    let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+    if was_active && !activity.is_active() {
+        if tauri::async_runtime::block_on(suspend_capture(&audio)) {
+            tracing::info!("System audio capture suspended while the session is inactive");
+        }
+    } else if activity.is_active() {
+        match tauri::async_runtime::block_on(resume_capture(&audio)) {
+            Ok(true) => tracing::info!("System audio capture resumed"),
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to resume system audio capture: {}", e),
+        }
+    }
+}
+
+/// Poll the session state until the watcher is disabled.
+fn spawn_watcher(app: AppHandle, generation: u64) {
+    thread::spawn(move || {
+        let state = app.state::<SessionLockState>();
+        while state.generation.load(Ordering::SeqCst) == generation {
+            if let Some(activity) = session_activity() {
+                if state.generation.load(Ordering::SeqCst) != generation {
+                    break;
+                }
+                apply(&app, activity);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Start the watcher if enabled. Called once at startup.
+pub fn init(app: &AppHandle) {
+    let state = app.state::<SessionLockState>();
+    if state.enabled.load(Ordering::SeqCst) {
+        let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        spawn_watcher(app.clone(), generation);
+    }
+}
+
+/// Turn suspending capture on lock / user switch on or off. Turning it off
+/// resumes a suspended capture.
+#[tauri::command]
+pub async fn session_lock_set_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let state = app.state::<SessionLockState>();
+    state.enabled.store(enabled, Ordering::SeqCst);
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    if enabled {
+        spawn_watcher(app.clone(), generation);
+    } else {
+        if let Ok(mut activity) = state.activity.lock() {
+            *activity = None;
+        }
+        resume_capture(app.state::<Arc<SystemAudioState>>().inner()).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn session_lock_status(app: AppHandle) -> Result<SessionLockStatus, String> {
+    let activity = tauri::async_runtime::spawn_blocking(session_activity)
+        .await
+        .map_err(|e| e.to_string())?;
+    let state = app.state::<SessionLockState>();
+    Ok(SessionLockStatus {
+        enabled: state.enabled.load(Ordering::SeqCst),
+        activity,
+        capture_suspended: app.state::<Arc<SystemAudioState>>().is_suspended(),
+    })
+}
+
+#[cfg(test)]
+mod tests;
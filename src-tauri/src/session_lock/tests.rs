@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn parses_loginctl_output() {
+    assert_eq!(
+        parse_loginctl("LockedHint=no\nActive=yes\n"),
+        Some(SessionActivity {
+            locked: false,
+            switched_away: false,
+        })
+    );
+    assert_eq!(
+        parse_loginctl("LockedHint=yes\nActive=yes\n"),
+        Some(SessionActivity {
+            locked: true,
+            switched_away: false,
+        })
+    );
+    assert_eq!(
+        parse_loginctl("Active=no\n"),
+        Some(SessionActivity {
+            locked: false,
+            switched_away: true,
+        })
+    );
+}
+
+#[test]
+fn loginctl_output_without_active_is_unknown() {
+    assert_eq!(parse_loginctl(""), None);
+    assert_eq!(parse_loginctl("LockedHint=yes\n"), None);
+}
+
+#[test]
+fn only_an_unlocked_console_session_is_active() {
+    let active = SessionActivity {
+        locked: false,
+        switched_away: false,
+    };
+    assert!(active.is_active());
+    assert!(!SessionActivity {
+        locked: true,
+        ..active
+    }
+    .is_active());
+    assert!(!SessionActivity {
+        switched_away: true,
+        ..active
+    }
+    .is_active());
+}
@@ -0,0 +1,218 @@
+//! Sharing the recent audio and its transcript to Slack or Discord through
+//! an incoming webhook.
+//!
+//! Webhook URLs are credentials (anyone holding one can post), so they're
+//! kept in the credential store (see `secrets`) and only used here; the
+//! webview sets them through `share_set_webhook` and never reads them back.
+//!
+//! Discord webhooks take attachments, so the audio and full transcript are
+//! attached. Slack incoming webhooks are text only: the audio is linked
+//! through `s3_upload` when a bucket is configured and left out otherwise.
+
+use crate::network;
+use crate::s3_upload;
+use crate::secrets;
+use crate::system_audio::SystemAudioState;
+use base64::Engine;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long the audio link in a Slack message stays valid.
+const AUDIO_LINK_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Discord rejects message content over 2000 characters.
+const DISCORD_CONTENT_LIMIT: usize = 2000;
+/// Slack truncates message text past 40000 characters; stay well below.
+const SLACK_TEXT_LIMIT: usize = 3000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShareTarget {
+    Slack,
+    Discord,
+}
+
+impl ShareTarget {
+    fn secret_key(self) -> &'static str {
+        match self {
+            Self::Slack => "share.slack.webhook_url",
+            Self::Discord => "share.discord.webhook_url",
+        }
+    }
+
+    /// Only the target's own webhook hosts, so a pasted URL can't send the
+    /// transcript somewhere else.
+    fn validate(self, url: &str) -> Result<(), String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+        let host = parsed.host_str().unwrap_or_default();
+        let valid = parsed.scheme() == "https"
+            && match self {
+                Self::Slack => host == "hooks.slack.com",
+                Self::Discord => {
+                    matches!(host, "discord.com" | "discordapp.com" | "ptb.discord.com")
+                        && parsed.path().starts_with("/api/webhooks/")
+                }
+            };
+        if !valid {
+            return Err(format!("Not a {:?} webhook URL", self));
+        }
+        Ok(())
+    }
+}
+
+/// `text` cut to at most `limit` characters, ending in `…` if cut.
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(limit.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+fn message_text(transcript: Option<&str>, limit: usize) -> String {
+    match transcript.filter(|t| !t.trim().is_empty()) {
+        Some(transcript) => truncate(&format!("Recent audio:\n> {}", transcript.trim()), limit),
+        None => "Recent audio (no transcript)".to_string(),
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<(), String> {
+    let response = request
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Share request failed: {}", e))?;
+    if !response.status().is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Share failed: {}", body.trim()));
+    }
+    Ok(())
+}
+
+async fn post_discord(
+    app: &AppHandle,
+    url: &str,
+    name: &str,
+    ogg: Vec<u8>,
+    transcript: Option<&str>,
+) -> Result<(), String> {
+    let payload = serde_json::json!({
+        "content": message_text(transcript, DISCORD_CONTENT_LIMIT),
+    });
+    let mut form = Form::new().text("payload_json", payload.to_string()).part(
+        "files[0]",
+        Part::bytes(ogg)
+            .file_name(format!("{}.ogg", name))
+            .mime_str("audio/ogg")
+            .map_err(|e| e.to_string())?,
+    );
+    if let Some(transcript) = transcript.filter(|t| !t.trim().is_empty()) {
+        form = form.part(
+            "files[1]",
+            Part::text(transcript.to_string())
+                .file_name(format!("{}.txt", name))
+                .mime_str("text/plain")
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    send(network::client(app, None)?.post(url).multipart(form)).await
+}
+
+async fn post_slack(
+    app: &AppHandle,
+    url: &str,
+    name: &str,
+    ogg: Vec<u8>,
+    transcript: Option<&str>,
+) -> Result<(), String> {
+    let mut text = message_text(transcript, SLACK_TEXT_LIMIT);
+    let file_name = format!("{}.ogg", name);
+    match s3_upload::upload_shared(app, &file_name, ogg, "audio/ogg", AUDIO_LINK_TTL).await {
+        Ok(link) => text.push_str(&format!("\n<{}|{}>", link, file_name)),
+        Err(e) => tracing::info!("Sharing to Slack without audio: {}", e),
+    }
+    send(
+        network::client(app, None)?
+            .post(url)
+            .json(&serde_json::json!({ "text": text })),
+    )
+    .await
+}
+
+/// Store the webhook URL for `target`.
+#[tauri::command]
+pub async fn share_set_webhook(target: ShareTarget, url: String) -> Result<(), String> {
+    target.validate(&url)?;
+    tauri::async_runtime::spawn_blocking(move || secrets::set(target.secret_key(), &url))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn share_clear_webhook(target: ShareTarget) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || secrets::delete(target.secret_key()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn share_webhook_configured(target: ShareTarget) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        secrets::get(target.secret_key()).map(|url| url.is_some())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Export the recent audio (the last `seconds`, or the whole buffer),
+/// transcribe it and post both to `target`.
+#[tauri::command]
+pub async fn share_recent(
+    app: AppHandle,
+    target: ShareTarget,
+    seconds: Option<f64>,
+) -> Result<(), String> {
+    if let Some(seconds) = seconds {
+        if !seconds.is_finite() || seconds <= 0.0 {
+            return Err(format!("seconds must be positive, got {}", seconds));
+        }
+    }
+    let url = tauri::async_runtime::spawn_blocking(move || secrets::get(target.secret_key()))
+        .await
+        .map_err(|e| e.to_string())??
+        .ok_or_else(|| format!("No {:?} webhook configured", target))?;
+
+    let state = app.state::<Arc<SystemAudioState>>().inner().clone();
+    let audio = tauri::async_runtime::spawn_blocking(move || match seconds {
+        Some(seconds) => state.get_last_timed(seconds),
+        None => state.get_recent_timed(),
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let audio_base64 = base64::engine::general_purpose::STANDARD.encode(&audio.ogg);
+    // Share the audio even if transcription isn't set up or fails.
+    let transcript = match crate::api::transcribe(app.clone(), audio_base64).await {
+        Ok(response) => response.into_transcription(),
+        Err(e) => {
+            tracing::warn!("Sharing without transcript: {}", e);
+            None
+        }
+    };
+
+    let name = format!(
+        "recording-{}",
+        chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+    );
+    let transcript = transcript.as_deref();
+    match target {
+        ShareTarget::Discord => post_discord(&app, &url, &name, audio.ogg, transcript).await,
+        ShareTarget::Slack => post_slack(&app, &url, &name, audio.ogg, transcript).await,
+    }
+}
+
+#[cfg(test)]
+mod tests;
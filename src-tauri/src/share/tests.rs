@@ -0,0 +1,43 @@
+use super::*;
+
+#[test]
+fn truncate_keeps_short_text_and_marks_cuts() {
+    assert_eq!(truncate("hello", 5), "hello");
+    assert_eq!(truncate("hello world", 6), "hello…");
+    assert_eq!(truncate("ééééé", 3).chars().count(), 3);
+}
+
+#[test]
+fn message_text_quotes_the_transcript() {
+    assert_eq!(
+        message_text(Some(" ship it \n"), 100),
+        "Recent audio:\n> ship it"
+    );
+    assert_eq!(
+        message_text(Some("  "), 100),
+        "Recent audio (no transcript)"
+    );
+    assert_eq!(message_text(None, 100), "Recent audio (no transcript)");
+    assert_eq!(
+        message_text(Some(&"a".repeat(5000)), DISCORD_CONTENT_LIMIT)
+            .chars()
+            .count(),
+        DISCORD_CONTENT_LIMIT
+    );
+}
+
+#[test]
+fn webhook_urls_must_belong_to_the_target() {
+    let slack = "https://hooks.slack.com/services/T000/B000/XXXX";
+    let discord = "https://discord.com/api/webhooks/123/abc";
+    assert!(ShareTarget::Slack.validate(slack).is_ok());
+    assert!(ShareTarget::Discord.validate(discord).is_ok());
+    assert!(ShareTarget::Slack.validate(discord).is_err());
+    assert!(ShareTarget::Discord.validate(slack).is_err());
+    assert!(ShareTarget::Discord
+        .validate("https://discord.com/channels/1/2")
+        .is_err());
+    assert!(ShareTarget::Slack
+        .validate("http://hooks.slack.com/services/T000/B000/XXXX")
+        .is_err());
+}
@@ -0,0 +1,75 @@
+//! Orderly teardown on app exit: the buffer is auto-saved if enabled (see
+//! `autosave`) and a running screen recording finished, then capture is
+//! stopped, its threads joined and the native capture objects (on macOS
+//! the process tap and its aggregate device) destroyed, so quitting never
+//! leaves an orphaned device in Audio MIDI Setup. SIGTERM and SIGINT go
+//! through the same path as Quit.
+
+use crate::audio_session;
+use crate::autosave;
+use crate::screen_record;
+use crate::system_audio::{stop_system_audio, SystemAudioState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Longest the exit path waits for capture to stop before giving up.
+const STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Exit the app cleanly on SIGTERM / SIGINT (e.g. logout or `kill`).
+/// Called once at startup.
+pub fn install(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = wait_for_signal().await {
+            tracing::warn!("Failed to listen for termination signals: {}", e);
+            return;
+        }
+        tracing::info!("Termination signal received, exiting");
+        app.exit(0);
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = terminate.recv() => Ok(()),
+        result = tokio::signal::ctrl_c() => result,
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+/// Stop capture and release everything it holds. Runs once, from
+/// `RunEvent::Exit`; later calls do nothing.
+pub fn run(app: &AppHandle) {
+    if SHUT_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let state = app.state::<Arc<SystemAudioState>>().inner().clone();
+    // Before stopping: stopping zeroizes the buffer.
+    autosave::save_on_exit(app, &state);
+    // Also before: a screen recording may still be draining the buffer.
+    screen_record::finish_on_exit(app);
+    let stopped = tauri::async_runtime::block_on(async {
+        tokio::time::timeout(STOP_TIMEOUT, stop_system_audio(&state)).await
+    });
+    if stopped.is_err() {
+        tracing::error!(
+            "System audio capture did not stop within {:?}",
+            STOP_TIMEOUT
+        );
+    }
+    // Don't leave captured audio resident in memory after exit, even if
+    // the stop above timed out.
+    state.zeroize_buffer();
+    audio_session::restore_on_exit(app);
+}
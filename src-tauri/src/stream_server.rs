@@ -0,0 +1,401 @@
+//! Optional localhost WebSocket server streaming live system audio.
+//!
+//! Clients connecting to `ws://127.0.0.1:<port>` must present the token
+//! returned by `stream_server_start`, as `Authorization: Bearer <token>` or,
+//! for browsers, which can't set headers on a WebSocket, a `?token=` query
+//! parameter. Handshakes carrying an `Origin` other than the app's own or a
+//! loopback page are refused, so a website open in the user's browser
+//! can't subscribe to the transcripts published here.
+//!
+//! Once connected, clients receive a JSON `hello` message describing the
+//! stream, then:
+//! - binary messages: raw Opus packets, one per 20 ms frame (mono, at the
+//!   rate announced in `hello`)
+//! - text messages: JSON events (`level`, `transcript`)
+
+use crate::http_api::constant_time_eq;
+use crate::system_audio::{measure_level, SystemAudioState, OUTPUT_CHANNELS};
+use crate::system_audio_dsp::DspPipeline;
+use crate::system_audio_encoder::{empty_packet, new_opus_encoder, SilenceGate};
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header, StatusCode};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Default port for the live stream server.
+const DEFAULT_PORT: u16 = 4849;
+/// How often the producer pulls new audio from the ring buffer.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Messages buffered per client before it starts lagging.
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone)]
+enum StreamMessage {
+    Audio(Vec<u8>),
+    Event(String),
+}
+
+struct RunningServer {
+    port: u16,
+    token: String,
+    shutdown: watch::Sender<bool>,
+    events: broadcast::Sender<StreamMessage>,
+}
+
+#[derive(Default)]
+pub struct StreamServerState {
+    server: Mutex<Option<RunningServer>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct StreamServerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub token: Option<String>,
+    pub clients: usize,
+}
+
+/// Send a JSON event to all connected clients, if the server is running.
+pub fn publish_event(app: &AppHandle, event: serde_json::Value) {
+    let state = app.state::<StreamServerState>();
+    let guard = match state.server.lock() {
+        Ok(g) => g,
+        Err(_) => return,
+    };
+    if let Some(server) = guard.as_ref() {
+        let _ = server.events.send(StreamMessage::Event(event.to_string()));
+    }
+}
+
+/// Publish a finished transcript to stream subscribers.
+pub fn publish_transcript(app: &AppHandle, text: &str) {
+    publish_event(app, json!({ "type": "transcript", "text": text }));
+}
+
+fn hello_message(sample_rate: u32) -> String {
+    json!({
+        "type": "hello",
+        "codec": "opus",
+        "sampleRate": sample_rate,
+        "channels": OUTPUT_CHANNELS,
+        "frameMs": 20,
+    })
+    .to_string()
+}
+
+/// Whether a handshake's `Origin` is the app itself or a page served from
+/// this machine. Clients outside a browser send no `Origin` at all.
+fn origin_allowed(origin: &str) -> bool {
+    let Some((scheme, rest)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match rest.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(""),
+        None => rest.split(':').next().unwrap_or(""),
+    };
+    matches!(scheme, "http" | "https" | "tauri")
+        && matches!(host, "localhost" | "tauri.localhost" | "127.0.0.1" | "::1")
+}
+
+/// The token a handshake presents, from the `Authorization` header or the
+/// `token` query parameter.
+fn presented_token(request: &Request) -> Option<&str> {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    bearer.or_else(|| {
+        request
+            .uri()
+            .query()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+    })
+}
+
+/// Check a handshake's origin and token before upgrading it.
+fn authorize(request: &Request, token: &str) -> Result<(), (StatusCode, &'static str)> {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        if !origin.to_str().is_ok_and(origin_allowed) {
+            return Err((StatusCode::FORBIDDEN, "Origin not allowed"));
+        }
+    }
+    let provided = presented_token(request).unwrap_or("");
+    if !constant_time_eq(provided.as_bytes(), token.as_bytes()) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or missing token"));
+    }
+    Ok(())
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    token: Arc<String>,
+    sample_rate: u32,
+    mut events: broadcast::Receiver<StreamMessage>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let callback = |request: &Request, response: Response| match authorize(request, &token) {
+        Ok(()) => Ok(response),
+        Err((status, message)) => {
+            let mut rejection = ErrorResponse::new(Some(message.to_string()));
+            *rejection.status_mut() = status;
+            Err(rejection)
+        }
+    };
+    let ws = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            tracing::warn!("WebSocket handshake failed: {}", e);
+            return;
+        }
+    };
+    let (mut sink, mut source) = ws.split();
+    if sink.send(Message::Text(hello_message(sample_rate))).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            incoming = source.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+            msg = events.recv() => {
+                let outgoing = match msg {
+                    Ok(StreamMessage::Audio(bytes)) => Message::Binary(bytes),
+                    Ok(StreamMessage::Event(text)) => Message::Text(text),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("Stream client lagged, skipped {} messages", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if sink.send(outgoing).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = sink.close().await;
+}
+
+/// Pull new audio from the ring buffer, run it through the DSP pipeline,
+/// encode it into 20 ms Opus packets and broadcast packets plus a level
+/// event per poll.
+async fn run_producer(
+    audio: Arc<SystemAudioState>,
+    events: broadcast::Sender<StreamMessage>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut encoder: Option<opus::Encoder> = None;
+    let mut pipeline: Option<DspPipeline> = None;
+    let mut frame_size = 0usize;
+    let mut silence = SilenceGate::new(false);
+    let mut position = audio.written_position();
+    let mut pending: Vec<f32> = Vec::new();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => return,
+            _ = interval.tick() => {}
+        }
+
+        if events.receiver_count() == 0 || !audio.is_recording() {
+            // Nobody listening (or nothing to hear): stay at the live edge.
+            position = audio.written_position();
+            pending.clear();
+            encoder = None;
+            pipeline = None;
+            continue;
+        }
+
+        let (samples, next) = match audio.read_since(position) {
+            Ok(read) => read,
+            Err(_) => continue,
+        };
+        position = next;
+        if samples.is_empty() {
+            continue;
+        }
+
+        let level = measure_level(&samples);
+        let _ = events.send(StreamMessage::Event(
+            json!({ "type": "level", "rmsDbfs": level.rms_dbfs, "peakDbfs": level.peak_dbfs })
+                .to_string(),
+        ));
+
+        if encoder.is_none() {
+            let options = audio.encode_options().unwrap_or_default();
+            let config = audio.dsp_config().unwrap_or_default();
+            let sample_rate = config.output_sample_rate();
+            frame_size = (sample_rate as usize) * 20 / 1000;
+            let dsp = config.build();
+            tracing::debug!("Stream DSP stages: {:?}", dsp.stage_names());
+            pipeline = Some(dsp);
+            silence = SilenceGate::new(options.skip_silence);
+            encoder = match new_opus_encoder(sample_rate, &options) {
+                Ok(enc) => Some(enc),
+                Err(e) => {
+                    tracing::error!("Stream encoder init failed: {}", e);
+                    continue;
+                }
+            };
+        }
+        let (Some(enc), Some(dsp)) = (encoder.as_mut(), pipeline.as_mut()) else {
+            continue;
+        };
+
+        pending.extend(dsp.process(samples).samples);
+        let full = pending.len() / frame_size * frame_size;
+        for frame in pending[..full].chunks_exact(frame_size) {
+            match enc.encode_vec_float(frame, 4000) {
+                Ok(packet) => {
+                    let packet = if silence.skip(frame) {
+                        empty_packet(&packet)
+                    } else {
+                        packet
+                    };
+                    let _ = events.send(StreamMessage::Audio(packet));
+                }
+                Err(e) => tracing::warn!("Stream Opus encode failed: {}", e),
+            }
+        }
+        pending.drain(..full);
+    }
+}
+
+fn status_of(server: &RunningServer) -> StreamServerStatus {
+    StreamServerStatus {
+        running: true,
+        port: Some(server.port),
+        token: Some(server.token.clone()),
+        clients: server.events.receiver_count(),
+    }
+}
+
+/// Start the localhost WebSocket stream server. Returns the bound port and
+/// the token clients must present; a random token is generated when none
+/// is given.
+#[tauri::command]
+pub async fn stream_server_start(
+    app: AppHandle,
+    port: Option<u16>,
+    token: Option<String>,
+) -> Result<StreamServerStatus, String> {
+    let state = app.state::<StreamServerState>();
+    if let Some(server) = state.server.lock().map_err(|e| e.to_string())?.as_ref() {
+        return Ok(status_of(server));
+    }
+
+    let token = token
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+
+    let listener = TcpListener::bind(("127.0.0.1", port.unwrap_or(DEFAULT_PORT)))
+        .await
+        .map_err(|e| format!("Failed to bind stream server: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read stream server address: {}", e))?
+        .port();
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let (events_tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+    {
+        let mut guard = state.server.lock().map_err(|e| e.to_string())?;
+        if let Some(server) = guard.as_ref() {
+            // Lost a race with a concurrent start; keep the existing server.
+            return Ok(status_of(server));
+        }
+        *guard = Some(RunningServer {
+            port,
+            token: token.clone(),
+            shutdown: shutdown_tx,
+            events: events_tx.clone(),
+        });
+    }
+
+    let audio = app.state::<Arc<SystemAudioState>>().inner().clone();
+    tauri::async_runtime::spawn(run_producer(
+        audio.clone(),
+        events_tx.clone(),
+        shutdown_rx.clone(),
+    ));
+
+    let mut accept_shutdown = shutdown_rx.clone();
+    let client_token = Arc::new(token.clone());
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = accept_shutdown.changed() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, addr)) => {
+                        tracing::info!("Stream client connected from {}", addr);
+                        let sample_rate = audio
+                            .dsp_config()
+                            .unwrap_or_default()
+                            .output_sample_rate();
+                        tauri::async_runtime::spawn(handle_connection(
+                            stream,
+                            client_token.clone(),
+                            sample_rate,
+                            events_tx.subscribe(),
+                            shutdown_rx.clone(),
+                        ));
+                    }
+                    Err(e) => tracing::warn!("Stream server accept failed: {}", e),
+                }
+            }
+        }
+        tracing::info!("Stream server stopped");
+    });
+
+    tracing::info!("Stream server listening on ws://127.0.0.1:{}", port);
+    Ok(StreamServerStatus {
+        running: true,
+        port: Some(port),
+        token: Some(token),
+        clients: 0,
+    })
+}
+
+/// Stop the WebSocket stream server and disconnect all clients.
+#[tauri::command]
+pub fn stream_server_stop(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<StreamServerState>();
+    let server = state.server.lock().map_err(|e| e.to_string())?.take();
+    if let Some(server) = server {
+        let _ = server.shutdown.send(true);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stream_server_status(app: AppHandle) -> Result<StreamServerStatus, String> {
+    let state = app.state::<StreamServerState>();
+    let guard = state.server.lock().map_err(|e| e.to_string())?;
+    Ok(match guard.as_ref() {
+        Some(server) => status_of(server),
+        None => StreamServerStatus {
+            running: false,
+            port: None,
+            token: None,
+            clients: 0,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests;
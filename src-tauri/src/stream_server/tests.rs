@@ -0,0 +1,66 @@
+use super::*;
+
+fn request(headers: &[(&str, &str)], uri: &str) -> Request {
+    let mut builder = Request::builder().uri(uri);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    builder.body(()).unwrap()
+}
+
+fn status(result: Result<(), (StatusCode, &'static str)>) -> Option<StatusCode> {
+    result.err().map(|(status, _)| status)
+}
+
+#[test]
+fn bearer_header_or_query_token_is_accepted() {
+    let header = request(&[("Authorization", "Bearer secret")], "/");
+    assert_eq!(status(authorize(&header, "secret")), None);
+    let query = request(&[], "/?a=1&token=secret");
+    assert_eq!(status(authorize(&query, "secret")), None);
+}
+
+#[test]
+fn missing_or_wrong_token_is_unauthorized() {
+    let missing = request(&[], "/");
+    assert_eq!(
+        status(authorize(&missing, "secret")),
+        Some(StatusCode::UNAUTHORIZED)
+    );
+    let wrong = request(&[("Authorization", "Bearer nope")], "/?token=nope");
+    assert_eq!(
+        status(authorize(&wrong, "secret")),
+        Some(StatusCode::UNAUTHORIZED)
+    );
+}
+
+#[test]
+fn foreign_origins_are_forbidden_even_with_the_token() {
+    for origin in [
+        "https://example.com",
+        "http://localhost.example.com",
+        "null",
+        "file://",
+    ] {
+        let req = request(&[("Origin", origin)], "/?token=secret");
+        assert_eq!(
+            status(authorize(&req, "secret")),
+            Some(StatusCode::FORBIDDEN),
+            "{}",
+            origin
+        );
+    }
+}
+
+#[test]
+fn app_and_loopback_origins_are_allowed() {
+    for origin in [
+        "tauri://localhost",
+        "http://tauri.localhost",
+        "http://localhost:1420",
+        "http://127.0.0.1:8080",
+        "http://[::1]:3000",
+    ] {
+        assert!(origin_allowed(origin), "{}", origin);
+    }
+}
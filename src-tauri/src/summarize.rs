@@ -0,0 +1,217 @@
+//! `summarize_recent`: meeting notes for the last minutes of captured
+//! audio, a summary plus action items, saved to history with the recording
+//! and its transcript.
+//!
+//! Only what the capture buffer still holds can be summarized, so asking
+//! for more minutes than it keeps is an error. A transcript too long for
+//! one prompt is summarized map-reduce: each chunk on its own, then the chunk summaries
+//! together into the notes. Both passes go through the selected chat
+//! provider, without retrieved context (see `retrieval`) so earlier meetings
+//! don't leak into the notes.
+//!
+//! The recording is saved to the recordings folder like an auto-save, so
+//! encrypted buffers are refused: plaintext must not reach the disk.
+
+use crate::api;
+use crate::autosave;
+use crate::embeddings;
+use crate::history::{self, ActionItem, HistoryEntry, NewRecording, RecordingSummary};
+use crate::system_audio::{SystemAudioState, TimedAudio};
+use crate::system_audio_encoder::{encoded_duration_seconds, ExportFormat};
+use base64::Engine;
+use chrono::Local;
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// History tag of summarized recordings.
+const HISTORY_TAG: &str = "summary";
+/// Transcript characters per summarization prompt, about 3000 tokens.
+const CHUNK_CHARS: usize = 12_000;
+const CHUNK_OVERLAP: usize = 200;
+
+const MAP_PROMPT: &str = "You summarize one part of a meeting transcript. List the topics \
+    discussed, the decisions made and any tasks someone took on, with who and by when if said. \
+    Be brief and add nothing that isn't in the transcript.";
+
+const REDUCE_PROMPT: &str = "You write meeting notes from a transcript, or from summaries of \
+    its parts in order. Reply with only a JSON object: {\"summary\": \"...\", \"actionItems\": \
+    [{\"task\": \"...\", \"owner\": \"...\", \"due\": \"...\"}]}. The summary covers the topics \
+    and decisions in a few short paragraphs or bullet points. Action items are tasks someone \
+    agreed to do; owner and due are null when not said. Use an empty list if there are none.";
+
+/// Payload of `summarize-progress`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress {
+    /// `"transcribing"` or `"summarizing"`.
+    stage: &'static str,
+    done: usize,
+    total: usize,
+}
+
+/// Result of `summarize_recent`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingSummary {
+    pub entry: HistoryEntry,
+    pub summary: RecordingSummary,
+}
+
+/// The notes as the model is asked to write them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Notes {
+    summary: String,
+    action_items: Vec<ActionItem>,
+}
+
+/// Check `minutes` is positive and within the `retained_seconds` the
+/// buffer keeps.
+fn check_minutes(minutes: f64, retained_seconds: f64) -> Result<(), String> {
+    if !minutes.is_finite() || minutes <= 0.0 {
+        return Err(format!("minutes must be positive, got {}", minutes));
+    }
+    // Whole seconds, so a 5-minute buffer covers `minutes: 5`.
+    if (minutes * 60.0).floor() > retained_seconds.ceil() {
+        return Err(format!(
+            "Only the last {} minutes of audio are kept; ask for that or less",
+            retained_seconds / 60.0
+        ));
+    }
+    Ok(())
+}
+
+/// The summary and action items in the model's reply. Models don't always
+/// stick to bare JSON, so code fences and text around the object are
+/// ignored, and a reply without usable JSON is taken as the summary.
+fn parse_notes(reply: &str) -> (String, Vec<ActionItem>) {
+    let notes = reply
+        .find('{')
+        .zip(reply.rfind('}'))
+        .filter(|(start, end)| start < end)
+        .and_then(|(start, end)| serde_json::from_str::<Notes>(&reply[start..=end]).ok())
+        .filter(|notes| !notes.summary.trim().is_empty());
+    let Some(notes) = notes else {
+        return (reply.trim().to_string(), Vec::new());
+    };
+    let said = |text: Option<String>| {
+        text.map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+    let action_items = notes
+        .action_items
+        .into_iter()
+        .filter(|item| !item.task.trim().is_empty())
+        .map(|item| ActionItem {
+            task: item.task.trim().to_string(),
+            owner: said(item.owner),
+            due: said(item.due),
+        })
+        .collect();
+    (notes.summary.trim().to_string(), action_items)
+}
+
+/// The prompt for the reduce pass over `parts`: the transcript itself if
+/// it fit in one chunk, else the summaries of its chunks.
+fn reduce_input(parts: &[String], summarized: bool) -> String {
+    if !summarized {
+        return format!("Transcript:\n\n{}", parts.join("\n\n"));
+    }
+    let mut input = "Summaries of the meeting's parts, in order:".to_string();
+    for (i, part) in parts.iter().enumerate() {
+        input.push_str(&format!("\n\nPart {}:\n{}", i + 1, part.trim()));
+    }
+    input
+}
+
+fn progress(app: &AppHandle, stage: &'static str, done: usize, total: usize) {
+    let _ = app.emit("summarize-progress", Progress { stage, done, total });
+}
+
+/// `audio` transcribed. The buffer holds at most `MAX_BUFFER_SECONDS`,
+/// well within providers' upload limits, so it goes in one request.
+async fn transcribe(app: &AppHandle, audio: &TimedAudio) -> Result<String, String> {
+    progress(app, "transcribing", 0, 1);
+    let audio_base64 = base64::engine::general_purpose::STANDARD.encode(&audio.ogg);
+    let transcript = api::transcribe(app.clone(), audio_base64)
+        .await?
+        .into_transcription()
+        .ok_or_else(|| "Transcription failed".to_string())?;
+    progress(app, "transcribing", 1, 1);
+    Ok(transcript.trim().to_string())
+}
+
+/// Map-reduce `transcript` into a summary and action items.
+async fn summarize(app: &AppHandle, transcript: &str) -> Result<(String, Vec<ActionItem>), String> {
+    let chunks = embeddings::chunk_text(transcript, CHUNK_CHARS, CHUNK_OVERLAP);
+    let summarized = chunks.len() > 1;
+    // Chunk summaries plus the final pass.
+    let total = if summarized { chunks.len() + 1 } else { 1 };
+    progress(app, "summarizing", 0, total);
+    let parts = if summarized {
+        let count = chunks.len();
+        let parts = try_join_all(chunks.into_iter().enumerate().map(|(i, chunk)| {
+            let input = format!("Part {} of {}:\n\n{}", i + 1, count, chunk);
+            api::complete(app, MAP_PROMPT, input)
+        }))
+        .await?;
+        progress(app, "summarizing", count, total);
+        parts
+    } else {
+        chunks
+    };
+    let reply = api::complete(app, REDUCE_PROMPT, reduce_input(&parts, summarized)).await?;
+    progress(app, "summarizing", total, total);
+    Ok(parse_notes(&reply))
+}
+
+/// Transcribe and summarize the last `minutes` of captured audio, and save
+/// the recording, transcript, summary and action items to history. Emits
+/// `summarize-progress` along the way.
+#[tauri::command]
+pub async fn summarize_recent(app: AppHandle, minutes: f64) -> Result<MeetingSummary, String> {
+    let state = app.state::<Arc<SystemAudioState>>().inner().clone();
+    check_minutes(minutes, state.buffer_seconds()?)?;
+    if state.is_buffer_encrypted() {
+        return Err("The capture buffer is encrypted and can't be saved".to_string());
+    }
+    let audio = {
+        let state = state.clone();
+        tauri::async_runtime::spawn_blocking(move || state.get_last_timed(minutes * 60.0))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+
+    let transcript = transcribe(&app, &audio).await?;
+    if transcript.is_empty() {
+        return Err(format!(
+            "Nothing was said in the last {} minutes to summarize",
+            minutes
+        ));
+    }
+    let (summary, action_items) = summarize(&app, &transcript).await?;
+
+    let path = autosave::write_recording(
+        &autosave::recordings_dir(&app)?,
+        Local::now(),
+        ExportFormat::OggOpus,
+        &audio.ogg,
+    )?;
+    let entry = history::record(
+        &app,
+        NewRecording {
+            path: path.to_string_lossy().to_string(),
+            duration_seconds: encoded_duration_seconds(&audio.ogg),
+            transcript: Some(transcript),
+            tags: vec![HISTORY_TAG.to_string()],
+            ..Default::default()
+        },
+    )
+    .await?;
+    let summary = history::save_summary(&app, entry.id, &summary, &action_items).await?;
+    Ok(MeetingSummary { entry, summary })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn minutes_must_fit_in_the_buffer() {
+    assert!(check_minutes(5.0, 300.0).is_ok());
+    assert!(check_minutes(0.5, 300.0).is_ok());
+    assert!(check_minutes(2.5, 120.0).is_err());
+    assert!(check_minutes(0.0, 300.0).is_err());
+    assert!(check_minutes(f64::NAN, 300.0).is_err());
+}
+
+#[test]
+fn notes_are_read_from_fenced_json() {
+    let reply = "Here are the notes:\n```json\n{\"summary\": \" Budget approved. \", \
+        \"actionItems\": [{\"task\": \"Send the deck\", \"owner\": \"Sam\", \"due\": null}, \
+        {\"task\": \"Book a room\", \"owner\": \" \", \"due\": \"Friday\"}, {\"task\": \"\"}]}\n```";
+    let (summary, items) = parse_notes(reply);
+    assert_eq!(summary, "Budget approved.");
+    assert_eq!(
+        items,
+        vec![
+            ActionItem {
+                task: "Send the deck".to_string(),
+                owner: Some("Sam".to_string()),
+                due: None,
+            },
+            ActionItem {
+                task: "Book a room".to_string(),
+                owner: None,
+                due: Some("Friday".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn replies_without_usable_json_become_the_summary() {
+    assert_eq!(
+        parse_notes("  We agreed on the {budget}.  "),
+        ("We agreed on the {budget}.".to_string(), Vec::new())
+    );
+    assert_eq!(
+        parse_notes("{\"summary\": \"\", \"actionItems\": []}").0,
+        "{\"summary\": \"\", \"actionItems\": []}"
+    );
+}
+
+#[test]
+fn reduce_input_numbers_chunk_summaries() {
+    let parts = vec!["Intro.".to_string(), " Budget. ".to_string()];
+    assert_eq!(
+        reduce_input(&parts, true),
+        "Summaries of the meeting's parts, in order:\n\nPart 1:\nIntro.\n\nPart 2:\nBudget."
+    );
+    assert_eq!(reduce_input(&parts[..1], false), "Transcript:\n\nIntro.");
+}
@@ -6,34 +6,205 @@
 //! On Windows 10/11: uses WASAPI loopback capture via cpal.
 //! On other platforms: returns "unsupported".
 
+use crate::hidden_command;
+use crate::system_audio_backend::{CaptureBackend, CaptureBackendConfig, CaptureSettings};
+use crate::system_audio_cipher::BufferCipher;
+use crate::system_audio_dsp::{audible_range, AudioBlock, DspConfig, TimeStretch};
+use crate::system_audio_encoder::{encode_wav, iso8601_utc, Downmix, EncodeOptions, ExportFormat};
+use crate::system_audio_loudness::measure_loudness;
+use crate::system_audio_memory::{choose_buffer_seconds, system_memory};
+use crate::system_audio_perf::{PerfCounters, PerfStats, TimingGuard};
+use crate::system_audio_spectrum::{
+    spectrum, Spectrum, MAX_SPECTRUM_BANDS, MIN_SPECTRUM_BANDS, SPECTRUM_FFT_SIZE,
+};
 use base64::Engine;
 use serde::Serialize;
-use std::io::Cursor;
+use std::collections::VecDeque;
 use std::io::Write;
 use std::process::{Command, Stdio};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use zeroize::Zeroize;
 
 /// Output sample rate for Opus encoding (speech-optimized).
-const OUTPUT_SAMPLE_RATE: u32 = 16000;
+pub(crate) const OUTPUT_SAMPLE_RATE: u32 = 16000;
 /// Output is mono.
-const OUTPUT_CHANNELS: u16 = 1;
+pub(crate) const OUTPUT_CHANNELS: u16 = 1;
 
-/// Max buffer we allocate (seconds). Actual used length is set on start.
-const MAX_BUFFER_SECONDS: u32 = 300;
+/// Longest buffer (seconds). The ring is sized to the length set on start.
+pub(crate) const MAX_BUFFER_SECONDS: u32 = 300;
+/// How long `crash_snapshot_wav` waits for the ring lock.
+const CRASH_LOCK_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Samples at or above this magnitude (-0.1 dBFS) count as clipped.
+const CLIP_LEVEL: f32 = 0.988_553;
+
+/// Default level below which audio counts as silence for the idle timeout.
+const DEFAULT_IDLE_THRESHOLD_DBFS: f32 = -60.0;
+/// How often the idle monitor checks for prolonged silence.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the capture watchdog looks for a stalled backend.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+/// A backend that hasn't delivered audio for this long is considered dead.
+const STALL_TIMEOUT: Duration = Duration::from_secs(3);
+/// Without audio above the idle threshold for this long, a running capture
+/// is shown as silent rather than capturing.
+const SILENT_AFTER: Duration = Duration::from_secs(2);
+/// Upper bound for the delay between failed recovery attempts.
+const MAX_RECOVERY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Default payload per chunk of a chunked export. A multiple of 3, so the
+/// base64 chunks concatenate to the base64 of the whole file.
+const DEFAULT_EXPORT_CHUNK_BYTES: usize = 192 * 1024;
+/// Fastest time-compressed export; beyond this speech stops being
+/// intelligible.
+const MAX_EXPORT_SPEED: f64 = 4.0;
+
+/// Bands in `system_audio_spectrum` when the caller doesn't ask for a count.
+const DEFAULT_SPECTRUM_BANDS: usize = 128;
+
+/// Longest window `get_audio_at` returns; scrubbing needs a glimpse, not an
+/// export.
+const MAX_SCRUB_SECONDS: f64 = 5.0;
+
+/// Minimum session length before a clock drift estimate is reported; below
+/// this, callback jitter outweighs any real drift.
+const MIN_DRIFT_WINDOW: Duration = Duration::from_secs(60);
+/// Fewest samples between block stamps (10 ms), unless the host clock has
+/// run ahead of the audio by more than that since the last one (a pause or
+/// dropped blocks). Blocks in between are timed from the last stamp.
+const STAMP_SPACING: usize = (OUTPUT_SAMPLE_RATE as usize) * (OUTPUT_CHANNELS as usize) / 100;
+
+/// Capture is paused because a blocklisted app is in the foreground.
+pub const PAUSE_REASON_PRIVACY_APP: u32 = 1 << 0;
+/// Capture is paused because the user is typing into a password field.
+pub const PAUSE_REASON_SECURE_INPUT: u32 = 1 << 1;
+/// Capture is paused because a Focus / Do Not Disturb mode is on.
+pub const PAUSE_REASON_FOCUS_MODE: u32 = 1 << 2;
+/// Capture is suspended because the session is locked or switched away.
+pub const PAUSE_REASON_SESSION_INACTIVE: u32 = 1 << 3;
+/// Capture is paused while a spoken answer plays, where the tap can't
+/// leave out this app's own output.
+pub const PAUSE_REASON_OWN_SPEECH: u32 = 1 << 4;
+
+/// Ring storage guarded by `SystemAudioState::ring`.
+struct RingBuffer {
+    buf: Vec<f32>,
+    write_index: usize,
+    /// Present when the current session keeps samples encrypted in memory.
+    cipher: Option<BufferCipher>,
+    /// Capture time of blocks at least `STAMP_SPACING` apart, oldest first.
+    /// Covers at least the resident part of the buffer, and is allocated
+    /// with the buffer so the audio thread never grows it.
+    stamps: VecDeque<BlockStamp>,
+    /// First stamp of the session, kept after pruning to measure drift.
+    anchor: Option<BlockStamp>,
+    /// Clock the stamps are read from, restarted with each session.
+    clock: HostClock,
+}
+
+/// Wall-clock time read off the monotonic clock: the wall time at `origin`
+/// plus the time elapsed since. Cheap enough for the audio thread, and
+/// stamps stay ordered if the system clock is stepped mid-session.
+#[derive(Debug, Clone, Copy)]
+struct HostClock {
+    origin: Instant,
+    /// Microseconds since the Unix epoch at `origin`.
+    origin_us: u64,
+}
+
+impl HostClock {
+    fn start() -> Self {
+        Self {
+            origin: Instant::now(),
+            origin_us: now_micros(),
+        }
+    }
+
+    fn now_us(&self) -> u64 {
+        self.origin_us + self.origin.elapsed().as_micros() as u64
+    }
+}
+
+/// Host wall-clock time of the first sample of a pushed block.
+#[derive(Debug, Clone, Copy)]
+struct BlockStamp {
+    /// Absolute sample position of the block's first sample.
+    position: usize,
+    /// Microseconds since the Unix epoch.
+    time_us: u64,
+}
 
 /// Shared state for the system audio ring buffer and daemon control.
 pub struct SystemAudioState {
-    /// Ring buffer: physical capacity = MAX_BUFFER_SECONDS * OUTPUT_SAMPLE_RATE * OUTPUT_CHANNELS.
+    /// Ring buffer: physical capacity = buffer_seconds * OUTPUT_SAMPLE_RATE * OUTPUT_CHANNELS,
+    /// resized while stopped by `set_buffer_seconds`.
     /// Logical length (samples to return) = buffer_seconds * OUTPUT_SAMPLE_RATE * OUTPUT_CHANNELS.
-    ring: Mutex<(Vec<f32>, usize)>,
-    capacity: usize,
+    ring: Mutex<RingBuffer>,
+    /// Length of the ring's `buf`.
+    capacity: AtomicUsize,
+    /// Largest the ring may grow to.
+    max_capacity: usize,
     /// Number of samples to return in get_recent (logical_seconds * rate * ch).
     logical_len: Mutex<usize>,
     /// Total number of samples successfully written to the ring buffer since
     /// the current capture session started.
     written_samples: AtomicUsize,
+    /// Samples lost because the ring was locked when they arrived.
+    dropped_samples: AtomicUsize,
+    /// Samples at or near full scale this session.
+    clipped_samples: AtomicUsize,
+    /// Wall-clock time (ms) of the last clipped chunk; 0 if none yet.
+    last_clip_ms: AtomicU64,
+    /// Bookmarks dropped into the current capture session.
+    markers: Mutex<Vec<AudioMarker>>,
+    /// Wall-clock time (ms) the backend last delivered audio, silent or not.
+    last_delivery_ms: AtomicU64,
+    /// Set by backends that learn their device is gone; handled by the
+    /// capture watchdog.
+    recovery_request: Mutex<Option<RecoveryReason>>,
+    /// Times the capture was rebuilt by the watchdog this session.
+    recoveries: AtomicU32,
+    /// Recording, but the backend has stopped delivering audio.
+    degraded: AtomicBool,
+    /// The backend is being rebuilt; `recording` is briefly false but the
+    /// session goes on.
+    restarting: AtomicBool,
+    /// The backend is stopped by `suspend_capture` while the session is
+    /// inactive; the buffer and session are kept for `resume_capture`.
+    suspended: AtomicBool,
+    /// Wall-clock time (ms) of the last pushed chunk above the idle threshold.
+    last_activity_ms: AtomicU64,
+    /// Idle threshold as linear peak amplitude (f32 bits).
+    idle_threshold: AtomicU32,
+    /// Incremented on every start so background tasks can tell sessions apart.
+    session: AtomicU64,
+    /// Bitmask of `PAUSE_REASON_*` flags; incoming audio is dropped while non-zero.
+    pause_reasons: AtomicU32,
+    /// Opus/OGG encoder settings used for every export.
+    encode_options: Mutex<EncodeOptions>,
+    /// Processing stages applied before encoding exports and streams.
+    dsp_config: Mutex<DspConfig>,
+    /// Whether the next session should keep the ring buffer encrypted.
+    encrypt_buffer: AtomicBool,
+    /// Source of captured audio; only swapped while stopped.
+    backend: Mutex<Arc<dyn CaptureBackend>>,
+    /// Exclusions, mute behavior and device pin for the native backend.
+    capture_settings: Mutex<CaptureSettings>,
+    /// Native format the backend is converting from, once it is known.
+    capture_format: Mutex<Option<CaptureFormat>>,
+    /// What this session is recording, e.g. a calendar event title. Cleared
+    /// at session start.
+    session_label: Mutex<Option<String>>,
+    /// Callback and encode timings and CPU use this session.
+    perf: PerfCounters,
+    /// Buffer length asked for at the last start when memory forced a
+    /// shorter one; 0 if it wasn't capped.
+    buffer_capped_from: AtomicU32,
     /// Whether the daemon is currently recording.
     recording: AtomicBool,
     /// Join handle for the capture thread (macOS only).
@@ -43,45 +214,282 @@ pub struct SystemAudioState {
 
 impl SystemAudioState {
     pub fn new() -> Self {
-        let capacity = (MAX_BUFFER_SECONDS as usize)
-            .saturating_mul(OUTPUT_SAMPLE_RATE as usize)
-            .saturating_mul(OUTPUT_CHANNELS as usize);
-        let capacity = capacity.max(1);
+        Self::with_capacity(
+            (MAX_BUFFER_SECONDS as usize)
+                .saturating_mul(OUTPUT_SAMPLE_RATE as usize)
+                .saturating_mul(OUTPUT_CHANNELS as usize),
+        )
+    }
+
+    /// State whose ring buffer can grow to `max_capacity` samples. It
+    /// starts out holding the default 30 s.
+    fn with_capacity(max_capacity: usize) -> Self {
+        let max_capacity = max_capacity.max(1);
         let default_seconds = 30u32;
         let logical_len = (default_seconds as usize)
             .saturating_mul(OUTPUT_SAMPLE_RATE as usize)
             .saturating_mul(OUTPUT_CHANNELS as usize)
-            .min(capacity);
+            .min(max_capacity);
         Self {
-            ring: Mutex::new((vec![0.0; capacity], 0)),
-            capacity,
+            ring: Mutex::new(RingBuffer {
+                buf: vec![0.0; logical_len],
+                write_index: 0,
+                cipher: None,
+                stamps: VecDeque::with_capacity(stamp_capacity(logical_len)),
+                anchor: None,
+                clock: HostClock::start(),
+            }),
+            capacity: AtomicUsize::new(logical_len),
+            max_capacity,
             logical_len: Mutex::new(logical_len),
             written_samples: AtomicUsize::new(0),
+            dropped_samples: AtomicUsize::new(0),
+            clipped_samples: AtomicUsize::new(0),
+            last_clip_ms: AtomicU64::new(0),
+            markers: Mutex::new(Vec::new()),
+            last_delivery_ms: AtomicU64::new(0),
+            recovery_request: Mutex::new(None),
+            recoveries: AtomicU32::new(0),
+            degraded: AtomicBool::new(false),
+            restarting: AtomicBool::new(false),
+            suspended: AtomicBool::new(false),
+            last_activity_ms: AtomicU64::new(0),
+            idle_threshold: AtomicU32::new(dbfs_to_linear(DEFAULT_IDLE_THRESHOLD_DBFS).to_bits()),
+            session: AtomicU64::new(0),
+            pause_reasons: AtomicU32::new(0),
+            encode_options: Mutex::new(EncodeOptions::default()),
+            dsp_config: Mutex::new(DspConfig::default()),
+            encrypt_buffer: AtomicBool::new(false),
+            backend: Mutex::new(CaptureBackendConfig::from_env().build()),
+            capture_settings: Mutex::new(CaptureSettings::default()),
+            capture_format: Mutex::new(None),
+            session_label: Mutex::new(None),
+            perf: PerfCounters::default(),
+            buffer_capped_from: AtomicU32::new(0),
             recording: AtomicBool::new(false),
             capture_handle: Mutex::new(None),
         }
     }
 
     /// Clear ring state at session start so short recordings don't include
-    /// stale or zero-padded history from previous sessions.
-    pub fn reset_capture_state(&self) {
+    /// stale or zero-padded history from previous sessions. Fails if the
+    /// buffer should be encrypted and no key can be made; the session must
+    /// not start in plaintext instead.
+    pub fn reset_capture_state(&self) -> Result<(), String> {
+        let session_cipher = if self.encrypt_buffer.load(Ordering::SeqCst) {
+            Some(BufferCipher::generate().map_err(|e| format!("Buffer encryption: {}", e))?)
+        } else {
+            None
+        };
         if let Ok(mut ring) = self.ring.lock() {
-            let (buf, idx) = &mut *ring;
+            let RingBuffer {
+                buf,
+                write_index: idx,
+                cipher,
+                stamps,
+                anchor,
+                clock,
+            } = &mut *ring;
             buf.fill(0.0);
             *idx = 0;
+            stamps.clear();
+            *anchor = None;
+            *clock = HostClock::start();
+            *cipher = session_cipher;
+        }
+        self.written_samples.store(0, Ordering::SeqCst);
+        self.dropped_samples.store(0, Ordering::SeqCst);
+        self.clipped_samples.store(0, Ordering::SeqCst);
+        self.last_clip_ms.store(0, Ordering::SeqCst);
+        if let Ok(mut format) = self.capture_format.lock() {
+            *format = None;
+        }
+        if let Ok(mut label) = self.session_label.lock() {
+            *label = None;
+        }
+        if let Ok(mut markers) = self.markers.lock() {
+            markers.clear();
+        }
+        if let Ok(mut request) = self.recovery_request.lock() {
+            *request = None;
         }
+        self.recoveries.store(0, Ordering::SeqCst);
+        self.degraded.store(false, Ordering::SeqCst);
+        self.perf.reset();
+        self.last_delivery_ms.store(now_millis(), Ordering::SeqCst);
+        self.last_activity_ms.store(now_millis(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Securely zero the ring buffer and forget the session (write position,
+    /// markers, encryption key) so captured audio doesn't linger in memory.
+    pub fn zeroize_buffer(&self) {
+        let mut ring = match self.ring.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        ring.buf.as_mut_slice().zeroize();
+        ring.write_index = 0;
+        ring.cipher = None;
+        ring.stamps.clear();
+        ring.anchor = None;
         self.written_samples.store(0, Ordering::SeqCst);
+        if let Ok(mut markers) = self.markers.lock() {
+            markers.clear();
+        }
+    }
+
+    /// The last `seconds` of the buffer as 16-bit WAV, for the panic hook.
+    /// Gives up on the ring lock after `CRASH_LOCK_TIMEOUT`, since the
+    /// panicking thread may hold it. `None` if nothing was recorded or the
+    /// buffer is encrypted (plaintext must not reach the disk).
+    pub fn crash_snapshot_wav(&self, seconds: u32) -> Option<Vec<u8>> {
+        let deadline = Instant::now() + CRASH_LOCK_TIMEOUT;
+        let mut ring = loop {
+            match self.ring.try_lock() {
+                Ok(guard) => break guard,
+                Err(TryLockError::Poisoned(poisoned)) => break poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Err(TryLockError::WouldBlock) => return None,
+            }
+        };
+        if ring.cipher.is_some() {
+            return None;
+        }
+        let written = self.written_samples.load(Ordering::Acquire);
+        let len = (seconds as usize)
+            .saturating_mul(OUTPUT_SAMPLE_RATE as usize)
+            .saturating_mul(OUTPUT_CHANNELS as usize)
+            .min(written)
+            .min(self.capacity());
+        if len == 0 {
+            return None;
+        }
+        let mut samples = self.copy_from_ring(&mut ring, written - len, len);
+        drop(ring);
+        let wav = encode_wav(&samples, OUTPUT_SAMPLE_RATE);
+        samples.zeroize();
+        Some(wav)
+    }
+
+    /// Count non-zero samples left in the ring buffer.
+    pub fn count_nonzero_samples(&self) -> Result<usize, String> {
+        let ring = self.ring.lock().map_err(|e| e.to_string())?;
+        Ok(ring.buf.iter().filter(|s| s.to_bits() != 0).count())
+    }
+
+    /// Set the level (dBFS) a chunk's peak must reach to count as activity.
+    pub fn set_idle_threshold_dbfs(&self, dbfs: f32) {
+        self.idle_threshold
+            .store(dbfs_to_linear(dbfs).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Milliseconds since the last chunk above the idle threshold.
+    pub fn idle_millis(&self) -> u64 {
+        now_millis().saturating_sub(self.last_activity_ms.load(Ordering::Relaxed))
+    }
+
+    /// Milliseconds since the backend last delivered audio.
+    pub fn delivery_gap_millis(&self) -> u64 {
+        now_millis().saturating_sub(self.last_delivery_ms.load(Ordering::Relaxed))
+    }
+
+    /// Ask the capture watchdog to rebuild the backend. Backends call this
+    /// from device notifications, outside the audio thread.
+    pub fn request_recovery(&self, reason: RecoveryReason) {
+        if let Ok(mut request) = self.recovery_request.lock() {
+            request.get_or_insert(reason);
+        }
+    }
+
+    /// Mark the capture as stalled or healthy. Returns true if that changed.
+    fn set_degraded(&self, degraded: bool) -> bool {
+        self.degraded.swap(degraded, Ordering::SeqCst) != degraded
+    }
+
+    /// Recording, or between the stop and start of a backend rebuild.
+    fn session_running(&self) -> bool {
+        self.is_recording() || self.restarting.load(Ordering::SeqCst) || self.is_suspended()
+    }
+
+    /// The backend is stopped until the session becomes active again.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::SeqCst)
+    }
+
+    /// What the recording indicator should show right now.
+    pub fn indicator_state(&self) -> IndicatorState {
+        if !self.session_running() {
+            IndicatorState::Stopped
+        } else if self.degraded.load(Ordering::Relaxed)
+            || self.is_paused()
+            || self.idle_millis() >= SILENT_AFTER.as_millis() as u64
+        {
+            IndicatorState::Silent
+        } else {
+            IndicatorState::Capturing
+        }
+    }
+
+    /// Recording, but no audio has arrived for a while.
+    pub fn is_degraded(&self) -> bool {
+        self.is_recording() && self.degraded.load(Ordering::Relaxed)
+    }
+
+    fn take_recovery_request(&self) -> Option<RecoveryReason> {
+        self.recovery_request.lock().ok().and_then(|mut r| r.take())
     }
 
     /// Set logical buffer length (samples to keep/return) for next start. Call before start.
+    /// While stopped the ring is resized to match, so a short buffer doesn't
+    /// hold memory for the longest one.
     pub fn set_buffer_seconds(&self, buffer_seconds: u32) {
         let len = (buffer_seconds as usize)
             .saturating_mul(OUTPUT_SAMPLE_RATE as usize)
-            .saturating_mul(OUTPUT_CHANNELS as usize)
-            .min(self.capacity)
-            .max(1);
+            .saturating_mul(OUTPUT_CHANNELS as usize);
+        if !self.is_recording() {
+            self.resize_ring(len);
+        }
+        self.set_logical_len(len);
+    }
+
+    /// Reallocate the ring to hold `len` samples, clamped to the maximum,
+    /// dropping what it held. Only while stopped.
+    fn resize_ring(&self, len: usize) {
+        let len = len.clamp(1, self.max_capacity);
+        let mut ring = match self.ring.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if ring.buf.len() == len {
+            return;
+        }
+        ring.buf.as_mut_slice().zeroize();
+        ring.buf = vec![0.0; len];
+        ring.write_index = 0;
+        ring.stamps = VecDeque::with_capacity(stamp_capacity(len));
+        ring.anchor = None;
+        self.capacity.store(len, Ordering::Release);
+        self.written_samples.store(0, Ordering::SeqCst);
+    }
+
+    /// Samples the ring holds.
+    fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Acquire)
+    }
+
+    /// How many seconds of audio the buffer keeps.
+    pub fn buffer_seconds(&self) -> Result<f64, String> {
+        let logical_len = *self.logical_len.lock().map_err(|e| e.to_string())?;
+        Ok(logical_len as f64 / (OUTPUT_SAMPLE_RATE as f64 * OUTPUT_CHANNELS as f64))
+    }
+
+    /// Set the logical buffer length in samples, clamped to the capacity.
+    fn set_logical_len(&self, len: usize) {
         if let Ok(mut l) = self.logical_len.lock() {
-            *l = len;
+            *l = len.clamp(1, self.capacity());
         }
     }
 
@@ -89,6 +497,120 @@ impl SystemAudioState {
         self.recording.load(Ordering::SeqCst)
     }
 
+    /// Whether capture is running and is still `session`.
+    pub fn is_session_running(&self, session: u64) -> bool {
+        self.is_recording() && self.session.load(Ordering::SeqCst) == session
+    }
+
+    pub fn set_encode_options(&self, options: EncodeOptions) -> Result<(), String> {
+        options.validate()?;
+        *self.encode_options.lock().map_err(|e| e.to_string())? = options;
+        Ok(())
+    }
+
+    /// Downmix strategy for converters created at capture start.
+    pub fn downmix(&self) -> Downmix {
+        self.encode_options()
+            .map(|options| options.downmix)
+            .unwrap_or_default()
+    }
+
+    pub fn encode_options(&self) -> Result<EncodeOptions, String> {
+        Ok(self.encode_options.lock().map_err(|e| e.to_string())?.clone())
+    }
+
+    pub fn set_dsp_config(&self, config: DspConfig) -> Result<(), String> {
+        config.validate()?;
+        *self.dsp_config.lock().map_err(|e| e.to_string())? = config;
+        Ok(())
+    }
+
+    pub fn dsp_config(&self) -> Result<DspConfig, String> {
+        Ok(self.dsp_config.lock().map_err(|e| e.to_string())?.clone())
+    }
+
+    /// Switch the capture backend. Only allowed while stopped, so the
+    /// backend that started a session is the one that stops it.
+    pub fn set_capture_backend(&self, config: CaptureBackendConfig) -> Result<(), String> {
+        config.validate()?;
+        let mut backend = self.backend.lock().map_err(|e| e.to_string())?;
+        if self.is_recording() {
+            return Err("Stop system audio before switching capture backends".to_string());
+        }
+        *backend = config.build();
+        Ok(())
+    }
+
+    pub fn capture_backend(&self) -> Result<Arc<dyn CaptureBackend>, String> {
+        Ok(self.backend.lock().map_err(|e| e.to_string())?.clone())
+    }
+
+    /// Store capture settings for the next backend start. Use
+    /// `reconfigure_system_audio` to apply them to a running capture.
+    pub fn set_capture_settings(&self, settings: CaptureSettings) -> Result<(), String> {
+        settings.validate()?;
+        *self.capture_settings.lock().map_err(|e| e.to_string())? = settings;
+        Ok(())
+    }
+
+    pub fn capture_settings(&self) -> Result<CaptureSettings, String> {
+        Ok(self
+            .capture_settings
+            .lock()
+            .map_err(|e| e.to_string())?
+            .clone())
+    }
+
+    /// Record the native stream format a backend is capturing in. Backends
+    /// call this at start and again whenever the device renegotiates.
+    pub fn set_capture_format(&self, format: Option<CaptureFormat>) {
+        if let Ok(mut current) = self.capture_format.lock() {
+            *current = format;
+        }
+    }
+
+    pub fn capture_format(&self) -> Option<CaptureFormat> {
+        self.capture_format.lock().ok().and_then(|f| f.clone())
+    }
+
+    /// Label the running session; exports carry it as their `TITLE`.
+    pub fn set_session_label(&self, label: Option<String>) {
+        if let Ok(mut current) = self.session_label.lock() {
+            *current = label;
+        }
+    }
+
+    pub fn session_label(&self) -> Option<String> {
+        self.session_label.lock().ok().and_then(|l| l.clone())
+    }
+
+    /// Keep ring buffer contents encrypted from the next start onward.
+    pub fn set_buffer_encryption(&self, enabled: bool) {
+        self.encrypt_buffer.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Whether the current session's buffer is encrypted in memory.
+    pub fn is_buffer_encrypted(&self) -> bool {
+        self.ring
+            .lock()
+            .map(|ring| ring.cipher.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Set or clear a `PAUSE_REASON_*` flag. Returns true if the flag changed.
+    pub fn set_paused(&self, reason: u32, paused: bool) -> bool {
+        let prev = if paused {
+            self.pause_reasons.fetch_or(reason, Ordering::SeqCst)
+        } else {
+            self.pause_reasons.fetch_and(!reason, Ordering::SeqCst)
+        };
+        (prev & reason != 0) != paused
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause_reasons.load(Ordering::Relaxed) != 0
+    }
+
     /// Store the capture thread handle so it can be joined on stop (macOS fallback).
     pub fn store_capture_handle(&self, handle: thread::JoinHandle<()>) {
         if let Ok(mut h) = self.capture_handle.lock() {
@@ -96,6 +618,12 @@ impl SystemAudioState {
         }
     }
 
+    /// Time a backend callback until the returned guard is dropped; shows
+    /// up as `perf.callback` in the status.
+    pub fn callback_timer(&self) -> TimingGuard<'_> {
+        self.perf.callback.start()
+    }
+
     /// Push 16 kHz mono samples from a real-time audio thread. Uses try_lock to avoid
     /// blocking the audio IO thread. Drops samples if the mutex is held
     /// (e.g. during get_recent_base64), which is acceptable for a background
@@ -104,263 +632,846 @@ impl SystemAudioState {
         if !self.recording.load(Ordering::Relaxed) {
             return;
         }
-        if samples.is_empty() {
+        // Paused capture still counts as a live backend for the watchdog.
+        self.last_delivery_ms.store(now_millis(), Ordering::Relaxed);
+        if samples.is_empty() || self.is_paused() {
             return;
         }
+        let threshold = f32::from_bits(self.idle_threshold.load(Ordering::Relaxed));
+        if samples.iter().any(|s| s.abs() >= threshold) {
+            self.last_activity_ms.store(now_millis(), Ordering::Relaxed);
+        }
+        let clipped = samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+        if clipped > 0 {
+            self.clipped_samples.fetch_add(clipped, Ordering::Relaxed);
+            self.last_clip_ms.store(now_millis(), Ordering::Relaxed);
+        }
         if let Ok(mut ring) = self.ring.try_lock() {
-            let (buf, idx) = &mut *ring;
-            let cap = self.capacity;
+            let RingBuffer {
+                buf,
+                write_index: idx,
+                cipher,
+                stamps,
+                anchor,
+                clock,
+            } = &mut *ring;
+            let cap = buf.len();
             if cap == 0 {
                 return;
             }
 
             // If the incoming chunk is larger than capacity, keep only the tail
-            // that fits in the ring buffer.
-            let src = if samples.len() > cap {
-                &samples[samples.len() - cap..]
-            } else {
-                samples
-            };
+            // that fits in the ring buffer. The skipped head still counts as
+            // written, so absolute positions stay in step with `write_index`.
+            let skipped = samples.len().saturating_sub(cap);
+            let src = &samples[skipped..];
 
-            let start = *idx;
+            let start = (*idx + skipped) % cap;
             let len = src.len();
+            let position = self.written_samples.load(Ordering::Relaxed) + skipped;
 
             if start + len <= cap {
                 buf[start..start + len].copy_from_slice(src);
+                if let Some(c) = cipher {
+                    c.apply(position, &mut buf[start..start + len]);
+                }
                 *idx = (start + len) % cap;
             } else {
                 let first_part = cap - start;
                 buf[start..cap].copy_from_slice(&src[..first_part]);
                 buf[..len - first_part].copy_from_slice(&src[first_part..]);
+                if let Some(c) = cipher {
+                    c.apply(position, &mut buf[start..cap]);
+                    c.apply(position + first_part, &mut buf[..len - first_part]);
+                }
                 *idx = len - first_part;
             }
 
-            self.written_samples.fetch_add(len, Ordering::Relaxed);
+            let first = position - skipped;
+            // The block ends now; back-date its first sample by its duration.
+            let stamp = BlockStamp {
+                position: first,
+                time_us: clock.now_us().saturating_sub(samples_to_micros(samples.len())),
+            };
+            let due = stamps.back().is_none_or(|last| {
+                let audio_us = samples_to_micros(first - last.position);
+                first >= last.position + STAMP_SPACING
+                    || stamp.time_us > last.time_us + audio_us + samples_to_micros(STAMP_SPACING)
+            });
+            if due {
+                anchor.get_or_insert(stamp);
+                // `stamp_capacity` leaves room, but never reallocate here.
+                if stamps.len() == stamps.capacity() {
+                    stamps.pop_front();
+                }
+                stamps.push_back(stamp);
+            }
+            // Keep the newest stamp at or before the oldest resident sample.
+            let oldest = (first + samples.len()).saturating_sub(cap);
+            while stamps.len() > 1 && stamps[1].position <= oldest {
+                stamps.pop_front();
+            }
+
+            self.written_samples.fetch_add(samples.len(), Ordering::Relaxed);
+        } else {
+            self.dropped_samples.fetch_add(samples.len(), Ordering::Relaxed);
+        }
+    }
+
+    /// Compare the session's sample count against the host clock. Returns
+    /// `None` until the session spans `MIN_DRIFT_WINDOW`.
+    pub fn clock_drift(&self) -> Result<Option<ClockDrift>, String> {
+        let ring = self.ring.lock().map_err(|e| e.to_string())?;
+        let (Some(first), Some(last)) = (ring.anchor, ring.stamps.back().copied()) else {
+            return Ok(None);
+        };
+        let wall_us = last.time_us.saturating_sub(first.time_us);
+        if wall_us < MIN_DRIFT_WINDOW.as_micros() as u64 {
+            return Ok(None);
         }
+        // Dropped samples were captured, just not stored; count them so
+        // lock contention doesn't read as a slow device clock.
+        let dropped = self.dropped_samples.load(Ordering::Relaxed);
+        let audio_us = samples_to_micros(last.position - first.position + dropped);
+        let offset_us = audio_us as f64 - wall_us as f64;
+        Ok(Some(ClockDrift {
+            ppm: offset_us / wall_us as f64 * 1e6,
+            offset_ms: offset_us / 1000.0,
+            measured_seconds: wall_us as f64 / 1e6,
+        }))
+    }
+
+    /// Host wall-clock time (ms since the Unix epoch) at which absolute
+    /// sample `position` of this session was captured, interpolated from
+    /// the nearest earlier block. `None` if no block covers it.
+    pub fn wall_time_ms_at(&self, position: usize) -> Option<u64> {
+        let ring = self.ring.lock().ok()?;
+        Some(time_at_position(&ring.stamps, position)? / 1000)
     }
 
     /// Snapshot the last N seconds (logical_len) from the ring buffer,
-    /// encode as Opus inside an OGG container,
+    /// run it through the DSP pipeline, encode as Opus inside an OGG container,
     /// and return the result as a base64 string.
     pub fn get_recent_base64(&self) -> Result<String, String> {
-        let logical_len = *self.logical_len.lock().map_err(|e| e.to_string())?;
-        let captured = self.written_samples.load(Ordering::Acquire);
-        let available_len = logical_len.min(captured.min(self.capacity));
+        Ok(base64::engine::general_purpose::STANDARD.encode(self.get_recent_ogg()?))
+    }
 
-        if available_len == 0 {
-            return Err("No audio recorded yet".to_string());
-        }
+    /// Encode the whole retained buffer as OGG/Opus bytes.
+    pub fn get_recent_ogg(&self) -> Result<Vec<u8>, String> {
+        Ok(self.get_recent_timed()?.ogg)
+    }
 
-        let ordered = {
-            // Lock, copy only the requested slice, and unlock immediately.
-            let ring = self.ring.lock().map_err(|e| e.to_string())?;
-            let (buf, write_index) = &*ring;
+    /// Encode the whole retained buffer as OGG/Opus bytes, along with the
+    /// wall-clock time span it covers.
+    pub fn get_recent_timed(&self) -> Result<TimedAudio, String> {
+        let mut audio = self.get_recent_formats(&[ExportFormat::OggOpus])?;
+        Ok(TimedAudio {
+            ogg: audio.encoded.remove(0),
+            start_time_ms: audio.start_time_ms,
+            end_time_ms: audio.end_time_ms,
+        })
+    }
 
-            if buf.is_empty() {
-                return Err("No audio recorded yet".to_string());
-            }
+    /// Like `get_recent_timed`, but only the last `seconds` of the buffer.
+    pub fn get_last_timed(&self, seconds: f64) -> Result<TimedAudio, String> {
+        let (samples, start) = self.snapshot_latest(self.window_len(Some(seconds))?)?;
+        let (samples, start) = self.trim_edge_silence(samples, start)?;
+        let end = start + samples.len();
+        Ok(TimedAudio {
+            start_time_ms: self.wall_time_ms_at(start),
+            end_time_ms: self.wall_time_ms_at(end),
+            ogg: self.encode_export(samples, start)?,
+        })
+    }
 
-            let cap = self.capacity;
-            let mut temp_ordered: Vec<f32> = Vec::with_capacity(available_len);
-            let start = (*write_index + cap - available_len) % cap;
+    /// The last `seconds` (the whole retained buffer if `None`) as it would
+    /// be exported, through the DSP pipeline but not encoded, for local
+    /// playback.
+    pub fn get_last_processed(&self, seconds: Option<f64>) -> Result<AudioBlock, String> {
+        let (samples, start) = self.snapshot_latest(self.window_len(seconds)?)?;
+        let (samples, _) = self.trim_edge_silence(samples, start)?;
+        Ok(self.dsp_config()?.build().process(samples))
+    }
 
-            if start + available_len <= cap {
-                temp_ordered.extend_from_slice(&buf[start..start + available_len]);
-            } else {
-                let first_part = cap - start;
-                temp_ordered.extend_from_slice(&buf[start..cap]);
-                temp_ordered.extend_from_slice(&buf[..available_len - first_part]);
-            }
-            temp_ordered
-        };
+    /// Encode the whole retained buffer once per entry of `formats`, all
+    /// from the same snapshot so every file covers the exact same window.
+    pub fn get_recent_formats(&self, formats: &[ExportFormat]) -> Result<TimedExports, String> {
+        self.get_recent_at_speed(formats, 1.0)
+    }
+
+    /// Like `get_recent_formats`, but the audio is time-compressed to play
+    /// `speed` times faster at the same pitch, for quick review. The
+    /// reported time span is still that of the original audio.
+    pub fn get_recent_at_speed(
+        &self,
+        formats: &[ExportFormat],
+        speed: f64,
+    ) -> Result<TimedExports, String> {
+        if formats.is_empty() {
+            return Err("No export formats requested".to_string());
+        }
+        if !(1.0..=MAX_EXPORT_SPEED).contains(&speed) {
+            return Err(format!(
+                "speed must be between 1 and {}, got {}",
+                MAX_EXPORT_SPEED, speed
+            ));
+        }
+        let logical_len = *self.logical_len.lock().map_err(|e| e.to_string())?;
+        let (ordered, start) = self.snapshot_latest(logical_len)?;
 
         if ordered.is_empty() {
             return Err("No audio recorded yet".to_string());
         }
 
-        // --- 2. Encode as Opus inside OGG ---
-        let mut encoder = opus::Encoder::new(
-            OUTPUT_SAMPLE_RATE,
-            opus::Channels::Mono,
-            opus::Application::Voip,
-        )
-        .map_err(|e| format!("Opus encoder init: {}", e))?;
+        let (ordered, start) = self.trim_edge_silence(ordered, start)?;
+        let end = start + ordered.len();
+        Ok(TimedExports {
+            start_time_ms: self.wall_time_ms_at(start),
+            end_time_ms: self.wall_time_ms_at(end),
+            encoded: self.encode_exports(ordered, start, formats, speed)?,
+        })
+    }
 
-        let frame_size: usize = (OUTPUT_SAMPLE_RATE as usize) * 20 / 1000; // 320 samples (20 ms)
-        let mut cursor = Cursor::new(Vec::<u8>::new());
+    /// Cut the leading and trailing silence off `samples` (starting at
+    /// absolute position `start`) if `trim_silence_dbfs` is configured, and
+    /// return what's left with its new start position.
+    fn trim_edge_silence(
+        &self,
+        mut samples: Vec<f32>,
+        start: usize,
+    ) -> Result<(Vec<f32>, usize), String> {
+        let Some(threshold) = self.dsp_config()?.trim_silence_dbfs else {
+            return Ok((samples, start));
+        };
+        let Some(range) = audible_range(&samples, OUTPUT_SAMPLE_RATE, threshold) else {
+            samples.zeroize();
+            return Err(format!(
+                "No audio above {} dBFS in the exported window",
+                threshold
+            ));
+        };
+        if range.len() == samples.len() {
+            return Ok((samples, start));
+        }
+        let trimmed = samples[range.clone()].to_vec();
+        samples.zeroize();
+        Ok((trimmed, start + range.start))
+    }
 
-        {
-            let mut pw = ogg::writing::PacketWriter::new(&mut cursor);
-            let serial: u32 = 0x504C5545; // "PLUE"
-
-            // -- OpusHead --
-            let pre_skip: u16 = 312;
-            let mut head = Vec::with_capacity(19);
-            head.extend_from_slice(b"OpusHead");
-            head.push(1); // version
-            head.push(OUTPUT_CHANNELS as u8);
-            head.extend_from_slice(&pre_skip.to_le_bytes());
-            head.extend_from_slice(&OUTPUT_SAMPLE_RATE.to_le_bytes());
-            head.extend_from_slice(&0u16.to_le_bytes()); // output gain
-            head.push(0); // channel mapping family
-            pw.write_packet(
-                head,
-                serial,
-                ogg::writing::PacketWriteEndInfo::EndPage,
-                0,
-            )
-            .map_err(|e| format!("OGG write OpusHead: {}", e))?;
-
-            // -- OpusTags --
-            let vendor = b"runningbord";
-            let mut tags = Vec::new();
-            tags.extend_from_slice(b"OpusTags");
-            tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
-            tags.extend_from_slice(vendor);
-            tags.extend_from_slice(&0u32.to_le_bytes()); // 0 comments
-            pw.write_packet(
-                tags,
-                serial,
-                ogg::writing::PacketWriteEndInfo::EndPage,
-                0,
-            )
-            .map_err(|e| format!("OGG write OpusTags: {}", e))?;
-
-            // -- Audio packets --
-            // Granule position is always at 48 kHz for Opus
-            let granule_increment: u64 = 960; // 20 ms at 48 kHz
-            let mut granule_pos: u64 = 0;
-            let total_frames = ordered.len() / frame_size;
-            let mut encode_buf = vec![0u8; 4000]; // max Opus packet
-
-            for i in 0..total_frames {
-                let frame = &ordered[i * frame_size..(i + 1) * frame_size];
-                let n = encoder
-                    .encode_float(frame, &mut encode_buf)
-                    .map_err(|e| format!("Opus encode: {}", e))?;
-                granule_pos += granule_increment;
-
-                let end_info = if i == total_frames - 1 {
-                    ogg::writing::PacketWriteEndInfo::EndStream
-                } else {
-                    ogg::writing::PacketWriteEndInfo::NormalPacket
-                };
-                pw.write_packet(
-                    encode_buf[..n].to_vec(),
-                    serial,
-                    end_info,
-                    granule_pos,
-                )
-                .map_err(|e| format!("OGG write audio: {}", e))?;
-            }
+    /// Run `samples` (starting at absolute position `start`) through the
+    /// configured DSP pipeline and encode the result as OGG/Opus tagged with
+    /// its capture context. Every intermediate copy is zeroized.
+    fn encode_export(&self, samples: Vec<f32>, start: usize) -> Result<Vec<u8>, String> {
+        let mut encoded = self.encode_exports(samples, start, &[ExportFormat::OggOpus], 1.0)?;
+        Ok(encoded.remove(0))
+    }
 
-            // Handle remaining samples (pad with silence to fill a frame)
-            let remainder = ordered.len() % frame_size;
-            if remainder > 0 {
-                let mut last_frame = vec![0.0f32; frame_size];
-                let offset = total_frames * frame_size;
-                last_frame[..remainder].copy_from_slice(&ordered[offset..offset + remainder]);
-                let n = encoder
-                    .encode_float(&last_frame, &mut encode_buf)
-                    .map_err(|e| format!("Opus encode tail: {}", e))?;
-                granule_pos += granule_increment;
-                pw.write_packet(
-                    encode_buf[..n].to_vec(),
-                    serial,
-                    ogg::writing::PacketWriteEndInfo::EndStream,
-                    granule_pos,
-                )
-                .map_err(|e| format!("OGG write tail: {}", e))?;
-            }
+    /// Like `encode_export`, but the processed audio is encoded once per
+    /// entry of `formats`, in order, after a time stretch to `speed` if it
+    /// isn't 1. The DSP pipeline runs only once.
+    fn encode_exports(
+        &self,
+        samples: Vec<f32>,
+        start: usize,
+        formats: &[ExportFormat],
+        speed: f64,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let _timer = self.perf.encode.start();
+        self.perf.add_encoded_audio(Duration::from_secs_f64(
+            samples.len() as f64 / OUTPUT_SAMPLE_RATE as f64,
+        ));
+        let mut comments = self.export_comments(start)?;
+        let mut pipeline = self.dsp_config()?.build();
+        if speed != 1.0 {
+            pipeline.push(Box::new(TimeStretch::new(speed)));
+            comments.push(("PLAYBACK_SPEED".to_string(), speed.to_string()));
         }
-
-        let bytes = cursor.into_inner();
-        Ok(base64::engine::general_purpose::STANDARD.encode(&bytes))
+        let mut block = pipeline.process(samples);
+        let encoded = if block.samples.is_empty() {
+            Err("No audio left after processing".to_string())
+        } else {
+            self.encode_options().and_then(|options| {
+                formats
+                    .iter()
+                    .map(|format| {
+                        format.encode(&block.samples, block.sample_rate, &options, &comments)
+                    })
+                    .collect()
+            })
+        };
+        block.samples.zeroize();
+        encoded
     }
-}
 
-/// Lightweight converter that downmixes native interleaved audio to mono and
-/// resamples to 16 kHz using linear interpolation with phase continuity.
-pub struct AudioConverter {
-    src_sample_rate: u32,
-    src_channels: u16,
-    prev_mono_sample: Option<f32>,
-    resample_pos: f64,
-}
+    /// OpusTags comments describing where an export starting at `start`
+    /// came from, so shared or archived files are self-describing.
+    fn export_comments(&self, start: usize) -> Result<Vec<(String, String)>, String> {
+        let logical_len = *self.logical_len.lock().map_err(|e| e.to_string())?;
+        let mut comments = Vec::new();
+        if let Some(start_ms) = self.wall_time_ms_at(start) {
+            comments.push(("DATE".to_string(), iso8601_utc(start_ms)));
+            comments.push(("CAPTURE_START_MS".to_string(), start_ms.to_string()));
+        }
+        if let Some(label) = self.session_label() {
+            comments.push(("TITLE".to_string(), label));
+        }
+        comments.push((
+            "APP_VERSION".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        ));
+        comments.push((
+            "CAPTURE_BACKEND".to_string(),
+            self.capture_backend()?.name().to_string(),
+        ));
+        comments.push((
+            "CAPTURE_DEVICE".to_string(),
+            self.capture_settings()?
+                .device
+                .unwrap_or_else(|| "default output".to_string()),
+        ));
+        if let Some(format) = self.capture_format() {
+            comments.push((
+                "CAPTURE_FORMAT".to_string(),
+                format!(
+                    "{} Hz, {} ch, {}",
+                    format.sample_rate, format.channels, format.sample_format
+                ),
+            ));
+        }
+        comments.push((
+            "BUFFER_SECONDS".to_string(),
+            (logical_len / OUTPUT_SAMPLE_RATE as usize).to_string(),
+        ));
+        Ok(comments)
+    }
 
-impl AudioConverter {
-    pub fn new(src_sample_rate: u32, src_channels: u16) -> Self {
-        Self {
-            src_sample_rate,
-            src_channels,
-            prev_mono_sample: None,
-            resample_pos: 0.0,
+    /// Estimate the size and duration of exporting the last `seconds` of
+    /// audio (the whole retained buffer if `None`) as `format`, without
+    /// touching the audio itself.
+    pub fn estimate_export(
+        &self,
+        seconds: Option<f64>,
+        format: ExportFormat,
+    ) -> Result<ExportEstimate, String> {
+        let written = self.written_samples.load(Ordering::Acquire);
+        let samples = self.window_len(seconds)?.min(written).min(self.capacity());
+        if samples == 0 {
+            return Err("No audio recorded yet".to_string());
         }
+        // The DSP pipeline's resampler changes what the encoder sees.
+        let sample_rate = self.dsp_config()?.output_sample_rate();
+        let encoded_samples =
+            (samples as u64 * sample_rate as u64 / OUTPUT_SAMPLE_RATE as u64) as usize;
+        let bytes = format.estimated_size(encoded_samples, sample_rate);
+        Ok(ExportEstimate {
+            format,
+            duration_ms: samples_to_micros(samples) / 1000,
+            estimated_bytes: bytes,
+            estimated_base64_bytes: bytes.div_ceil(3) * 4,
+        })
     }
 
-    pub fn reconfigure(&mut self, src_sample_rate: u32, src_channels: u16) {
-        self.src_sample_rate = src_sample_rate;
-        self.src_channels = src_channels;
-        self.prev_mono_sample = None;
-        self.resample_pos = 0.0;
+    /// Integrated loudness and true peak of the last `seconds` of raw
+    /// capture (the whole retained buffer if `None`), before any DSP.
+    pub fn measure_loudness(&self, seconds: Option<f64>) -> Result<LoudnessReport, String> {
+        let (mut samples, _) = self.snapshot_latest(self.window_len(seconds)?)?;
+        let loudness = measure_loudness(&samples, OUTPUT_SAMPLE_RATE);
+        let duration_ms = samples_to_micros(samples.len()) / 1000;
+        samples.zeroize();
+        Ok(LoudnessReport {
+            integrated_lufs: loudness.integrated_lufs,
+            true_peak_dbtp: loudness.true_peak_dbtp,
+            sample_peak_dbfs: loudness.sample_peak_dbfs,
+            duration_ms,
+        })
     }
 
-    pub fn update_source_channels_preserve_phase(&mut self, src_channels: u16) {
-        self.src_channels = src_channels;
+    /// Magnitude spectrum of the most recent ~1 s of raw capture, folded
+    /// into `bands` log-spaced bands.
+    pub fn spectrum(&self, bands: usize) -> Result<Spectrum, String> {
+        if !(MIN_SPECTRUM_BANDS..=MAX_SPECTRUM_BANDS).contains(&bands) {
+            return Err(format!(
+                "bands must be between {} and {}, got {}",
+                MIN_SPECTRUM_BANDS, MAX_SPECTRUM_BANDS, bands
+            ));
+        }
+        let (mut samples, _) = self.snapshot_latest(SPECTRUM_FFT_SIZE)?;
+        let result = spectrum(&samples, OUTPUT_SAMPLE_RATE, bands);
+        samples.zeroize();
+        Ok(result)
     }
 
-    pub fn source_sample_rate(&self) -> u32 {
-        self.src_sample_rate
+    /// Samples in the last `seconds`, capped at the configured buffer length.
+    fn window_len(&self, seconds: Option<f64>) -> Result<usize, String> {
+        let logical_len = *self.logical_len.lock().map_err(|e| e.to_string())?;
+        match seconds {
+            None => Ok(logical_len),
+            Some(seconds) if seconds.is_finite() && seconds > 0.0 => {
+                Ok(logical_len.min((seconds * OUTPUT_SAMPLE_RATE as f64) as usize))
+            }
+            Some(seconds) => Err(format!("seconds must be positive, got {}", seconds)),
+        }
     }
 
-    pub fn source_channels(&self) -> u16 {
-        self.src_channels
+    pub fn status(&self) -> Result<SystemAudioStatus, String> {
+        let logical_len: usize = *self.logical_len.lock().map_err(|e| e.to_string())?;
+        let buffer_seconds = (logical_len as u32) / (OUTPUT_SAMPLE_RATE * OUTPUT_CHANNELS as u32);
+        let backend = self.capture_backend()?.name();
+        Ok(SystemAudioStatus {
+            recording: self.is_recording(),
+            paused: self.is_paused(),
+            encrypted: self.is_buffer_encrypted(),
+            buffer_seconds,
+            dropped_samples: self.dropped_samples.load(Ordering::Relaxed),
+            clipped_samples: self.clipped_samples.load(Ordering::Relaxed),
+            last_clip_ms: Some(self.last_clip_ms.load(Ordering::Relaxed)).filter(|ms| *ms != 0),
+            clock_drift: self.clock_drift()?,
+            capture_format: self.capture_format(),
+            recoveries: self.recoveries.load(Ordering::Relaxed),
+            degraded: self.is_degraded(),
+            indicator: self.indicator_state(),
+            session_label: self.session_label(),
+            perf: self.perf.stats(),
+            buffer_capped_from: Some(self.buffer_capped_from.load(Ordering::Relaxed))
+                .filter(|s| *s != 0),
+            // Mock backends run anywhere.
+            supported: backend != "platform"
+                || cfg!(any(target_os = "macos", target_os = "linux", target_os = "windows")),
+            backend: backend.to_string(),
+        })
     }
 
-    pub fn convert_interleaved(&mut self, input: &[f32]) -> Vec<f32> {
-        if self.src_sample_rate == 0 || self.src_channels == 0 {
-            return Vec::new();
-        }
-        let src_channels = self.src_channels as usize;
+    /// Absolute write position (samples written this session).
+    pub fn written_position(&self) -> usize {
+        self.written_samples.load(Ordering::Acquire)
+    }
 
-        let frames = input.len() / src_channels;
-        if frames == 0 {
-            return Vec::new();
+    /// Copy everything written after absolute position `position`, clamped
+    /// to what is still resident. Returns the samples and the new position;
+    /// a position from an older session resyncs to the current one.
+    pub fn read_since(&self, position: usize) -> Result<(Vec<f32>, usize), String> {
+        let mut ring = self.ring.lock().map_err(|e| e.to_string())?;
+        let written = self.written_samples.load(Ordering::Acquire);
+        if position >= written {
+            return Ok((Vec::new(), written));
         }
+        let start = position.max(written.saturating_sub(self.capacity()));
+        Ok((self.copy_from_ring(&mut ring, start, written - start), written))
+    }
 
-        let mut mono = Vec::with_capacity(frames + 1);
-        if let Some(prev) = self.prev_mono_sample {
-            mono.push(prev);
+    /// Drop a bookmark at the current write position of the capture session.
+    pub fn add_marker(&self, label: Option<String>) -> Result<AudioMarker, String> {
+        if !self.is_recording() {
+            return Err("System audio is not recording".to_string());
         }
+        let mut markers = self.markers.lock().map_err(|e| e.to_string())?;
+        let marker = AudioMarker {
+            id: uuid::Uuid::new_v4().to_string(),
+            label,
+            sample_position: self.written_samples.load(Ordering::Acquire),
+            created_at_ms: now_millis(),
+        };
+        markers.push(marker.clone());
+        Ok(marker)
+    }
 
-        for frame in input.chunks_exact(src_channels) {
-            let sum: f32 = frame.iter().copied().sum();
-            mono.push(sum / src_channels as f32);
-        }
+    pub fn list_markers(&self) -> Result<Vec<AudioMarker>, String> {
+        Ok(self.markers.lock().map_err(|e| e.to_string())?.clone())
+    }
 
-        let last = match mono.last().copied() {
-            Some(v) => v,
-            None => return Vec::new(),
+    /// Encode exactly the span between two markers (in either order) as
+    /// base64 OGG/Opus. Fails if any part of the span has already been
+    /// overwritten by newer audio.
+    pub fn get_audio_between_markers_base64(&self, a: &str, b: &str) -> Result<String, String> {
+        let (pos_a, pos_b) = {
+            let markers = self.markers.lock().map_err(|e| e.to_string())?;
+            let find = |id: &str| {
+                markers
+                    .iter()
+                    .find(|m| m.id == id)
+                    .map(|m| m.sample_position)
+                    .ok_or_else(|| format!("Marker not found: {}", id))
+            };
+            (find(a)?, find(b)?)
         };
 
-        // Need at least two points for interpolation.
-        if mono.len() < 2 {
-            self.prev_mono_sample = Some(last);
-            return Vec::new();
+        let (start, end) = (pos_a.min(pos_b), pos_a.max(pos_b));
+        if start == end {
+            return Err("Markers point at the same position; no audio between them".to_string());
         }
 
-        let step = self.src_sample_rate as f64 / OUTPUT_SAMPLE_RATE as f64;
-        let mut out = Vec::new();
+        let samples = self.snapshot_range(start, end)?;
+        let (samples, start) = self.trim_edge_silence(samples, start)?;
+        let encoded = self.encode_export(samples, start)?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(&encoded))
+    }
 
-        while self.resample_pos + 1.0 < mono.len() as f64 {
-            let idx = self.resample_pos.floor() as usize;
-            let frac = (self.resample_pos - idx as f64) as f32;
+    /// Encode the audio captured between two wall-clock times (ms since the
+    /// Unix epoch) as base64 OGG/Opus. Times map to samples through the
+    /// per-block host stamps rather than the nominal sample rate, so the
+    /// device clock drifting against the host clock doesn't skew long
+    /// sessions. An end time in the future is clamped to now.
+    pub fn get_audio_between_times_base64(
+        &self,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<String, String> {
+        if start_ms >= end_ms {
+            return Err("start_ms must be before end_ms".to_string());
+        }
+        let (start, end) = {
+            let ring = self.ring.lock().map_err(|e| e.to_string())?;
+            let written = self.written_samples.load(Ordering::Acquire);
+            let start =
+                position_at_time(&ring.stamps, start_ms * 1000, written).ok_or_else(|| {
+                    "Requested time is before the oldest audio in the buffer".to_string()
+                })?;
+            let end = position_at_time(&ring.stamps, end_ms * 1000, written).unwrap_or(start);
+            (start, end)
+        };
+        if start >= end {
+            return Err("No audio recorded in the requested time range".to_string());
+        }
 
-            let s0 = mono[idx];
-            let s1 = mono[idx + 1];
-            out.push(s0 + (s1 - s0) * frac);
+        let samples = self.snapshot_range(start, end)?;
+        let (samples, start) = self.trim_edge_silence(samples, start)?;
+        let encoded = self.encode_export(samples, start)?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(&encoded))
+    }
 
-            self.resample_pos += step;
+    /// Raw samples covering as much of the wall-clock span `[start_ms,
+    /// end_ms)` as is still in the buffer, with the wall time of the first
+    /// one. For lining audio up with something recorded alongside it, so
+    /// unlike the exports nothing is trimmed or processed.
+    pub fn get_raw_between_times(
+        &self,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<TimedSamples, String> {
+        if start_ms >= end_ms {
+            return Err("start_ms must be before end_ms".to_string());
+        }
+        let mut ring = self.ring.lock().map_err(|e| e.to_string())?;
+        let written = self.written_samples.load(Ordering::Acquire);
+        let oldest = written.saturating_sub(self.capacity());
+        let first_stamped = ring
+            .stamps
+            .front()
+            .ok_or_else(|| "No audio recorded yet".to_string())?
+            .position;
+        let start = position_at_time(&ring.stamps, start_ms * 1000, written)
+            .unwrap_or(first_stamped)
+            .max(first_stamped)
+            .max(oldest);
+        let end = position_at_time(&ring.stamps, end_ms * 1000, written).unwrap_or(start);
+        if start >= end {
+            return Err("No audio recorded in the requested time range".to_string());
+        }
+        let start_time_ms = time_at_position(&ring.stamps, start)
+            .ok_or_else(|| "No audio recorded in the requested time range".to_string())?
+            / 1000;
+        Ok(TimedSamples {
+            samples: self.copy_from_ring(&mut ring, start, end - start),
+            start_time_ms,
+        })
+    }
+
+    /// Up to `length_seconds` (capped at `MAX_SCRUB_SECONDS`) of raw PCM
+    /// starting `offset_seconds` after the oldest retained sample, clamped
+    /// to the buffer. Offsets index the same window `get_recent_base64`
+    /// exports, so a scrubber can be drawn over it.
+    pub fn get_audio_at(
+        &self,
+        offset_seconds: f64,
+        length_seconds: f64,
+    ) -> Result<AudioWindow, String> {
+        if !offset_seconds.is_finite() || offset_seconds < 0.0 {
+            return Err(format!(
+                "offset_seconds must not be negative, got {}",
+                offset_seconds
+            ));
+        }
+        if !length_seconds.is_finite() || length_seconds <= 0.0 {
+            return Err(format!(
+                "length_seconds must be positive, got {}",
+                length_seconds
+            ));
+        }
+        let logical_len = *self.logical_len.lock().map_err(|e| e.to_string())?;
+        let mut ring = self.ring.lock().map_err(|e| e.to_string())?;
+        let written = self.written_samples.load(Ordering::Acquire);
+        let retained = logical_len.min(written).min(self.capacity());
+        if retained == 0 || ring.buf.is_empty() {
+            return Err("No audio recorded yet".to_string());
+        }
+        let to_samples = |seconds: f64| micros_to_samples((seconds * 1_000_000.0) as u64);
+        let start = to_samples(offset_seconds).min(retained);
+        let len = to_samples(length_seconds.min(MAX_SCRUB_SECONDS)).min(retained - start);
+        Ok(AudioWindow {
+            samples: self.copy_from_ring(&mut ring, written - retained + start, len),
+            sample_rate: OUTPUT_SAMPLE_RATE,
+            offset_seconds: samples_to_seconds(start),
+            buffer_seconds: samples_to_seconds(retained),
+        })
+    }
+
+    /// Copy up to `max_len` of the most recent samples out of the ring
+    /// buffer, oldest first, with the absolute position of the first one.
+    fn snapshot_latest(&self, max_len: usize) -> Result<(Vec<f32>, usize), String> {
+        // Lock, copy only the requested slice, and unlock immediately.
+        let mut ring = self.ring.lock().map_err(|e| e.to_string())?;
+
+        // Read under the ring lock so the writer can't advance in between.
+        let written = self.written_samples.load(Ordering::Acquire);
+        let available_len = max_len.min(written.min(self.capacity()));
+        if available_len == 0 || ring.buf.is_empty() {
+            return Err("No audio recorded yet".to_string());
+        }
+
+        let start = written - available_len;
+        Ok((self.copy_from_ring(&mut ring, start, available_len), start))
+    }
+
+    /// Copy the absolute sample range `[start, end)` of the current session
+    /// out of the ring buffer, oldest first.
+    fn snapshot_range(&self, start: usize, end: usize) -> Result<Vec<f32>, String> {
+        let mut ring = self.ring.lock().map_err(|e| e.to_string())?;
+
+        if ring.buf.is_empty() {
+            return Err("No audio recorded yet".to_string());
+        }
+
+        let written = self.written_samples.load(Ordering::Acquire);
+        if end > written {
+            return Err("Requested audio has not been recorded yet".to_string());
+        }
+        let oldest = written.saturating_sub(self.capacity());
+        if start < oldest {
+            let overwritten = (oldest - start).min(end - start);
+            return Err(format!(
+                "Requested audio is no longer in the buffer: {:.1}s of {:.1}s was overwritten",
+                samples_to_seconds(overwritten),
+                samples_to_seconds(end - start)
+            ));
+        }
+
+        Ok(self.copy_from_ring(&mut ring, start, end - start))
+    }
+
+    /// Copy `len` samples starting at absolute position `start`, decrypting
+    /// if needed. The caller must hold the ring lock and have checked the
+    /// range is still resident.
+    fn copy_from_ring(&self, ring: &mut RingBuffer, start: usize, len: usize) -> Vec<f32> {
+        let buf = &ring.buf;
+        let cap = buf.len();
+        let mut ordered: Vec<f32> = Vec::with_capacity(len);
+        let ring_start = start % cap;
+
+        if ring_start + len <= cap {
+            ordered.extend_from_slice(&buf[ring_start..ring_start + len]);
+        } else {
+            let first_part = cap - ring_start;
+            ordered.extend_from_slice(&buf[ring_start..cap]);
+            ordered.extend_from_slice(&buf[..len - first_part]);
+        }
+        if let Some(c) = &mut ring.cipher {
+            c.apply(start, &mut ordered);
+        }
+        ordered
+    }
+}
+
+/// RMS and peak level of a block of samples, in dBFS.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AudioLevel {
+    pub rms_dbfs: f32,
+    pub peak_dbfs: f32,
+}
+
+/// Floor reported for digital silence.
+const LEVEL_FLOOR_DBFS: f32 = -120.0;
+
+pub fn measure_level(samples: &[f32]) -> AudioLevel {
+    if samples.is_empty() {
+        return AudioLevel {
+            rms_dbfs: LEVEL_FLOOR_DBFS,
+            peak_dbfs: LEVEL_FLOOR_DBFS,
+        };
+    }
+    let mut sum_sq = 0.0f64;
+    let mut peak = 0.0f32;
+    for s in samples {
+        sum_sq += (*s as f64) * (*s as f64);
+        peak = peak.max(s.abs());
+    }
+    let rms = (sum_sq / samples.len() as f64).sqrt() as f32;
+    AudioLevel {
+        rms_dbfs: linear_to_dbfs(rms),
+        peak_dbfs: linear_to_dbfs(peak),
+    }
+}
+
+pub(crate) fn linear_to_dbfs(value: f32) -> f32 {
+    if value <= 0.0 {
+        LEVEL_FLOOR_DBFS
+    } else {
+        (20.0 * value.log10()).max(LEVEL_FLOOR_DBFS)
+    }
+}
+
+pub(crate) fn dbfs_to_linear(dbfs: f32) -> f32 {
+    10f32.powf(dbfs / 20.0)
+}
+
+fn samples_to_seconds(samples: usize) -> f64 {
+    samples as f64 / (OUTPUT_SAMPLE_RATE as f64 * OUTPUT_CHANNELS as f64)
+}
+
+/// Absolute sample position captured at `time_us`, found from the nearest
+/// earlier block stamp and clamped to the next block (or `written`).
+fn position_at_time(stamps: &VecDeque<BlockStamp>, time_us: u64, written: usize) -> Option<usize> {
+    let after = stamps.partition_point(|s| s.time_us <= time_us);
+    let stamp = stamps.get(after.checked_sub(1)?)?;
+    let limit = stamps.get(after).map_or(written, |next| next.position);
+    Some((stamp.position + micros_to_samples(time_us - stamp.time_us)).min(limit))
+}
+
+/// Host time (µs since the Unix epoch) at which absolute sample `position`
+/// was captured, from the nearest earlier block stamp.
+fn time_at_position(stamps: &VecDeque<BlockStamp>, position: usize) -> Option<u64> {
+    let after = stamps.partition_point(|s| s.position <= position);
+    let stamp = stamps.get(after.checked_sub(1)?)?;
+    Some(stamp.time_us + samples_to_micros(position - stamp.position))
+}
+
+/// Stamps a ring of `capacity` samples can need: one per `STAMP_SPACING`,
+/// plus the one before the oldest resident sample and a new one pushed
+/// before the old ones are pruned.
+fn stamp_capacity(capacity: usize) -> usize {
+    capacity / STAMP_SPACING + 3
+}
+
+fn samples_to_micros(samples: usize) -> u64 {
+    samples as u64 * 1_000_000 / (OUTPUT_SAMPLE_RATE as u64 * OUTPUT_CHANNELS as u64)
+}
+
+fn micros_to_samples(micros: u64) -> usize {
+    (micros as u128 * (OUTPUT_SAMPLE_RATE as u128 * OUTPUT_CHANNELS as u128) / 1_000_000) as usize
+}
+
+fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// How long `Downmix::MidSide` takes to crossfade between mid and side.
+const MID_SIDE_RAMP_SECS: f32 = 0.01;
+
+/// Lightweight converter that downmixes native interleaved audio to mono and
+/// resamples to 16 kHz using linear interpolation with phase continuity.
+pub struct AudioConverter {
+    src_sample_rate: u32,
+    src_channels: u16,
+    downmix: Downmix,
+    /// Per-channel gains for `Downmix::Average`, see `average_weights`.
+    weights: Vec<f32>,
+    /// `Downmix::MidSide` blend, 0 (all mid) to 1 (all side). Ramps toward
+    /// the louder of the two instead of jumping at block boundaries.
+    side_gain: f32,
+    prev_mono_sample: Option<f32>,
+    resample_pos: f64,
+}
+
+impl AudioConverter {
+    pub fn new(src_sample_rate: u32, src_channels: u16) -> Self {
+        Self {
+            src_sample_rate,
+            src_channels,
+            downmix: Downmix::default(),
+            weights: average_weights(src_channels),
+            side_gain: 0.0,
+            prev_mono_sample: None,
+            resample_pos: 0.0,
+        }
+    }
+
+    pub fn with_downmix(mut self, downmix: Downmix) -> Self {
+        self.downmix = downmix;
+        self
+    }
+
+    pub fn reconfigure(&mut self, src_sample_rate: u32, src_channels: u16) {
+        self.src_sample_rate = src_sample_rate;
+        self.src_channels = src_channels;
+        self.weights = average_weights(src_channels);
+        self.side_gain = 0.0;
+        self.prev_mono_sample = None;
+        self.resample_pos = 0.0;
+    }
+
+    pub fn update_source_channels_preserve_phase(&mut self, src_channels: u16) {
+        self.src_channels = src_channels;
+        self.weights = average_weights(src_channels);
+    }
+
+    pub fn source_sample_rate(&self) -> u32 {
+        self.src_sample_rate
+    }
+
+    pub fn source_channels(&self) -> u16 {
+        self.src_channels
+    }
+
+    pub fn convert_interleaved(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.src_sample_rate == 0 || self.src_channels == 0 {
+            return Vec::new();
+        }
+        let src_channels = self.src_channels as usize;
+
+        let frames = input.len() / src_channels;
+        if frames == 0 {
+            return Vec::new();
+        }
+
+        let mut mono = Vec::with_capacity(frames + 1);
+        if let Some(prev) = self.prev_mono_sample {
+            mono.push(prev);
+        }
+
+        self.downmix_into(input, &mut mono);
+
+        let last = match mono.last().copied() {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        // Need at least two points for interpolation.
+        if mono.len() < 2 {
+            self.prev_mono_sample = Some(last);
+            return Vec::new();
+        }
+
+        let step = self.src_sample_rate as f64 / OUTPUT_SAMPLE_RATE as f64;
+        let mut out = Vec::new();
+
+        while self.resample_pos + 1.0 < mono.len() as f64 {
+            let idx = self.resample_pos.floor() as usize;
+            let frac = (self.resample_pos - idx as f64) as f32;
+
+            let s0 = mono[idx];
+            let s1 = mono[idx + 1];
+            out.push(s0 + (s1 - s0) * frac);
+
+            self.resample_pos += step;
         }
 
         self.resample_pos -= (mono.len() - 1) as f64;
@@ -368,90 +1479,1071 @@ impl AudioConverter {
 
         out
     }
+
+    /// Fold interleaved frames to mono according to the downmix strategy.
+    fn downmix_into(&mut self, input: &[f32], mono: &mut Vec<f32>) {
+        let channels = self.src_channels as usize;
+        let frames = input.chunks_exact(channels);
+        // Mono sources have no right channel; fall back to the only one.
+        let right = 1.min(channels - 1);
+        match self.downmix {
+            Downmix::Left => mono.extend(frames.map(|f| f[0])),
+            Downmix::Right => mono.extend(frames.map(|f| f[right])),
+            Downmix::MidSide if channels >= 2 => {
+                let (mut mid_energy, mut side_energy) = (0.0f32, 0.0f32);
+                for f in frames.clone() {
+                    mid_energy += (f[0] + f[1]) * (f[0] + f[1]);
+                    side_energy += (f[0] - f[1]) * (f[0] - f[1]);
+                }
+                let target = if side_energy > mid_energy { 1.0 } else { 0.0 };
+                let step = 1.0 / (self.src_sample_rate as f32 * MID_SIDE_RAMP_SECS).max(1.0);
+                for f in frames {
+                    self.side_gain += (target - self.side_gain).clamp(-step, step);
+                    let (mid, side) = ((f[0] + f[1]) * 0.5, (f[0] - f[1]) * 0.5);
+                    mono.push(mid + (side - mid) * self.side_gain);
+                }
+            }
+            Downmix::Average | Downmix::MidSide => mono.extend(
+                frames.map(|f| f.iter().zip(&self.weights).map(|(s, w)| s * w).sum::<f32>()),
+            ),
+        }
+    }
+}
+
+/// Gains that fold `channels` interleaved channels to mono. Surround layouts
+/// are assumed to be in the standard WAVE order (L R C LFE, then surrounds),
+/// which is what CoreAudio, WASAPI and PipeWire deliver by default. 5.1 and
+/// 7.1 use the ITU-R BS.775 stereo fold (centre and surrounds at -3 dB, LFE
+/// dropped) averaged to mono; any other count is a plain average.
+fn average_weights(channels: u16) -> Vec<f32> {
+    const HALF: f32 = 0.5;
+    const CENTRE: f32 = std::f32::consts::FRAC_1_SQRT_2;
+    const SURROUND: f32 = std::f32::consts::FRAC_1_SQRT_2 * 0.5;
+    match channels {
+        6 => vec![HALF, HALF, CENTRE, 0.0, SURROUND, SURROUND],
+        8 => vec![
+            HALF, HALF, CENTRE, 0.0, SURROUND, SURROUND, SURROUND, SURROUND,
+        ],
+        n => vec![1.0 / n.max(1) as f32; n as usize],
+    }
+}
+
+/// Exported audio and the host wall-clock span it covers.
+pub struct TimedAudio {
+    pub ogg: Vec<u8>,
+    pub start_time_ms: Option<u64>,
+    pub end_time_ms: Option<u64>,
+}
+
+/// Raw mono samples at `OUTPUT_SAMPLE_RATE` and the host wall-clock time
+/// of the first.
+pub struct TimedSamples {
+    pub samples: Vec<f32>,
+    pub start_time_ms: u64,
+}
+
+/// The same window encoded in several formats, with its wall-clock span.
+pub struct TimedExports {
+    /// One file per requested format, in request order.
+    pub encoded: Vec<Vec<u8>>,
+    pub start_time_ms: Option<u64>,
+    pub end_time_ms: Option<u64>,
+}
+
+/// One file of a multi-format export, for the frontend.
+#[derive(Clone, Serialize)]
+pub struct FormatExport {
+    pub format: ExportFormat,
+    pub audio_base64: String,
+}
+
+/// Result of `system_audio_export_formats`.
+#[derive(Clone, Serialize)]
+pub struct MultiFormatExport {
+    pub exports: Vec<FormatExport>,
+    pub start_time_ms: Option<u64>,
+    pub end_time_ms: Option<u64>,
+}
+
+/// Base64 export with its wall-clock span, for the frontend.
+#[derive(Clone, Serialize)]
+pub struct RecentAudio {
+    pub audio_base64: String,
+    pub start_time_ms: Option<u64>,
+    pub end_time_ms: Option<u64>,
+}
+
+/// Raw PCM window for scrubbing, from `get_audio_at`.
+#[derive(Clone, Serialize)]
+pub struct AudioWindow {
+    /// Mono PCM at `sample_rate`, before any DSP.
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    /// Where the window starts, from the oldest retained sample.
+    pub offset_seconds: f64,
+    /// Length of the retained buffer the offset is measured in.
+    pub buffer_seconds: f64,
+}
+
+/// Expected cost of an export, from `system_audio_estimate_export`.
+#[derive(Clone, Serialize)]
+pub struct ExportEstimate {
+    pub format: ExportFormat,
+    pub duration_ms: u64,
+    pub estimated_bytes: usize,
+    /// Size of the base64 string the IPC bridge would carry.
+    pub estimated_base64_bytes: usize,
+}
+
+/// Loudness of the capture window, from `system_audio_measure_loudness`.
+#[derive(Clone, Serialize)]
+pub struct LoudnessReport {
+    /// BS.1770 integrated loudness; `None` for less than 400 ms of audio or
+    /// near-silence.
+    pub integrated_lufs: Option<f32>,
+    pub true_peak_dbtp: f32,
+    pub sample_peak_dbfs: f32,
+    pub duration_ms: u64,
+}
+
+/// One piece of a chunked export, sent over the command's channel in order.
+#[derive(Clone, Serialize)]
+pub struct ExportChunk {
+    pub index: usize,
+    pub total: usize,
+    pub data_base64: String,
+}
+
+/// Summary returned once every chunk of a chunked export has been sent.
+#[derive(Clone, Serialize)]
+pub struct ChunkedExport {
+    pub total_bytes: usize,
+    pub chunks: usize,
+    pub start_time_ms: Option<u64>,
+    pub end_time_ms: Option<u64>,
+}
+
+/// A bookmark in the capture session, positioned by absolute sample count.
+#[derive(Clone, Serialize)]
+pub struct AudioMarker {
+    pub id: String,
+    pub label: Option<String>,
+    pub sample_position: usize,
+    pub created_at_ms: u64,
 }
 
 #[derive(Clone, Serialize)]
 pub struct SystemAudioStatus {
     pub recording: bool,
+    pub paused: bool,
+    pub encrypted: bool,
     pub buffer_seconds: u32,
     pub supported: bool,
+    /// Name of the active capture backend (`platform`, `mock-sine`, ...).
+    pub backend: String,
+    pub dropped_samples: usize,
+    /// Samples at or near full scale this session; a steady count means the
+    /// output device is driving the capture into clipping.
+    pub clipped_samples: usize,
+    pub last_clip_ms: Option<u64>,
+    pub clock_drift: Option<ClockDrift>,
+    pub capture_format: Option<CaptureFormat>,
+    /// Times the watchdog rebuilt a dead capture this session.
+    pub recoveries: u32,
+    /// Recording, but the backend hasn't delivered audio for a while.
+    pub degraded: bool,
+    pub indicator: IndicatorState,
+    /// What the session is recording, e.g. the calendar event title.
+    pub session_label: Option<String>,
+    /// Callback and encode timings and CPU use, for diagnosing slow machines.
+    pub perf: PerfStats,
+    /// The buffer length asked for, when too little memory was free for it
+    /// and `buffer_seconds` was shortened.
+    pub buffer_capped_from: Option<u32>,
 }
 
-/// Start the system audio daemon. On non-macOS or if tap fails, returns error.
-#[tauri::command]
-pub async fn system_audio_start(
-    buffer_seconds: u32,
-    state: tauri::State<'_, Arc<SystemAudioState>>,
+/// Effective capture state for the recording indicator, as opposed to the
+/// bare `recording` flag. Emitted as `system-audio-indicator` on change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IndicatorState {
+    /// Audio above the idle threshold is arriving.
+    Capturing,
+    /// Recording, but nothing audible is arriving: quiet output, paused or
+    /// a stalled backend.
+    Silent,
+    Stopped,
+}
+
+/// Payload of `system-audio-health`, emitted when a running capture stalls
+/// or starts delivering audio again.
+#[derive(Clone, Serialize)]
+pub struct SystemAudioHealth {
+    pub degraded: bool,
+    /// Time since the backend last delivered audio.
+    pub stalled_ms: u64,
+}
+
+/// Why the capture watchdog rebuilt (or tried to rebuild) the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecoveryReason {
+    /// No audio was delivered for `STALL_TIMEOUT`.
+    Stalled,
+    /// The capture device disappeared.
+    DeviceLost,
+    /// The system audio service restarted underneath us.
+    ServiceRestarted,
+}
+
+/// Payload of `system-audio-recovered` and `system-audio-recovery-failed`.
+#[derive(Clone, Serialize)]
+pub struct SystemAudioRecovery {
+    pub reason: RecoveryReason,
+    /// How long the backend had been silent when recovery started.
+    pub stalled_ms: u64,
+    /// Attempts made for this outage, including this one.
+    pub attempt: u32,
+    pub error: Option<String>,
+}
+
+/// Native format of the captured stream, before conversion to 16 kHz mono.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Sample encoding as reported by the platform (`f32`, `i16`, ...).
+    pub sample_format: String,
+}
+
+/// Capture clock vs. host clock over the current session.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ClockDrift {
+    /// Positive when the capture device delivers samples faster than the
+    /// host clock advances.
+    pub ppm: f64,
+    /// Accumulated difference between audio time and wall time, in ms.
+    pub offset_ms: f64,
+    /// Wall-clock length of the measurement.
+    pub measured_seconds: f64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SystemAudioIdleStopped {
+    pub idle_seconds: u64,
+}
+
+/// Start the configured capture backend. Returns its error if it fails to start.
+///
+/// `buffer_seconds` is shortened if the system is short on memory; `None`
+/// picks a length from the machine's memory.
+pub async fn start_system_audio(
+    state: &Arc<SystemAudioState>,
+    buffer_seconds: Option<u32>,
 ) -> Result<(), String> {
     if state.recording.load(Ordering::SeqCst) {
         return Ok(());
     }
-    state.set_buffer_seconds(buffer_seconds);
-    state.reset_capture_state();
+    let choice =
+        choose_buffer_seconds(buffer_seconds, MAX_BUFFER_SECONDS, system_memory().as_ref());
+    if let Some(requested) = choice.capped_from {
+        tracing::warn!(
+            "Not enough free memory for a {} s buffer; using {} s",
+            requested,
+            choice.seconds
+        );
+    }
+    state
+        .buffer_capped_from
+        .store(choice.capped_from.unwrap_or(0), Ordering::Relaxed);
+    state.set_buffer_seconds(choice.seconds);
+    state.suspended.store(false, Ordering::SeqCst);
+    state.set_paused(PAUSE_REASON_SESSION_INACTIVE, false);
+    state.reset_capture_state()?;
+    state.session.fetch_add(1, Ordering::SeqCst);
+    let backend = state.capture_backend()?;
     // Set recording true before spawning capture so the thread sees it
     state.recording.store(true, Ordering::SeqCst);
-    #[cfg(target_os = "macos")]
-    {
-        if let Err(e) = crate::system_audio_macos::start_capture(state.inner().clone()).await {
-            state.recording.store(false, Ordering::SeqCst);
-            return Err(e);
-        }
+    if let Err(e) = backend.start(state.clone()).await {
+        state.recording.store(false, Ordering::SeqCst);
+        return Err(e);
     }
-    #[cfg(target_os = "linux")]
-    {
-        if let Err(e) = crate::system_audio_linux::start_capture(state.inner().clone()).await {
-            state.recording.store(false, Ordering::SeqCst);
-            return Err(e);
-        }
+    Ok(())
+}
+
+/// Stop the capture backend and join any capture thread.
+pub async fn stop_system_audio(state: &Arc<SystemAudioState>) {
+    state.recording.store(false, Ordering::SeqCst);
+    state.suspended.store(false, Ordering::SeqCst);
+    state.set_paused(PAUSE_REASON_SESSION_INACTIVE, false);
+    if let Ok(backend) = state.capture_backend() {
+        backend.stop().await;
     }
-    #[cfg(target_os = "windows")]
-    {
-        if let Err(e) = crate::system_audio_windows::start_capture(state.inner().clone()).await {
-            state.recording.store(false, Ordering::SeqCst);
-            return Err(e);
+    if let Ok(mut h) = state.capture_handle.lock() {
+        if let Some(handle) = h.take() {
+            let _ = handle.join();
         }
     }
-    #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
-    {
-        let _ = buffer_seconds;
+    state.zeroize_buffer();
+}
+
+/// Start capture on behalf of an automation (meeting or calendar
+/// auto-capture), watched like a session from `system_audio_start`. Returns
+/// the new session number, or `None` if capture was already running and
+/// belongs to someone else.
+pub async fn start_automatic_session(
+    app: tauri::AppHandle,
+    state: &Arc<SystemAudioState>,
+    buffer_seconds: u32,
+) -> Result<Option<u64>, String> {
+    if state.is_recording() {
+        return Ok(None);
+    }
+    start_system_audio(state, Some(buffer_seconds)).await?;
+    spawn_capture_watchdog(app, state.clone());
+    Ok(Some(state.session.load(Ordering::SeqCst)))
+}
+
+/// Stop capture if `session` from `start_automatic_session` is still the
+/// running one. Returns whether it was stopped.
+pub async fn stop_automatic_session(state: &Arc<SystemAudioState>, session: u64) -> bool {
+    if !state.is_session_running(session) {
+        return false;
+    }
+    stop_system_audio(state).await;
+    true
+}
+
+/// Apply new capture settings. While recording, the backend is stopped and
+/// started again with them; the ring buffer, its positions and the session
+/// are kept, so audio captured before the switch can still be exported.
+pub async fn reconfigure_system_audio(
+    state: &Arc<SystemAudioState>,
+    settings: CaptureSettings,
+) -> Result<(), String> {
+    state.set_capture_settings(settings)?;
+    if !state.is_recording() {
+        return Ok(());
+    }
+    if let Err(e) = restart_backend(state).await {
         state.recording.store(false, Ordering::SeqCst);
-        return Err("System audio capture is not supported on this platform".to_string());
+        return Err(e);
     }
+    tracing::info!("System audio capture reconfigured");
     Ok(())
 }
 
-/// Stop the system audio daemon.
-#[tauri::command]
-pub async fn system_audio_stop(state: tauri::State<'_, Arc<SystemAudioState>>) -> Result<(), String> {
+/// Stop and start the backend of a running session, keeping the ring
+/// buffer, its positions and the session. `recording` is left set even if
+/// the start fails; callers decide whether to give up.
+async fn restart_backend(state: &Arc<SystemAudioState>) -> Result<(), String> {
+    let backend = state.capture_backend()?;
+    // Capture threads and IO procs run while `recording` is set; clear it so
+    // they wind down, but skip `stop_system_audio`, which wipes the buffer.
+    state.restarting.store(true, Ordering::SeqCst);
     state.recording.store(false, Ordering::SeqCst);
-    #[cfg(target_os = "macos")]
-    {
-        crate::system_audio_macos::stop_capture().await;
-    }
-    #[cfg(target_os = "linux")]
-    {
-        crate::system_audio_linux::stop_capture().await;
+    backend.stop().await;
+    if let Ok(mut h) = state.capture_handle.lock() {
+        if let Some(handle) = h.take() {
+            let _ = handle.join();
+        }
     }
-    #[cfg(target_os = "windows")]
-    {
-        crate::system_audio_windows::stop_capture().await;
+    state.recording.store(true, Ordering::SeqCst);
+    state.restarting.store(false, Ordering::SeqCst);
+    state.last_delivery_ms.store(now_millis(), Ordering::SeqCst);
+    backend.start(state.clone()).await
+}
+
+/// Stop the backend of a running session while the user session is locked
+/// or switched away, keeping the buffer and the session. Returns false if
+/// nothing was recording.
+pub async fn suspend_capture(state: &Arc<SystemAudioState>) -> bool {
+    if !state.is_recording() {
+        return false;
     }
+    let Ok(backend) = state.capture_backend() else {
+        return false;
+    };
+    state.suspended.store(true, Ordering::SeqCst);
+    state.set_paused(PAUSE_REASON_SESSION_INACTIVE, true);
+    state.recording.store(false, Ordering::SeqCst);
+    backend.stop().await;
     if let Ok(mut h) = state.capture_handle.lock() {
         if let Some(handle) = h.take() {
             let _ = handle.join();
         }
     }
+    true
+}
+
+/// Start the backend stopped by `suspend_capture` again. Returns false if
+/// capture wasn't suspended. On error the session is left stopped.
+pub async fn resume_capture(state: &Arc<SystemAudioState>) -> Result<bool, String> {
+    if !state.suspended.swap(false, Ordering::SeqCst) {
+        return Ok(false);
+    }
+    state.set_paused(PAUSE_REASON_SESSION_INACTIVE, false);
+    let backend = state.capture_backend()?;
+    state.recording.store(true, Ordering::SeqCst);
+    state.last_delivery_ms.store(now_millis(), Ordering::SeqCst);
+    // The time away isn't silence the user left running.
+    state.last_activity_ms.store(now_millis(), Ordering::SeqCst);
+    if let Err(e) = backend.start(state.clone()).await {
+        state.recording.store(false, Ordering::SeqCst);
+        return Err(e);
+    }
+    Ok(true)
+}
+
+/// Self-test tone frequency; well inside the 16 kHz capture band.
+const TEST_TONE_HZ: f32 = 1000.0;
+const TEST_TONE_MS: u32 = 500;
+const TEST_TONE_RATE: u32 = 48000;
+/// Give up on detecting the tone after this long.
+const LATENCY_TIMEOUT: Duration = Duration::from_secs(3);
+/// Tone detection window (10 ms at 16 kHz).
+const DETECT_WINDOW: usize = (OUTPUT_SAMPLE_RATE as usize) / 100;
+/// Minimum tone amplitude in a detection window (~ -40 dBFS).
+const DETECT_MIN_AMPLITUDE: f32 = 0.01;
+
+/// Result of the capture latency self-test. All times are relative to the
+/// moment the tone was handed to the system player.
+#[derive(Clone, Serialize)]
+pub struct CaptureLatency {
+    /// Until the tone onset could be read from the ring buffer. Includes the
+    /// player's own startup time, so it is an upper bound.
+    pub playback_to_available_ms: u64,
+    /// Until the host time stamped on the tone onset's capture block; the
+    /// offset to apply when lining captured audio up with "now".
+    pub playback_to_capture_ms: Option<i64>,
+}
+
+/// 16-bit mono WAV with a faded sine burst.
+fn test_tone_wav() -> Vec<u8> {
+    let frames = (TEST_TONE_RATE * TEST_TONE_MS / 1000) as usize;
+    let fade = (TEST_TONE_RATE / 200) as usize; // 5 ms
+    let tone: Vec<f32> = (0..frames)
+        .map(|n| {
+            let t = n as f32 / TEST_TONE_RATE as f32;
+            let envelope = (n.min(frames - 1 - n) as f32 / fade as f32).min(1.0);
+            (t * TEST_TONE_HZ * std::f32::consts::TAU).sin() * 0.5 * envelope
+        })
+        .collect();
+    encode_wav(&tone, TEST_TONE_RATE)
+}
+
+/// `text` as a PowerShell single-quoted string literal. Quotes inside are
+/// doubled, including the typographic ones PowerShell also ends a string on.
+fn powershell_quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('\'');
+    for c in text.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Play a WAV file through the default output with the platform's stock
+/// command-line player.
+fn play_wav(path: &std::path::Path) -> Result<std::process::Child, String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("afplay");
+        c.arg(path);
+        c
+    } else if cfg!(target_os = "windows") {
+        let mut c = hidden_command::new("powershell");
+        c.args(["-NoProfile", "-Command"]).arg(format!(
+            "(New-Object Media.SoundPlayer {}).PlaySync()",
+            powershell_quote(&path.display().to_string())
+        ));
+        c
+    } else {
+        let mut c = Command::new("paplay");
+        c.arg(path);
+        c
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start audio player: {}", e))
+}
+
+/// Amplitude of the `TEST_TONE_HZ` component in `window` (Goertzel).
+fn tone_amplitude(window: &[f32]) -> f32 {
+    let coeff = 2.0 * (std::f32::consts::TAU * TEST_TONE_HZ / OUTPUT_SAMPLE_RATE as f32).cos();
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+    for x in window {
+        let s0 = x + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+    2.0 * power.max(0.0).sqrt() / window.len() as f32
+}
+
+/// Poll the ring buffer from `position` until a window dominated by the
+/// test tone shows up. Returns the tone's absolute onset position.
+async fn detect_tone_onset(
+    state: &SystemAudioState,
+    mut position: usize,
+) -> Result<Option<usize>, String> {
+    let mut pending: Vec<f32> = Vec::new();
+    let mut pending_start = position;
+    let deadline = tokio::time::Instant::now() + LATENCY_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let (samples, next) = state.read_since(position)?;
+        if next - samples.len() != pending_start + pending.len() {
+            // Fell behind the ring; restart from what is resident.
+            pending.clear();
+            pending_start = next - samples.len();
+        }
+        position = next;
+        pending.extend_from_slice(&samples);
+
+        let hit = pending.chunks_exact(DETECT_WINDOW).position(|w| {
+            let amplitude = tone_amplitude(w);
+            let rms = (w.iter().map(|s| s * s).sum::<f32>() / w.len() as f32).sqrt();
+            // The tone must carry at least half the window's power.
+            amplitude >= DETECT_MIN_AMPLITUDE && amplitude >= rms
+        });
+        if let Some(i) = hit {
+            return Ok(Some(pending_start + i * DETECT_WINDOW));
+        }
+        let consumed = pending.len() / DETECT_WINDOW * DETECT_WINDOW;
+        pending.drain(..consumed);
+        pending_start += consumed;
+    }
+    Ok(None)
+}
+
+/// Play the self-test tone and time how long it takes to show up in the
+/// ring buffer. Capture must be running and the output audible to the tap.
+pub async fn measure_capture_latency(
+    state: &Arc<SystemAudioState>,
+) -> Result<CaptureLatency, String> {
+    if !state.is_recording() {
+        return Err("System audio is not recording".to_string());
+    }
+    let path = std::env::temp_dir().join(format!("runningbord-tone-{}.wav", uuid::Uuid::new_v4()));
+    std::fs::File::create(&path)
+        .and_then(|mut f| f.write_all(&test_tone_wav()))
+        .map_err(|e| format!("Failed to write test tone: {}", e))?;
+
+    let position = state.written_position();
+    let started_ms = now_millis();
+    let result = match play_wav(&path) {
+        Ok(mut player) => {
+            let onset = detect_tone_onset(state, position).await;
+            let detected_ms = now_millis();
+            let _ = player.kill();
+            let _ = player.wait();
+            onset.and_then(|onset| {
+                let onset = onset.ok_or_else(|| {
+                    "Test tone was not captured; check the output device and volume".to_string()
+                })?;
+                Ok(CaptureLatency {
+                    playback_to_available_ms: detected_ms.saturating_sub(started_ms),
+                    playback_to_capture_ms: state
+                        .wall_time_ms_at(onset)
+                        .map(|ms| ms as i64 - started_ms as i64),
+                })
+            })
+        }
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Watch the running session and stop it once nothing above the idle
+/// threshold has been captured for `timeout`. Emits `system-audio-idle-stopped`.
+/// A suspended session captures nothing on purpose, so it isn't idle.
+fn spawn_idle_monitor(app: tauri::AppHandle, state: Arc<SystemAudioState>, timeout: Duration) {
+    let session = state.session.load(Ordering::SeqCst);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+            if !state.session_running() || state.session.load(Ordering::SeqCst) != session {
+                return;
+            }
+            if state.is_suspended() {
+                continue;
+            }
+            let idle_ms = state.idle_millis();
+            if idle_ms >= timeout.as_millis() as u64 {
+                tracing::info!("System audio idle for {} ms, stopping capture", idle_ms);
+                stop_system_audio(&state).await;
+                let _ = app.emit(
+                    "system-audio-idle-stopped",
+                    SystemAudioIdleStopped {
+                        idle_seconds: idle_ms / 1000,
+                    },
+                );
+                return;
+            }
+        }
+    });
+}
+
+/// Whether a delivery gap means the backend has stalled. WASAPI loopback
+/// delivers nothing while the output device is idle, so on Windows only the
+/// stream's own error callback triggers recovery.
+fn stalled(gap_ms: u64) -> bool {
+    cfg!(not(target_os = "windows")) && gap_ms >= STALL_TIMEOUT.as_millis() as u64
+}
+
+/// Watch the running session for a backend that stopped delivering audio.
+/// Every stall and every return to health is reported as
+/// `system-audio-health`, and the status shows `degraded` in between.
+/// Changes of the indicator state, including the final stop, are emitted
+/// as `system-audio-indicator`.
+///
+/// A native backend that stalled, or that reported its device gone, is also
+/// rebuilt with exponential backoff, emitting `system-audio-recovered` or
+/// `system-audio-recovery-failed` for every attempt. Mock backends end on
+/// their own and are left alone.
+fn spawn_capture_watchdog(app: tauri::AppHandle, state: Arc<SystemAudioState>) {
+    let session = state.session.load(Ordering::SeqCst);
+    tauri::async_runtime::spawn(async move {
+        let mut attempt: u32 = 0;
+        let mut backoff = STALL_TIMEOUT;
+        let mut retry_at = tokio::time::Instant::now();
+        let mut pending: Option<RecoveryReason> = None;
+        let mut indicator: Option<IndicatorState> = None;
+        loop {
+            tokio::time::sleep(WATCHDOG_INTERVAL).await;
+            if state.session.load(Ordering::SeqCst) != session {
+                // A newer session has its own watchdog.
+                return;
+            }
+            if !state.session_running() {
+                if indicator != Some(IndicatorState::Stopped) {
+                    let _ = app.emit("system-audio-indicator", IndicatorState::Stopped);
+                }
+                return;
+            }
+            let stalled_ms = state.delivery_gap_millis();
+            // Nothing is delivered on purpose while suspended.
+            let is_stalled = !state.is_suspended() && stalled(stalled_ms);
+            if state.set_degraded(is_stalled) {
+                if is_stalled {
+                    tracing::warn!(
+                        "System audio capture stalled ({} ms without audio)",
+                        stalled_ms
+                    );
+                } else {
+                    tracing::info!("System audio capture delivering again");
+                }
+                let _ = app.emit(
+                    "system-audio-health",
+                    SystemAudioHealth {
+                        degraded: is_stalled,
+                        stalled_ms,
+                    },
+                );
+            }
+            let current = state.indicator_state();
+            if indicator != Some(current) {
+                indicator = Some(current);
+                let _ = app.emit("system-audio-indicator", current);
+            }
+
+            let is_platform = matches!(state.capture_backend(), Ok(b) if b.name() == "platform");
+            if !is_platform || state.is_suspended() {
+                continue;
+            }
+            pending = pending
+                .or_else(|| state.take_recovery_request())
+                .or_else(|| is_stalled.then_some(RecoveryReason::Stalled));
+            let Some(reason) = pending else {
+                attempt = 0;
+                backoff = STALL_TIMEOUT;
+                continue;
+            };
+            if tokio::time::Instant::now() < retry_at {
+                continue;
+            }
+
+            attempt += 1;
+            tracing::warn!(
+                "System audio capture unhealthy ({:?}, {} ms without audio), rebuilding (attempt {})",
+                reason,
+                stalled_ms,
+                attempt
+            );
+            let result = restart_backend(&state).await;
+            let mut event = SystemAudioRecovery {
+                reason,
+                stalled_ms,
+                attempt,
+                error: None,
+            };
+            match result {
+                Ok(()) => {
+                    tracing::info!("System audio capture recovered");
+                    state.recoveries.fetch_add(1, Ordering::Relaxed);
+                    pending = None;
+                    let _ = app.emit("system-audio-recovered", event);
+                }
+                Err(e) => {
+                    tracing::error!("System audio recovery failed: {}", e);
+                    retry_at = tokio::time::Instant::now() + backoff;
+                    backoff = (backoff * 2).min(MAX_RECOVERY_BACKOFF);
+                    event.error = Some(e);
+                    let _ = app.emit("system-audio-recovery-failed", event);
+                }
+            }
+        }
+    });
+}
+
+/// Start the system audio daemon. On non-macOS or if tap fails, returns error.
+/// With `idle_timeout_minutes` set, capture stops automatically after that
+/// long without audio above `idle_threshold_dbfs` (default -60 dBFS).
+/// Without `buffer_seconds` the buffer length is picked from the machine's
+/// memory.
+#[tauri::command]
+pub async fn system_audio_start(
+    app: tauri::AppHandle,
+    buffer_seconds: Option<u32>,
+    idle_timeout_minutes: Option<u32>,
+    idle_threshold_dbfs: Option<f32>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<(), String> {
+    if state.is_recording() {
+        return Ok(());
+    }
+    state.set_idle_threshold_dbfs(idle_threshold_dbfs.unwrap_or(DEFAULT_IDLE_THRESHOLD_DBFS));
+    start_system_audio(state.inner(), buffer_seconds).await?;
+    spawn_capture_watchdog(app.clone(), state.inner().clone());
+    if let Some(minutes) = idle_timeout_minutes.filter(|m| *m > 0) {
+        spawn_idle_monitor(
+            app,
+            state.inner().clone(),
+            Duration::from_secs(minutes as u64 * 60),
+        );
+    }
+    Ok(())
+}
+
+/// Stop the system audio daemon.
+#[tauri::command]
+pub async fn system_audio_stop(state: tauri::State<'_, Arc<SystemAudioState>>) -> Result<(), String> {
+    stop_system_audio(state.inner()).await;
     Ok(())
 }
 
 /// Get the last N seconds of system audio as base64 OGG/Opus (16 kHz mono).
+/// When the app isn't capturing and a capture daemon is attached, the
+/// daemon's buffer is returned instead.
 #[tauri::command]
 pub async fn system_audio_get_recent_base64(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<SystemAudioState>>,
 ) -> Result<String, String> {
+    if !state.is_recording() {
+        if let Some(recent) = crate::capture_daemon::recent_base64(&app).await {
+            return recent;
+        }
+    }
     state.get_recent_base64()
 }
 
+/// Like `system_audio_get_recent_base64`, plus the wall-clock time (ms since
+/// the Unix epoch) of the first and last sample, for lining the audio up
+/// with screenshots and transcripts.
+#[tauri::command]
+pub async fn system_audio_get_recent_timed(
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<RecentAudio, String> {
+    let audio = state.get_recent_timed()?;
+    Ok(RecentAudio {
+        audio_base64: base64::engine::general_purpose::STANDARD.encode(&audio.ogg),
+        start_time_ms: audio.start_time_ms,
+        end_time_ms: audio.end_time_ms,
+    })
+}
+
+/// Like `system_audio_get_recent_timed`, but the OGG file is streamed over
+/// `on_chunk` in pieces of about `chunk_bytes` (192 KiB by default) instead
+/// of one large IPC message, so long buffers don't stall the webview. The
+/// chunks' base64 strings concatenate to the base64 of the whole file.
+#[tauri::command]
+pub async fn system_audio_export_chunked(
+    chunk_bytes: Option<usize>,
+    on_chunk: tauri::ipc::Channel<ExportChunk>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<ChunkedExport, String> {
+    let chunk_bytes = chunk_bytes.unwrap_or(DEFAULT_EXPORT_CHUNK_BYTES);
+    // Whole base64 quanta, so no chunk but the last carries padding.
+    let chunk_bytes = (chunk_bytes / 3 * 3).max(3);
+    let audio_state = state.inner().clone();
+    let audio = tauri::async_runtime::spawn_blocking(move || audio_state.get_recent_timed())
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let total = audio.ogg.len().div_ceil(chunk_bytes);
+    for (index, chunk) in audio.ogg.chunks(chunk_bytes).enumerate() {
+        on_chunk
+            .send(ExportChunk {
+                index,
+                total,
+                data_base64: base64::engine::general_purpose::STANDARD.encode(chunk),
+            })
+            .map_err(|e| format!("Failed to send export chunk: {}", e))?;
+        // Give the IPC bridge a turn between chunks.
+        tokio::task::yield_now().await;
+    }
+    Ok(ChunkedExport {
+        total_bytes: audio.ogg.len(),
+        chunks: total,
+        start_time_ms: audio.start_time_ms,
+        end_time_ms: audio.end_time_ms,
+    })
+}
+
+/// Encode the retained buffer in each of `formats` (e.g. OggOpus for
+/// transcription plus Wav for an archive) from a single snapshot, so the
+/// files cover the exact same window.
+#[tauri::command]
+pub async fn system_audio_export_formats(
+    formats: Vec<ExportFormat>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<MultiFormatExport, String> {
+    let audio_state = state.inner().clone();
+    let requested = formats.clone();
+    let audio =
+        tauri::async_runtime::spawn_blocking(move || audio_state.get_recent_formats(&requested))
+            .await
+            .map_err(|e| e.to_string())??;
+    Ok(MultiFormatExport {
+        exports: formats
+            .into_iter()
+            .zip(audio.encoded)
+            .map(|(format, bytes)| FormatExport {
+                format,
+                audio_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+            })
+            .collect(),
+        start_time_ms: audio.start_time_ms,
+        end_time_ms: audio.end_time_ms,
+    })
+}
+
+/// Export the retained buffer time-compressed to play `speed` times faster
+/// (e.g. 2 turns five minutes into two and a half) without changing pitch,
+/// for quick human review. Transcription keeps using the normal exports.
+#[tauri::command]
+pub async fn system_audio_export_time_compressed(
+    speed: f64,
+    format: Option<ExportFormat>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<RecentAudio, String> {
+    let audio_state = state.inner().clone();
+    let format = format.unwrap_or_default();
+    let mut audio = tauri::async_runtime::spawn_blocking(move || {
+        audio_state.get_recent_at_speed(&[format], speed)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(RecentAudio {
+        audio_base64: base64::engine::general_purpose::STANDARD.encode(audio.encoded.remove(0)),
+        start_time_ms: audio.start_time_ms,
+        end_time_ms: audio.end_time_ms,
+    })
+}
+
+/// Estimate the encoded size and duration of exporting the last `seconds`
+/// (default: the whole buffer) as `format` (default: OggOpus) without
+/// encoding anything, so the UI can warn about or avoid large exports.
+#[tauri::command]
+pub async fn system_audio_estimate_export(
+    seconds: Option<f64>,
+    format: Option<ExportFormat>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<ExportEstimate, String> {
+    state.estimate_export(seconds, format.unwrap_or_default())
+}
+
+/// Report integrated loudness (LUFS) and true peak (dBTP) of the last
+/// `seconds` of captured audio (default: the whole buffer), e.g. to tell a
+/// quiet source from a broken capture.
+#[tauri::command]
+pub async fn system_audio_measure_loudness(
+    seconds: Option<f64>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<LoudnessReport, String> {
+    let audio_state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || audio_state.measure_loudness(seconds))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Magnitude spectrum of the last second of captured audio in `bands`
+/// log-spaced bands (default 128), for a live spectrum analyzer.
+#[tauri::command]
+pub async fn system_audio_spectrum(
+    bands: Option<usize>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<Spectrum, String> {
+    state.spectrum(bands.unwrap_or(DEFAULT_SPECTRUM_BANDS))
+}
+
+/// Drop a bookmark at the current position of the running capture.
+#[tauri::command]
+pub async fn system_audio_add_marker(
+    label: Option<String>,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<AudioMarker, String> {
+    state.add_marker(label)
+}
+
+/// List bookmarks dropped in the current capture session.
+#[tauri::command]
+pub async fn system_audio_list_markers(
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<Vec<AudioMarker>, String> {
+    state.list_markers()
+}
+
+/// Get the audio between two bookmarks as base64 OGG/Opus (16 kHz mono).
+#[tauri::command]
+pub async fn get_audio_between_markers(
+    a: String,
+    b: String,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<String, String> {
+    state.get_audio_between_markers_base64(&a, &b)
+}
+
+/// Get the audio captured between two wall-clock times (ms since the Unix
+/// epoch) as base64 OGG/Opus.
+#[tauri::command]
+pub async fn get_audio_between_times(
+    start_ms: u64,
+    end_ms: u64,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<String, String> {
+    state.get_audio_between_times_base64(start_ms, end_ms)
+}
+
+/// A short window of raw PCM at `offset_seconds` into the retained buffer,
+/// for waveform scrubbing. See `SystemAudioState::get_audio_at`.
+#[tauri::command]
+pub async fn get_audio_at(
+    offset_seconds: f64,
+    length_seconds: f64,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<AudioWindow, String> {
+    state.get_audio_at(offset_seconds, length_seconds)
+}
+
+/// Keep the ring buffer encrypted in memory (decrypted only during export).
+/// Takes effect on the next `system_audio_start`.
+#[tauri::command]
+pub async fn system_audio_set_buffer_encryption(
+    enabled: bool,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<(), String> {
+    state.set_buffer_encryption(enabled);
+    Ok(())
+}
+
+/// Measure playback-to-capture latency with the self-test tone.
+#[tauri::command]
+pub async fn system_audio_measure_latency(
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<CaptureLatency, String> {
+    measure_capture_latency(state.inner()).await
+}
+
+#[derive(Clone, Serialize)]
+pub struct SystemAudioZeroizeReport {
+    pub zeroized: bool,
+    pub nonzero_samples: usize,
+}
+
+/// Verify that the ring buffer holds no captured audio (e.g. after stop).
+#[tauri::command]
+pub async fn system_audio_verify_zeroized(
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<SystemAudioZeroizeReport, String> {
+    let nonzero_samples = state.count_nonzero_samples()?;
+    Ok(SystemAudioZeroizeReport {
+        zeroized: nonzero_samples == 0,
+        nonzero_samples,
+    })
+}
+
+/// Update the Opus encoder settings used for exports.
+#[tauri::command]
+pub async fn system_audio_set_encode_options(
+    options: EncodeOptions,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<(), String> {
+    state.set_encode_options(options)
+}
+
+#[tauri::command]
+pub async fn system_audio_get_encode_options(
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<EncodeOptions, String> {
+    state.encode_options()
+}
+
+/// Switch the capture backend, e.g. to a simulated WAV source while working
+/// on a machine without native capture. Mock backends need a debug build.
+#[tauri::command]
+pub async fn system_audio_set_capture_backend(
+    config: CaptureBackendConfig,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<(), String> {
+    if !cfg!(debug_assertions) && config != CaptureBackendConfig::Platform {
+        return Err("Mock capture backends are only available in development builds".to_string());
+    }
+    state.set_capture_backend(config)
+}
+
+/// Update the processing stages applied before encoding.
+#[tauri::command]
+pub async fn system_audio_set_dsp_config(
+    config: DspConfig,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<(), String> {
+    state.set_dsp_config(config)
+}
+
+#[tauri::command]
+pub async fn system_audio_get_dsp_config(
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<DspConfig, String> {
+    state.dsp_config()
+}
+
+/// Change exclusions, mute behavior or the pinned output device. A running
+/// capture is rebuilt in place without losing buffered audio.
+#[tauri::command]
+pub async fn system_audio_reconfigure(
+    settings: CaptureSettings,
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<(), String> {
+    reconfigure_system_audio(&state, settings).await
+}
+
+#[tauri::command]
+pub async fn system_audio_get_capture_settings(
+    state: tauri::State<'_, Arc<SystemAudioState>>,
+) -> Result<CaptureSettings, String> {
+    state.capture_settings()
+}
+
 /// Return whether the daemon is currently recording.
 #[tauri::command]
 pub async fn system_audio_is_recording(
@@ -465,18 +2557,14 @@ pub async fn system_audio_is_recording(
 pub async fn system_audio_status(
     state: tauri::State<'_, Arc<SystemAudioState>>,
 ) -> Result<SystemAudioStatus, String> {
-    let logical_len: usize = *state.logical_len.lock().map_err(|e| e.to_string())?;
-    let buffer_seconds = (logical_len as u32) / (OUTPUT_SAMPLE_RATE * OUTPUT_CHANNELS as u32);
-    Ok(SystemAudioStatus {
-        recording: state.is_recording(),
-        buffer_seconds,
-        supported: cfg!(any(target_os = "macos", target_os = "linux", target_os = "windows")),
-    })
+    state.status()
 }
 
-/// Save base64-encoded OGG/Opus audio to a user-selected path using native Save dialog.
+/// Save base64-encoded OGG/Opus audio to a user-selected path using native Save dialog,
+/// and add it to the recordings history.
 #[tauri::command]
 pub async fn system_audio_save_ogg_base64(
+    app: tauri::AppHandle,
     base64_data: String,
     suggested_filename: Option<String>,
 ) -> Result<Option<String>, String> {
@@ -504,7 +2592,25 @@ pub async fn system_audio_save_ogg_base64(
         .decode(base64_data)
         .map_err(|e| format!("Invalid base64 audio payload: {}", e))?;
 
-    std::fs::write(&path, bytes).map_err(|e| format!("Failed to save audio file: {}", e))?;
+    std::fs::write(&path, &bytes).map_err(|e| format!("Failed to save audio file: {}", e))?;
+
+    let path = path.to_string_lossy().to_string();
+    let recording = crate::history::NewRecording {
+        path: path.clone(),
+        duration_seconds: crate::system_audio_encoder::encoded_duration_seconds(&bytes),
+        ..Default::default()
+    };
+    if let Err(e) = crate::history::record(&app, recording).await {
+        tracing::error!("Failed to add the saved recording to history: {}", e);
+    }
 
-    Ok(Some(path.to_string_lossy().to_string()))
+    Ok(Some(path))
 }
+
+#[cfg(feature = "bench")]
+pub mod bench;
+
+#[cfg(test)]
+mod ring_tests;
+#[cfg(test)]
+mod tests;
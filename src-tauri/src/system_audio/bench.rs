@@ -0,0 +1,67 @@
+//! Entry points for `benches/system_audio.rs`. Only built with the `bench`
+//! feature; not part of the app's API.
+
+use super::*;
+use crate::system_audio_encoder::encode_ogg_opus;
+
+/// A recording state whose ring buffer holds `seconds` of audio.
+pub struct Fixture {
+    state: SystemAudioState,
+}
+
+impl Fixture {
+    pub fn new(seconds: u32) -> Self {
+        let state = SystemAudioState::new();
+        state.set_buffer_seconds(seconds);
+        state.recording.store(true, Ordering::SeqCst);
+        let len = seconds as usize * OUTPUT_SAMPLE_RATE as usize;
+        let audio = speech_like(len);
+        for chunk in audio.chunks(320) {
+            state.push_samples_realtime(chunk);
+        }
+        Self { state }
+    }
+
+    /// Copy the retained audio out of the ring buffer.
+    pub fn snapshot(&self) -> Vec<f32> {
+        let logical_len = *self.state.logical_len.lock().unwrap();
+        self.state.snapshot_latest(logical_len).unwrap().0
+    }
+
+    /// Full export: snapshot, DSP pipeline and OGG/Opus encode.
+    pub fn export(&self) -> Vec<u8> {
+        self.state.get_recent_ogg().unwrap()
+    }
+}
+
+/// Downmix and resample interleaved audio to 16 kHz mono in 10 ms blocks,
+/// the way capture callbacks deliver it.
+pub fn downsample(input: &[f32], sample_rate: u32, channels: u16) -> usize {
+    let mut converter = AudioConverter::new(sample_rate, channels);
+    let block = (sample_rate / 100) as usize * channels as usize;
+    input
+        .chunks(block)
+        .map(|chunk| converter.convert_interleaved(chunk).len())
+        .sum()
+}
+
+/// Encode 16 kHz mono samples as OGG/Opus with default options.
+pub fn encode(samples: &[f32]) -> Vec<u8> {
+    encode_ogg_opus(samples, OUTPUT_SAMPLE_RATE, &EncodeOptions::default(), &[]).unwrap()
+}
+
+/// Deterministic test signal with speech-like energy: a few partials under
+/// a syllable-rate envelope, so the encoder doesn't hit its silence paths.
+pub fn speech_like(len: usize) -> Vec<f32> {
+    let rate = OUTPUT_SAMPLE_RATE as f32;
+    (0..len)
+        .map(|n| {
+            let t = n as f32 / rate;
+            let envelope = 0.5 + 0.5 * (t * 4.0 * std::f32::consts::TAU).sin();
+            let tone = (t * 180.0 * std::f32::consts::TAU).sin()
+                + 0.5 * (t * 720.0 * std::f32::consts::TAU).sin()
+                + 0.25 * (t * 2400.0 * std::f32::consts::TAU).sin();
+            0.2 * envelope * tone
+        })
+        .collect()
+}
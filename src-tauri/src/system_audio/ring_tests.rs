@@ -0,0 +1,123 @@
+//! Property tests for the ring buffer: arbitrary interleavings of pushes,
+//! logical-length changes and reads are checked against a plain `Vec`
+//! holding everything written, so wraparound can never reorder audio or
+//! leak stale samples into a read.
+
+use super::*;
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Push(usize),
+    SetLogicalLen(usize),
+    Snapshot,
+    /// `read_since` from this many samples behind the write position.
+    ReadSince(usize),
+    /// `snapshot_range` over `[written - back, written - back + len)`.
+    Range {
+        back: usize,
+        len: usize,
+    },
+}
+
+fn op(capacity: usize) -> impl Strategy<Value = Op> {
+    let max = capacity * 3;
+    prop_oneof![
+        4 => (0..=max).prop_map(Op::Push),
+        1 => (0..=max).prop_map(Op::SetLogicalLen),
+        2 => Just(Op::Snapshot),
+        2 => (0..=max).prop_map(Op::ReadSince),
+        2 => (0..=max, 0..=max).prop_map(|(back, len)| Op::Range { back, len }),
+    ]
+}
+
+fn scenario() -> impl Strategy<Value = (usize, Vec<Op>, bool)> {
+    (1usize..=256).prop_flat_map(|capacity| {
+        (
+            Just(capacity),
+            prop::collection::vec(op(capacity), 1..64),
+            any::<bool>(),
+        )
+    })
+}
+
+fn recording(capacity: usize, encrypted: bool) -> SystemAudioState {
+    let state = SystemAudioState::with_capacity(capacity);
+    state.set_buffer_encryption(encrypted);
+    state.reset_capture_state().unwrap();
+    state.recording.store(true, Ordering::SeqCst);
+    state
+}
+
+fn run(capacity: usize, ops: Vec<Op>, encrypted: bool) -> Result<(), TestCaseError> {
+    let state = recording(capacity, encrypted);
+    // Every sample ever written; values are unique and non-zero so a stale
+    // or zero-filled slot can't pass for real audio.
+    let mut written: Vec<f32> = Vec::new();
+    let mut logical_len = *state.logical_len.lock().unwrap();
+
+    for op in ops {
+        let w = written.len();
+        let resident = w.saturating_sub(capacity);
+        match op {
+            Op::Push(len) => {
+                let chunk: Vec<f32> = (w..w + len).map(|n| (n + 1) as f32).collect();
+                state.push_samples_realtime(&chunk);
+                written.extend_from_slice(&chunk);
+                prop_assert_eq!(state.written_position(), written.len());
+            }
+            Op::SetLogicalLen(len) => {
+                state.set_logical_len(len);
+                logical_len = len.clamp(1, capacity);
+            }
+            Op::Snapshot => {
+                let expected_len = logical_len.min(w).min(capacity);
+                match state.snapshot_latest(logical_len) {
+                    Ok((samples, start)) => {
+                        prop_assert!(expected_len > 0);
+                        prop_assert_eq!(start, w - expected_len);
+                        prop_assert_eq!(&samples[..], &written[w - expected_len..]);
+                    }
+                    Err(_) => prop_assert_eq!(expected_len, 0),
+                }
+            }
+            Op::ReadSince(back) => {
+                let position = w.saturating_sub(back);
+                let (samples, next) = state.read_since(position).unwrap();
+                prop_assert_eq!(next, w);
+                prop_assert_eq!(&samples[..], &written[position.max(resident)..]);
+            }
+            Op::Range { back, len } => {
+                let start = w.saturating_sub(back);
+                let end = start + len;
+                match state.snapshot_range(start, end) {
+                    Ok(samples) => {
+                        prop_assert!(end <= w && start >= resident);
+                        prop_assert_eq!(&samples[..], &written[start..end]);
+                    }
+                    Err(_) => prop_assert!(end > w || start < resident),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn ring_buffer_matches_model((capacity, ops, encrypted) in scenario()) {
+        run(capacity, ops, encrypted)?;
+    }
+}
+
+#[test]
+fn oversized_push_keeps_positions_aligned() {
+    let state = recording(8, false);
+    state.push_samples_realtime(&[1.0, 2.0, 3.0]);
+    let big: Vec<f32> = (4..=23).map(|n| n as f32).collect();
+    state.push_samples_realtime(&big);
+
+    assert_eq!(state.written_position(), 23);
+    let (tail, _) = state.read_since(15).unwrap();
+    assert_eq!(tail, (16..=23).map(|n| n as f32).collect::<Vec<_>>());
+}
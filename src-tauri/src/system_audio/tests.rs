@@ -0,0 +1,645 @@
+//! Golden tests for the export path: PCM fixtures go through the ring
+//! buffer and `get_recent_base64`, and the OGG/Opus output is decoded and
+//! checked against the input.
+
+use super::*;
+use std::io::Cursor;
+
+/// Opus pre-skip written into OpusHead, in 48 kHz samples: libopus's
+/// lookahead in VoIP mode (6.5 ms).
+const PRE_SKIP_48K: usize = 312;
+/// The same lookahead at 16 kHz; exports are padded by this much.
+const LOOKAHEAD_16K: usize = PRE_SKIP_48K / 3;
+/// 20 ms at 16 kHz.
+const FRAME_16K: usize = 320;
+
+/// A known PCM signal at 16 kHz mono.
+fn sine(frequency_hz: f32, amplitude: f32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|n| {
+            let t = n as f32 / OUTPUT_SAMPLE_RATE as f32;
+            (t * frequency_hz * std::f32::consts::TAU).sin() * amplitude
+        })
+        .collect()
+}
+
+/// A recording state holding exactly `samples`, pushed the way a capture
+/// backend would in 20 ms chunks.
+fn recorded(samples: &[f32]) -> SystemAudioState {
+    let state = SystemAudioState::new();
+    state.set_buffer_seconds(MAX_BUFFER_SECONDS);
+    state.recording.store(true, Ordering::SeqCst);
+    for chunk in samples.chunks(FRAME_16K) {
+        state.push_samples_realtime(chunk);
+    }
+    state
+}
+
+struct Decoded {
+    head: Vec<u8>,
+    tags: Vec<u8>,
+    /// Audio packets with the granule position of the page they end.
+    packets: Vec<(Vec<u8>, u64)>,
+    ended: bool,
+}
+
+fn read_ogg(base64_ogg: &str) -> Decoded {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(base64_ogg)
+        .expect("export is valid base64");
+    let mut reader = ogg::reading::PacketReader::new(Cursor::new(bytes));
+    let mut packets = Vec::new();
+    let mut ended = false;
+    while let Some(packet) = reader.read_packet().expect("export is valid OGG") {
+        ended = packet.last_in_stream();
+        let granule = packet.absgp_page();
+        packets.push((packet.data, granule));
+    }
+    assert!(packets.len() >= 2, "missing OpusHead/OpusTags");
+    let mut packets = packets.into_iter();
+    Decoded {
+        head: packets.next().unwrap().0,
+        tags: packets.next().unwrap().0,
+        packets: packets.collect(),
+        ended,
+    }
+}
+
+/// Decode every packet at `sample_rate` and drop the pre-skip.
+fn decode_pcm(decoded: &Decoded, sample_rate: u32) -> Vec<f32> {
+    let mut decoder = opus::Decoder::new(sample_rate, opus::Channels::Mono).unwrap();
+    let mut pcm = Vec::new();
+    let mut frame = vec![0.0f32; sample_rate as usize * 120 / 1000];
+    for (packet, _) in &decoded.packets {
+        let n = decoder.decode_float(packet, &mut frame, false).unwrap();
+        pcm.extend_from_slice(&frame[..n]);
+    }
+    let pre_skip = PRE_SKIP_48K * sample_rate as usize / 48000;
+    pcm.split_off(pre_skip.min(pcm.len()))
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt()
+}
+
+/// Highest normalized cross-correlation of `a` and `b` within `max_lag`
+/// samples, and the lag it occurs at.
+fn best_correlation(a: &[f32], b: &[f32], max_lag: usize) -> (f32, isize) {
+    let mut best = (f32::MIN, 0isize);
+    for lag in -(max_lag as isize)..=max_lag as isize {
+        let (x, y) = if lag >= 0 {
+            (a, &b[lag as usize..])
+        } else {
+            (&a[(-lag) as usize..], b)
+        };
+        let n = x.len().min(y.len());
+        let dot: f32 = x[..n].iter().zip(&y[..n]).map(|(p, q)| p * q).sum();
+        let norm = (rms(&x[..n]) * rms(&y[..n]) * n as f32).max(f32::EPSILON);
+        if dot / norm > best.0 {
+            best = (dot / norm, lag);
+        }
+    }
+    best
+}
+
+#[test]
+fn opus_head_and_tags_are_well_formed() {
+    let state = recorded(&sine(440.0, 0.25, OUTPUT_SAMPLE_RATE as usize));
+    let ogg = read_ogg(&state.get_recent_base64().unwrap());
+
+    let head = &ogg.head;
+    assert_eq!(head.len(), 19);
+    assert_eq!(&head[0..8], b"OpusHead");
+    assert_eq!(head[8], 1, "version");
+    assert_eq!(head[9], OUTPUT_CHANNELS as u8, "channels");
+    assert_eq!(
+        u16::from_le_bytes([head[10], head[11]]) as usize,
+        PRE_SKIP_48K
+    );
+    assert_eq!(
+        u32::from_le_bytes([head[12], head[13], head[14], head[15]]),
+        OUTPUT_SAMPLE_RATE
+    );
+    assert_eq!(i16::from_le_bytes([head[16], head[17]]), 0, "output gain");
+    assert_eq!(head[18], 0, "channel mapping family");
+
+    let tags = &ogg.tags;
+    assert_eq!(&tags[0..8], b"OpusTags");
+    let vendor_len = u32::from_le_bytes([tags[8], tags[9], tags[10], tags[11]]) as usize;
+    assert_eq!(&tags[12..12 + vendor_len], b"runningbord");
+    let comments = opus_comments(&tags[12 + vendor_len..]);
+    let value = |key: &str| {
+        comments
+            .iter()
+            .find_map(|c| c.strip_prefix(key)?.strip_prefix('='))
+            .unwrap_or_else(|| panic!("missing {} comment in {:?}", key, comments))
+    };
+    assert_eq!(value("APP_VERSION"), env!("CARGO_PKG_VERSION"));
+    assert_eq!(value("CAPTURE_BACKEND"), "platform");
+    assert_eq!(value("BUFFER_SECONDS"), MAX_BUFFER_SECONDS.to_string());
+    let start_ms: u64 = value("CAPTURE_START_MS").parse().unwrap();
+    assert!(start_ms > 0);
+    assert!(value("DATE").ends_with('Z'));
+}
+
+/// The user comment list of an OpusTags packet, after the vendor string.
+fn opus_comments(mut bytes: &[u8]) -> Vec<String> {
+    fn take_u32(bytes: &mut &[u8]) -> usize {
+        let (n, rest) = bytes.split_at(4);
+        *bytes = rest;
+        u32::from_le_bytes(n.try_into().unwrap()) as usize
+    }
+    let count = take_u32(&mut bytes);
+    (0..count)
+        .map(|_| {
+            let len = take_u32(&mut bytes);
+            let (comment, rest) = bytes.split_at(len);
+            bytes = rest;
+            String::from_utf8(comment.to_vec()).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn granule_positions_match_duration() {
+    // 2.5 s plus a partial frame, so the padded tail is exercised too.
+    let len = OUTPUT_SAMPLE_RATE as usize * 5 / 2 + 123;
+    let state = recorded(&sine(440.0, 0.25, len));
+    let ogg = read_ogg(&state.get_recent_base64().unwrap());
+
+    let frames = (len + LOOKAHEAD_16K).div_ceil(FRAME_16K);
+    assert_eq!(ogg.packets.len(), frames);
+    assert!(ogg.ended, "last packet must end the stream");
+    // The final granule marks the exact input length (plus pre-skip), not
+    // the padded frame; players trim the rest.
+    let (_, last_granule) = ogg.packets.last().unwrap();
+    assert_eq!(*last_granule, (PRE_SKIP_48K + len * 3) as u64);
+    assert!(*last_granule <= frames as u64 * 960);
+    let granules: Vec<u64> = ogg.packets.iter().map(|(_, g)| *g).collect();
+    assert!(
+        granules.windows(2).all(|w| w[0] <= w[1]),
+        "granules must not decrease"
+    );
+}
+
+#[test]
+fn decoded_audio_matches_input() {
+    let input = sine(440.0, 0.25, OUTPUT_SAMPLE_RATE as usize * 2);
+    let state = recorded(&input);
+    let ogg = read_ogg(&state.get_recent_base64().unwrap());
+    let pcm = decode_pcm(&ogg, OUTPUT_SAMPLE_RATE);
+
+    // Pre-skip removed and the encoder flushed: the whole input comes back.
+    assert!(pcm.len() >= input.len());
+    // Skip the encoder's warm-up before comparing.
+    let warm_up = OUTPUT_SAMPLE_RATE as usize / 10;
+    let n = input.len().min(pcm.len()) - warm_up;
+    let (expected, actual) = (&input[warm_up..warm_up + n], &pcm[warm_up..warm_up + n]);
+
+    let level_db = 20.0 * (rms(actual) / rms(expected)).log10();
+    assert!(level_db.abs() < 2.0, "level off by {:.2} dB", level_db);
+    let (correlation, lag) = best_correlation(expected, actual, 16);
+    assert!(correlation > 0.9, "correlation {:.3}", correlation);
+    assert!(lag.abs() <= 4, "decoded audio shifted by {} samples", lag);
+}
+
+#[test]
+fn skipped_silence_keeps_timing() {
+    let len = OUTPUT_SAMPLE_RATE as usize * 2;
+    let state = recorded(&vec![0.0; len]);
+    state
+        .set_encode_options(EncodeOptions {
+            skip_silence: true,
+            ..Default::default()
+        })
+        .unwrap();
+    let ogg = read_ogg(&state.get_recent_base64().unwrap());
+
+    let frames = (len + LOOKAHEAD_16K).div_ceil(FRAME_16K);
+    assert_eq!(ogg.packets.len(), frames);
+    assert_eq!(
+        ogg.packets.last().unwrap().1,
+        (PRE_SKIP_48K + len * 3) as u64
+    );
+    let empty = ogg.packets.iter().filter(|(p, _)| p.len() == 1).count();
+    assert!(empty > frames / 2, "only {} empty packets", empty);
+    let pcm = decode_pcm(&ogg, OUTPUT_SAMPLE_RATE);
+    assert!(rms(&pcm) < 1e-3);
+}
+
+#[test]
+fn resampled_export_reports_new_rate() {
+    let len = OUTPUT_SAMPLE_RATE as usize;
+    let state = recorded(&sine(440.0, 0.25, len));
+    state
+        .set_dsp_config(DspConfig {
+            resample_hz: Some(48000),
+            ..Default::default()
+        })
+        .unwrap();
+    let ogg = read_ogg(&state.get_recent_base64().unwrap());
+
+    let head = &ogg.head;
+    assert_eq!(
+        u32::from_le_bytes([head[12], head[13], head[14], head[15]]),
+        48000
+    );
+    // Same duration, so the final granule is one second plus pre-skip.
+    let last_granule = ogg.packets.last().unwrap().1;
+    assert_eq!(last_granule, (PRE_SKIP_48K + 48000) as u64);
+    let pcm = decode_pcm(&ogg, 48000);
+    let level_db = 20.0 * (rms(&pcm[4800..]) / rms(&sine(440.0, 0.25, len))).log10();
+    assert!(level_db.abs() < 2.0, "level off by {:.2} dB", level_db);
+}
+
+#[test]
+fn downsampling_filters_out_aliases() {
+    let len = OUTPUT_SAMPLE_RATE as usize;
+    let config = DspConfig {
+        resample_hz: Some(8000),
+        ..Default::default()
+    };
+    let level_db = |frequency_hz: f32| {
+        let out = config.build().process(sine(frequency_hz, 0.5, len)).samples;
+        20.0 * (rms(&out[800..]) / rms(&sine(frequency_hz, 0.5, len))).log10()
+    };
+    // 6 kHz is above the new 4 kHz Nyquist frequency and would fold back to
+    // 2 kHz; 1 kHz is well inside the pass band.
+    let aliased = level_db(6000.0);
+    assert!(aliased < -40.0, "6 kHz comes through at {:.1} dB", aliased);
+    let kept = level_db(1000.0);
+    assert!(kept.abs() < 0.5, "1 kHz comes through at {:.1} dB", kept);
+}
+
+#[test]
+fn multi_format_export_shares_one_window() {
+    let len = OUTPUT_SAMPLE_RATE as usize + 77;
+    let state = recorded(&sine(440.0, 0.25, len));
+    let audio = state
+        .get_recent_formats(&[ExportFormat::OggOpus, ExportFormat::Wav])
+        .unwrap();
+    assert_eq!(audio.encoded.len(), 2);
+
+    let wav = &audio.encoded[1];
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(wav.len(), 44 + len * 2);
+    let ogg = read_ogg(&base64::engine::general_purpose::STANDARD.encode(&audio.encoded[0]));
+    assert_eq!(
+        ogg.packets.last().unwrap().1,
+        (PRE_SKIP_48K + len * 3) as u64
+    );
+}
+
+#[test]
+fn export_durations_are_read_back() {
+    use crate::system_audio_encoder::encoded_duration_seconds;
+    let len = OUTPUT_SAMPLE_RATE as usize * 3 / 2;
+    let state = recorded(&sine(440.0, 0.25, len));
+    let audio = state
+        .get_recent_formats(&[ExportFormat::OggOpus, ExportFormat::Wav])
+        .unwrap();
+    for encoded in &audio.encoded {
+        let seconds = encoded_duration_seconds(encoded).unwrap();
+        assert!((seconds - 1.5).abs() < 1e-6, "{} s", seconds);
+    }
+    assert_eq!(encoded_duration_seconds(b"not audio"), None);
+}
+
+#[test]
+fn edge_silence_is_trimmed_from_exports() {
+    let (lead, tone, tail) = (16_000, 8_000, 24_000);
+    let mut input = vec![0.0; lead];
+    input.extend(sine(440.0, 0.25, tone));
+    input.extend(vec![0.0; tail]);
+    let state = recorded(&input);
+    state
+        .set_dsp_config(DspConfig {
+            trim_silence_dbfs: Some(-50.0),
+            ..Default::default()
+        })
+        .unwrap();
+    let audio = state.get_recent_formats(&[ExportFormat::Wav]).unwrap();
+
+    // The tone plus 50 ms of margin either side.
+    let margin = OUTPUT_SAMPLE_RATE as usize / 20;
+    assert_eq!(audio.encoded[0].len(), 44 + (tone + 2 * margin) * 2);
+
+    // Nothing above the threshold: nothing to export.
+    let silent = recorded(&vec![0.0; 3200]);
+    silent
+        .set_dsp_config(DspConfig {
+            trim_silence_dbfs: Some(-50.0),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(silent.get_recent_formats(&[ExportFormat::Wav]).is_err());
+}
+
+#[test]
+fn time_compressed_export_keeps_pitch() {
+    let len = OUTPUT_SAMPLE_RATE as usize * 2;
+    let state = recorded(&sine(440.0, 0.25, len));
+    let audio = state
+        .get_recent_at_speed(&[ExportFormat::Wav], 2.0)
+        .unwrap();
+    let pcm: Vec<f32> = audio.encoded[0][44..]
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32)
+        .collect();
+
+    // Half as long, less the ~40 ms the stretch holds back at the end.
+    assert!(pcm.len() <= len / 2 && pcm.len() > len / 2 - 800);
+    // Still 440 Hz: 880 zero crossings per second of output.
+    let crossings = pcm
+        .windows(2)
+        .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+        .count();
+    let per_second = crossings as f32 * OUTPUT_SAMPLE_RATE as f32 / pcm.len() as f32;
+    assert!(
+        (per_second - 880.0).abs() < 20.0,
+        "{} crossings/s",
+        per_second
+    );
+    assert!(state
+        .get_recent_at_speed(&[ExportFormat::Wav], 8.0)
+        .is_err());
+}
+
+#[test]
+fn loudness_of_reference_tone() {
+    // A 1 kHz sine at -20 dBFS peak reads about -23 LUFS.
+    let state = recorded(&sine(1000.0, 0.1, OUTPUT_SAMPLE_RATE as usize * 3));
+    let report = state.measure_loudness(None).unwrap();
+    let lufs = report.integrated_lufs.unwrap();
+    assert!((lufs + 23.0).abs() < 0.2, "{} LUFS", lufs);
+    assert!((report.true_peak_dbtp + 20.0).abs() < 0.2);
+    assert_eq!(report.duration_ms, 3000);
+
+    // Too short to fill one 400 ms gating block.
+    let short = state.measure_loudness(Some(0.2)).unwrap();
+    assert!(short.integrated_lufs.is_none());
+}
+
+#[test]
+fn exports_are_timed() {
+    let state = recorded(&sine(440.0, 0.5, 2 * OUTPUT_SAMPLE_RATE as usize));
+    state.get_recent_base64().unwrap();
+    let perf = state.status().unwrap().perf;
+    assert_eq!(perf.encode.count, 1);
+    assert!(perf.encode.max_us > 0);
+    assert!(perf.encode_realtime_factor.is_some_and(|f| f > 0.0));
+    assert_eq!(perf.callback.count, 0);
+}
+
+#[test]
+fn clipping_is_counted_and_limited() {
+    let len = OUTPUT_SAMPLE_RATE as usize;
+    let state = recorded(&sine(440.0, 1.5, len));
+    let clipped = state.status().unwrap().clipped_samples;
+    assert!(clipped > len / 2, "only {} clipped samples", clipped);
+    assert!(state.status().unwrap().last_clip_ms.is_some());
+
+    state
+        .set_dsp_config(DspConfig {
+            limiter: true,
+            ..Default::default()
+        })
+        .unwrap();
+    let audio = state.get_recent_formats(&[ExportFormat::Wav]).unwrap();
+    let peak = audio.encoded[0][44..]
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]).unsigned_abs())
+        .max()
+        .unwrap();
+    // -1 dBFS ceiling.
+    assert!(peak as f32 <= i16::MAX as f32 * 0.892, "peak {}", peak);
+}
+
+#[test]
+fn spectrum_peaks_at_tone_frequency() {
+    let state = recorded(&sine(1000.0, 0.5, OUTPUT_SAMPLE_RATE as usize * 2));
+    let spectrum = state.spectrum(64).unwrap();
+    assert_eq!(spectrum.magnitudes_db.len(), 64);
+
+    let (band, peak) = spectrum
+        .magnitudes_db
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap();
+    let centre = spectrum.frequencies_hz[band];
+    assert!((900.0..1100.0).contains(&centre), "peak at {} Hz", centre);
+    // -6 dBFS sine, within Hann scalloping.
+    assert!((peak + 6.0).abs() < 1.5, "peak {} dB", peak);
+    assert!(state.spectrum(4).is_err());
+}
+
+#[test]
+fn crash_snapshot_holds_the_last_seconds() {
+    let input = sine(440.0, 0.5, OUTPUT_SAMPLE_RATE as usize * 3);
+    let state = recorded(&input);
+    let wav = state.crash_snapshot_wav(1).unwrap();
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(wav.len(), 44 + OUTPUT_SAMPLE_RATE as usize * 2);
+    // Asking for more than was recorded returns what there is.
+    let wav = state.crash_snapshot_wav(60).unwrap();
+    assert_eq!(wav.len(), 44 + input.len() * 2);
+    assert!(SystemAudioState::new().crash_snapshot_wav(60).is_none());
+}
+
+#[test]
+fn crash_snapshot_gives_up_on_a_held_lock() {
+    let state = recorded(&sine(440.0, 0.5, OUTPUT_SAMPLE_RATE as usize));
+    let _ring = state.ring.lock().unwrap();
+    assert!(state.crash_snapshot_wav(1).is_none());
+}
+
+#[test]
+fn suspended_capture_keeps_its_buffer() {
+    tauri::async_runtime::block_on(async {
+        let state = Arc::new(SystemAudioState::new());
+        state
+            .set_capture_backend(CaptureBackendConfig::Sine {
+                frequency_hz: 440.0,
+                amplitude: 0.5,
+            })
+            .unwrap();
+        start_system_audio(&state, Some(10)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(suspend_capture(&state).await);
+        assert!(state.is_suspended() && state.is_paused() && !state.is_recording());
+        assert_eq!(state.indicator_state(), IndicatorState::Silent);
+        let written = state.written_samples.load(Ordering::SeqCst);
+        assert!(written > 0);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(state.written_samples.load(Ordering::SeqCst), written);
+
+        // Time spent suspended doesn't count towards the idle timeout.
+        state.last_activity_ms.store(0, Ordering::SeqCst);
+        assert!(resume_capture(&state).await.unwrap());
+        assert!(state.is_recording() && !state.is_paused());
+        assert!(state.idle_millis() < 1000);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(state.written_samples.load(Ordering::SeqCst) > written);
+        assert!(!resume_capture(&state).await.unwrap());
+
+        stop_system_audio(&state).await;
+        assert!(!suspend_capture(&state).await);
+    });
+}
+
+#[test]
+fn mock_file_capture_round_trips() {
+    // Half a second of a WAV fixture, played once by the file backend in
+    // real time, comes back out of the ring buffer and the export.
+    let input = sine(440.0, 0.25, OUTPUT_SAMPLE_RATE as usize / 2);
+    let path = std::env::temp_dir().join(format!(
+        "runningbord-mock-capture-{}.wav",
+        std::process::id()
+    ));
+    std::fs::write(&path, encode_wav(&input, OUTPUT_SAMPLE_RATE)).unwrap();
+    tauri::async_runtime::block_on(async {
+        let state = Arc::new(SystemAudioState::new());
+        state
+            .set_capture_backend(CaptureBackendConfig::File { path: path.clone() })
+            .unwrap();
+        start_system_audio(&state, Some(10)).await.unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while state.written_position() + FRAME_16K < input.len() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        // Let the last chunk land.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (captured, written) = state.read_since(0).unwrap();
+        assert_eq!(captured.len(), written);
+        assert!(input.len().abs_diff(captured.len()) <= 1);
+        let (correlation, lag) = best_correlation(&input, &captured, 8);
+        assert!(correlation > 0.999, "correlation {:.4}", correlation);
+        assert_eq!(lag, 0);
+
+        let ogg = read_ogg(&state.get_recent_base64().unwrap());
+        let pcm = decode_pcm(&ogg, OUTPUT_SAMPLE_RATE);
+        assert!(pcm.len() >= captured.len());
+        let warm_up = OUTPUT_SAMPLE_RATE as usize / 10;
+        let level_db =
+            20.0 * (rms(&pcm[warm_up..captured.len()]) / rms(&captured[warm_up..])).log10();
+        assert!(level_db.abs() < 2.0, "level off by {:.2} dB", level_db);
+
+        stop_system_audio(&state).await;
+    });
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn audio_at_returns_a_clamped_window_of_the_buffer() {
+    let input: Vec<f32> = (0..OUTPUT_SAMPLE_RATE as usize * 3)
+        .map(|n| n as f32 / 100_000.0)
+        .collect();
+    let state = recorded(&input);
+    let rate = OUTPUT_SAMPLE_RATE as usize;
+
+    let window = state.get_audio_at(1.0, 0.5).unwrap();
+    assert_eq!(window.samples, input[rate..rate + rate / 2]);
+    assert_eq!(window.offset_seconds, 1.0);
+    assert_eq!(window.buffer_seconds, 3.0);
+
+    // Past the end: only what's left; beyond it: nothing.
+    let window = state.get_audio_at(2.5, 2.0).unwrap();
+    assert_eq!(window.samples, input[rate * 5 / 2..]);
+    assert!(state.get_audio_at(10.0, 1.0).unwrap().samples.is_empty());
+    // Long requests are capped.
+    let window = state.get_audio_at(0.0, 60.0).unwrap();
+    assert_eq!(window.samples.len(), rate * MAX_SCRUB_SECONDS as usize);
+
+    assert!(state.get_audio_at(-1.0, 1.0).is_err());
+    assert!(state.get_audio_at(0.0, 0.0).is_err());
+    assert!(SystemAudioState::new().get_audio_at(0.0, 1.0).is_err());
+}
+
+#[test]
+fn raw_between_times_covers_what_is_buffered() {
+    let input = sine(440.0, 0.5, OUTPUT_SAMPLE_RATE as usize);
+    let state = recorded(&input);
+    let now = now_millis();
+
+    // A span reaching past both ends is clamped to the buffer.
+    let timed = state.get_raw_between_times(0, now + 60_000).unwrap();
+    assert_eq!(timed.samples, input);
+    assert!(timed.start_time_ms <= now);
+
+    assert!(state.get_raw_between_times(now, now).is_err());
+    assert!(SystemAudioState::new()
+        .get_raw_between_times(0, now)
+        .is_err());
+}
+
+#[test]
+fn ring_is_sized_to_the_buffer_length() {
+    let seconds = |s: u32| s as usize * OUTPUT_SAMPLE_RATE as usize;
+    let state = SystemAudioState::new();
+    assert_eq!(state.capacity(), seconds(30));
+    state.set_buffer_seconds(10);
+    assert_eq!(state.capacity(), seconds(10));
+    state.set_buffer_seconds(MAX_BUFFER_SECONDS + 60);
+    assert_eq!(state.capacity(), seconds(MAX_BUFFER_SECONDS));
+
+    // A running session keeps its ring; only the length returned changes.
+    state.recording.store(true, Ordering::SeqCst);
+    state.set_buffer_seconds(10);
+    assert_eq!(state.capacity(), seconds(MAX_BUFFER_SECONDS));
+    assert_eq!(state.buffer_seconds().unwrap(), 10.0);
+}
+
+#[test]
+fn mid_side_fades_between_blocks() {
+    // Two unrelated tones: whether mid or side is louder changes from block
+    // to block, and a hard switch would step by the whole right channel.
+    let left = sine(440.0, 0.5, OUTPUT_SAMPLE_RATE as usize);
+    let right = sine(97.0, 0.5, left.len());
+    let interleaved: Vec<f32> = left
+        .iter()
+        .zip(&right)
+        .flat_map(|(&l, &r)| [l, r])
+        .collect();
+    let mut converter =
+        AudioConverter::new(OUTPUT_SAMPLE_RATE, 2).with_downmix(Downmix::MidSide);
+    let (mut mono, mut side_blocks) = (Vec::new(), 0);
+    // 10 ms blocks.
+    let blocks = interleaved.chunks(320);
+    let block_count = blocks.len();
+    for block in blocks {
+        let (mid, side) = block.chunks_exact(2).fold((0.0, 0.0), |(mid, side), f| {
+            (mid + (f[0] + f[1]).powi(2), side + (f[0] - f[1]).powi(2))
+        });
+        side_blocks += usize::from(side > mid);
+        mono.extend(converter.convert_interleaved(block));
+    }
+    assert!(
+        side_blocks > 0 && side_blocks < block_count,
+        "{} of {} blocks favour side",
+        side_blocks,
+        block_count
+    );
+
+    // The tones alone move the output by at most ~0.05 per sample.
+    let max_step = mono
+        .windows(2)
+        .map(|w| (w[1] - w[0]).abs())
+        .fold(0.0, f32::max);
+    assert!(max_step < 0.08, "mono output steps by {:.3}", max_step);
+}
+
+#[test]
+fn powershell_paths_stay_one_literal() {
+    assert_eq!(
+        powershell_quote(r"C:\Users\O'Brien\tone.wav"),
+        r"'C:\Users\O''Brien\tone.wav'"
+    );
+    assert_eq!(
+        powershell_quote("x\u{2019}); calc; ('"),
+        "'x\u{2019}\u{2019}); calc; ('''"
+    );
+}
@@ -0,0 +1,539 @@
+//! Capture backends that feed the system audio ring buffer.
+//!
+//! `PlatformBackend` dispatches to the native capture module for the current
+//! OS. `MockBackend` generates deterministic audio (a sine wave or a WAV
+//! file) so the ring buffer, encoder and command layers can run on machines
+//! without a working audio stack, e.g. CI. The simulated source loops a WAV
+//! file forever, for frontend and transcription work on machines whose
+//! native backend isn't usable yet.
+//!
+//! In development builds the backend is picked from
+//! `RUNNINGBORD_CAPTURE_BACKEND` at startup (`platform`, `sine[:<hz>]`,
+//! `file:<path.wav>` or `simulate:<path.wav>`); release builds ignore it,
+//! so nothing in the environment can swap real capture for a fake one. It
+//! can be switched with `SystemAudioState::set_capture_backend` while
+//! capture is stopped.
+
+use crate::system_audio::{AudioConverter, SystemAudioState, OUTPUT_SAMPLE_RATE};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Environment variable selecting the capture backend.
+pub const CAPTURE_BACKEND_ENV: &str = "RUNNINGBORD_CAPTURE_BACKEND";
+
+/// Default tone for `sine` without a frequency.
+const DEFAULT_SINE_HZ: f32 = 440.0;
+/// Mock sine amplitude (~ -12 dBFS peak).
+const DEFAULT_SINE_AMPLITUDE: f32 = 0.25;
+/// Mock backends push audio in chunks of this length.
+const MOCK_CHUNK_MS: u64 = 20;
+
+/// Source of the audio pushed into the ring buffer.
+pub trait CaptureBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Begin pushing 16 kHz mono samples into `state`. Called with
+    /// `state.is_recording()` already true.
+    fn start(&self, state: Arc<SystemAudioState>) -> BoxFuture<'static, Result<(), String>>;
+    /// Stop pushing samples. Threads registered through
+    /// `store_capture_handle` are joined by the caller.
+    fn stop(&self) -> BoxFuture<'static, ()>;
+}
+
+/// Serializable backend choice.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CaptureBackendConfig {
+    #[default]
+    Platform,
+    #[serde(rename_all = "camelCase")]
+    Sine {
+        frequency_hz: f32,
+        amplitude: f32,
+    },
+    File {
+        path: PathBuf,
+    },
+    /// Development source: loops a WAV file in real time.
+    Simulated {
+        path: PathBuf,
+    },
+}
+
+impl FromStr for CaptureBackendConfig {
+    type Err = String;
+
+    /// Parse `platform`, `sine`, `sine:<hz>`, `file:<path>` or `simulate:<path>`.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = match spec.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (spec, None),
+        };
+        match (kind.trim(), arg) {
+            ("platform", None) => Ok(Self::Platform),
+            ("sine", arg) => {
+                let frequency_hz = match arg {
+                    Some(hz) => hz
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid sine frequency: {}", hz))?,
+                    None => DEFAULT_SINE_HZ,
+                };
+                Ok(Self::Sine {
+                    frequency_hz,
+                    amplitude: DEFAULT_SINE_AMPLITUDE,
+                })
+            }
+            ("file", Some(path)) if !path.is_empty() => Ok(Self::File {
+                path: PathBuf::from(path),
+            }),
+            ("simulate", Some(path)) if !path.is_empty() => Ok(Self::Simulated {
+                path: PathBuf::from(path),
+            }),
+            _ => Err(format!(
+                "Unknown capture backend '{}' (expected platform, sine[:<hz>], file:<path> or simulate:<path>)",
+                spec
+            )),
+        }
+    }
+}
+
+impl CaptureBackendConfig {
+    /// Read the backend from `RUNNINGBORD_CAPTURE_BACKEND`, falling back to
+    /// the platform backend if it is unset or invalid, and always in
+    /// release builds.
+    pub fn from_env() -> Self {
+        let Ok(spec) = std::env::var(CAPTURE_BACKEND_ENV) else {
+            return Self::Platform;
+        };
+        if !cfg!(debug_assertions) {
+            tracing::warn!(
+                "{} is ignored in release builds; using platform capture",
+                CAPTURE_BACKEND_ENV
+            );
+            return Self::Platform;
+        }
+        spec.parse().unwrap_or_else(|e| {
+            tracing::warn!("{}; using platform capture", e);
+            Self::Platform
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Platform => Ok(()),
+            Self::Sine {
+                frequency_hz,
+                amplitude,
+            } => {
+                let nyquist = OUTPUT_SAMPLE_RATE as f32 / 2.0;
+                if !(*frequency_hz > 0.0 && *frequency_hz < nyquist) {
+                    return Err(format!(
+                        "frequency_hz must be between 0 and {} Hz, got {}",
+                        nyquist, frequency_hz
+                    ));
+                }
+                if !(0.0..=1.0).contains(amplitude) {
+                    return Err(format!(
+                        "amplitude must be between 0 and 1, got {}",
+                        amplitude
+                    ));
+                }
+                Ok(())
+            }
+            Self::File { path } | Self::Simulated { path } => {
+                if path.is_file() {
+                    Ok(())
+                } else {
+                    Err(format!("Audio file not found: {}", path.display()))
+                }
+            }
+        }
+    }
+
+    pub fn build(&self) -> Arc<dyn CaptureBackend> {
+        match self {
+            Self::Platform => Arc::new(PlatformBackend::default()),
+            Self::Sine {
+                frequency_hz,
+                amplitude,
+            } => Arc::new(MockBackend::new(MockSource::Sine {
+                frequency_hz: *frequency_hz,
+                amplitude: *amplitude,
+            })),
+            Self::File { path } => Arc::new(MockBackend::new(MockSource::File {
+                path: path.clone(),
+                looped: false,
+            })),
+            Self::Simulated { path } => Arc::new(MockBackend::new(MockSource::File {
+                path: path.clone(),
+                looped: true,
+            })),
+        }
+    }
+}
+
+/// Settings for the native capture backend. Changing them while recording
+/// goes through `reconfigure_system_audio`, which rebuilds the capture
+/// without clearing the ring buffer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CaptureSettings {
+    /// Processes whose audio is left out of the capture (macOS only).
+    pub excluded_pids: Vec<i32>,
+    /// What the tapped processes sound like while captured (macOS only).
+    pub mute_behavior: MuteBehavior,
+    /// Capture this output device instead of following the system default:
+    /// the CoreAudio device UID on macOS, the PipeWire node name on Linux and
+    /// the device name on Windows.
+    pub device: Option<String>,
+    /// Also play the tapped audio live on this output device (a CoreAudio
+    /// device UID), e.g. headphones to check what's being captured (macOS
+    /// only).
+    pub monitor_device: Option<String>,
+    /// Include this app's own output, such as spoken answers, in the
+    /// capture. Off by default so the assistant doesn't transcribe itself:
+    /// macOS leaves this process out of the tap, and elsewhere capture is
+    /// paused while an answer plays.
+    pub capture_own_audio: bool,
+}
+
+/// Whether tapped audio still reaches the speakers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MuteBehavior {
+    #[default]
+    Unmuted,
+    Muted,
+    /// Muted only while a capture is running.
+    MutedWhenTapped,
+}
+
+impl CaptureSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(device) = &self.device {
+            if device.trim().is_empty() {
+                return Err("device must not be empty".to_string());
+            }
+        }
+        if let Some(monitor) = &self.monitor_device {
+            if monitor.trim().is_empty() {
+                return Err("monitor_device must not be empty".to_string());
+            }
+            if self.capture_own_audio {
+                // The monitor output would be tapped straight back in.
+                return Err("capture_own_audio can't be used with monitor_device".to_string());
+            }
+        }
+        if cfg!(not(target_os = "macos")) {
+            if !self.excluded_pids.is_empty() {
+                return Err("excluded_pids is only supported on macOS".to_string());
+            }
+            if self.mute_behavior != MuteBehavior::Unmuted {
+                return Err("mute_behavior is only supported on macOS".to_string());
+            }
+            if self.monitor_device.is_some() {
+                return Err("monitor_device is only supported on macOS".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Native capture for the current OS.
+#[derive(Default)]
+pub struct PlatformBackend {
+    /// This backend's process tap; each state builds its own backend, so
+    /// taps aren't shared between sessions.
+    #[cfg(target_os = "macos")]
+    tap: Arc<crate::system_audio_macos::ProcessTap>,
+}
+
+impl CaptureBackend for PlatformBackend {
+    fn name(&self) -> &'static str {
+        "platform"
+    }
+
+    fn start(&self, state: Arc<SystemAudioState>) -> BoxFuture<'static, Result<(), String>> {
+        #[cfg(target_os = "macos")]
+        let tap = self.tap.clone();
+        Box::pin(async move {
+            #[cfg(target_os = "macos")]
+            {
+                tap.start(state).await
+            }
+            #[cfg(target_os = "linux")]
+            {
+                crate::system_audio_linux::start_capture(state).await
+            }
+            #[cfg(target_os = "windows")]
+            {
+                crate::system_audio_windows::start_capture(state).await
+            }
+            #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+            {
+                let _ = state;
+                Err("System audio capture is not supported on this platform".to_string())
+            }
+        })
+    }
+
+    fn stop(&self) -> BoxFuture<'static, ()> {
+        #[cfg(target_os = "macos")]
+        let tap = self.tap.clone();
+        Box::pin(async move {
+            #[cfg(target_os = "macos")]
+            {
+                tap.stop().await;
+            }
+            #[cfg(target_os = "linux")]
+            {
+                crate::system_audio_linux::stop_capture().await;
+            }
+            #[cfg(target_os = "windows")]
+            {
+                crate::system_audio_windows::stop_capture().await;
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MockSource {
+    Sine {
+        frequency_hz: f32,
+        amplitude: f32,
+    },
+    /// A WAV file, converted to 16 kHz mono and played once or looped.
+    File {
+        path: PathBuf,
+        looped: bool,
+    },
+}
+
+/// Deterministic capture backend. Samples are pushed from a thread in
+/// real-time paced chunks, so idle detection, streaming and exports behave
+/// as they would with a live device.
+pub struct MockBackend {
+    source: MockSource,
+    running: Arc<AtomicBool>,
+}
+
+impl MockBackend {
+    pub fn new(source: MockSource) -> Self {
+        Self {
+            source,
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl CaptureBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        match self.source {
+            MockSource::Sine { .. } => "mock-sine",
+            MockSource::File { looped: false, .. } => "mock-file",
+            MockSource::File { looped: true, .. } => "simulated",
+        }
+    }
+
+    fn start(&self, state: Arc<SystemAudioState>) -> BoxFuture<'static, Result<(), String>> {
+        let source = self.source.clone();
+        let running = self.running.clone();
+        Box::pin(async move {
+            // Decode up front so a bad file fails `start` instead of the thread.
+            let file_samples = match &source {
+                MockSource::File { path, looped } => {
+                    let samples = read_wav_16k_mono(path)?;
+                    if *looped && samples.is_empty() {
+                        return Err(format!("{} contains no audio", path.display()));
+                    }
+                    Some(samples)
+                }
+                MockSource::Sine { .. } => None,
+            };
+            running.store(true, Ordering::SeqCst);
+
+            let thread_running = running.clone();
+            let thread_state = state.clone();
+            let handle = thread::Builder::new()
+                .name("mock-audio-capture".to_string())
+                .spawn(move || {
+                    let mut chunks = MockChunks::new(source, file_samples);
+                    let chunk_len = (OUTPUT_SAMPLE_RATE as u64 * MOCK_CHUNK_MS / 1000) as usize;
+                    let started = Instant::now();
+                    let mut sent: u64 = 0;
+                    while thread_running.load(Ordering::SeqCst) && thread_state.is_recording() {
+                        let Some(chunk) = chunks.next(chunk_len) else {
+                            break;
+                        };
+                        {
+                            let _timer = thread_state.callback_timer();
+                            thread_state.push_samples_realtime(&chunk);
+                        }
+                        sent += 1;
+                        let due = started + Duration::from_millis(sent * MOCK_CHUNK_MS);
+                        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                            thread::sleep(wait);
+                        }
+                    }
+                })
+                .map_err(|e| {
+                    running.store(false, Ordering::SeqCst);
+                    format!("Failed to spawn mock capture thread: {}", e)
+                })?;
+            state.store_capture_handle(handle);
+            tracing::info!("Mock system audio capture started");
+            Ok(())
+        })
+    }
+
+    fn stop(&self) -> BoxFuture<'static, ()> {
+        self.running.store(false, Ordering::SeqCst);
+        Box::pin(async {})
+    }
+}
+
+/// Produces successive chunks of mock audio.
+struct MockChunks {
+    source: MockSource,
+    file_samples: Option<Vec<f32>>,
+    position: usize,
+}
+
+impl MockChunks {
+    fn new(source: MockSource, file_samples: Option<Vec<f32>>) -> Self {
+        Self {
+            source,
+            file_samples,
+            position: 0,
+        }
+    }
+
+    /// Next `len` samples, or `None` once a non-looping file is exhausted.
+    fn next(&mut self, len: usize) -> Option<Vec<f32>> {
+        let start = self.position;
+        self.position += len;
+        match (&self.source, &self.file_samples) {
+            (
+                MockSource::Sine {
+                    frequency_hz,
+                    amplitude,
+                },
+                _,
+            ) => {
+                let rate = OUTPUT_SAMPLE_RATE as f64;
+                let hz = *frequency_hz as f64;
+                Some(
+                    (start..start + len)
+                        .map(|n| {
+                            // Reduce the phase per sample so long runs don't lose precision.
+                            let cycles = (n as f64 * hz / rate).fract();
+                            (cycles * std::f64::consts::TAU).sin() as f32 * amplitude
+                        })
+                        .collect(),
+                )
+            }
+            (MockSource::File { looped: true, .. }, Some(samples)) => Some(
+                (start..start + len)
+                    .map(|n| samples[n % samples.len()])
+                    .collect(),
+            ),
+            (MockSource::File { .. }, Some(samples)) => {
+                if start >= samples.len() {
+                    return None;
+                }
+                Some(samples[start..(start + len).min(samples.len())].to_vec())
+            }
+            (MockSource::File { .. }, None) => None,
+        }
+    }
+}
+
+/// Read a PCM (8/16/24/32-bit integer) or 32-bit float WAV file and convert
+/// it to 16 kHz mono.
+pub fn read_wav_16k_mono(path: &Path) -> Result<Vec<f32>, String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let (samples, sample_rate, channels) =
+        parse_wav(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut converter = AudioConverter::new(sample_rate, channels);
+    Ok(converter.convert_interleaved(&samples))
+}
+
+/// Decode a WAV file into interleaved f32 samples, sample rate and channels.
+fn parse_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32, u16), String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("not a RIFF/WAVE file".to_string());
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let u32_at =
+        |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32_at(offset + 4) as usize;
+        let body = offset + 8;
+        let end = body.saturating_add(size).min(bytes.len());
+        match id {
+            b"fmt " if end - body >= 16 => {
+                let mut tag = u16_at(body);
+                // WAVE_FORMAT_EXTENSIBLE: the real format is the first two
+                // bytes of the sub-format GUID.
+                if tag == 0xFFFE && end - body >= 26 {
+                    tag = u16_at(body + 24);
+                }
+                format = Some((tag, u16_at(body + 2), u32_at(body + 4), u16_at(body + 14)));
+            }
+            b"data" => {
+                let (tag, channels, sample_rate, bits) =
+                    format.ok_or("data chunk before fmt chunk")?;
+                if channels == 0 || sample_rate == 0 {
+                    return Err("invalid fmt chunk".to_string());
+                }
+                let data = &bytes[body..end];
+                let samples: Vec<f32> = match (tag, bits) {
+                    (1, 8) => data.iter().map(|b| (*b as f32 - 128.0) / 128.0).collect(),
+                    (1, 16) => data
+                        .chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+                        .collect(),
+                    (1, 24) => data
+                        .chunks_exact(3)
+                        .map(|b| {
+                            (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0
+                        })
+                        .collect(),
+                    (1, 32) => data
+                        .chunks_exact(4)
+                        .map(|b| {
+                            i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0
+                        })
+                        .collect(),
+                    (3, 32) => data
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                    _ => {
+                        return Err(format!(
+                            "unsupported WAV encoding (format {}, {} bits)",
+                            tag, bits
+                        ))
+                    }
+                };
+                return Ok((samples, sample_rate, channels));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length.
+        offset = body.saturating_add(size + (size & 1));
+    }
+    Err("no data chunk".to_string())
+}
@@ -0,0 +1,60 @@
+//! Optional in-memory encryption for the system audio ring buffer.
+//!
+//! Samples are XORed with a ChaCha20 keystream addressed by their absolute
+//! sample position in the session, so any span can be decrypted on its own
+//! during export without touching the rest of the buffer. The key is random
+//! per session and only ever lives in process memory; the keystream is set
+//! up once per session and only seeked afterwards, so the real-time writer
+//! doesn't redo the key setup on every block.
+
+use chacha20::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use chacha20::{ChaCha20, Key, Nonce};
+use zeroize::Zeroize;
+
+/// Samples processed per keystream block on the stack.
+const CHUNK_SAMPLES: usize = 256;
+
+/// Zeroizes its key schedule on drop.
+pub struct BufferCipher {
+    stream: ChaCha20,
+}
+
+impl BufferCipher {
+    /// Create a cipher with a fresh random key and nonce.
+    pub fn generate() -> Result<Self, String> {
+        let mut key = [0u8; 32];
+        let mut nonce = [0u8; 12];
+        let random = getrandom::getrandom(&mut key)
+            .map_err(|e| format!("Random key: {}", e))
+            .and_then(|_| {
+                getrandom::getrandom(&mut nonce).map_err(|e| format!("Random nonce: {}", e))
+            });
+        let stream = ChaCha20::new(Key::from_slice(&key), Nonce::from_slice(&nonce));
+        key.zeroize();
+        nonce.zeroize();
+        random.map(|_| Self { stream })
+    }
+
+    /// Encrypt or decrypt `samples` in place, where `samples[0]` sits at
+    /// absolute sample `position` of the session. The operation is its own
+    /// inverse.
+    pub fn apply(&mut self, position: usize, samples: &mut [f32]) {
+        let cipher = &mut self.stream;
+        cipher.seek((position as u64) * 4);
+
+        let mut bytes = [0u8; CHUNK_SAMPLES * 4];
+        for chunk in samples.chunks_mut(CHUNK_SAMPLES) {
+            let n = chunk.len() * 4;
+            for (i, s) in chunk.iter().enumerate() {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&s.to_bits().to_le_bytes());
+            }
+            cipher.apply_keystream(&mut bytes[..n]);
+            for (i, s) in chunk.iter_mut().enumerate() {
+                let mut word = [0u8; 4];
+                word.copy_from_slice(&bytes[i * 4..i * 4 + 4]);
+                *s = f32::from_bits(u32::from_le_bytes(word));
+            }
+        }
+        bytes.zeroize();
+    }
+}
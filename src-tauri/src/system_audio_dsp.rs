@@ -0,0 +1,574 @@
+//! Processing chain applied to ring buffer audio before it is encoded for
+//! export or streaming: resample -> noise gate -> AGC -> VAD -> limiter, then
+//! Opus.
+//!
+//! Each stage implements `AudioProcessor` and keeps its own state, so the
+//! same pipeline can be fed one export in a single call or a live stream
+//! chunk by chunk. New stages plug in by implementing the trait and adding
+//! a switch to `DspConfig`.
+
+use crate::system_audio::{dbfs_to_linear, measure_level, OUTPUT_SAMPLE_RATE};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use zeroize::Zeroize;
+
+/// Sample rates the Opus encoder accepts.
+const OPUS_SAMPLE_RATES: [u32; 5] = [8000, 12000, 16000, 24000, 48000];
+/// Analysis frame for the level-driven stages.
+const FRAME_MS: u32 = 10;
+/// Audio kept either side of the audible part when trimming edge silence,
+/// so soft onsets and decays aren't clipped.
+const EDGE_MARGIN_MS: u32 = 50;
+/// Length of the anti-aliasing filter run before downsampling.
+const LOWPASS_TAPS: usize = 63;
+
+/// A run of mono samples and the rate they are at.
+pub struct AudioBlock {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+/// One stage of the processing chain.
+pub trait AudioProcessor: Send {
+    fn name(&self) -> &'static str;
+    /// Process `block` in place. Stages may change its length or rate; a
+    /// stage that swaps out the sample buffer must zeroize the old one.
+    fn process(&mut self, block: &mut AudioBlock);
+}
+
+/// Which stages run, exposed to the frontend's processing settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DspConfig {
+    /// Output sample rate; `None` keeps the 16 kHz capture rate.
+    pub resample_hz: Option<u32>,
+    /// Fade stretches below the noise floor. This is a gate: noise under
+    /// speech is left alone. Saved settings may still call this `denoise`.
+    #[serde(alias = "denoise")]
+    pub noise_gate: bool,
+    /// Automatic gain control towards a speech-level RMS.
+    pub agc: bool,
+    /// Drop frames without voice activity (shortens the output).
+    pub vad: bool,
+    /// Peak limiter just before encoding, for sources that clip the capture.
+    pub limiter: bool,
+    /// Cut leading and trailing audio quieter than this (dBFS) from exports,
+    /// so they start where sound starts. Unlike `vad`, pauses in between are
+    /// kept. Not applied to live streams.
+    pub trim_silence_dbfs: Option<f32>,
+}
+
+impl DspConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(rate) = self.resample_hz {
+            if !OPUS_SAMPLE_RATES.contains(&rate) {
+                return Err(format!(
+                    "resample_hz must be one of {:?}, got {}",
+                    OPUS_SAMPLE_RATES, rate
+                ));
+            }
+        }
+        if let Some(dbfs) = self.trim_silence_dbfs {
+            if !(-100.0..=0.0).contains(&dbfs) {
+                return Err(format!(
+                    "trim_silence_dbfs must be between -100 and 0, got {}",
+                    dbfs
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sample rate of the pipeline's output.
+    pub fn output_sample_rate(&self) -> u32 {
+        self.resample_hz.unwrap_or(OUTPUT_SAMPLE_RATE)
+    }
+
+    pub fn build(&self) -> DspPipeline {
+        let mut stages: Vec<Box<dyn AudioProcessor>> = Vec::new();
+        if let Some(rate) = self.resample_hz.filter(|r| *r != OUTPUT_SAMPLE_RATE) {
+            stages.push(Box::new(Resampler::new(rate)));
+        }
+        if self.noise_gate {
+            stages.push(Box::new(NoiseGate::new(-50.0, -24.0)));
+        }
+        if self.agc {
+            stages.push(Box::new(Agc::new(-20.0, 20.0)));
+        }
+        if self.vad {
+            stages.push(Box::new(Vad::new(-45.0, 30)));
+        }
+        if self.limiter {
+            stages.push(Box::new(Limiter::new(-1.0, 50)));
+        }
+        DspPipeline { stages }
+    }
+}
+
+/// An ordered chain of processors.
+pub struct DspPipeline {
+    stages: Vec<Box<dyn AudioProcessor>>,
+}
+
+impl DspPipeline {
+    /// Append a stage that isn't driven by `DspConfig`, e.g. a per-export
+    /// time stretch.
+    pub fn push(&mut self, stage: Box<dyn AudioProcessor>) {
+        self.stages.push(stage);
+    }
+
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|s| s.name()).collect()
+    }
+
+    /// Run 16 kHz capture samples through every stage.
+    pub fn process(&mut self, samples: Vec<f32>) -> AudioBlock {
+        let mut block = AudioBlock {
+            samples,
+            sample_rate: OUTPUT_SAMPLE_RATE,
+        };
+        for stage in self.stages.iter_mut() {
+            stage.process(&mut block);
+        }
+        block
+    }
+}
+
+fn frame_len(sample_rate: u32) -> usize {
+    ((sample_rate * FRAME_MS / 1000) as usize).max(1)
+}
+
+fn frame_rms(frame: &[f32]) -> f32 {
+    let sum: f32 = frame.iter().map(|s| s * s).sum();
+    (sum / frame.len().max(1) as f32).sqrt()
+}
+
+/// The part of `samples` between the first and last 10 ms frame whose RMS
+/// reaches `threshold_dbfs`, widened by `EDGE_MARGIN_MS` on both sides.
+/// `None` if no frame is loud enough.
+pub fn audible_range(
+    samples: &[f32],
+    sample_rate: u32,
+    threshold_dbfs: f32,
+) -> Option<Range<usize>> {
+    let len = frame_len(sample_rate);
+    let threshold = dbfs_to_linear(threshold_dbfs);
+    let loud = |frame: &[f32]| frame_rms(frame) >= threshold;
+    let first = samples.chunks(len).position(loud)?;
+    let last = samples.chunks(len).rposition(loud)?;
+    let margin = (sample_rate * EDGE_MARGIN_MS / 1000) as usize;
+    let start = (first * len).saturating_sub(margin);
+    let end = ((last + 1) * len + margin).min(samples.len());
+    Some(start..end)
+}
+
+/// Linear-interpolation resampler. Carries the last input sample and the
+/// fractional read position between calls so chunked input stays seamless.
+/// When downsampling, a low-pass filter runs first so content above the new
+/// Nyquist frequency is removed instead of folding back as aliases.
+pub struct Resampler {
+    target_rate: u32,
+    position: f64,
+    last: Option<f32>,
+    filter: Option<LowPass>,
+}
+
+impl Resampler {
+    pub fn new(target_rate: u32) -> Self {
+        Self {
+            target_rate,
+            position: 0.0,
+            last: None,
+            filter: None,
+        }
+    }
+}
+
+impl AudioProcessor for Resampler {
+    fn name(&self) -> &'static str {
+        "resample"
+    }
+
+    fn process(&mut self, block: &mut AudioBlock) {
+        if block.sample_rate == self.target_rate || block.samples.is_empty() {
+            block.sample_rate = self.target_rate;
+            return;
+        }
+        if self.target_rate < block.sample_rate {
+            // Pass band up to 80 % of the new Nyquist frequency, so the
+            // filter's roll-off is finished by the Nyquist frequency itself.
+            let cutoff = 0.4 * self.target_rate as f64 / block.sample_rate as f64;
+            self.filter
+                .get_or_insert_with(|| LowPass::new(cutoff))
+                .process(&mut block.samples);
+        }
+        let step = block.sample_rate as f64 / self.target_rate as f64;
+        let mut input: Vec<f32> = self
+            .last
+            .into_iter()
+            .chain(block.samples.iter().copied())
+            .collect();
+        block.samples.zeroize();
+
+        let mut out = Vec::with_capacity((input.len() as f64 / step) as usize + 1);
+        let mut pos = self.position;
+        while (pos as usize) + 1 < input.len() {
+            let i = pos as usize;
+            let frac = (pos - i as f64) as f32;
+            out.push(input[i] * (1.0 - frac) + input[i + 1] * frac);
+            pos += step;
+        }
+        // The last input sample becomes index 0 of the next call.
+        self.position = pos - (input.len() - 1) as f64;
+        self.last = input.last().copied();
+        input.zeroize();
+
+        block.samples = out;
+        block.sample_rate = self.target_rate;
+    }
+}
+
+/// Blackman-windowed sinc low-pass. Linear phase, so it delays the audio by
+/// `LOWPASS_TAPS / 2` samples (about 2 ms at 16 kHz) without smearing it.
+struct LowPass {
+    taps: Vec<f32>,
+    /// The last `LOWPASS_TAPS - 1` input samples.
+    history: Vec<f32>,
+}
+
+impl LowPass {
+    /// `cutoff` is a fraction of the sample rate (below 0.5).
+    fn new(cutoff: f64) -> Self {
+        let middle = (LOWPASS_TAPS / 2) as f64;
+        let mut taps: Vec<f32> = (0..LOWPASS_TAPS)
+            .map(|i| {
+                let x = i as f64 - middle;
+                let sinc = if x == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (std::f64::consts::TAU * cutoff * x).sin() / (std::f64::consts::PI * x)
+                };
+                let phase = std::f64::consts::TAU * i as f64 / (LOWPASS_TAPS - 1) as f64;
+                let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+                (sinc * window) as f32
+            })
+            .collect();
+        // Unity gain at DC.
+        let sum: f32 = taps.iter().sum();
+        taps.iter_mut().for_each(|t| *t /= sum);
+        Self {
+            taps,
+            history: vec![0.0; LOWPASS_TAPS - 1],
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        let mut input = Vec::with_capacity(self.history.len() + samples.len());
+        input.extend_from_slice(&self.history);
+        input.extend_from_slice(samples);
+        // The taps are symmetric, so no need to reverse them.
+        for (out, window) in samples.iter_mut().zip(input.windows(LOWPASS_TAPS)) {
+            *out = window.iter().zip(&self.taps).map(|(x, t)| x * t).sum();
+        }
+        let keep = input.len() - self.history.len();
+        self.history.copy_from_slice(&input[keep..]);
+        input.zeroize();
+    }
+}
+
+impl Drop for LowPass {
+    fn drop(&mut self) {
+        self.history.zeroize();
+    }
+}
+
+/// Downward expander: frames whose RMS sits under `threshold_dbfs` are
+/// faded towards `reduction_db`, which takes the edge off steady hiss and
+/// fan noise between phrases.
+pub struct NoiseGate {
+    threshold: f32,
+    floor_gain: f32,
+    gain: f32,
+}
+
+impl NoiseGate {
+    pub fn new(threshold_dbfs: f32, reduction_db: f32) -> Self {
+        Self {
+            threshold: dbfs_to_linear(threshold_dbfs),
+            floor_gain: dbfs_to_linear(reduction_db),
+            gain: 1.0,
+        }
+    }
+}
+
+impl AudioProcessor for NoiseGate {
+    fn name(&self) -> &'static str {
+        "noise_gate"
+    }
+
+    fn process(&mut self, block: &mut AudioBlock) {
+        let len = frame_len(block.sample_rate);
+        for frame in block.samples.chunks_mut(len) {
+            let target = if frame_rms(frame) < self.threshold {
+                self.floor_gain
+            } else {
+                1.0
+            };
+            // Ramp across the frame to avoid zipper noise.
+            let step = (target - self.gain) / frame.len() as f32;
+            for s in frame.iter_mut() {
+                self.gain += step;
+                *s *= self.gain;
+            }
+        }
+    }
+}
+
+/// Automatic gain control: pulls frame RMS towards `target_dbfs` with fast
+/// attack and slow release, never boosting more than `max_gain_db`.
+pub struct Agc {
+    target: f32,
+    max_gain: f32,
+    gain: f32,
+}
+
+impl Agc {
+    /// Frames quieter than this are left at the current gain.
+    const GATE_DBFS: f32 = -60.0;
+    const ATTACK: f32 = 0.5;
+    const RELEASE: f32 = 0.02;
+
+    pub fn new(target_dbfs: f32, max_gain_db: f32) -> Self {
+        Self {
+            target: dbfs_to_linear(target_dbfs),
+            max_gain: dbfs_to_linear(max_gain_db),
+            gain: 1.0,
+        }
+    }
+}
+
+impl AudioProcessor for Agc {
+    fn name(&self) -> &'static str {
+        "agc"
+    }
+
+    fn process(&mut self, block: &mut AudioBlock) {
+        let len = frame_len(block.sample_rate);
+        let gate = dbfs_to_linear(Self::GATE_DBFS);
+        for frame in block.samples.chunks_mut(len) {
+            let rms = frame_rms(frame);
+            if rms > gate {
+                let desired = (self.target / rms).min(self.max_gain);
+                let rate = if desired < self.gain {
+                    Self::ATTACK
+                } else {
+                    Self::RELEASE
+                };
+                self.gain += (desired - self.gain) * rate;
+            }
+            for s in frame.iter_mut() {
+                *s = (*s * self.gain).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+/// Energy-based voice activity detection. Frames below `threshold_dbfs` are
+/// dropped once `hangover_frames` quiet frames have passed, so word endings
+/// are kept but long pauses disappear.
+pub struct Vad {
+    threshold_dbfs: f32,
+    hangover_frames: u32,
+    silent_run: u32,
+}
+
+impl Vad {
+    pub fn new(threshold_dbfs: f32, hangover_frames: u32) -> Self {
+        Self {
+            threshold_dbfs,
+            hangover_frames,
+            silent_run: 0,
+        }
+    }
+}
+
+impl AudioProcessor for Vad {
+    fn name(&self) -> &'static str {
+        "vad"
+    }
+
+    fn process(&mut self, block: &mut AudioBlock) {
+        let len = frame_len(block.sample_rate);
+        let mut kept = Vec::with_capacity(block.samples.len());
+        for frame in block.samples.chunks(len) {
+            if measure_level(frame).rms_dbfs >= self.threshold_dbfs {
+                self.silent_run = 0;
+            } else {
+                self.silent_run = self.silent_run.saturating_add(1);
+            }
+            if self.silent_run <= self.hangover_frames {
+                kept.extend_from_slice(frame);
+            }
+        }
+        std::mem::replace(&mut block.samples, kept).zeroize();
+    }
+}
+
+/// Peak limiter with instant attack: no sample leaves above
+/// `ceiling_dbfs`, and the gain recovers over roughly `release_ms`.
+pub struct Limiter {
+    ceiling: f32,
+    release_ms: u32,
+    gain: f32,
+}
+
+impl Limiter {
+    pub fn new(ceiling_dbfs: f32, release_ms: u32) -> Self {
+        Self {
+            ceiling: dbfs_to_linear(ceiling_dbfs),
+            release_ms,
+            gain: 1.0,
+        }
+    }
+}
+
+impl AudioProcessor for Limiter {
+    fn name(&self) -> &'static str {
+        "limiter"
+    }
+
+    fn process(&mut self, block: &mut AudioBlock) {
+        let release_samples = (block.sample_rate * self.release_ms / 1000).max(1);
+        let release = 1.0 - (-1.0 / release_samples as f32).exp();
+        for s in block.samples.iter_mut() {
+            let limit = (self.ceiling / s.abs()).min(1.0);
+            if limit < self.gain {
+                self.gain = limit;
+            } else {
+                self.gain += (limit - self.gain) * release;
+            }
+            *s *= self.gain;
+        }
+    }
+}
+
+/// WSOLA time stretch: plays the input `speed` times faster without
+/// changing pitch. Each 30 ms Hann-windowed output frame is taken from near
+/// its nominal input position, at the offset that best continues the
+/// previous frame's waveform, and overlap-added at 50 %.
+///
+/// Input the search window can't cover yet (the last ~40 ms) is held back
+/// for the next call.
+pub struct TimeStretch {
+    speed: f64,
+    /// Unconsumed input; `input[0]` is absolute input sample `base`.
+    input: Vec<f32>,
+    base: usize,
+    /// Nominal input position of the next frame.
+    nominal: f64,
+    /// Input position the previous frame was taken from.
+    previous: Option<usize>,
+    /// Second half of the previous windowed frame, waiting for overlap.
+    tail: Vec<f32>,
+    window: Vec<f32>,
+}
+
+impl TimeStretch {
+    const FRAME_MS: u32 = 30;
+
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            input: Vec::new(),
+            base: 0,
+            nominal: 0.0,
+            previous: None,
+            tail: Vec::new(),
+            window: Vec::new(),
+        }
+    }
+
+    /// Offset in `[lo, hi]` whose frame correlates best with `target`.
+    /// Every second sample is compared, which is plenty for picking a
+    /// splice point and halves the cost.
+    fn best_offset(&self, target: &[f32], lo: usize, hi: usize) -> usize {
+        let mut best = (lo, f32::MIN);
+        for pos in lo..=hi {
+            let candidate = &self.input[pos - self.base..pos - self.base + target.len()];
+            let score: f32 = target
+                .iter()
+                .zip(candidate)
+                .step_by(2)
+                .map(|(a, b)| a * b)
+                .sum();
+            if score > best.1 {
+                best = (pos, score);
+            }
+        }
+        best.0
+    }
+}
+
+impl AudioProcessor for TimeStretch {
+    fn name(&self) -> &'static str {
+        "time_stretch"
+    }
+
+    fn process(&mut self, block: &mut AudioBlock) {
+        let n = ((block.sample_rate * Self::FRAME_MS / 1000) as usize).max(4) & !1;
+        let hop = n / 2;
+        let tolerance = hop / 2;
+        if self.window.len() != n {
+            // Periodic Hann: overlapping halves sum to exactly one.
+            self.window = (0..n)
+                .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / n as f32).cos())
+                .collect();
+            self.tail = vec![0.0; hop];
+        }
+        self.input.extend_from_slice(&block.samples);
+        block.samples.zeroize();
+
+        let end = self.base + self.input.len();
+        let mut out = Vec::with_capacity((self.input.len() as f64 / self.speed) as usize + n);
+        loop {
+            let nominal = self.nominal.round() as usize;
+            let pos = match self.previous {
+                None if nominal + n <= end => nominal,
+                Some(previous) if nominal + tolerance + n <= end && previous + hop + n <= end => {
+                    let start = previous + hop - self.base;
+                    let target = &self.input[start..start + n];
+                    let lo = nominal.saturating_sub(tolerance).max(self.base);
+                    self.best_offset(target, lo, nominal + tolerance)
+                }
+                _ => break,
+            };
+            let frame = &self.input[pos - self.base..pos - self.base + n];
+            for i in 0..hop {
+                out.push(self.tail[i] + frame[i] * self.window[i]);
+                self.tail[i] = frame[hop + i] * self.window[hop + i];
+            }
+            self.previous = Some(pos);
+            self.nominal += hop as f64 * self.speed;
+        }
+
+        // Drop input no later frame or correlation target can reach.
+        let keep_from = self
+            .previous
+            .map_or(0, |p| p + hop)
+            .min((self.nominal.round() as usize).saturating_sub(tolerance))
+            .max(self.base);
+        let consumed = keep_from - self.base;
+        self.input[..consumed].zeroize();
+        self.input.drain(..consumed);
+        self.base = keep_from;
+
+        block.samples = out;
+    }
+}
+
+impl Drop for TimeStretch {
+    fn drop(&mut self) {
+        self.input.zeroize();
+        self.tail.zeroize();
+    }
+}
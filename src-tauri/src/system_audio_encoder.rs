@@ -0,0 +1,384 @@
+//! Opus/OGG encoding for system audio exports (mono, 16 kHz unless the DSP
+//! pipeline resamples).
+
+use crate::system_audio::OUTPUT_CHANNELS;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use zeroize::Zeroize;
+
+/// Frame peak below which a 20 ms frame counts as silence (~ -70 dBFS).
+const SILENCE_THRESHOLD: f32 = 3.2e-4;
+/// Silent frames encoded normally before they start being skipped (200 ms),
+/// so the tail of a sound decodes untouched.
+const SILENCE_HANGOVER_FRAMES: u32 = 10;
+/// While skipping, still send a full frame every this many frames (400 ms).
+const SILENCE_KEEPALIVE_FRAMES: u32 = 20;
+
+/// Encoder settings exposed to the frontend's encoding settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EncodeOptions {
+    /// Long silent stretches are sent as empty packets, so silence costs
+    /// ~1 byte per 20 ms. This is not Opus DTX: decoders conceal the empty
+    /// packets as lost ones, which is inaudible only because the audio
+    /// around them is already silent. Saved settings may still call this
+    /// `dtx`.
+    #[serde(alias = "dtx")]
+    pub skip_silence: bool,
+    /// In-band forward error correction: each packet carries a low-bitrate
+    /// copy of the previous frame so a single lost packet can be recovered.
+    pub inband_fec: bool,
+    /// Expected packet loss (0-100 %) of the link the audio is streamed over.
+    /// Higher values make the encoder spend more bits on FEC.
+    pub packet_loss_perc: u8,
+    /// How multichannel capture is folded to mono. Takes effect on the next
+    /// `system_audio_start`.
+    pub downmix: Downmix,
+}
+
+/// Channel downmix strategy for the mono capture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Downmix {
+    /// Average of all channels; 5.1 and 7.1 use the standard surround fold
+    /// with the LFE dropped. Content that is out of phase between L and R
+    /// cancels out.
+    #[default]
+    Average,
+    Left,
+    Right,
+    /// Per block, fade toward whichever of mid (L+R) or side (L-R) carries
+    /// more energy, so phase-inverted material survives the fold to mono.
+    MidSide,
+}
+
+/// Container/codec of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    /// Opus in OGG, what every export and the transcription path use.
+    #[default]
+    OggOpus,
+    /// 16-bit PCM WAV: lossless, about 8x the size of OggOpus at 16 kHz.
+    Wav,
+}
+
+/// Rough OggOpus container overhead: OpusHead and OpusTags pages.
+const OGG_HEADER_BYTES: usize = 256;
+/// Ogg page header without its lacing table.
+const OGG_PAGE_HEADER_BYTES: usize = 27;
+/// The ogg writer starts a new page about every this many bytes.
+const OGG_PAGE_BYTES: usize = 4096;
+
+impl ExportFormat {
+    /// File extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::OggOpus => "ogg",
+            ExportFormat::Wav => "wav",
+        }
+    }
+
+    /// Encode mono `samples` at `sample_rate`. `options` and `comments` only
+    /// apply to OggOpus.
+    pub fn encode(
+        self,
+        samples: &[f32],
+        sample_rate: u32,
+        options: &EncodeOptions,
+        comments: &[(String, String)],
+    ) -> Result<Vec<u8>, String> {
+        match self {
+            ExportFormat::OggOpus => encode_ogg_opus(samples, sample_rate, options, comments),
+            ExportFormat::Wav => Ok(encode_wav(samples, sample_rate)),
+        }
+    }
+
+    /// Size in bytes of `samples` mono samples at `sample_rate` encoded in
+    /// this format. Exact for WAV. For OggOpus this assumes libopus's
+    /// default VBR bitrate and no skipped silence, so silent stretches make it
+    /// an upper bound.
+    pub fn estimated_size(self, samples: usize, sample_rate: u32) -> usize {
+        match self {
+            ExportFormat::Wav => 44 + samples * 2,
+            ExportFormat::OggOpus => {
+                let frame = sample_rate as usize / 50; // 20 ms
+                let packets = samples.div_ceil(frame.max(1));
+                // libopus's automatic bitrate: 60 bits per frame plus one
+                // bit per input sample.
+                let bitrate = 60 * 50 + sample_rate as usize * OUTPUT_CHANNELS as usize;
+                let payload = packets * bitrate / 50 / 8;
+                // One lacing byte per packet, plus page headers.
+                let pages = (payload + packets).div_ceil(OGG_PAGE_BYTES);
+                let framing = packets + pages * OGG_PAGE_HEADER_BYTES;
+                OGG_HEADER_BYTES + payload + framing
+            }
+        }
+    }
+}
+
+impl EncodeOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.packet_loss_perc > 100 {
+            return Err(format!(
+                "packet_loss_perc must be between 0 and 100, got {}",
+                self.packet_loss_perc
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Create a mono Opus encoder at `sample_rate` configured from `options`.
+pub fn new_opus_encoder(
+    sample_rate: u32,
+    options: &EncodeOptions,
+) -> Result<opus::Encoder, String> {
+    let mut encoder = opus::Encoder::new(
+        sample_rate,
+        opus::Channels::Mono,
+        opus::Application::Voip,
+    )
+    .map_err(|e| format!("Opus encoder init: {}", e))?;
+    encoder
+        .set_inband_fec(options.inband_fec)
+        .map_err(|e| format!("Opus set FEC: {}", e))?;
+    encoder
+        .set_packet_loss_perc(options.packet_loss_perc.min(100) as i32)
+        .map_err(|e| format!("Opus set packet loss: {}", e))?;
+    Ok(encoder)
+}
+
+/// Shrink a packet to its TOC byte (code 0, zero-length frame). Decoders
+/// run packet loss concealment for it, which fades out to silence.
+pub(crate) fn empty_packet(packet: &[u8]) -> Vec<u8> {
+    vec![packet[0] & 0xFC]
+}
+
+/// Tracks silent runs to decide which frames may be sent as empty packets.
+pub(crate) struct SilenceGate {
+    enabled: bool,
+    silent_run: u32,
+}
+
+impl SilenceGate {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            silent_run: 0,
+        }
+    }
+
+    /// Returns true if this frame can be replaced by an empty packet.
+    pub(crate) fn skip(&mut self, frame: &[f32]) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if frame.iter().any(|s| s.abs() >= SILENCE_THRESHOLD) {
+            self.silent_run = 0;
+            return false;
+        }
+        self.silent_run += 1;
+        self.silent_run > SILENCE_HANGOVER_FRAMES
+            && (self.silent_run - SILENCE_HANGOVER_FRAMES) % SILENCE_KEEPALIVE_FRAMES != 0
+    }
+}
+
+/// Convert a sample count at `sample_rate` to 48 kHz, the granule rate of
+/// every Ogg Opus stream.
+fn to_48k(samples: usize, sample_rate: u32) -> u64 {
+    samples as u64 * 48_000 / sample_rate as u64
+}
+
+/// Format ms since the Unix epoch as an ISO 8601 UTC timestamp, the usual
+/// form of a Vorbis `DATE` comment.
+pub(crate) fn iso8601_utc(unix_ms: u64) -> String {
+    let secs = unix_ms / 1000;
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        unix_ms % 1000
+    )
+}
+
+/// Encode mono samples at `sample_rate` as a 16-bit PCM WAV file.
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&(OUTPUT_CHANNELS as u16).to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let pcm = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&pcm.to_le_bytes());
+    }
+    wav
+}
+
+/// Playing time of an export in seconds: OggOpus, or WAV with the plain
+/// 44-byte header `encode_wav` writes. `None` for anything else.
+pub fn encoded_duration_seconds(bytes: &[u8]) -> Option<f64> {
+    if bytes.starts_with(b"RIFF") && bytes.get(8..16) == Some(b"WAVEfmt ") {
+        let u16_at = |i: usize| Some(u16::from_le_bytes(bytes.get(i..i + 2)?.try_into().ok()?));
+        let u32_at = |i: usize| Some(u32::from_le_bytes(bytes.get(i..i + 4)?.try_into().ok()?));
+        let frame_bytes = u32::from(u16_at(22)?) * u32::from(u16_at(34)? / 8);
+        let sample_rate = u32_at(24)?;
+        if frame_bytes == 0 || sample_rate == 0 || bytes.get(36..40) != Some(b"data") {
+            return None;
+        }
+        return Some(u32_at(40)? as f64 / frame_bytes as f64 / sample_rate as f64);
+    }
+    if !bytes.starts_with(b"OggS") {
+        return None;
+    }
+    // The last granule position counts 48 kHz samples including pre-skip.
+    let mut reader = ogg::reading::PacketReader::new(Cursor::new(bytes));
+    let head = reader.read_packet().ok()??;
+    if !head.data.starts_with(b"OpusHead") || head.data.len() < 12 {
+        return None;
+    }
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as u64;
+    let mut end_granule = head.absgp_page();
+    while let Ok(Some(packet)) = reader.read_packet() {
+        end_granule = packet.absgp_page();
+    }
+    Some(end_granule.saturating_sub(pre_skip) as f64 / 48_000.0)
+}
+
+/// Encode mono samples at `sample_rate` as Opus inside an OGG container.
+/// `comments` are written into OpusTags as `KEY=value` pairs.
+pub fn encode_ogg_opus(
+    ordered: &[f32],
+    sample_rate: u32,
+    options: &EncodeOptions,
+    comments: &[(String, String)],
+) -> Result<Vec<u8>, String> {
+    let mut encoder = new_opus_encoder(sample_rate, options)?;
+
+    let frame_size: usize = (sample_rate as usize) * 20 / 1000; // 320 samples at 16 kHz (20 ms)
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut silence = SilenceGate::new(options.skip_silence);
+
+    {
+        let mut pw = ogg::writing::PacketWriter::new(&mut cursor);
+        let serial: u32 = 0x504C5545; // "PLUE"
+
+        // -- OpusHead --
+        // Decoders drop the first `pre_skip` samples: exactly the encoder's
+        // algorithmic delay, so decoded audio lines up with the input.
+        let lookahead = encoder
+            .get_lookahead()
+            .map_err(|e| format!("Opus get lookahead: {}", e))?
+            .max(0) as usize;
+        let pre_skip = u16::try_from(to_48k(lookahead, sample_rate))
+            .map_err(|_| format!("Opus lookahead too long: {} samples", lookahead))?;
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1); // version
+        head.push(OUTPUT_CHANNELS as u8);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&sample_rate.to_le_bytes());
+        head.extend_from_slice(&0u16.to_le_bytes()); // output gain
+        head.push(0); // channel mapping family
+        pw.write_packet(
+            head,
+            serial,
+            ogg::writing::PacketWriteEndInfo::EndPage,
+            0,
+        )
+        .map_err(|e| format!("OGG write OpusHead: {}", e))?;
+
+        // -- OpusTags --
+        let vendor = b"runningbord";
+        let mut tags = Vec::new();
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor);
+        tags.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for (key, value) in comments {
+            let comment = format!("{}={}", key, value);
+            tags.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            tags.extend_from_slice(comment.as_bytes());
+        }
+        pw.write_packet(
+            tags,
+            serial,
+            ogg::writing::PacketWriteEndInfo::EndPage,
+            0,
+        )
+        .map_err(|e| format!("OGG write OpusTags: {}", e))?;
+
+        // -- Audio packets --
+        // The input is followed by `lookahead` samples of silence so the
+        // encoder flushes the real tail instead of holding it back.
+        // Granule positions count 48 kHz samples decoded so far, pre-skip
+        // included; the last page instead marks the exact end of the input,
+        // so players trim the padding.
+        let packets = (ordered.len() + lookahead).div_ceil(frame_size);
+        let end_granule = pre_skip as u64 + to_48k(ordered.len(), sample_rate);
+        let mut encode_buf = vec![0u8; 4000]; // max Opus packet
+        let mut padded = vec![0.0f32; frame_size];
+
+        for i in 0..packets {
+            let offset = i * frame_size;
+            let frame = if offset + frame_size <= ordered.len() {
+                &ordered[offset..offset + frame_size]
+            } else {
+                // Final frames: what's left of the input, then silence.
+                let available = ordered.len().saturating_sub(offset);
+                padded.fill(0.0);
+                padded[..available].copy_from_slice(&ordered[offset..offset + available]);
+                &padded[..]
+            };
+            // Always run the encoder so its prediction state stays continuous
+            // across skipped frames.
+            let n = encoder
+                .encode_float(frame, &mut encode_buf)
+                .map_err(|e| format!("Opus encode: {}", e))?;
+
+            let packet = if silence.skip(frame) {
+                empty_packet(&encode_buf[..n])
+            } else {
+                encode_buf[..n].to_vec()
+            };
+            let (end_info, granule_pos) = if i == packets - 1 {
+                (ogg::writing::PacketWriteEndInfo::EndStream, end_granule)
+            } else {
+                (
+                    ogg::writing::PacketWriteEndInfo::NormalPacket,
+                    to_48k(offset + frame_size, sample_rate),
+                )
+            };
+            pw.write_packet(packet, serial, end_info, granule_pos)
+                .map_err(|e| format!("OGG write audio: {}", e))?;
+        }
+        padded.zeroize();
+    }
+
+    Ok(cursor.into_inner())
+}
@@ -2,7 +2,7 @@
 //! Captures the monitor of the default audio sink (system audio output).
 //! Requires PipeWire to be running (default on Ubuntu 24+, Fedora 34+, etc.).
 
-use crate::system_audio::{AudioConverter, SystemAudioState};
+use crate::system_audio::{AudioConverter, CaptureFormat, SystemAudioState};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex as StdMutex};
 use std::thread;
@@ -25,7 +25,8 @@ static CAPTURE_STATE: StdMutex<Option<CaptureHandle>> = StdMutex::new(None);
 
 /// Start capturing system audio via PipeWire.
 /// Creates a PipeWire stream connected to the default audio sink monitor
-/// (i.e. what is being played through speakers), capturing 48 kHz stereo F32.
+/// (i.e. what is being played through speakers), capturing F32 at whatever
+/// rate and channel count the graph negotiates.
 pub async fn start_capture(state: Arc<SystemAudioState>) -> Result<(), String> {
     // Check if already capturing
     {
@@ -93,17 +94,18 @@ fn run_pipewire_capture(
     // "stream.capture.sink" = "true" tells PipeWire to capture what goes to
     // speakers – the monitor port of the default sink.
     // -----------------------------------------------------------------------
-    let stream = pw::stream::Stream::new(
-        &core,
-        "pluely-system-audio",
-        pw::properties::properties! {
-            "media.type" => "Audio",
-            "media.category" => "Capture",
-            "media.role" => "Music",
-            "stream.capture.sink" => "true",
-        },
-    )
-    .map_err(|e| format!("Failed to create PipeWire Stream: {}", e))?;
+    let mut props = pw::properties::properties! {
+        "media.type" => "Audio",
+        "media.category" => "Capture",
+        "media.role" => "Music",
+        "stream.capture.sink" => "true",
+    };
+    // A pinned device names the sink whose monitor we record.
+    if let Some(device) = state.capture_settings()?.device {
+        props.insert("target.object", device);
+    }
+    let stream = pw::stream::Stream::new(&core, "pluely-system-audio", props)
+        .map_err(|e| format!("Failed to create PipeWire Stream: {}", e))?;
 
     // -----------------------------------------------------------------------
     // Build audio format Pod: F32LE only.
@@ -133,12 +135,13 @@ fn run_pipewire_capture(
     // -----------------------------------------------------------------------
     let user_data = UserData {
         format: spa::param::audio::AudioInfoRaw::new(),
-        converter: AudioConverter::new(48000, 2),
+        converter: AudioConverter::new(48000, 2).with_downmix(state.downmix()),
     };
 
+    let format_state = state.clone();
     let _listener = stream
         .add_local_listener_with_user_data(user_data)
-        .param_changed(|_, user_data, id, param| {
+        .param_changed(move |_, user_data, id, param| {
             let Some(param) = param else {
                 return;
             };
@@ -160,6 +163,12 @@ fn run_pipewire_capture(
                 let rate = user_data.format.rate().max(1);
                 let channels = user_data.format.channels().max(1) as u16;
                 user_data.converter.reconfigure(rate, channels);
+                // The EnumFormat pod above only offers F32LE.
+                format_state.set_capture_format(Some(CaptureFormat {
+                    sample_rate: rate,
+                    channels,
+                    sample_format: "f32".to_string(),
+                }));
                 tracing::info!(
                     "PipeWire negotiated format: {} Hz, {} ch",
                     rate,
@@ -168,6 +177,7 @@ fn run_pipewire_capture(
             }
         })
         .process(move |stream, user_data| {
+            let _timer = state.callback_timer();
             match stream.dequeue_buffer() {
                 None => { /* no buffer available this cycle */ }
                 Some(mut buffer) => {
@@ -0,0 +1,181 @@
+//! Loudness and true-peak measurement following ITU-R BS.1770-4 (mono).
+//!
+//! Integrated loudness is K-weighted mean square over gated 400 ms blocks;
+//! true peak is the sample peak of a 4x oversampled copy, which catches
+//! inter-sample overs that a plain sample peak misses.
+
+use crate::system_audio::linear_to_dbfs;
+use serde::Serialize;
+
+/// Block length and hop of the gating analysis.
+const BLOCK_MS: u32 = 400;
+const HOP_MS: u32 = 100;
+/// Blocks quieter than this never count.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks this far below the ungated mean are dropped as well.
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Oversampling factor for true peak, as in BS.1770-4 Annex 2.
+const TRUE_PEAK_OVERSAMPLING: usize = 4;
+/// Interpolation filter taps on either side of each output sample.
+const TRUE_PEAK_HALF_TAPS: usize = 8;
+
+/// Result of `measure_loudness`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Loudness {
+    /// `None` if the audio is shorter than one 400 ms block or every block
+    /// is below the -70 LUFS gate.
+    pub integrated_lufs: Option<f32>,
+    pub true_peak_dbtp: f32,
+    pub sample_peak_dbfs: f32,
+}
+
+/// Measure mono `samples` at `sample_rate`.
+pub fn measure_loudness(samples: &[f32], sample_rate: u32) -> Loudness {
+    let sample_peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    Loudness {
+        integrated_lufs: integrated_loudness(samples, sample_rate).map(|l| l as f32),
+        true_peak_dbtp: linear_to_dbfs(true_peak(samples).max(sample_peak)),
+        sample_peak_dbfs: linear_to_dbfs(sample_peak),
+    }
+}
+
+/// Gated integrated loudness in LUFS.
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32) -> Option<f64> {
+    let block = (sample_rate * BLOCK_MS / 1000) as usize;
+    let hop = (sample_rate * HOP_MS / 1000) as usize;
+    if block == 0 || samples.len() < block {
+        return None;
+    }
+
+    // Squared K-weighted signal, prefix-summed so each block is O(1).
+    let mut filter = KWeighting::new(sample_rate);
+    let mut energy = Vec::with_capacity(samples.len() + 1);
+    energy.push(0.0f64);
+    let mut total = 0.0f64;
+    for s in samples {
+        let y = filter.process(*s as f64);
+        total += y * y;
+        energy.push(total);
+    }
+    let blocks: Vec<f64> = (0..=(samples.len() - block) / hop)
+        .map(|i| (energy[i * hop + block] - energy[i * hop]) / block as f64)
+        .collect();
+
+    let loudness = |mean_square: f64| -0.691 + 10.0 * mean_square.log10();
+    let gated_mean = |gate: f64| {
+        let kept: Vec<f64> = blocks
+            .iter()
+            .copied()
+            .filter(|z| *z > 0.0 && loudness(*z) > gate)
+            .collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+    let ungated = gated_mean(ABSOLUTE_GATE_LUFS)?;
+    let relative_gate = (loudness(ungated) + RELATIVE_GATE_LU).max(ABSOLUTE_GATE_LUFS);
+    gated_mean(relative_gate).map(loudness)
+}
+
+/// Peak of `samples` interpolated at `TRUE_PEAK_OVERSAMPLING` times the
+/// rate with a Hann-windowed sinc. Only the in-between points are computed;
+/// the caller folds in the sample peak.
+fn true_peak(samples: &[f32]) -> f32 {
+    let h = TRUE_PEAK_HALF_TAPS as isize;
+    let phases: Vec<Vec<f32>> = (1..TRUE_PEAK_OVERSAMPLING)
+        .map(|phase| {
+            let t = phase as f64 / TRUE_PEAK_OVERSAMPLING as f64;
+            let taps: Vec<f64> = (-h + 1..=h)
+                .map(|k| {
+                    let x = k as f64 - t;
+                    let sinc = (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x);
+                    let hann = 0.5 + 0.5 * (std::f64::consts::PI * x / h as f64).cos();
+                    sinc * hann
+                })
+                .collect();
+            let sum: f64 = taps.iter().sum();
+            taps.iter().map(|tap| (tap / sum) as f32).collect()
+        })
+        .collect();
+
+    let at = |i: isize| -> f32 {
+        if i < 0 || i as usize >= samples.len() {
+            0.0
+        } else {
+            samples[i as usize]
+        }
+    };
+    let mut peak = 0.0f32;
+    for n in 0..samples.len() as isize {
+        for taps in &phases {
+            let y: f32 = taps
+                .iter()
+                .enumerate()
+                .map(|(j, tap)| tap * at(n - h + 1 + j as isize))
+                .sum();
+            peak = peak.max(y.abs());
+        }
+    }
+    peak
+}
+
+/// The BS.1770 K-weighting filter: a high-shelf "head" filter followed by
+/// an RLB high-pass, designed for any sample rate (libebur128's
+/// formulation of the 48 kHz reference coefficients).
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let fs = sample_rate as f64;
+
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (std::f64::consts::PI * f0 / fs).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (std::f64::consts::PI * f0 / fs).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        Self {
+            stages: [shelf, high_pass],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        self.stages.iter_mut().fold(x, |x, stage| stage.process(x))
+    }
+}
+
+/// Direct form II transposed biquad.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
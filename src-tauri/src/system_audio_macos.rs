@@ -1,20 +1,21 @@
 //! macOS system audio capture using Core Audio Process Tap API (macOS 14.2+).
 //! Falls back to a silence placeholder thread if the tap API is unavailable.
 
-use crate::system_audio::{AudioConverter, SystemAudioState};
-use std::ffi::{c_char, c_void, CStr};
+use crate::system_audio::{AudioConverter, CaptureFormat, RecoveryReason, SystemAudioState};
+use crate::system_audio_backend::{CaptureSettings, MuteBehavior};
+use std::ffi::{c_char, c_void, CStr, CString};
 use std::ptr;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // ObjC types for CATapDescription
 use objc2::rc::Retained;
 use objc2::runtime::AnyClass;
 use objc2::AnyThread;
 use objc2_core_audio::{CATapDescription, CATapMuteBehavior};
-use objc2_foundation::{NSArray, NSNumber};
+use objc2_foundation::{NSArray, NSNumber, NSString};
 
 // ---------------------------------------------------------------------------
 // Raw FFI types
@@ -43,10 +44,18 @@ type AudioIOProc = unsafe extern "C" fn(
 
 type AudioIOProcID = Option<AudioIOProc>;
 
+/// Property listener function pointer type (AudioObjectPropertyListenerProc)
+type AudioObjectPropertyListenerProc = unsafe extern "C" fn(
+    object_id: AudioObjectID,
+    number_addresses: u32,
+    addresses: *const AudioObjectPropertyAddress,
+    client_data: *mut c_void,
+) -> OSStatus;
+
 // Raw AudioBuffer / AudioBufferList for reading in the IO proc callback
 #[repr(C)]
 struct RawAudioBuffer {
-    _number_channels: u32,
+    number_channels: u32,
     data_byte_size: u32,
     data: *mut c_void,
 }
@@ -57,6 +66,21 @@ struct RawAudioBufferList {
     buffers: [RawAudioBuffer; 1], // C flexible array member
 }
 
+// AudioStreamBasicDescription, as returned for the tap / aggregate stream
+#[repr(C)]
+#[derive(Default)]
+struct AudioStreamBasicDescription {
+    sample_rate: f64,
+    format_id: u32,
+    format_flags: u32,
+    bytes_per_packet: u32,
+    frames_per_packet: u32,
+    bytes_per_frame: u32,
+    channels_per_frame: u32,
+    bits_per_channel: u32,
+    _reserved: u32,
+}
+
 // ---------------------------------------------------------------------------
 // CoreAudio C functions (linked through the CoreAudio framework,
 // which objc2-core-audio already links)
@@ -100,11 +124,36 @@ extern "C" {
         data_size: *mut u32,
         data: *mut c_void,
     ) -> OSStatus;
+    fn AudioObjectAddPropertyListener(
+        object_id: AudioObjectID,
+        address: *const AudioObjectPropertyAddress,
+        listener: AudioObjectPropertyListenerProc,
+        client_data: *mut c_void,
+    ) -> OSStatus;
+    fn AudioObjectRemovePropertyListener(
+        object_id: AudioObjectID,
+        address: *const AudioObjectPropertyAddress,
+        listener: AudioObjectPropertyListenerProc,
+        client_data: *mut c_void,
+    ) -> OSStatus;
 }
 
 const K_AUDIO_DEVICE_PROPERTY_NOMINAL_SAMPLE_RATE: u32 = 0x6e73_7274; // 'nsrt'
 const K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL: u32 = 0x676c_6f62; // 'glob'
 const K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN: u32 = 0;
+const K_AUDIO_OBJECT_PROPERTY_SCOPE_INPUT: u32 = 0x696e_7074; // 'inpt'
+const K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT: u32 = 0x6f75_7470; // 'outp'
+const K_AUDIO_OBJECT_SYSTEM_OBJECT: AudioObjectID = 1;
+const K_AUDIO_HARDWARE_PROPERTY_TRANSLATE_PID_TO_PROCESS_OBJECT: u32 = 0x6964_3270; // 'id2p'
+const K_AUDIO_HARDWARE_PROPERTY_SERVICE_RESTARTED: u32 = 0x7372_7374; // 'srst'
+const K_AUDIO_DEVICE_PROPERTY_DEVICE_IS_ALIVE: u32 = 0x6c69_766e; // 'livn'
+const K_AUDIO_TAP_PROPERTY_FORMAT: u32 = 0x7466_6d74; // 'tfmt'
+const K_AUDIO_DEVICE_PROPERTY_STREAM_FORMAT: u32 = 0x7366_6d74; // 'sfmt'
+const K_AUDIO_FORMAT_LINEAR_PCM: u32 = 0x6c70_636d; // 'lpcm'
+const K_AUDIO_FORMAT_FLAG_IS_FLOAT: u32 = 1 << 0;
+const K_AUDIO_FORMAT_FLAG_IS_BIG_ENDIAN: u32 = 1 << 1;
+const K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER: u32 = 1 << 2;
+const K_AUDIO_FORMAT_FLAG_IS_NON_INTERLEAVED: u32 = 1 << 5;
 
 // ---------------------------------------------------------------------------
 // CoreFoundation C functions for building the aggregate device dictionary
@@ -157,24 +206,334 @@ extern "C" {
 }
 
 // ---------------------------------------------------------------------------
-// State for the active tap (singleton – only one tap at a time)
+// State for a running tap
 // ---------------------------------------------------------------------------
 
 struct TapState {
     tap_id: AudioObjectID,
     aggregate_device_id: AudioObjectID,
     io_proc_id: AudioIOProcID,
-    /// Prevent the Arc from being dropped while the IO proc holds a raw ptr.
-    _context_arc: Arc<CallbackContext>,
+    /// What the IO proc and the listeners get as `client_data`.
+    gate: Arc<CallbackGate>,
+    /// Keeps the context alive while the gate hands it to callbacks.
+    context_arc: Arc<CallbackContext>,
 }
 
 unsafe impl Send for TapState {}
 
-static TAP_STATE: StdMutex<Option<TapState>> = StdMutex::new(None);
+impl TapState {
+    /// Quiesce, then destroy the IO proc, the aggregate device and the tap.
+    ///
+    /// `AudioDeviceStop` can return while the IO proc is still running on
+    /// the real-time thread, and a listener may be mid-call, so the
+    /// callback gate is closed first and every callback that got in is
+    /// waited for before anything they point at goes away.
+    unsafe fn teardown(self) {
+        let gate = &self.gate;
+        gate.close();
+        AudioDeviceStop(self.aggregate_device_id, self.io_proc_id);
+        set_tap_listeners(
+            self.tap_id,
+            self.aggregate_device_id,
+            Arc::as_ptr(gate) as *mut c_void,
+            false,
+        );
+        let quiesced = gate.quiesce(QUIESCE_TIMEOUT);
+        if quiesced {
+            gate.context.store(ptr::null_mut(), Ordering::SeqCst);
+        }
+        AudioDeviceDestroyIOProcID(self.aggregate_device_id, self.io_proc_id);
+        AudioHardwareDestroyAggregateDevice(self.aggregate_device_id);
+        AudioHardwareDestroyProcessTap(self.tap_id);
+        if !quiesced {
+            // A callback is stuck holding the context; leak it rather than
+            // free memory it is still reading.
+            tracing::error!("Process Tap callbacks did not finish; leaking their context");
+            std::mem::forget(self.gate);
+            std::mem::forget(self.context_arc);
+        }
+    }
+}
+
+/// The process tap of one capture session. Owned by the `PlatformBackend`
+/// of a `SystemAudioState`, so every state has its own tap and the tap is
+/// torn down when the backend is dropped.
+#[derive(Default)]
+pub struct ProcessTap {
+    /// Held across start and stop, so the two never interleave.
+    active: StdMutex<Option<TapState>>,
+}
+
+impl ProcessTap {
+    /// Start capturing system audio into the given state's ring buffer.
+    /// On macOS 14.2+: uses Core Audio Process Tap API (no virtual driver needed).
+    /// On older macOS: falls back to a silence placeholder thread.
+    pub async fn start(&self, state: Arc<SystemAudioState>) -> Result<(), String> {
+        let mut active = self.active.lock().map_err(|e| e.to_string())?;
+        if let Some(stale) = active.take() {
+            tracing::warn!("Process Tap was still running; tearing it down before restarting");
+            unsafe { stale.teardown() };
+        }
+        // Try the real Process Tap first
+        match try_start_process_tap(state.clone()) {
+            Ok(tap) => {
+                *active = Some(tap);
+                tracing::info!("System audio capture started via Core Audio Process Tap");
+            }
+            Err(e) => {
+                tracing::warn!("Process Tap failed ({}), using silence placeholder", e);
+                start_silence_fallback(state);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop the capture (tear down tap, aggregate device, IO proc).
+    pub async fn stop(&self) {
+        let tap_state = match self.active.lock() {
+            Ok(mut guard) => guard.take(),
+            Err(e) => {
+                tracing::error!("Process Tap mutex poisoned: {}", e);
+                return;
+            }
+        };
 
+        if let Some(ts) = tap_state {
+            unsafe { ts.teardown() };
+            tracing::info!("System audio Process Tap stopped");
+        }
+        // If the silence fallback thread is running, it will exit because
+        // state.recording was set to false in system_audio_stop().
+    }
+}
+
+impl Drop for ProcessTap {
+    fn drop(&mut self) {
+        if let Some(ts) = self.active.get_mut().ok().and_then(Option::take) {
+            unsafe { ts.teardown() };
+        }
+    }
+}
+
+/// Longest teardown waits for in-flight callbacks to return.
+const QUIESCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// What the CoreAudio callbacks get as `client_data`, tracking the ones
+/// running against a `CallbackContext` so teardown can wait for them.
+/// It has its own `Arc`, apart from the context: every callback enters
+/// the gate before it touches the context, and once the gate is closed,
+/// new callbacks return without reaching it.
+struct CallbackGate {
+    closed: AtomicBool,
+    /// Callbacks that have entered, and that have left, since the start.
+    entered: AtomicU64,
+    exited: AtomicU64,
+    /// Owned by the `TapState` alongside the gate.
+    context: AtomicPtr<CallbackContext>,
+}
+
+/// Leaves the gate on drop. Derefs to the context while inside.
+struct GateGuard<'a>(&'a CallbackGate, &'a CallbackContext);
+
+impl Drop for GateGuard<'_> {
+    fn drop(&mut self) {
+        self.0.exited.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl std::ops::Deref for GateGuard<'_> {
+    type Target = CallbackContext;
+
+    fn deref(&self) -> &CallbackContext {
+        self.1
+    }
+}
+
+impl CallbackGate {
+    fn new(context: &Arc<CallbackContext>) -> Self {
+        Self {
+            closed: AtomicBool::new(false),
+            entered: AtomicU64::new(0),
+            exited: AtomicU64::new(0),
+            context: AtomicPtr::new(Arc::as_ptr(context) as *mut CallbackContext),
+        }
+    }
+
+    /// The context, or `None` once the gate is closed.
+    fn enter(&self) -> Option<GateGuard<'_>> {
+        // Count first: a callback counted after `quiesce` takes its
+        // snapshot is guaranteed to see `closed`.
+        self.entered.fetch_add(1, Ordering::SeqCst);
+        // SAFETY: the owning `TapState` keeps the context alive until the
+        // gate is closed and quiesced, and clears the pointer before
+        // dropping it.
+        let context = match self.closed.load(Ordering::SeqCst) {
+            true => None,
+            false => unsafe { self.context.load(Ordering::SeqCst).as_ref() },
+        };
+        match context {
+            Some(context) => Some(GateGuard(self, context)),
+            None => {
+                self.exited.fetch_add(1, Ordering::SeqCst);
+                None
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Wait until every callback that entered before `close` has left.
+    /// False on timeout.
+    fn quiesce(&self, timeout: Duration) -> bool {
+        let target = self.entered.load(Ordering::SeqCst);
+        let deadline = Instant::now() + timeout;
+        while self.exited.load(Ordering::SeqCst) < target {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+}
+
+/// Reached by the callbacks only through a `CallbackGate`.
 struct CallbackContext {
     state: Arc<SystemAudioState>,
     converter: StdMutex<AudioConverter>,
+    /// Current tap format; replaced by the format listener when the output
+    /// device changes rate or layout.
+    format: StdMutex<StreamFormat>,
+    /// Copy the tapped audio to the aggregate's output leg (the monitor
+    /// device).
+    monitor: bool,
+    /// A monitor device is a sub-device of the aggregate, whether or not its
+    /// output is written, so its inputs must be trimmed off.
+    has_monitor_device: bool,
+}
+
+impl CallbackContext {
+    /// Switch to `format`: publish it in state and restart the converter
+    /// at the new rate and channel count. An unknown format leaves the
+    /// converter to configure itself from the first callback.
+    fn apply_format(&self, format: StreamFormat) {
+        if let Ok(mut current) = self.format.lock() {
+            *current = format;
+        }
+        if let Ok(mut converter) = self.converter.lock() {
+            converter.reconfigure(format.sample_rate, format.channels);
+        }
+        self.state.set_capture_format(format.capture_format());
+    }
+}
+
+/// Sample encoding of the tap stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SampleFormat {
+    F32,
+    I16,
+    I24,
+    I32,
+}
+
+impl SampleFormat {
+    fn name(self) -> &'static str {
+        match self {
+            SampleFormat::F32 => "f32",
+            SampleFormat::I16 => "i16",
+            SampleFormat::I24 => "i24",
+            SampleFormat::I32 => "i32",
+        }
+    }
+
+    fn bytes(self) -> usize {
+        match self {
+            SampleFormat::F32 | SampleFormat::I32 => 4,
+            SampleFormat::I24 => 3,
+            SampleFormat::I16 => 2,
+        }
+    }
+}
+
+/// The parts of the stream's ASBD the IO proc needs to read its buffers.
+#[derive(Debug, Clone, Copy)]
+struct StreamFormat {
+    sample_rate: u32,
+    channels: u16,
+    sample: SampleFormat,
+}
+
+impl StreamFormat {
+    /// What the tap delivers when the format can't be queried.
+    const FALLBACK: StreamFormat = StreamFormat {
+        sample_rate: 0,
+        channels: 0,
+        sample: SampleFormat::F32,
+    };
+
+    fn from_asbd(asbd: &AudioStreamBasicDescription) -> Result<Self, String> {
+        if asbd.format_id != K_AUDIO_FORMAT_LINEAR_PCM {
+            return Err(format!(
+                "unsupported tap format id {:#x} (expected linear PCM)",
+                asbd.format_id
+            ));
+        }
+        let float = asbd.format_flags & K_AUDIO_FORMAT_FLAG_IS_FLOAT != 0;
+        let signed = asbd.format_flags & K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER != 0;
+        let sample = match (float, signed, asbd.bits_per_channel) {
+            (true, _, 32) => SampleFormat::F32,
+            (false, true, 16) => SampleFormat::I16,
+            (false, true, 24) => SampleFormat::I24,
+            (false, true, 32) => SampleFormat::I32,
+            (_, _, bits) => {
+                return Err(format!(
+                    "unsupported tap sample format ({} bit, flags {:#x})",
+                    bits, asbd.format_flags
+                ))
+            }
+        };
+        // Samples are decoded in native byte order.
+        let big_endian = asbd.format_flags & K_AUDIO_FORMAT_FLAG_IS_BIG_ENDIAN != 0;
+        if big_endian != cfg!(target_endian = "big") {
+            return Err(format!(
+                "unsupported tap byte order ({} endian)",
+                if big_endian { "big" } else { "little" }
+            ));
+        }
+        let planar = asbd.format_flags & K_AUDIO_FORMAT_FLAG_IS_NON_INTERLEAVED != 0;
+        let per_buffer_channels = if planar {
+            1
+        } else {
+            asbd.channels_per_frame.max(1) as usize
+        };
+        // Padded layouts (e.g. 24-in-32) aren't decoded; only packed samples.
+        if asbd.bytes_per_frame as usize != sample.bytes() * per_buffer_channels {
+            return Err(format!(
+                "unsupported tap frame layout ({} bytes per frame for {} channel(s) of {} bit)",
+                asbd.bytes_per_frame, per_buffer_channels, asbd.bits_per_channel
+            ));
+        }
+        let sample_rate = if asbd.sample_rate.is_finite() && asbd.sample_rate > 0.0 {
+            asbd.sample_rate.round() as u32
+        } else {
+            0
+        };
+        Ok(Self {
+            sample_rate,
+            channels: asbd.channels_per_frame.min(u16::MAX as u32) as u16,
+            sample,
+        })
+    }
+
+    fn capture_format(&self) -> Option<CaptureFormat> {
+        (self.sample_rate != 0 && self.channels != 0).then(|| CaptureFormat {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            sample_format: self.sample.name().to_string(),
+        })
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -186,7 +545,7 @@ unsafe extern "C" fn audio_io_proc_callback(
     _now: *const c_void,
     input_data: *const c_void,
     _input_time: *const c_void,
-    _output_data: *mut c_void,
+    output_data: *mut c_void,
     _output_time: *const c_void,
     client_data: *mut c_void,
 ) -> OSStatus {
@@ -196,10 +555,14 @@ unsafe extern "C" fn audio_io_proc_callback(
         return 0;
     }
 
-    let context = &*(client_data as *const CallbackContext);
+    let gate = &*(client_data as *const CallbackGate);
+    let Some(context) = gate.enter() else {
+        return 0;
+    };
     if !context.state.is_recording() {
         return 0;
     }
+    let _timer = context.state.callback_timer();
 
     let buf_list = &*(input_data as *const RawAudioBufferList);
     let n = buf_list.number_buffers as usize;
@@ -214,38 +577,46 @@ unsafe extern "C" fn audio_io_proc_callback(
     let mut source_channels: u16 = 0;
     let mut empty_buffers: usize = 0;
 
-    // One buffer with multiple channels (interleaved)
-    if n == 1 {
-        let buf = &buffers[0];
-        if !buf.data.is_null() && buf.data_byte_size > 0 {
-            let num_samples = buf.data_byte_size as usize / std::mem::size_of::<f32>();
-            let samples = std::slice::from_raw_parts(buf.data as *const f32, num_samples);
-            interleaved.extend_from_slice(samples);
-            source_channels = buf._number_channels.max(1) as u16;
-        } else {
+    // Each buffer holds `mNumberChannels` interleaved channels: a single
+    // buffer for an interleaved stream, one per channel for a planar one,
+    // or one per sub-stream of the aggregate. Channels are numbered across
+    // buffers in order, so stitch every buffer's frames back together.
+    // Skip the cycle rather than block while the format listener swaps formats.
+    let (sample, tap_channels) = match context.format.try_lock() {
+        Ok(format) => (format.sample, format.channels as usize),
+        Err(_) => return 0,
+    };
+    let buffers = if context.has_monitor_device {
+        tap_buffers(buffers, tap_channels)
+    } else {
+        buffers
+    };
+    let mut streams: Vec<(Vec<f32>, usize)> = Vec::with_capacity(n);
+    let mut min_frames = usize::MAX;
+    for buf in buffers {
+        if buf.data.is_null() || buf.data_byte_size == 0 {
             empty_buffers += 1;
+            continue;
         }
-    } else {
-        // Multiple buffers are typically planar channels. Interleave by frame.
-        let mut planes: Vec<&[f32]> = Vec::new();
-        let mut min_samples = usize::MAX;
-        for buf in buffers {
-            if buf.data.is_null() || buf.data_byte_size == 0 {
-                empty_buffers += 1;
-                continue;
-            }
-            let num_samples = buf.data_byte_size as usize / std::mem::size_of::<f32>();
-            let samples = std::slice::from_raw_parts(buf.data as *const f32, num_samples);
-            min_samples = min_samples.min(samples.len());
-            planes.push(samples);
-        }
-
-        if !planes.is_empty() && min_samples != usize::MAX {
-            source_channels = planes.len() as u16;
-            interleaved.reserve(min_samples * planes.len());
-            for i in 0..min_samples {
-                for p in &planes {
-                    interleaved.push(p[i]);
+        let channels = buf.number_channels.max(1) as usize;
+        let bytes = std::slice::from_raw_parts(buf.data as *const u8, buf.data_byte_size as usize);
+        let samples = decode_samples(bytes, sample);
+        min_frames = min_frames.min(samples.len() / channels);
+        streams.push((samples, channels));
+    }
+
+    if !streams.is_empty() && min_frames != usize::MAX {
+        let total_channels: usize = streams.iter().map(|(_, ch)| ch).sum();
+        source_channels = total_channels.min(u16::MAX as usize) as u16;
+        if streams.len() == 1 {
+            let (mut samples, _) = streams.pop().unwrap();
+            samples.truncate(min_frames * total_channels);
+            interleaved = samples;
+        } else {
+            interleaved.reserve(min_frames * total_channels);
+            for i in 0..min_frames {
+                for (samples, ch) in &streams {
+                    interleaved.extend_from_slice(&samples[i * ch..(i + 1) * ch]);
                 }
             }
         }
@@ -291,9 +662,16 @@ unsafe extern "C" fn audio_io_proc_callback(
         );
     }
 
+    if context.monitor && !output_data.is_null() {
+        write_monitor_output(output_data, &interleaved, source_channels as usize);
+    }
+
     if let Ok(mut converter) = context.converter.try_lock() {
         // Lazily initialize converter only after capture has started and we
         // have real callback format data.
+        // Normally configured from the tap's format up front; if that
+        // couldn't be read, fall back to the device's nominal rate and the
+        // channel count of the buffers we actually got.
         if converter.source_sample_rate() == 0 || converter.source_channels() == 0 {
             let actual_rate = query_device_sample_rate(device).unwrap_or(48000);
             converter.reconfigure(actual_rate, source_channels.max(1));
@@ -311,6 +689,78 @@ unsafe extern "C" fn audio_io_proc_callback(
     0 // noErr
 }
 
+/// With a monitor device in the aggregate, its own inputs (e.g. a headset
+/// mic) come ahead of the tap's channels; keep only the trailing buffers
+/// that make up the tap's `tap_channels`. Unknown channel counts keep all.
+fn tap_buffers(buffers: &[RawAudioBuffer], tap_channels: usize) -> &[RawAudioBuffer] {
+    if tap_channels == 0 {
+        return buffers;
+    }
+    let mut remaining: usize = buffers
+        .iter()
+        .map(|buf| buf.number_channels.max(1) as usize)
+        .sum();
+    let mut start = 0;
+    while start < buffers.len() && remaining > tap_channels {
+        remaining -= buffers[start].number_channels.max(1) as usize;
+        start += 1;
+    }
+    &buffers[start..]
+}
+
+/// Write the tapped frames (`source_channels` wide) to the aggregate's
+/// float32 output buffers. Output channels are numbered across buffers like
+/// the input ones; output channel `c` plays tapped channel
+/// `c % source_channels`. Frames beyond the tapped ones stay silent.
+unsafe fn write_monitor_output(
+    output_data: *mut c_void,
+    interleaved: &[f32],
+    source_channels: usize,
+) {
+    let list = &mut *(output_data as *mut RawAudioBufferList);
+    let buffers =
+        std::slice::from_raw_parts_mut(list.buffers.as_mut_ptr(), list.number_buffers as usize);
+    let mut first_channel = 0;
+    for buf in buffers {
+        let channels = buf.number_channels.max(1) as usize;
+        if !buf.data.is_null() {
+            let out = std::slice::from_raw_parts_mut(
+                buf.data as *mut f32,
+                buf.data_byte_size as usize / std::mem::size_of::<f32>(),
+            );
+            for (frame, input) in out
+                .chunks_exact_mut(channels)
+                .zip(interleaved.chunks_exact(source_channels))
+            {
+                for (c, sample) in frame.iter_mut().enumerate() {
+                    *sample = input[(first_channel + c) % source_channels];
+                }
+            }
+        }
+        first_channel += channels;
+    }
+}
+
+/// Whether the aggregate's output leg takes float32, the only format
+/// `write_monitor_output` writes. An unreported format is assumed to be the
+/// HAL's usual float32.
+unsafe fn monitor_output_supported(aggregate_device_id: AudioObjectID) -> bool {
+    match query_asbd(
+        aggregate_device_id,
+        K_AUDIO_DEVICE_PROPERTY_STREAM_FORMAT,
+        K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT,
+    ) {
+        Some(asbd) => matches!(
+            StreamFormat::from_asbd(&asbd),
+            Ok(StreamFormat {
+                sample: SampleFormat::F32,
+                ..
+            })
+        ),
+        None => true,
+    }
+}
+
 unsafe fn query_device_sample_rate(device_id: AudioObjectID) -> Option<u32> {
     let address = AudioObjectPropertyAddress {
         m_selector: K_AUDIO_DEVICE_PROPERTY_NOMINAL_SAMPLE_RATE,
@@ -334,6 +784,221 @@ unsafe fn query_device_sample_rate(device_id: AudioObjectID) -> Option<u32> {
     }
 }
 
+/// Convert raw linear PCM bytes to f32 samples in [-1, 1].
+fn decode_samples(bytes: &[u8], sample: SampleFormat) -> Vec<f32> {
+    match sample {
+        SampleFormat::F32 => bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        SampleFormat::I16 => bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_ne_bytes([b[0], b[1]]) as f32 / 32_768.0)
+            .collect(),
+        // Native byte order like the rest, placed in the top three bytes of
+        // an i32 so the sign carries.
+        SampleFormat::I24 => bytes
+            .chunks_exact(3)
+            .map(|b| {
+                let value = if cfg!(target_endian = "big") {
+                    i32::from_be_bytes([b[0], b[1], b[2], 0])
+                } else {
+                    i32::from_le_bytes([0, b[0], b[1], b[2]])
+                };
+                value as f32 / 2_147_483_648.0
+            })
+            .collect(),
+        SampleFormat::I32 => bytes
+            .chunks_exact(4)
+            .map(|b| i32::from_ne_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+    }
+}
+
+/// Called by CoreAudio (not on the IO thread) when the tap's format
+/// changes, e.g. the output device was switched from 48 kHz to 44.1 kHz.
+unsafe extern "C" fn tap_format_listener(
+    tap_id: AudioObjectID,
+    _number_addresses: u32,
+    _addresses: *const AudioObjectPropertyAddress,
+    client_data: *mut c_void,
+) -> OSStatus {
+    if client_data.is_null() {
+        return 0;
+    }
+    let gate = &*(client_data as *const CallbackGate);
+    let Some(context) = gate.enter() else {
+        return 0;
+    };
+    let asbd = query_asbd(
+        tap_id,
+        K_AUDIO_TAP_PROPERTY_FORMAT,
+        K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    match asbd.map(|asbd| StreamFormat::from_asbd(&asbd)) {
+        Some(Ok(format)) => {
+            tracing::info!(
+                sample_rate = format.sample_rate,
+                channels = format.channels,
+                sample = ?format.sample,
+                "Process tap format changed"
+            );
+            context.apply_format(format);
+        }
+        Some(Err(e)) => tracing::error!("Process tap switched to {}", e),
+        None => tracing::warn!("Process tap format changed but could not be read"),
+    }
+    0
+}
+
+fn global_address(selector: u32) -> AudioObjectPropertyAddress {
+    AudioObjectPropertyAddress {
+        m_selector: selector,
+        m_scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+        m_element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+    }
+}
+
+/// Called by CoreAudio when the aggregate device dies or coreaudiod
+/// restarts. Either way the tap is gone; hand it to the capture watchdog.
+unsafe extern "C" fn device_health_listener(
+    object_id: AudioObjectID,
+    number_addresses: u32,
+    addresses: *const AudioObjectPropertyAddress,
+    client_data: *mut c_void,
+) -> OSStatus {
+    if client_data.is_null() || addresses.is_null() {
+        return 0;
+    }
+    let gate = &*(client_data as *const CallbackGate);
+    let Some(context) = gate.enter() else {
+        return 0;
+    };
+    let addresses = std::slice::from_raw_parts(addresses, number_addresses as usize);
+    for address in addresses {
+        match address.m_selector {
+            K_AUDIO_HARDWARE_PROPERTY_SERVICE_RESTARTED => {
+                tracing::warn!("coreaudiod restarted, requesting tap recovery");
+                context
+                    .state
+                    .request_recovery(RecoveryReason::ServiceRestarted);
+            }
+            K_AUDIO_DEVICE_PROPERTY_DEVICE_IS_ALIVE if !device_is_alive(object_id) => {
+                tracing::warn!("Tap aggregate device died, requesting tap recovery");
+                context.state.request_recovery(RecoveryReason::DeviceLost);
+            }
+            _ => {}
+        }
+    }
+    0
+}
+
+unsafe fn device_is_alive(device_id: AudioObjectID) -> bool {
+    let address = global_address(K_AUDIO_DEVICE_PROPERTY_DEVICE_IS_ALIVE);
+    let mut alive: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = AudioObjectGetPropertyData(
+        device_id,
+        &address,
+        0,
+        ptr::null(),
+        &mut size,
+        (&mut alive as *mut u32).cast(),
+    );
+    status == 0 && alive != 0
+}
+
+/// Register (or, with `add == false`, remove) the property listeners that
+/// follow the tap's format and the health of the devices behind it.
+unsafe fn set_tap_listeners(
+    tap_id: AudioObjectID,
+    aggregate_device_id: AudioObjectID,
+    client_data: *mut c_void,
+    add: bool,
+) {
+    let listeners: [(AudioObjectID, u32, AudioObjectPropertyListenerProc); 3] = [
+        (tap_id, K_AUDIO_TAP_PROPERTY_FORMAT, tap_format_listener),
+        (
+            aggregate_device_id,
+            K_AUDIO_DEVICE_PROPERTY_DEVICE_IS_ALIVE,
+            device_health_listener,
+        ),
+        (
+            K_AUDIO_OBJECT_SYSTEM_OBJECT,
+            K_AUDIO_HARDWARE_PROPERTY_SERVICE_RESTARTED,
+            device_health_listener,
+        ),
+    ];
+    for (object_id, selector, listener) in listeners {
+        let address = global_address(selector);
+        let status = if add {
+            AudioObjectAddPropertyListener(object_id, &address, listener, client_data)
+        } else {
+            AudioObjectRemovePropertyListener(object_id, &address, listener, client_data)
+        };
+        if status != 0 {
+            tracing::warn!(
+                "{} property listener {:#x} failed with status {}",
+                if add { "Adding" } else { "Removing" },
+                selector,
+                status
+            );
+        }
+    }
+}
+
+/// Read an AudioStreamBasicDescription property.
+unsafe fn query_asbd(
+    object_id: AudioObjectID,
+    selector: u32,
+    scope: u32,
+) -> Option<AudioStreamBasicDescription> {
+    let address = AudioObjectPropertyAddress {
+        m_selector: selector,
+        m_scope: scope,
+        m_element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+    };
+    let mut asbd = AudioStreamBasicDescription::default();
+    let mut size = std::mem::size_of::<AudioStreamBasicDescription>() as u32;
+    let status = AudioObjectGetPropertyData(
+        object_id,
+        &address,
+        0,
+        ptr::null(),
+        &mut size,
+        (&mut asbd as *mut AudioStreamBasicDescription).cast(),
+    );
+    (status == 0 && size as usize == std::mem::size_of::<AudioStreamBasicDescription>())
+        .then_some(asbd)
+}
+
+/// The format the IO proc will receive: the tap's own format, or the
+/// aggregate device's input stream format if the tap doesn't report one.
+unsafe fn query_stream_format(
+    tap_id: AudioObjectID,
+    aggregate_device_id: AudioObjectID,
+) -> Result<StreamFormat, String> {
+    let asbd = query_asbd(
+        tap_id,
+        K_AUDIO_TAP_PROPERTY_FORMAT,
+        K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    )
+    .or_else(|| {
+        query_asbd(
+            aggregate_device_id,
+            K_AUDIO_DEVICE_PROPERTY_STREAM_FORMAT,
+            K_AUDIO_OBJECT_PROPERTY_SCOPE_INPUT,
+        )
+    });
+    match asbd {
+        Some(asbd) => StreamFormat::from_asbd(&asbd),
+        None => {
+            tracing::warn!("Could not query tap stream format; assuming float32");
+            Ok(StreamFormat::FALLBACK)
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Helper: build the CFDictionary for AudioHardwareCreateAggregateDevice
 // ---------------------------------------------------------------------------
@@ -347,30 +1012,114 @@ unsafe fn cf_str(s: &[u8]) -> *const c_void {
     )
 }
 
+/// Look up the CoreAudio process object for `pid`. Processes that have never
+/// opened an audio client don't have one.
+unsafe fn process_object_for_pid(pid: i32) -> Option<AudioObjectID> {
+    let address = AudioObjectPropertyAddress {
+        m_selector: K_AUDIO_HARDWARE_PROPERTY_TRANSLATE_PID_TO_PROCESS_OBJECT,
+        m_scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+        m_element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+    };
+    let mut object_id: AudioObjectID = 0;
+    let mut size = std::mem::size_of::<AudioObjectID>() as u32;
+    let status = AudioObjectGetPropertyData(
+        K_AUDIO_OBJECT_SYSTEM_OBJECT,
+        &address,
+        std::mem::size_of::<i32>() as u32,
+        (&pid as *const i32).cast(),
+        &mut size,
+        (&mut object_id as *mut AudioObjectID).cast(),
+    );
+    (status == 0 && object_id != 0).then_some(object_id)
+}
+
+/// Describe the tap for the current capture settings: every process except
+/// the excluded ones, mixed to stereo or taken from a pinned output device.
+/// Unless `capture_own_audio` is set, this process is excluded too, so
+/// spoken answers and the monitor output aren't tapped back into the
+/// capture.
+unsafe fn build_tap_description(settings: &CaptureSettings) -> Retained<CATapDescription> {
+    let own_pid = (!settings.capture_own_audio).then(|| std::process::id() as i32);
+    let excluded: Vec<Retained<NSNumber>> = settings
+        .excluded_pids
+        .iter()
+        .copied()
+        .chain(own_pid)
+        .filter_map(|pid| match process_object_for_pid(pid) {
+            Some(object_id) => Some(NSNumber::new_u32(object_id)),
+            None => {
+                tracing::debug!(pid, "No audio process object for excluded pid, skipping");
+                None
+            }
+        })
+        .collect();
+    let excluded = NSArray::from_retained_slice(&excluded);
+
+    let tap_desc = match &settings.device {
+        // Stream 0 of the device's output: its native channel layout, which
+        // the IO proc reads from the tap format.
+        Some(device_uid) => CATapDescription::initExcludingProcesses_andDeviceUID_withStream(
+            CATapDescription::alloc(),
+            &excluded,
+            &NSString::from_str(device_uid),
+            0,
+        ),
+        None => CATapDescription::initStereoGlobalTapButExcludeProcesses(
+            CATapDescription::alloc(),
+            &excluded,
+        ),
+    };
+
+    tap_desc.setMuteBehavior(match settings.mute_behavior {
+        MuteBehavior::Unmuted => CATapMuteBehavior::Unmuted,
+        MuteBehavior::Muted => CATapMuteBehavior::Muted,
+        MuteBehavior::MutedWhenTapped => CATapMuteBehavior::MutedWhenTapped,
+    });
+    tap_desc
+}
+
+/// Build a `{ "uid": <uid>, "drift": 1 }` entry for the aggregate's tap or
+/// sub-device list, with drift compensation so either can run off the
+/// other's clock. Returns a CFDictionaryRef that the caller must CFRelease.
+unsafe fn build_sub_entry_dict(uid_cstr: *const c_char) -> *const c_void {
+    let key_cb = core::ptr::addr_of!(kCFTypeDictionaryKeyCallBacks) as *const c_void;
+    let val_cb = core::ptr::addr_of!(kCFTypeDictionaryValueCallBacks) as *const c_void;
+
+    let uid_key = cf_str(b"uid\0");
+    let drift_key = cf_str(b"drift\0");
+    let uid_val = CFStringCreateWithCString(ptr::null(), uid_cstr, CFSTR_ENCODING_UTF8);
+    let one: i32 = 1;
+    let drift_val = CFNumberCreate(
+        ptr::null(),
+        CF_NUMBER_SINT32_TYPE,
+        &one as *const i32 as *const c_void,
+    );
+    let keys = [uid_key, drift_key];
+    let vals = [uid_val, drift_val];
+    let dict = CFDictionaryCreate(ptr::null(), keys.as_ptr(), vals.as_ptr(), 2, key_cb, val_cb);
+    CFRelease(uid_key);
+    CFRelease(drift_key);
+    CFRelease(uid_val);
+    CFRelease(drift_val);
+    dict
+}
+
 /// Build the aggregate device description dictionary.
 /// The dictionary includes the tap (identified by `tap_uuid_cstr`) and is
-/// configured as a private device with auto-start.
+/// configured as a private device with auto-start. With a monitor device
+/// UID, that device is added as the aggregate's sub-device and clock, so
+/// the IO proc's output goes to it.
 /// Returns a CFDictionaryRef that the caller must CFRelease.
-unsafe fn build_aggregate_device_dict(tap_uuid_cstr: *const c_char) -> *const c_void {
+unsafe fn build_aggregate_device_dict(
+    tap_uuid_cstr: *const c_char,
+    monitor_uid_cstr: Option<&CStr>,
+) -> *const c_void {
     let key_cb = core::ptr::addr_of!(kCFTypeDictionaryKeyCallBacks) as *const c_void;
     let val_cb = core::ptr::addr_of!(kCFTypeDictionaryValueCallBacks) as *const c_void;
     let arr_cb = core::ptr::addr_of!(kCFTypeArrayCallBacks) as *const c_void;
 
-    // --- Sub-dict for the tap entry: { "uid": "<tap_uuid>" } ---
-    let sub_uid_key = cf_str(b"uid\0");
-    let sub_uid_val = CFStringCreateWithCString(ptr::null(), tap_uuid_cstr, CFSTR_ENCODING_UTF8);
-    let sub_keys = [sub_uid_key];
-    let sub_vals = [sub_uid_val];
-    let sub_dict = CFDictionaryCreate(
-        ptr::null(),
-        sub_keys.as_ptr(),
-        sub_vals.as_ptr(),
-        1,
-        key_cb,
-        val_cb,
-    );
-    CFRelease(sub_uid_key);
-    CFRelease(sub_uid_val);
+    // --- Sub-dict for the tap entry: { "uid": "<tap_uuid>", "drift": 1 } ---
+    let sub_dict = build_sub_entry_dict(tap_uuid_cstr);
 
     // --- Tap list array: [ sub_dict ] ---
     let arr_vals: [*const c_void; 1] = [sub_dict];
@@ -395,56 +1144,65 @@ unsafe fn build_aggregate_device_dict(tap_uuid_cstr: *const c_char) -> *const c_
     // tap_array is already created above
     let autostart_val = kCFBooleanTrue;
 
-    let keys = [uid_key, name_key, private_key, taps_key, autostart_key];
-    let vals = [
+    let mut keys = vec![uid_key, name_key, private_key, taps_key, autostart_key];
+    let mut vals = vec![
         uid_val,
         name_val,
         private_val,
         tap_array as *const c_void,
         autostart_val,
     ];
+    // Everything but kCFBooleanTrue is ours to release once the dict holds it.
+    let mut owned: Vec<*const c_void> = vec![
+        uid_key,
+        name_key,
+        private_key,
+        taps_key,
+        autostart_key,
+        uid_val,
+        name_val,
+        private_val,
+        tap_array,
+    ];
 
-    let dict = CFDictionaryCreate(ptr::null(), keys.as_ptr(), vals.as_ptr(), 5, key_cb, val_cb);
+    // --- Monitor output: "subdevices": [ { "uid": ... } ], "master": uid ---
+    if let Some(monitor_uid) = monitor_uid_cstr {
+        let monitor_entry = build_sub_entry_dict(monitor_uid.as_ptr());
+        let entries: [*const c_void; 1] = [monitor_entry];
+        let subdevices = CFArrayCreate(ptr::null(), entries.as_ptr(), 1, arr_cb);
+        CFRelease(monitor_entry);
+        let subdevices_key = cf_str(b"subdevices\0");
+        let master_key = cf_str(b"master\0");
+        let master_val =
+            CFStringCreateWithCString(ptr::null(), monitor_uid.as_ptr(), CFSTR_ENCODING_UTF8);
+        keys.extend([subdevices_key, master_key]);
+        vals.extend([subdevices, master_val]);
+        owned.extend([subdevices_key, master_key, subdevices, master_val]);
+    }
+
+    let dict = CFDictionaryCreate(
+        ptr::null(),
+        keys.as_ptr(),
+        vals.as_ptr(),
+        keys.len() as isize,
+        key_cb,
+        val_cb,
+    );
 
     // Release our refs (the dict retains what it needs)
-    CFRelease(uid_key);
-    CFRelease(name_key);
-    CFRelease(private_key);
-    CFRelease(taps_key);
-    CFRelease(autostart_key);
-    CFRelease(uid_val);
-    CFRelease(name_val);
-    CFRelease(private_val);
-    CFRelease(tap_array);
-    // autostart_val (kCFBooleanTrue) is a global constant – don't release
+    for cf in owned {
+        CFRelease(cf);
+    }
 
     dict
 }
 
 // ---------------------------------------------------------------------------
-// Public API
+// Tap setup
 // ---------------------------------------------------------------------------
 
-/// Start capturing system audio into the given state's ring buffer.
-/// On macOS 14.2+: uses Core Audio Process Tap API (no virtual driver needed).
-/// On older macOS: falls back to a silence placeholder thread.
-pub async fn start_capture(state: Arc<SystemAudioState>) -> Result<(), String> {
-    // Try the real Process Tap first
-    match try_start_process_tap(state.clone()) {
-        Ok(()) => {
-            tracing::info!("System audio capture started via Core Audio Process Tap");
-            Ok(())
-        }
-        Err(e) => {
-            tracing::warn!("Process Tap failed ({}), using silence placeholder", e);
-            start_silence_fallback(state);
-            Ok(())
-        }
-    }
-}
-
 /// Attempt to start real system audio capture via the Core Audio Process Tap API.
-fn try_start_process_tap(state: Arc<SystemAudioState>) -> Result<(), String> {
+fn try_start_process_tap(state: Arc<SystemAudioState>) -> Result<TapState, String> {
     // Runtime check: CATapDescription class must exist (macOS 14.2+)
     let cls_name =
         CStr::from_bytes_with_nul(b"CATapDescription\0").expect("invalid CStr");
@@ -454,16 +1212,11 @@ fn try_start_process_tap(state: Arc<SystemAudioState>) -> Result<(), String> {
         );
     }
 
-    unsafe {
-        // 1. Create tap description – stereo global tap of all processes
-        let empty_array: Retained<NSArray<NSNumber>> = NSArray::new();
-        let tap_desc = CATapDescription::initStereoGlobalTapButExcludeProcesses(
-            CATapDescription::alloc(),
-            &empty_array,
-        );
+    let settings = state.capture_settings()?;
 
-        // Audio should still play through speakers (unmuted)
-        tap_desc.setMuteBehavior(CATapMuteBehavior::Unmuted);
+    unsafe {
+        // 1. Create tap description from the capture settings
+        let tap_desc = build_tap_description(&settings);
 
         // 2. Create the process tap
         let mut tap_id: AudioObjectID = 0;
@@ -488,7 +1241,15 @@ fn try_start_process_tap(state: Arc<SystemAudioState>) -> Result<(), String> {
         }
 
         // 4. Build the aggregate device dictionary and create the device
-        let agg_dict = build_aggregate_device_dict(uuid_cstr);
+        let monitor_uid = match settings.monitor_device.as_deref().map(CString::new) {
+            Some(Ok(uid)) => Some(uid),
+            Some(Err(_)) => {
+                AudioHardwareDestroyProcessTap(tap_id);
+                return Err("monitor_device contains a NUL byte".to_string());
+            }
+            None => None,
+        };
+        let agg_dict = build_aggregate_device_dict(uuid_cstr, monitor_uid.as_deref());
         if agg_dict.is_null() {
             AudioHardwareDestroyProcessTap(tap_id);
             return Err("Failed to create aggregate device dictionary".to_string());
@@ -506,12 +1267,38 @@ fn try_start_process_tap(state: Arc<SystemAudioState>) -> Result<(), String> {
             ));
         }
 
-        // 5. Register our IO proc callback on the aggregate device
+        // 5. Find out what the IO proc will actually be handed. Taps on
+        //    surround outputs can deliver more than two channels.
+        let format = match query_stream_format(tap_id, agg_device_id) {
+            Ok(format) => format,
+            Err(e) => {
+                AudioHardwareDestroyAggregateDevice(agg_device_id);
+                AudioHardwareDestroyProcessTap(tap_id);
+                return Err(e);
+            }
+        };
+        tracing::info!(
+            sample_rate = format.sample_rate,
+            channels = format.channels,
+            sample = ?format.sample,
+            "Process tap stream format"
+        );
+        let monitor = monitor_uid.is_some() && monitor_output_supported(agg_device_id);
+        if monitor_uid.is_some() && !monitor {
+            tracing::warn!("Monitor device output isn't float32; monitoring disabled");
+        }
+
+        // 6. Register our IO proc callback on the aggregate device
         let callback_context = Arc::new(CallbackContext {
             state: state.clone(),
-            converter: StdMutex::new(AudioConverter::new(0, 0)),
+            converter: StdMutex::new(AudioConverter::new(0, 0).with_downmix(state.downmix())),
+            format: StdMutex::new(format),
+            monitor,
+            has_monitor_device: monitor_uid.is_some(),
         });
-        let state_ptr = Arc::as_ptr(&callback_context) as *mut c_void;
+        callback_context.apply_format(format);
+        let gate = Arc::new(CallbackGate::new(&callback_context));
+        let state_ptr = Arc::as_ptr(&gate) as *mut c_void;
         let mut io_proc_id: AudioIOProcID = None;
         let status = AudioDeviceCreateIOProcID(
             agg_device_id,
@@ -528,7 +1315,7 @@ fn try_start_process_tap(state: Arc<SystemAudioState>) -> Result<(), String> {
             ));
         }
 
-        // 6. Start the device – audio will now flow through the callback
+        // 7. Start the device – audio will now flow through the callback
         let status = AudioDeviceStart(agg_device_id, io_proc_id);
         if status != 0 {
             AudioDeviceDestroyIOProcID(agg_device_id, io_proc_id);
@@ -540,17 +1327,19 @@ fn try_start_process_tap(state: Arc<SystemAudioState>) -> Result<(), String> {
             ));
         }
 
-        // 7. Store state for cleanup
-        let mut guard = TAP_STATE.lock().map_err(|e| e.to_string())?;
-        *guard = Some(TapState {
+        // 8. Follow format changes and device health for the rest of the
+        //    session
+        set_tap_listeners(tap_id, agg_device_id, state_ptr, true);
+
+        // 9. Hand the handles to the caller for cleanup
+        Ok(TapState {
             tap_id,
             aggregate_device_id: agg_device_id,
             io_proc_id,
-            _context_arc: callback_context,
-        });
+            gate,
+            context_arc: callback_context,
+        })
     }
-
-    Ok(())
 }
 
 /// Fallback: run a thread that pushes silence into the ring buffer so the
@@ -568,29 +1357,3 @@ fn start_silence_fallback(state: Arc<SystemAudioState>) {
     });
     state.store_capture_handle(handle);
 }
-
-/// Stop the capture (tear down tap, aggregate device, IO proc).
-pub async fn stop_capture() {
-    let tap_state = {
-        let mut guard = match TAP_STATE.lock() {
-            Ok(g) => g,
-            Err(e) => {
-                tracing::error!("TAP_STATE mutex poisoned: {}", e);
-                return;
-            }
-        };
-        guard.take()
-    };
-
-    if let Some(ts) = tap_state {
-        unsafe {
-            AudioDeviceStop(ts.aggregate_device_id, ts.io_proc_id);
-            AudioDeviceDestroyIOProcID(ts.aggregate_device_id, ts.io_proc_id);
-            AudioHardwareDestroyAggregateDevice(ts.aggregate_device_id);
-            AudioHardwareDestroyProcessTap(ts.tap_id);
-        }
-        tracing::info!("System audio Process Tap stopped");
-    }
-    // If the silence fallback thread is running, it will exit because
-    // state.recording was set to false in system_audio_stop().
-}
@@ -0,0 +1,200 @@
+//! System memory lookup, and the ring buffer length it can afford.
+//!
+//! Every export copies the whole buffered window of 16 kHz f32 samples a few
+//! times over (window copy, DSP, encoder input), so long buffers are capped
+//! when memory is short, and "auto" picks a shorter default on small
+//! machines.
+//!
+//! On Linux: `/proc/meminfo` (total and available).
+//! On Windows: `GlobalMemoryStatusEx` (total and available).
+//! On macOS: `hw.memsize` (total only; the budget falls back to a share of
+//! it).
+
+use crate::system_audio::OUTPUT_SAMPLE_RATE;
+
+/// Export working copies, with headroom, per buffered second.
+const BYTES_PER_BUFFERED_SECOND: u64 = OUTPUT_SAMPLE_RATE as u64 * 4 * 4;
+/// Share of available memory the buffer may use.
+const AVAILABLE_SHARE: u64 = 16;
+/// Share of total memory the buffer may use when availability is unknown.
+const TOTAL_SHARE: u64 = 64;
+/// Memory is never allowed to cap the buffer below this.
+const MIN_CAPPED_SECONDS: u32 = 30;
+/// Machines with this much memory or less get the short auto length.
+const SMALL_MACHINE_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+const SMALL_MACHINE_AUTO_SECONDS: u32 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemMemory {
+    pub total_bytes: u64,
+    /// Memory that can be handed out without swapping, if the OS says.
+    pub available_bytes: Option<u64>,
+}
+
+impl SystemMemory {
+    /// Longest buffer, in seconds, that fits the memory budget.
+    fn affordable_seconds(&self) -> u32 {
+        let budget = match self.available_bytes {
+            Some(available) => available / AVAILABLE_SHARE,
+            None => self.total_bytes / TOTAL_SHARE,
+        };
+        (budget / BYTES_PER_BUFFERED_SECOND).min(u32::MAX as u64) as u32
+    }
+}
+
+/// Buffer length picked by `choose_buffer_seconds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferChoice {
+    pub seconds: u32,
+    /// The length asked for (or picked by auto) when memory forced a
+    /// shorter one.
+    pub capped_from: Option<u32>,
+}
+
+/// Buffer length for a session: `requested` (or an automatic length when
+/// `None`) clamped to `1..=max_seconds` and to what `memory` can afford.
+pub fn choose_buffer_seconds(
+    requested: Option<u32>,
+    max_seconds: u32,
+    memory: Option<&SystemMemory>,
+) -> BufferChoice {
+    let wanted = match requested {
+        Some(seconds) => seconds,
+        None if memory.is_some_and(|m| m.total_bytes <= SMALL_MACHINE_BYTES) => {
+            SMALL_MACHINE_AUTO_SECONDS
+        }
+        None => max_seconds,
+    }
+    .clamp(1, max_seconds);
+    let affordable = memory
+        .map(|m| m.affordable_seconds().max(MIN_CAPPED_SECONDS))
+        .unwrap_or(u32::MAX);
+    if wanted > affordable {
+        BufferChoice {
+            seconds: affordable,
+            capped_from: Some(wanted),
+        }
+    } else {
+        BufferChoice {
+            seconds: wanted,
+            capped_from: None,
+        }
+    }
+}
+
+/// Total and available physical memory, if the platform reports it.
+pub fn system_memory() -> Option<SystemMemory> {
+    platform::system_memory()
+}
+
+/// Parse `MemTotal` and `MemAvailable` (in kB) from `/proc/meminfo`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_meminfo(text: &str) -> Option<SystemMemory> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| {
+                value
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            })
+            .map(|kb| kb * 1024)
+    };
+    Some(SystemMemory {
+        total_bytes: field("MemTotal")?,
+        available_bytes: field("MemAvailable"),
+    })
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{parse_meminfo, SystemMemory};
+
+    pub fn system_memory() -> Option<SystemMemory> {
+        parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::SystemMemory;
+    use std::ffi::{c_char, c_void};
+
+    extern "C" {
+        fn sysctlbyname(
+            name: *const c_char,
+            oldp: *mut c_void,
+            oldlenp: *mut usize,
+            newp: *const c_void,
+            newlen: usize,
+        ) -> i32;
+    }
+
+    pub fn system_memory() -> Option<SystemMemory> {
+        let mut total: u64 = 0;
+        let mut len = std::mem::size_of::<u64>();
+        let status = unsafe {
+            sysctlbyname(
+                b"hw.memsize\0".as_ptr() as *const c_char,
+                &mut total as *mut u64 as *mut c_void,
+                &mut len,
+                std::ptr::null(),
+                0,
+            )
+        };
+        (status == 0 && total > 0).then_some(SystemMemory {
+            total_bytes: total,
+            available_bytes: None,
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::SystemMemory;
+
+    #[repr(C)]
+    struct MemoryStatusEx {
+        length: u32,
+        memory_load: u32,
+        total_phys: u64,
+        avail_phys: u64,
+        total_page_file: u64,
+        avail_page_file: u64,
+        total_virtual: u64,
+        avail_virtual: u64,
+        avail_extended_virtual: u64,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GlobalMemoryStatusEx(buffer: *mut MemoryStatusEx) -> i32;
+    }
+
+    pub fn system_memory() -> Option<SystemMemory> {
+        let mut status: MemoryStatusEx = unsafe { std::mem::zeroed() };
+        status.length = std::mem::size_of::<MemoryStatusEx>() as u32;
+        if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+            return None;
+        }
+        Some(SystemMemory {
+            total_bytes: status.total_phys,
+            available_bytes: Some(status.avail_phys),
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::SystemMemory;
+
+    pub fn system_memory() -> Option<SystemMemory> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,99 @@
+use super::*;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+fn memory(total_gib: u64, available_mib: Option<u64>) -> SystemMemory {
+    SystemMemory {
+        total_bytes: total_gib * GIB,
+        available_bytes: available_mib.map(|mib| mib * 1024 * 1024),
+    }
+}
+
+#[test]
+fn auto_picks_a_shorter_buffer_on_small_machines() {
+    let small = memory(8, Some(4096));
+    let large = memory(32, Some(16384));
+    assert_eq!(
+        choose_buffer_seconds(None, 300, Some(&small)),
+        BufferChoice {
+            seconds: SMALL_MACHINE_AUTO_SECONDS,
+            capped_from: None,
+        }
+    );
+    assert_eq!(choose_buffer_seconds(None, 300, Some(&large)).seconds, 300);
+    assert_eq!(choose_buffer_seconds(None, 300, None).seconds, 300);
+}
+
+#[test]
+fn requested_length_is_capped_by_available_memory() {
+    // 256 MiB free: a sixteenth of it buys 65 s.
+    let tight = memory(8, Some(256));
+    assert_eq!(
+        choose_buffer_seconds(Some(300), 300, Some(&tight)),
+        BufferChoice {
+            seconds: 65,
+            capped_from: Some(300),
+        }
+    );
+    assert_eq!(
+        choose_buffer_seconds(Some(60), 300, Some(&tight)),
+        BufferChoice {
+            seconds: 60,
+            capped_from: None,
+        }
+    );
+}
+
+#[test]
+fn memory_never_caps_below_the_floor() {
+    let starved = memory(4, Some(16));
+    assert_eq!(
+        choose_buffer_seconds(Some(300), 300, Some(&starved)),
+        BufferChoice {
+            seconds: MIN_CAPPED_SECONDS,
+            capped_from: Some(300),
+        }
+    );
+    // Asking for less than the floor is left alone.
+    assert_eq!(
+        choose_buffer_seconds(Some(10), 300, Some(&starved)).seconds,
+        10
+    );
+}
+
+#[test]
+fn total_memory_is_used_when_availability_is_unknown() {
+    // 1 GiB total: a sixty-fourth of it buys 65 s.
+    let unknown = memory(1, None);
+    assert_eq!(
+        choose_buffer_seconds(Some(300), 300, Some(&unknown)).seconds,
+        65
+    );
+}
+
+#[test]
+fn requested_length_is_clamped_to_the_maximum() {
+    assert_eq!(choose_buffer_seconds(Some(900), 300, None).seconds, 300);
+    assert_eq!(choose_buffer_seconds(Some(0), 300, None).seconds, 1);
+}
+
+#[test]
+fn meminfo_is_parsed() {
+    let text =
+        "MemTotal:       16303428 kB\nMemFree:         1183796 kB\nMemAvailable:    9623520 kB\n";
+    assert_eq!(
+        parse_meminfo(text),
+        Some(SystemMemory {
+            total_bytes: 16303428 * 1024,
+            available_bytes: Some(9623520 * 1024),
+        })
+    );
+    assert_eq!(
+        parse_meminfo("MemTotal: 1024 kB\n"),
+        Some(SystemMemory {
+            total_bytes: 1024 * 1024,
+            available_bytes: None,
+        })
+    );
+    assert_eq!(parse_meminfo("MemFree: 1 kB\n"), None);
+}
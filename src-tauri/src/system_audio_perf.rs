@@ -0,0 +1,226 @@
+//! Performance counters for diagnosing capture cost in the field: how long
+//! backend callbacks take, how long exports take to encode, and how much
+//! CPU the process has used since the session started.
+//!
+//! Counters are lock-free so callbacks on the real-time audio thread can
+//! record into them.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Running count, total and maximum of one kind of timed work.
+#[derive(Default)]
+pub struct Timing {
+    count: AtomicU64,
+    total_us: AtomicU64,
+    max_us: AtomicU64,
+    last_us: AtomicU64,
+}
+
+impl Timing {
+    pub fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+        self.last_us.store(us, Ordering::Relaxed);
+    }
+
+    /// Record the time until the returned guard is dropped.
+    pub fn start(&self) -> TimingGuard<'_> {
+        TimingGuard {
+            timing: self,
+            started: Instant::now(),
+        }
+    }
+
+    fn reset(&self) {
+        self.count.store(0, Ordering::Relaxed);
+        self.total_us.store(0, Ordering::Relaxed);
+        self.max_us.store(0, Ordering::Relaxed);
+        self.last_us.store(0, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> TimingStats {
+        let count = self.count.load(Ordering::Relaxed);
+        let total_us = self.total_us.load(Ordering::Relaxed);
+        TimingStats {
+            count,
+            total_ms: total_us as f64 / 1000.0,
+            mean_us: if count == 0 {
+                0.0
+            } else {
+                total_us as f64 / count as f64
+            },
+            max_us: self.max_us.load(Ordering::Relaxed),
+            last_us: self.last_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Records into its `Timing` when dropped, so early returns are counted.
+pub struct TimingGuard<'a> {
+    timing: &'a Timing,
+    started: Instant,
+}
+
+impl Drop for TimingGuard<'_> {
+    fn drop(&mut self) {
+        self.timing.record(self.started.elapsed());
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingStats {
+    pub count: u64,
+    pub total_ms: f64,
+    pub mean_us: f64,
+    pub max_us: u64,
+    pub last_us: u64,
+}
+
+/// Per-session performance counters, reset at every capture start.
+#[derive(Default)]
+pub struct PerfCounters {
+    /// Backend callbacks: native format decode, conversion to 16 kHz mono
+    /// and the ring write.
+    pub callback: Timing,
+    /// DSP and encoding of each export, in all its formats.
+    pub encode: Timing,
+    /// Audio encoded by `encode`, in microseconds.
+    encoded_audio_us: AtomicU64,
+    /// Wall clock and process CPU time at session start.
+    baseline: Mutex<Option<(Instant, Option<Duration>)>>,
+}
+
+impl PerfCounters {
+    pub fn reset(&self) {
+        self.callback.reset();
+        self.encode.reset();
+        self.encoded_audio_us.store(0, Ordering::Relaxed);
+        if let Ok(mut baseline) = self.baseline.lock() {
+            *baseline = Some((Instant::now(), process_cpu_time()));
+        }
+    }
+
+    /// Count `audio` as encoded, for the realtime factor.
+    pub fn add_encoded_audio(&self, audio: Duration) {
+        let us = audio.as_micros().min(u64::MAX as u128) as u64;
+        self.encoded_audio_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> PerfStats {
+        let cpu = process_cpu_time();
+        let baseline = self.baseline.lock().ok().and_then(|b| *b);
+        let since_start = baseline.and_then(|(started, cpu_at_start)| {
+            let wall = started.elapsed().as_secs_f64();
+            let used = cpu?.checked_sub(cpu_at_start?)?.as_secs_f64();
+            (wall > 0.0).then(|| used / wall * 100.0)
+        });
+        let encode = self.encode.stats();
+        let encoded_audio_ms = self.encoded_audio_us.load(Ordering::Relaxed) as f64 / 1000.0;
+        PerfStats {
+            process_cpu_ms: cpu.map(|c| c.as_secs_f64() * 1000.0),
+            session_cpu_percent: since_start,
+            callback: self.callback.stats(),
+            encode_realtime_factor: (encode.total_ms > 0.0)
+                .then(|| encoded_audio_ms / encode.total_ms),
+            encode,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PerfStats {
+    /// CPU time (user + system) used by the whole process so far.
+    pub process_cpu_ms: Option<f64>,
+    /// Process CPU usage since the session started, in percent of one core.
+    pub session_cpu_percent: Option<f64>,
+    pub callback: TimingStats,
+    pub encode: TimingStats,
+    /// Seconds of audio encoded per second spent encoding.
+    pub encode_realtime_factor: Option<f64>,
+}
+
+/// CPU time (user + system) used by this process so far.
+pub fn process_cpu_time() -> Option<Duration> {
+    platform::process_cpu_time()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::time::Duration;
+
+    #[repr(C)]
+    struct Timespec {
+        tv_sec: i64,
+        tv_nsec: i64,
+    }
+
+    extern "C" {
+        fn clock_gettime(clock_id: i32, tp: *mut Timespec) -> i32;
+    }
+
+    #[cfg(target_os = "macos")]
+    const CLOCK_PROCESS_CPUTIME_ID: i32 = 12;
+    #[cfg(not(target_os = "macos"))]
+    const CLOCK_PROCESS_CPUTIME_ID: i32 = 2;
+
+    pub fn process_cpu_time() -> Option<Duration> {
+        let mut ts = Timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        if unsafe { clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &mut ts) } != 0 {
+            return None;
+        }
+        Some(Duration::new(
+            ts.tv_sec.max(0) as u64,
+            ts.tv_nsec.clamp(0, 999_999_999) as u32,
+        ))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::time::Duration;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> *mut c_void;
+        fn GetProcessTimes(
+            process: *mut c_void,
+            creation: *mut u64,
+            exit: *mut u64,
+            kernel: *mut u64,
+            user: *mut u64,
+        ) -> i32;
+    }
+
+    pub fn process_cpu_time() -> Option<Duration> {
+        let (mut creation, mut exit, mut kernel, mut user) = (0u64, 0u64, 0u64, 0u64);
+        let ok = unsafe {
+            GetProcessTimes(
+                GetCurrentProcess(),
+                &mut creation,
+                &mut exit,
+                &mut kernel,
+                &mut user,
+            )
+        };
+        // FILETIME counts 100 ns intervals.
+        (ok != 0).then(|| Duration::from_nanos((kernel + user).saturating_mul(100)))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::time::Duration;
+
+    pub fn process_cpu_time() -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,107 @@
+//! Magnitude spectrum of the most recent capture audio, for the live
+//! spectrum analyzer.
+
+use crate::system_audio::linear_to_dbfs;
+use serde::Serialize;
+use zeroize::Zeroize;
+
+/// FFT length: 16384 samples, about one second at 16 kHz.
+pub const SPECTRUM_FFT_SIZE: usize = 1 << 14;
+/// Lowest frequency of the first band.
+const MIN_FREQUENCY_HZ: f32 = 20.0;
+/// Limits on the number of bands a caller may ask for.
+pub const MIN_SPECTRUM_BANDS: usize = 8;
+pub const MAX_SPECTRUM_BANDS: usize = 1024;
+
+/// Log-spaced bands from 20 Hz to Nyquist, from `system_audio_spectrum`.
+#[derive(Clone, Serialize)]
+pub struct Spectrum {
+    pub sample_rate: u32,
+    pub fft_size: usize,
+    /// Centre frequency of each band.
+    pub frequencies_hz: Vec<f32>,
+    /// Peak magnitude within each band in dBFS; a full-scale sine reads 0.
+    pub magnitudes_db: Vec<f32>,
+}
+
+/// Spectrum of the last `SPECTRUM_FFT_SIZE` of `samples` (zero-padded at
+/// the front if shorter), Hann-windowed and folded into `bands` bands.
+pub fn spectrum(samples: &[f32], sample_rate: u32, bands: usize) -> Spectrum {
+    let n = SPECTRUM_FFT_SIZE;
+    let tail = &samples[samples.len().saturating_sub(n)..];
+    let offset = n - tail.len();
+    let window = |i: usize| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / n as f32).cos();
+    let mut re = vec![0.0f32; n];
+    let mut im = vec![0.0f32; n];
+    for (i, s) in tail.iter().enumerate() {
+        re[offset + i] = s * window(offset + i);
+    }
+    fft(&mut re, &mut im);
+
+    // A full-scale sine puts half the window's sum into its bin.
+    let scale = 2.0 / (n as f32 * 0.5);
+    let magnitudes: Vec<f32> = (0..=n / 2)
+        .map(|k| (re[k] * re[k] + im[k] * im[k]).sqrt() * scale)
+        .collect();
+    re.zeroize();
+    im.zeroize();
+
+    let bin_hz = sample_rate as f32 / n as f32;
+    let nyquist = sample_rate as f32 / 2.0;
+    let ratio = (nyquist / MIN_FREQUENCY_HZ).powf(1.0 / bands as f32);
+    let mut frequencies_hz = Vec::with_capacity(bands);
+    let mut magnitudes_db = Vec::with_capacity(bands);
+    for band in 0..bands {
+        let lo = MIN_FREQUENCY_HZ * ratio.powi(band as i32);
+        let hi = lo * ratio;
+        let centre = (lo * hi).sqrt();
+        let first = (lo / bin_hz).ceil() as usize;
+        let last = ((hi / bin_hz).floor() as usize).min(n / 2);
+        // Bands narrower than one bin take the nearest bin.
+        let peak = if first <= last {
+            magnitudes[first..=last].iter().copied().fold(0.0, f32::max)
+        } else {
+            magnitudes[((centre / bin_hz).round() as usize).min(n / 2)]
+        };
+        frequencies_hz.push(centre);
+        magnitudes_db.push(linear_to_dbfs(peak));
+    }
+
+    Spectrum {
+        sample_rate,
+        fft_size: n,
+        frequencies_hz,
+        magnitudes_db,
+    }
+}
+
+/// In-place iterative radix-2 FFT; `re.len()` must be a power of two.
+pub(crate) fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -std::f64::consts::TAU / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (w_re, w_im) = (cos as f32, sin as f32);
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -3,8 +3,9 @@
 //! on Windows 10/11. Building an input stream on an output device triggers WASAPI's
 //! `AUDCLNT_STREAMFLAGS_LOOPBACK` mode automatically.
 
-use crate::system_audio::{AudioConverter, SystemAudioState};
+use crate::system_audio::{AudioConverter, CaptureFormat, RecoveryReason, SystemAudioState};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
 use std::sync::{Arc, Mutex as StdMutex};
 
 /// Holds the active cpal loopback stream. Dropping it stops capture.
@@ -33,9 +34,16 @@ pub async fn start_capture(state: Arc<SystemAudioState>) -> Result<(), String> {
 
     let host = cpal::default_host();
 
-    let device = host
-        .default_output_device()
-        .ok_or_else(|| "No default output audio device found".to_string())?;
+    let device = match state.capture_settings()?.device {
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| format!("Failed to list output devices: {}", e))?
+            .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| format!("Output device '{}' not found", name))?,
+        None => host
+            .default_output_device()
+            .ok_or_else(|| "No default output audio device found".to_string())?,
+    };
 
     let supported_config = device
         .default_output_config()
@@ -43,41 +51,50 @@ pub async fn start_capture(state: Arc<SystemAudioState>) -> Result<(), String> {
 
     let device_sample_rate = supported_config.sample_rate().0;
     let device_channels = supported_config.channels() as u16;
+    let sample_format = supported_config.sample_format();
 
     tracing::info!(
         "WASAPI loopback: device config {} Hz, {} ch, {:?}",
         device_sample_rate,
         device_channels,
-        supported_config.sample_format()
+        sample_format
     );
 
     let stream_config: cpal::StreamConfig = supported_config.into();
 
-    // Build an input stream on the output device → WASAPI loopback mode.
-    // The data callback receives interleaved f32 samples.
-    let state_clone = state.clone();
-    let converter = Arc::new(StdMutex::new(AudioConverter::new(
-        device_sample_rate,
-        device_channels,
-    )));
-    let converter_clone = converter.clone();
-    let stream = device
-        .build_input_stream(
-            &stream_config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if let Ok(mut conv) = converter_clone.try_lock() {
-                    let converted = conv.convert_interleaved(data);
-                    if !converted.is_empty() {
-                        state_clone.push_samples_realtime(&converted);
-                    }
-                }
-            },
-            |err| {
-                tracing::error!("WASAPI loopback stream error: {}", err);
-            },
-            None, // no timeout
-        )
-        .map_err(|e| format!("Failed to build WASAPI loopback stream: {}", e))?;
+    // Build an input stream on the output device → WASAPI loopback mode, in
+    // the device's own sample format; samples are converted to f32 before
+    // downmixing and resampling.
+    let converter = Arc::new(StdMutex::new(
+        AudioConverter::new(device_sample_rate, device_channels).with_downmix(state.downmix()),
+    ));
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => {
+            build_loopback_stream::<f32>(&device, &stream_config, state.clone(), converter)
+        }
+        cpal::SampleFormat::I16 => {
+            build_loopback_stream::<i16>(&device, &stream_config, state.clone(), converter)
+        }
+        cpal::SampleFormat::I32 => {
+            build_loopback_stream::<i32>(&device, &stream_config, state.clone(), converter)
+        }
+        cpal::SampleFormat::U16 => {
+            build_loopback_stream::<u16>(&device, &stream_config, state.clone(), converter)
+        }
+        other => {
+            return Err(format!(
+                "Unsupported WASAPI loopback sample format: {}",
+                other
+            ))
+        }
+    }
+    .map_err(|e| format!("Failed to build WASAPI loopback stream: {}", e))?;
+
+    state.set_capture_format(Some(CaptureFormat {
+        sample_rate: device_sample_rate,
+        channels: device_channels,
+        sample_format: sample_format.to_string(),
+    }));
 
     stream
         .play()
@@ -91,6 +108,44 @@ pub async fn start_capture(state: Arc<SystemAudioState>) -> Result<(), String> {
     Ok(())
 }
 
+/// Build the loopback stream for samples of type `T`.
+fn build_loopback_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    state: Arc<SystemAudioState>,
+    converter: Arc<StdMutex<AudioConverter>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let mut samples: Vec<f32> = Vec::new();
+    let error_state = state.clone();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let _timer = state.callback_timer();
+            if let Ok(mut conv) = converter.try_lock() {
+                samples.clear();
+                samples.extend(data.iter().map(|s| s.to_sample::<f32>()));
+                let converted = conv.convert_interleaved(&samples);
+                if !converted.is_empty() {
+                    state.push_samples_realtime(&converted);
+                }
+            }
+        },
+        move |err| {
+            tracing::error!("WASAPI loopback stream error: {}", err);
+            // The stream is dead once its device goes away; let the
+            // watchdog rebuild it.
+            if let cpal::StreamError::DeviceNotAvailable = err {
+                error_state.request_recovery(RecoveryReason::DeviceLost);
+            }
+        },
+        None, // no timeout
+    )
+}
+
 /// Stop the WASAPI loopback capture. Dropping the stream handle releases all
 /// WASAPI / COM resources.
 pub async fn stop_capture() {
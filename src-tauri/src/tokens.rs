@@ -0,0 +1,76 @@
+//! Token counts with the bundled OpenAI tokenizers, so context can be
+//! budgeted in tokens rather than characters. Other providers' tokenizers
+//! aren't public; their counts come from `cl100k_base`, which is close
+//! enough for budgeting and flagged as not exact.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tiktoken_rs::CoreBPE;
+
+static O200K: Lazy<CoreBPE> =
+    Lazy::new(|| tiktoken_rs::o200k_base().expect("bundled o200k_base ranks"));
+static CL100K: Lazy<CoreBPE> =
+    Lazy::new(|| tiktoken_rs::cl100k_base().expect("bundled cl100k_base ranks"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    O200kBase,
+    Cl100kBase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenCount {
+    pub tokens: usize,
+    pub encoding: Encoding,
+    /// The model uses `encoding`; otherwise the count is an estimate.
+    pub exact: bool,
+}
+
+/// The encoding `model` uses, and whether it's really that model's. Provider
+/// prefixes like `openai/` are ignored.
+fn encoding_for(model: &str) -> (Encoding, bool) {
+    let model = model.trim().to_ascii_lowercase();
+    let model = model.rsplit('/').next().unwrap_or_default();
+    const O200K_PREFIXES: [&str; 7] = ["gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "o1", "o3", "o4"];
+    const CL100K_PREFIXES: [&str; 4] = [
+        "gpt-4",
+        "gpt-3.5",
+        "text-embedding-3",
+        "text-embedding-ada-002",
+    ];
+    if O200K_PREFIXES.iter().any(|p| model.starts_with(p)) {
+        (Encoding::O200kBase, true)
+    } else if CL100K_PREFIXES.iter().any(|p| model.starts_with(p)) {
+        (Encoding::Cl100kBase, true)
+    } else {
+        (Encoding::Cl100kBase, false)
+    }
+}
+
+/// Tokens in `text` for `model`. Special tokens in `text` count as plain
+/// text, as providers treat them in user content.
+pub fn count(text: &str, model: &str) -> TokenCount {
+    let (encoding, exact) = encoding_for(model);
+    let bpe = match encoding {
+        Encoding::O200kBase => &*O200K,
+        Encoding::Cl100kBase => &*CL100K,
+    };
+    TokenCount {
+        tokens: bpe.encode_ordinary(text).len(),
+        encoding,
+        exact,
+    }
+}
+
+/// Count the tokens `text` takes for `model`.
+#[tauri::command]
+pub async fn count_tokens(text: String, model: String) -> Result<TokenCount, String> {
+    tauri::async_runtime::spawn_blocking(move || count(&text, &model))
+        .await
+        .map_err(|e| format!("Task panicked: {}", e))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn models_map_to_their_encoding() {
+    assert_eq!(encoding_for("gpt-4o-mini"), (Encoding::O200kBase, true));
+    assert_eq!(encoding_for("openai/GPT-4.1"), (Encoding::O200kBase, true));
+    assert_eq!(encoding_for("o3-mini"), (Encoding::O200kBase, true));
+    assert_eq!(encoding_for("gpt-4-turbo"), (Encoding::Cl100kBase, true));
+    assert_eq!(encoding_for("gpt-3.5-turbo"), (Encoding::Cl100kBase, true));
+}
+
+#[test]
+fn other_models_are_estimated() {
+    assert_eq!(
+        encoding_for("claude-sonnet-4"),
+        (Encoding::Cl100kBase, false)
+    );
+    assert_eq!(
+        encoding_for("google/gemini-2.5-pro"),
+        (Encoding::Cl100kBase, false)
+    );
+    assert_eq!(encoding_for(""), (Encoding::Cl100kBase, false));
+}
+
+#[test]
+fn text_is_counted() {
+    assert_eq!(count("", "gpt-4o").tokens, 0);
+    assert_eq!(count("hello world", "gpt-4o").tokens, 2);
+    assert_eq!(count("hello world", "gpt-4").tokens, 2);
+    // Special tokens are plain text.
+    assert!(count("<|endoftext|>", "gpt-4").tokens > 1);
+}
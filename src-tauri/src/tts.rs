@@ -0,0 +1,700 @@
+//! Text-to-speech for assistant answers. Providers implement `TtsProvider`;
+//! OpenAI and ElevenLabs are built in, Piper runs offline (see
+//! `tts_piper`) and the OS's own voices need no setup (see `tts_native`).
+//! API keys live in the credential store (see `secrets`), never in the
+//! config.
+//!
+//! Every provider streams raw PCM. `tts_speak` plays it on an output device
+//! as it arrives, so an answer starts playing with the first chunk rather
+//! than once it's fully synthesized. `tts_synthesize` returns the whole
+//! answer as WAV instead, for the webview to play or save. Spoken answers
+//! go through `audio_session`, which ducks other audio if enabled. On
+//! macOS the system-audio tap leaves out this app's own output (see
+//! `CaptureSettings::capture_own_audio`), so answers aren't transcribed;
+//! elsewhere the capture is paused while an answer plays.
+//!
+//! Emits `tts-finished` when a spoken answer has played to the end and
+//! `tts-error` if its stream fails part way.
+
+use crate::audio_session;
+use crate::network;
+use crate::playback::find_output_device;
+use crate::secrets;
+use crate::system_audio::{SystemAudioState, PAUSE_REASON_OWN_SPEECH};
+use crate::system_audio_encoder::encode_wav;
+use crate::tts_native::{NativeProvider, NativeSpeechOptions};
+use crate::tts_piper::PiperProvider;
+use base64::Engine;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdout, Command};
+
+/// Covers the whole streamed body, not just the first byte.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(180);
+/// OpenAI and ElevenLabs stream PCM at this rate.
+const CLOUD_SAMPLE_RATE: u32 = 24_000;
+/// Bytes read from a local synthesizer's output at a time.
+const READ_CHUNK: usize = 8192;
+/// How often the player thread checks for stop and end of speech.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long capture stays paused after an answer stops, for the last of it
+/// to come through the loopback.
+const CAPTURE_TAIL: Duration = Duration::from_millis(300);
+/// Longest text one request takes (OpenAI's limit).
+const MAX_TEXT_CHARS: usize = 4096;
+
+/// Speech as it's synthesized: 16-bit little-endian mono PCM, in chunks of
+/// any length.
+pub struct SpeechStream {
+    pub sample_rate: u32,
+    pub pcm: BoxStream<'static, Result<Vec<u8>, String>>,
+}
+
+/// Speech synthesis service.
+pub trait TtsProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Start synthesizing `text`.
+    fn synthesize(&self, text: String) -> BoxFuture<'static, Result<SpeechStream, String>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TtsProviderConfig {
+    /// The `/v1/audio/speech` endpoint; the key is the `openai` provider's
+    /// (`provider.openai.api_key`).
+    #[serde(rename = "openai")]
+    OpenAi { model: String, voice: String },
+    /// The streaming text-to-speech endpoint; the key is the `elevenlabs`
+    /// provider's (`provider.elevenlabs.api_key`).
+    #[serde(rename = "elevenlabs", rename_all = "camelCase")]
+    ElevenLabs { voice_id: String, model_id: String },
+    /// A local Piper voice from `tts_piper_download_voice`, spoken by the
+    /// `piper` executable (`binary`, or `piper` on the PATH).
+    #[serde(rename = "piper")]
+    Piper {
+        voice: String,
+        #[serde(default)]
+        binary: Option<String>,
+    },
+    /// A system voice: AVSpeechSynthesizer on macOS, SAPI on Windows.
+    #[serde(rename = "native")]
+    Native(NativeSpeechOptions),
+}
+
+impl TtsProviderConfig {
+    fn validate(&self) -> Result<(), String> {
+        let fields = match self {
+            Self::OpenAi { model, voice } => [("model", model), ("voice", voice)],
+            Self::ElevenLabs { voice_id, model_id } => {
+                [("voiceId", voice_id), ("modelId", model_id)]
+            }
+            Self::Piper { voice, binary } => {
+                crate::tts_piper::parse_voice_name(voice)?;
+                if binary.as_ref().is_some_and(|b| b.trim().is_empty()) {
+                    return Err("binary must not be empty".to_string());
+                }
+                return Ok(());
+            }
+            Self::Native(options) => return options.validate(),
+        };
+        for (name, value) in fields {
+            if value.trim().is_empty() {
+                return Err(format!("{} must not be empty", name));
+            }
+        }
+        Ok(())
+    }
+
+    fn build(&self, app: &AppHandle) -> Result<Arc<dyn TtsProvider>, String> {
+        match self {
+            Self::OpenAi { model, voice } => Ok(Arc::new(OpenAiProvider {
+                client: network::client(app, Some("openai"))?,
+                url: network::provider_url(
+                    app,
+                    Some("openai"),
+                    "https://api.openai.com/v1/audio/speech",
+                )?,
+                api_key: secrets::require(&secrets::api_key_secret("openai")?)?,
+                model: model.clone(),
+                voice: voice.clone(),
+            })),
+            Self::ElevenLabs { voice_id, model_id } => {
+                let mut url = reqwest::Url::parse("https://api.elevenlabs.io/v1/text-to-speech")
+                    .expect("valid ElevenLabs URL");
+                url.path_segments_mut()
+                    .expect("ElevenLabs URL takes a path")
+                    .extend([voice_id.as_str(), "stream"]);
+                Ok(Arc::new(ElevenLabsProvider {
+                    client: network::client(app, Some("elevenlabs"))?,
+                    url: network::provider_url(app, Some("elevenlabs"), url.as_str())?,
+                    api_key: secrets::require(&secrets::api_key_secret("elevenlabs")?)?,
+                    model_id: model_id.clone(),
+                }))
+            }
+            Self::Piper { voice, binary } => {
+                Ok(Arc::new(PiperProvider::new(app, voice, binary.as_deref())?))
+            }
+            Self::Native(options) => Ok(Arc::new(NativeProvider::new(options.clone()))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TtsConfig {
+    pub provider: Option<TtsProviderConfig>,
+    /// Output device name for `tts_speak` (one of
+    /// `playback_output_devices`); the default output if `None`.
+    pub device: Option<String>,
+}
+
+/// Send a cloud provider's request and stream its PCM response.
+async fn send(request: reqwest::RequestBuilder) -> Result<SpeechStream, String> {
+    let response = request
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("TTS request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("TTS failed ({}): {}", status, body.trim()));
+    }
+    let pcm = response
+        .bytes_stream()
+        .map(|chunk| {
+            chunk
+                .map(|bytes| bytes.to_vec())
+                .map_err(|e| format!("TTS stream failed: {}", e))
+        })
+        .boxed();
+    Ok(SpeechStream {
+        sample_rate: CLOUD_SAMPLE_RATE,
+        pcm,
+    })
+}
+
+struct OpenAiProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    model: String,
+    voice: String,
+}
+
+impl TtsProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn synthesize(&self, text: String) -> BoxFuture<'static, Result<SpeechStream, String>> {
+        let request =
+            self.client
+                .post(&self.url)
+                .bearer_auth(&self.api_key)
+                .json(&serde_json::json!({
+                    "model": self.model,
+                    "voice": self.voice,
+                    "input": text,
+                    "response_format": "pcm",
+                }));
+        Box::pin(send(request))
+    }
+}
+
+struct ElevenLabsProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    model_id: String,
+}
+
+impl TtsProvider for ElevenLabsProvider {
+    fn name(&self) -> &'static str {
+        "elevenlabs"
+    }
+
+    fn synthesize(&self, text: String) -> BoxFuture<'static, Result<SpeechStream, String>> {
+        let request = self
+            .client
+            .post(&self.url)
+            .query(&[("output_format", "pcm_24000")])
+            .header("xi-api-key", &self.api_key)
+            .json(&serde_json::json!({
+                "text": text,
+                "model_id": self.model_id,
+            }));
+        Box::pin(send(request))
+    }
+}
+
+/// A local synthesizer's stdout as a stream of chunks. At the end of the
+/// output the process is reaped, and a failed run is reported with its
+/// stderr.
+fn process_output(
+    name: &'static str,
+    child: Child,
+    stdout: ChildStdout,
+) -> BoxStream<'static, Result<Vec<u8>, String>> {
+    futures_util::stream::unfold(Some((child, stdout)), move |running| async move {
+        let (child, mut stdout) = running?;
+        let mut chunk = vec![0u8; READ_CHUNK];
+        match stdout.read(&mut chunk).await {
+            Ok(0) => {
+                let output = match child.wait_with_output().await {
+                    Ok(output) => output,
+                    Err(e) => return Some((Err(format!("{} failed: {}", name, e)), None)),
+                };
+                if output.status.success() {
+                    return None;
+                }
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Some((Err(format!("{} failed: {}", name, stderr.trim())), None))
+            }
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok(chunk), Some((child, stdout))))
+            }
+            Err(e) => Some((Err(format!("Failed to read {} output: {}", name, e)), None)),
+        }
+    })
+    .boxed()
+}
+
+/// Run a local synthesizer (`name` is for errors) that reads `input` on
+/// stdin and writes 16-bit mono PCM at `sample_rate` to stdout.
+pub async fn speech_process(
+    mut command: Command,
+    name: &'static str,
+    input: String,
+    sample_rate: u32,
+) -> Result<SpeechStream, String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Stopping playback drops the stream and ends the process.
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| format!("{} has no stdin", name))?;
+    // Answers are capped well below a pipe buffer, so this doesn't wait on
+    // the process reading.
+    stdin
+        .write_all(input.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send text to {}: {}", name, e))?;
+    drop(stdin);
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| format!("{} has no stdout", name))?;
+    Ok(SpeechStream {
+        sample_rate,
+        pcm: process_output(name, child, stdout),
+    })
+}
+
+/// Append `chunk` to `pending` and decode the whole 16-bit samples in it;
+/// an odd trailing byte stays in `pending` for the next chunk.
+fn decode_pcm16(pending: &mut Vec<u8>, chunk: &[u8]) -> Vec<f32> {
+    pending.extend_from_slice(chunk);
+    let whole = pending.len() / 2 * 2;
+    let samples = pending[..whole]
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+        .collect();
+    pending.drain(..whole);
+    samples
+}
+
+/// PCM on its way from the provider to the output stream.
+struct SpeechQueue {
+    sample_rate: u32,
+    samples: Mutex<VecDeque<f32>>,
+    /// Set once the download ended; what's left in `samples` is the rest.
+    complete: AtomicBool,
+    /// Set by the stream once everything has been played.
+    finished: AtomicBool,
+}
+
+impl SpeechQueue {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            samples: Mutex::new(VecDeque::new()),
+            complete: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Linear-interpolation resampler reading from a queue that's still being
+/// filled.
+struct StreamCursor {
+    prev: f32,
+    next: f32,
+    /// Position between `prev` and `next`, in source samples.
+    phase: f64,
+    /// Source samples per output frame.
+    step: f64,
+}
+
+impl StreamCursor {
+    fn new(step: f64) -> Self {
+        Self {
+            prev: 0.0,
+            next: 0.0,
+            phase: 1.0,
+            step,
+        }
+    }
+
+    /// Fill `out` (interleaved, `channels` wide) from `queue`. Once the
+    /// queue runs dry the rest is silent and the cursor waits for more.
+    /// Returns whether it ran dry.
+    fn render<T>(&mut self, queue: &mut VecDeque<f32>, out: &mut [T], channels: usize) -> bool
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        let mut starved = false;
+        for frame in out.chunks_mut(channels) {
+            while self.phase >= 1.0 && !starved {
+                match queue.pop_front() {
+                    Some(sample) => {
+                        self.prev = self.next;
+                        self.next = sample;
+                        self.phase -= 1.0;
+                    }
+                    None => starved = true,
+                }
+            }
+            if starved {
+                frame.fill(T::EQUILIBRIUM);
+                continue;
+            }
+            let value = self.prev + (self.next - self.prev) * self.phase as f32;
+            frame.fill(T::from_sample(value));
+            self.phase += self.step;
+        }
+        starved
+    }
+}
+
+fn build_output_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: Arc<SpeechQueue>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let mut cursor = StreamCursor::new(queue.sample_rate as f64 / config.sample_rate.0 as f64);
+    device.build_output_stream(
+        config,
+        move |out: &mut [T], _: &cpal::OutputCallbackInfo| {
+            // Never wait on the download; a missed lock is one silent buffer.
+            let Ok(mut samples) = queue.samples.try_lock() else {
+                out.fill(T::EQUILIBRIUM);
+                return;
+            };
+            let starved = cursor.render(&mut samples, out, channels);
+            if starved && queue.complete.load(Ordering::Acquire) {
+                queue.finished.store(true, Ordering::Release);
+            }
+        },
+        |err| tracing::error!("TTS stream error: {}", err),
+        None,
+    )
+}
+
+fn open_output(device: &cpal::Device, queue: Arc<SpeechQueue>) -> Result<cpal::Stream, String> {
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get output config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => build_output_stream::<f32>(device, &config, queue),
+        cpal::SampleFormat::I16 => build_output_stream::<i16>(device, &config, queue),
+        cpal::SampleFormat::I32 => build_output_stream::<i32>(device, &config, queue),
+        cpal::SampleFormat::U16 => build_output_stream::<u16>(device, &config, queue),
+        other => return Err(format!("Unsupported output sample format: {}", other)),
+    }
+    .map_err(|e| format!("Failed to open output device: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start speech: {}", e))?;
+    Ok(stream)
+}
+
+struct Speech {
+    queue: Arc<SpeechQueue>,
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct TtsState {
+    config: Mutex<TtsConfig>,
+    speech: Mutex<Option<Speech>>,
+}
+
+/// Pause system-audio capture while an answer plays, unless the tap leaves
+/// this app's output out by itself (macOS) or the user wants it captured.
+fn pause_capture_for_speech(app: &AppHandle, speaking: bool) {
+    if cfg!(target_os = "macos") {
+        return;
+    }
+    let audio = app.state::<Arc<SystemAudioState>>();
+    let capture_own_audio = audio
+        .capture_settings()
+        .map(|settings| settings.capture_own_audio)
+        .unwrap_or(false);
+    audio.set_paused(PAUSE_REASON_OWN_SPEECH, speaking && !capture_own_audio);
+}
+
+/// Play `queue` on `device` from a new thread until it's finished or
+/// `stop` is set, bracketed by the audio session so other audio is ducked.
+fn spawn_speaker(
+    app: AppHandle,
+    device: Option<String>,
+    queue: Arc<SpeechQueue>,
+    stop: Arc<AtomicBool>,
+) -> Result<thread::JoinHandle<()>, String> {
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+    let handle = thread::spawn(move || {
+        let opened = find_output_device(device.as_deref())
+            .and_then(|device| open_output(&device, queue.clone()));
+        let stream = match opened {
+            Ok(stream) => {
+                let _ = ready_tx.send(Ok(()));
+                stream
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        pause_capture_for_speech(&app, true);
+        if let Err(e) = audio_session::speech_started(&app) {
+            tracing::warn!("Failed to duck other audio: {}", e);
+        }
+        while !stop.load(Ordering::SeqCst) && !queue.finished.load(Ordering::Acquire) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        drop(stream);
+        if let Err(e) = audio_session::speech_ended(&app) {
+            tracing::warn!("Failed to restore other audio: {}", e);
+        }
+        if queue.finished.load(Ordering::Acquire) {
+            let _ = app.emit("tts-finished", ());
+        }
+        // The loopback capture trails the output a little.
+        thread::sleep(CAPTURE_TAIL);
+        pause_capture_for_speech(&app, false);
+    });
+    ready_rx
+        .recv()
+        .map_err(|_| "TTS thread exited".to_string())??;
+    Ok(handle)
+}
+
+/// Feed the provider's PCM into `queue` until it ends or `stop` is set.
+async fn download(
+    mut pcm: BoxStream<'static, Result<Vec<u8>, String>>,
+    queue: &SpeechQueue,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let mut pending = Vec::new();
+    while let Some(chunk) = pcm.next().await {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let chunk = chunk?;
+        let samples = decode_pcm16(&mut pending, &chunk);
+        queue
+            .samples
+            .lock()
+            .map_err(|e| e.to_string())?
+            .extend(samples);
+    }
+    Ok(())
+}
+
+fn validate_text(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("Nothing to say".to_string());
+    }
+    let chars = text.chars().count();
+    if chars > MAX_TEXT_CHARS {
+        return Err(format!(
+            "Text is {} characters; at most {} can be spoken at once",
+            chars, MAX_TEXT_CHARS
+        ));
+    }
+    Ok(())
+}
+
+fn current_config(app: &AppHandle) -> Result<TtsConfig, String> {
+    Ok(app
+        .state::<TtsState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+/// The configured provider, with its key read from the credential store.
+async fn provider(app: &AppHandle, config: &TtsConfig) -> Result<Arc<dyn TtsProvider>, String> {
+    let provider = config
+        .provider
+        .clone()
+        .ok_or_else(|| "No TTS provider configured".to_string())?;
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || provider.build(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn tts_set_config(app: AppHandle, config: TtsConfig) -> Result<(), String> {
+    if let Some(provider) = &config.provider {
+        provider.validate()?;
+    }
+    if config.device.as_ref().is_some_and(|d| d.trim().is_empty()) {
+        return Err("device must not be empty".to_string());
+    }
+    *app.state::<TtsState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+/// Play answers on `device` (the default output if `None`) from the next
+/// `tts_speak` on, checking first that it exists.
+#[tauri::command]
+pub async fn tts_set_output_device(app: AppHandle, device: Option<String>) -> Result<(), String> {
+    if let Some(name) = device.clone() {
+        tauri::async_runtime::spawn_blocking(move || find_output_device(Some(&name)))
+            .await
+            .map_err(|e| e.to_string())??;
+    }
+    app.state::<TtsState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .device = device;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn tts_get_config(app: AppHandle) -> Result<TtsConfig, String> {
+    current_config(&app)
+}
+
+/// Synthesize `text` and return it as base64 WAV.
+#[tauri::command]
+pub async fn tts_synthesize(app: AppHandle, text: String) -> Result<String, String> {
+    validate_text(&text)?;
+    let provider = provider(&app, &current_config(&app)?).await?;
+    let mut speech = provider.synthesize(text).await?;
+    let mut pending = Vec::new();
+    let mut samples = Vec::new();
+    while let Some(chunk) = speech.pcm.next().await {
+        samples.extend(decode_pcm16(&mut pending, &chunk?));
+    }
+    let wav = encode_wav(&samples, speech.sample_rate);
+    Ok(base64::engine::general_purpose::STANDARD.encode(&wav))
+}
+
+/// Speak `text` on the configured output device, replacing any answer
+/// being spoken. Returns once playback has started.
+#[tauri::command]
+pub async fn tts_speak(app: AppHandle, text: String) -> Result<(), String> {
+    validate_text(&text)?;
+    let config = current_config(&app)?;
+    let provider = provider(&app, &config).await?;
+    tts_stop(app.clone()).await?;
+    let speech = provider.synthesize(text).await?;
+    tracing::info!("Speaking answer with {}", provider.name());
+
+    let queue = Arc::new(SpeechQueue::new(speech.sample_rate));
+    let stop = Arc::new(AtomicBool::new(false));
+    let speaker_app = app.clone();
+    let (speaker_queue, speaker_stop) = (queue.clone(), stop.clone());
+    let thread = tauri::async_runtime::spawn_blocking(move || {
+        spawn_speaker(speaker_app, config.device, speaker_queue, speaker_stop)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let (download_queue, download_stop) = (queue.clone(), stop.clone());
+    let download_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = download(speech.pcm, &download_queue, &download_stop).await;
+        download_queue.complete.store(true, Ordering::Release);
+        if let Err(e) = result {
+            tracing::warn!("{}", e);
+            let _ = download_app.emit("tts-error", e);
+        }
+    });
+
+    *app.state::<TtsState>()
+        .speech
+        .lock()
+        .map_err(|e| e.to_string())? = Some(Speech {
+        queue,
+        stop,
+        thread,
+    });
+    Ok(())
+}
+
+/// Stop the answer being spoken, if any.
+#[tauri::command]
+pub async fn tts_stop(app: AppHandle) -> Result<(), String> {
+    let speech = app
+        .state::<TtsState>()
+        .speech
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+    if let Some(speech) = speech {
+        speech.stop.store(true, Ordering::SeqCst);
+        tauri::async_runtime::spawn_blocking(move || speech.thread.join())
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|_| "TTS thread panicked".to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn tts_is_speaking(app: AppHandle) -> Result<bool, String> {
+    let state = app.state::<TtsState>();
+    let speech = state.speech.lock().map_err(|e| e.to_string())?;
+    Ok(speech
+        .as_ref()
+        .is_some_and(|speech| !speech.queue.finished.load(Ordering::Acquire)))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,63 @@
+use super::*;
+
+#[test]
+fn pcm_is_decoded_across_chunk_boundaries() {
+    let mut pending = Vec::new();
+    // 0x4000 = 16384 (0.5) split over two chunks, then -32768 (-1.0).
+    assert_eq!(decode_pcm16(&mut pending, &[0x00, 0x00, 0x00]), vec![0.0]);
+    assert_eq!(pending, vec![0x00]);
+    assert_eq!(
+        decode_pcm16(&mut pending, &[0x40, 0x00, 0x80]),
+        vec![0.5, -1.0]
+    );
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn cursor_waits_for_more_samples_when_starved() {
+    let mut cursor = StreamCursor::new(1.0);
+    let mut queue: VecDeque<f32> = VecDeque::from(vec![0.25, 0.5]);
+    let mut out = [1.0f32; 6];
+    // Stereo: two frames from the queue, then silence.
+    assert!(cursor.render(&mut queue, &mut out, 2));
+    assert_eq!(out, [0.0, 0.0, 0.25, 0.25, 0.0, 0.0]);
+
+    // Picks up where it left off once more arrives.
+    queue.extend([0.75]);
+    let mut out = [1.0f32; 1];
+    assert!(!cursor.render(&mut queue, &mut out, 1));
+    assert_eq!(out, [0.5]);
+}
+
+#[test]
+fn cursor_interpolates_when_upsampling() {
+    let mut cursor = StreamCursor::new(0.5);
+    let mut queue: VecDeque<f32> = VecDeque::from(vec![1.0, 0.0]);
+    let mut out = [0.0f32; 4];
+    assert!(!cursor.render(&mut queue, &mut out, 1));
+    assert_eq!(out, [0.0, 0.5, 1.0, 0.5]);
+}
+
+#[test]
+fn provider_config_is_validated() {
+    let config: TtsProviderConfig = serde_json::from_str(
+        r#"{"type":"elevenlabs","voiceId":"abc","modelId":"eleven_flash_v2_5"}"#,
+    )
+    .unwrap();
+    assert!(config.validate().is_ok());
+    let config = TtsProviderConfig::OpenAi {
+        model: "gpt-4o-mini-tts".to_string(),
+        voice: " ".to_string(),
+    };
+    assert_eq!(
+        config.validate(),
+        Err("voice must not be empty".to_string())
+    );
+}
+
+#[test]
+fn text_length_is_limited() {
+    assert!(validate_text("Hello").is_ok());
+    assert!(validate_text("  ").is_err());
+    assert!(validate_text(&"a".repeat(MAX_TEXT_CHARS + 1)).is_err());
+}
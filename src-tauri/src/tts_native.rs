@@ -0,0 +1,422 @@
+//! Text-to-speech through the OS's own voices, so answers can be spoken
+//! without an API key or a download.
+//!
+//! On macOS: AVSpeechSynthesizer, rendering to buffers rather than the
+//! speakers so the audio goes through `tts` like any other provider's.
+//! On Windows: SAPI, through System.Speech in PowerShell, with rate, pitch
+//! and voice set in SSML.
+//! Not available on Linux.
+
+use crate::tts::{SpeechStream, TtsProvider};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+/// Accepted range for the rate and pitch multipliers.
+const MIN_MULTIPLIER: f32 = 0.5;
+const MAX_MULTIPLIER: f32 = 2.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NativeSpeechOptions {
+    /// Voice id from `tts_native_list_voices`; the system voice if `None`.
+    pub voice: Option<String>,
+    /// Speaking rate; 1.0 is the voice's normal rate.
+    pub rate: f32,
+    /// Pitch; 1.0 is the voice's normal pitch.
+    pub pitch: f32,
+}
+
+impl Default for NativeSpeechOptions {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            rate: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+impl NativeSpeechOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [("rate", self.rate), ("pitch", self.pitch)] {
+            if !(MIN_MULTIPLIER..=MAX_MULTIPLIER).contains(&value) {
+                return Err(format!(
+                    "{} must be between {} and {}, got {}",
+                    name, MIN_MULTIPLIER, MAX_MULTIPLIER, value
+                ));
+            }
+        }
+        if self.voice.as_ref().is_some_and(|v| v.trim().is_empty()) {
+            return Err("voice must not be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NativeVoice {
+    /// What to put in `NativeSpeechOptions::voice`.
+    pub id: String,
+    pub name: String,
+    /// BCP 47 tag, e.g. `en-US`.
+    pub language: String,
+}
+
+/// Float samples as 16-bit little-endian PCM.
+#[cfg(any(target_os = "macos", test))]
+fn pcm16_bytes(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|&s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+/// AVSpeechUtterance's rate runs from 0 to 1 with 0.5 as normal speech.
+#[cfg(any(target_os = "macos", test))]
+fn utterance_rate(rate: f32) -> f32 {
+    (0.5 * rate).clamp(0.0, 1.0)
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A multiplier as an SSML relative change, e.g. 1.5 as `+50%`.
+fn relative_percent(multiplier: f32) -> String {
+    format!("{:+}%", ((multiplier - 1.0) * 100.0).round() as i32)
+}
+
+/// `text` as an SSML document carrying the voice, rate and pitch.
+#[cfg_attr(not(any(target_os = "windows", test)), allow(dead_code))]
+fn ssml(text: &str, options: &NativeSpeechOptions) -> String {
+    let prosody = format!(
+        "<prosody rate=\"{}\" pitch=\"{}\">{}</prosody>",
+        relative_percent(options.rate),
+        relative_percent(options.pitch),
+        xml_escape(text)
+    );
+    let body = match &options.voice {
+        Some(voice) => format!("<voice name=\"{}\">{}</voice>", xml_escape(voice), prosody),
+        None => prosody,
+    };
+    format!(
+        "<speak version=\"1.0\" xmlns=\"http://www.w3.org/2001/10/synthesis\" xml:lang=\"en-US\">{}</speak>",
+        body
+    )
+}
+
+/// One `name<TAB>culture` line per installed SAPI voice.
+#[cfg(any(target_os = "windows", test))]
+fn parse_sapi_voices(output: &str) -> Vec<NativeVoice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, language) = line.trim_end_matches('\r').split_once('\t')?;
+            (!name.is_empty()).then(|| NativeVoice {
+                id: name.to_string(),
+                name: name.to_string(),
+                language: language.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{pcm16_bytes, utterance_rate, NativeSpeechOptions, NativeVoice};
+    use crate::tts::SpeechStream;
+    use block2::RcBlock;
+    use futures_util::stream::{self, StreamExt};
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2::sel;
+    use objc2_foundation::NSString;
+    use std::ffi::CStr;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+    #[link(name = "AVFAudio", kind = "framework")]
+    extern "C" {}
+
+    /// AVAudioCommonFormat.
+    const PCM_FORMAT_FLOAT32: usize = 1;
+    const PCM_FORMAT_INT16: usize = 3;
+    /// AVSpeechBoundaryImmediate.
+    const BOUNDARY_IMMEDIATE: isize = 0;
+    /// Longest the synthesizer thread waits for the last buffer.
+    const MAX_SYNTHESIS: Duration = Duration::from_secs(10 * 60);
+
+    /// A buffer of speech: its sample rate and 16-bit PCM.
+    type Chunk = Result<(u32, Vec<u8>), String>;
+
+    fn class(name: &[u8]) -> Result<&'static AnyClass, String> {
+        CStr::from_bytes_with_nul(name)
+            .ok()
+            .and_then(AnyClass::get)
+            .ok_or_else(|| "AVSpeechSynthesizer is not available".to_string())
+    }
+
+    fn to_string(value: Option<Retained<NSString>>) -> String {
+        value.map(|s| s.to_string()).unwrap_or_default()
+    }
+
+    pub fn voices() -> Result<Vec<NativeVoice>, String> {
+        unsafe {
+            let voices: Option<Retained<AnyObject>> =
+                msg_send![class(b"AVSpeechSynthesisVoice\0")?, speechVoices];
+            let Some(voices) = voices else {
+                return Ok(Vec::new());
+            };
+            let count: usize = msg_send![&*voices, count];
+            let mut found = Vec::with_capacity(count);
+            for i in 0..count {
+                let voice: Option<Retained<AnyObject>> = msg_send![&*voices, objectAtIndex: i];
+                let Some(voice) = voice else {
+                    continue;
+                };
+                let id: Option<Retained<NSString>> = msg_send![&*voice, identifier];
+                let name: Option<Retained<NSString>> = msg_send![&*voice, name];
+                let language: Option<Retained<NSString>> = msg_send![&*voice, language];
+                found.push(NativeVoice {
+                    id: to_string(id),
+                    name: to_string(name),
+                    language: to_string(language),
+                });
+            }
+            Ok(found)
+        }
+    }
+
+    /// The samples in an AVAudioPCMBuffer, or `None` for the empty buffer
+    /// that ends the utterance (or one that can't be read).
+    unsafe fn read_buffer(buffer: *mut AnyObject) -> Option<(u32, Vec<u8>)> {
+        let buffer = buffer.as_ref()?;
+        let pcm: bool = msg_send![buffer, respondsToSelector: sel!(frameLength)];
+        if !pcm {
+            return None;
+        }
+        let frames: u32 = msg_send![buffer, frameLength];
+        if frames == 0 {
+            return None;
+        }
+        let format: Option<Retained<AnyObject>> = msg_send![buffer, format];
+        let format = format?;
+        let sample_rate: f64 = msg_send![&*format, sampleRate];
+        let common_format: usize = msg_send![&*format, commonFormat];
+        let frames = frames as usize;
+        // Mono voices; only the first channel is read.
+        let bytes = match common_format {
+            PCM_FORMAT_FLOAT32 => {
+                let channels: *const *const f32 = msg_send![buffer, floatChannelData];
+                if channels.is_null() || (*channels).is_null() {
+                    return None;
+                }
+                pcm16_bytes(std::slice::from_raw_parts(*channels, frames))
+            }
+            PCM_FORMAT_INT16 => {
+                let channels: *const *const i16 = msg_send![buffer, int16ChannelData];
+                if channels.is_null() || (*channels).is_null() {
+                    return None;
+                }
+                std::slice::from_raw_parts(*channels, frames)
+                    .iter()
+                    .flat_map(|s| s.to_le_bytes())
+                    .collect()
+            }
+            other => {
+                tracing::warn!("Unsupported speech buffer format {}", other);
+                return None;
+            }
+        };
+        Some((sample_rate.round() as u32, bytes))
+    }
+
+    /// Render the utterance, sending each buffer to `chunks`, and return
+    /// once it's done or `chunks` is closed. Owns the synthesizer, which
+    /// must outlive the callbacks.
+    unsafe fn render(
+        text: &str,
+        options: &NativeSpeechOptions,
+        chunks: UnboundedSender<Chunk>,
+    ) -> Result<(), String> {
+        let synthesizer: Option<Retained<AnyObject>> =
+            msg_send![class(b"AVSpeechSynthesizer\0")?, new];
+        let synthesizer =
+            synthesizer.ok_or_else(|| "Failed to create speech synthesizer".to_string())?;
+        let text = NSString::from_str(text);
+        let utterance: Option<Retained<AnyObject>> = msg_send![
+            class(b"AVSpeechUtterance\0")?,
+            speechUtteranceWithString: &*text
+        ];
+        let utterance = utterance.ok_or_else(|| "Failed to create utterance".to_string())?;
+        let _: () = msg_send![&*utterance, setRate: utterance_rate(options.rate)];
+        let _: () = msg_send![&*utterance, setPitchMultiplier: options.pitch];
+        if let Some(id) = &options.voice {
+            let voice: Option<Retained<AnyObject>> = msg_send![
+                class(b"AVSpeechSynthesisVoice\0")?,
+                voiceWithIdentifier: &*NSString::from_str(id)
+            ];
+            let voice = voice.ok_or_else(|| format!("Voice not found: {}", id))?;
+            let _: () = msg_send![&*utterance, setVoice: &*voice];
+        }
+
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let block = RcBlock::new(move |buffer: *mut AnyObject| match read_buffer(buffer) {
+            Some(chunk) => {
+                // Closed: playback was stopped.
+                if chunks.send(Ok(chunk)).is_err() {
+                    let _ = done_tx.send(());
+                }
+            }
+            None => {
+                let _ = done_tx.send(());
+            }
+        });
+        let _: () = msg_send![
+            &*synthesizer,
+            writeUtterance: &*utterance,
+            toBufferCallback: &*block
+        ];
+        let _ = done_rx.recv_timeout(MAX_SYNTHESIS);
+        let _: bool = msg_send![&*synthesizer, stopSpeakingAtBoundary: BOUNDARY_IMMEDIATE];
+        Ok(())
+    }
+
+    pub async fn synthesize(
+        text: String,
+        options: NativeSpeechOptions,
+    ) -> Result<SpeechStream, String> {
+        let (tx, mut rx) = unbounded_channel::<Chunk>();
+        thread::spawn(move || {
+            if let Err(e) = unsafe { render(&text, &options, tx.clone()) } {
+                let _ = tx.send(Err(e));
+            }
+        });
+        // The sample rate is only known from the first buffer.
+        let (sample_rate, first) = rx
+            .recv()
+            .await
+            .ok_or_else(|| "The system voice produced no audio".to_string())??;
+        let rest = stream::unfold(rx, |mut rx| async move {
+            let chunk = rx.recv().await?;
+            Some((chunk.map(|(_, pcm)| pcm), rx))
+        });
+        Ok(SpeechStream {
+            sample_rate,
+            pcm: stream::once(async move { Ok(first) }).chain(rest).boxed(),
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{parse_sapi_voices, ssml, NativeSpeechOptions, NativeVoice};
+    use crate::hidden_command;
+    use crate::tts::{speech_process, SpeechStream};
+
+    /// SAPI renders at this rate; 16-bit mono.
+    const SAMPLE_RATE: u32 = 22_050;
+
+    const VOICES_SCRIPT: &str = r#"
+Add-Type -AssemblyName System.Speech
+(New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() |
+  Where-Object { $_.Enabled } |
+  ForEach-Object { $_.VoiceInfo.Name + "`t" + $_.VoiceInfo.Culture.Name }
+"#;
+
+    /// Reads SSML on stdin and writes raw PCM to stdout.
+    const SPEAK_SCRIPT: &str = r#"
+Add-Type -AssemblyName System.Speech
+[Console]::InputEncoding = [Text.Encoding]::UTF8
+$ssml = [Console]::In.ReadToEnd()
+$synth = New-Object System.Speech.Synthesis.SpeechSynthesizer
+$format = New-Object System.Speech.AudioFormat.SpeechAudioFormatInfo({rate}, [System.Speech.AudioFormat.AudioBitsPerSample]::Sixteen, [System.Speech.AudioFormat.AudioChannel]::Mono)
+$out = [Console]::OpenStandardOutput()
+$synth.SetOutputToAudioStream($out, $format)
+$synth.SpeakSsml($ssml)
+$out.Flush()
+"#;
+
+    pub fn voices() -> Result<Vec<NativeVoice>, String> {
+        let output = hidden_command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", VOICES_SCRIPT])
+            .output()
+            .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(parse_sapi_voices(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    pub async fn synthesize(
+        text: String,
+        options: NativeSpeechOptions,
+    ) -> Result<SpeechStream, String> {
+        let script = SPEAK_SCRIPT.replace("{rate}", &SAMPLE_RATE.to_string());
+        let mut command = hidden_command::new_async("powershell");
+        command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        speech_process(command, "SAPI", ssml(&text, &options), SAMPLE_RATE).await
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::{NativeSpeechOptions, NativeVoice};
+    use crate::tts::SpeechStream;
+
+    pub fn voices() -> Result<Vec<NativeVoice>, String> {
+        Err("System voices are only supported on macOS and Windows".to_string())
+    }
+
+    pub async fn synthesize(
+        _text: String,
+        _options: NativeSpeechOptions,
+    ) -> Result<SpeechStream, String> {
+        Err("System voices are only supported on macOS and Windows".to_string())
+    }
+}
+
+pub struct NativeProvider {
+    options: NativeSpeechOptions,
+}
+
+impl NativeProvider {
+    pub fn new(options: NativeSpeechOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl TtsProvider for NativeProvider {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn synthesize(&self, text: String) -> BoxFuture<'static, Result<SpeechStream, String>> {
+        Box::pin(platform::synthesize(text, self.options.clone()))
+    }
+}
+
+/// The OS's installed voices.
+#[tauri::command]
+pub async fn tts_native_list_voices() -> Result<Vec<NativeVoice>, String> {
+    tauri::async_runtime::spawn_blocking(platform::voices)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,66 @@
+use super::*;
+
+#[test]
+fn options_are_range_checked() {
+    assert!(NativeSpeechOptions::default().validate().is_ok());
+    let options = |rate, pitch| NativeSpeechOptions {
+        voice: None,
+        rate,
+        pitch,
+    };
+    assert!(options(0.5, 2.0).validate().is_ok());
+    assert!(options(0.4, 1.0).validate().is_err());
+    assert!(options(1.0, 2.1).validate().is_err());
+    assert!(options(f32::NAN, 1.0).validate().is_err());
+    let blank_voice = NativeSpeechOptions {
+        voice: Some(" ".to_string()),
+        ..Default::default()
+    };
+    assert!(blank_voice.validate().is_err());
+}
+
+#[test]
+fn multipliers_become_relative_percentages() {
+    assert_eq!(relative_percent(1.0), "+0%");
+    assert_eq!(relative_percent(1.5), "+50%");
+    assert_eq!(relative_percent(0.8), "-20%");
+}
+
+#[test]
+fn ssml_escapes_text_and_voice() {
+    let options = NativeSpeechOptions {
+        voice: Some("Microsoft \"Zira\"".to_string()),
+        rate: 1.25,
+        pitch: 0.9,
+    };
+    let doc = ssml("1 < 2 & 'so' on", &options);
+    assert!(doc.contains("<voice name=\"Microsoft &quot;Zira&quot;\">"));
+    assert!(doc.contains("<prosody rate=\"+25%\" pitch=\"-10%\">"));
+    assert!(doc.contains("1 &lt; 2 &amp; &apos;so&apos; on</prosody>"));
+
+    let doc = ssml("hi", &NativeSpeechOptions::default());
+    assert!(!doc.contains("<voice"));
+}
+
+#[test]
+fn sapi_voice_lines_are_parsed() {
+    let voices = parse_sapi_voices("Microsoft David Desktop\ten-US\r\n\r\nbroken line\r\n");
+    assert_eq!(
+        voices,
+        vec![NativeVoice {
+            id: "Microsoft David Desktop".to_string(),
+            name: "Microsoft David Desktop".to_string(),
+            language: "en-US".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn samples_convert_to_pcm16() {
+    assert_eq!(
+        pcm16_bytes(&[0.0, 1.0, -2.0]),
+        vec![0, 0, 0xff, 0x7f, 0x01, 0x80]
+    );
+    assert_eq!(utterance_rate(1.0), 0.5);
+    assert_eq!(utterance_rate(2.0), 1.0);
+}
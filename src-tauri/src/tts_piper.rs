@@ -0,0 +1,263 @@
+//! Offline text-to-speech with Piper voices, for setups without a cloud
+//! provider.
+//!
+//! Voices are ONNX models from the rhasspy/piper-voices collection,
+//! downloaded by name (e.g. `en_US-lessac-medium`) into `piper_voices/` in
+//! the app data directory, each next to its `.onnx.json` config. Speech is
+//! synthesized by the `piper` executable, which the user installs; its raw
+//! output streams into `tts` like any other provider's.
+//!
+//! Emits `piper-voice-download-progress` while a voice downloads.
+
+use crate::hidden_command;
+use crate::tts::{speech_process, SpeechStream, TtsProvider};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+
+const VOICES_URL: &str = "https://huggingface.co/rhasspy/piper-voices/resolve/main";
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+/// Executable used when the config doesn't name one.
+const DEFAULT_BINARY: &str = "piper";
+const QUALITIES: [&str; 4] = ["x_low", "low", "medium", "high"];
+
+/// The parts of a voice name: `<language>-<dataset>-<quality>`, where the
+/// language is `<family>_<REGION>`.
+#[derive(Debug, PartialEq)]
+pub struct VoiceName<'a> {
+    pub family: &'a str,
+    pub language: &'a str,
+    pub dataset: &'a str,
+    pub quality: &'a str,
+}
+
+/// Split and check a voice name. Only letters, digits and `_` are allowed
+/// in each part, so a name is always safe as a file name and URL path.
+pub fn parse_voice_name(name: &str) -> Result<VoiceName<'_>, String> {
+    let invalid = || format!("Not a Piper voice name: {}", name);
+    let mut parts = name.split('-');
+    let (Some(language), Some(dataset), Some(quality), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    let word = |part: &str| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let family = language.split('_').next().unwrap_or_default();
+    if !word(language) || !word(dataset) || family.is_empty() || family == language {
+        return Err(invalid());
+    }
+    if !QUALITIES.contains(&quality) {
+        return Err(invalid());
+    }
+    Ok(VoiceName {
+        family,
+        language,
+        dataset,
+        quality,
+    })
+}
+
+/// Where the voice's model is published; its config is at the same URL
+/// plus `.json`.
+fn model_url(name: &str) -> Result<String, String> {
+    let voice = parse_voice_name(name)?;
+    Ok(format!(
+        "{}/{}/{}/{}/{}/{}.onnx",
+        VOICES_URL, voice.family, voice.language, voice.dataset, voice.quality, name
+    ))
+}
+
+/// The output sample rate from a voice's `.onnx.json` config.
+fn config_sample_rate(json: &str) -> Result<u32, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Invalid voice config: {}", e))?;
+    value
+        .pointer("/audio/sample_rate")
+        .and_then(|rate| rate.as_u64())
+        .filter(|&rate| rate > 0 && rate <= u32::MAX as u64)
+        .map(|rate| rate as u32)
+        .ok_or_else(|| "Voice config has no audio.sample_rate".to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PiperVoice {
+    pub name: String,
+    pub sample_rate: u32,
+    pub size_bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct DownloadProgress<'a> {
+    voice: &'a str,
+    downloaded_bytes: u64,
+    total_bytes: Option<u64>,
+}
+
+fn voices_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join("piper_voices");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create voices dir: {}", e))?;
+    Ok(dir)
+}
+
+fn model_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.onnx", name))
+}
+
+fn config_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.onnx.json", name))
+}
+
+/// An installed voice, or an error if its model or config is missing.
+fn installed_voice(dir: &Path, name: &str) -> Result<PiperVoice, String> {
+    parse_voice_name(name)?;
+    let size_bytes = fs::metadata(model_path(dir, name))
+        .map_err(|_| format!("Piper voice not installed: {}", name))?
+        .len();
+    let json = fs::read_to_string(config_path(dir, name))
+        .map_err(|_| format!("Piper voice config missing: {}", name))?;
+    Ok(PiperVoice {
+        name: name.to_string(),
+        sample_rate: config_sample_rate(&json)?,
+        size_bytes,
+    })
+}
+
+/// Download `url` to `path`, through a `.part` file so an interrupted
+/// download never looks installed.
+async fn download_file(app: &AppHandle, voice: &str, url: &str, path: &Path) -> Result<(), String> {
+    let response = crate::network::client(app, None)?
+        .get(url)
+        .timeout(DOWNLOAD_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Voice download failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Voice download failed: {}", response.status()));
+    }
+    let total_bytes = response.content_length();
+    let part = path.with_extension("part");
+    let mut file = tokio::fs::File::create(&part)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", part.display(), e))?;
+    let mut body = response.bytes_stream();
+    let mut downloaded_bytes = 0u64;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| format!("Voice download failed: {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write voice: {}", e))?;
+        downloaded_bytes += chunk.len() as u64;
+        let _ = app.emit(
+            "piper-voice-download-progress",
+            DownloadProgress {
+                voice,
+                downloaded_bytes,
+                total_bytes,
+            },
+        );
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write voice: {}", e))?;
+    drop(file);
+    tokio::fs::rename(&part, path)
+        .await
+        .map_err(|e| format!("Failed to install voice: {}", e))
+}
+
+pub struct PiperProvider {
+    binary: String,
+    model: PathBuf,
+    sample_rate: u32,
+}
+
+impl PiperProvider {
+    /// Speak with the installed voice `voice`, through `binary` (`piper` on
+    /// the PATH if `None`).
+    pub fn new(app: &AppHandle, voice: &str, binary: Option<&str>) -> Result<Self, String> {
+        let dir = voices_dir(app)?;
+        let installed = installed_voice(&dir, voice)?;
+        Ok(Self {
+            binary: binary.unwrap_or(DEFAULT_BINARY).to_string(),
+            model: model_path(&dir, voice),
+            sample_rate: installed.sample_rate,
+        })
+    }
+}
+
+impl TtsProvider for PiperProvider {
+    fn name(&self) -> &'static str {
+        "piper"
+    }
+
+    fn synthesize(&self, text: String) -> BoxFuture<'static, Result<SpeechStream, String>> {
+        let (binary, model, sample_rate) =
+            (self.binary.clone(), self.model.clone(), self.sample_rate);
+        Box::pin(async move {
+            let mut command = hidden_command::new_async(&binary);
+            command.arg("--model").arg(&model).arg("--output-raw");
+            speech_process(command, "Piper", text, sample_rate).await
+        })
+    }
+}
+
+/// Installed voices, by name.
+#[tauri::command]
+pub fn tts_piper_list_voices(app: AppHandle) -> Result<Vec<PiperVoice>, String> {
+    let dir = voices_dir(&app)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to list voices: {}", e))?;
+    let mut voices: Vec<PiperVoice> = entries
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name();
+            let name = file_name.to_str()?.strip_suffix(".onnx")?;
+            installed_voice(&dir, name).ok()
+        })
+        .collect();
+    voices.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(voices)
+}
+
+/// Download the voice `name` (model and config), replacing any installed
+/// copy.
+#[tauri::command]
+pub async fn tts_piper_download_voice(app: AppHandle, name: String) -> Result<PiperVoice, String> {
+    let url = model_url(&name)?;
+    let dir = voices_dir(&app)?;
+    download_file(
+        &app,
+        &name,
+        &format!("{}.json", url),
+        &config_path(&dir, &name),
+    )
+    .await?;
+    download_file(&app, &name, &url, &model_path(&dir, &name)).await?;
+    installed_voice(&dir, &name)
+}
+
+#[tauri::command]
+pub fn tts_piper_delete_voice(app: AppHandle, name: String) -> Result<(), String> {
+    parse_voice_name(&name)?;
+    let dir = voices_dir(&app)?;
+    for path in [model_path(&dir, &name), config_path(&dir, &name)] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete {}: {}", path.display(), e)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,59 @@
+use super::*;
+
+#[test]
+fn voice_names_are_parsed() {
+    assert_eq!(
+        parse_voice_name("en_US-lessac-medium"),
+        Ok(VoiceName {
+            family: "en",
+            language: "en_US",
+            dataset: "lessac",
+            quality: "medium",
+        })
+    );
+    assert!(parse_voice_name("de_DE-thorsten_emotional-x_low").is_ok());
+    for name in [
+        "en_US-lessac",
+        "en-lessac-medium",
+        "en_US-lessac-ultra",
+        "en_US-../x-medium",
+        "en_US-lessac-medium-extra",
+        "",
+    ] {
+        assert!(parse_voice_name(name).is_err(), "{}", name);
+    }
+}
+
+#[test]
+fn model_url_follows_the_voices_layout() {
+    assert_eq!(
+        model_url("en_GB-alba-medium").unwrap(),
+        "https://huggingface.co/rhasspy/piper-voices/resolve/main/en/en_GB/alba/medium/en_GB-alba-medium.onnx"
+    );
+}
+
+#[test]
+fn sample_rate_is_read_from_the_voice_config() {
+    let json = r#"{"audio":{"sample_rate":22050,"quality":"medium"},"num_speakers":1}"#;
+    assert_eq!(config_sample_rate(json), Ok(22050));
+    assert!(config_sample_rate(r#"{"audio":{}}"#).is_err());
+    assert!(config_sample_rate("not json").is_err());
+}
+
+#[test]
+fn installed_voices_need_model_and_config() {
+    let dir = std::env::temp_dir().join(format!("runningbord-piper-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let name = "en_US-amy-low";
+    fs::write(model_path(&dir, name), [0u8; 16]).unwrap();
+    assert!(installed_voice(&dir, name).is_err());
+    fs::write(
+        config_path(&dir, name),
+        r#"{"audio":{"sample_rate":16000}}"#,
+    )
+    .unwrap();
+    let voice = installed_voice(&dir, name).unwrap();
+    assert_eq!(voice.sample_rate, 16000);
+    assert_eq!(voice.size_bytes, 16);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,234 @@
+//! Update checks against the GitHub releases of the repository in
+//! `Cargo.toml`, so the frontend can offer a newer version with its release
+//! notes. The stable channel only sees full releases; beta also sees
+//! pre-releases. Drafts are never offered.
+
+use crate::network;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const RELEASES_PER_PAGE: u32 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+#[derive(Default)]
+pub struct UpdateState {
+    channel: Mutex<UpdateChannel>,
+}
+
+/// A release as the GitHub API lists it.
+#[derive(Debug, Clone, Deserialize)]
+struct Release {
+    tag_name: String,
+    name: Option<String>,
+    body: Option<String>,
+    html_url: String,
+    published_at: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableUpdate {
+    pub version: String,
+    pub name: Option<String>,
+    /// Markdown.
+    pub release_notes: Option<String>,
+    pub url: String,
+    pub published_at: Option<String>,
+    pub prerelease: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheck {
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    /// `None` if the current version is the newest on the channel.
+    pub update: Option<AvailableUpdate>,
+}
+
+/// A semantic version; build metadata is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    numbers: [u64; 3],
+    pre: Vec<String>,
+}
+
+impl Version {
+    /// `1.2.3`, `v1.2.3-beta.1`, `1.2` (as `1.2.0`).
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_start_matches(['v', 'V']);
+        let text = text.split('+').next()?;
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, pre.split('.').map(str::to_string).collect()),
+            None => (text, Vec::new()),
+        };
+        let mut numbers = [0; 3];
+        let mut parts = core.split('.');
+        for (i, number) in numbers.iter_mut().enumerate() {
+            match parts.next() {
+                Some(part) => *number = part.parse().ok()?,
+                None if i > 0 => break,
+                None => return None,
+            }
+        }
+        if parts.next().is_some() || pre.iter().any(String::is_empty) {
+            return None;
+        }
+        Some(Self { numbers, pre })
+    }
+}
+
+/// Pre-release identifiers: numeric ones compare as numbers and sort
+/// before alphanumeric ones.
+fn compare_identifiers(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers.cmp(&other.numbers).then_with(|| {
+            // A pre-release sorts before its release.
+            match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self
+                    .pre
+                    .iter()
+                    .zip(&other.pre)
+                    .map(|(a, b)| compare_identifiers(a, b))
+                    .find(|o| o.is_ne())
+                    .unwrap_or_else(|| self.pre.len().cmp(&other.pre.len())),
+            }
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The newest release on `channel` if it's newer than `current`. Tags that
+/// aren't versions are skipped.
+fn newest_update(
+    releases: &[Release],
+    current: &str,
+    channel: UpdateChannel,
+) -> Option<AvailableUpdate> {
+    let current = Version::parse(current)?;
+    releases
+        .iter()
+        .filter(|r| !r.draft && (channel == UpdateChannel::Beta || !r.prerelease))
+        .filter_map(|r| Some((Version::parse(&r.tag_name)?, r)))
+        .filter(|(version, _)| *version > current)
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| AvailableUpdate {
+            version: release.tag_name.trim_start_matches(['v', 'V']).to_string(),
+            name: release.name.clone().filter(|n| !n.trim().is_empty()),
+            release_notes: release.body.clone().filter(|b| !b.trim().is_empty()),
+            url: release.html_url.clone(),
+            published_at: release.published_at.clone(),
+            prerelease: release.prerelease,
+        })
+}
+
+/// `https://api.github.com/repos/<owner>/<repo>/releases` for a
+/// `https://github.com/<owner>/<repo>` repository URL.
+fn releases_url(repository: &str) -> Option<String> {
+    let path = repository
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .strip_prefix("https://github.com/")?;
+    let mut parts = path.split('/');
+    let (owner, repo) = (parts.next()?, parts.next()?);
+    if owner.is_empty() || repo.is_empty() || parts.next().is_some() {
+        return None;
+    }
+    Some(format!(
+        "https://api.github.com/repos/{}/{}/releases",
+        owner, repo
+    ))
+}
+
+fn channel(app: &AppHandle) -> Result<UpdateChannel, String> {
+    app.state::<UpdateState>()
+        .channel
+        .lock()
+        .map(|channel| *channel)
+        .map_err(|e| e.to_string())
+}
+
+/// Look for a newer release on `channel` (the configured one by default).
+#[tauri::command]
+pub async fn check_for_update(
+    app: AppHandle,
+    channel: Option<UpdateChannel>,
+) -> Result<UpdateCheck, String> {
+    let channel = match channel {
+        Some(channel) => channel,
+        None => self::channel(&app)?,
+    };
+    let current_version = app.package_info().version.to_string();
+    let url = releases_url(env!("CARGO_PKG_REPOSITORY"))
+        .ok_or_else(|| "The repository isn't on GitHub".to_string())?;
+    let response = network::client(&app, None)?
+        .get(&url)
+        .query(&[("per_page", RELEASES_PER_PAGE)])
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", format!("runningbord/{}", current_version))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to check for updates: GitHub returned {}",
+            response.status()
+        ));
+    }
+    let releases: Vec<Release> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to read the releases: {}", e))?;
+    Ok(UpdateCheck {
+        update: newest_update(&releases, &current_version, channel),
+        current_version,
+        channel,
+    })
+}
+
+#[tauri::command]
+pub fn update_set_channel(app: AppHandle, channel: UpdateChannel) -> Result<(), String> {
+    *app.state::<UpdateState>()
+        .channel
+        .lock()
+        .map_err(|e| e.to_string())? = channel;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_get_channel(app: AppHandle) -> Result<UpdateChannel, String> {
+    channel(&app)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,86 @@
+use super::*;
+
+fn release(tag: &str, prerelease: bool, draft: bool) -> Release {
+    Release {
+        tag_name: tag.to_string(),
+        name: Some(format!("Release {}", tag)),
+        body: Some("- Fixes".to_string()),
+        html_url: format!("https://github.com/o/r/releases/tag/{}", tag),
+        published_at: None,
+        draft,
+        prerelease,
+    }
+}
+
+fn version(text: &str) -> Version {
+    Version::parse(text).unwrap()
+}
+
+#[test]
+fn versions_parse_with_prefix_and_pre_release() {
+    assert_eq!(
+        Version::parse("v1.2.3-beta.1+build.5"),
+        Some(Version {
+            numbers: [1, 2, 3],
+            pre: vec!["beta".to_string(), "1".to_string()],
+        })
+    );
+    assert_eq!(version("1.2"), version("1.2.0"));
+    for bad in ["", "latest", "1.2.3.4", "1.x.0", "1.2.3-", "1.2.3-a..b"] {
+        assert_eq!(Version::parse(bad), None, "{}", bad);
+    }
+}
+
+#[test]
+fn versions_order_like_semver() {
+    let ordered = [
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "1.0.1",
+        "1.10.0",
+    ];
+    for pair in ordered.windows(2) {
+        assert!(version(pair[0]) < version(pair[1]), "{:?}", pair);
+    }
+}
+
+#[test]
+fn channels_pick_the_newest_release() {
+    let releases = vec![
+        release("v0.2.0-beta.1", true, false),
+        release("v0.1.10", false, false),
+        release("v0.3.0", false, true),
+        release("nightly", true, false),
+    ];
+    let stable = newest_update(&releases, "0.1.9", UpdateChannel::Stable).unwrap();
+    assert_eq!(stable.version, "0.1.10");
+    assert_eq!(stable.release_notes.as_deref(), Some("- Fixes"));
+
+    let beta = newest_update(&releases, "0.1.9", UpdateChannel::Beta).unwrap();
+    assert_eq!(beta.version, "0.2.0-beta.1");
+    assert!(beta.prerelease);
+
+    assert_eq!(
+        newest_update(&releases, "0.1.10", UpdateChannel::Stable),
+        None
+    );
+}
+
+#[test]
+fn releases_url_comes_from_the_repository() {
+    assert_eq!(
+        releases_url("https://github.com/owner/repo").as_deref(),
+        Some("https://api.github.com/repos/owner/repo/releases")
+    );
+    assert_eq!(
+        releases_url("https://github.com/owner/repo.git/").as_deref(),
+        Some("https://api.github.com/repos/owner/repo/releases")
+    );
+    assert_eq!(releases_url("https://gitlab.com/owner/repo"), None);
+    assert_eq!(releases_url("https://github.com/owner"), None);
+}
@@ -0,0 +1,186 @@
+//! Token usage and estimated cost of chat and transcription calls, kept in
+//! the history database and summed by day, provider or model.
+//!
+//! Costs come from the providers' list prices below and are estimates:
+//! cached-input discounts, batch pricing and free tiers aren't modelled.
+//! Calls to models not listed are counted but not priced.
+
+use crate::history::{self, NewUsage, UsageGrouping, UsageSummary};
+use tauri::AppHandle;
+
+/// USD per million input and output tokens, by model id prefix.
+const CHAT_PRICES: [(&str, (f64, f64)); 17] = [
+    ("gpt-4o-mini", (0.15, 0.6)),
+    ("gpt-4o", (2.5, 10.0)),
+    ("gpt-4.1-nano", (0.1, 0.4)),
+    ("gpt-4.1-mini", (0.4, 1.6)),
+    ("gpt-4.1", (2.0, 8.0)),
+    ("gpt-5-nano", (0.05, 0.4)),
+    ("gpt-5-mini", (0.25, 2.0)),
+    ("gpt-5", (1.25, 10.0)),
+    ("o4-mini", (1.1, 4.4)),
+    ("o3", (2.0, 8.0)),
+    ("claude-opus-4", (15.0, 75.0)),
+    ("claude-sonnet-4", (3.0, 15.0)),
+    ("claude-3-5-haiku", (0.8, 4.0)),
+    ("gemini-2.5-pro", (1.25, 10.0)),
+    ("gemini-2.5-flash", (0.3, 2.5)),
+    ("gemini-2.0-flash", (0.1, 0.4)),
+    ("llama-3.3-70b", (0.59, 0.79)),
+];
+
+/// USD per minute of audio, by model id prefix.
+const TRANSCRIPTION_PRICES: [(&str, f64); 6] = [
+    ("whisper-1", 0.006),
+    ("gpt-4o-transcribe", 0.006),
+    ("gpt-4o-mini-transcribe", 0.003),
+    ("whisper-large-v3-turbo", 0.04 / 60.0),
+    ("whisper-large-v3", 0.111 / 60.0),
+    ("nova-3", 0.0043),
+];
+
+/// The entry of `prices` with the longest prefix of `model`, ignoring case
+/// and any `provider/` prefix.
+fn price_for<T: Copy>(prices: &[(&str, T)], model: &str) -> Option<T> {
+    let model = model.trim().to_ascii_lowercase();
+    let model = model.rsplit('/').next().unwrap_or_default();
+    prices
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|&(_, price)| price)
+}
+
+fn chat_cost(model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+    let (input, output) = price_for(&CHAT_PRICES, model)?;
+    Some((f64::from(input_tokens) * input + f64::from(output_tokens) * output) / 1_000_000.0)
+}
+
+fn transcription_cost(model: &str, audio_seconds: f64) -> Option<f64> {
+    price_for(&TRANSCRIPTION_PRICES, model).map(|per_minute| per_minute * audio_seconds / 60.0)
+}
+
+/// Input and output tokens from a chat response's `usage` object, in the
+/// OpenAI (`prompt_tokens`) or Anthropic (`input_tokens`) shape.
+pub fn reported_tokens(usage: &serde_json::Value) -> (Option<u32>, Option<u32>) {
+    let field = |names: [&str; 2]| {
+        names
+            .iter()
+            .find_map(|name| usage.get(name)?.as_u64())
+            .map(|n| u32::try_from(n).unwrap_or(u32::MAX))
+    };
+    (
+        field(["prompt_tokens", "input_tokens"]),
+        field(["completion_tokens", "output_tokens"]),
+    )
+}
+
+/// Length of the audio in an upload. WAV is read from its `fmt ` and
+/// `data` chunks, so extra chunks and streamed sizes are fine; anything
+/// else, like Ogg Opus from the encoding settings, goes to the encoder.
+pub fn audio_duration_seconds(bytes: &[u8]) -> Option<f64> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return crate::system_audio_encoder::encoded_duration_seconds(bytes);
+    }
+    let u32_at = |at: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+    };
+    let mut byte_rate = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let size = u32_at(at + 4)? as usize;
+        let body = at + 8;
+        match &bytes[at..at + 4] {
+            b"fmt " => byte_rate = u32_at(body + 8).filter(|&rate| rate > 0),
+            b"data" => {
+                // Streamed files leave the size unset; take what's there.
+                let len = size.min(bytes.len() - body);
+                return Some(len as f64 / f64::from(byte_rate?));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size.
+        at = body.checked_add(size)?.checked_add(size % 2)?;
+    }
+    None
+}
+
+/// Record a chat call in the background.
+pub fn record_chat(
+    app: &AppHandle,
+    provider: Option<&str>,
+    model: &str,
+    input_tokens: u32,
+    output_tokens: u32,
+) {
+    record(
+        app,
+        NewUsage {
+            kind: "chat",
+            provider: provider.unwrap_or("default").to_string(),
+            model: model.to_string(),
+            input_tokens,
+            output_tokens,
+            audio_seconds: 0.0,
+            cost_usd: chat_cost(model, input_tokens, output_tokens),
+        },
+    );
+}
+
+/// Record a transcription call in the background. Audio of unknown length
+/// isn't priced.
+pub fn record_transcription(
+    app: &AppHandle,
+    provider: Option<&str>,
+    model: &str,
+    audio_seconds: Option<f64>,
+) {
+    record(
+        app,
+        NewUsage {
+            kind: "transcription",
+            provider: provider.unwrap_or("default").to_string(),
+            model: model.to_string(),
+            input_tokens: 0,
+            output_tokens: 0,
+            audio_seconds: audio_seconds.unwrap_or(0.0),
+            cost_usd: audio_seconds.and_then(|seconds| transcription_cost(model, seconds)),
+        },
+    );
+}
+
+fn record(app: &AppHandle, usage: NewUsage) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = history::record_usage(&app, &usage).await {
+            tracing::warn!("Failed to record {} usage: {}", usage.kind, e);
+        }
+    });
+}
+
+fn parse_date(date: Option<String>) -> Result<Option<String>, String> {
+    match date {
+        Some(date) => chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map(|date| Some(date.format("%Y-%m-%d").to_string()))
+            .map_err(|_| format!("Dates must be YYYY-MM-DD, got {}", date)),
+        None => Ok(None),
+    }
+}
+
+/// Requests, tokens, audio and estimated cost grouped by `group_by` (day,
+/// provider or model), optionally between the UTC dates `from` and `to`
+/// (`YYYY-MM-DD`, inclusive).
+#[tauri::command]
+pub async fn get_usage_summary(
+    app: AppHandle,
+    group_by: UsageGrouping,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<UsageSummary>, String> {
+    let from = parse_date(from)?;
+    let to = parse_date(to)?;
+    history::usage_summary(&app, group_by, from.as_deref(), to.as_deref()).await
+}
+
+#[cfg(test)]
+mod tests;
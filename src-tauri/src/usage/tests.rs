@@ -0,0 +1,76 @@
+use super::*;
+
+fn wav(byte_rate: u32, data_len: usize, data_size: u32) -> Vec<u8> {
+    let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+    bytes.extend(b"fmt ");
+    bytes.extend(16u32.to_le_bytes());
+    bytes.extend([1, 0, 1, 0]);
+    bytes.extend(16_000u32.to_le_bytes());
+    bytes.extend(byte_rate.to_le_bytes());
+    bytes.extend([2, 0, 16, 0]);
+    bytes.extend(b"LIST");
+    bytes.extend(3u32.to_le_bytes());
+    bytes.extend([0, 0, 0, 0]);
+    bytes.extend(b"data");
+    bytes.extend(data_size.to_le_bytes());
+    bytes.extend(vec![0; data_len]);
+    bytes
+}
+
+#[test]
+fn longest_prefix_sets_the_price() {
+    assert_eq!(
+        chat_cost("gpt-4o-mini-2024-07-18", 1_000_000, 0),
+        Some(0.15)
+    );
+    assert_eq!(chat_cost("openai/GPT-4o", 0, 1_000_000), Some(10.0));
+    assert_eq!(
+        chat_cost("claude-sonnet-4-20250514", 1000, 1000),
+        Some(0.018)
+    );
+    assert_eq!(chat_cost("my-local-model", 1000, 1000), None);
+}
+
+#[test]
+fn transcription_is_priced_per_minute() {
+    assert_eq!(transcription_cost("whisper-1", 120.0), Some(0.012));
+    assert_eq!(
+        transcription_cost("gpt-4o-mini-transcribe", 60.0),
+        Some(0.003)
+    );
+    assert_eq!(transcription_cost("unknown-stt", 60.0), None);
+}
+
+#[test]
+fn wav_length_comes_from_its_chunks() {
+    // 32 000 bytes per second; the odd LIST chunk is padded.
+    assert_eq!(
+        audio_duration_seconds(&wav(32_000, 64_000, 64_000)),
+        Some(2.0)
+    );
+    // Unset streaming size: whatever data is present.
+    assert_eq!(
+        audio_duration_seconds(&wav(32_000, 16_000, u32::MAX)),
+        Some(0.5)
+    );
+    assert_eq!(audio_duration_seconds(&wav(0, 100, 100)), None);
+    assert_eq!(audio_duration_seconds(b"OggS\0\0\0\0\0\0\0\0"), None);
+}
+
+#[test]
+fn ogg_opus_length_is_read_too() {
+    use crate::system_audio_encoder::{encode_ogg_opus, EncodeOptions};
+    let silence = vec![0.0; 16_000 * 3];
+    let ogg = encode_ogg_opus(&silence, 16_000, &EncodeOptions::default(), &[]).unwrap();
+    let seconds = audio_duration_seconds(&ogg).unwrap();
+    assert!((seconds - 3.0).abs() < 1e-6, "{} s", seconds);
+}
+
+#[test]
+fn reported_tokens_accept_both_shapes() {
+    let openai = serde_json::json!({"prompt_tokens": 120, "completion_tokens": 40});
+    assert_eq!(reported_tokens(&openai), (Some(120), Some(40)));
+    let anthropic = serde_json::json!({"input_tokens": 7, "output_tokens": 3});
+    assert_eq!(reported_tokens(&anthropic), (Some(7), Some(3)));
+    assert_eq!(reported_tokens(&serde_json::json!({})), (None, None));
+}
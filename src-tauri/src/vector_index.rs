@@ -0,0 +1,328 @@
+//! A small on-disk vector index: embedded chunks of transcripts and notes
+//! with their text, searched by brute-force cosine similarity. A few
+//! thousand chunks is a few milliseconds to scan, which is all the history
+//! of one user needs.
+//!
+//! Every vector in one index comes from the same model, named by `model`;
+//! vectors from different models can't be compared, so callers start a new
+//! index when the model changes. The file is little-endian:
+//!
+//! ```text
+//! "RBVI" version:u8 dimensions:u32 model:str16 count:u32
+//! count × { kind:u8 id:str16 chunk:u32 text:str32 vector:[f32; dimensions] }
+//! ```
+//!
+//! where `strN` is a `uN` byte length followed by UTF-8.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"RBVI";
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// A history entry's transcript; `id` is the entry id.
+    Transcript,
+    /// A note from the frontend; `id` is the frontend's.
+    Note,
+}
+
+impl SourceKind {
+    fn code(self) -> u8 {
+        match self {
+            Self::Transcript => 0,
+            Self::Note => 1,
+        }
+    }
+
+    fn from_code(code: u8) -> Result<Self, String> {
+        match code {
+            0 => Ok(Self::Transcript),
+            1 => Ok(Self::Note),
+            _ => Err(format!("Unknown source kind {}", code)),
+        }
+    }
+}
+
+/// What a chunk was cut from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Source {
+    pub kind: SourceKind,
+    pub id: String,
+}
+
+impl Source {
+    pub fn transcript(entry_id: i64) -> Self {
+        Self {
+            kind: SourceKind::Transcript,
+            id: entry_id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Chunk {
+    source: Source,
+    chunk: u32,
+    text: String,
+    /// Unit length, so a dot product is the cosine similarity.
+    vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorMatch {
+    pub source: Source,
+    /// Position of the chunk within its source.
+    pub chunk: u32,
+    pub text: String,
+    /// Cosine similarity, -1 to 1.
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorIndex {
+    model: String,
+    /// 0 until the first vector is added.
+    dimensions: usize,
+    chunks: Vec<Chunk>,
+}
+
+/// `vector` scaled to unit length, or `None` if it's all zeros or not
+/// finite.
+fn normalize(mut vector: Vec<f32>) -> Option<Vec<f32>> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if !norm.is_finite() || norm == 0.0 {
+        return None;
+    }
+    vector.iter_mut().for_each(|x| *x /= norm);
+    Some(vector)
+}
+
+impl VectorIndex {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            dimensions: 0,
+            chunks: Vec::new(),
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Replace everything indexed for `source` with `chunks` of
+    /// `(text, vector)`, in order. An empty `chunks` removes the source.
+    pub fn replace(
+        &mut self,
+        source: &Source,
+        chunks: Vec<(String, Vec<f32>)>,
+    ) -> Result<(), String> {
+        // Other sources fix the dimensions; otherwise the first vector does.
+        let mut dimensions = match self.chunks.iter().any(|c| c.source != *source) {
+            true => self.dimensions,
+            false => 0,
+        };
+        let mut added = Vec::with_capacity(chunks.len());
+        for (chunk, (text, vector)) in chunks.into_iter().enumerate() {
+            if dimensions == 0 {
+                dimensions = vector.len();
+            }
+            if vector.len() != dimensions {
+                return Err(format!(
+                    "Expected {} dimensions, got {}",
+                    dimensions,
+                    vector.len()
+                ));
+            }
+            let vector =
+                normalize(vector).ok_or_else(|| "Embedding is zero or not finite".to_string())?;
+            added.push(Chunk {
+                source: source.clone(),
+                chunk: chunk as u32,
+                text,
+                vector,
+            });
+        }
+        self.remove(source);
+        self.dimensions = dimensions;
+        self.chunks.extend(added);
+        Ok(())
+    }
+
+    /// Drop everything indexed for `source`. Returns whether there was any.
+    pub fn remove(&mut self, source: &Source) -> bool {
+        let before = self.chunks.len();
+        self.chunks.retain(|c| c.source != *source);
+        if self.chunks.is_empty() {
+            self.dimensions = 0;
+        }
+        self.chunks.len() != before
+    }
+
+    /// The `limit` chunks most similar to `query`, best first.
+    pub fn search(&self, query: Vec<f32>, limit: usize) -> Result<Vec<VectorMatch>, String> {
+        if self.chunks.is_empty() {
+            return Ok(Vec::new());
+        }
+        if query.len() != self.dimensions {
+            return Err(format!(
+                "Expected {} dimensions, got {}",
+                self.dimensions,
+                query.len()
+            ));
+        }
+        let Some(query) = normalize(query) else {
+            return Ok(Vec::new());
+        };
+        let mut matches: Vec<VectorMatch> = self
+            .chunks
+            .iter()
+            .map(|c| VectorMatch {
+                source: c.source.clone(),
+                chunk: c.chunk,
+                text: c.text.clone(),
+                score: c.vector.iter().zip(&query).map(|(a, b)| a * b).sum(),
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + self.chunks.len() * (64 + self.dimensions * 4));
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&(self.dimensions as u32).to_le_bytes());
+        put_str16(&mut out, &self.model);
+        out.extend_from_slice(&(self.chunks.len() as u32).to_le_bytes());
+        for chunk in &self.chunks {
+            out.push(chunk.source.kind.code());
+            put_str16(&mut out, &chunk.source.id);
+            out.extend_from_slice(&chunk.chunk.to_le_bytes());
+            out.extend_from_slice(&(chunk.text.len() as u32).to_le_bytes());
+            out.extend_from_slice(chunk.text.as_bytes());
+            for x in &chunk.vector {
+                out.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err("Not a vector index".to_string());
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(format!("Unsupported vector index version {}", version));
+        }
+        let dimensions = reader.u32()? as usize;
+        let model = reader.str16()?;
+        let count = reader.u32()? as usize;
+        let mut chunks = Vec::with_capacity(count.min(bytes.len() / 16));
+        for _ in 0..count {
+            let kind = SourceKind::from_code(reader.take(1)?[0])?;
+            let id = reader.str16()?;
+            let chunk = reader.u32()?;
+            let len = reader.u32()? as usize;
+            let text = String::from_utf8(reader.take(len)?.to_vec()).map_err(|e| e.to_string())?;
+            let vector = reader
+                .take(dimensions.checked_mul(4).ok_or("Vector index is corrupt")?)?
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            chunks.push(Chunk {
+                source: Source { kind, id },
+                chunk,
+                text,
+                vector,
+            });
+        }
+        if !reader.bytes.is_empty() {
+            return Err("Vector index has trailing data".to_string());
+        }
+        Ok(Self {
+            model,
+            dimensions,
+            chunks,
+        })
+    }
+
+    /// The index at `path`, or `None` if there's none yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        match fs::read(path) {
+            Ok(bytes) => Self::decode(&bytes)
+                .map(Some)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Write the index to `path` through a temporary file, so a crash
+    /// mid-write leaves the previous index intact.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, self.encode())
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed to save the vector index: {}", e))
+    }
+}
+
+fn put_str16(out: &mut Vec<u8>, text: &str) {
+    let mut end = text.len().min(u16::MAX as usize);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let bytes = &text.as_bytes()[..end];
+    out.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() {
+            return Err("Vector index is truncated".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn str16(&mut self) -> Result<String, String> {
+        let b = self.take(2)?;
+        let len = u16::from_le_bytes([b[0], b[1]]) as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,115 @@
+use super::*;
+
+fn note(id: &str) -> Source {
+    Source {
+        kind: SourceKind::Note,
+        id: id.to_string(),
+    }
+}
+
+fn chunks(items: &[(&str, [f32; 3])]) -> Vec<(String, Vec<f32>)> {
+    items
+        .iter()
+        .map(|(text, vector)| (text.to_string(), vector.to_vec()))
+        .collect()
+}
+
+#[test]
+fn search_ranks_by_cosine_similarity() {
+    let mut index = VectorIndex::new("openai/text-embedding-3-small");
+    index
+        .replace(
+            &Source::transcript(1),
+            chunks(&[("standup", [1.0, 0.0, 0.0]), ("budget", [0.0, 2.0, 0.0])]),
+        )
+        .unwrap();
+    index
+        .replace(&note("n1"), chunks(&[("roadmap", [1.0, 1.0, 0.0])]))
+        .unwrap();
+
+    let matches = index.search(vec![3.0, 0.0, 0.0], 2).unwrap();
+    assert_eq!(
+        matches.iter().map(|m| m.text.as_str()).collect::<Vec<_>>(),
+        ["standup", "roadmap"]
+    );
+    assert!((matches[0].score - 1.0).abs() < 1e-6);
+    assert!((matches[1].score - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+    assert_eq!(matches[1].source, note("n1"));
+
+    assert!(index.search(vec![1.0, 0.0], 2).is_err());
+}
+
+#[test]
+fn replace_swaps_a_sources_chunks() {
+    let mut index = VectorIndex::new("m");
+    index
+        .replace(
+            &Source::transcript(1),
+            chunks(&[("a", [1.0, 0.0, 0.0]), ("b", [0.0, 1.0, 0.0])]),
+        )
+        .unwrap();
+    index
+        .replace(&Source::transcript(1), chunks(&[("c", [0.0, 0.0, 1.0])]))
+        .unwrap();
+    assert_eq!(index.len(), 1);
+
+    // Other sources pin the dimensions.
+    index
+        .replace(&note("n"), chunks(&[("d", [1.0, 0.0, 0.0])]))
+        .unwrap();
+    assert!(index
+        .replace(&note("n"), vec![("e".to_string(), vec![1.0, 0.0])])
+        .is_err());
+    assert!(index
+        .replace(&note("n"), vec![("e".to_string(), vec![0.0; 3])])
+        .is_err());
+    assert_eq!(index.len(), 2);
+
+    assert!(index.remove(&Source::transcript(1)));
+    assert!(!index.remove(&Source::transcript(1)));
+    index.replace(&note("n"), Vec::new()).unwrap();
+    assert_eq!(index.len(), 0);
+    // Empty again, so any model size fits.
+    index
+        .replace(&note("n"), vec![("f".to_string(), vec![1.0, 0.0])])
+        .unwrap();
+}
+
+#[test]
+fn index_round_trips_through_bytes() {
+    let mut index = VectorIndex::new("local/nomic-embed-text");
+    index
+        .replace(
+            &Source::transcript(7),
+            chunks(&[("héllo", [0.5, -0.5, 0.0])]),
+        )
+        .unwrap();
+    index
+        .replace(&note("note-1"), chunks(&[("", [0.0, 0.0, 4.0])]))
+        .unwrap();
+
+    let bytes = index.encode();
+    assert_eq!(VectorIndex::decode(&bytes).unwrap(), index);
+    assert!(VectorIndex::decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(VectorIndex::decode(b"nope").is_err());
+
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert!(VectorIndex::decode(&longer).is_err());
+}
+
+#[test]
+fn index_saves_and_loads() {
+    let dir = std::env::temp_dir().join(format!("runningbord-vectors-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.join("vectors.idx");
+    assert_eq!(VectorIndex::load(&path).unwrap(), None);
+
+    let mut index = VectorIndex::new("m");
+    index
+        .replace(&note("n"), chunks(&[("text", [1.0, 2.0, 3.0])]))
+        .unwrap();
+    index.save(&path).unwrap();
+    assert_eq!(VectorIndex::load(&path).unwrap(), Some(index));
+    let _ = fs::remove_dir_all(&dir);
+}
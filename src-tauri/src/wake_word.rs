@@ -0,0 +1,736 @@
+//! Optional always-on wake word ("hey Pluely") listening on the microphone.
+//!
+//! The built-in spotter is speaker-dependent: the user enrolls the phrase a
+//! few times, each recording is stored as a sequence of MFCC frames, and the
+//! live mic stream is searched for those templates with subsequence dynamic
+//! time warping, after cepstral mean normalization so a different
+//! microphone or room doesn't throw the match off. Enroll with the
+//! microphone that will be listening where possible. Everything runs
+//! locally and the templates never leave the app data directory.
+//!
+//! A pretrained, speaker-independent openWakeWord ONNX model can listen
+//! instead (see `wake_word_onnx`); other engines plug in by implementing
+//! `KeywordSpotter`.
+//!
+//! Fires `wake-word-detected` when the phrase is heard, then captures the
+//! moment (screenshot, recent audio, transcription) and emits it as
+//! `moment-captured`, so the whole flow works hands-free.
+
+use crate::focus_mode::suppresses_auto_capture;
+use crate::moment::{capture_moment_in_background, MomentTrigger, DEFAULT_MOMENT_SECONDS};
+use crate::system_audio::{linear_to_dbfs, AudioConverter, OUTPUT_SAMPLE_RATE};
+use crate::system_audio_dsp::audible_range;
+use crate::system_audio_spectrum::fft;
+use crate::wake_word_onnx::{installed_keywords, models_dir, OnnxSpotter};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use zeroize::Zeroize;
+
+/// 25 ms analysis frames every 10 ms, at 16 kHz.
+const FRAME_LEN: usize = 400;
+const HOP_LEN: usize = 160;
+const FFT_LEN: usize = 512;
+const MEL_FILTERS: usize = 26;
+/// Cepstral coefficients kept per frame (c1..c12; c0 is just loudness).
+pub(crate) const CEPSTRA: usize = 12;
+/// Match against the templates every this many new frames (50 ms).
+const CHECK_EVERY_FRAMES: usize = 5;
+/// A match may end this many frames before the newest one, so the phrase
+/// isn't missed between checks.
+const MATCH_TAIL_FRAMES: usize = 2 * CHECK_EVERY_FRAMES;
+/// The silence gate looks at this many of the newest frames (400 ms).
+const GATE_FRAMES: usize = 40;
+/// Frames ignored after a detection, so one utterance fires once.
+const REFRACTORY_FRAMES: usize = 150;
+/// Audio quieter than this on average is not matched at all.
+const SILENCE_GATE_DBFS: f32 = -50.0;
+
+/// Length of one enrollment recording.
+const ENROLL_DURATION: Duration = Duration::from_millis(2500);
+/// Level that marks where the enrolled phrase starts and ends.
+const ENROLL_TRIM_DBFS: f32 = -45.0;
+/// Accepted length of an enrolled phrase.
+const MIN_PHRASE_FRAMES: usize = 30;
+const MAX_PHRASE_FRAMES: usize = 200;
+/// Templates needed before listening can start (the spread between them
+/// sets the match threshold), and the most that are kept.
+pub const MIN_TEMPLATES: usize = 3;
+const MAX_TEMPLATES: usize = 5;
+const DEFAULT_SENSITIVITY: f32 = 0.5;
+
+pub(crate) type Frame = [f32; CEPSTRA];
+
+/// A keyword spotting engine fed 16 kHz mono microphone audio.
+pub trait KeywordSpotter: Send {
+    fn name(&self) -> &'static str;
+    /// Feed the next chunk of audio. Returns the match score if the keyword
+    /// ended within it; what the score means depends on the engine.
+    fn process(&mut self, samples: &[f32]) -> Option<f32>;
+}
+
+/// Recorded wake phrases, persisted as JSON.
+#[derive(Default, Serialize, Deserialize)]
+struct Templates {
+    templates: Vec<Vec<Frame>>,
+}
+
+struct Listener {
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+    engine: &'static str,
+    sensitivity: f32,
+    capture_seconds: f64,
+}
+
+#[derive(Default)]
+pub struct WakeWordState {
+    /// Loaded from disk on first use.
+    templates: Mutex<Option<Templates>>,
+    listener: Mutex<Option<Listener>>,
+    /// Microphone audio is dropped unheard while set (secure input).
+    paused: AtomicBool,
+}
+
+impl WakeWordState {
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct WakeWordStatus {
+    pub listening: bool,
+    /// The running engine, or the enrolled templates when not listening.
+    pub engine: &'static str,
+    pub templates: usize,
+    pub min_templates: usize,
+    /// openWakeWord models installed in `wake_word_models/`.
+    pub keywords: Vec<String>,
+    pub sensitivity: Option<f32>,
+    /// Audio included in the moment captured on each detection.
+    pub capture_seconds: Option<f64>,
+    /// Listening is suspended while the user types into a password field.
+    pub paused: bool,
+}
+
+/// Payload of `wake-word-detected`.
+#[derive(Clone, Serialize)]
+pub struct WakeWordDetected {
+    pub engine: &'static str,
+    /// For `template`, the DTW distance to the closest template (lower is
+    /// a closer match); for `onnx`, the model's confidence from 0 to 1.
+    pub score: f32,
+    pub detected_at_ms: u64,
+}
+
+fn templates_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data_dir.join("wake_word_templates.json"))
+}
+
+/// Run `f` on the templates, loading them from disk first if needed.
+fn with_templates<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut Templates) -> Result<T, String>,
+) -> Result<T, String> {
+    let state = app.state::<WakeWordState>();
+    let mut guard = state.templates.lock().map_err(|e| e.to_string())?;
+    if guard.is_none() {
+        let path = templates_path(app)?;
+        let loaded = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("Failed to parse wake word templates: {}", e))?,
+            Err(_) => Templates::default(),
+        };
+        *guard = Some(loaded);
+    }
+    f(guard.as_mut().expect("templates loaded above"))
+}
+
+fn save_templates(app: &AppHandle, templates: &Templates) -> Result<(), String> {
+    let json = serde_json::to_string(templates).map_err(|e| e.to_string())?;
+    fs::write(templates_path(app)?, json)
+        .map_err(|e| format!("Failed to save wake word templates: {}", e))
+}
+
+/// Open the default microphone and send its audio, converted to 16 kHz mono,
+/// to `tx`. Capture runs until the returned stream is dropped.
+fn open_microphone(tx: mpsc::Sender<Vec<f32>>) -> Result<cpal::Stream, String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| "No microphone found".to_string())?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get microphone config: {}", e))?;
+    let converter = AudioConverter::new(supported.sample_rate().0, supported.channels());
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => build_mic_stream::<f32>(&device, &config, converter, tx),
+        cpal::SampleFormat::I16 => build_mic_stream::<i16>(&device, &config, converter, tx),
+        cpal::SampleFormat::I32 => build_mic_stream::<i32>(&device, &config, converter, tx),
+        cpal::SampleFormat::U16 => build_mic_stream::<u16>(&device, &config, converter, tx),
+        other => return Err(format!("Unsupported microphone sample format: {}", other)),
+    }
+    .map_err(|e| format!("Failed to open microphone: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start microphone: {}", e))?;
+    Ok(stream)
+}
+
+fn build_mic_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut converter: AudioConverter,
+    tx: mpsc::Sender<Vec<f32>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let mut samples: Vec<f32> = Vec::new();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            samples.clear();
+            samples.extend(data.iter().map(|s| s.to_sample::<f32>()));
+            let converted = converter.convert_interleaved(&samples);
+            if !converted.is_empty() {
+                let _ = tx.send(converted);
+            }
+        },
+        |err| tracing::error!("Microphone stream error: {}", err),
+        None,
+    )
+}
+
+/// Record one utterance of the wake phrase and return its MFCC frames.
+fn record_phrase() -> Result<Vec<Frame>, String> {
+    let (tx, rx) = mpsc::channel();
+    let stream = open_microphone(tx)?;
+    let deadline = Instant::now() + ENROLL_DURATION;
+    let mut audio = Vec::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if let Ok(mut chunk) = rx.recv_timeout(left) {
+            audio.extend_from_slice(&chunk);
+            chunk.zeroize();
+        }
+    }
+    drop(stream);
+
+    let frames = audible_range(&audio, OUTPUT_SAMPLE_RATE, ENROLL_TRIM_DBFS)
+        .map(|range| phrase_features(&audio[range]))
+        .unwrap_or_default();
+    audio.zeroize();
+    if frames.is_empty() {
+        return Err("Didn't hear anything; say the wake phrase louder".to_string());
+    }
+    if frames.len() < MIN_PHRASE_FRAMES {
+        return Err("Wake phrase too short; use a few syllables".to_string());
+    }
+    if frames.len() > MAX_PHRASE_FRAMES {
+        return Err("Wake phrase too long or too much background noise".to_string());
+    }
+    Ok(frames)
+}
+
+/// MFCC frames of one trimmed utterance, as stored in templates.
+fn phrase_features(audio: &[f32]) -> Vec<Frame> {
+    Mfcc::new()
+        .push(audio)
+        .into_iter()
+        .map(|(f, _)| f)
+        .collect()
+}
+
+/// Spotter that matches MFCC sequences against enrolled templates with DTW.
+pub struct TemplateSpotter {
+    templates: Vec<Vec<Frame>>,
+    threshold: f32,
+    features: Mfcc,
+    /// Most recent frames (1.5x the longest template) with their level in
+    /// dBFS.
+    history: VecDeque<(Frame, f32)>,
+    history_len: usize,
+    since_check: usize,
+    refractory: usize,
+}
+
+impl TemplateSpotter {
+    /// `sensitivity` (0-1) scales how far from the templates a match may
+    /// be, relative to how far apart the templates themselves are.
+    pub fn new(templates: Vec<Vec<Frame>>, sensitivity: f32) -> Result<Self, String> {
+        if templates.len() < MIN_TEMPLATES {
+            return Err(format!(
+                "Enroll the wake phrase at least {} times first ({} so far)",
+                MIN_TEMPLATES,
+                templates.len()
+            ));
+        }
+        let templates: Vec<Vec<Frame>> = templates
+            .into_iter()
+            .map(|mut template| {
+                let mean = cepstral_mean(template.iter());
+                subtract_mean(&mut template, &mean);
+                template
+            })
+            .collect();
+        let mut spread = 0.0f32;
+        for (i, a) in templates.iter().enumerate() {
+            for b in &templates[i + 1..] {
+                spread = spread
+                    .max(subsequence_distance(a, b, MATCH_TAIL_FRAMES))
+                    .max(subsequence_distance(b, a, MATCH_TAIL_FRAMES));
+            }
+        }
+        let history_len = templates.iter().map(Vec::len).max().unwrap_or(0) * 3 / 2;
+        Ok(Self {
+            threshold: spread * (0.8 + 0.6 * sensitivity.clamp(0.0, 1.0)),
+            templates,
+            features: Mfcc::new(),
+            history: VecDeque::with_capacity(history_len),
+            history_len,
+            since_check: 0,
+            refractory: 0,
+        })
+    }
+
+    fn best_match(&self) -> Option<f32> {
+        let recent = self.history.len().min(GATE_FRAMES);
+        let level = self
+            .history
+            .iter()
+            .rev()
+            .take(recent)
+            .map(|(_, db)| db)
+            .sum::<f32>()
+            / recent.max(1) as f32;
+        if recent == 0 || level < SILENCE_GATE_DBFS {
+            return None;
+        }
+        // Normalize by the speech in the window; the templates are
+        // trimmed to the phrase, so their mean is over speech only.
+        let speech = self
+            .history
+            .iter()
+            .filter(|(_, db)| *db >= ENROLL_TRIM_DBFS);
+        let mean = cepstral_mean(speech.map(|(f, _)| f));
+        let mut history: Vec<Frame> = self.history.iter().map(|(f, _)| *f).collect();
+        subtract_mean(&mut history, &mean);
+        self.templates
+            .iter()
+            .map(|template| subsequence_distance(template, &history, MATCH_TAIL_FRAMES))
+            .min_by(f32::total_cmp)
+    }
+}
+
+impl KeywordSpotter for TemplateSpotter {
+    fn name(&self) -> &'static str {
+        "template"
+    }
+
+    fn process(&mut self, samples: &[f32]) -> Option<f32> {
+        let mut detection = None;
+        for frame in self.features.push(samples) {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back(frame);
+            if self.refractory > 0 {
+                self.refractory -= 1;
+                continue;
+            }
+            self.since_check += 1;
+            if self.since_check < CHECK_EVERY_FRAMES {
+                continue;
+            }
+            self.since_check = 0;
+            if let Some(score) = self.best_match().filter(|s| *s <= self.threshold) {
+                self.refractory = REFRACTORY_FRAMES;
+                self.history.clear();
+                detection = Some(score);
+            }
+        }
+        detection
+    }
+}
+
+/// Incremental MFCC front end: pre-emphasis, Hamming window, 26 mel bands,
+/// log, DCT.
+pub(crate) struct Mfcc {
+    pending: Vec<f32>,
+    last: f32,
+    window: Vec<f32>,
+    /// Per mel band: (first FFT bin, weights).
+    filters: Vec<(usize, Vec<f32>)>,
+}
+
+impl Mfcc {
+    pub(crate) fn new() -> Self {
+        let window = (0..FRAME_LEN)
+            .map(|i| {
+                0.54 - 0.46 * (std::f32::consts::TAU * i as f32 / (FRAME_LEN - 1) as f32).cos()
+            })
+            .collect();
+        let mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+        let hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+        let (lo, hi) = (mel(20.0), mel(OUTPUT_SAMPLE_RATE as f32 / 2.0));
+        let bin = |f: f32| f * FFT_LEN as f32 / OUTPUT_SAMPLE_RATE as f32;
+        let edges: Vec<f32> = (0..MEL_FILTERS + 2)
+            .map(|i| bin(hz(lo + (hi - lo) * i as f32 / (MEL_FILTERS + 1) as f32)))
+            .collect();
+        let filters = edges
+            .windows(3)
+            .map(|e| {
+                let first = e[0].ceil() as usize;
+                let last = (e[2].floor() as usize).min(FFT_LEN / 2);
+                let weights = (first..=last)
+                    .map(|k| {
+                        let k = k as f32;
+                        if k <= e[1] {
+                            (k - e[0]) / (e[1] - e[0])
+                        } else {
+                            (e[2] - k) / (e[2] - e[1])
+                        }
+                    })
+                    .collect();
+                (first, weights)
+            })
+            .collect();
+        Self {
+            pending: Vec::new(),
+            last: 0.0,
+            window,
+            filters,
+        }
+    }
+
+    /// Append samples; returns every frame completed, with its level.
+    pub(crate) fn push(&mut self, samples: &[f32]) -> Vec<(Frame, f32)> {
+        for s in samples {
+            self.pending.push(s - 0.97 * self.last);
+            self.last = *s;
+        }
+        let mut frames = Vec::new();
+        let mut re = vec![0.0f32; FFT_LEN];
+        let mut im = vec![0.0f32; FFT_LEN];
+        let mut consumed = 0;
+        while consumed + FRAME_LEN <= self.pending.len() {
+            let frame = &self.pending[consumed..consumed + FRAME_LEN];
+            let rms = (frame.iter().map(|s| s * s).sum::<f32>() / FRAME_LEN as f32).sqrt();
+            re.fill(0.0);
+            im.fill(0.0);
+            for (i, s) in frame.iter().enumerate() {
+                re[i] = s * self.window[i];
+            }
+            fft(&mut re, &mut im);
+            let energies: Vec<f32> = self
+                .filters
+                .iter()
+                .map(|(first, weights)| {
+                    let e: f32 = weights
+                        .iter()
+                        .enumerate()
+                        .map(|(j, w)| {
+                            let k = first + j;
+                            w * (re[k] * re[k] + im[k] * im[k])
+                        })
+                        .sum();
+                    (e + 1e-10).ln()
+                })
+                .collect();
+            let mut cepstrum = [0.0f32; CEPSTRA];
+            for (c, value) in cepstrum.iter_mut().enumerate() {
+                let n = (c + 1) as f32;
+                *value = energies
+                    .iter()
+                    .enumerate()
+                    .map(|(m, e)| {
+                        e * (std::f32::consts::PI * n * (m as f32 + 0.5) / MEL_FILTERS as f32).cos()
+                    })
+                    .sum();
+            }
+            frames.push((cepstrum, linear_to_dbfs(rms)));
+            consumed += HOP_LEN;
+        }
+        self.pending[..consumed].zeroize();
+        self.pending.drain(..consumed);
+        re.zeroize();
+        im.zeroize();
+        frames
+    }
+}
+
+/// Average of `frames`; zero if there are none.
+fn cepstral_mean<'a>(frames: impl Iterator<Item = &'a Frame>) -> Frame {
+    let mut mean = [0.0f32; CEPSTRA];
+    let mut count = 0;
+    for frame in frames {
+        for (m, c) in mean.iter_mut().zip(frame) {
+            *m += c;
+        }
+        count += 1;
+    }
+    if count > 0 {
+        mean.iter_mut().for_each(|m| *m /= count as f32);
+    }
+    mean
+}
+
+/// Cepstral mean normalization. A different microphone or room is a
+/// fixed filter on the voice, which shows up as a constant offset in the
+/// cepstrum; taking out the mean keeps it from moving the phrase away
+/// from its templates.
+fn subtract_mean(frames: &mut [Frame], mean: &Frame) {
+    for frame in frames {
+        for (c, m) in frame.iter_mut().zip(mean) {
+            *c -= m;
+        }
+    }
+}
+
+/// Subsequence DTW: the cost of the best warped match of all of `template`
+/// against any stretch of `history` ending in its last `tail` frames,
+/// averaged per template frame.
+fn subsequence_distance(template: &[Frame], history: &[Frame], tail: usize) -> f32 {
+    let (n, m) = (template.len(), history.len());
+    if n == 0 || m == 0 {
+        return f32::INFINITY;
+    }
+    // A match may start anywhere in `history`: row 0 costs nothing.
+    let mut prev = vec![0.0f32; m + 1];
+    let mut cur = vec![f32::INFINITY; m + 1];
+    for t in template {
+        cur[0] = f32::INFINITY;
+        for j in 1..=m {
+            let cost = t
+                .iter()
+                .zip(&history[j - 1])
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+                .sqrt();
+            cur[j] = cost + prev[j].min(prev[j - 1]).min(cur[j - 1]);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    let ends = m.saturating_sub(tail).max(1)..=m;
+    prev[ends].iter().copied().fold(f32::INFINITY, f32::min) / n as f32
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Feed the microphone to `spotter` on a dedicated thread (the cpal stream
+/// must stay on the thread that opened it) until `stop` is set.
+fn spawn_listener(
+    app: AppHandle,
+    mut spotter: Box<dyn KeywordSpotter>,
+    capture_seconds: f64,
+    stop: Arc<AtomicBool>,
+) -> Result<thread::JoinHandle<()>, String> {
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+    let handle = thread::spawn(move || {
+        let (tx, rx) = mpsc::channel();
+        let stream = match open_microphone(tx) {
+            Ok(stream) => {
+                let _ = ready_tx.send(Ok(()));
+                stream
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        let engine = spotter.name();
+        tracing::info!("Wake word listener started ({} engine)", engine);
+        while !stop.load(Ordering::SeqCst) {
+            let Ok(mut chunk) = rx.recv_timeout(Duration::from_millis(100)) else {
+                continue;
+            };
+            if app.state::<WakeWordState>().paused.load(Ordering::SeqCst) {
+                chunk.zeroize();
+                continue;
+            }
+            let detected = spotter.process(&chunk);
+            chunk.zeroize();
+            if let Some(score) = detected {
+                tracing::info!("Wake word detected (score {:.2})", score);
+                let _ = app.emit(
+                    "wake-word-detected",
+                    WakeWordDetected {
+                        engine,
+                        score,
+                        detected_at_ms: now_millis(),
+                    },
+                );
+                if suppresses_auto_capture(&app) {
+                    tracing::info!("Wake word capture skipped: Focus mode is on");
+                } else {
+                    capture_moment_in_background(
+                        app.clone(),
+                        capture_seconds,
+                        MomentTrigger::WakeWord,
+                    );
+                }
+            }
+        }
+        drop(stream);
+        tracing::info!("Wake word listener stopped");
+    });
+    ready_rx
+        .recv()
+        .map_err(|_| "Wake word listener exited during startup".to_string())??;
+    Ok(handle)
+}
+
+/// Record the wake phrase once (about 2.5 s from the default microphone)
+/// and add it to the templates. Returns how many are enrolled.
+#[tauri::command]
+pub async fn wake_word_enroll(app: AppHandle) -> Result<usize, String> {
+    if with_templates(&app, |t| Ok(t.templates.len()))? >= MAX_TEMPLATES {
+        return Err(format!(
+            "Already {} recordings; clear them to re-enroll",
+            MAX_TEMPLATES
+        ));
+    }
+    let frames = tauri::async_runtime::spawn_blocking(record_phrase)
+        .await
+        .map_err(|e| e.to_string())??;
+    with_templates(&app, |t| {
+        t.templates.push(frames);
+        save_templates(&app, t)?;
+        Ok(t.templates.len())
+    })
+}
+
+/// Delete every enrolled recording (stops listening first).
+#[tauri::command]
+pub async fn wake_word_clear(app: AppHandle) -> Result<(), String> {
+    wake_word_stop(app.clone()).await?;
+    with_templates(&app, |t| {
+        t.templates.clear();
+        save_templates(&app, t)
+    })
+}
+
+/// Start listening for the wake phrase: the enrolled one, or with
+/// `keyword`, an installed openWakeWord model of that name.
+/// `sensitivity` (0-1, default 0.5) trades missed detections against false
+/// triggers; `capture_seconds` (default 30) is how much recent audio each
+/// detection captures.
+#[tauri::command]
+pub async fn wake_word_start(
+    app: AppHandle,
+    sensitivity: Option<f32>,
+    capture_seconds: Option<f64>,
+    keyword: Option<String>,
+) -> Result<(), String> {
+    let sensitivity = sensitivity.unwrap_or(DEFAULT_SENSITIVITY);
+    if !(0.0..=1.0).contains(&sensitivity) {
+        return Err(format!(
+            "sensitivity must be between 0 and 1, got {}",
+            sensitivity
+        ));
+    }
+    let capture_seconds = capture_seconds.unwrap_or(DEFAULT_MOMENT_SECONDS);
+    if !capture_seconds.is_finite() || capture_seconds <= 0.0 {
+        return Err(format!(
+            "capture_seconds must be positive, got {}",
+            capture_seconds
+        ));
+    }
+    wake_word_stop(app.clone()).await?;
+    let spotter: Box<dyn KeywordSpotter> = match keyword {
+        Some(keyword) => {
+            let dir = models_dir(&app)?;
+            tauri::async_runtime::spawn_blocking(move || {
+                OnnxSpotter::new(&dir, &keyword, sensitivity)
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|spotter| Box::new(spotter) as Box<dyn KeywordSpotter>)?
+        }
+        None => {
+            let templates = with_templates(&app, |t| Ok(t.templates.clone()))?;
+            Box::new(TemplateSpotter::new(templates, sensitivity)?)
+        }
+    };
+    let engine = spotter.name();
+    let stop = Arc::new(AtomicBool::new(false));
+    let listener_app = app.clone();
+    let listener_stop = stop.clone();
+    let thread = tauri::async_runtime::spawn_blocking(move || {
+        spawn_listener(listener_app, spotter, capture_seconds, listener_stop)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let state = app.state::<WakeWordState>();
+    *state.listener.lock().map_err(|e| e.to_string())? = Some(Listener {
+        stop,
+        thread,
+        engine,
+        sensitivity,
+        capture_seconds,
+    });
+    Ok(())
+}
+
+/// Stop listening and release the microphone.
+#[tauri::command]
+pub async fn wake_word_stop(app: AppHandle) -> Result<(), String> {
+    let listener = app
+        .state::<WakeWordState>()
+        .listener
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+    if let Some(listener) = listener {
+        listener.stop.store(true, Ordering::SeqCst);
+        tauri::async_runtime::spawn_blocking(move || listener.thread.join())
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|_| "Wake word listener panicked".to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn wake_word_status(app: AppHandle) -> Result<WakeWordStatus, String> {
+    let templates = with_templates(&app, |t| Ok(t.templates.len()))?;
+    let keywords = installed_keywords(&models_dir(&app)?);
+    let state = app.state::<WakeWordState>();
+    let listener = state.listener.lock().map_err(|e| e.to_string())?;
+    Ok(WakeWordStatus {
+        listening: listener.is_some(),
+        engine: listener.as_ref().map_or("template", |l| l.engine),
+        templates,
+        min_templates: MIN_TEMPLATES,
+        keywords,
+        sensitivity: listener.as_ref().map(|l| l.sensitivity),
+        capture_seconds: listener.as_ref().map(|l| l.capture_seconds),
+        paused: state.paused.load(Ordering::SeqCst),
+    })
+}
+
+#[cfg(test)]
+mod tests;
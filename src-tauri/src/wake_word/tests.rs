@@ -0,0 +1,120 @@
+//! Template spotter tests on synthetic "phrases": three 250 ms harmonic
+//! segments, so the order of the segments is what tells two phrases apart.
+
+use super::*;
+
+const WAKE_PHRASE: [[f32; 3]; 3] = [
+    [300.0, 700.0, 2200.0],
+    [250.0, 1900.0, 2600.0],
+    [500.0, 900.0, 2400.0],
+];
+const OTHER_PHRASE: [[f32; 3]; 3] = [
+    [500.0, 900.0, 2400.0],
+    [350.0, 1200.0, 2900.0],
+    [300.0, 700.0, 2200.0],
+];
+
+/// Deterministic low-level noise floor.
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 40) as f32 / (1u64 << 24) as f32 - 0.5) * 0.006
+    }
+
+    fn silence(&mut self, len: usize) -> Vec<f32> {
+        (0..len).map(|_| self.next()).collect()
+    }
+}
+
+/// `segments` spoken at `tempo` (1.0 = 250 ms per segment) and `amplitude`.
+fn phrase(segments: &[[f32; 3]], tempo: f32, amplitude: f32, noise: &mut Noise) -> Vec<f32> {
+    let len = (4000.0 * tempo) as usize;
+    let mut out = Vec::new();
+    for (index, partials) in segments.iter().enumerate() {
+        for n in 0..len {
+            let t = n as f32 / OUTPUT_SAMPLE_RATE as f32;
+            let envelope = (n.min(len - 1 - n) as f32 / 400.0).min(1.0);
+            let voice: f32 = partials
+                .iter()
+                .enumerate()
+                .map(|(k, f)| (t * f * std::f32::consts::TAU + index as f32).sin() / (k + 1) as f32)
+                .sum();
+            out.push(voice * amplitude * envelope * 0.5 + noise.next());
+        }
+    }
+    out
+}
+
+fn enrolled(noise: &mut Noise) -> TemplateSpotter {
+    let templates = [(0.9, 0.3), (1.0, 0.5), (1.1, 0.4)]
+        .iter()
+        .map(|(tempo, amplitude)| phrase_features(&phrase(&WAKE_PHRASE, *tempo, *amplitude, noise)))
+        .collect();
+    TemplateSpotter::new(templates, DEFAULT_SENSITIVITY).unwrap()
+}
+
+/// Feed one second of silence, `speech`, then one second of silence in
+/// 20 ms chunks; returns the number of detections.
+fn detections(spotter: &mut TemplateSpotter, speech: &[f32], noise: &mut Noise) -> usize {
+    let mut stream = noise.silence(OUTPUT_SAMPLE_RATE as usize);
+    stream.extend_from_slice(speech);
+    stream.extend(noise.silence(OUTPUT_SAMPLE_RATE as usize));
+    stream
+        .chunks(320)
+        .filter(|chunk| spotter.process(chunk).is_some())
+        .count()
+}
+
+#[test]
+fn enrolled_phrase_is_detected_once() {
+    let mut noise = Noise(12345);
+    for tempo in [1.05, 1.2] {
+        let mut spotter = enrolled(&mut noise);
+        let speech = phrase(&WAKE_PHRASE, tempo, 0.35, &mut noise);
+        assert_eq!(
+            detections(&mut spotter, &speech, &mut noise),
+            1,
+            "tempo {}",
+            tempo
+        );
+    }
+}
+
+#[test]
+fn other_phrase_and_silence_are_ignored() {
+    let mut noise = Noise(12345);
+    let mut spotter = enrolled(&mut noise);
+    let speech = phrase(&OTHER_PHRASE, 1.0, 0.35, &mut noise);
+    assert_eq!(detections(&mut spotter, &speech, &mut noise), 0);
+    assert_eq!(detections(&mut spotter, &[], &mut noise), 0);
+}
+
+#[test]
+fn too_few_templates_are_rejected() {
+    let mut noise = Noise(1);
+    let template = phrase_features(&phrase(&WAKE_PHRASE, 1.0, 0.4, &mut noise));
+    assert!(TemplateSpotter::new(vec![template; MIN_TEMPLATES - 1], DEFAULT_SENSITIVITY).is_err());
+}
+
+#[test]
+fn phrase_is_detected_through_a_different_microphone() {
+    let mut noise = Noise(777);
+    let mut spotter = enrolled(&mut noise);
+    // A brighter microphone: a fixed first-order high-frequency boost.
+    let speech = phrase(&WAKE_PHRASE, 1.0, 0.35, &mut noise);
+    let mut previous = 0.0;
+    let filtered: Vec<f32> = speech
+        .iter()
+        .map(|s| {
+            let out = s - 0.45 * previous;
+            previous = *s;
+            out
+        })
+        .collect();
+    assert_eq!(detections(&mut spotter, &filtered, &mut noise), 1);
+}
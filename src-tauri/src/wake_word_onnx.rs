@@ -0,0 +1,238 @@
+//! Wake word engine running pretrained openWakeWord ONNX models.
+//!
+//! Unlike the enrolled templates, these models are speaker-independent.
+//! openWakeWord splits the work in three: a melspectrogram model and an
+//! embedding model shared by every keyword, and a small classifier per
+//! keyword that scores the last couple of seconds of embeddings. All of
+//! them go in `wake_word_models/` in the app data directory; the shared two
+//! keep their upstream names (`melspectrogram.onnx`, `embedding_model.onnx`)
+//! and each keyword model is chosen by its file stem (`hey_pluely.onnx` is
+//! `hey_pluely`). The models are the user's to install, so nothing is
+//! downloaded and audio never leaves the app.
+
+use crate::wake_word::KeywordSpotter;
+use ort::session::Session;
+use ort::value::Tensor;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use zeroize::Zeroize;
+
+const MELSPECTROGRAM_MODEL: &str = "melspectrogram.onnx";
+const EMBEDDING_MODEL: &str = "embedding_model.onnx";
+/// The models step in 80 ms chunks of 16 kHz audio.
+const CHUNK_SAMPLES: usize = 1280;
+/// Audio from the previous chunk the melspectrogram needs for its first
+/// frames.
+const MEL_CONTEXT_SAMPLES: usize = 480;
+const MEL_BINS: usize = 32;
+/// Mel frames per embedding (about 775 ms).
+const EMBEDDING_WINDOW: usize = 76;
+const EMBEDDING_LEN: usize = 96;
+/// Embeddings per keyword score (about 1.3 s).
+const KEYWORD_WINDOW: usize = 16;
+/// Chunks ignored after a detection, so one utterance fires once (1.5 s).
+const REFRACTORY_CHUNKS: usize = 19;
+
+/// `wake_word_models/` in the app data directory.
+pub fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join("wake_word_models");
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create wake word models dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Keyword models installed in `dir`, by name, sorted.
+pub fn installed_keywords(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| keyword_name(&entry.file_name().to_string_lossy()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// The keyword name of a model file, or `None` for the shared models and
+/// anything else.
+pub fn keyword_name(file_name: &str) -> Option<String> {
+    if file_name == MELSPECTROGRAM_MODEL || file_name == EMBEDDING_MODEL {
+        return None;
+    }
+    let stem = file_name.strip_suffix(".onnx")?;
+    valid_keyword(stem).then(|| stem.to_string())
+}
+
+/// Only letters, digits and `_`, so a keyword is always safe as a file
+/// name.
+fn valid_keyword(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn load(path: &Path) -> Result<Session, String> {
+    if !path.is_file() {
+        return Err(format!("Wake word model not installed: {}", path.display()));
+    }
+    Session::builder()
+        .and_then(|builder| builder.with_intra_threads(1))
+        .and_then(|builder| builder.commit_from_file(path))
+        .map_err(|e| format!("Failed to load {}: {}", path.display(), e))
+}
+
+/// Run a single-input model and return its first output, flattened.
+fn run<const N: usize>(
+    session: &mut Session,
+    shape: [usize; N],
+    data: Vec<f32>,
+) -> Result<Vec<f32>, String> {
+    let input = Tensor::from_array((shape, data)).map_err(|e| e.to_string())?;
+    let outputs = session
+        .run(ort::inputs![input])
+        .map_err(|e| e.to_string())?;
+    let (_, values) = outputs[0]
+        .try_extract_tensor::<f32>()
+        .map_err(|e| e.to_string())?;
+    Ok(values.to_vec())
+}
+
+pub struct OnnxSpotter {
+    melspectrogram: Session,
+    embedding: Session,
+    keyword: Session,
+    threshold: f32,
+    /// Audio waiting for a full chunk, scaled to the 16-bit range the
+    /// models were trained on.
+    pending: Vec<f32>,
+    /// The tail of the previous chunk.
+    context: Vec<f32>,
+    mel: VecDeque<[f32; MEL_BINS]>,
+    embeddings: VecDeque<Vec<f32>>,
+    refractory: usize,
+    /// A model that failed once is reported once, not every chunk.
+    failed: bool,
+}
+
+impl OnnxSpotter {
+    /// Load the shared models and `keyword` from `dir`. `sensitivity`
+    /// (0-1) lowers the score a detection needs; 0.5 is openWakeWord's
+    /// default threshold.
+    pub fn new(dir: &Path, keyword: &str, sensitivity: f32) -> Result<Self, String> {
+        if !valid_keyword(keyword) {
+            return Err(format!("Not a wake word model name: {}", keyword));
+        }
+        Ok(Self {
+            melspectrogram: load(&dir.join(MELSPECTROGRAM_MODEL))?,
+            embedding: load(&dir.join(EMBEDDING_MODEL))?,
+            keyword: load(&dir.join(format!("{}.onnx", keyword)))?,
+            threshold: 1.0 - sensitivity.clamp(0.0, 1.0),
+            pending: Vec::with_capacity(2 * CHUNK_SAMPLES),
+            context: vec![0.0; MEL_CONTEXT_SAMPLES],
+            mel: VecDeque::with_capacity(EMBEDDING_WINDOW),
+            embeddings: VecDeque::with_capacity(KEYWORD_WINDOW),
+            refractory: 0,
+            failed: false,
+        })
+    }
+
+    /// Feed one chunk through the three models; the keyword score once
+    /// enough audio has been seen.
+    fn step(&mut self, chunk: &[f32]) -> Result<Option<f32>, String> {
+        let mut audio = Vec::with_capacity(MEL_CONTEXT_SAMPLES + chunk.len());
+        audio.extend_from_slice(&self.context);
+        audio.extend_from_slice(chunk);
+        self.context
+            .copy_from_slice(&audio[audio.len() - MEL_CONTEXT_SAMPLES..]);
+        let len = audio.len();
+        let mel = run(&mut self.melspectrogram, [1, len], audio)?;
+        for frame in mel.chunks_exact(MEL_BINS) {
+            if self.mel.len() == EMBEDDING_WINDOW {
+                self.mel.pop_front();
+            }
+            // The scaling openWakeWord applies before the embedding model.
+            self.mel
+                .push_back(std::array::from_fn(|i| frame[i] / 10.0 + 2.0));
+        }
+        if self.mel.len() < EMBEDDING_WINDOW {
+            return Ok(None);
+        }
+
+        let window = self.mel.iter().flatten().copied().collect();
+        let embedding = run(
+            &mut self.embedding,
+            [1, EMBEDDING_WINDOW, MEL_BINS, 1],
+            window,
+        )?;
+        if embedding.len() != EMBEDDING_LEN {
+            return Err(format!(
+                "Embedding model returned {} values, expected {}",
+                embedding.len(),
+                EMBEDDING_LEN
+            ));
+        }
+        if self.embeddings.len() == KEYWORD_WINDOW {
+            self.embeddings.pop_front();
+        }
+        self.embeddings.push_back(embedding);
+        if self.embeddings.len() < KEYWORD_WINDOW {
+            return Ok(None);
+        }
+
+        let features = self.embeddings.iter().flatten().copied().collect();
+        let score = run(
+            &mut self.keyword,
+            [1, KEYWORD_WINDOW, EMBEDDING_LEN],
+            features,
+        )?;
+        Ok(score.first().copied())
+    }
+}
+
+impl KeywordSpotter for OnnxSpotter {
+    fn name(&self) -> &'static str {
+        "onnx"
+    }
+
+    fn process(&mut self, samples: &[f32]) -> Option<f32> {
+        self.pending
+            .extend(samples.iter().map(|s| s * i16::MAX as f32));
+        let mut detection = None;
+        while self.pending.len() >= CHUNK_SAMPLES {
+            let mut chunk: Vec<f32> = self.pending.drain(..CHUNK_SAMPLES).collect();
+            let score = self.step(&chunk);
+            chunk.zeroize();
+            if self.refractory > 0 {
+                self.refractory -= 1;
+                continue;
+            }
+            match score {
+                Ok(Some(score)) if score >= self.threshold => {
+                    self.refractory = REFRACTORY_CHUNKS;
+                    detection = Some(score);
+                }
+                Ok(_) => {}
+                Err(e) if !self.failed => {
+                    self.failed = true;
+                    tracing::warn!("Wake word model failed: {}", e);
+                }
+                Err(_) => {}
+            }
+        }
+        detection
+    }
+}
+
+impl Drop for OnnxSpotter {
+    fn drop(&mut self) {
+        self.pending.zeroize();
+        self.context.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,43 @@
+use super::*;
+
+#[test]
+fn keyword_models_are_named_by_their_stem() {
+    assert_eq!(
+        keyword_name("hey_pluely.onnx"),
+        Some("hey_pluely".to_string())
+    );
+    for file_name in [
+        MELSPECTROGRAM_MODEL,
+        EMBEDDING_MODEL,
+        "hey_pluely.onnx.json",
+        "hey pluely.onnx",
+        "../x.onnx",
+        ".onnx",
+        "notes.txt",
+    ] {
+        assert_eq!(keyword_name(file_name), None, "{}", file_name);
+    }
+}
+
+#[test]
+fn installed_keywords_skip_the_shared_models() {
+    let dir = std::env::temp_dir().join(format!("runningbord-wake-word-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for file_name in [
+        MELSPECTROGRAM_MODEL,
+        EMBEDDING_MODEL,
+        "weather.onnx",
+        "alexa.onnx",
+    ] {
+        fs::write(dir.join(file_name), b"").unwrap();
+    }
+    assert_eq!(installed_keywords(&dir), vec!["alexa", "weather"]);
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(installed_keywords(&dir).is_empty());
+}
+
+#[test]
+fn keyword_names_are_checked_before_loading() {
+    let dir = std::env::temp_dir();
+    assert!(OnnxSpotter::new(&dir, "../secret", 0.5).is_err());
+}
@@ -0,0 +1,179 @@
+//! Webhook for new transcripts: every moment capture that produced a
+//! transcription is POSTed as JSON to a configured URL, for Zapier, n8n
+//! and similar automations. Optionally the moment's audio is uploaded
+//! through `s3_upload` and linked from the payload.
+//!
+//! Off by default. Delivery is fire-and-forget: failures are logged, not
+//! retried.
+
+use crate::moment::{MomentContext, MomentTrigger};
+use crate::network;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long the audio link in a payload stays valid.
+const AUDIO_LINK_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WebhookConfig {
+    pub enabled: bool,
+    pub url: String,
+    /// Upload each moment's audio to the S3 bucket and include a link.
+    pub include_audio_link: bool,
+}
+
+#[derive(Default)]
+pub struct WebhookState {
+    config: Mutex<WebhookConfig>,
+}
+
+/// Body of every POST.
+#[derive(Debug, Serialize)]
+struct TranscriptPayload<'a> {
+    event: &'static str,
+    trigger: MomentTrigger,
+    captured_at_ms: u64,
+    transcription: &'a str,
+    start_time_ms: Option<u64>,
+    end_time_ms: Option<u64>,
+    audio_url: Option<String>,
+    errors: &'a [String],
+}
+
+fn payload<'a>(
+    context: &'a MomentContext,
+    transcription: &'a str,
+    audio_url: Option<String>,
+) -> TranscriptPayload<'a> {
+    TranscriptPayload {
+        event: "transcript.created",
+        trigger: context.trigger,
+        captured_at_ms: context.captured_at_ms,
+        transcription,
+        start_time_ms: context.audio.as_ref().and_then(|a| a.start_time_ms),
+        end_time_ms: context.audio.as_ref().and_then(|a| a.end_time_ms),
+        audio_url,
+        errors: &context.errors,
+    }
+}
+
+fn validate_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+    if !matches!(parsed.scheme(), "https" | "http") {
+        return Err("Webhook URL must be http(s)".to_string());
+    }
+    Ok(())
+}
+
+fn current_config(app: &AppHandle) -> Result<WebhookConfig, String> {
+    Ok(app
+        .state::<WebhookState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+async fn post(app: &AppHandle, url: &str, payload: &TranscriptPayload<'_>) -> Result<(), String> {
+    let response = network::client(app, None)?
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Upload the moment's audio and return a shareable link.
+async fn audio_link(app: &AppHandle, context: &MomentContext) -> Result<Option<String>, String> {
+    let Some(audio) = &context.audio else {
+        return Ok(None);
+    };
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&audio.audio_base64)
+        .map_err(|e| e.to_string())?;
+    let name = format!("moment-{}.ogg", context.captured_at_ms);
+    crate::s3_upload::upload_shared(app, &name, bytes, "audio/ogg", AUDIO_LINK_TTL)
+        .await
+        .map(Some)
+}
+
+/// Send `context` to the webhook in the background, if one is enabled and
+/// the moment has a transcription. Called for every assembled moment.
+pub fn moment_captured(app: &AppHandle, context: &MomentContext) {
+    let config = match current_config(app) {
+        Ok(config) if config.enabled && !config.url.is_empty() => config,
+        _ => return,
+    };
+    if context.transcription.is_none() {
+        return;
+    }
+    let app = app.clone();
+    let context = context.clone();
+    tauri::async_runtime::spawn(async move {
+        let audio_url = if config.include_audio_link {
+            // A failed upload still delivers the transcript.
+            audio_link(&app, &context).await.unwrap_or_else(|e| {
+                tracing::warn!("Webhook audio upload failed: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+        let transcription = context.transcription.as_deref().unwrap_or_default();
+        let payload = payload(&context, transcription, audio_url);
+        if let Err(e) = post(&app, &config.url, &payload).await {
+            tracing::warn!("{}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn webhook_set_config(app: AppHandle, config: WebhookConfig) -> Result<(), String> {
+    if config.enabled || !config.url.is_empty() {
+        validate_url(&config.url)?;
+    }
+    *app.state::<WebhookState>()
+        .config
+        .lock()
+        .map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn webhook_get_config(app: AppHandle) -> Result<WebhookConfig, String> {
+    current_config(&app)
+}
+
+/// POST a sample payload to `url` so the user can check the integration
+/// before enabling it.
+#[tauri::command]
+pub async fn webhook_test(app: AppHandle, url: String) -> Result<(), String> {
+    validate_url(&url)?;
+    let context = MomentContext {
+        trigger: MomentTrigger::Manual,
+        captured_at_ms: chrono::Utc::now().timestamp_millis() as u64,
+        screenshot_base64: None,
+        audio: None,
+        transcription: None,
+        errors: Vec::new(),
+    };
+    post(
+        &app,
+        &url,
+        &payload(&context, "This is a test transcript.", None),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,49 @@
+use super::*;
+use crate::system_audio::RecentAudio;
+
+fn context() -> MomentContext {
+    MomentContext {
+        trigger: MomentTrigger::WakeWord,
+        captured_at_ms: 1_700_000_030_000,
+        screenshot_base64: Some("c2NyZWVu".to_string()),
+        audio: Some(RecentAudio {
+            audio_base64: "b2dn".to_string(),
+            start_time_ms: Some(1_700_000_000_000),
+            end_time_ms: Some(1_700_000_030_000),
+        }),
+        transcription: Some("ship it on friday".to_string()),
+        errors: vec!["Screenshot: denied".to_string()],
+    }
+}
+
+#[test]
+fn payload_carries_the_transcript_but_no_media() {
+    let context = context();
+    let body = serde_json::to_value(payload(
+        &context,
+        "ship it on friday",
+        Some("https://s3/x".to_string()),
+    ))
+    .unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "event": "transcript.created",
+            "trigger": "wakeWord",
+            "captured_at_ms": 1_700_000_030_000u64,
+            "transcription": "ship it on friday",
+            "start_time_ms": 1_700_000_000_000u64,
+            "end_time_ms": 1_700_000_030_000u64,
+            "audio_url": "https://s3/x",
+            "errors": ["Screenshot: denied"],
+        })
+    );
+}
+
+#[test]
+fn only_http_urls_are_accepted() {
+    assert!(validate_url("https://hooks.zapier.com/hooks/catch/1/abc").is_ok());
+    assert!(validate_url("http://localhost:5678/webhook/x").is_ok());
+    assert!(validate_url("file:///etc/passwd").is_err());
+    assert!(validate_url("not a url").is_err());
+}
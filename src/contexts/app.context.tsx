@@ -5,7 +5,12 @@ import {
   SPEECH_TO_TEXT_PROVIDERS,
   STORAGE_KEYS,
 } from "@/config";
-import { getPlatform, safeLocalStorage, trackAppStart } from "@/lib";
+import {
+  getPlatform,
+  safeLocalStorage,
+  storeApiKey,
+  trackAppStart,
+} from "@/lib";
 import { getShortcutsConfig } from "@/lib/storage";
 import {
   getCustomizableState,
@@ -29,6 +34,39 @@ import {
   useState,
 } from "react";
 
+type SelectedProvider = {
+  provider: string;
+  variables: Record<string, string>;
+};
+
+/**
+ * API keys saved before they moved to the keychain are still in
+ * localStorage. Move them over; the sync effect then saves the placeholder
+ * in their place.
+ */
+const migrateApiKey = async (
+  selected: SelectedProvider,
+  providers: TYPE_PROVIDER[],
+  setSelected: (selected: SelectedProvider) => void
+) => {
+  if (!selected?.provider || !selected.variables?.api_key) return;
+  const provider = providers.find((p) => p.id === selected.provider);
+  if (!provider?.curl) return;
+  try {
+    const variables = await storeApiKey(
+      selected.provider,
+      provider.curl,
+      selected.variables,
+      "api_key"
+    );
+    if (variables !== selected.variables) {
+      setSelected({ ...selected, variables });
+    }
+  } catch (error) {
+    console.error("Failed to move the API key to the keychain:", error);
+  }
+};
+
 const validateAndProcessCurlProviders = (
   providersJson: string,
   providerType: "AI" | "STT"
@@ -331,7 +369,13 @@ export const AppProvider = ({ children }: { children: ReactNode }) => {
       STORAGE_KEYS.SELECTED_AI_PROVIDER
     );
     if (savedSelectedAi) {
-      setSelectedAIProvider(JSON.parse(savedSelectedAi));
+      const selected = JSON.parse(savedSelectedAi);
+      setSelectedAIProvider(selected);
+      migrateApiKey(
+        selected,
+        [...AI_PROVIDERS, ...aiList],
+        setSelectedAIProvider
+      );
     }
 
     // Load selected STT provider
@@ -339,7 +383,13 @@ export const AppProvider = ({ children }: { children: ReactNode }) => {
       STORAGE_KEYS.SELECTED_STT_PROVIDER
     );
     if (savedSelectedStt) {
-      setSelectedSttProvider(JSON.parse(savedSelectedStt));
+      const selected = JSON.parse(savedSelectedStt);
+      setSelectedSttProvider(selected);
+      migrateApiKey(
+        selected,
+        [...SPEECH_TO_TEXT_PROVIDERS, ...sttList],
+        setSelectedSttProvider
+      );
     }
 
     // Load customizable state
@@ -5,6 +5,7 @@ import {
   getByPath,
   getStreamingContent,
 } from "./common.function";
+import { providerFetch, usesStoredApiKey } from "./provider-fetch.function";
 import { Message, TYPE_PROVIDER } from "@/types";
 import { fetch as tauriFetch } from "@tauri-apps/plugin-http";
 import { invoke } from "@tauri-apps/api/core";
@@ -106,7 +107,10 @@ async function* fetchRunningbordAIResponse(params: {
       }
 
       // Start the streaming request using the new API response endpoint
-      await invoke("chat_stream_response", {
+      const response = await invoke<{
+        error?: string | null;
+        queued_id?: string;
+      }>("chat_stream_response", {
         userMessage,
         systemPrompt,
         imageBase64,
@@ -114,6 +118,14 @@ async function* fetchRunningbordAIResponse(params: {
         history: historyString,
       });
 
+      // Queued while offline; the answer arrives with
+      // `offline-request-completed` instead of streaming.
+      if (response.queued_id) {
+        yield response.error ??
+          "Offline; the request will be sent once back online";
+        return;
+      }
+
       // Yield chunks as they come in
       let lastIndex = 0;
       while (!streamComplete) {
@@ -317,7 +329,12 @@ export async function* fetchAIResponse(params: {
       }
     }
 
-    const fetchFunction = url?.includes("http") ? fetch : tauriFetch;
+    const fetchFunction = usesStoredApiKey(selectedProvider.variables)
+      ? (url: string, init: Parameters<typeof providerFetch>[2]) =>
+          providerFetch(selectedProvider.provider, url, init)
+      : url?.includes("http")
+      ? fetch
+      : tauriFetch;
 
     let response;
     try {
@@ -2,3 +2,4 @@ export * from "./ai-response.function";
 export * from "./stt.function";
 export * from "./common.function";
 export * from "./runningbord.api";
+export * from "./provider-fetch.function";
@@ -0,0 +1,185 @@
+import curl2Json from "@bany/curl-to-json";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import { blobToBase64, deepVariableReplacer } from "./common.function";
+
+/**
+ * Stands in for a provider's API key in stored variables and requests. The
+ * key itself lives in the OS keychain and is filled in by the backend, so it
+ * never reaches the webview. Matches `secrets::API_KEY_PLACEHOLDER`.
+ */
+export const API_KEY_PLACEHOLDER = "{{API_KEY}}";
+
+/** Whether the provider's API key is kept in the keychain. */
+export function usesStoredApiKey(variables?: Record<string, string>): boolean {
+  return Object.values(variables ?? {}).includes(API_KEY_PLACEHOLDER);
+}
+
+/**
+ * Move a provider's API key into the keychain. Returns the variables to
+ * store instead, with the placeholder in place of the key. The backend only
+ * sends the key to the endpoint in the provider's `curl`.
+ */
+export async function storeApiKey(
+  provider: string,
+  curl: string,
+  variables: Record<string, string>,
+  apiKeyVariable: string
+): Promise<Record<string, string>> {
+  const apiKey = variables[apiKeyVariable]?.trim();
+  if (!apiKey || apiKey === API_KEY_PLACEHOLDER) return variables;
+  const url = deepVariableReplacer(
+    curl2Json(curl).url || "",
+    Object.fromEntries(
+      Object.entries(variables)
+        .filter(([key]) => key !== apiKeyVariable)
+        .map(([key, value]) => [key.toUpperCase(), value])
+    )
+  );
+  await invoke("set_api_key", { provider, apiKey, url });
+  return { ...variables, [apiKeyVariable]: API_KEY_PLACEHOLDER };
+}
+
+/** Remove a provider's API key from the keychain and the variables. */
+export async function deleteApiKey(
+  provider: string,
+  variables: Record<string, string>,
+  apiKeyVariable: string
+): Promise<Record<string, string>> {
+  await invoke("delete_api_key", { provider });
+  return { ...variables, [apiKeyVariable]: "" };
+}
+
+type ProviderEvent =
+  | {
+      type: "head";
+      status: number;
+      statusText: string;
+      contentType?: string | null;
+    }
+  | { type: "chunk"; dataBase64: string }
+  | { type: "end" };
+
+type ProviderBody =
+  | { type: "text"; text: string }
+  | { type: "bytes"; dataBase64: string }
+  | {
+      type: "form";
+      fields: (
+        | { type: "text"; name: string; value: string }
+        | {
+            type: "file";
+            name: string;
+            fileName: string;
+            mimeType: string;
+            dataBase64: string;
+          }
+      )[];
+    };
+
+const NULL_BODY_STATUSES = [101, 204, 205, 304];
+
+function base64ToBytes(base64: string): Uint8Array {
+  return Uint8Array.from(atob(base64), (c) => c.charCodeAt(0));
+}
+
+async function toProviderBody(
+  body: BodyInit | null | undefined
+): Promise<ProviderBody | undefined> {
+  if (body == null) return undefined;
+  if (typeof body === "string") return { type: "text", text: body };
+  if (body instanceof FormData) {
+    const fields: Extract<ProviderBody, { type: "form" }>["fields"] = [];
+    for (const [name, value] of body.entries()) {
+      if (typeof value === "string") {
+        fields.push({ type: "text", name, value });
+      } else {
+        fields.push({
+          type: "file",
+          name,
+          fileName: value.name || "blob",
+          mimeType: value.type || "application/octet-stream",
+          dataBase64: await blobToBase64(value),
+        });
+      }
+    }
+    return { type: "form", fields };
+  }
+  if (body instanceof Blob) {
+    return { type: "bytes", dataBase64: await blobToBase64(body) };
+  }
+  throw new Error("Unsupported request body");
+}
+
+/**
+ * `fetch` for a request to `provider` that carries `API_KEY_PLACEHOLDER`.
+ * The backend sends it with the stored key filled in and streams the
+ * response back, so streamed answers read the same as with `fetch`.
+ */
+export async function providerFetch(
+  provider: string,
+  url: string,
+  init: {
+    method?: string;
+    headers?: Record<string, string>;
+    body?: BodyInit | null;
+    signal?: AbortSignal;
+    /** Which of the provider's rate limits the request counts against. */
+    kind?: "chat" | "transcription";
+  }
+): Promise<Response> {
+  const body = await toProviderBody(init.body);
+  let controller!: ReadableStreamDefaultController<Uint8Array>;
+  const stream = new ReadableStream<Uint8Array>({
+    start(c) {
+      controller = c;
+    },
+  });
+  let closed = false;
+  const fail = (error: Error) => {
+    if (closed) return;
+    closed = true;
+    controller.error(error);
+  };
+  init.signal?.addEventListener("abort", () =>
+    fail(new DOMException("Aborted", "AbortError"))
+  );
+
+  return new Promise<Response>((resolve, reject) => {
+    const onEvent = new Channel<ProviderEvent>();
+    onEvent.onmessage = (event) => {
+      if (closed) return;
+      if (event.type === "head") {
+        const nullBody = NULL_BODY_STATUSES.includes(event.status);
+        resolve(
+          new Response(nullBody ? null : stream, {
+            status: event.status,
+            statusText: event.statusText,
+            headers: event.contentType
+              ? { "Content-Type": event.contentType }
+              : {},
+          })
+        );
+      } else if (event.type === "chunk") {
+        controller.enqueue(base64ToBytes(event.dataBase64));
+      } else {
+        closed = true;
+        controller.close();
+      }
+    };
+    invoke("provider_fetch", {
+      provider,
+      request: {
+        kind: init.kind ?? "chat",
+        url,
+        method: init.method ?? "POST",
+        headers: Object.entries(init.headers ?? {}),
+        body,
+      },
+      onEvent,
+    }).catch((error) => {
+      const failure = new Error(String(error));
+      reject(failure);
+      fail(failure);
+    });
+  });
+}
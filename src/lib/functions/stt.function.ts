@@ -3,6 +3,7 @@ import {
   getByPath,
   blobToBase64,
 } from "./common.function";
+import { providerFetch, usesStoredApiKey } from "./provider-fetch.function";
 import { fetch as tauriFetch } from "@tauri-apps/plugin-http";
 import { invoke } from "@tauri-apps/api/core";
 
@@ -185,7 +186,15 @@ export async function fetchSTT(params: STTParams): Promise<string> {
       body = JSON.stringify(deepVariableReplacer(dataObj, allVariables));
     }
 
-    const fetchFunction = url?.includes("http") ? fetch : tauriFetch;
+    const fetchFunction = usesStoredApiKey(selectedProvider.variables)
+      ? (url: string, init: Parameters<typeof providerFetch>[2]) =>
+          providerFetch(selectedProvider.provider, url, {
+            ...init,
+            kind: "transcription",
+          })
+      : url?.includes("http")
+      ? fetch
+      : tauriFetch;
 
     // Send request
     let response: Response;
@@ -1,4 +1,9 @@
 import { Button, Header, Input, Selection, TextInput } from "@/components";
+import {
+  API_KEY_PLACEHOLDER,
+  deleteApiKey,
+  storeApiKey,
+} from "@/lib";
 import { UseSettingsReturn } from "@/types";
 import curl2Json, { ResultJSON } from "@bany/curl-to-json";
 import { KeyIcon, TrashIcon } from "lucide-react";
@@ -12,6 +17,7 @@ export const Providers = ({
 }: UseSettingsReturn) => {
   const [localSelectedProvider, setLocalSelectedProvider] =
     useState<ResultJSON | null>(null);
+  const [apiKeyDraft, setApiKeyDraft] = useState("");
 
   useEffect(() => {
     if (selectedSttProvider?.provider) {
@@ -35,8 +41,41 @@ export const Providers = ({
     return selectedSttProvider?.variables?.[apiKeyVar.key] || "";
   };
 
-  const isApiKeyEmpty = () => {
-    return !getApiKeyValue().trim();
+  const isApiKeyStored = () => {
+    const apiKeyVar = findKeyAndValue("api_key");
+    return !!apiKeyVar && getApiKeyValue() === API_KEY_PLACEHOLDER;
+  };
+
+  const submitApiKey = async () => {
+    const apiKeyVar = findKeyAndValue("api_key");
+    if (!apiKeyVar || !selectedSttProvider?.provider || !apiKeyDraft.trim()) return;
+    try {
+      const variables = await storeApiKey(
+        selectedSttProvider.provider,
+        allSttProviders?.find((p) => p?.id === selectedSttProvider.provider)?.curl ?? "",
+        { ...selectedSttProvider.variables, [apiKeyVar.key]: apiKeyDraft },
+        apiKeyVar.key
+      );
+      onSetSelectedSttProvider({ ...selectedSttProvider, variables });
+      setApiKeyDraft("");
+    } catch (error) {
+      console.error("Failed to store API key:", error);
+    }
+  };
+
+  const removeApiKey = async () => {
+    const apiKeyVar = findKeyAndValue("api_key");
+    if (!apiKeyVar || !selectedSttProvider?.provider) return;
+    try {
+      const variables = await deleteApiKey(
+        selectedSttProvider.provider,
+        selectedSttProvider.variables,
+        apiKeyVar.key
+      );
+      onSetSelectedSttProvider({ ...selectedSttProvider, variables });
+    } catch (error) {
+      console.error("Failed to remove API key:", error);
+    }
   };
 
   return (
@@ -85,59 +124,32 @@ export const Providers = ({
               )?.isCustom
                 ? "Custom Provider"
                 : selectedSttProvider?.provider
-            } API key to authenticate and access STT models. Your key is stored locally and never shared.`}
+            } API key to authenticate and access STT models. Your key is kept in your system keychain and never shared.`}
           />
 
           <div className="space-y-2">
             <div className="flex gap-2">
               <Input
                 type="password"
-                placeholder="**********"
-                value={getApiKeyValue()}
-                onChange={(value) => {
-                  const apiKeyVar = findKeyAndValue("api_key");
-                  if (!apiKeyVar || !selectedSttProvider) return;
-
-                  onSetSelectedSttProvider({
-                    ...selectedSttProvider,
-                    variables: {
-                      ...selectedSttProvider.variables,
-                      [apiKeyVar.key]:
-                        typeof value === "string" ? value : value.target.value,
-                    },
-                  });
-                }}
+                placeholder={
+                  isApiKeyStored() ? "Stored in your keychain" : "**********"
+                }
+                value={isApiKeyStored() ? "" : apiKeyDraft}
+                onChange={(value) =>
+                  setApiKeyDraft(
+                    typeof value === "string" ? value : value.target.value
+                  )
+                }
                 onKeyDown={(e) => {
-                  const apiKeyVar = findKeyAndValue("api_key");
-                  if (!apiKeyVar || !selectedSttProvider) return;
-
-                  onSetSelectedSttProvider({
-                    ...selectedSttProvider,
-                    variables: {
-                      ...selectedSttProvider.variables,
-                      [apiKeyVar.key]: (e.target as HTMLInputElement).value,
-                    },
-                  });
+                  if (e.key === "Enter") submitApiKey();
                 }}
-                disabled={false}
+                disabled={isApiKeyStored()}
                 className="flex-1 h-11 border-1 border-input/50 focus:border-primary/50 transition-colors"
               />
-              {isApiKeyEmpty() ? (
+              {!isApiKeyStored() ? (
                 <Button
-                  onClick={() => {
-                    const apiKeyVar = findKeyAndValue("api_key");
-                    if (!apiKeyVar || !selectedSttProvider || isApiKeyEmpty())
-                      return;
-
-                    onSetSelectedSttProvider({
-                      ...selectedSttProvider,
-                      variables: {
-                        ...selectedSttProvider.variables,
-                        [apiKeyVar.key]: getApiKeyValue(),
-                      },
-                    });
-                  }}
-                  disabled={isApiKeyEmpty()}
+                  onClick={submitApiKey}
+                  disabled={!apiKeyDraft.trim()}
                   size="icon"
                   className="shrink-0 h-11 w-11"
                   title="Submit API Key"
@@ -146,18 +158,7 @@ export const Providers = ({
                 </Button>
               ) : (
                 <Button
-                  onClick={() => {
-                    const apiKeyVar = findKeyAndValue("api_key");
-                    if (!apiKeyVar || !selectedSttProvider) return;
-
-                    onSetSelectedSttProvider({
-                      ...selectedSttProvider,
-                      variables: {
-                        ...selectedSttProvider.variables,
-                        [apiKeyVar.key]: "",
-                      },
-                    });
-                  }}
+                  onClick={removeApiKey}
                   size="icon"
                   variant="destructive"
                   className="shrink-0 h-11 w-11"